@@ -0,0 +1,158 @@
+// Minimal client for manual QA: speaks the native JSON protocol over a WebSocket.
+(() => {
+  const CLASSIC_CHOICES = ["rock", "paper", "scissors"];
+  const $ = (id) => document.getElementById(id);
+
+  let socket = null;
+  let seats = []; // Player ids in GameStart order, for blitz's seat-indexed rounds
+  let me = null;
+
+  // The server reports its WebSocket port; the page itself is served from the REST port
+  const host = location.hostname || "localhost";
+  $("server-url").value = `ws://${host}:8080`;
+  fetch("config.json")
+    .then((response) => response.json())
+    .then((config) => {
+      $("server-url").value = `ws://${host}:${config.websocketPort}`;
+    })
+    .catch(() => {});
+
+  function log(direction, message) {
+    const item = document.createElement("li");
+    item.className = direction === "out" ? "outgoing" : message.type === "error" ? "error" : "";
+    item.textContent = `${direction === "out" ? "→" : "←"} ${JSON.stringify(message)}`;
+    $("log").prepend(item);
+  }
+
+  function send(message) {
+    if (!socket || socket.readyState !== WebSocket.OPEN) {
+      return;
+    }
+    log("out", message);
+    socket.send(JSON.stringify(message));
+  }
+
+  function showChoices(choices) {
+    const container = $("choices");
+    container.replaceChildren();
+    for (const choice of choices) {
+      const button = document.createElement("button");
+      button.textContent = choice;
+      button.addEventListener("click", () => send({ type: "playerMove", choice }));
+      container.append(button);
+    }
+  }
+
+  function showScores(scores) {
+    $("scores").textContent = Object.entries(scores || {})
+      .map(([player, score]) => `${player}: ${score}`)
+      .join(" · ");
+  }
+
+  function showDecision(ms) {
+    $("decision").textContent = ms === undefined || ms === null ? "" : `You decided in ${ms} ms`;
+  }
+
+  function showSummary(rounds) {
+    $("summary").replaceChildren(
+      ...(rounds || []).map((round) => {
+        const item = document.createElement("li");
+        const moves = Object.entries(round.moves)
+          .map(([player, choice]) => `${player}: ${choice}`)
+          .join(" vs ");
+        const outcome = round.winner ? `${round.winner} wins` : round.replayed ? "draw, replayed" : "draw";
+        item.textContent = `Round ${round.round}: ${moves} (${outcome})`;
+        return item;
+      })
+    );
+  }
+
+  function handle(message) {
+    log("in", message);
+    switch (message.type) {
+      case "connected":
+        me = message.playerId;
+        if (message.resumeToken) {
+          sessionStorage.setItem(`rps-resume:${me}`, message.resumeToken);
+        }
+        $("status").textContent = `Connected as ${message.playerId}`;
+        $("lobby").hidden = false;
+        break;
+      case "gameStart":
+        $("game").hidden = false;
+        seats = message.players.map((player) => player.id);
+        $("round").textContent = `Round 1 of ${message.maxRounds}`;
+        showChoices(message.rules ? message.rules.choices : CLASSIC_CHOICES);
+        showScores({});
+        showSummary([]);
+        showDecision();
+        break;
+      case "roundResult":
+        showScores(message.scores);
+        showDecision((message.decisionMs || {})[me]);
+        break;
+      case "blitzRound":
+        showScores(Object.fromEntries(seats.map((id, seat) => [id, message.scores[seat]])));
+        showDecision((message.decisionMs || [])[seats.indexOf(me)]);
+        if (message.nextRound) {
+          $("round").textContent = `Round ${message.nextRound}`;
+        }
+        break;
+      case "opponentMoved":
+        if (message.playerId !== me) {
+          $("decision").textContent = `${message.playerId} has moved`;
+        }
+        break;
+      case "nextRound":
+        $("round").textContent = `Round ${message.round}`;
+        break;
+      case "gameResumed":
+        $("game").hidden = false;
+        $("round").textContent = `Round ${message.round}`;
+        showScores(message.scores);
+        break;
+      case "gameEnd":
+        $("round").textContent = message.winner ? `Winner: ${message.winner}` : "Draw";
+        showScores(message.finalScores);
+        showSummary(message.rounds);
+        $("choices").replaceChildren();
+        break;
+      case "seriesUpdate":
+        $("round").textContent = `Game ${message.nextGame} of ${message.bestOf} starts shortly`;
+        showScores(message.scores);
+        break;
+      case "seriesEnd":
+        $("round").textContent = message.winner ? `Series winner: ${message.winner}` : "Series drawn";
+        showScores(message.scores);
+        break;
+      case "notifications":
+        send({ type: "ackNotifications", ids: message.notifications.map((n) => n.id) });
+        break;
+    }
+  }
+
+  $("connect").addEventListener("click", () => {
+    if (socket) {
+      socket.close();
+    }
+    socket = new WebSocket($("server-url").value);
+    $("status").textContent = "Connecting…";
+    socket.addEventListener("open", () => {
+      const playerId = $("player-id").value.trim() || null;
+      // Proves this tab is the player it was before, so a paused game resumes
+      const reconnectToken = playerId && sessionStorage.getItem(`rps-resume:${playerId}`);
+      send({ type: "connect", playerId, reconnectToken: reconnectToken || undefined, locale: navigator.language, clientVersion: "demo-web/1" });
+    });
+    socket.addEventListener("message", (event) => handle(JSON.parse(event.data)));
+    socket.addEventListener("close", () => {
+      $("status").textContent = "Disconnected";
+      $("lobby").hidden = true;
+      $("game").hidden = true;
+    });
+  });
+
+  $("find-match").addEventListener("click", () => send({ type: "findMatch", mode: $("mode").value }));
+  $("join-room").addEventListener("click", () => send({ type: "joinRoom", room: $("room").value.trim() }));
+  $("pause").addEventListener("click", () => send({ type: "pauseRequest" }));
+  $("resume").addEventListener("click", () => send({ type: "resumeRequest" }));
+})();
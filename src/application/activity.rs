@@ -0,0 +1,174 @@
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use super::event_bus::EventBus;
+use super::game_history::{GameHistory, GameRecord};
+use crate::config::ActivityConfig;
+use crate::domain::{EventEnvelope, GameEndReason, GameEvent, GameMode};
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GameOutcome {
+    Won,
+    Lost,
+    Drawn,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Activity {
+    Connected,
+    Disconnected,
+    Queued,
+    Matched {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        opponents: Vec<String>,
+    },
+    Game {
+        #[serde(rename = "gameId")]
+        game_id: String,
+        #[serde(rename = "roomId")]
+        room_id: String,
+        mode: GameMode,
+        opponents: Vec<String>,
+        outcome: GameOutcome,
+        reason: Option<GameEndReason>,
+        scores: HashMap<String, u32>,
+        #[serde(rename = "startedAt")]
+        started_at: DateTime<Utc>,
+    },
+    Kicked { reason: String },
+    Banned { reason: String },
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ActivityEntry {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub activity: Activity,
+}
+
+/// Recent activity per player for support lookups: connections and matchmaking from the
+/// event bus, finished games with their results from the game history.
+pub struct PlayerActivity {
+    entries: RwLock<HashMap<String, VecDeque<ActivityEntry>>>, // Oldest first
+    history: Arc<GameHistory>,
+    max_entries: usize,
+    max_players: usize,
+}
+
+impl PlayerActivity {
+    pub fn new(history: Arc<GameHistory>, config: &ActivityConfig) -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            history,
+            max_entries: config.max_entries_per_player,
+            max_players: config.max_players,
+        }
+    }
+
+    /// Records what one event says about each player it names. Game results are left to
+    /// the game history, which keeps the scores and rounds.
+    pub fn apply(&self, envelope: &EventEnvelope) {
+        let updates: Vec<(String, Activity)> = match &envelope.event {
+            GameEvent::PlayerConnected { player_id } => vec![(player_id.clone(), Activity::Connected)],
+            GameEvent::PlayerDisconnected { player_id } => vec![(player_id.clone(), Activity::Disconnected)],
+            GameEvent::PlayerQueued { player_id } => vec![(player_id.clone(), Activity::Queued)],
+            GameEvent::MatchCreated { room_id, players, .. } => players
+                .iter()
+                .map(|player_id| {
+                    let opponents = players.iter().filter(|p| *p != player_id).cloned().collect();
+                    (player_id.clone(), Activity::Matched { room_id: room_id.clone(), opponents })
+                })
+                .collect(),
+            GameEvent::PlayerKicked { player_id, reason } => vec![(player_id.clone(), Activity::Kicked { reason: reason.clone() })],
+            GameEvent::PlayerBanned { player_id, reason } => vec![(player_id.clone(), Activity::Banned { reason: reason.clone() })],
+            GameEvent::RoundStarted { .. } | GameEvent::GameEnded { .. } | GameEvent::SeriesEnded { .. } | GameEvent::QueueStarved { .. } | GameEvent::MatchReserved { .. } => {
+                Vec::new()
+            }
+        };
+
+        let mut entries = self.entries.write();
+        for (player_id, activity) in updates {
+            if !entries.contains_key(&player_id) && entries.len() >= self.max_players {
+                // Make room by forgetting whoever has been quiet the longest
+                let quietest = entries
+                    .iter()
+                    .min_by_key(|(_, timeline)| timeline.back().map(|entry| entry.at))
+                    .map(|(id, _)| id.clone());
+                if let Some(quietest) = quietest {
+                    entries.remove(&quietest);
+                }
+            }
+            let timeline = entries.entry(player_id).or_default();
+            timeline.push_back(ActivityEntry { at: envelope.timestamp, activity });
+            while timeline.len() > self.max_entries {
+                timeline.pop_front();
+            }
+        }
+    }
+
+    /// Up to `limit` entries, newest first.
+    pub fn timeline(&self, player_id: &str, limit: usize) -> Vec<ActivityEntry> {
+        let mut timeline: Vec<ActivityEntry> = self
+            .entries
+            .read()
+            .get(player_id)
+            .map(|entries| entries.iter().rev().take(limit).cloned().collect())
+            .unwrap_or_default();
+        timeline.extend(self.history.games_of(player_id, limit).iter().filter_map(|record| game_entry(player_id, record)));
+        timeline.sort_by_key(|entry| std::cmp::Reverse(entry.at));
+        timeline.truncate(limit);
+        timeline
+    }
+
+    /// Drops the player's timeline; false if there was none.
+    pub fn forget(&self, player_id: &str) -> bool {
+        self.entries.write().remove(player_id).is_some()
+    }
+
+    /// Keeps the timelines in sync with the bus until the bus is dropped.
+    pub fn spawn(self: Arc<Self>, events: &EventBus) -> JoinHandle<()> {
+        let mut receiver = events.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(envelope) => self.apply(&envelope),
+                    Err(RecvError::Lagged(skipped)) => warn!("Player activity lagged; {} events dropped", skipped),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+fn game_entry(player_id: &str, record: &GameRecord) -> Option<ActivityEntry> {
+    // In team games the winner is a team id
+    let side = record.teams.get(player_id).map_or(player_id, String::as_str);
+    let outcome = match &record.winner {
+        Some(winner) if winner == side => GameOutcome::Won,
+        Some(_) => GameOutcome::Lost,
+        None => GameOutcome::Drawn,
+    };
+    Some(ActivityEntry {
+        at: record.ended_at?,
+        activity: Activity::Game {
+            game_id: record.game_id.clone(),
+            room_id: record.room_id.clone(),
+            mode: record.mode,
+            opponents: record.players.iter().filter(|p| *p != player_id).cloned().collect(),
+            outcome,
+            reason: record.reason.clone(),
+            scores: record.scores.clone(),
+            started_at: record.created_at,
+        },
+    })
+}
@@ -0,0 +1,160 @@
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+use crate::config::BotDetectionConfig;
+
+// Chance that a random player counters the opponent's previous move
+const RANDOM_COUNTER_RATE: f64 = 1.0 / 3.0;
+
+/// One accepted move, as seen by the detector.
+#[derive(Debug, Clone)]
+pub struct MoveSample {
+    pub player_id: String,
+    pub reaction_ms: u64, // Time from round start to the move
+    pub countered_previous: Option<bool>, // Beat the opponent's previous move; None in round one
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuspicionReport {
+    pub player_id: String,
+    pub score: f64, // 0.0 = human-looking, 1.0 = almost certainly scripted
+    pub samples: usize,
+    pub timing_cv: Option<f64>,    // Coefficient of variation of reaction times
+    pub counter_rate: Option<f64>, // Share of moves that beat the opponent's previous move
+    pub flagged: bool,
+}
+
+#[derive(Default)]
+struct PlayerSignals {
+    reaction_ms: VecDeque<u64>,
+    counters: VecDeque<bool>,
+}
+
+/// Scores players on two bot tells: reaction times that barely vary, and
+/// always playing whatever beats the opponent's last move.
+pub struct BotDetector {
+    config: BotDetectionConfig,
+    players: RwLock<HashMap<String, PlayerSignals>>,
+}
+
+impl BotDetector {
+    pub fn new(config: BotDetectionConfig) -> Self {
+        Self {
+            config,
+            players: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn separate_pool(&self) -> bool {
+        self.config.enabled && self.config.separate_pool
+    }
+
+    pub fn record(&self, sample: &MoveSample) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let window = self.config.window.max(1);
+        let mut players = self.players.write();
+        let signals = players.entry(sample.player_id.clone()).or_default();
+
+        signals.reaction_ms.push_back(sample.reaction_ms);
+        if signals.reaction_ms.len() > window {
+            signals.reaction_ms.pop_front();
+        }
+
+        if let Some(countered) = sample.countered_previous {
+            signals.counters.push_back(countered);
+            if signals.counters.len() > window {
+                signals.counters.pop_front();
+            }
+        }
+    }
+
+    pub fn report(&self, player_id: &str) -> Option<SuspicionReport> {
+        let players = self.players.read();
+        players.get(player_id).map(|signals| self.score(player_id, signals))
+    }
+
+    pub fn is_suspected(&self, player_id: &str) -> bool {
+        self.report(player_id).is_some_and(|report| report.flagged)
+    }
+
+    /// Flagged players, most suspicious first.
+    pub fn suspects(&self) -> Vec<SuspicionReport> {
+        let players = self.players.read();
+        let mut suspects: Vec<_> = players
+            .iter()
+            .map(|(player_id, signals)| self.score(player_id, signals))
+            .filter(|report| report.flagged)
+            .collect();
+        suspects.sort_by(|a, b| b.score.total_cmp(&a.score));
+        suspects
+    }
+
+    fn score(&self, player_id: &str, signals: &PlayerSignals) -> SuspicionReport {
+        let min_samples = self.config.min_samples.max(2);
+
+        let timing_cv = (signals.reaction_ms.len() >= min_samples).then(|| {
+            let n = signals.reaction_ms.len() as f64;
+            let mean = signals.reaction_ms.iter().sum::<u64>() as f64 / n;
+            let variance = signals.reaction_ms.iter().map(|&ms| (ms as f64 - mean).powi(2)).sum::<f64>() / n;
+            if mean > 0.0 { variance.sqrt() / mean } else { 0.0 }
+        });
+
+        let counter_rate = (signals.counters.len() >= min_samples)
+            .then(|| signals.counters.iter().filter(|&&hit| hit).count() as f64 / signals.counters.len() as f64);
+
+        let timing_score = timing_cv
+            .map(|cv| 1.0 - (cv / self.config.human_timing_cv).min(1.0))
+            .unwrap_or(0.0);
+        let counter_score = counter_rate
+            .map(|rate| ((rate - RANDOM_COUNTER_RATE) / (1.0 - RANDOM_COUNTER_RATE)).max(0.0))
+            .unwrap_or(0.0);
+        let score = timing_score.max(counter_score);
+
+        SuspicionReport {
+            player_id: player_id.to_string(),
+            score,
+            samples: signals.reaction_ms.len(),
+            timing_cv,
+            counter_rate,
+            flagged: score >= self.config.suspicion_threshold,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::application::{BotDetector, MoveSample};
+    use crate::config::BotDetectionConfig;
+    use crate::domain::{GameChoice, RuleSet};
+
+    #[test]
+    fn test_bot_detector_flags_scripted_play() {
+        let detector = BotDetector::new(BotDetectionConfig::default());
+        let rules = RuleSet::classic();
+        let choices = [GameChoice::Rock, GameChoice::Paper, GameChoice::Scissors];
+
+        for i in 0..20 {
+            detector.record(&MoveSample {
+                player_id: "bot".to_string(),
+                reaction_ms: 200 + (i as u64 % 2),
+                countered_previous: Some(true),
+            });
+            detector.record(&MoveSample {
+                player_id: "human".to_string(),
+                reaction_ms: 400 + (i as u64 * 797) % 2500,
+                countered_previous: Some(rules.beats(&choices[(i * 7 / 3) % 3], &choices[i % 3])),
+            });
+        }
+
+        let bot = detector.report("bot").unwrap();
+        assert!(bot.flagged && bot.score > 0.9);
+        assert!(!detector.report("human").unwrap().flagged);
+        assert_eq!(detector.suspects().len(), 1);
+        assert!(detector.report("nobody").is_none());
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::domain::{GameChoice, Player, RuleSet};
+
+/// Server-side opponent whose whole move sequence is fixed by a secret seed.
+///
+/// `GameStart` carries `sha256(seed)` as the commitment and `GameEnd` reveals the
+/// seed, so players can check every bot move was `bot_move(seed, n, rules)` for the
+/// n-th move it played (replayed rounds included) and not chosen after seeing theirs.
+pub struct BotOpponent {
+    player: Arc<Player>,
+    seed: [u8; 32],
+    moves_played: u32,
+}
+
+/// A bot mid-game, so its room can move to another node and keep the committed sequence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BotState {
+    pub id: String,
+    pub seed: String, // Hex
+    pub moves_played: u32,
+}
+
+impl BotOpponent {
+    pub fn new() -> Self {
+        // Two v4 UUIDs give 244 bits from the OS RNG
+        let mut seed = [0u8; 32];
+        seed[..16].copy_from_slice(Uuid::new_v4().as_bytes());
+        seed[16..].copy_from_slice(Uuid::new_v4().as_bytes());
+        Self::with_seed(format!("bot-{}", Uuid::new_v4()), seed, 0)
+    }
+
+    /// None if the seed isn't 32 bytes of hex.
+    pub fn from_state(state: &BotState) -> Option<Self> {
+        let seed = hex::decode(&state.seed).ok()?.try_into().ok()?;
+        Some(Self::with_seed(state.id.clone(), seed, state.moves_played))
+    }
+
+    fn with_seed(id: String, seed: [u8; 32], moves_played: u32) -> Self {
+        // Nobody reads the bot's messages; the room skips it when broadcasting
+        let (sender, _) = mpsc::unbounded_channel();
+        Self {
+            player: Arc::new(Player::new(id, sender)),
+            seed,
+            moves_played,
+        }
+    }
+
+    pub fn state(&self) -> BotState {
+        BotState {
+            id: self.id().to_string(),
+            seed: self.seed(),
+            moves_played: self.moves_played,
+        }
+    }
+
+    pub fn player(&self) -> &Arc<Player> {
+        &self.player
+    }
+
+    pub fn id(&self) -> &str {
+        &self.player.id
+    }
+
+    pub fn commitment(&self) -> String {
+        hex::encode(Sha256::digest(self.seed))
+    }
+
+    pub fn seed(&self) -> String {
+        hex::encode(self.seed)
+    }
+
+    pub fn next_move(&mut self, rules: &RuleSet) -> GameChoice {
+        let choice = bot_move(&self.seed, self.moves_played, rules);
+        self.moves_played += 1;
+        choice
+    }
+}
+
+impl Default for BotOpponent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The bot's `index`-th move (0-based): the first 8 bytes of
+/// `sha256(seed || index as big-endian u32)`, modulo the number of choices.
+pub fn bot_move(seed: &[u8], index: u32, rules: &RuleSet) -> GameChoice {
+    let digest = Sha256::new().chain_update(seed).chain_update(index.to_be_bytes()).finalize();
+    let value = u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 digest is 32 bytes"));
+    rules.choices[(value % rules.choices.len() as u64) as usize].clone()
+}
@@ -0,0 +1,109 @@
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Faults injected into the running server; all off until set through the admin API.
+pub static CHAOS: Lazy<Chaos> = Lazy::new(Chaos::new);
+
+/// Which faults to inject. Meant for test environments, to exercise client
+/// reconnect and resync logic and the server's own cleanup paths.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ChaosSettings {
+    pub drop_outbound_percent: f64, // Outbound messages silently discarded before reaching the socket
+    pub broadcast_delay_ms: u64,    // Added before each room broadcast is delivered; the room itself carries on
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChaosSnapshot {
+    pub settings: ChaosSettings,
+    pub dropped_messages: u64,
+    pub delayed_broadcasts: u64,
+    pub killed_rooms: u64,
+}
+
+pub struct Chaos {
+    settings: RwLock<ChaosSettings>,
+    dropped_messages: AtomicU64,
+    delayed_broadcasts: AtomicU64,
+    killed_rooms: AtomicU64,
+}
+
+impl Chaos {
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(ChaosSettings::default()),
+            dropped_messages: AtomicU64::new(0),
+            delayed_broadcasts: AtomicU64::new(0),
+            killed_rooms: AtomicU64::new(0),
+        }
+    }
+
+    pub fn set(&self, settings: ChaosSettings) {
+        *self.settings.write() = ChaosSettings {
+            drop_outbound_percent: settings.drop_outbound_percent.clamp(0.0, 100.0),
+            ..settings
+        };
+    }
+
+    pub fn clear(&self) {
+        self.set(ChaosSettings::default());
+    }
+
+    /// Whether to discard the outbound message about to be written.
+    pub fn drop_outbound(&self) -> bool {
+        let percent = self.settings.read().drop_outbound_percent;
+        if percent <= 0.0 {
+            return false;
+        }
+        let roll = (Uuid::new_v4().as_u128() % 10_000) as f64 / 100.0;
+        let dropped = roll < percent;
+        if dropped {
+            self.dropped_messages.fetch_add(1, Ordering::Relaxed);
+        }
+        dropped
+    }
+
+    /// How long to hold back the room broadcast about to be sent.
+    pub fn broadcast_delay(&self) -> Option<Duration> {
+        let delay_ms = self.settings.read().broadcast_delay_ms;
+        if delay_ms == 0 {
+            return None;
+        }
+        self.delayed_broadcasts.fetch_add(1, Ordering::Relaxed);
+        Some(Duration::from_millis(delay_ms))
+    }
+
+    pub fn rooms_killed(&self, count: usize) {
+        self.killed_rooms.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    /// `count` distinct picks from `candidates`, in random order.
+    pub fn pick<T>(&self, mut candidates: Vec<T>, count: usize) -> Vec<T> {
+        let mut picked = Vec::with_capacity(count.min(candidates.len()));
+        while picked.len() < count && !candidates.is_empty() {
+            let index = (Uuid::new_v4().as_u128() % candidates.len() as u128) as usize;
+            picked.push(candidates.swap_remove(index));
+        }
+        picked
+    }
+
+    pub fn snapshot(&self) -> ChaosSnapshot {
+        ChaosSnapshot {
+            settings: *self.settings.read(),
+            dropped_messages: self.dropped_messages.load(Ordering::Relaxed),
+            delayed_broadcasts: self.delayed_broadcasts.load(Ordering::Relaxed),
+            killed_rooms: self.killed_rooms.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for Chaos {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Where rooms and the game manager read the time and wait for deadlines: move windows,
+/// pauses, reconnect grace. Tests swap in a `MockClock` to step through them without
+/// real sleeps.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time on the same clock, for timestamps that are stored or sent.
+    fn utc_now(&self) -> DateTime<Utc>;
+
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send + '_>>;
+}
+
+/// The real time, and tokio's timers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(tokio::time::sleep_until(deadline.into()))
+    }
+}
+
+/// A clock that only moves when `advance` is called; sleepers wake once it passes their
+/// deadline.
+pub struct MockClock {
+    start: Instant,
+    start_utc: DateTime<Utc>,
+    elapsed: Mutex<Duration>,
+    advanced: Notify,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            start_utc: Utc::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+            advanced: Notify::new(),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.elapsed.lock() += by;
+        self.advanced.notify_waiters();
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        self.start_utc + *self.elapsed.lock()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(async move {
+            loop {
+                // Registered before checking, so an advance in between isn't missed
+                let advanced = self.advanced.notified();
+                tokio::pin!(advanced);
+                advanced.as_mut().enable();
+                if self.now() >= deadline {
+                    return;
+                }
+                advanced.await;
+            }
+        })
+    }
+}
@@ -0,0 +1,41 @@
+use chrono::Utc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::domain::{EventEnvelope, GameEvent};
+
+const EVENT_BUS_CAPACITY: usize = 1024;
+
+/// In-process fan-out of lifecycle events. Publishing never blocks; slow
+/// subscribers lag and skip events rather than back-pressuring the game loop.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<EventEnvelope>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: GameEvent) {
+        let envelope = EventEnvelope {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            event,
+        };
+        // No subscribers is fine
+        let _ = self.sender.send(envelope);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
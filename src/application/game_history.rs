@@ -0,0 +1,283 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+use crate::domain::{GameEndReason, GameMode, GameStatus, RoundSummary};
+
+/// Everything kept about one game, live or finished, for lookups by game id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub game_id: String,
+    pub room_id: String,
+    pub mode: GameMode,
+    pub status: GameStatus,
+    pub players: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub teams: HashMap<String, String>, // playerId -> teamId, team mode only
+    pub winner: Option<String>,
+    pub scores: HashMap<String, u32>,
+    pub reason: Option<GameEndReason>, // None while the game is still running
+    pub rounds: Vec<RoundSummary>,
+    pub created_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl GameRecord {
+    pub fn involves(&self, player_id: &str) -> bool {
+        self.players.iter().any(|p| p == player_id)
+    }
+
+    /// Replaces every mention of `player_id` with `alias`; false if the player wasn't in the game.
+    pub fn anonymize(&mut self, player_id: &str, alias: &str) -> bool {
+        if !self.involves(player_id) {
+            return false;
+        }
+        let rename = |id: &mut String| {
+            if id == player_id {
+                *id = alias.to_string();
+            }
+        };
+        fn rekey<V>(map: &mut HashMap<String, V>, player_id: &str, alias: &str) {
+            if let Some(value) = map.remove(player_id) {
+                map.insert(alias.to_string(), value);
+            }
+        }
+
+        self.players.iter_mut().for_each(rename);
+        if let Some(winner) = &mut self.winner {
+            rename(winner);
+        }
+        rekey(&mut self.teams, player_id, alias);
+        rekey(&mut self.scores, player_id, alias);
+        for round in &mut self.rounds {
+            if let Some(winner) = &mut round.winner {
+                rename(winner);
+            }
+            rekey(&mut round.moves, player_id, alias);
+            rekey(&mut round.response_ms, player_id, alias);
+            rekey(&mut round.decision_ms, player_id, alias);
+        }
+        true
+    }
+}
+
+/// Win/loss tally of one player over the games still in the history, so a recent window
+/// of at most `game_history.capacity` games rather than a lifetime record.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerResults {
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub decisions: u32, // Moves with a decision time; bots and missed moves have none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_decision_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fastest_decision_ms: Option<u64>,
+}
+
+/// One row of the fastest-finger leaderboard, quickest average first. Like
+/// [`PlayerResults`], it covers only the games still in the history.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FastestPlayer {
+    pub player_id: String,
+    pub decisions: u32,
+    pub avg_decision_ms: u64,
+    pub fastest_decision_ms: u64,
+}
+
+#[derive(Default)]
+struct DecisionTally {
+    count: u32,
+    total_ms: u64,
+    fastest_ms: u64,
+}
+
+impl DecisionTally {
+    fn add(&mut self, ms: u64) {
+        self.fastest_ms = if self.count == 0 { ms } else { self.fastest_ms.min(ms) };
+        self.count += 1;
+        self.total_ms += ms;
+    }
+
+    fn average_ms(&self) -> Option<u64> {
+        (self.count > 0).then(|| self.total_ms / self.count as u64)
+    }
+}
+
+/// Bounded index of recently finished games; the oldest record goes first once full.
+pub struct GameHistory {
+    capacity: usize,
+    inner: RwLock<HistoryInner>,
+}
+
+/// Longer-term storage for games that have left the [`GameHistory`], consulted when a
+/// lookup misses it.
+pub trait GameArchive: Send + Sync {
+    fn fetch<'a>(&'a self, game_id: &'a str) -> BoxFuture<'a, Result<Option<GameRecord>>>;
+}
+
+#[derive(Default)]
+struct HistoryInner {
+    order: VecDeque<String>, // Game ids, oldest first
+    records: HashMap<String, GameRecord>,
+    evicted: Option<Vec<GameRecord>>, // Dropped for capacity, awaiting an archive; None when nothing archives
+}
+
+impl GameHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: RwLock::new(HistoryInner::default()),
+        }
+    }
+
+    pub fn record(&self, record: GameRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut inner = self.inner.write();
+        if inner.records.insert(record.game_id.clone(), record.clone()).is_none() {
+            inner.order.push_back(record.game_id);
+        }
+        while inner.order.len() > self.capacity {
+            if let Some(oldest) = inner.order.pop_front() {
+                let record = inner.records.remove(&oldest);
+                if let (Some(evicted), Some(record)) = (inner.evicted.as_mut(), record) {
+                    evicted.push(record);
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, game_id: &str) -> Option<GameRecord> {
+        self.inner.read().records.get(game_id).cloned()
+    }
+
+    /// Holds on to records evicted for capacity until [`Self::take_archivable`] collects them.
+    pub fn keep_evicted(&self) {
+        self.inner.write().evicted.get_or_insert_with(Vec::new);
+    }
+
+    /// Removes and returns games that ended before `cutoff`, plus any evicted since the last call.
+    pub fn take_archivable(&self, cutoff: DateTime<Utc>) -> Vec<GameRecord> {
+        let mut inner = self.inner.write();
+        let mut taken = inner.evicted.as_mut().map(std::mem::take).unwrap_or_default();
+        // Ids are in insertion order, which is end order, so aged-out games are at the front
+        while let Some(oldest) = inner.order.front() {
+            match inner.records.get(oldest).and_then(|record| record.ended_at) {
+                Some(ended_at) if ended_at < cutoff => {
+                    let oldest = inner.order.pop_front().expect("front exists");
+                    taken.extend(inner.records.remove(&oldest));
+                }
+                _ => break,
+            }
+        }
+        taken
+    }
+
+    /// Tallies finished games for several players in a single scan, in the order asked.
+    pub fn results_of(&self, player_ids: &[String]) -> Vec<PlayerResults> {
+        let mut results: HashMap<&str, (PlayerResults, DecisionTally)> =
+            player_ids.iter().map(|id| (id.as_str(), Default::default())).collect();
+        let inner = self.inner.read();
+        for record in inner.records.values().filter(|record| record.ended_at.is_some()) {
+            for player_id in &record.players {
+                let Some((tally, decisions)) = results.get_mut(player_id.as_str()) else {
+                    continue;
+                };
+                // In team games the winner is a team id
+                let side = record.teams.get(player_id).unwrap_or(player_id);
+                tally.games += 1;
+                match &record.winner {
+                    Some(winner) if winner == side => tally.wins += 1,
+                    Some(_) => tally.losses += 1,
+                    None => tally.draws += 1,
+                }
+                record.rounds.iter().filter_map(|round| round.decision_ms.get(player_id)).for_each(|ms| decisions.add(*ms));
+            }
+        }
+        player_ids
+            .iter()
+            .map(|id| {
+                let (tally, decisions) = &results[id.as_str()];
+                PlayerResults {
+                    decisions: decisions.count,
+                    avg_decision_ms: decisions.average_ms(),
+                    fastest_decision_ms: (decisions.count > 0).then_some(decisions.fastest_ms),
+                    ..*tally
+                }
+            })
+            .collect()
+    }
+
+    /// Players with at least `min_decisions` timed moves in the finished games still held,
+    /// quickest average decision first, then by their single fastest move. Decision times
+    /// are as the server measured them, without latency compensation.
+    pub fn fastest_players(&self, limit: usize, min_decisions: u32) -> Vec<FastestPlayer> {
+        let mut decisions: HashMap<&str, DecisionTally> = HashMap::new();
+        let inner = self.inner.read();
+        for record in inner.records.values().filter(|record| record.ended_at.is_some()) {
+            for round in &record.rounds {
+                for (player_id, ms) in &round.decision_ms {
+                    decisions.entry(player_id.as_str()).or_default().add(*ms);
+                }
+            }
+        }
+
+        let mut fastest: Vec<FastestPlayer> = decisions
+            .into_iter()
+            .filter(|(_, tally)| tally.count >= min_decisions.max(1))
+            .map(|(player_id, tally)| FastestPlayer {
+                player_id: player_id.to_string(),
+                decisions: tally.count,
+                avg_decision_ms: tally.average_ms().unwrap_or_default(),
+                fastest_decision_ms: tally.fastest_ms,
+            })
+            .collect();
+        fastest.sort_by(|a, b| {
+            (a.avg_decision_ms, a.fastest_decision_ms, &a.player_id).cmp(&(b.avg_decision_ms, b.fastest_decision_ms, &b.player_id))
+        });
+        fastest.truncate(limit);
+        fastest
+    }
+
+    /// Up to `limit` of the player's finished games, most recently ended first.
+    pub fn games_of(&self, player_id: &str, limit: usize) -> Vec<GameRecord> {
+        let inner = self.inner.read();
+        inner
+            .order
+            .iter()
+            .rev()
+            .filter_map(|game_id| inner.records.get(game_id))
+            .filter(|record| record.ended_at.is_some() && record.involves(player_id))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Anonymizes every recorded game the player was in; returns how many.
+    pub fn anonymize(&self, player_id: &str, alias: &str) -> usize {
+        let mut guard = self.inner.write();
+        let inner = &mut *guard;
+        let mut count = inner.records.values_mut().map(|record| record.anonymize(player_id, alias)).filter(|changed| *changed).count();
+        if let Some(evicted) = &mut inner.evicted {
+            count += evicted.iter_mut().map(|record| record.anonymize(player_id, alias)).filter(|changed| *changed).count();
+        }
+        count
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
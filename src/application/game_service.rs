@@ -1,32 +1,300 @@
 use anyhow::Result;
-use chrono::Utc;
-use std::collections::HashMap;
-use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 use tracing::{info, warn};
+use uuid::Uuid;
 
-use crate::domain::{GameChoice, GameConfig, GameResult, GameStatus, Player, PlayerInfo, PlayerMove, ServerMessage};
+use super::clock::{Clock, SystemClock};
+use super::bot_detection::MoveSample;
+use super::bot_opponent::{BotOpponent, BotState};
+use super::event_bus::EventBus;
+use super::game_history::{GameHistory, GameRecord};
+use super::live_stats::LiveStats;
+use super::result_signing::{ordered, ResultSigner, SignedResult};
+use crate::domain::{
+    DrawPolicy, Game, GameChoice, GameConfig, GameEndReason, GameEvent, GameMode, GameResult, GameStatus, GameType, MoveChangePolicy, PauseReason, Player, PlayerInfo, PlayerMove, PlayerPhase,
+    ResultSignature, RoundSummary, ServerMessage,
+};
+
+/// Operator-defined rule sets, looked up by the script name a room template gives.
+pub trait RuleScripts: Send + Sync {
+    fn game(&self, script: &str) -> Option<Box<dyn Game>>;
+}
+
+/// Where a room finds the title each player shows in `GameStart`.
+pub trait TitleLookup: Send + Sync {
+    fn equipped_title(&self, player_id: &str) -> Option<String>;
+}
+
+/// Why a move was refused: the player already moved this round and the room keeps first moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveAlreadySubmitted {
+    pub round: u32,
+}
+
+impl fmt::Display for MoveAlreadySubmitted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Already moved in round {}", self.round)
+    }
+}
+
+impl std::error::Error for MoveAlreadySubmitted {}
+
+/// Why a move was refused before it was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveRefused {
+    NotSeated,     // The mover has no seat in the room
+    Paused,        // Moves wait until the game resumes
+    RoundClosed,   // No round is open: the game hasn't started or is over, or the window has closed
+    InvalidChoice, // Not a choice in the room's rules
+}
+
+impl fmt::Display for MoveRefused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveRefused::NotSeated => write!(f, "Not seated in this room"),
+            MoveRefused::Paused => write!(f, "The game is paused"),
+            MoveRefused::RoundClosed => write!(f, "No round is open for moves"),
+            MoveRefused::InvalidChoice => write!(f, "Not a choice in this room's rules"),
+        }
+    }
+}
+
+impl std::error::Error for MoveRefused {}
+
+// Recipients a broadcast is handed to directly while the fan-out worker is idle; bigger
+// audiences, mostly spectators, are left to the worker so the room isn't held up
+const INLINE_FANOUT_MAX: usize = 16;
+
+struct Delivery {
+    recipients: Vec<Arc<Player>>,
+    message: ServerMessage,
+}
+
+/// A room's outbound messages, delivered in order by one task off the room's lock.
+struct FanOut {
+    tx: mpsc::UnboundedSender<Delivery>,
+    pending: Arc<AtomicUsize>, // Deliveries queued or in progress; inline sends wait for zero to keep order
+}
+
+impl FanOut {
+    fn spawn() -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Delivery>();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let worker_pending = pending.clone();
+        // Ends with the room, once its sender is dropped and the queue drained
+        tokio::spawn(async move {
+            while let Some(delivery) = rx.recv().await {
+                #[cfg(feature = "chaos")]
+                if let Some(delay) = super::chaos::CHAOS.broadcast_delay() {
+                    tokio::time::sleep(delay).await;
+                }
+                send_all(&delivery.recipients, &delivery.message);
+                worker_pending.fetch_sub(1, Ordering::Release);
+            }
+        });
+        Self { tx, pending }
+    }
+}
+
+fn send_all(recipients: &[Arc<Player>], message: &ServerMessage) {
+    for player in recipients {
+        if player.sender.send(message.clone()).is_err() {
+            warn!("Failed to send message to player {}", player.id);
+        }
+    }
+}
 
 pub struct GameRoom {
     pub id: String,
+    pub game_id: String, // Unique per game and kept after the room is gone
     pub players: Vec<Arc<Player>>,
+    pub spectators: Vec<Arc<Player>>,
+    pub teams: HashMap<String, String>, // playerId -> teamId, team mode only
     pub current_round: u32,
     pub config: GameConfig,
+    game: Box<dyn Game>, // Built from `config.game`
     pub scores: HashMap<String, u32>,
     pub moves: HashMap<String, PlayerMove>,
     pub status: GameStatus,
+    pub started_at: Option<Instant>,
+    pub disconnected: HashMap<String, Instant>, // playerId -> forfeit deadline
+    pub pause_requests: HashSet<String>,
+    pub resume_requests: HashSet<String>,
+    pub consent_pause_until: Option<Instant>,
+    paused_since: Option<Instant>,
+    paused_for: Duration, // Time spent paused; not charged to the match clock
+    events: EventBus,
+    round_started_at: Option<Instant>,
+    previous_moves: HashMap<String, GameChoice>, // Last resolved round
+    round_history: Vec<RoundSummary>, // Sent with GameEnd
+    move_samples: Vec<MoveSample>,
+    bot: Option<BotOpponent>,
+    created_at: DateTime<Utc>,
+    outcome: Option<(Option<String>, GameEndReason, DateTime<Utc>)>, // Winner, reason, end time
+    history: Option<Arc<GameHistory>>,
+    signer: Option<Arc<ResultSigner>>,
+    stats: Option<Arc<LiveStats>>,
+    titles: Option<Arc<dyn TitleLookup>>,
+    fanout: OnceLock<FanOut>, // Started by the first broadcast too big to send inline
+    seq: AtomicU64,           // Last sequence number stamped on a broadcast
+    clock: Arc<dyn Clock>,
+}
+
+/// Rough heap footprint of one subsystem, from element counts and struct sizes.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct MemoryEstimate {
+    pub rooms_bytes: usize,
+    pub queues_bytes: usize,
+    pub move_history_bytes: usize, // Per-room move samples kept for bot detection
+}
+
+/// Public view of a room for REST clients. Pending moves are never included.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomSnapshot {
+    pub id: String,
+    pub status: GameStatus,
+    pub players: Vec<RoomPlayer>,
+    pub spectator_count: usize,
+    pub current_round: u32,
+    pub scores: HashMap<String, u32>,
+    pub config: GameConfig,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomPlayer {
+    pub id: String,
+    pub connected: bool,
+    pub team: Option<String>,
+}
+
+/// A live room in transferable form, for handing it to another node: players, scores and
+/// the round in progress with any moves already made. Connections and spectators stay
+/// behind; the receiving node seats players as they reconnect.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomState {
+    pub id: String,
+    pub game_id: String,
+    pub players: Vec<String>, // Seat order
+    pub teams: HashMap<String, String>,
+    pub config: GameConfig,
+    pub status: GameStatus,
+    pub current_round: u32,
+    pub scores: HashMap<String, u32>,
+    pub moves: HashMap<String, PlayerMove>, // This round's moves so far
+    pub previous_moves: HashMap<String, GameChoice>,
+    pub rounds: Vec<RoundSummary>,
+    pub disconnected: Vec<String>,
+    pub pause_requests: Vec<String>,
+    pub resume_requests: Vec<String>,
+    pub consent_pause_left_ms: Option<u64>,
+    pub played_ms: u64, // Match clock spent so far, pauses excluded
+    pub bot: Option<BotState>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub seq: u64, // Last broadcast sequence number, so clients see no jump after a migration
 }
 
 impl GameRoom {
     pub fn new(id: String, config: GameConfig) -> Self {
         Self {
             id,
+            game_id: Uuid::new_v4().to_string(),
             players: Vec::new(),
+            spectators: Vec::new(),
+            teams: HashMap::new(),
             current_round: 1,
+            game: config.game.build(&config.rules),
             config,
             scores: HashMap::new(),
             moves: HashMap::new(),
             status: GameStatus::Waiting,
+            started_at: None,
+            disconnected: HashMap::new(),
+            pause_requests: HashSet::new(),
+            resume_requests: HashSet::new(),
+            consent_pause_until: None,
+            paused_since: None,
+            paused_for: Duration::ZERO,
+            events: EventBus::new(),
+            round_started_at: None,
+            previous_moves: HashMap::new(),
+            round_history: Vec::new(),
+            move_samples: Vec::new(),
+            bot: None,
+            created_at: Utc::now(),
+            outcome: None,
+            history: None,
+            signer: None,
+            stats: None,
+            titles: None,
+            fanout: OnceLock::new(),
+            seq: AtomicU64::new(0),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Times moves, pauses and grace periods by `clock` instead of the system time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // Time since `at` by the room's clock
+    fn since(&self, at: Instant) -> Duration {
+        self.clock.now().saturating_duration_since(at)
+    }
+
+    /// Finished games are recorded in `history` so they can be looked up after the room is removed.
+    pub fn with_history(mut self, history: Arc<GameHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Round results and the game end are signed when a signer is set.
+    pub fn with_signer(mut self, signer: Arc<ResultSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Status changes are counted in `stats`, which `GameManager::get_stats` reads.
+    pub fn with_stats(mut self, stats: Arc<LiveStats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    pub fn with_titles(mut self, titles: Arc<dyn TitleLookup>) -> Self {
+        self.titles = Some(titles);
+        self
+    }
+
+    /// Plays rounds by `game`, a rule script's rules, in place of those of `config.game`.
+    pub fn with_game(mut self, game: Box<dyn Game>) -> Self {
+        self.game = game;
+        self
+    }
+
+    fn set_status(&mut self, status: GameStatus) {
+        if let Some(stats) = &self.stats {
+            stats.status_changed(&self.status, &status);
         }
+        self.status = status;
+    }
+
+    fn sign(&self, result: SignedResult) -> Option<ResultSignature> {
+        self.signer.as_ref().and_then(|signer| signer.sign(&result))
     }
 
     pub fn add_player(&mut self, player: Arc<Player>) -> Result<bool> {
@@ -34,48 +302,401 @@ impl GameRoom {
             return Ok(false);
         }
 
-        self.scores.insert(player.id.clone(), 0);
+        // Teams fill in join order: first two players are team-a
+        if self.config.mode == GameMode::Teams {
+            let team = if self.players.len() < 2 { "team-a" } else { "team-b" };
+            self.teams.insert(player.id.clone(), team.to_string());
+        }
+
+        self.scores.insert(self.side_of(&player.id), 0);
         self.players.push(player);
 
         if self.players.len() >= self.config.min_players {
-            self.status = GameStatus::Playing;
+            self.set_status(GameStatus::Playing);
+            self.started_at = Some(self.clock.now());
+            self.round_started_at = self.started_at;
         }
 
         Ok(true)
     }
 
+    /// Seats a server bot in the next free slot.
+    pub fn add_bot(&mut self) -> Result<bool> {
+        let bot = BotOpponent::new();
+        let player = bot.player().clone();
+        self.bot = Some(bot);
+        self.add_player(player)
+    }
+
+    fn is_bot(&self, player_id: &str) -> bool {
+        self.bot.as_ref().is_some_and(|bot| bot.id() == player_id)
+    }
+
     pub async fn start_game(&self) -> Result<()> {
         let message = ServerMessage::GameStart {
+            seq: None,
             room_id: self.id.clone(),
-            players: self.players.iter().map(|p| PlayerInfo { id: p.id.clone() }).collect(),
+            game_id: self.game_id.clone(),
+            players: self
+                .players
+                .iter()
+                .map(|p| PlayerInfo {
+                    id: p.id.clone(),
+                    title: self.titles.as_ref().and_then(|titles| titles.equipped_title(&p.id)),
+                    name: p.display_name.clone(),
+                })
+                .collect(),
             max_rounds: self.config.max_rounds,
+            draw_policy: self.config.draw_policy,
+            teams: self.teams_field(),
+            rules: (!self.config.rules.is_classic()).then(|| self.config.rules.clone()),
+            fairness_commitment: self.bot.as_ref().map(BotOpponent::commitment),
+            round_window_ms: self.config.round_window_ms,
+            game: self.config.game,
+            template: self.config.template.clone(),
+            chat_enabled: self.config.chat_enabled,
         };
 
         self.broadcast_to_all(&message).await
     }
 
-    pub fn submit_move(&mut self, player_id: &str, choice: GameChoice) -> Result<bool> {
-        if self.status != GameStatus::Playing {
+    pub fn is_over_time_limit(&self) -> bool {
+        match (self.config.match_time_limit_ms, self.started_at) {
+            (Some(limit_ms), Some(started_at)) => {
+                let played = self.since(started_at).saturating_sub(self.paused_for);
+                self.status == GameStatus::Playing && played >= Duration::from_millis(limit_ms)
+            }
+            _ => false,
+        }
+    }
+
+    /// Ends the game in favour of the current leader once the match time budget is spent.
+    pub async fn enforce_time_limit(&mut self) -> Result<bool> {
+        if !self.is_over_time_limit() {
             return Ok(false);
         }
 
-        if !self.players.iter().any(|p| p.id == player_id) {
+        info!("Room {} exceeded its match time limit", self.id);
+        self.end_game(GameEndReason::TimeLimit).await?;
+        Ok(true)
+    }
+
+    /// Pauses the game for the configured grace period instead of tearing it down.
+    /// Returns false when the room should be dissolved immediately.
+    pub async fn handle_disconnect(&mut self, player_id: &str) -> Result<bool> {
+        let grace_ms = self.config.reconnect_grace_ms;
+        let in_progress = matches!(self.status, GameStatus::Playing | GameStatus::Paused);
+        if grace_ms == 0 || !in_progress || !self.players.iter().any(|p| p.id == player_id) {
             return Ok(false);
         }
 
-        self.moves.insert(
-            player_id.to_string(),
-            PlayerMove {
-                choice,
-                timestamp: Utc::now(),
-            },
-        );
+        self.disconnected
+            .insert(player_id.to_string(), self.clock.now() + Duration::from_millis(grace_ms));
+        self.enter_pause();
+        info!("Room {} paused: {} disconnected", self.id, player_id);
+
+        let message = ServerMessage::GamePaused {
+            seq: None,
+            room_id: self.id.clone(),
+            reason: PauseReason::PlayerDisconnected,
+            player_id: Some(player_id.to_string()),
+            resume_within_ms: grace_ms,
+        };
+        self.broadcast_to_all(&message).await?;
+        Ok(true)
+    }
+
+    /// Swaps in the reconnected player's new connection and resumes once everyone is back.
+    pub async fn reconnect(&mut self, player: Arc<Player>) -> Result<bool> {
+        if self.disconnected.remove(&player.id).is_none() {
+            return Ok(false);
+        }
+
+        if let Some(slot) = self.players.iter_mut().find(|p| p.id == player.id) {
+            *slot = player.clone();
+        }
+        info!("Player {} rejoined room {}", player.id, self.id);
+
+        self.try_resume().await?;
+        Ok(true)
+    }
+
+    /// Relays a chat line from a seated player to the room, spectators included. A
+    /// shadow-muted line only goes back to its sender. False if the player isn't seated.
+    pub async fn chat(&self, player_id: &str, text: String, shadow_muted: bool) -> Result<bool> {
+        let Some(sender) = self.players.iter().find(|p| p.id == player_id) else {
+            return Ok(false);
+        };
+
+        let message = ServerMessage::Chat { seq: None, player_id: player_id.to_string(), text };
+        if shadow_muted {
+            self.send_to(sender, message);
+        } else {
+            self.broadcast_to_all(&message).await?;
+        }
+        Ok(true)
+    }
+
+    /// Records a pause vote; the game pauses once every player has asked.
+    pub async fn request_pause(&mut self, player_id: &str) -> Result<bool> {
+        if self.status != GameStatus::Playing || !self.players.iter().any(|p| p.id == player_id) {
+            return Ok(false);
+        }
+
+        self.pause_requests.insert(player_id.to_string());
+        if self.pause_requests.len() < self.players.len() {
+            let message = ServerMessage::PauseRequested {
+                seq: None,
+                player_id: player_id.to_string(),
+            };
+            self.broadcast_to_all(&message).await?;
+            return Ok(true);
+        }
+
+        let max_pause_ms = self.config.max_pause_ms;
+        self.pause_requests.clear();
+        self.consent_pause_until = Some(self.clock.now() + Duration::from_millis(max_pause_ms));
+        self.enter_pause();
+        info!("Room {} paused by mutual consent", self.id);
+
+        let message = ServerMessage::GamePaused {
+            seq: None,
+            room_id: self.id.clone(),
+            reason: PauseReason::MutualConsent,
+            player_id: None,
+            resume_within_ms: max_pause_ms,
+        };
+        self.broadcast_to_all(&message).await?;
+        Ok(true)
+    }
+
+    /// Records a resume vote; the game resumes once every player has asked.
+    pub async fn request_resume(&mut self, player_id: &str) -> Result<bool> {
+        if self.consent_pause_until.is_none() || !self.players.iter().any(|p| p.id == player_id) {
+            return Ok(false);
+        }
+
+        self.resume_requests.insert(player_id.to_string());
+        if self.resume_requests.len() < self.players.len() {
+            let message = ServerMessage::ResumeRequested {
+                seq: None,
+                player_id: player_id.to_string(),
+            };
+            self.broadcast_to_all(&message).await?;
+            return Ok(true);
+        }
+
+        self.end_consent_pause().await?;
+        Ok(true)
+    }
+
+    /// Resumes a consent pause that has run past `max_pause_ms`.
+    pub async fn enforce_pause_limit(&mut self) -> Result<bool> {
+        match self.consent_pause_until {
+            Some(deadline) if deadline <= self.clock.now() => {
+                info!("Room {} pause expired", self.id);
+                self.end_consent_pause().await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn end_consent_pause(&mut self) -> Result<()> {
+        self.resume_requests.clear();
+        self.consent_pause_until = None;
+        self.try_resume().await?;
+        Ok(())
+    }
+
+    fn enter_pause(&mut self) {
+        if self.status != GameStatus::Paused {
+            self.set_status(GameStatus::Paused);
+            self.paused_since = Some(self.clock.now());
+        }
+    }
+
+    // Resumes only when no player is missing and no consent pause is still running
+    async fn try_resume(&mut self) -> Result<bool> {
+        if self.status != GameStatus::Paused || !self.disconnected.is_empty() || self.consent_pause_until.is_some() {
+            return Ok(false);
+        }
+
+        if let Some(since) = self.paused_since.take() {
+            self.paused_for += self.since(since);
+        }
+        self.set_status(GameStatus::Playing);
+        // Nobody loses a timed round to the pause
+        if self.config.round_window_ms.is_some() {
+            self.round_started_at = Some(self.clock.now());
+        }
+
+        let message = ServerMessage::GameResumed {
+            seq: None,
+            room_id: self.id.clone(),
+            round: self.current_round,
+            scores: self.scores.clone(),
+        };
+        self.broadcast_to_all(&message).await?;
+        Ok(true)
+    }
+
+    /// Forfeits the game to the players still connected once a grace period lapses.
+    pub async fn enforce_reconnect_grace(&mut self) -> Result<bool> {
+        let now = self.clock.now();
+        if !self.disconnected.values().any(|deadline| *deadline <= now) {
+            return Ok(false);
+        }
+
+        let remaining: HashSet<_> = self
+            .players
+            .iter()
+            .filter(|p| !self.disconnected.contains_key(&p.id))
+            .map(|p| self.side_of(&p.id))
+            .collect();
+        let winner = if remaining.len() == 1 { remaining.into_iter().next() } else { None };
+
+        info!("Room {} forfeited after reconnect grace expired", self.id);
+        self.finish(winner, GameEndReason::Forfeit).await?;
+        Ok(true)
+    }
+
+    /// When the current round closes whether or not everyone has moved; blitz rounds only.
+    pub fn round_deadline(&self) -> Option<Instant> {
+        let window = Duration::from_millis(self.config.round_window_ms?);
+        match (&self.status, self.round_started_at) {
+            (GameStatus::Playing, Some(started)) => Some(started + window),
+            _ => None,
+        }
+    }
+
+    /// Resolves the round once its window has closed; whoever hasn't moved loses it.
+    pub async fn expire_round(&mut self) -> Result<bool> {
+        match self.round_deadline() {
+            Some(deadline) if deadline <= self.clock.now() => {
+                self.process_round().await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Whether every move of the round has been in for at least `grace` without the round
+    /// being resolved. Resolution follows the last move at once, so this only happens when
+    /// resolving failed or panicked part way.
+    pub fn is_stuck(&self, grace: Duration) -> bool {
+        if self.status != GameStatus::Playing || !self.round_complete() {
+            return false;
+        }
+        self.moves
+            .values()
+            .map(|player_move| player_move.timestamp)
+            .max()
+            .is_some_and(|last| (self.clock.utc_now() - last).to_std().unwrap_or_default() >= grace)
+    }
+
+    /// Takes the player's move; true once every seat has moved. A move the room can't take
+    /// fails with [`MoveRefused`], and a second move in the round with [`MoveAlreadySubmitted`]
+    /// unless the room lets moves change.
+    pub fn submit_move(&mut self, player_id: &str, choice: GameChoice) -> Result<bool> {
+        let Some(player) = self.players.iter().find(|p| p.id == player_id) else {
+            return Err(MoveRefused::NotSeated.into());
+        };
+        if self.status == GameStatus::Paused {
+            return Err(MoveRefused::Paused.into());
+        }
+        if self.status != GameStatus::Playing || self.round_deadline().is_some_and(|deadline| deadline <= self.clock.now()) {
+            return Err(MoveRefused::RoundClosed.into());
+        }
+        let choice = self.game.canonical(choice);
+        if !self.game.validate_move(&choice) {
+            return Err(MoveRefused::InvalidChoice.into());
+        }
+        let first_move = !self.moves.contains_key(player_id);
+        if !first_move && self.config.move_changes == MoveChangePolicy::Reject {
+            return Err(MoveAlreadySubmitted { round: self.current_round }.into());
+        }
+
+        // The round start reached the player half a round trip late and the move took the other
+        // half to come back, so the whole (capped) round trip comes off the response time
+        let compensation = player
+            .latency
+            .rtt()
+            .unwrap_or_default()
+            .min(Duration::from_millis(self.config.max_latency_compensation_ms));
+        let elapsed = self.round_started_at.map(|started| self.since(started));
+        let response_ms = elapsed.map(|elapsed| elapsed.saturating_sub(compensation).as_millis() as u64);
+        let decision_ms = elapsed.map(|elapsed| elapsed.as_millis() as u64);
+
+        // A changed move is the same decision, so bot detection only sees the first
+        if first_move {
+            self.record_move_sample(player_id, &choice);
+        }
+        let player_move = PlayerMove {
+            choice,
+            timestamp: self.clock.utc_now(),
+            response_ms,
+            decision_ms,
+        };
+        self.game.apply_move(&mut self.moves, player_id, player_move);
+
+        // The bot's move comes from its committed sequence, never from the move it just saw
+        if let Some(bot) = &mut self.bot {
+            if !self.moves.contains_key(bot.id()) {
+                let choice = bot.next_move(&self.config.rules);
+                self.moves.insert(
+                    bot.id().to_string(),
+                    PlayerMove {
+                        choice,
+                        timestamp: self.clock.utc_now(),
+                        response_ms: None,
+                        decision_ms: None,
+                    },
+                );
+            }
+        }
+
+        Ok(self.round_complete())
+    }
+
+    // Every seat has a move in for the current round
+    fn round_complete(&self) -> bool {
+        let seats: Vec<String> = self.players.iter().map(|p| p.id.clone()).collect();
+        self.game.is_complete(&self.moves, &seats)
+    }
+
+    fn record_move_sample(&mut self, player_id: &str, choice: &GameChoice) {
+        let Some(round_started_at) = self.round_started_at else {
+            return;
+        };
+
+        // Only rock-paper-scissors has a move that counters the last one
+        let side = self.side_of(player_id);
+        let countered_previous = self
+            .previous_moves
+            .iter()
+            .find(|(id, _)| self.side_of(id) != side && self.config.game == GameType::RockPaperScissors)
+            .map(|(_, previous)| self.config.rules.beats(choice, previous));
 
-        // Check if all players have moved
-        Ok(self.moves.len() == self.players.len())
+        self.move_samples.push(MoveSample {
+            player_id: player_id.to_string(),
+            reaction_ms: self.since(round_started_at).as_millis() as u64,
+            countered_previous,
+        });
+    }
+
+    /// Moves accepted since the last call, for bot detection.
+    pub fn take_move_samples(&mut self) -> Vec<MoveSample> {
+        std::mem::take(&mut self.move_samples)
     }
 
+    /// Resolves the current round once every move is in or its window has closed; otherwise
+    /// there is nothing to resolve, as when the round timer got to it first, and it does nothing.
     pub async fn process_round(&mut self) -> Result<()> {
+        let window_closed = self.round_deadline().is_some_and(|deadline| deadline <= self.clock.now());
+        if self.status != GameStatus::Playing || !(self.round_complete() || window_closed) {
+            return Ok(());
+        }
         let result = self.calculate_round_result()?;
         
         info!(
@@ -86,23 +707,70 @@ impl GameRoom {
         );
 
         // Update scores
-        if let Some(ref winner_id) = result.winner {
-            *self.scores.get_mut(winner_id).unwrap() += 1;
+        let replay = result.winner.is_none() && self.config.draw_policy == DrawPolicy::Replay;
+        match (&result.winner, self.config.draw_policy) {
+            (Some(winner_id), _) => *self.scores.get_mut(winner_id).unwrap() += 1,
+            (None, DrawPolicy::BothScore) => self.scores.values_mut().for_each(|score| *score += 1),
+            (None, _) => {}
         }
 
+        // Decision times are reported as the server measured them; `response_ms` is the fair-play view
+        let decision_ms: HashMap<String, u64> = self
+            .moves
+            .iter()
+            .filter_map(|(id, player_move)| Some((id.clone(), player_move.decision_ms?)))
+            .collect();
+
         // Send round result
-        let round_result = ServerMessage::RoundResult {
-            round: result.round,
-            winner: result.winner.clone(),
-            moves: result.moves,
-            scores: self.scores.clone(),
-        };
+        let game_over = !replay && self.should_end_game();
+        if self.config.round_window_ms.is_some() {
+            let next_round = (!game_over).then_some(self.current_round + 1);
+            self.broadcast_to_all(&self.blitz_round(&result, &decision_ms, next_round)).await?;
+        } else {
+            let signature = self.sign(SignedResult::RoundResult {
+                game_id: self.game_id.clone(),
+                room_id: self.id.clone(),
+                round: result.round,
+                winner: result.winner.clone(),
+                moves: ordered(&result.moves),
+                scores: ordered(&self.scores),
+                signed_at: Utc::now(),
+            });
+            let round_result = ServerMessage::RoundResult {
+                seq: None,
+                round: result.round,
+                winner: result.winner.clone(),
+                moves: result.moves.clone(),
+                scores: self.scores.clone(),
+                replay,
+                decision_ms: decision_ms.clone(),
+                teams: self.teams_field(),
+                signature,
+            };
 
-        self.broadcast_to_all(&round_result).await?;
+            self.broadcast_to_all(&round_result).await?;
+        }
+
+        self.round_history.push(RoundSummary {
+            round: result.round,
+            moves: result.moves.clone(),
+            winner: result.winner,
+            replayed: replay,
+            response_ms: self
+                .moves
+                .iter()
+                .filter_map(|(id, player_move)| Some((id.clone(), player_move.response_ms?)))
+                .collect(),
+            decision_ms,
+        });
 
         // Check for game end
-        if self.should_end_game() {
-            self.end_game().await?;
+        self.previous_moves = result.moves;
+
+        if replay {
+            self.replay_round().await?;
+        } else if game_over {
+            self.end_game(GameEndReason::Completed).await?;
         } else {
             self.next_round().await?;
         }
@@ -113,19 +781,19 @@ impl GameRoom {
     fn calculate_round_result(&self) -> Result<GameResult> {
         let player_ids: Vec<String> = self.players.iter().map(|p| p.id.clone()).collect();
         
-        if player_ids.len() != 2 {
+        if player_ids.len() != self.config.mode.players_per_room() {
             return Err(anyhow::anyhow!("Invalid number of players"));
         }
 
-        let p1_move = &self.moves[&player_ids[0]];
-        let p2_move = &self.moves[&player_ids[1]];
-
-        let winner = if p1_move.choice == p2_move.choice {
-            None // Draw
-        } else if p1_move.choice.beats(&p2_move.choice) {
-            Some(player_ids[0].clone())
-        } else {
-            Some(player_ids[1].clone())
+        let winner = match self.config.mode {
+            // Only a timed round can close with a move missing; the side that missed it loses
+            GameMode::Solo | GameMode::Bot | GameMode::Blitz => match (self.moves.get(&player_ids[0]), self.moves.get(&player_ids[1])) {
+                (Some(p1_move), Some(p2_move)) => self.game.result([&p1_move.choice, &p2_move.choice]).map(|seat| player_ids[seat].clone()),
+                (None, Some(_)) => Some(player_ids[1].clone()),
+                (Some(_), None) => Some(player_ids[0].clone()),
+                (None, None) => None,
+            },
+            GameMode::Teams => self.team_round_winner(),
         };
 
         let moves_map: HashMap<String, GameChoice> = self
@@ -142,30 +810,135 @@ impl GameRoom {
         })
     }
 
+    // Every player is matched against every opponent; the team with more wins takes the round
+    fn team_round_winner(&self) -> Option<String> {
+        let mut wins: HashMap<String, u32> = HashMap::new();
+        for (id, player_move) in &self.moves {
+            for (other_id, other_move) in &self.moves {
+                if self.side_of(id) != self.side_of(other_id) && self.config.rules.beats(&player_move.choice, &other_move.choice) {
+                    *wins.entry(self.side_of(id)).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let best = *wins.values().max()?;
+        let mut leaders = wins.into_iter().filter(|(_, count)| *count == best);
+        match (leaders.next(), leaders.next()) {
+            (Some((team, _)), None) => Some(team),
+            _ => None, // Tied aggregate
+        }
+    }
+
+    /// The scoring side a player belongs to: their team in team mode, otherwise themselves.
+    pub fn side_of(&self, player_id: &str) -> String {
+        self.teams.get(player_id).cloned().unwrap_or_else(|| player_id.to_string())
+    }
+
+    // Seat-ordered, so a round fits in a few bytes per player
+    fn blitz_round(&self, result: &GameResult, decision_ms: &HashMap<String, u64>, next_round: Option<u32>) -> ServerMessage {
+        ServerMessage::BlitzRound {
+            seq: None,
+            round: result.round,
+            winner: result.winner.as_ref().and_then(|winner| self.players.iter().position(|p| p.id == *winner)),
+            moves: self.players.iter().map(|p| result.moves.get(&p.id).cloned()).collect(),
+            scores: self.players.iter().map(|p| self.scores.get(&p.id).copied().unwrap_or(0)).collect(),
+            decision_ms: self.players.iter().map(|p| decision_ms.get(&p.id).copied()).collect(),
+            next_round,
+        }
+    }
+
+    fn teams_field(&self) -> Option<HashMap<String, String>> {
+        (!self.teams.is_empty()).then(|| self.teams.clone())
+    }
+
     fn should_end_game(&self) -> bool {
         let max_score = *self.scores.values().max().unwrap_or(&0);
-        max_score >= 2 || self.current_round >= self.config.max_rounds
+        max_score >= self.wins_needed() || self.current_round >= self.config.max_rounds
+    }
+
+    // Majority of the round count, e.g. 2 for best-of-3
+    fn wins_needed(&self) -> u32 {
+        self.config.max_rounds / 2 + 1
+    }
+
+    async fn replay_round(&mut self) -> Result<()> {
+        self.moves.clear();
+        self.round_started_at = Some(self.clock.now());
+
+        let message = ServerMessage::NextRound {
+            seq: None,
+            round: self.current_round,
+        };
+
+        self.broadcast_to_all(&message).await
     }
 
     async fn next_round(&mut self) -> Result<()> {
         self.current_round += 1;
         self.moves.clear();
+        self.round_started_at = Some(self.clock.now());
+
+        self.events.publish(GameEvent::RoundStarted {
+            room_id: self.id.clone(),
+            players: self.players.iter().map(|p| p.id.clone()).collect(),
+            round: self.current_round,
+            max_rounds: self.config.max_rounds,
+        });
 
+        // Blitz already announced it with the last round's result
+        if self.config.round_window_ms.is_some() {
+            return Ok(());
+        }
         let message = ServerMessage::NextRound {
+            seq: None,
             round: self.current_round,
         };
 
         self.broadcast_to_all(&message).await
     }
 
-    async fn end_game(&mut self) -> Result<()> {
-        self.status = GameStatus::Finished;
-
+    async fn end_game(&mut self, reason: GameEndReason) -> Result<()> {
         let final_winner = self.determine_final_winner();
+        self.finish(final_winner, reason).await
+    }
+
+    async fn finish(&mut self, winner: Option<String>, reason: GameEndReason) -> Result<()> {
+        self.set_status(GameStatus::Finished);
+        self.outcome = Some((winner.clone(), reason.clone(), Utc::now()));
+        if let Some(history) = &self.history {
+            history.record(self.record());
+        }
+
+        let signature = self.sign(SignedResult::GameEnd {
+            game_id: self.game_id.clone(),
+            room_id: self.id.clone(),
+            winner: winner.clone(),
+            final_scores: ordered(&self.scores),
+            reason: reason.clone(),
+            teams: ordered(&self.teams),
+            signed_at: Utc::now(),
+        });
+
+        self.events.publish(GameEvent::GameEnded {
+            room_id: self.id.clone(),
+            game_id: self.game_id.clone(),
+            winner: winner.clone(),
+            final_scores: self.scores.clone(),
+            reason: reason.clone(),
+            teams: self.teams.clone(),
+            signature: signature.clone().map(Box::new),
+        });
 
         let message = ServerMessage::GameEnd {
-            winner: final_winner,
+            seq: None,
+            game_id: self.game_id.clone(),
+            winner,
             final_scores: self.scores.clone(),
+            reason,
+            teams: self.teams_field(),
+            fairness_seed: self.bot.as_ref().map(BotOpponent::seed),
+            rounds: self.round_history.clone(),
+            signature,
         };
 
         self.broadcast_to_all(&message).await
@@ -186,22 +959,236 @@ impl GameRoom {
         }
     }
 
-    async fn broadcast_to_all(&self, message: &ServerMessage) -> Result<()> {
-        for player in &self.players {
-            if let Err(e) = player.send_message(message).await {
-                warn!("Failed to send message to player {}: {}", player.id, e);
-            }
+    /// Shallow estimate of this room's heap use; `queues_bytes` is always zero here.
+    pub fn memory_estimate(&self) -> MemoryEstimate {
+        let members = (self.players.capacity() + self.spectators.capacity()) * std::mem::size_of::<Arc<Player>>();
+        let per_player = self.scores.capacity() * std::mem::size_of::<(String, u32)>()
+            + self.moves.capacity() * std::mem::size_of::<(String, PlayerMove)>()
+            + self.teams.capacity() * std::mem::size_of::<(String, String)>();
+        let history = self.round_history.capacity() * std::mem::size_of::<RoundSummary>()
+            + self.round_history.iter().map(|r| r.moves.capacity() * std::mem::size_of::<(String, GameChoice)>()).sum::<usize>();
+        MemoryEstimate {
+            rooms_bytes: std::mem::size_of::<Self>() + members + per_player + history,
+            queues_bytes: 0,
+            move_history_bytes: self.move_samples.capacity() * std::mem::size_of::<MoveSample>(),
+        }
+    }
+
+    pub fn snapshot(&self) -> RoomSnapshot {
+        RoomSnapshot {
+            id: self.id.clone(),
+            status: self.status.clone(),
+            players: self
+                .players
+                .iter()
+                .map(|p| RoomPlayer {
+                    id: p.id.clone(),
+                    connected: !self.disconnected.contains_key(&p.id),
+                    team: self.teams.get(&p.id).cloned(),
+                })
+                .collect(),
+            spectator_count: self.spectators.len(),
+            current_round: self.current_round,
+            scores: self.scores.clone(),
+            config: self.config.clone(),
+        }
+    }
+
+    /// The `State` reply for one of the room's players: their own move, but nobody else's.
+    pub fn state_for(&self, player_id: &str, phase: PlayerPhase) -> ServerMessage {
+        let awaiting_moves = if self.status == GameStatus::Playing {
+            self.players
+                .iter()
+                .filter(|p| !self.moves.contains_key(&p.id) && !self.is_bot(&p.id))
+                .map(|p| p.id.clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        ServerMessage::State {
+            phase,
+            room_id: Some(self.id.clone()),
+            game_id: Some(self.game_id.clone()),
+            status: Some(self.status.clone()),
+            round: Some(self.current_round),
+            scores: self.scores.clone(),
+            awaiting_moves,
+            your_move: self.moves.get(player_id).map(|m| m.choice.clone()),
+            seq: Some(self.seq()),
+        }
+    }
+
+    pub fn state(&self) -> RoomState {
+        let now = self.clock.now();
+        let paused = self.paused_since.map_or(Duration::ZERO, |since| self.since(since));
+        let played = self
+            .started_at
+            .map_or(Duration::ZERO, |started_at| self.since(started_at).saturating_sub(self.paused_for + paused));
+        RoomState {
+            id: self.id.clone(),
+            game_id: self.game_id.clone(),
+            players: self.players.iter().map(|p| p.id.clone()).collect(),
+            teams: self.teams.clone(),
+            config: self.config.clone(),
+            status: self.status.clone(),
+            current_round: self.current_round,
+            scores: self.scores.clone(),
+            moves: self.moves.clone(),
+            previous_moves: self.previous_moves.clone(),
+            rounds: self.round_history.clone(),
+            disconnected: self.disconnected.keys().cloned().collect(),
+            pause_requests: self.pause_requests.iter().cloned().collect(),
+            resume_requests: self.resume_requests.iter().cloned().collect(),
+            consent_pause_left_ms: self.consent_pause_until.map(|until| until.saturating_duration_since(now).as_millis() as u64),
+            played_ms: played.as_millis() as u64,
+            bot: self.bot.as_ref().map(BotOpponent::state),
+            created_at: self.created_at,
+            seq: self.seq.load(Ordering::Acquire),
+        }
+    }
+
+    /// Takes over a room exported with `state`. Players missing from `connections`, and
+    /// those already away, get a fresh reconnect grace period and the game stays paused
+    /// until they are all back. The room must have been created with `state.config`.
+    pub fn restore(&mut self, state: RoomState, connections: &HashMap<String, Arc<Player>>) -> Result<()> {
+        let bot = match &state.bot {
+            Some(bot) => Some(BotOpponent::from_state(bot).ok_or_else(|| anyhow::anyhow!("Bad bot seed in room {}", state.id))?),
+            None => None,
+        };
+        let now = self.clock.now();
+        let deadline = now + Duration::from_millis(state.config.reconnect_grace_ms);
+
+        self.players = state
+            .players
+            .iter()
+            .map(|id| match (connections.get(id), &bot) {
+                (Some(player), _) => player.clone(),
+                (None, Some(bot)) if bot.id() == id => bot.player().clone(),
+                (None, _) => {
+                    // Stands in until the player reconnects; nothing is sent to it
+                    let (sender, _) = mpsc::unbounded_channel();
+                    Arc::new(Player::new(id.clone(), sender))
+                }
+            })
+            .collect();
+        self.disconnected = state
+            .players
+            .iter()
+            .filter(|id| state.disconnected.contains(id) || !connections.contains_key(*id))
+            .filter(|id| bot.as_ref().is_none_or(|bot| bot.id() != id.as_str()))
+            .map(|id| (id.clone(), deadline))
+            .collect();
+        self.bot = bot;
+        self.id = state.id;
+        self.game_id = state.game_id;
+        self.teams = state.teams;
+        self.config = state.config;
+        self.current_round = state.current_round;
+        self.scores = state.scores;
+        self.moves = state.moves;
+        self.previous_moves = state.previous_moves;
+        self.round_history = state.rounds;
+        self.pause_requests = state.pause_requests.into_iter().collect();
+        self.resume_requests = state.resume_requests.into_iter().collect();
+        self.consent_pause_until = state.consent_pause_left_ms.map(|left| now + Duration::from_millis(left));
+        self.started_at = Some(now.checked_sub(Duration::from_millis(state.played_ms)).unwrap_or(now));
+        self.round_started_at = Some(now);
+        self.created_at = state.created_at;
+        *self.seq.get_mut() = state.seq;
+
+        if state.status == GameStatus::Paused || !self.disconnected.is_empty() {
+            self.enter_pause();
+        } else {
+            self.set_status(state.status);
+        }
+        Ok(())
+    }
+
+    /// The game as a lookup record; winner, reason and end time stay empty until it finishes.
+    pub fn record(&self) -> GameRecord {
+        let (winner, reason, ended_at) = match &self.outcome {
+            Some((winner, reason, ended_at)) => (winner.clone(), Some(reason.clone()), Some(*ended_at)),
+            None => (None, None, None),
+        };
+        GameRecord {
+            game_id: self.game_id.clone(),
+            room_id: self.id.clone(),
+            mode: self.config.mode,
+            status: self.status.clone(),
+            players: self.players.iter().map(|p| p.id.clone()).collect(),
+            teams: self.teams.clone(),
+            winner,
+            scores: self.scores.clone(),
+            reason,
+            rounds: self.round_history.clone(),
+            created_at: self.created_at,
+            ended_at,
+        }
+    }
+
+    /// Queues `message` for every connected player and spectator, stamped with the room's
+    /// next sequence number. Returns without waiting for delivery, so a crowded room's round
+    /// processing isn't held up by its audience.
+    pub async fn broadcast_to_all(&self, message: &ServerMessage) -> Result<()> {
+        let connected = self
+            .players
+            .iter()
+            .filter(|p| !self.disconnected.contains_key(&p.id) && !self.is_bot(&p.id));
+        let recipients: Vec<Arc<Player>> = connected.chain(self.spectators.iter()).cloned().collect();
+        let mut message = message.clone();
+        if let Some(seq) = message.seq_mut() {
+            *seq = Some(self.seq.fetch_add(1, Ordering::AcqRel) + 1);
         }
+        self.deliver(recipients, message);
         Ok(())
     }
 
+    /// Sequence number of the latest broadcast; 0 before the first.
+    pub fn seq(&self) -> u64 {
+        self.seq.load(Ordering::Acquire)
+    }
+
+    /// Sends to one member of the room, in order with the room's broadcasts.
+    pub fn send_to(&self, player: &Arc<Player>, message: ServerMessage) {
+        self.deliver(vec![player.clone()], message);
+    }
+
+    fn deliver(&self, recipients: Vec<Arc<Player>>, message: ServerMessage) {
+        // Small audiences go straight to the players' queues, unless something queued
+        // earlier hasn't been delivered yet and would be overtaken
+        let idle = self.fanout.get().is_none_or(|fanout| fanout.pending.load(Ordering::Acquire) == 0);
+        #[cfg(feature = "chaos")]
+        let idle = idle && super::chaos::CHAOS.snapshot().settings.broadcast_delay_ms == 0;
+        if idle && recipients.len() <= INLINE_FANOUT_MAX {
+            send_all(&recipients, &message);
+            return;
+        }
+
+        let fanout = self.fanout.get_or_init(FanOut::spawn);
+        fanout.pending.fetch_add(1, Ordering::AcqRel);
+        if fanout.tx.send(Delivery { recipients, message }).is_err() {
+            fanout.pending.fetch_sub(1, Ordering::AcqRel);
+            warn!("Fan-out worker for room {} is gone", self.id);
+        }
+    }
+
     pub async fn notify_player_left(&self, player_id: &str) -> Result<()> {
         let message = ServerMessage::PlayerLeft {
+            seq: None,
             player_id: player_id.to_string(),
         };
         self.broadcast_to_all(&message).await
     }
 
+    pub async fn notify_room_closed(&self, reason: &str) -> Result<()> {
+        let message = ServerMessage::RoomClosed {
+            seq: None,
+            room_id: self.id.clone(),
+            reason: reason.to_string(),
+        };
+        self.broadcast_to_all(&message).await
+    }
+
     fn format_moves(&self, moves: &HashMap<String, GameChoice>) -> String {
         moves
             .iter()
@@ -216,4 +1203,73 @@ impl GameRoom {
             None => "Draw".to_string(),
         }
     }
-}
\ No newline at end of file
+}
+impl Drop for GameRoom {
+    fn drop(&mut self) {
+        if let Some(stats) = &self.stats {
+            stats.room_dropped(&self.status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::application::GameRoom;
+    use crate::domain::{DrawPolicy, GameChoice, GameConfig, GameEndReason, GameStatus, Player, ServerMessage};
+
+    #[test]
+    fn test_game_room_creation() {
+        let config = GameConfig::default();
+        let room = GameRoom::new("test-room".to_string(), config);
+        assert_eq!(room.id, "test-room");
+        assert_eq!(room.status, GameStatus::Waiting);
+        assert_eq!(room.current_round, 1);
+        assert_eq!(room.config.max_rounds, 3);
+    }
+
+    #[tokio::test]
+    async fn test_game_room_time_limit_ends_game() {
+        let config = GameConfig {
+            match_time_limit_ms: Some(0),
+            ..GameConfig::default()
+        };
+        let mut room = GameRoom::new("timed-room".to_string(), config);
+        let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+        let (tx2, mut rx2) = tokio::sync::mpsc::unbounded_channel();
+        room.add_player(Arc::new(Player::new("p1".to_string(), tx1))).unwrap();
+        room.add_player(Arc::new(Player::new("p2".to_string(), tx2))).unwrap();
+        room.scores.insert("p1".to_string(), 1);
+
+        assert!(room.enforce_time_limit().await.unwrap());
+        assert_eq!(room.status, GameStatus::Finished);
+        match rx2.recv().await.unwrap() {
+            ServerMessage::GameEnd { winner, reason, .. } => {
+                assert_eq!(winner.as_deref(), Some("p1"));
+                assert_eq!(reason, GameEndReason::TimeLimit);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_draw_policies() {
+        let play_draw = |policy: DrawPolicy| async move {
+            let config = GameConfig { draw_policy: policy, ..GameConfig::default() };
+            let mut room = GameRoom::new("draw-room".to_string(), config);
+            let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+            let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+            room.add_player(Arc::new(Player::new("p1".to_string(), tx1))).unwrap();
+            room.add_player(Arc::new(Player::new("p2".to_string(), tx2))).unwrap();
+            room.submit_move("p1", GameChoice::Rock).unwrap();
+            room.submit_move("p2", GameChoice::Rock).unwrap();
+            room.process_round().await.unwrap();
+            (room.current_round, room.scores["p1"], room.scores["p2"])
+        };
+
+        assert_eq!(play_draw(DrawPolicy::NoPoint).await, (2, 0, 0));
+        assert_eq!(play_draw(DrawPolicy::BothScore).await, (2, 1, 1));
+        assert_eq!(play_draw(DrawPolicy::Replay).await, (1, 0, 0));
+    }
+}
@@ -0,0 +1,115 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::domain::GameStatus;
+
+/// Counters bumped at each room transition, so stats can be read without locking any
+/// room. Rooms report their own status changes; the manager reports rooms it opens
+/// and closes.
+#[derive(Debug, Default)]
+pub struct LiveStats {
+    rooms_created: AtomicU64,
+    rooms_closed: AtomicU64,
+    games_started: AtomicU64,
+    games_finished: AtomicU64,
+    playing: AtomicU64, // Rooms currently in `Playing`, paused ones excluded
+    queue_overflows: AtomicU64,
+    series_started: AtomicU64,
+    series_finished: AtomicU64,
+    rooms_recovered: AtomicU64,
+    room_panics: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveStatsSnapshot {
+    pub rooms_created: u64,
+    pub rooms_closed: u64,
+    pub open_rooms: u64,
+    pub games_started: u64,
+    pub games_finished: u64,
+    pub active_games: u64,
+    pub queue_overflows: u64, // FindMatch refused, or a player moved to a bot game, because a queue was full
+    pub series_started: u64,
+    pub series_finished: u64,
+    pub rooms_recovered: u64, // Rooms the watchdog found stuck on a round with every move in
+    pub room_panics: u64,     // Room tasks that panicked; the room is closed on the watchdog's next pass
+}
+
+impl LiveStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn room_opened(&self) {
+        self.rooms_created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn room_closed(&self) {
+        self.rooms_closed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A room moved from `from` to `to`.
+    pub fn status_changed(&self, from: &GameStatus, to: &GameStatus) {
+        if from == to {
+            return;
+        }
+        if *from == GameStatus::Waiting && matches!(to, GameStatus::Playing | GameStatus::Paused) {
+            self.games_started.fetch_add(1, Ordering::Relaxed);
+        }
+        if *to == GameStatus::Finished {
+            self.games_finished.fetch_add(1, Ordering::Relaxed);
+        }
+        if *from == GameStatus::Playing {
+            self.playing.fetch_sub(1, Ordering::Relaxed);
+        }
+        if *to == GameStatus::Playing {
+            self.playing.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn queue_overflowed(&self) {
+        self.queue_overflows.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn series_started(&self) {
+        self.series_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn series_finished(&self) {
+        self.series_finished.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn room_recovered(&self) {
+        self.rooms_recovered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn room_panicked(&self) {
+        self.room_panics.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A room was dropped while still in `status`.
+    pub fn room_dropped(&self, status: &GameStatus) {
+        if *status == GameStatus::Playing {
+            self.playing.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> LiveStatsSnapshot {
+        let rooms_created = self.rooms_created.load(Ordering::Relaxed);
+        let rooms_closed = self.rooms_closed.load(Ordering::Relaxed);
+        LiveStatsSnapshot {
+            rooms_created,
+            rooms_closed,
+            open_rooms: rooms_created.saturating_sub(rooms_closed),
+            games_started: self.games_started.load(Ordering::Relaxed),
+            games_finished: self.games_finished.load(Ordering::Relaxed),
+            active_games: self.playing.load(Ordering::Relaxed),
+            queue_overflows: self.queue_overflows.load(Ordering::Relaxed),
+            series_started: self.series_started.load(Ordering::Relaxed),
+            series_finished: self.series_finished.load(Ordering::Relaxed),
+            rooms_recovered: self.rooms_recovered.load(Ordering::Relaxed),
+            room_panics: self.room_panics.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -1,100 +1,1364 @@
-use anyhow::Result;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
-use tracing::info;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use futures_util::FutureExt;
+use std::ops::{Deref, DerefMut};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::{Mutex, MutexGuard, RwLock};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use crate::domain::{GameChoice, GameConfig, Player, ServerMessage};
-use super::game_service::GameRoom;
+use crate::config::{
+    AbandonedMatchPolicy, BlitzConfig, BotDetectionConfig, CapacityConfig, GameHistoryConfig, MatchmakingConfig, QueueOverflowPolicy,
+    QuitPenaltyConfig, QuotasConfig, SpamGuardConfig,
+    DEFAULT_MANAGER_SHARDS,
+};
+use crate::domain::{DrawPolicy, GameChoice, GameConfig, GameEvent, GameMode, GameStatus, GameType, Player, PlayerPhase, RoomTemplate, RuleSet, ServerMessage, Tenant};
+use super::bot_detection::BotDetector;
+use super::clock::{Clock, SystemClock};
+use super::event_bus::EventBus;
+use super::game_history::{GameArchive, GameHistory, GameRecord};
+use super::live_stats::{LiveStats, LiveStatsSnapshot};
+use super::result_signing::ResultSigner;
+use super::shadow_matchmaking::{ShadowMatchmaker, ShadowReport};
+use super::sharded::Sharded;
+use super::profanity::{ProfanityFilter, Screened};
+use super::quit_penalty::QuitPenalties;
+use super::quotas::{QuotaExceeded, QuotaUsage, Quotas};
+use super::starvation::{QueueWaits, StarvationReport};
+use super::spam_guard::{Muted, SpamAction, SpamGuard};
+use super::supervision::panic_message;
+use super::game_service::{GameRoom, MemoryEstimate, MoveRefused, RoomSnapshot, RoomState, RuleScripts, TitleLookup};
 
+const JOIN_CODE_LEN: usize = 8;
+const ROUND_TIMER_IDLE_POLL: Duration = Duration::from_millis(250); // While a timed room is paused
+
+/// Where matchmaking finds the room templates players can ask to be matched on. Looked
+/// up at every match, so an edited template applies from the next room made from it.
+pub trait RoomTemplates: Send + Sync {
+    fn template(&self, name: &str) -> Option<RoomTemplate>;
+}
+
+type RoomMap = HashMap<String, Arc<Mutex<GameRoom>>>;
+
+/// A matchmaking queue, oldest first, whose length can be read without taking its lock.
+/// `add_to_queue` keeps it within `matchmaking.max_queue_len`.
+#[derive(Default)]
+struct PlayerQueue {
+    players: Mutex<VecDeque<Arc<Player>>>,
+    len: AtomicUsize,
+}
+
+impl PlayerQueue {
+    async fn lock(&self) -> QueueGuard<'_> {
+        QueueGuard {
+            players: self.players.lock().await,
+            len: &self.len,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+}
+
+/// Publishes the queue's length when released, whatever was done to it.
+struct QueueGuard<'a> {
+    players: MutexGuard<'a, VecDeque<Arc<Player>>>,
+    len: &'a AtomicUsize,
+}
+
+impl Deref for QueueGuard<'_> {
+    type Target = VecDeque<Arc<Player>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.players
+    }
+}
+
+impl DerefMut for QueueGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.players
+    }
+}
+
+impl Drop for QueueGuard<'_> {
+    fn drop(&mut self) {
+        self.len.store(self.players.len(), Ordering::Relaxed);
+    }
+}
+
+/// Outcome of offering a reserved room's open seat to one player.
+enum Backfill {
+    Seated(Box<ServerMessage>),
+    Skipped, // This player can't take the seat, e.g. they already hold one in the room
+    Closed,  // The room no longer has a seat to give away
+}
+
+/// Why rules sent along with a new room were turned down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRules {
+    pub reason: String,
+}
+
+impl fmt::Display for InvalidRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid rules: {}", self.reason)
+    }
+}
+
+impl std::error::Error for InvalidRules {}
+
+/// A room created ahead of time for specific players, e.g. by a tournament organizer.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservedMatch {
+    pub room_id: String,
+    pub join_code: String,
+    pub players: Vec<String>,
+    pub open_seats: usize, // Seats left for whoever holds an invite to the room
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tournament: Option<String>, // Bracket the match is streamed under
+}
+
+impl ReservedMatch {
+    /// Seats the game needs filled before it starts.
+    pub fn seats(&self) -> usize {
+        self.players.len() + self.open_seats
+    }
+}
+
+/// What one shard of the manager holds, for `/ultra-metrics`.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardStats {
+    pub rooms: usize,
+    pub seated_players: usize,
+    pub queued_players: usize,
+}
+
+/// Full picture from walking every room and queue, for admins. Takes each room's lock,
+/// so keep it off hot paths; `GameManager::get_stats` is the cheap version.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepStats {
+    pub rooms: usize,
+    pub rooms_by_status: HashMap<GameStatus, usize>,
+    pub seated_players: usize,
+    pub queued: HashMap<String, usize>, // Queue name -> players waiting in it
+    pub counters: LiveStatsSnapshot,
+}
+
+/// Rooms are sharded by room id and the per-player maps by player id, so games in
+/// different shards don't contend. The pairing queues stay whole: a pair has to come
+/// out of one queue, and splitting it would keep players in different shards apart.
 pub struct GameManager {
-    rooms: Arc<RwLock<HashMap<String, Arc<Mutex<GameRoom>>>>>,
-    waiting_queue: Arc<Mutex<Vec<Arc<Player>>>>,
-    player_rooms: Arc<RwLock<HashMap<String, String>>>, // playerId -> roomId
+    rooms: Arc<Sharded<RwLock<RoomMap>>>,
+    waiting_queue: Arc<PlayerQueue>,
+    suspect_queue: Arc<PlayerQueue>, // Suspected bots, when kept apart
+    team_queue: Arc<PlayerQueue>,
+    blitz_queue: Arc<PlayerQueue>,
+    game_queues: HashMap<GameType, Arc<PlayerQueue>>, // Solo queues for every game but rock-paper-scissors
+    template_queues: parking_lot::RwLock<HashMap<String, Arc<PlayerQueue>>>, // Room template name -> its queue, made on first use
+    queued_players: Arc<Sharded<Mutex<HashSet<String>>>>, // Ids in any queue, or being matched from one
+    player_rooms: Arc<Sharded<RwLock<HashMap<String, String>>>>, // playerId -> roomId
+    reservations: Arc<RwLock<HashMap<String, ReservedMatch>>>, // roomId -> pre-created match not yet started
     config: GameConfig,
+    blitz: BlitzConfig,
+    events: EventBus,
+    bot_detector: Arc<BotDetector>,
+    matchmaking: MatchmakingConfig,
+    history: Arc<GameHistory>, // Recently finished games by game id
+    archive: Option<Arc<dyn GameArchive>>, // Older games, once they leave `history`
+    shadow: Option<Arc<ShadowMatchmaker>>, // Experimental pairing strategy run alongside the live one
+    signer: Option<Arc<ResultSigner>>,
+    titles: Option<Arc<dyn TitleLookup>>,
+    scripts: Option<Arc<dyn RuleScripts>>, // Rules for rooms whose template names a script
+    room_templates: Option<Arc<dyn RoomTemplates>>,
+    spam_guard: Arc<SpamGuard>, // Mutes shared by rematch, challenge, and chat requests
+    profanity: Arc<ProfanityFilter>, // Screens chat here and display names at Connect
+    capacity: CapacityConfig,   // `max_players` always resolved
+    quotas: Arc<Quotas>,
+    quit_penalties: Arc<QuitPenalties>,
+    queue_waits: Arc<QueueWaits>,
+    backfill_rooms: Arc<Mutex<Vec<String>>>, // Reserved rooms whose seated players accepted a stranger, oldest first
+    panicked_rooms: Arc<parking_lot::Mutex<Vec<String>>>, // Rooms whose round timer panicked, closed by the watchdog's next pass
+    stats: Arc<LiveStats>,
+    clock: Arc<dyn Clock>, // Shared with every room
+}
+
+pub const MAX_CHAT_CHARS: usize = 200;
+
+/// Why a chat line wasn't relayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatRefused {
+    Invalid, // Blank or longer than `MAX_CHAT_CHARS`
+    NotInGame,
+    Disabled, // The room's template turned chat off
+    Muted(Muted),
+    Inappropriate, // A listed word under `ProfanityAction::Reject`
 }
 
-impl GameManager {
-    pub fn new(config: GameConfig) -> Self {
-        Self {
-            rooms: Arc::new(RwLock::new(HashMap::new())),
-            waiting_queue: Arc::new(Mutex::new(Vec::new())),
-            player_rooms: Arc::new(RwLock::new(HashMap::new())),
-            config,
+impl GameManager {
+    pub fn new(config: GameConfig) -> Self {
+        Self {
+            rooms: Arc::new(Sharded::new(DEFAULT_MANAGER_SHARDS, Default::default)),
+            waiting_queue: Arc::new(PlayerQueue::default()),
+            suspect_queue: Arc::new(PlayerQueue::default()),
+            team_queue: Arc::new(PlayerQueue::default()),
+            blitz_queue: Arc::new(PlayerQueue::default()),
+            game_queues: GameType::ALL
+                .into_iter()
+                .filter(|game| !game.is_default())
+                .map(|game| (game, Arc::new(PlayerQueue::default())))
+                .collect(),
+            template_queues: parking_lot::RwLock::new(HashMap::new()),
+            queued_players: Arc::new(Sharded::new(DEFAULT_MANAGER_SHARDS, Default::default)),
+            player_rooms: Arc::new(Sharded::new(DEFAULT_MANAGER_SHARDS, Default::default)),
+            reservations: Arc::new(RwLock::new(HashMap::new())),
+            config,
+            blitz: BlitzConfig::default(),
+            events: EventBus::new(),
+            bot_detector: Arc::new(BotDetector::new(BotDetectionConfig::default())),
+            matchmaking: MatchmakingConfig::default(),
+            history: Arc::new(GameHistory::new(GameHistoryConfig::default().max_finished_games)),
+            archive: None,
+            shadow: None,
+            signer: None,
+            titles: None,
+            scripts: None,
+            room_templates: None,
+            spam_guard: Arc::new(SpamGuard::new(SpamGuardConfig::default())),
+            profanity: Arc::new(ProfanityFilter::default()),
+            capacity: CapacityConfig {
+                max_players: Some(usize::MAX),
+                ..CapacityConfig::default()
+            },
+            quotas: Arc::new(Quotas::new(QuotasConfig::default())),
+            quit_penalties: Arc::new(QuitPenalties::new(QuitPenaltyConfig::default())),
+            queue_waits: Arc::new(QueueWaits::new()),
+            backfill_rooms: Arc::new(Mutex::new(Vec::new())),
+            panicked_rooms: Arc::new(parking_lot::Mutex::new(Vec::new())),
+            stats: Arc::new(LiveStats::new()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Splits rooms and the per-player maps into `count` shards. Call before any player
+    /// arrives; anything already stored is dropped.
+    pub fn with_shards(mut self, count: usize) -> Self {
+        self.rooms = Arc::new(Sharded::new(count, Default::default));
+        self.queued_players = Arc::new(Sharded::new(count, Default::default));
+        self.player_rooms = Arc::new(Sharded::new(count, Default::default));
+        self
+    }
+
+    pub fn with_bot_detection(mut self, config: BotDetectionConfig) -> Self {
+        self.bot_detector = Arc::new(BotDetector::new(config));
+        self
+    }
+
+    pub fn with_matchmaking(mut self, config: MatchmakingConfig) -> Self {
+        self.matchmaking = config;
+        self
+    }
+
+    pub fn with_blitz(mut self, config: BlitzConfig) -> Self {
+        self.blitz = config;
+        self
+    }
+
+    pub fn with_game_history(mut self, config: GameHistoryConfig) -> Self {
+        self.history = Arc::new(GameHistory::new(config.max_finished_games));
+        self
+    }
+
+    /// Lookups that miss the history fall back to `archive`; capacity evictions are kept for it.
+    pub fn with_game_archive(mut self, archive: Arc<dyn GameArchive>) -> Self {
+        self.history.keep_evicted();
+        self.archive = Some(archive);
+        self
+    }
+
+    pub fn game_history(&self) -> &Arc<GameHistory> {
+        &self.history
+    }
+
+    /// The counters behind `live_stats`, for services that track games across rooms.
+    pub fn stats_counters(&self) -> &Arc<LiveStats> {
+        &self.stats
+    }
+
+    pub fn with_shadow_matchmaking(mut self, shadow: Arc<ShadowMatchmaker>) -> Self {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    pub fn with_result_signer(mut self, signer: Arc<ResultSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn with_titles(mut self, titles: Arc<dyn TitleLookup>) -> Self {
+        self.titles = Some(titles);
+        self
+    }
+
+    /// Times rooms by `clock` instead of the system time; rooms made before keep theirs.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_rule_scripts(mut self, scripts: Arc<dyn RuleScripts>) -> Self {
+        self.scripts = Some(scripts);
+        self
+    }
+
+    pub fn with_room_templates(mut self, templates: Arc<dyn RoomTemplates>) -> Self {
+        self.room_templates = Some(templates);
+        self
+    }
+
+    pub fn with_spam_guard(mut self, config: SpamGuardConfig) -> Self {
+        self.spam_guard = Arc::new(SpamGuard::new(config));
+        self
+    }
+
+    pub fn with_profanity_filter(mut self, filter: Arc<ProfanityFilter>) -> Self {
+        self.profanity = filter;
+        self
+    }
+
+    /// Caps live rooms and engaged players; `max_connections` stands in for an unset `max_players`.
+    pub fn with_capacity(mut self, config: CapacityConfig, max_connections: usize) -> Self {
+        self.capacity = CapacityConfig {
+            max_players: Some(config.max_players.unwrap_or(max_connections)),
+            ..config
+        };
+        self
+    }
+
+    /// Per-tenant and per-mode limits checked at FindMatch, on top of `with_capacity`'s.
+    pub fn with_quotas(mut self, config: QuotasConfig) -> Self {
+        self.quotas = Arc::new(Quotas::new(config));
+        self
+    }
+
+    pub fn with_quit_penalty(mut self, config: QuitPenaltyConfig) -> Self {
+        self.quit_penalties = Arc::new(QuitPenalties::new(config));
+        self
+    }
+
+    pub fn quit_penalties(&self) -> Arc<QuitPenalties> {
+        self.quit_penalties.clone()
+    }
+
+    /// Forgets quit records that have aged out of the window.
+    pub fn prune_quit_penalties(&self) -> usize {
+        self.quit_penalties.prune(self.clock.now())
+    }
+
+    pub fn shadow_report(&self) -> Option<ShadowReport> {
+        self.shadow.as_ref().map(|shadow| shadow.report())
+    }
+
+    pub fn bot_detector(&self) -> &BotDetector {
+        &self.bot_detector
+    }
+
+    pub fn spam_guard(&self) -> Arc<SpamGuard> {
+        self.spam_guard.clone()
+    }
+
+    pub fn profanity_filter(&self) -> Arc<ProfanityFilter> {
+        self.profanity.clone()
+    }
+
+    fn queue_name(&self, player_id: &str, config: &GameConfig) -> &'static str {
+        if config.template.is_some() {
+            "template"
+        } else if !config.game.is_default() {
+            config.game.name()
+        } else if config.mode == GameMode::Teams {
+            "teams"
+        } else if config.mode == GameMode::Blitz {
+            "blitz"
+        } else if self.bot_detector.separate_pool() && self.bot_detector.is_suspected(player_id) && !self.queue_waits.is_relaxed(player_id) {
+            "suspect"
+        } else {
+            "waiting"
+        }
+    }
+
+    fn queue_for(&self, player_id: &str, config: &GameConfig) -> Arc<PlayerQueue> {
+        if let Some(template) = &config.template {
+            return self.template_queues.write().entry(template.clone()).or_default().clone();
+        }
+        if let Some(queue) = self.game_queues.get(&config.game) {
+            return queue.clone();
+        }
+        match self.queue_name(player_id, config) {
+            "teams" => self.team_queue.clone(),
+            "blitz" => self.blitz_queue.clone(),
+            "suspect" => self.suspect_queue.clone(),
+            _ => self.waiting_queue.clone(),
+        }
+    }
+
+    // Every queue with the name `deep_stats` reports it under: game queues by their game,
+    // template queues by their template, prefixed so they can't shadow a fixed queue
+    fn all_queues(&self) -> Vec<(String, Arc<PlayerQueue>)> {
+        [("waiting", &self.waiting_queue), ("suspect", &self.suspect_queue), ("teams", &self.team_queue), ("blitz", &self.blitz_queue)]
+            .into_iter()
+            .map(|(name, queue)| (name.to_string(), queue.clone()))
+            .chain(self.game_queues.iter().map(|(game, queue)| (game.name().to_string(), queue.clone())))
+            .chain(self.template_queues.read().iter().map(|(name, queue)| (format!("template:{}", name), queue.clone())))
+            .collect()
+    }
+
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    pub async fn find_match(&self, player: Arc<Player>) -> Result<ServerMessage> {
+        self.find_match_in_mode(player, GameMode::Solo).await
+    }
+
+    pub async fn find_match_in_mode(&self, player: Arc<Player>, mode: GameMode) -> Result<ServerMessage> {
+        self.find_match_for(player, mode, GameType::default()).await
+    }
+
+    /// Queues the player, or matches them with whoever is waiting. A player who is already
+    /// queued is acknowledged again without a second queue entry.
+    pub async fn find_match_for(&self, player: Arc<Player>, mode: GameMode, game: GameType) -> Result<ServerMessage> {
+        if !game.is_default() && mode != GameMode::Solo {
+            bail!("{} is only played one against one", game.name());
+        }
+        self.find_match_with(player, self.room_config(mode, game)).await
+    }
+
+    /// Like [`Self::find_match_for`], pairing only players who asked for the same room template.
+    pub async fn find_match_on_template(&self, player: Arc<Player>, template: &str) -> Result<ServerMessage> {
+        let config = self.template_config(template)?;
+        self.find_match_with(player, config).await
+    }
+
+    async fn find_match_with(&self, player: Arc<Player>, config: GameConfig) -> Result<ServerMessage> {
+        let mode = config.mode;
+        if let Some(busy) = self.check_capacity(&player.id).await {
+            return Ok(busy);
+        }
+        self.quit_penalties.check(&player.id, self.clock.now())?;
+        self.check_quotas(&player, mode).await?;
+
+        if mode == GameMode::Bot {
+            if self.is_queued(&player.id).await {
+                debug!("Player {} asked for a bot game while queued", player.id);
+                return Ok(Self::waiting_message());
+            }
+            return self.create_match(config, vec![player]).await;
+        }
+
+        // Claimed before the queue is touched, so concurrent requests can't both get through
+        if !self.claim_queued(&player.id).await {
+            match self.queued_entry(&player.id).await {
+                Some(queued) if queued.is_connected() && queued.same_session(&player) => {
+                    debug!("Player {} is already queued", player.id);
+                    return Ok(Self::waiting_message());
+                }
+                // A dead connection's entry, or one from another connection using the same id
+                Some(_) => {
+                    info!("Replacing stale queue entry for player {}", player.id);
+                    self.remove_queued_entry(&player.id).await;
+                }
+                // Being matched by a concurrent request
+                None => return Ok(Self::waiting_message()),
+            }
+        }
+
+        if mode == GameMode::Solo && self.queue_name(&player.id, &config) == "waiting" {
+            if let Some(message) = self.take_backfill_seat(player.clone()).await? {
+                self.release_queued(&player.id).await;
+                return Ok(message);
+            }
+        }
+
+        self.join_queue(player, config, false).await
+    }
+
+    /// `ServerBusy` when another room or another engaged player would go over capacity.
+    /// A player already queued or seated is never refused, since they add nothing.
+    async fn check_capacity(&self, player_id: &str) -> Option<ServerMessage> {
+        if self.is_queued(player_id).await || self.player_room_id(player_id).await.is_some() {
+            return None;
+        }
+
+        let stats = self.shard_stats().await;
+        let rooms: usize = stats.iter().map(|shard| shard.rooms).sum();
+        let players: usize = stats.iter().map(|shard| shard.queued_players + shard.seated_players).sum();
+        let max_players = self.capacity.max_players.unwrap_or(usize::MAX);
+        if rooms < self.capacity.max_rooms && players < max_players {
+            return None;
+        }
+
+        warn!(
+            "At capacity ({} rooms of {}, {} players of {}); refusing match for {}",
+            rooms, self.capacity.max_rooms, players, max_players, player_id
+        );
+        Some(ServerMessage::ServerBusy {
+            retry_after_ms: self.capacity.retry_after_ms,
+        })
+    }
+
+    /// `QuotaExceeded` when the player's tenant or mode is at one of its limits. Like
+    /// capacity, a player already queued or seated is never refused.
+    async fn check_quotas(&self, player: &Player, mode: GameMode) -> Result<(), QuotaExceeded> {
+        if !self.quotas.is_enabled() || self.is_queued(&player.id).await || self.player_room_id(&player.id).await.is_some() {
+            return Ok(());
+        }
+
+        let tenant = player.extensions.get::<Tenant>().map(|tenant| tenant.0.as_str());
+        self.quotas.check(&player.id, tenant, mode, self.clock.now()).inspect_err(|exceeded| {
+            info!("Refusing match for {}: {}", player.id, exceeded);
+        })
+    }
+
+    fn quota_usage(mode: GameMode, players: &[Arc<Player>]) -> QuotaUsage {
+        let mut tenants: Vec<String> = players.iter().filter_map(|p| p.extensions.get::<Tenant>()).map(|tenant| tenant.0.clone()).collect();
+        tenants.sort();
+        tenants.dedup();
+        QuotaUsage { mode, tenants }
+    }
+
+    /// Pairs the player with whoever is waiting, or queues them; `priority` queues them first
+    /// in line. The caller must already hold the player's `queued_players` claim.
+    async fn join_queue(&self, player: Arc<Player>, config: GameConfig, priority: bool) -> Result<ServerMessage> {
+        let queue_name = self.queue_name(&player.id, &config);
+        let queue = self.queue_for(&player.id, &config);
+        let opponents_needed = config.mode.players_per_room() - 1;
+        let waiting_players = loop {
+            let opponents = {
+                let mut queue = queue.lock().await;
+                self.evict_dead_entries(&mut queue).await;
+
+                // Entries for this player's id or connection would pair them with themselves
+                let mut own = Vec::new();
+                queue.retain(|p| {
+                    let is_own = p.id == player.id || p.same_session(&player);
+                    if is_own && p.id != player.id {
+                        own.push(p.id.clone());
+                    }
+                    !is_own
+                });
+                for id in &own {
+                    self.release_queued(id).await;
+                }
+
+                if queue.len() >= opponents_needed {
+                    queue.drain(..opponents_needed).collect::<Vec<_>>()
+                } else {
+                    Vec::new()
+                }
+            };
+            if opponents.is_empty() {
+                break opponents;
+            }
+
+            // Checked before anyone leaves the queue for good: a connection can close after the
+            // eviction above, and the match would then fail with the others already drained
+            let mut players = opponents.clone();
+            players.push(player.clone());
+            let Err(e) = Self::check_match_players(&players) else {
+                break opponents;
+            };
+            let stale_opponent = opponents.iter().any(|p| !p.is_connected());
+            {
+                let mut queue = queue.lock().await;
+                for opponent in opponents.into_iter().rev() {
+                    queue.push_front(opponent);
+                }
+            }
+            // The next pass evicts the stale entry; the others keep their place in line
+            if !stale_opponent || !player.is_connected() {
+                self.release_queued(&player.id).await;
+                return Err(e);
+            }
+        };
+
+        if let Some(shadow) = &self.shadow {
+            let opponents: Vec<String> = waiting_players.iter().map(|p| p.id.clone()).collect();
+            let live_opponents = (!opponents.is_empty()).then_some(opponents.as_slice());
+            shadow.observe_join(queue_name, &player.id, opponents_needed, live_opponents);
+        }
+
+        if waiting_players.is_empty() {
+            if self.quotas.is_enabled() {
+                self.quotas.player_queued(&player.id, Self::quota_usage(config.mode, std::slice::from_ref(&player)));
+            }
+            self.queue_waits.queued(&player.id, queue_name, self.clock.now());
+            self.add_to_queue(&queue, player, priority).await
+        } else {
+            let mut players = waiting_players;
+            players.push(player);
+            for player in &players {
+                self.release_queued(&player.id).await;
+            }
+            self.create_match(config, players).await
+        }
+    }
+
+    /// Drops queue entries whose connection has closed, e.g. after a crash that skipped
+    /// `remove_player`. Returns how many were dropped.
+    async fn evict_dead_entries(&self, queue: &mut VecDeque<Arc<Player>>) -> usize {
+        let mut dead = Vec::new();
+        queue.retain(|p| {
+            if p.is_connected() {
+                return true;
+            }
+            dead.push(p.id.clone());
+            false
+        });
+        if dead.is_empty() {
+            return 0;
+        }
+
+        for id in &dead {
+            self.release_queued(id).await;
+            if let Some(shadow) = &self.shadow {
+                shadow.observe_leave(id);
+            }
+            self.events.publish(GameEvent::PlayerDisconnected { player_id: id.clone() });
+        }
+        info!("Evicted {} stale queue entries", dead.len());
+        dead.len()
+    }
+
+    /// Evicts dead entries from every queue; run periodically so they don't linger until
+    /// the next FindMatch in their queue.
+    pub async fn evict_stale_queue_entries(&self) -> usize {
+        let mut evicted = 0;
+        for (_, queue) in self.all_queues() {
+            let mut queue = queue.lock().await;
+            evicted += self.evict_dead_entries(&mut queue).await;
+        }
+        evicted
+    }
+
+    /// Reports players who have waited past the starvation SLO since the last pass, with a
+    /// `QueueStarved` event each. With `relax_pairing`, starved suspected bots are paired
+    /// from the main queue instead. Run periodically; returns how many went past the SLO.
+    pub async fn check_starvation(&self) -> usize {
+        let config = &self.matchmaking.starvation;
+        let slo = Duration::from_millis(config.slo_ms);
+        let starved = self.queue_waits.newly_starved(slo, self.clock.now());
+        for player in &starved {
+            warn!("Player {} has waited {:?} in the {} queue, past the {:?} SLO", player.player_id, player.waited, player.queue, slo);
+            self.events.publish(GameEvent::QueueStarved {
+                player_id: player.player_id.clone(),
+                queue: player.queue.to_string(),
+                waited_ms: player.waited.as_millis() as u64,
+            });
+            if config.relax_pairing && player.queue == "suspect" {
+                if let Err(e) = self.relax_pairing(&player.player_id).await {
+                    error!("Failed to pair starved player {} from the main queue: {}", player.player_id, e);
+                }
+            }
+        }
+        starved.len()
+    }
+
+    /// Moves a starved player out of the suspected-bot pool and pairs them like anyone else,
+    /// ahead of the main queue since they've waited longest.
+    async fn relax_pairing(&self, player_id: &str) -> Result<()> {
+        let player = {
+            let mut queue = self.suspect_queue.lock().await;
+            let Some(position) = queue.iter().position(|p| p.id == player_id) else {
+                return Ok(());
+            };
+            queue.remove(position)
+        };
+        let Some(player) = player else {
+            return Ok(());
+        };
+        self.queue_waits.relax(player_id);
+        info!("Pairing starved player {} from the main queue", player_id);
+
+        let message = self.join_queue(player.clone(), self.room_config(GameMode::Solo, GameType::default()), true).await?;
+        if !matches!(message, ServerMessage::Matchmaking { matched: false, .. }) {
+            let _ = player.send_message(&message).await;
+        }
+        Ok(())
+    }
+
+    /// Current and recent matchmaking waits, by queue, against the starvation SLO.
+    pub fn starvation_report(&self) -> StarvationReport {
+        self.queue_waits.report(Duration::from_millis(self.matchmaking.starvation.slo_ms), self.clock.now())
+    }
+
+    /// Players waiting in the queue `mode` pairs from, read without taking its lock.
+    /// Suspected bots kept in their own pool aren't counted.
+    pub fn waiting_players(&self, mode: GameMode) -> usize {
+        match mode {
+            GameMode::Solo => self.waiting_queue.len(),
+            GameMode::Teams => self.team_queue.len(),
+            GameMode::Blitz => self.blitz_queue.len(),
+            GameMode::Bot => 0,
+        }
+    }
+
+    /// Takes the longest-waiting connected player `movable` accepts out of `mode`'s queue,
+    /// e.g. to hand them to another server instance.
+    pub async fn take_waiting(&self, mode: GameMode, movable: impl Fn(&Player) -> bool) -> Option<Arc<Player>> {
+        let queue = match mode {
+            GameMode::Solo => &self.waiting_queue,
+            GameMode::Teams => &self.team_queue,
+            GameMode::Bot | GameMode::Blitz => return None, // Never hosted elsewhere
+        };
+        let player = {
+            let mut queue = queue.lock().await;
+            let position = queue.iter().position(|p| p.is_connected() && movable(p))?;
+            queue.remove(position)?
+        };
+        self.release_queued(&player.id).await;
+        if let Some(shadow) = &self.shadow {
+            shadow.observe_leave(&player.id);
+        }
+        Some(player)
+    }
+
+    async fn queued_entry(&self, player_id: &str) -> Option<Arc<Player>> {
+        for (_, queue) in self.all_queues() {
+            if let Some(player) = queue.lock().await.iter().find(|p| p.id == player_id) {
+                return Some(player.clone());
+            }
+        }
+        None
+    }
+
+    async fn remove_queued_entry(&self, player_id: &str) {
+        for (_, queue) in self.all_queues() {
+            queue.lock().await.retain(|p| p.id != player_id);
+        }
+    }
+
+    /// Every seat must go to a different, still connected player.
+    fn check_match_players(players: &[Arc<Player>]) -> Result<()> {
+        for (i, player) in players.iter().enumerate() {
+            if !player.is_connected() {
+                bail!("Player {} disconnected before the match started", player.id);
+            }
+            if players[..i].iter().any(|p| p.id == player.id || p.same_session(player)) {
+                bail!("Player {} would be matched against themselves", player.id);
+            }
+        }
+        Ok(())
+    }
+
+    fn waiting_message() -> ServerMessage {
+        ServerMessage::Matchmaking {
+            matched: false,
+            waiting: Some(true),
+            room_id: None,
+        }
+    }
+
+    /// Settings for a room of `mode` playing `game`, before any room template.
+    fn room_config(&self, mode: GameMode, game: GameType) -> GameConfig {
+        let mut config = GameConfig {
+            mode,
+            game,
+            min_players: mode.players_per_room(),
+            max_players: mode.players_per_room(),
+            ..self.config.clone()
+        };
+        if mode == GameMode::Blitz {
+            config.max_rounds = self.blitz.rounds;
+            config.round_window_ms = Some(self.blitz.round_window_ms);
+            config.draw_policy = DrawPolicy::NoPoint; // Replaying draws would stretch a timed game
+        }
+        config
+    }
+
+    fn template_config(&self, name: &str) -> Result<GameConfig> {
+        let Some(template) = self.room_templates.as_ref().and_then(|templates| templates.template(name)) else {
+            bail!("Unknown room template {}", name);
+        };
+        let mut config = self.room_config(template.mode, template.game);
+        template.apply(&mut config);
+        Ok(config)
+    }
+
+    fn new_room(&self, config: GameConfig) -> Result<GameRoom> {
+        config.rules.validate()?;
+        let script = config.script.clone();
+        let room = GameRoom::new(Uuid::new_v4().to_string(), config)
+            .with_events(self.events.clone())
+            .with_history(self.history.clone())
+            .with_stats(self.stats.clone())
+            .with_clock(self.clock.clone());
+        let room = match &self.titles {
+            Some(titles) => room.with_titles(titles.clone()),
+            None => room,
+        };
+        let room = match &self.signer {
+            Some(signer) => room.with_signer(signer.clone()),
+            None => room,
+        };
+        let Some(script) = script else {
+            return Ok(room);
+        };
+        match self.scripts.as_ref().and_then(|scripts| scripts.game(&script)) {
+            Some(game) => Ok(room.with_game(game)),
+            None => bail!("Unknown rule script {}", script),
+        }
+    }
+
+    async fn create_match(&self, config: GameConfig, players: Vec<Arc<Player>>) -> Result<ServerMessage> {
+        self.create_match_with(config, players, |_| {}).await
+    }
+
+    // `before_start` sees the new room id before any game message is sent
+    async fn create_match_with(
+        &self,
+        config: GameConfig,
+        players: Vec<Arc<Player>>,
+        before_start: impl FnOnce(&str),
+    ) -> Result<ServerMessage> {
+        Self::check_match_players(&players)?;
+        let mode = config.mode;
+        let mut room = self.new_room(config)?;
+        let room_id = room.id.clone();
+
+        for player in &players {
+            room.add_player(player.clone())?;
+        }
+        if mode == GameMode::Bot {
+            room.add_bot()?;
+        }
+
+        let timed = room.config.round_window_ms.is_some();
+        let room_arc = Arc::new(Mutex::new(room));
+
+        // Store room and player mappings
+        self.insert_room(&room_id, room_arc.clone()).await;
+        for player in &players {
+            self.set_player_room(&player.id, &room_id).await;
+        }
+        if timed {
+            self.spawn_round_timer(Arc::downgrade(&room_arc));
+        }
+        before_start(&room_id);
+
+        // Start the game
+        let room = room_arc.lock().await;
+        self.start_match(&room).await
+    }
+
+    async fn start_match(&self, room: &GameRoom) -> Result<ServerMessage> {
+        room.start_game().await?;
+        if room.config.mode != GameMode::Bot {
+            for player in &room.players {
+                self.quit_penalties.game_started(&player.id, self.clock.now());
+            }
+        }
+        if self.quotas.is_enabled() {
+            self.quotas.game_started(room.players.iter().map(|p| p.id.as_str()), self.clock.now());
+        }
+
+        let player_ids: Vec<String> = room.players.iter().map(|p| p.id.clone()).collect();
+        info!("Match created: {}", player_ids.join(" vs "));
+        self.events.publish(GameEvent::MatchCreated {
+            room_id: room.id.clone(),
+            players: player_ids,
+            max_rounds: room.config.max_rounds,
+        });
+
+        Ok(ServerMessage::Matchmaking {
+            matched: true,
+            waiting: None,
+            room_id: Some(room.id.clone()),
+        })
+    }
+
+    /// Closes a timed room's rounds as their windows run out, until the room finishes or
+    /// is dropped. If closing a round panics the timer stops and the room is left for
+    /// `recover_stuck_rooms` to close.
+    fn spawn_round_timer(&self, room: Weak<Mutex<GameRoom>>) {
+        let panicked_rooms = self.panicked_rooms.clone();
+        let stats = self.stats.clone();
+        let clock = self.clock.clone();
+        tokio::spawn(async move {
+            loop {
+                let Some(room_arc) = room.upgrade() else {
+                    return;
+                };
+                let deadline = {
+                    let mut room = room_arc.lock().await;
+                    match AssertUnwindSafe(room.expire_round()).catch_unwind().await {
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => warn!("Failed to close round in room {}: {}", room.id, e),
+                        Err(panic) => {
+                            error!(
+                                "Round timer for room {} panicked in round {}: {}",
+                                room.id,
+                                room.current_round,
+                                panic_message(panic.as_ref())
+                            );
+                            stats.room_panicked();
+                            panicked_rooms.lock().push(room.id.clone());
+                            return;
+                        }
+                    }
+                    if room.status == GameStatus::Finished {
+                        return;
+                    }
+                    room.round_deadline()
+                };
+                drop(room_arc);
+
+                match deadline {
+                    Some(deadline) => clock.sleep_until(deadline).await,
+                    None => clock.sleep_until(clock.now() + ROUND_TIMER_IDLE_POLL).await,
+                }
+            }
+        });
+    }
+
+    /// Pre-creates a room that only the given players can enter, via `JoinRoom` with
+    /// the room id or join code. The game starts once all of them have joined.
+    pub async fn reserve_match(&self, player_ids: Vec<String>) -> Result<ReservedMatch> {
+        self.reserve_match_from(player_ids, None).await
+    }
+
+    /// Like [`Self::reserve_match`], with the room set up from `template` when set.
+    pub async fn reserve_match_from(&self, player_ids: Vec<String>, template: Option<&str>) -> Result<ReservedMatch> {
+        self.reserve_seats(player_ids, 0, template, None, None).await
+    }
+
+    /// Like [`Self::reserve_match_from`], for a match of `tournament`, whose bracket
+    /// follows it through its `MatchReserved` event.
+    pub async fn reserve_tournament_match(&self, tournament: &str, player_ids: Vec<String>, template: Option<&str>) -> Result<ReservedMatch> {
+        self.reserve_seats(player_ids, 0, template, None, Some(tournament)).await
+    }
+
+    /// Pre-creates a room holding `host`, with the rest of its seats left open for whoever
+    /// is invited, and seats the host there to wait for them.
+    pub async fn reserve_invite(&self, host: Arc<Player>, template: Option<&str>, rules: Option<RuleSet>) -> Result<ReservedMatch> {
+        if self.is_queued(&host.id).await {
+            bail!("Player {} is already queued", host.id);
+        }
+        if let Some(rules) = &rules {
+            if template.is_some() {
+                return Err(InvalidRules { reason: "a template room plays the template's rules".to_string() }.into());
+            }
+            if let Err(e) = rules.validate() {
+                return Err(InvalidRules { reason: e.to_string() }.into());
+            }
+        }
+        let seats = match template {
+            Some(template) => self.template_config(template)?.mode.players_per_room(),
+            None => GameMode::Solo.players_per_room(),
+        };
+        let reserved = self.reserve_seats(vec![host.id.clone()], seats.saturating_sub(1), template, rules, None).await?;
+        self.join_room(host, &reserved.room_id).await?;
+        Ok(reserved)
+    }
+
+    /// Gives `player` an open seat in the invite room `room` (a room id or join code) and
+    /// seats them, as [`Self::join_room`] does. Returns `None` when the room has no seat
+    /// left for them, or they already have a room or a seat elsewhere.
+    pub async fn claim_invite_seat(&self, player: Arc<Player>, room: &str) -> Result<Option<ServerMessage>> {
+        let current_room = self.player_room_id(&player.id).await;
+        if self.is_queued(&player.id).await {
+            return Ok(None);
+        }
+        {
+            let mut reservations = self.reservations.write().await;
+            let seated_elsewhere = reservations
+                .values()
+                .any(|r| r.players.contains(&player.id) && r.room_id != room && !r.join_code.eq_ignore_ascii_case(room));
+            let Some(reserved) = reservations.values_mut().find(|r| r.room_id == room || r.join_code.eq_ignore_ascii_case(room)) else {
+                return Ok(None);
+            };
+            if seated_elsewhere || current_room.is_some_and(|id| id != reserved.room_id) {
+                return Ok(None);
+            }
+            if !reserved.players.contains(&player.id) {
+                if reserved.open_seats == 0 {
+                    return Ok(None);
+                }
+                reserved.open_seats -= 1;
+                reserved.players.push(player.id.clone());
+            }
+        }
+        self.join_room(player, room).await
+    }
+
+    /// The not yet started reservation for `room`, a room id or join code.
+    pub async fn reservation(&self, room: &str) -> Option<ReservedMatch> {
+        let reservations = self.reservations.read().await;
+        reservations.values().find(|r| r.room_id == room || r.join_code.eq_ignore_ascii_case(room)).cloned()
+    }
+
+    /// Reserves a room for `player_ids` plus `open_seats` seats claimed later by invite.
+    async fn reserve_seats(
+        &self,
+        player_ids: Vec<String>,
+        open_seats: usize,
+        template: Option<&str>,
+        rules: Option<RuleSet>,
+        tournament: Option<&str>,
+    ) -> Result<ReservedMatch> {
+        let mut config = match template {
+            Some(template) => self.template_config(template)?,
+            None => self.room_config(GameMode::Solo, GameType::default()),
+        };
+        if let Some(rules) = rules {
+            config.rules = rules;
+        }
+        let mode = config.mode;
+        if mode == GameMode::Bot {
+            bail!("Bot games can't be reserved");
+        }
+        let mut unique = player_ids.clone();
+        unique.sort();
+        unique.dedup();
+        if unique.len() != player_ids.len() || player_ids.len() + open_seats != mode.players_per_room() {
+            bail!("A match needs {} distinct player ids", mode.players_per_room());
+        }
+
+        let room = self.new_room(config)?;
+        let reserved = ReservedMatch {
+            room_id: room.id.clone(),
+            join_code: room.id.replace('-', "")[..JOIN_CODE_LEN].to_uppercase(),
+            players: player_ids,
+            open_seats,
+            tournament: tournament.map(str::to_string),
+        };
+
+        for player_id in &reserved.players {
+            if self.player_room_id(player_id).await.is_some() {
+                bail!("Player {} already has a room", player_id);
+            }
+        }
+        {
+            let mut reservations = self.reservations.write().await;
+            let taken = reserved.players.iter().find(|id| reservations.values().any(|r| r.players.contains(id)));
+            if let Some(player_id) = taken {
+                bail!("Player {} already has a room", player_id);
+            }
+            reservations.insert(reserved.room_id.clone(), reserved.clone());
+        }
+        self.insert_room(&room.id.clone(), Arc::new(Mutex::new(room))).await;
+
+        self.events.publish(GameEvent::MatchReserved {
+            room_id: reserved.room_id.clone(),
+            players: reserved.players.clone(),
+            tournament: reserved.tournament.clone(),
+        });
+        info!("Match reserved: {} ({})", reserved.players.join(" vs "), reserved.room_id);
+        Ok(reserved)
+    }
+
+    /// Seats a player in a reserved room. Returns `None` when no reservation matches
+    /// `room` (a room id or join code) for this player.
+    pub async fn join_room(&self, player: Arc<Player>, room: &str) -> Result<Option<ServerMessage>> {
+        let reserved = {
+            let reservations = self.reservations.read().await;
+            reservations
+                .values()
+                .find(|r| r.room_id == room || r.join_code.eq_ignore_ascii_case(room))
+                .filter(|r| r.players.contains(&player.id))
+                .cloned()
+        };
+        let Some(reserved) = reserved else {
+            return Ok(None);
+        };
+        let Some(room_arc) = self.room(&reserved.room_id).await else {
+            return Ok(None);
+        };
+
+        let mut room = room_arc.lock().await;
+        if !room.players.iter().any(|p| p.id == player.id) {
+            room.add_player(player.clone())?;
+        }
+        self.set_player_room(&player.id, &reserved.room_id).await;
+
+        if room.players.len() < reserved.seats() {
+            return Ok(Some(ServerMessage::Matchmaking {
+                matched: false,
+                waiting: Some(true),
+                room_id: Some(reserved.room_id),
+            }));
         }
+
+        self.reservations.write().await.remove(&reserved.room_id);
+        self.start_match(&room).await.map(Some)
     }
 
-    pub async fn find_match(&self, player: Arc<Player>) -> Result<ServerMessage> {
-        let waiting_player = {
-            let mut queue = self.waiting_queue.lock().await;
-            queue.pop()
+    /// Starts a new game between the players of the finished room `room_id`, calling
+    /// `before_start` with the new room's id just before it starts. Returns that id, or
+    /// `None` if any of the players has disconnected or moved on since.
+    pub async fn start_next_game(&self, room_id: &str, before_start: impl FnOnce(&str)) -> Result<Option<String>> {
+        let Some(room_arc) = self.room(room_id).await else {
+            return Ok(None);
+        };
+        let (players, config) = {
+            let room = room_arc.lock().await;
+            if room.status != GameStatus::Finished {
+                return Ok(None);
+            }
+            (room.players.clone(), room.config.clone())
         };
+        if config.mode == GameMode::Bot {
+            bail!("Bot games aren't played again");
+        }
+        for player in &players {
+            let moved_on = self.is_queued(&player.id).await || self.player_room_id(&player.id).await.as_deref() != Some(room_id);
+            if moved_on || !player.is_connected() {
+                return Ok(None);
+            }
+        }
 
-        if let Some(waiting_player) = waiting_player {
-            self.create_match(waiting_player, player).await
-        } else {
-            self.add_to_queue(player).await
+        match self.create_match_with(config, players, before_start).await? {
+            ServerMessage::Matchmaking { room_id, .. } => Ok(room_id),
+            _ => Ok(None),
         }
     }
 
-    async fn create_match(&self, player1: Arc<Player>, player2: Arc<Player>) -> Result<ServerMessage> {
-        let room_id = Uuid::new_v4().to_string();
-        let mut room = GameRoom::new(room_id.clone(), self.config.clone());
+    /// Sends `message` to everyone in the room; false if there is no such room.
+    pub async fn broadcast_to_room(&self, room_id: &str, message: &ServerMessage) -> Result<bool> {
+        let Some(room_arc) = self.room(room_id).await else {
+            return Ok(false);
+        };
+        room_arc.lock().await.broadcast_to_all(message).await?;
+        Ok(true)
+    }
 
-        room.add_player(player1.clone())?;
-        room.add_player(player2.clone())?;
+    /// Tells the players seated in a reserved room that `left_player_id` is gone, so they can
+    /// let a stranger from the queue take the seat instead of waiting for them.
+    async fn offer_backfill(&self, room_id: &str, left_player_id: &str) {
+        let Some(room_arc) = self.room(room_id).await else {
+            return;
+        };
+        let room = room_arc.lock().await;
+        if room.status != GameStatus::Waiting {
+            return;
+        }
 
-        let room_arc = Arc::new(Mutex::new(room));
+        let offer = ServerMessage::BackfillOffer {
+            room_id: room_id.to_string(),
+            player_id: left_player_id.to_string(),
+        };
+        for player in room.players.iter().filter(|p| p.id != left_player_id) {
+            let _ = player.send_message(&offer).await;
+        }
+    }
 
-        // Store room and player mappings
-        {
-            let mut rooms = self.rooms.write().await;
-            rooms.insert(room_id.clone(), room_arc.clone());
+    /// A seated player's answer to a `BackfillOffer`. On consent the open seat goes to the
+    /// longest-waiting player, or to the next one to ask for a match if nobody is waiting.
+    /// Returns `None` when the player has no reserved room with an open seat.
+    pub async fn answer_backfill(&self, player_id: &str, accept: bool) -> Result<Option<ServerMessage>> {
+        if !self.matchmaking.backfill_reserved_rooms {
+            return Ok(None);
         }
-        {
-            let mut player_rooms = self.player_rooms.write().await;
-            player_rooms.insert(player1.id.clone(), room_id.clone());
-            player_rooms.insert(player2.id.clone(), room_id.clone());
+        let Some(room_id) = self.player_room_id(player_id).await else {
+            return Ok(None);
+        };
+        if !self.reservations.read().await.contains_key(&room_id) {
+            return Ok(None);
         }
 
-        // Start the game
-        {
-            let room = room_arc.lock().await;
-            room.start_game().await?;
+        let waiting = ServerMessage::Matchmaking {
+            matched: false,
+            waiting: Some(true),
+            room_id: Some(room_id.clone()),
+        };
+        if !accept {
+            self.backfill_rooms.lock().await.retain(|id| *id != room_id);
+            info!("Player {} kept the open seat in room {} for its reserved player", player_id, room_id);
+            return Ok(Some(waiting));
         }
 
-        info!("Match created: {} vs {}", player1.id, player2.id);
+        let candidate = {
+            let mut queue = self.waiting_queue.lock().await;
+            self.evict_dead_entries(&mut queue).await;
+            queue.pop_front()
+        };
+        let Some(candidate) = candidate else {
+            let mut backfill_rooms = self.backfill_rooms.lock().await;
+            if !backfill_rooms.contains(&room_id) {
+                backfill_rooms.push(room_id.clone());
+            }
+            return Ok(Some(waiting));
+        };
 
-        Ok(ServerMessage::Matchmaking {
-            matched: true,
-            waiting: None,
-            room_id: Some(room_id),
-        })
+        match self.seat_backfill(&room_id, candidate.clone()).await? {
+            Backfill::Seated(message) => {
+                self.release_queued(&candidate.id).await;
+                if let Some(shadow) = &self.shadow {
+                    shadow.observe_leave(&candidate.id);
+                }
+                let _ = candidate.send_message(&message).await;
+                Ok(Some(*message))
+            }
+            Backfill::Skipped => {
+                self.waiting_queue.lock().await.push_front(candidate);
+                Ok(Some(waiting))
+            }
+            Backfill::Closed => {
+                self.waiting_queue.lock().await.push_front(candidate);
+                Ok(None)
+            }
+        }
     }
 
-    async fn add_to_queue(&self, player: Arc<Player>) -> Result<ServerMessage> {
-        let mut queue = self.waiting_queue.lock().await;
-        queue.push(player);
+    /// Seats the player in the oldest reserved room waiting for a backfill, if any.
+    async fn take_backfill_seat(&self, player: Arc<Player>) -> Result<Option<ServerMessage>> {
+        let room_ids = self.backfill_rooms.lock().await.clone();
+        for room_id in room_ids {
+            match self.seat_backfill(&room_id, player.clone()).await? {
+                Backfill::Seated(message) => return Ok(Some(*message)),
+                Backfill::Skipped => {}
+                Backfill::Closed => self.backfill_rooms.lock().await.retain(|id| *id != room_id),
+            }
+        }
+        Ok(None)
+    }
 
-        Ok(ServerMessage::Matchmaking {
-            matched: false,
-            waiting: Some(true),
-            room_id: None,
-        })
+    /// Hands a reserved room's first unclaimed seat to `player`, starting the game once the
+    /// room is full. The seat's reserved player loses their claim to it.
+    async fn seat_backfill(&self, room_id: &str, player: Arc<Player>) -> Result<Backfill> {
+        let Some(room_arc) = self.room(room_id).await else {
+            return Ok(Backfill::Closed);
+        };
+
+        let mut room = room_arc.lock().await;
+        let (left, full) = {
+            let mut reservations = self.reservations.write().await;
+            let Some(reserved) = reservations.get_mut(room_id) else {
+                return Ok(Backfill::Closed);
+            };
+            if room.status != GameStatus::Waiting || room.players.is_empty() {
+                return Ok(Backfill::Closed);
+            }
+            let Some(seat) = reserved.players.iter().position(|id| !room.players.iter().any(|p| p.id == *id)) else {
+                return Ok(Backfill::Closed);
+            };
+            if !player.is_connected()
+                || reserved.players.contains(&player.id)
+                || room.players.iter().any(|p| p.same_session(&player))
+            {
+                return Ok(Backfill::Skipped);
+            }
+
+            room.add_player(player.clone())?;
+            let left = std::mem::replace(&mut reserved.players[seat], player.id.clone());
+            let full = room.players.len() >= reserved.seats();
+            if full {
+                reservations.remove(room_id);
+            }
+            (left, full)
+        };
+        self.set_player_room(&player.id, room_id).await;
+        info!("Player {} took {}'s seat in reserved room {}", player.id, left, room_id);
+
+        if full {
+            self.backfill_rooms.lock().await.retain(|id| id != room_id);
+            let message = self.start_match(&room).await?;
+            Ok(Backfill::Seated(Box::new(message)))
+        } else {
+            Ok(Backfill::Seated(Box::new(ServerMessage::Matchmaking {
+                matched: false,
+                waiting: Some(true),
+                room_id: Some(room_id.to_string()),
+            })))
+        }
+    }
+
+    /// Queues the player. A full queue applies the overflow policy: the newcomer is
+    /// refused with `ServerBusy`, or the longest-waiting player is moved to a bot game.
+    async fn add_to_queue(&self, queue: &PlayerQueue, player: Arc<Player>, priority: bool) -> Result<ServerMessage> {
+        let overflowed = {
+            let mut queue = queue.lock().await;
+            let overflowed = if queue.len() < self.matchmaking.max_queue_len {
+                None
+            } else if self.matchmaking.queue_overflow == QueueOverflowPolicy::Reject {
+                drop(queue);
+                self.stats.queue_overflowed();
+                self.release_queued(&player.id).await;
+                if let Some(shadow) = &self.shadow {
+                    shadow.observe_leave(&player.id);
+                }
+                warn!("Queue full ({} players); refusing player {}", self.matchmaking.max_queue_len, player.id);
+                return Ok(ServerMessage::ServerBusy {
+                    retry_after_ms: self.capacity.retry_after_ms,
+                });
+            } else {
+                queue.pop_front()
+            };
+
+            if priority {
+                queue.push_front(player.clone());
+            } else {
+                queue.push_back(player.clone());
+            }
+            overflowed
+        };
+        self.events.publish(GameEvent::PlayerQueued {
+            player_id: player.id.clone(),
+        });
+
+        if let Some(oldest) = overflowed {
+            self.stats.queue_overflowed();
+            self.release_queued(&oldest.id).await;
+            if let Some(shadow) = &self.shadow {
+                shadow.observe_leave(&oldest.id);
+            }
+            info!("Queue full; moving longest-waiting player {} to a bot game", oldest.id);
+            match self.create_match(self.room_config(GameMode::Bot, GameType::default()), vec![oldest.clone()]).await {
+                Ok(message) => {
+                    let _ = oldest.send_message(&message).await;
+                }
+                Err(e) => warn!("Failed to start a bot game for overflowed player {}: {}", oldest.id, e),
+            }
+        }
+
+        Ok(Self::waiting_message())
     }
 
     pub async fn submit_move(&self, player_id: &str, choice: GameChoice) -> Result<bool> {
         let room_arc = self.get_player_room(player_id).await;
 
         if let Some(room_arc) = room_arc {
-            let should_process = {
-                let mut room = room_arc.lock().await;
-                room.submit_move(player_id, choice)?
-            };
+            let mut room = room_arc.lock().await;
+            // A move that arrives after its round closed is refused, not carried into the next
+            if room.enforce_time_limit().await? || room.expire_round().await? {
+                return Err(MoveRefused::RoundClosed.into());
+            }
+            let first_move = !room.moves.contains_key(player_id);
+            let should_process = room.submit_move(player_id, choice)?;
+            for sample in room.take_move_samples() {
+                self.bot_detector.record(&sample);
+            }
+            // The rest of the room learns that the move is in, never what it was; the mover
+            // gets it too, so everyone's sequence numbers stay gapless
+            if first_move && !should_process && room.moves.contains_key(player_id) {
+                let moved = ServerMessage::OpponentMoved {
+                    seq: None,
+                    player_id: player_id.to_string(),
+                };
+                room.broadcast_to_all(&moved).await?;
+            }
 
+            // Resolved under the same lock, so the round timer or the watchdog can't resolve
+            // the round in between
             if should_process {
-                let mut room = room_arc.lock().await;
                 room.process_round().await?;
             }
 
@@ -105,61 +1369,584 @@ impl GameManager {
     }
 
     pub async fn remove_player(&self, player_id: &str) -> Result<()> {
-        // Remove from waiting queue
-        {
-            let mut queue = self.waiting_queue.lock().await;
-            queue.retain(|p| p.id != player_id);
+        // Remove from waiting queues
+        self.remove_queued_entry(player_id).await;
+        self.release_queued(player_id).await;
+        if let Some(shadow) = &self.shadow {
+            shadow.observe_leave(player_id);
         }
 
         // Remove from room if exists
-        let room_id = {
-            let mut player_rooms = self.player_rooms.write().await;
-            player_rooms.remove(player_id)
-        };
+        let room_id = self.clear_player_room(player_id).await;
+
+        // A reserved room keeps waiting for its players, so leaving early only gives up the seat
+        let reserved_room = self
+            .reservations
+            .read()
+            .await
+            .values()
+            .find(|r| r.players.iter().any(|id| id == player_id))
+            .map(|r| r.room_id.clone());
+        if let Some(reserved_room) = reserved_room {
+            if let Some(room_arc) = self.room(&reserved_room).await {
+                let emptied = {
+                    let mut room = room_arc.lock().await;
+                    room.players.retain(|p| p.id != player_id);
+                    room.players.is_empty()
+                };
+                if emptied {
+                    self.backfill_rooms.lock().await.retain(|id| *id != reserved_room);
+                } else if self.matchmaking.backfill_reserved_rooms {
+                    self.offer_backfill(&reserved_room, player_id).await;
+                }
+            }
+            if room_id.as_ref() == Some(&reserved_room) {
+                return Ok(());
+            }
+        }
 
         if let Some(room_id) = room_id {
-            let room_arc = {
-                let mut rooms = self.rooms.write().await;
-                rooms.remove(&room_id)
+
+            if let Some(room_arc) = self.remove_room(&room_id).await {
+                let (abandoned, config) = {
+                    let room = room_arc.lock().await;
+                    room.notify_player_left(player_id).await?;
+                    let in_progress = matches!(room.status, GameStatus::Playing | GameStatus::Paused);
+                    if in_progress && room.config.mode != GameMode::Bot {
+                        self.quit_penalties.player_quit(player_id, self.clock.now());
+                    }
+                    let others: Vec<_> = room.players.iter().filter(|p| p.id != player_id).cloned().collect();
+                    (if in_progress { others } else { Vec::new() }, room.config.clone())
+                };
+
+                for player in &abandoned {
+                    self.clear_player_room(&player.id).await;
+                }
+                self.rematch_abandoned(abandoned, config).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies the abandoned-match policy to players whose game ended because an opponent
+    /// left. Each is sent the resulting matchmaking message, as if they had asked for it.
+    async fn rematch_abandoned(&self, players: Vec<Arc<Player>>, config: GameConfig) {
+        let policy = self.matchmaking.abandoned_match;
+        if policy == AbandonedMatchPolicy::Off || config.mode == GameMode::Bot {
+            return;
+        }
+
+        for player in players.into_iter().filter(|p| p.is_connected()) {
+            let result = match policy {
+                AbandonedMatchPolicy::Bot => {
+                    self.create_match(self.room_config(GameMode::Bot, GameType::default()), vec![player.clone()]).await
+                }
+                _ => {
+                    if !self.claim_queued(&player.id).await {
+                        continue;
+                    }
+                    self.join_queue(player.clone(), config.clone(), true).await
+                }
             };
+            match result {
+                Ok(message) => {
+                    info!("Player {} rematched after their opponent left ({:?})", player.id, policy);
+                    let _ = player.send_message(&message).await;
+                }
+                Err(e) => warn!("Failed to rematch player {}: {}", player.id, e),
+            }
+        }
+    }
+
+    /// Connection dropped: pause an in-progress game for the reconnect grace period,
+    /// otherwise remove the player right away.
+    pub async fn disconnect_player(&self, player_id: &str) -> Result<()> {
+        self.events.publish(GameEvent::PlayerDisconnected {
+            player_id: player_id.to_string(),
+        });
+
+        if let Some(room_arc) = self.get_player_room(player_id).await {
+            let mut room = room_arc.lock().await;
+            if room.handle_disconnect(player_id).await? {
+                return Ok(());
+            }
+        }
+
+        self.remove_player(player_id).await
+    }
+
+    /// Reattaches a returning player to a paused room. Returns the room id on success.
+    pub async fn reconnect_player(&self, player: Arc<Player>) -> Result<Option<String>> {
+        let Some(room_arc) = self.get_player_room(&player.id).await else {
+            return Ok(None);
+        };
+
+        let mut room = room_arc.lock().await;
+        if room.reconnect(player).await? {
+            Ok(Some(room.id.clone()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn expire_reconnect_grace(&self) -> Result<usize> {
+        let room_arcs = self.all_rooms().await;
+
+        let mut forfeited = Vec::new();
+        for room_arc in room_arcs {
+            let mut room = room_arc.lock().await;
+            if room.enforce_reconnect_grace().await? {
+                if room.config.mode != GameMode::Bot {
+                    for player_id in room.disconnected.keys() {
+                        self.quit_penalties.player_quit(player_id, self.clock.now());
+                    }
+                }
+                let player_ids: Vec<String> = room.players.iter().map(|p| p.id.clone()).collect();
+                let abandoned: Vec<_> =
+                    room.players.iter().filter(|p| !room.disconnected.contains_key(&p.id)).cloned().collect();
+                forfeited.push((room.id.clone(), player_ids, abandoned, room.config.clone()));
+            }
+        }
+
+        // Room locks are released before touching the shared maps
+        for (room_id, player_ids, ..) in &forfeited {
+            self.remove_room(room_id).await;
+            for player_id in player_ids {
+                self.clear_player_room(player_id).await;
+            }
+        }
+
+        let count = forfeited.len();
+        for (_, _, abandoned, config) in forfeited {
+            self.rematch_abandoned(abandoned, config).await;
+        }
+        Ok(count)
+    }
+
+    /// Screens a chat line and relays it to the player's room.
+    pub async fn send_chat(&self, player_id: &str, text: &str) -> Result<(), ChatRefused> {
+        let text = text.trim();
+        if text.is_empty() || text.chars().count() > MAX_CHAT_CHARS {
+            return Err(ChatRefused::Invalid);
+        }
+        let room_arc = self.get_player_room(player_id).await.ok_or(ChatRefused::NotInGame)?;
+        let room = room_arc.lock().await;
+        if !room.config.chat_enabled {
+            return Err(ChatRefused::Disabled);
+        }
+        self.spam_guard.check(player_id, SpamAction::Chat).map_err(ChatRefused::Muted)?;
+
+        let (text, shadow_muted) = match self.profanity.screen(text) {
+            Screened::Allowed(text) => (text, false),
+            Screened::ShadowMuted(text) => (text, true),
+            Screened::Rejected => return Err(ChatRefused::Inappropriate),
+        };
+        match room.chat(player_id, text, shadow_muted).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(ChatRefused::NotInGame),
+            Err(e) => {
+                warn!("Failed to relay chat from {}: {}", player_id, e);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn request_pause(&self, player_id: &str) -> Result<bool> {
+        match self.get_player_room(player_id).await {
+            Some(room_arc) => room_arc.lock().await.request_pause(player_id).await,
+            None => Ok(false),
+        }
+    }
+
+    pub async fn request_resume(&self, player_id: &str) -> Result<bool> {
+        match self.get_player_room(player_id).await {
+            Some(room_arc) => room_arc.lock().await.request_resume(player_id).await,
+            None => Ok(false),
+        }
+    }
+
+    pub async fn expire_pauses(&self) -> Result<usize> {
+        let mut resumed = 0;
+        for room_arc in self.all_rooms().await {
+            if room_arc.lock().await.enforce_pause_limit().await? {
+                resumed += 1;
+            }
+        }
+        Ok(resumed)
+    }
+
+    pub async fn kick_player(&self, player_id: &str, reason: &str) -> Result<bool> {
+        let player = match self.queued_entry(player_id).await {
+            Some(player) => Some(player),
+            None => match self.get_player_room(player_id).await {
+                Some(room_arc) => {
+                    let room = room_arc.lock().await;
+                    room.players.iter().find(|p| p.id == player_id).cloned()
+                }
+                None => None,
+            },
+        };
+
+        let Some(player) = player else {
+            return Ok(false);
+        };
+
+        let _ = player
+            .send_message(&ServerMessage::Kicked {
+                reason: reason.to_string(),
+            })
+            .await;
+        self.remove_player(player_id).await?;
+
+        info!("Player {} kicked: {}", player_id, reason);
+        self.events.publish(GameEvent::PlayerKicked {
+            player_id: player_id.to_string(),
+            reason: reason.to_string(),
+        });
+        Ok(true)
+    }
+
+    pub async fn close_room(&self, room_id: &str, reason: &str) -> Result<bool> {
+        let Some(room_arc) = self.remove_room(room_id).await else {
+            return Ok(false);
+        };
+        self.reservations.write().await.remove(room_id);
+
+        let room = room_arc.lock().await;
+        for player in &room.players {
+            self.clear_player_room(&player.id).await;
+        }
+        room.notify_room_closed(reason).await?;
+
+        info!("Room {} closed: {}", room_id, reason);
+        Ok(true)
+    }
 
-            if let Some(room_arc) = room_arc {
-                let room = room_arc.lock().await;
-                room.notify_player_left(player_id).await?;
+    /// Closes up to `count` rooms picked at random, through the same path as an admin close.
+    #[cfg(feature = "chaos")]
+    pub async fn kill_random_rooms(&self, count: usize) -> Vec<String> {
+        let mut room_ids = Vec::new();
+        for shard in self.rooms.iter() {
+            room_ids.extend(shard.read().await.keys().cloned());
+        }
+
+        let mut killed = Vec::new();
+        for room_id in super::chaos::CHAOS.pick(room_ids, count) {
+            match self.close_room(&room_id, "Killed by chaos testing").await {
+                Ok(true) => killed.push(room_id),
+                Ok(false) => {}
+                Err(e) => warn!("Chaos kill of room {} failed: {}", room_id, e),
+            }
+        }
+        super::chaos::CHAOS.rooms_killed(killed.len());
+        killed
+    }
+
+    /// Rooms with a game being played or paused.
+    pub async fn live_room_ids(&self) -> Vec<String> {
+        let mut room_ids = Vec::new();
+        for room_arc in self.all_rooms().await {
+            let room = room_arc.lock().await;
+            if matches!(room.status, GameStatus::Playing | GameStatus::Paused) {
+                room_ids.push(room.id.clone());
+            }
+        }
+        room_ids
+    }
+
+    /// Removes a live room so it can be handed to another node, returning its state and
+    /// the connections it was using. Its players are left without a room here.
+    pub async fn take_room(&self, room_id: &str) -> Option<(RoomState, Vec<Arc<Player>>)> {
+        let room_arc = self.room(room_id).await?;
+        let room = room_arc.lock().await;
+        if !matches!(room.status, GameStatus::Playing | GameStatus::Paused) || self.remove_room(room_id).await.is_none() {
+            return None;
+        }
+        for player in &room.players {
+            self.clear_player_room(&player.id).await;
+        }
+
+        info!("Room {} taken for handover", room_id);
+        Some((room.state(), room.players.clone()))
+    }
+
+    /// Opens a room handed over from another node (or back from a refused handover),
+    /// seating the players in `connections` right away.
+    pub async fn restore_room(&self, state: RoomState, connections: &HashMap<String, Arc<Player>>) -> Result<()> {
+        if self.room(&state.id).await.is_some() {
+            bail!("Room {} already exists", state.id);
+        }
+        // The bot never had a player mapping
+        let player_ids: Vec<String> = state
+            .players
+            .iter()
+            .filter(|id| state.bot.as_ref().is_none_or(|bot| bot.id != **id))
+            .cloned()
+            .collect();
+        for player_id in &player_ids {
+            if self.player_room_id(player_id).await.is_some() {
+                bail!("Player {} already has a room", player_id);
             }
         }
+        state.config.rules.validate()?;
+
+        let room_id = state.id.clone();
+        let mut room = self.new_room(state.config.clone())?;
+        room.restore(state, connections)?;
+        let timed = room.config.round_window_ms.is_some();
+        let room_arc = Arc::new(Mutex::new(room));
+        self.insert_room(&room_id, room_arc.clone()).await;
+        if timed {
+            self.spawn_round_timer(Arc::downgrade(&room_arc));
+        }
+        for player_id in &player_ids {
+            self.set_player_room(player_id, &room_id).await;
+        }
 
+        info!("Room {} restored with {} of {} players connected", room_id, connections.len(), player_ids.len());
         Ok(())
     }
 
-    pub async fn get_stats(&self) -> (usize, usize, usize) {
-        let rooms = self.rooms.read().await;
-        let queue = self.waiting_queue.lock().await;
+    pub async fn enforce_time_limits(&self) -> Result<usize> {
+        let room_arcs = self.all_rooms().await;
+
+        let mut expired = 0;
+        for room_arc in room_arcs {
+            let mut room = room_arc.lock().await;
+            if room.enforce_time_limit().await? {
+                expired += 1;
+            }
+        }
+
+        Ok(expired)
+    }
+
+    /// Watchdog for rooms stuck on a round that has every move in, which otherwise stall
+    /// forever. Each is resolved again; one whose resolution fails or panics again is closed
+    /// and its players told, as is any room whose round timer panicked. Returns how many
+    /// rooms were recovered either way.
+    pub async fn recover_stuck_rooms(&self, grace: Duration) -> usize {
+        let mut broken = std::mem::take(&mut *self.panicked_rooms.lock());
+        let mut recovered = broken.len();
+        for room_arc in self.all_rooms().await {
+            let mut room = room_arc.lock().await;
+            if !room.is_stuck(grace) {
+                continue;
+            }
+            warn!("Room {} stuck in round {} with every move in; resolving it", room.id, room.current_round);
+            recovered += 1;
+            self.stats.room_recovered();
+            match AssertUnwindSafe(room.process_round()).catch_unwind().await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    error!("Stuck room {} failed to resolve again: {}", room.id, e);
+                    broken.push(room.id.clone());
+                }
+                Err(panic) => {
+                    error!("Stuck room {} panicked resolving again: {}", room.id, panic_message(panic.as_ref()));
+                    broken.push(room.id.clone());
+                }
+            }
+        }
+
+        // Room locks are released before closing, which takes the shared maps
+        for room_id in broken {
+            if let Err(e) = self.close_room(&room_id, "The game hit an internal error and was ended").await {
+                warn!("Failed to close stuck room {}: {}", room_id, e);
+            }
+        }
+        recovered
+    }
+
+    pub async fn room_snapshot(&self, room_id: &str) -> Option<RoomSnapshot> {
+        let room_arc = self.room(room_id).await?;
+        let room = room_arc.lock().await;
+        Some(room.snapshot())
+    }
+
+    /// A finished game from the history, or a live one by scanning the open rooms.
+    pub async fn game_record(&self, game_id: &str) -> Option<GameRecord> {
+        if let Some(record) = self.history.get(game_id) {
+            return Some(record);
+        }
 
-        let total_rooms = rooms.len();
-        let mut active_games = 0;
-        for room_arc in rooms.values() {
+        for room_arc in self.all_rooms().await {
             let room = room_arc.lock().await;
-            if room.status == crate::domain::GameStatus::Playing {
-                active_games += 1;
+            if room.game_id == game_id {
+                return Some(room.record());
+            }
+        }
+
+        let archive = self.archive.as_ref()?;
+        match archive.fetch(game_id).await {
+            Ok(record) => record,
+            Err(e) => {
+                warn!("Archive lookup for game {} failed: {}", game_id, e);
+                None
             }
         }
-        let waiting_players = queue.len();
+    }
+
+    /// Open rooms, games being played, and players waiting in any queue, from counters
+    /// kept at each transition; no room or queue is locked.
+    pub async fn get_stats(&self) -> (usize, usize, usize) {
+        let counters = self.stats.snapshot();
+        let waiting_players = self.all_queues().iter().map(|(_, queue)| queue.len()).sum();
+        (counters.open_rooms as usize, counters.active_games as usize, waiting_players)
+    }
 
-        (total_rooms, active_games, waiting_players)
+    pub fn live_stats(&self) -> LiveStatsSnapshot {
+        self.stats.snapshot()
     }
 
-    async fn get_player_room(&self, player_id: &str) -> Option<Arc<Mutex<GameRoom>>> {
-        let room_id = {
-            let player_rooms = self.player_rooms.read().await;
-            player_rooms.get(player_id).cloned()
+    /// Walks every room and queue; for the admin API only.
+    pub async fn deep_stats(&self) -> DeepStats {
+        let mut stats = DeepStats {
+            counters: self.stats.snapshot(),
+            ..DeepStats::default()
+        };
+        for room_arc in self.all_rooms().await {
+            let status = room_arc.lock().await.status.clone();
+            stats.rooms += 1;
+            *stats.rooms_by_status.entry(status).or_default() += 1;
+        }
+        for shard in self.player_rooms.iter() {
+            stats.seated_players += shard.read().await.len();
+        }
+        for (name, queue) in self.all_queues() {
+            stats.queued.insert(name, queue.lock().await.len());
+        }
+        stats
+    }
+
+    /// Rooms and players held by each shard, in shard order.
+    pub async fn shard_stats(&self) -> Vec<ShardStats> {
+        let mut stats = vec![ShardStats::default(); self.rooms.shard_count()];
+        for (shard, rooms) in stats.iter_mut().zip(self.rooms.iter()) {
+            shard.rooms = rooms.read().await.len();
+        }
+        for (shard, player_rooms) in stats.iter_mut().zip(self.player_rooms.iter()) {
+            shard.seated_players = player_rooms.read().await.len();
+        }
+        for (shard, queued) in stats.iter_mut().zip(self.queued_players.iter()) {
+            shard.queued_players = queued.lock().await.len();
+        }
+        stats
+    }
+
+    pub async fn player_phase(&self, player_id: &str) -> PlayerPhase {
+        if self.is_queued(player_id).await {
+            return PlayerPhase::Queued;
+        }
+
+        let Some(room_arc) = self.get_player_room(player_id).await else {
+            return PlayerPhase::Idle;
         };
+        let status = room_arc.lock().await.status.clone();
+        match status {
+            GameStatus::Waiting => PlayerPhase::Queued,
+            GameStatus::Playing | GameStatus::Paused => PlayerPhase::InGame,
+            GameStatus::Finished => PlayerPhase::PostGame,
+        }
+    }
 
-        if let Some(room_id) = room_id {
-            let rooms = self.rooms.read().await;
-            rooms.get(&room_id).cloned()
-        } else {
-            None
+    /// The `State` reply to `RequestState`: the player's phase and, when seated, their room.
+    pub async fn player_state(&self, player_id: &str) -> ServerMessage {
+        let phase = self.player_phase(player_id).await;
+        match self.get_player_room(player_id).await {
+            Some(room_arc) => room_arc.lock().await.state_for(player_id, phase),
+            None => ServerMessage::State {
+                phase,
+                room_id: None,
+                game_id: None,
+                status: None,
+                round: None,
+                scores: HashMap::new(),
+                awaiting_moves: Vec::new(),
+                your_move: None,
+                seq: None,
+            },
         }
     }
+
+    /// Rough per-subsystem heap use, for the performance monitor and `/ultra-metrics`.
+    pub async fn memory_estimate(&self) -> MemoryEstimate {
+        let mut estimate = MemoryEstimate::default();
+        for room_arc in self.all_rooms().await {
+            let room = room_arc.lock().await.memory_estimate();
+            estimate.rooms_bytes += room.rooms_bytes;
+            estimate.move_history_bytes += room.move_history_bytes;
+        }
+
+        for (_, queue) in self.all_queues() {
+            let queue = queue.lock().await;
+            estimate.queues_bytes += queue.capacity() * std::mem::size_of::<Arc<Player>>()
+                + queue.len() * std::mem::size_of::<Player>();
+        }
+        estimate
+    }
+
+    async fn all_rooms(&self) -> Vec<Arc<Mutex<GameRoom>>> {
+        let mut all = Vec::new();
+        for shard in self.rooms.iter() {
+            all.extend(shard.read().await.values().cloned());
+        }
+        all
+    }
+
+    async fn room(&self, room_id: &str) -> Option<Arc<Mutex<GameRoom>>> {
+        self.rooms.get(room_id).read().await.get(room_id).cloned()
+    }
+
+    async fn insert_room(&self, room_id: &str, room: Arc<Mutex<GameRoom>>) {
+        if self.quotas.is_enabled() {
+            let room = room.lock().await;
+            self.quotas.room_opened(room_id, Self::quota_usage(room.config.mode, &room.players));
+        }
+        if self.rooms.get(room_id).write().await.insert(room_id.to_string(), room).is_none() {
+            self.stats.room_opened();
+        }
+    }
+
+    async fn remove_room(&self, room_id: &str) -> Option<Arc<Mutex<GameRoom>>> {
+        let removed = self.rooms.get(room_id).write().await.remove(room_id);
+        if removed.is_some() {
+            self.stats.room_closed();
+            self.quotas.room_closed(room_id);
+        }
+        removed
+    }
+
+    async fn player_room_id(&self, player_id: &str) -> Option<String> {
+        self.player_rooms.get(player_id).read().await.get(player_id).cloned()
+    }
+
+    async fn set_player_room(&self, player_id: &str, room_id: &str) {
+        self.player_rooms.get(player_id).write().await.insert(player_id.to_string(), room_id.to_string());
+    }
+
+    async fn clear_player_room(&self, player_id: &str) -> Option<String> {
+        self.player_rooms.get(player_id).write().await.remove(player_id)
+    }
+
+    async fn is_queued(&self, player_id: &str) -> bool {
+        self.queued_players.get(player_id).lock().await.contains(player_id)
+    }
+
+    /// Marks the player as queued; false if they already were.
+    async fn claim_queued(&self, player_id: &str) -> bool {
+        self.queued_players.get(player_id).lock().await.insert(player_id.to_string())
+    }
+
+    async fn release_queued(&self, player_id: &str) {
+        self.queued_players.get(player_id).lock().await.remove(player_id);
+        self.quotas.player_unqueued(player_id);
+        self.queue_waits.left(player_id, self.clock.now());
+    }
+
+    async fn get_player_room(&self, player_id: &str) -> Option<Arc<Mutex<GameRoom>>> {
+        let room_id = self.player_room_id(player_id).await?;
+        self.room(&room_id).await
+    }
 }
\ No newline at end of file
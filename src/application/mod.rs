@@ -1,5 +1,49 @@
 pub mod game_service;
 pub mod matchmaking_service;
+pub mod event_bus;
+pub mod presence;
+pub mod bot_detection;
+pub mod bot_opponent;
+pub mod game_history;
+pub mod ratings;
+pub mod shadow_matchmaking;
+pub mod result_signing;
+pub mod spam_guard;
+pub mod rate_limiter;
+pub mod sharded;
+pub mod live_stats;
+pub mod series;
+pub mod activity;
+pub mod profanity;
+pub mod supervision;
+pub mod clock;
+pub mod quotas;
+pub mod quit_penalty;
+pub mod starvation;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 
 pub use game_service::*;
-pub use matchmaking_service::*;
\ No newline at end of file
+pub use matchmaking_service::*;
+pub use event_bus::*;
+pub use presence::*;
+pub use bot_detection::*;
+pub use bot_opponent::*;
+pub use game_history::*;
+pub use ratings::*;
+pub use shadow_matchmaking::*;
+pub use result_signing::*;
+pub use spam_guard::*;
+pub use rate_limiter::*;
+pub use sharded::*;
+pub use live_stats::*;
+pub use series::*;
+pub use activity::*;
+pub use profanity::*;
+pub use supervision::*;
+pub use clock::*;
+pub use quotas::*;
+pub use quit_penalty::*;
+pub use starvation::*;
+#[cfg(feature = "chaos")]
+pub use chaos::*;
@@ -0,0 +1,205 @@
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use super::event_bus::EventBus;
+use crate::domain::GameEvent;
+
+const PRESENCE_CHANGES_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum PresenceState {
+    Queued,
+    InGame {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        round: u32,
+        #[serde(rename = "maxRounds")]
+        max_rounds: u32,
+    },
+    Finished {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        won: Option<bool>, // None on a tied game
+    },
+    Offline,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Presence {
+    #[serde(rename = "playerId")]
+    pub player_id: String,
+    #[serde(flatten)]
+    pub state: PresenceState,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Latest known activity per player, derived from the event bus.
+pub struct PresenceRegistry {
+    entries: RwLock<HashMap<String, Presence>>,
+    changes: broadcast::Sender<Presence>,
+}
+
+impl PresenceRegistry {
+    pub fn new() -> Self {
+        let (changes, _) = broadcast::channel(PRESENCE_CHANGES_CAPACITY);
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            changes,
+        }
+    }
+
+    pub fn get(&self, player_id: &str) -> Option<Presence> {
+        self.entries.read().get(player_id).cloned()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Presence> {
+        self.changes.subscribe()
+    }
+
+    /// Updates presence from one event and notifies subscribers of every change.
+    pub fn apply(&self, event: &GameEvent) {
+        let updates: Vec<(String, PresenceState)> = match event {
+            GameEvent::PlayerQueued { player_id } => vec![(player_id.clone(), PresenceState::Queued)],
+            GameEvent::MatchCreated { room_id, players, max_rounds } => {
+                in_game(room_id, players, 1, *max_rounds)
+            }
+            GameEvent::RoundStarted { room_id, players, round, max_rounds } => {
+                in_game(room_id, players, *round, *max_rounds)
+            }
+            GameEvent::GameEnded { room_id, winner, final_scores, teams, .. } => {
+                // Scores are keyed by team in team mode, by player otherwise
+                let sides: Vec<(&String, &String)> = if teams.is_empty() {
+                    final_scores.keys().map(|player_id| (player_id, player_id)).collect()
+                } else {
+                    teams.iter().collect()
+                };
+                sides
+                    .into_iter()
+                    .map(|(player_id, side)| {
+                        let won = winner.as_ref().map(|winner| winner == side);
+                        (player_id.clone(), PresenceState::Finished { room_id: room_id.clone(), won })
+                    })
+                    .collect()
+            }
+            GameEvent::PlayerDisconnected { player_id }
+            | GameEvent::PlayerKicked { player_id, .. }
+            | GameEvent::PlayerBanned { player_id, .. } => vec![(player_id.clone(), PresenceState::Offline)],
+            GameEvent::PlayerConnected { .. } => Vec::new(), // Nothing to show until they queue
+            GameEvent::SeriesEnded { .. } => Vec::new(), // Each game already updated presence
+            GameEvent::QueueStarved { .. } => Vec::new(), // Still queued
+            GameEvent::MatchReserved { .. } => Vec::new(), // Shown once they join
+        };
+
+        let mut entries = self.entries.write();
+        for (player_id, state) in updates {
+            let unchanged = match entries.get(&player_id) {
+                Some(current) => current.state == state,
+                None => state == PresenceState::Offline,
+            };
+            if unchanged {
+                continue;
+            }
+
+            let presence = Presence {
+                player_id: player_id.clone(),
+                state,
+                updated_at: Utc::now(),
+            };
+            let _ = self.changes.send(presence.clone());
+
+            // Offline players are forgotten; the change above already announced it
+            if presence.state == PresenceState::Offline {
+                entries.remove(&player_id);
+            } else {
+                entries.insert(player_id, presence);
+            }
+        }
+    }
+
+    /// Keeps the registry in sync with the bus until the bus is dropped.
+    pub fn spawn(self: Arc<Self>, events: &EventBus) -> JoinHandle<()> {
+        let mut receiver = events.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(envelope) => self.apply(&envelope.event),
+                    Err(RecvError::Lagged(skipped)) => warn!("Presence registry lagged; {} events dropped", skipped),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+impl Default for PresenceRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn in_game(room_id: &str, players: &[String], round: u32, max_rounds: u32) -> Vec<(String, PresenceState)> {
+    players
+        .iter()
+        .map(|player_id| {
+            let state = PresenceState::InGame {
+                room_id: room_id.to_string(),
+                round,
+                max_rounds,
+            };
+            (player_id.clone(), state)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::application::{PresenceRegistry, PresenceState};
+    use crate::domain::{GameEndReason, GameEvent};
+
+    #[tokio::test]
+    async fn test_presence_registry_follows_lifecycle_events() {
+        let registry = PresenceRegistry::new();
+        let mut changes = registry.subscribe();
+        let players = vec!["p1".to_string(), "p2".to_string()];
+
+        registry.apply(&GameEvent::PlayerQueued { player_id: "p1".to_string() });
+        registry.apply(&GameEvent::MatchCreated { room_id: "r".to_string(), players: players.clone(), max_rounds: 3 });
+        registry.apply(&GameEvent::RoundStarted { room_id: "r".to_string(), players, round: 2, max_rounds: 3 });
+        assert_eq!(
+            registry.get("p1").unwrap().state,
+            PresenceState::InGame { room_id: "r".to_string(), round: 2, max_rounds: 3 }
+        );
+
+        registry.apply(&GameEvent::GameEnded {
+            room_id: "r".to_string(),
+            game_id: "g".to_string(),
+            winner: Some("p2".to_string()),
+            final_scores: [("p1".to_string(), 0), ("p2".to_string(), 2)].into_iter().collect(),
+            reason: GameEndReason::Completed,
+            teams: Default::default(),
+            signature: None,
+        });
+        assert_eq!(registry.get("p2").unwrap().state, PresenceState::Finished { room_id: "r".to_string(), won: Some(true) });
+
+        registry.apply(&GameEvent::PlayerDisconnected { player_id: "p1".to_string() });
+        registry.apply(&GameEvent::PlayerDisconnected { player_id: "ghost".to_string() });
+        assert!(registry.get("p1").is_none());
+
+        let mut count = 0;
+        while changes.try_recv().is_ok() {
+            count += 1;
+        }
+        // queued, 2x match, 2x round 2, 2x finished, offline
+        assert_eq!(count, 8);
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use parking_lot::RwLock;
+use std::collections::BTreeSet;
+
+use crate::config::{ProfanityAction, ProfanityConfig};
+
+/// The filter's verdict on one piece of text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Screened {
+    Allowed(String), // As sent, or masked under `ProfanityAction::Mask`
+    Rejected,
+    ShadowMuted(String), // Shown back to the sender only
+}
+
+/// Screens display names and chat against a word list that admins can replace at runtime.
+/// Words match whole words case-insensitively, after undoing common letter substitutions
+/// like `4` for `a` and `$` for `s`, so "b4d" is caught by "bad" but "badge" is not.
+pub struct ProfanityFilter {
+    words: RwLock<BTreeSet<String>>, // Normalized
+    action: RwLock<ProfanityAction>,
+}
+
+impl ProfanityFilter {
+    pub fn new(config: &ProfanityConfig) -> Result<Self> {
+        let mut words = config.words.clone();
+        if let Some(path) = &config.word_list_path {
+            let list = std::fs::read_to_string(path).with_context(|| format!("Failed to read word list {}", path))?;
+            words.extend(list.lines().map(str::to_string));
+        }
+        let filter = Self::default();
+        filter.set_words(words);
+        *filter.action.write() = config.action;
+        Ok(filter)
+    }
+
+    pub fn words(&self) -> Vec<String> {
+        self.words.read().iter().cloned().collect()
+    }
+
+    pub fn action(&self) -> ProfanityAction {
+        *self.action.read()
+    }
+
+    /// Replaces the whole list. Blank entries are ignored.
+    pub fn set_words(&self, words: impl IntoIterator<Item = String>) {
+        *self.words.write() = words.into_iter().map(|word| normalize(word.trim())).filter(|word| !word.is_empty()).collect();
+    }
+
+    pub fn set_action(&self, action: ProfanityAction) {
+        *self.action.write() = action;
+    }
+
+    pub fn screen(&self, text: &str) -> Screened {
+        let listed: Vec<(usize, usize)> = {
+            let words = self.words.read();
+            tokens(text).filter(|(start, end)| words.contains(&normalize(&text[*start..*end]))).collect()
+        };
+        if listed.is_empty() {
+            return Screened::Allowed(text.to_string());
+        }
+
+        match self.action() {
+            ProfanityAction::Reject => Screened::Rejected,
+            ProfanityAction::ShadowMute => Screened::ShadowMuted(text.to_string()),
+            ProfanityAction::Mask => {
+                let mut masked = String::with_capacity(text.len());
+                let mut last = 0;
+                for (start, end) in listed {
+                    masked.push_str(&text[last..start]);
+                    masked.extend(std::iter::repeat_n('*', text[start..end].chars().count()));
+                    last = end;
+                }
+                masked.push_str(&text[last..]);
+                Screened::Allowed(masked)
+            }
+        }
+    }
+}
+
+impl Default for ProfanityFilter {
+    fn default() -> Self {
+        Self {
+            words: RwLock::new(BTreeSet::new()),
+            action: RwLock::new(ProfanityAction::default()),
+        }
+    }
+}
+
+fn unleet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        c => c,
+    }
+}
+
+fn normalize(word: &str) -> String {
+    word.chars().map(unleet).flat_map(char::to_lowercase).collect()
+}
+
+// Byte ranges of the words in `text`, substituted letters included
+fn tokens(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while chars.next_if(|(_, c)| !unleet(*c).is_alphanumeric()).is_some() {}
+        let (start, _) = *chars.peek()?;
+        let mut end = start;
+        while let Some((i, c)) = chars.next_if(|(_, c)| unleet(*c).is_alphanumeric()) {
+            end = i + c.len_utf8();
+        }
+        Some((start, end))
+    })
+}
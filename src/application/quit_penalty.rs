@@ -0,0 +1,156 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::config::QuitPenaltyConfig;
+
+// Games remembered per player; older ones stop counting even inside the window
+const MAX_TRACKED_GAMES: usize = 50;
+
+/// Why FindMatch was refused: the player left too many recent games early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuitCooldown {
+    pub remaining: Duration,
+}
+
+impl QuitCooldown {
+    /// Remaining cooldown in whole seconds, rounded up, for the `quit_cooldown` message.
+    pub fn seconds(&self) -> u64 {
+        self.remaining.as_millis().div_ceil(1000) as u64
+    }
+}
+
+impl std::fmt::Display for QuitCooldown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Matchmaking cooldown for {}s after leaving games early", self.seconds())
+    }
+}
+
+impl std::error::Error for QuitCooldown {}
+
+/// A player's standing with the quit penalty, as shown in their stats.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QuitPenaltyStatus {
+    pub recent_games: u32, // Started within the window
+    pub recent_quits: u32,
+    pub quit_rate: f64,
+    pub penalized: bool, // Over the allowed rate; each further quit starts a cooldown
+    pub cooldown_remaining_ms: Option<u64>,
+}
+
+#[derive(Default)]
+struct QuitRecord {
+    games: VecDeque<(Instant, bool)>, // Start time, and whether the player left it early; oldest first
+    cooldown_until: Option<Instant>,
+}
+
+/// Tracks how often each player leaves games before they end, by disconnecting past the
+/// reconnect grace or leaving outright, over a sliding window. A player above the allowed
+/// rate is kept out of matchmaking for a short cooldown after every further quit; the
+/// penalty lapses as old games leave the window.
+pub struct QuitPenalties {
+    config: QuitPenaltyConfig,
+    records: Mutex<HashMap<String, QuitRecord>>,
+}
+
+impl QuitPenalties {
+    pub fn new(config: QuitPenaltyConfig) -> Self {
+        Self {
+            config,
+            records: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_millis(self.config.window_ms)
+    }
+
+    fn forget_old(&self, record: &mut QuitRecord, now: Instant) {
+        let window = self.window();
+        while record.games.front().is_some_and(|(at, _)| now.saturating_duration_since(*at) >= window) {
+            record.games.pop_front();
+        }
+    }
+
+    pub fn game_started(&self, player_id: &str, now: Instant) {
+        if !self.config.enabled {
+            return;
+        }
+        let mut records = self.records.lock();
+        let record = records.entry(player_id.to_string()).or_default();
+        self.forget_old(record, now);
+        if record.games.len() == MAX_TRACKED_GAMES {
+            record.games.pop_front();
+        }
+        record.games.push_back((now, false));
+    }
+
+    /// Marks the player's latest game as left early, starting a cooldown if that puts them
+    /// over the allowed rate.
+    pub fn player_quit(&self, player_id: &str, now: Instant) {
+        if !self.config.enabled {
+            return;
+        }
+        let mut records = self.records.lock();
+        let Some(record) = records.get_mut(player_id) else {
+            return;
+        };
+        self.forget_old(record, now);
+        let Some((_, quit)) = record.games.back_mut() else {
+            return;
+        };
+        *quit = true;
+
+        if self.over_rate(record) {
+            let cooldown = Duration::from_millis(self.config.cooldown_ms);
+            record.cooldown_until = Some(now + cooldown);
+            info!("Player {} left too many games early; matchmaking cooldown of {:?}", player_id, cooldown);
+        }
+    }
+
+    fn over_rate(&self, record: &QuitRecord) -> bool {
+        let games = record.games.len();
+        let quits = record.games.iter().filter(|(_, quit)| *quit).count();
+        games >= self.config.min_games && quits as f64 / games as f64 > self.config.max_quit_rate
+    }
+
+    /// Refuses matchmaking while the player's cooldown runs.
+    pub fn check(&self, player_id: &str, now: Instant) -> Result<(), QuitCooldown> {
+        let records = self.records.lock();
+        match records.get(player_id).and_then(|record| record.cooldown_until) {
+            Some(until) if until > now => Err(QuitCooldown { remaining: until - now }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn status(&self, player_id: &str, now: Instant) -> QuitPenaltyStatus {
+        let mut records = self.records.lock();
+        let Some(record) = records.get_mut(player_id) else {
+            return QuitPenaltyStatus::default();
+        };
+        self.forget_old(record, now);
+        let recent_games = record.games.len() as u32;
+        let recent_quits = record.games.iter().filter(|(_, quit)| *quit).count() as u32;
+        QuitPenaltyStatus {
+            recent_games,
+            recent_quits,
+            quit_rate: if recent_games == 0 { 0.0 } else { recent_quits as f64 / recent_games as f64 },
+            penalized: self.over_rate(record),
+            cooldown_remaining_ms: record.cooldown_until.filter(|until| *until > now).map(|until| (until - now).as_millis() as u64),
+        }
+    }
+
+    /// Drops players with no game in the window and no cooldown left; run periodically.
+    pub fn prune(&self, now: Instant) -> usize {
+        let mut records = self.records.lock();
+        let before = records.len();
+        records.retain(|_, record| {
+            self.forget_old(record, now);
+            !record.games.is_empty() || record.cooldown_until.is_some_and(|until| until > now)
+        });
+        before - records.len()
+    }
+}
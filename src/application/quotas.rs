@@ -0,0 +1,255 @@
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::config::{QuotaLimits, QuotasConfig};
+use crate::domain::GameMode;
+
+const GAMES_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Who a quota belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaScope {
+    Tenant(String),
+    Mode(GameMode),
+}
+
+impl fmt::Display for QuotaScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaScope::Tenant(tenant) => write!(f, "tenant {}", tenant),
+            QuotaScope::Mode(mode) => write!(f, "mode {:?}", mode),
+        }
+    }
+}
+
+/// Why FindMatch was refused: a tenant or game mode used up its share of the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaExceeded {
+    Rooms { scope: QuotaScope, limit: usize },
+    QueuedPlayers { scope: QuotaScope, limit: usize },
+    GamesPerHour { limit: u32, retry_after: Duration },
+}
+
+impl QuotaExceeded {
+    /// Which limit was hit, for the `quota_exceeded` message.
+    pub fn quota(&self) -> &'static str {
+        match self {
+            QuotaExceeded::Rooms { .. } => "rooms",
+            QuotaExceeded::QueuedPlayers { .. } => "queued_players",
+            QuotaExceeded::GamesPerHour { .. } => "games_per_hour",
+        }
+    }
+
+    pub fn limit(&self) -> u64 {
+        match self {
+            QuotaExceeded::Rooms { limit, .. } | QuotaExceeded::QueuedPlayers { limit, .. } => *limit as u64,
+            QuotaExceeded::GamesPerHour { limit, .. } => *limit as u64,
+        }
+    }
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaExceeded::Rooms { scope, limit } => write!(f, "{} is at its limit of {} rooms", scope, limit),
+            QuotaExceeded::QueuedPlayers { scope, limit } => write!(f, "{} is at its limit of {} queued players", scope, limit),
+            QuotaExceeded::GamesPerHour { limit, retry_after } => {
+                write!(f, "Played {} games in the last hour; next one in {}s", limit, retry_after.as_secs())
+            }
+        }
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// The tenant and mode a room or queue entry counts against.
+#[derive(Debug, Clone)]
+pub struct QuotaUsage {
+    pub mode: GameMode,
+    pub tenants: Vec<String>, // A room of players from several tenants counts against each
+}
+
+/// Per-tenant and per-mode limits on rooms, queued players, and each player's games per
+/// hour, so one community can't take the whole server. Only rooms and queue entries that
+/// were counted while limits were configured are tracked.
+pub struct Quotas {
+    config: QuotasConfig,
+    rooms: Mutex<HashMap<String, QuotaUsage>>,   // roomId -> usage
+    queued: Mutex<HashMap<String, QuotaUsage>>,  // playerId -> usage
+    games: Mutex<GameStarts>,
+}
+
+#[derive(Default)]
+struct GameStarts {
+    by_player: HashMap<String, VecDeque<Instant>>, // Starts within the last hour, oldest first
+    swept_len: usize,                              // Size after the last sweep of idle players
+}
+
+impl Quotas {
+    pub fn new(config: QuotasConfig) -> Self {
+        Self {
+            config,
+            rooms: Mutex::new(HashMap::new()),
+            queued: Mutex::new(HashMap::new()),
+            games: Mutex::new(GameStarts::default()),
+        }
+    }
+
+    /// Whether any limit is configured; nothing is tracked otherwise.
+    pub fn is_enabled(&self) -> bool {
+        !self.config.tenants.is_empty() || !self.config.modes.is_empty()
+    }
+
+    fn limits<'a>(&'a self, tenant: Option<&'a str>, mode: GameMode) -> impl Iterator<Item = (QuotaScope, &'a QuotaLimits)> + 'a {
+        let tenant = tenant.and_then(|tenant| self.config.tenants.get(tenant).map(|limits| (QuotaScope::Tenant(tenant.to_string()), limits)));
+        let mode = self.config.modes.get(&mode).map(|limits| (QuotaScope::Mode(mode), limits));
+        tenant.into_iter().chain(mode)
+    }
+
+    /// Refuses a player from `tenant` joining `mode` when that would go over a limit.
+    pub fn check(&self, player_id: &str, tenant: Option<&str>, mode: GameMode, now: Instant) -> Result<(), QuotaExceeded> {
+        for (scope, limits) in self.limits(tenant, mode) {
+            if let Some(limit) = limits.max_rooms {
+                if Self::count(&self.rooms.lock(), &scope) >= limit {
+                    return Err(QuotaExceeded::Rooms { scope, limit });
+                }
+            }
+            if let Some(limit) = limits.max_queued_players {
+                if Self::count(&self.queued.lock(), &scope) >= limit {
+                    return Err(QuotaExceeded::QueuedPlayers { scope, limit });
+                }
+            }
+        }
+
+        let limit = self.limits(tenant, mode).filter_map(|(_, limits)| limits.max_games_per_player_per_hour).min();
+        if let Some(limit) = limit {
+            let mut games = self.games.lock();
+            if let Some(starts) = games.by_player.get_mut(player_id) {
+                starts.retain(|at| now.saturating_duration_since(*at) < GAMES_WINDOW);
+                if starts.len() >= limit as usize {
+                    let retry_after = GAMES_WINDOW.saturating_sub(now.saturating_duration_since(starts[0]));
+                    return Err(QuotaExceeded::GamesPerHour { limit, retry_after });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn count(usage: &HashMap<String, QuotaUsage>, scope: &QuotaScope) -> usize {
+        usage
+            .values()
+            .filter(|usage| match scope {
+                QuotaScope::Tenant(tenant) => usage.tenants.contains(tenant),
+                QuotaScope::Mode(mode) => usage.mode == *mode,
+            })
+            .count()
+    }
+
+    pub fn room_opened(&self, room_id: &str, usage: QuotaUsage) {
+        self.rooms.lock().insert(room_id.to_string(), usage);
+    }
+
+    pub fn room_closed(&self, room_id: &str) {
+        self.rooms.lock().remove(room_id);
+    }
+
+    pub fn player_queued(&self, player_id: &str, usage: QuotaUsage) {
+        self.queued.lock().insert(player_id.to_string(), usage);
+    }
+
+    pub fn player_unqueued(&self, player_id: &str) {
+        self.queued.lock().remove(player_id);
+    }
+
+    /// Counts a game start towards each player's hourly limit.
+    pub fn game_started<'a>(&self, player_ids: impl IntoIterator<Item = &'a str>, now: Instant) {
+        let mut games = self.games.lock();
+        for player_id in player_ids {
+            games.by_player.entry(player_id.to_string()).or_default().push_back(now);
+        }
+        // Players who stopped playing are dropped once the map has doubled since the last sweep
+        if games.by_player.len() > (games.swept_len * 2).max(1024) {
+            games.by_player.retain(|_, starts| starts.back().is_some_and(|at| now.saturating_duration_since(*at) < GAMES_WINDOW));
+            games.swept_len = games.by_player.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::application::GameManager;
+    use crate::domain::{GameConfig, GameMode, Player, ServerMessage};
+
+    #[tokio::test]
+    async fn test_quotas_hold_each_tenant_and_mode_to_its_share() {
+        use crate::application::{QuotaExceeded, QuotaScope, Quotas};
+        use crate::config::{QuotaLimits, QuotasConfig};
+        use crate::domain::{Extensions, Tenant};
+        use std::collections::HashMap;
+        use std::time::{Duration, Instant};
+
+        let limits = |max_rooms, max_queued_players, max_games_per_player_per_hour| QuotaLimits {
+            max_rooms,
+            max_queued_players,
+            max_games_per_player_per_hour,
+        };
+        let config = QuotasConfig {
+            tenants: HashMap::from([("acme".to_string(), limits(Some(1), None, None))]),
+            modes: HashMap::from([(GameMode::Teams, limits(None, Some(1), None)), (GameMode::Solo, limits(None, None, Some(2)))]),
+        };
+        let manager = GameManager::new(GameConfig::default()).with_quotas(config.clone());
+        let mut receivers = Vec::new();
+        let mut player = |id: &str, tenant: Option<&str>| {
+            let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+            receivers.push(rx);
+            let mut extensions = Extensions::new();
+            if let Some(tenant) = tenant {
+                extensions.insert(Tenant(tenant.to_string()));
+            }
+            Arc::new(Player::new(id.to_string(), tx).with_extensions(extensions))
+        };
+
+        manager.find_match(player("a1", Some("acme"))).await.unwrap();
+        let ServerMessage::Matchmaking { room_id: Some(room_id), .. } = manager.find_match(player("a2", Some("acme"))).await.unwrap() else {
+            panic!("expected a match");
+        };
+        let refused = manager.find_match(player("a3", Some("acme"))).await.unwrap_err();
+        assert_eq!(
+            refused.downcast_ref::<QuotaExceeded>(),
+            Some(&QuotaExceeded::Rooms { scope: QuotaScope::Tenant("acme".to_string()), limit: 1 })
+        );
+        // Other communities aren't held to acme's share
+        let waiting = manager.find_match(player("b1", None)).await.unwrap();
+        assert!(matches!(waiting, ServerMessage::Matchmaking { matched: false, .. }));
+
+        manager.find_match_in_mode(player("t1", None), GameMode::Teams).await.unwrap();
+        let refused = manager.find_match_in_mode(player("t2", None), GameMode::Teams).await.unwrap_err();
+        let refused = refused.downcast_ref::<QuotaExceeded>().unwrap();
+        assert_eq!((refused.quota(), refused.limit()), ("queued_players", 1));
+        manager.remove_player("t1").await.unwrap();
+        assert!(manager.find_match_in_mode(player("t2", None), GameMode::Teams).await.is_ok());
+
+        // Once acme's room closes, a3 gets in and is paired with b1
+        assert!(manager.close_room(&room_id, "test").await.unwrap());
+        let matched = manager.find_match(player("a3", Some("acme"))).await.unwrap();
+        assert!(matches!(matched, ServerMessage::Matchmaking { matched: true, .. }));
+
+        // Two solo games an hour, whatever the tenant; bot games aren't limited
+        let quotas = Quotas::new(config);
+        let start = Instant::now();
+        let minutes = |n: u64| start + Duration::from_secs(60 * n);
+        quotas.game_started(["p1"], start);
+        quotas.game_started(["p1"], minutes(10));
+        assert!(quotas.check("p2", None, GameMode::Solo, minutes(20)).is_ok());
+        assert_eq!(
+            quotas.check("p1", Some("acme"), GameMode::Solo, minutes(20)),
+            Err(QuotaExceeded::GamesPerHour { limit: 2, retry_after: Duration::from_secs(40 * 60) })
+        );
+        assert!(quotas.check("p1", None, GameMode::Bot, minutes(20)).is_ok());
+        assert!(quotas.check("p1", None, GameMode::Solo, minutes(60)).is_ok());
+    }
+}
@@ -0,0 +1,54 @@
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// Allows `max` events per key in any sliding `window`. The spam guard counts player
+/// requests with it and the REST API counts client requests with it.
+pub struct RateLimiter<K> {
+    max: usize,
+    window: Duration,
+    recent: Mutex<HashMap<K, VecDeque<Instant>>>,
+}
+
+impl<K: Hash + Eq> RateLimiter<K> {
+    pub fn new(max: usize, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            recent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts one event for `key`, or refuses it with how long until the oldest event in
+    /// the window expires and another is allowed. Refused events aren't counted.
+    pub fn check(&self, key: K) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    pub fn check_at(&self, key: K, now: Instant) -> Result<(), Duration> {
+        let mut recent = self.recent.lock();
+        let events = recent.entry(key).or_default();
+        while events.front().is_some_and(|at| now.saturating_duration_since(*at) >= self.window) {
+            events.pop_front();
+        }
+        if events.len() < self.max {
+            events.push_back(now);
+            return Ok(());
+        }
+        Err(events.front().map_or(self.window, |oldest| self.window.saturating_sub(now.saturating_duration_since(*oldest))))
+    }
+
+    /// Forgets the events counted for `key`.
+    pub fn reset(&self, key: &K) {
+        self.recent.lock().remove(key);
+    }
+
+    /// Forgets keys with no events left in the window, returning how many.
+    pub fn prune(&self) -> usize {
+        let mut recent = self.recent.lock();
+        let before = recent.len();
+        recent.retain(|_, events| events.back().is_some_and(|at| at.elapsed() < self.window));
+        before - recent.len()
+    }
+}
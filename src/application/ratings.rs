@@ -0,0 +1,352 @@
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use super::event_bus::EventBus;
+use crate::config::RatingsConfig;
+use crate::domain::{GameEndReason, GameEvent};
+
+pub const DEFAULT_RATING: f64 = 1200.0;
+
+/// A finished game as handed to rating providers.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RatedGame {
+    pub game_id: String,
+    pub room_id: String,
+    pub players: Vec<String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub teams: HashMap<String, String>, // playerId -> teamId; `winner` is a team id when set
+    pub winner: Option<String>,         // None on a tie
+    pub reason: GameEndReason,
+}
+
+impl RatedGame {
+    pub fn from_event(event: &GameEvent) -> Option<Self> {
+        let GameEvent::GameEnded { room_id, game_id, winner, final_scores, reason, teams, .. } = event else {
+            return None;
+        };
+        let mut players: Vec<String> = if teams.is_empty() {
+            final_scores.keys().cloned().collect()
+        } else {
+            teams.keys().cloned().collect()
+        };
+        players.sort();
+
+        Some(Self {
+            game_id: game_id.clone(),
+            room_id: room_id.clone(),
+            players,
+            teams: teams.clone(),
+            winner: winner.clone(),
+            reason: reason.clone(),
+        })
+    }
+
+    /// Players grouped by the side they scored for: one per player, or one per team.
+    pub fn sides(&self) -> BTreeMap<String, Vec<String>> {
+        let mut sides: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for player_id in &self.players {
+            let side = self.teams.get(player_id).unwrap_or(player_id);
+            sides.entry(side.clone()).or_default().push(player_id.clone());
+        }
+        sides
+    }
+}
+
+/// Receives every finished game. Implement it to feed an external ranking service
+/// in place of the built-in [`EloRatings`]. An error makes [`RatingRecorder`] retry the
+/// same game later, so implementations should be idempotent per `game_id`.
+pub trait RatingProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn record_game<'a>(&'a self, game: &'a RatedGame) -> BoxFuture<'a, Result<()>>;
+}
+
+/// One row of a leaderboard, best first.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Standing {
+    pub rank: usize,
+    pub player_id: String,
+    pub rating: f64,
+    pub games: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PlayerRating {
+    rating: f64,
+    games: u32,
+}
+
+impl Default for PlayerRating {
+    fn default() -> Self {
+        Self { rating: DEFAULT_RATING, games: 0 }
+    }
+}
+
+/// Built-in two-sided ELO. Team members share their team's average rating as the
+/// baseline and all move by the team's delta.
+pub struct EloRatings {
+    k_factor: f64,
+    ratings: RwLock<HashMap<String, PlayerRating>>,
+}
+
+impl EloRatings {
+    pub fn new(k_factor: f64) -> Self {
+        Self {
+            k_factor,
+            ratings: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn rating(&self, player_id: &str) -> f64 {
+        self.ratings.read().get(player_id).map_or(DEFAULT_RATING, |entry| entry.rating)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ratings.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ratings.read().is_empty()
+    }
+
+    /// Rated players ordered by rating; ties go to whoever played more games, then by id.
+    pub fn leaderboard(&self, limit: usize) -> Vec<Standing> {
+        rank(self.ratings.read().iter(), limit)
+    }
+
+    /// Standings of several players from one pass over the table, in the order asked;
+    /// `None` for a player who has no rated game yet.
+    pub fn standings_of(&self, player_ids: &[String]) -> Vec<Option<Standing>> {
+        let mut standings: HashMap<String, Standing> =
+            rank(self.ratings.read().iter(), usize::MAX).into_iter().map(|standing| (standing.player_id.clone(), standing)).collect();
+        player_ids.iter().map(|player_id| standings.remove(player_id)).collect()
+    }
+
+    /// Drops the player from the table and so from the leaderboard; false if they had no rating.
+    pub fn remove(&self, player_id: &str) -> bool {
+        self.ratings.write().remove(player_id).is_some()
+    }
+
+    /// Clears every rating, e.g. when a season ends, and returns the final full leaderboard.
+    pub fn reset(&self) -> Vec<Standing> {
+        let ratings = std::mem::take(&mut *self.ratings.write());
+        rank(ratings.iter(), usize::MAX)
+    }
+
+    pub fn apply(&self, game: &RatedGame) {
+        let sides = self.sides_with_ratings(game);
+        let [(side_a, members_a, rating_a), (side_b, members_b, rating_b)] = match <[_; 2]>::try_from(sides) {
+            Ok(sides) => sides,
+            Err(_) => return, // Only head-to-head games are rated
+        };
+
+        let expected_a = 1.0 / (1.0 + 10f64.powf((rating_b - rating_a) / 400.0));
+        let score_a = match &game.winner {
+            Some(winner) if *winner == side_a => 1.0,
+            Some(winner) if *winner == side_b => 0.0,
+            _ => 0.5,
+        };
+        let delta = self.k_factor * (score_a - expected_a);
+
+        let mut ratings = self.ratings.write();
+        for (members, delta) in [(members_a, delta), (members_b, -delta)] {
+            for player_id in members {
+                let entry = ratings.entry(player_id).or_default();
+                entry.rating += delta;
+                entry.games += 1;
+            }
+        }
+    }
+
+    fn sides_with_ratings(&self, game: &RatedGame) -> Vec<(String, Vec<String>, f64)> {
+        game.sides()
+            .into_iter()
+            .map(|(side, members)| {
+                let average = members.iter().map(|p| self.rating(p)).sum::<f64>() / members.len() as f64;
+                (side, members, average)
+            })
+            .collect()
+    }
+}
+
+fn rank<'a>(ratings: impl Iterator<Item = (&'a String, &'a PlayerRating)>, limit: usize) -> Vec<Standing> {
+    let mut rows: Vec<_> = ratings.collect();
+    rows.sort_by(|a, b| b.1.rating.total_cmp(&a.1.rating).then(b.1.games.cmp(&a.1.games)).then(a.0.cmp(b.0)));
+    rows.into_iter()
+        .take(limit)
+        .enumerate()
+        .map(|(index, (player_id, entry))| Standing {
+            rank: index + 1,
+            player_id: player_id.clone(),
+            rating: entry.rating,
+            games: entry.games,
+        })
+        .collect()
+}
+
+impl RatingProvider for EloRatings {
+    fn name(&self) -> &str {
+        "elo"
+    }
+
+    fn record_game<'a>(&'a self, game: &'a RatedGame) -> BoxFuture<'a, Result<()>> {
+        self.apply(game);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Hands finished games to a [`RatingProvider`] in the order they ended. While the
+/// provider fails, games wait in a bounded queue and the head is retried with
+/// exponential backoff; past `max_pending` the oldest waiting game is dropped.
+pub struct RatingRecorder {
+    provider: Arc<dyn RatingProvider>,
+    max_pending: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RatingRecorder {
+    pub fn new(provider: Arc<dyn RatingProvider>, config: &RatingsConfig) -> Self {
+        Self {
+            provider,
+            max_pending: config.max_pending.max(1),
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms.max(config.initial_backoff_ms)),
+        }
+    }
+
+    /// Records every finished game until the bus is dropped and the queue is empty.
+    pub fn spawn(self, events: &EventBus) -> JoinHandle<()> {
+        let mut receiver = events.subscribe();
+
+        tokio::spawn(async move {
+            let mut pending: VecDeque<RatedGame> = VecDeque::new();
+            let mut backoff = self.initial_backoff;
+            let mut retry_at = Instant::now();
+            let mut closed = false;
+
+            loop {
+                if closed && pending.is_empty() {
+                    break;
+                }
+
+                tokio::select! {
+                    received = receiver.recv(), if !closed => match received {
+                        Ok(envelope) => {
+                            if let Some(game) = RatedGame::from_event(&envelope.event) {
+                                self.enqueue(&mut pending, game);
+                            }
+                        }
+                        Err(RecvError::Lagged(skipped)) => warn!("Rating recorder lagged; {} events dropped", skipped),
+                        Err(RecvError::Closed) => closed = true,
+                    },
+                    _ = tokio::time::sleep_until(retry_at), if !pending.is_empty() => {
+                        let game = &pending[0];
+                        match self.provider.record_game(game).await {
+                            Ok(()) => {
+                                info!("Rated game {} with {}", game.game_id, self.provider.name());
+                                pending.pop_front();
+                                backoff = self.initial_backoff;
+                            }
+                            Err(e) => {
+                                warn!(
+                                    "Rating provider {} failed on game {} ({} queued): {}; retrying in {:?}",
+                                    self.provider.name(),
+                                    game.game_id,
+                                    pending.len(),
+                                    e,
+                                    backoff
+                                );
+                                retry_at = Instant::now() + backoff;
+                                backoff = (backoff * 2).min(self.max_backoff);
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn enqueue(&self, pending: &mut VecDeque<RatedGame>, game: RatedGame) {
+        if pending.len() >= self.max_pending {
+            if let Some(dropped) = pending.pop_front() {
+                warn!("Rating queue full; dropping game {} unrated", dropped.game_id);
+            }
+        }
+        pending.push_back(game);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::application::{EloRatings, EventBus, RatedGame, RatingProvider, RatingRecorder};
+    use crate::config::RatingsConfig;
+    use crate::domain::{GameEndReason, GameEvent};
+
+    #[tokio::test]
+    async fn test_rating_provider_retries_queued_games_in_order() {
+        struct FlakyProvider {
+            failures_left: std::sync::atomic::AtomicUsize,
+            recorded: parking_lot::Mutex<Vec<String>>,
+            elo: EloRatings,
+        }
+
+        impl RatingProvider for FlakyProvider {
+            fn name(&self) -> &str {
+                "flaky"
+            }
+
+            fn record_game<'a>(&'a self, game: &'a RatedGame) -> futures_util::future::BoxFuture<'a, anyhow::Result<()>> {
+                Box::pin(async move {
+                    if self.failures_left.fetch_update(std::sync::atomic::Ordering::SeqCst, std::sync::atomic::Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+                        anyhow::bail!("rating service unavailable");
+                    }
+                    self.recorded.lock().push(game.game_id.clone());
+                    self.elo.record_game(game).await
+                })
+            }
+        }
+
+        let provider = Arc::new(FlakyProvider {
+            failures_left: std::sync::atomic::AtomicUsize::new(2),
+            recorded: parking_lot::Mutex::new(Vec::new()),
+            elo: EloRatings::new(32.0),
+        });
+        let config = RatingsConfig { initial_backoff_ms: 10, max_backoff_ms: 20, ..RatingsConfig::default() };
+        let bus = EventBus::new();
+        let recorder = RatingRecorder::new(provider.clone(), &config).spawn(&bus);
+
+        for (game_id, winner) in [("g1", Some("p1")), ("g2", None)] {
+            bus.publish(GameEvent::GameEnded {
+                room_id: "r".to_string(),
+                game_id: game_id.to_string(),
+                winner: winner.map(str::to_string),
+                final_scores: [("p1".to_string(), 2), ("p2".to_string(), 0)].into_iter().collect(),
+                reason: GameEndReason::Completed,
+                teams: Default::default(),
+                signature: None,
+            });
+        }
+        drop(bus);
+        tokio::time::timeout(std::time::Duration::from_secs(5), recorder).await.unwrap().unwrap();
+
+        assert_eq!(*provider.recorded.lock(), ["g1", "g2"]);
+        // The win moves 16 points; the draw then pulls the favourite back a little
+        let (p1, p2) = (provider.elo.rating("p1"), provider.elo.rating("p2"));
+        assert!(p1 > 1200.0 && p1 < 1216.0, "{}", p1);
+        assert!((p1 + p2 - 2400.0).abs() < 1e-9);
+    }
+}
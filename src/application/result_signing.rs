@@ -0,0 +1,174 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::config::{Secret, SecretStore};
+use crate::domain::{GameChoice, GameEndReason, ResultSignature};
+
+pub const RESULT_SIGNATURE_ALGORITHM: &str = "ed25519";
+
+/// What a result signature covers. Maps are ordered so the payload is stable.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SignedResult {
+    #[serde(rename_all = "camelCase")]
+    RoundResult {
+        game_id: String,
+        room_id: String,
+        round: u32,
+        winner: Option<String>,
+        moves: BTreeMap<String, GameChoice>,
+        scores: BTreeMap<String, u32>,
+        signed_at: DateTime<Utc>,
+    },
+    #[serde(rename_all = "camelCase")]
+    GameEnd {
+        game_id: String,
+        room_id: String,
+        winner: Option<String>,
+        final_scores: BTreeMap<String, u32>,
+        reason: GameEndReason,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        teams: BTreeMap<String, String>,
+        signed_at: DateTime<Utc>,
+    },
+}
+
+/// A published verification key.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultPublicKey {
+    pub key_id: String,
+    pub algorithm: &'static str,
+    pub public_key: String, // Hex, raw 32-byte Ed25519 key
+}
+
+/// Signs round and game results with the active result signing key from the secret store.
+/// Keys are read on every use, so a secrets reload rotates them without a restart.
+pub struct ResultSigner {
+    secrets: Arc<SecretStore>,
+}
+
+impl ResultSigner {
+    /// Returns `None` when no result signing key is configured; fails on malformed keys.
+    pub fn new(secrets: Arc<SecretStore>) -> Result<Option<Self>> {
+        let keys = secrets.result_signing_keys();
+        if keys.is_empty() {
+            return Ok(None);
+        }
+        for key in &keys {
+            key_pair(key)?;
+        }
+        Ok(Some(Self { secrets }))
+    }
+
+    pub fn sign(&self, result: &SignedResult) -> Option<ResultSignature> {
+        let key = self.secrets.result_signing_keys().into_iter().next()?;
+        let signed = serde_json::to_string(result).context("Failed to serialize result").and_then(|payload| {
+            let signature = key_pair(&key)?.sign(payload.as_bytes());
+            Ok(ResultSignature {
+                key_id: key.id().to_string(),
+                algorithm: RESULT_SIGNATURE_ALGORITHM.to_string(),
+                payload,
+                signature: hex::encode(signature.as_ref()),
+            })
+        });
+        match signed {
+            Ok(signature) => Some(signature),
+            Err(e) => {
+                warn!("Failed to sign result with key {}: {}", key.id(), e);
+                None
+            }
+        }
+    }
+
+    pub fn public_keys(&self) -> Vec<ResultPublicKey> {
+        self.secrets
+            .result_signing_keys()
+            .iter()
+            .filter_map(|key| {
+                let key_pair = key_pair(key).ok()?;
+                Some(ResultPublicKey {
+                    key_id: key.id().to_string(),
+                    algorithm: RESULT_SIGNATURE_ALGORITHM,
+                    public_key: hex::encode(key_pair.public_key().as_ref()),
+                })
+            })
+            .collect()
+    }
+}
+
+fn key_pair(key: &Secret) -> Result<Ed25519KeyPair> {
+    let seed = hex::decode(key.expose()).with_context(|| format!("Result signing key {} is not hex", key.id()))?;
+    if seed.len() != 32 {
+        bail!("Result signing key {} must be a 32-byte Ed25519 seed", key.id());
+    }
+    Ed25519KeyPair::from_seed_unchecked(&seed).map_err(|_| anyhow::anyhow!("Result signing key {} is invalid", key.id()))
+}
+
+pub(crate) fn ordered<V: Clone>(map: &HashMap<String, V>) -> BTreeMap<String, V> {
+    map.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::application::{GameManager, ResultSigner};
+    use crate::config::{Secret, SecretStore};
+    use crate::domain::{GameChoice, GameConfig, Player, ServerMessage};
+    use crate::infrastructure::create_result_key_routes;
+
+    #[tokio::test]
+    async fn test_results_are_signed_and_verifiable_with_published_key() {
+        let bad = SecretStore::from_keys(Vec::new(), Vec::new()).with_result_signing_keys(vec![Secret::new("bad", "not-hex")]);
+        assert!(ResultSigner::new(Arc::new(bad)).is_err());
+        let unsigned = Arc::new(SecretStore::from_keys(Vec::new(), Vec::new()));
+        assert!(ResultSigner::new(unsigned).unwrap().is_none());
+
+        let secrets = SecretStore::from_keys(Vec::new(), Vec::new())
+            .with_result_signing_keys(vec![Secret::new("results-2026", "07".repeat(32)), Secret::new("results-2025", "08".repeat(32))]);
+        let signer = Arc::new(ResultSigner::new(Arc::new(secrets)).unwrap().unwrap());
+        let manager = GameManager::new(GameConfig::default()).with_result_signer(signer.clone());
+        let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+        let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+        manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+        manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+        for _ in 0..2 {
+            manager.submit_move("p1", GameChoice::Paper).await.unwrap();
+            manager.submit_move("p2", GameChoice::Rock).await.unwrap();
+        }
+        let signatures: Vec<_> = std::iter::from_fn(|| rx1.try_recv().ok())
+            .filter_map(|message| match message {
+                ServerMessage::RoundResult { signature, .. } | ServerMessage::GameEnd { signature, .. } => signature,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(signatures.len(), 3);
+
+        let routes = create_result_key_routes(Some(signer));
+        let body: serde_json::Value =
+            serde_json::from_slice(warp::test::request().path("/results/keys").reply(&routes).await.body()).unwrap();
+        assert_eq!(body["keys"].as_array().unwrap().len(), 2);
+        let key = &body["keys"][0];
+        assert_eq!(key["keyId"], "results-2026");
+        let public_key = hex::decode(key["publicKey"].as_str().unwrap()).unwrap();
+        let verifier = ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key);
+
+        for signature in &signatures {
+            assert_eq!(signature.key_id, "results-2026");
+            let bytes = hex::decode(&signature.signature).unwrap();
+            assert!(verifier.verify(signature.payload.as_bytes(), &bytes).is_ok());
+            let tampered = signature.payload.replace("\"p1\"", "\"p3\"");
+            assert!(verifier.verify(tampered.as_bytes(), &bytes).is_err());
+        }
+        let game_end: serde_json::Value = serde_json::from_str(&signatures[2].payload).unwrap();
+        assert_eq!(game_end["type"], "gameEnd");
+        assert_eq!(game_end["winner"], "p1");
+        assert_eq!(game_end["finalScores"]["p1"], 2);
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::{bail, Result};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::matchmaking_service::{GameManager, ReservedMatch};
+use crate::config::SeriesConfig;
+use crate::domain::{GameEndReason, GameEvent, ServerMessage};
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SeriesStatus {
+    Playing,
+    Finished,
+}
+
+/// A best-of-N run of games between the same two players. The first game is a reserved
+/// match; each later one starts by itself a short while after the previous game ends.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Series {
+    pub id: String,
+    pub players: Vec<String>,
+    pub best_of: u32,
+    pub scores: HashMap<String, u32>, // Games won; drawn games count toward `best_of` only
+    pub games: Vec<String>,           // Finished game ids, in play order
+    pub room_id: String,              // Room of the current, or last, game
+    pub first_match: ReservedMatch,   // How the players enter the first game
+    pub status: SeriesStatus,
+    pub winner: Option<String>,
+    pub reason: Option<GameEndReason>,
+}
+
+impl Series {
+    // A game decides the series once someone holds a majority or no game is left
+    fn decided(&self) -> bool {
+        let leader = self.scores.values().copied().max().unwrap_or(0);
+        leader > self.best_of / 2 || self.games.len() as u32 >= self.best_of
+    }
+
+    fn leader(&self) -> Option<String> {
+        let best = self.scores.values().copied().max()?;
+        let mut leaders = self.scores.iter().filter(|(_, score)| **score == best);
+        match (leaders.next(), leaders.next()) {
+            (Some((player_id, _)), None) => Some(player_id.clone()),
+            _ => None,
+        }
+    }
+}
+
+// What a finished game means for its series
+enum Next {
+    Forfeit,
+    Decided(Option<String>), // Leader, None on a tie
+    Game { best_of: u32, scores: HashMap<String, u32>, next_game: u32 },
+}
+
+/// Tracks series and moves each one on from the `GameEnded` events of its games.
+pub struct SeriesManager {
+    manager: Arc<GameManager>,
+    config: SeriesConfig,
+    series: Mutex<HashMap<String, Series>>,
+    by_room: Mutex<HashMap<String, String>>, // roomId of a game in progress -> seriesId
+    finished: Mutex<VecDeque<String>>,       // Oldest first, for pruning
+}
+
+impl SeriesManager {
+    pub fn new(manager: Arc<GameManager>, config: SeriesConfig) -> Self {
+        Self {
+            manager,
+            config,
+            series: Mutex::new(HashMap::new()),
+            by_room: Mutex::new(HashMap::new()),
+            finished: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Reserves the first game for `players`, who enter it with `JoinRoom`.
+    pub async fn create(&self, players: Vec<String>, best_of: u32) -> Result<Series> {
+        if best_of.is_multiple_of(2) || best_of > self.config.max_best_of {
+            bail!("A series must be an odd number of games up to {}", self.config.max_best_of);
+        }
+        let reserved = self.manager.reserve_match(players).await?;
+        let series = Series {
+            id: Uuid::new_v4().to_string(),
+            players: reserved.players.clone(),
+            best_of,
+            scores: reserved.players.iter().map(|id| (id.clone(), 0)).collect(),
+            games: Vec::new(),
+            room_id: reserved.room_id.clone(),
+            first_match: reserved,
+            status: SeriesStatus::Playing,
+            winner: None,
+            reason: None,
+        };
+
+        self.by_room.lock().insert(series.room_id.clone(), series.id.clone());
+        self.series.lock().insert(series.id.clone(), series.clone());
+        self.manager.stats_counters().series_started();
+        info!("Series {} created: best of {}, {}", series.id, best_of, series.players.join(" vs "));
+        Ok(series)
+    }
+
+    pub fn get(&self, series_id: &str) -> Option<Series> {
+        self.series.lock().get(series_id).cloned()
+    }
+
+    /// Follows game results until the manager's event bus closes.
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        let mut receiver = self.manager.events().subscribe();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(envelope) => {
+                        if let GameEvent::GameEnded { room_id, game_id, winner, reason, .. } = envelope.event {
+                            self.game_ended(&room_id, game_id, winner, reason).await;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => warn!("Series manager lagged; {} events dropped", skipped),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    async fn game_ended(self: &Arc<Self>, room_id: &str, game_id: String, winner: Option<String>, reason: GameEndReason) {
+        let Some(series_id) = self.by_room.lock().remove(room_id) else {
+            return;
+        };
+        let next = {
+            let mut all = self.series.lock();
+            let Some(series) = all.get_mut(&series_id) else {
+                return;
+            };
+            series.games.push(game_id);
+            if let Some(score) = winner.as_ref().and_then(|winner| series.scores.get_mut(winner)) {
+                *score += 1;
+            }
+
+            // A forfeited game forfeits the series with it
+            if reason == GameEndReason::Forfeit {
+                Next::Forfeit
+            } else if series.decided() {
+                Next::Decided(series.leader())
+            } else {
+                Next::Game {
+                    best_of: series.best_of,
+                    scores: series.scores.clone(),
+                    next_game: series.games.len() as u32 + 1,
+                }
+            }
+        };
+
+        match next {
+            Next::Forfeit => self.finish(&series_id, winner, GameEndReason::Forfeit).await,
+            Next::Decided(leader) => self.finish(&series_id, leader, GameEndReason::Completed).await,
+            Next::Game { best_of, scores, next_game } => {
+                let message = ServerMessage::SeriesUpdate {
+                    seq: None,
+                    series_id: series_id.clone(),
+                    best_of,
+                    scores,
+                    next_game,
+                    starts_in_ms: self.config.next_game_delay_ms,
+                };
+                if let Err(e) = self.manager.broadcast_to_room(room_id, &message).await {
+                    warn!("Failed to send series update for {}: {}", series_id, e);
+                }
+                let this = self.clone();
+                let room_id = room_id.to_string();
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_millis(this.config.next_game_delay_ms)).await;
+                    this.start_next_game(&series_id, &room_id).await;
+                });
+            }
+        }
+    }
+
+    async fn start_next_game(&self, series_id: &str, previous_room: &str) {
+        // Registered before the game starts, so even a game that ends at once is followed
+        let register = |room_id: &str| {
+            self.by_room.lock().insert(room_id.to_string(), series_id.to_string());
+            if let Some(series) = self.series.lock().get_mut(series_id) {
+                series.room_id = room_id.to_string();
+            }
+        };
+        match self.manager.start_next_game(previous_room, register).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                info!("Series {} abandoned: a player left between games", series_id);
+                self.finish(series_id, None, GameEndReason::Forfeit).await;
+            }
+            Err(e) => {
+                warn!("Failed to start the next game of series {}: {}", series_id, e);
+                self.finish(series_id, None, GameEndReason::Forfeit).await;
+            }
+        }
+    }
+
+    async fn finish(&self, series_id: &str, winner: Option<String>, reason: GameEndReason) {
+        let series = {
+            let mut all = self.series.lock();
+            let Some(series) = all.get_mut(series_id) else {
+                return;
+            };
+            series.status = SeriesStatus::Finished;
+            series.winner = winner;
+            series.reason = Some(reason.clone());
+            series.clone()
+        };
+        self.prune(series_id);
+        self.manager.stats_counters().series_finished();
+        info!("Series {} finished ({:?}), winner {:?}", series.id, reason, series.winner);
+
+        let message = ServerMessage::SeriesEnd {
+            seq: None,
+            series_id: series.id.clone(),
+            winner: series.winner.clone(),
+            scores: series.scores.clone(),
+            games: series.games.clone(),
+            reason: reason.clone(),
+        };
+        if let Err(e) = self.manager.broadcast_to_room(&series.room_id, &message).await {
+            warn!("Failed to send series end for {}: {}", series.id, e);
+        }
+        self.manager.events().publish(GameEvent::SeriesEnded {
+            series_id: series.id,
+            players: series.players,
+            winner: series.winner,
+            scores: series.scores,
+            games: series.games,
+            reason,
+        });
+    }
+
+    fn prune(&self, series_id: &str) {
+        let mut finished = self.finished.lock();
+        finished.push_back(series_id.to_string());
+        while finished.len() > self.config.max_finished_series {
+            if let Some(oldest) = finished.pop_front() {
+                self.series.lock().remove(&oldest);
+            }
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::ratings::EloRatings;
+
+/// A player waiting in a queue, as seen by a pairing strategy.
+#[derive(Debug, Clone)]
+pub struct QueuedCandidate {
+    pub player_id: String,
+    pub waited: Duration,
+}
+
+/// Decides who a joining player is matched with.
+pub trait PairingStrategy: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Indices into `waiting` (oldest first) of the opponents for `joining`, or `None`
+    /// to queue them instead.
+    fn pair(&self, joining: &str, waiting: &[QueuedCandidate], opponents_needed: usize) -> Option<Vec<usize>>;
+}
+
+/// What the live matchmaker does: the longest-waiting players, first come first served.
+pub struct FifoPairing;
+
+impl PairingStrategy for FifoPairing {
+    fn name(&self) -> &str {
+        "fifo"
+    }
+
+    fn pair(&self, _joining: &str, waiting: &[QueuedCandidate], opponents_needed: usize) -> Option<Vec<usize>> {
+        (waiting.len() >= opponents_needed).then(|| (0..opponents_needed).collect())
+    }
+}
+
+/// Head-to-head only: the closest-rated waiting player within a band that widens the
+/// longer they have waited, and drops away once they're starved. Larger rooms fall back
+/// to first come first served.
+pub struct RatingBandPairing {
+    ratings: Arc<EloRatings>,
+    band: f64,
+    widen_per_sec: f64,
+    starved_after: Option<Duration>,
+}
+
+impl RatingBandPairing {
+    pub fn new(ratings: Arc<EloRatings>, band: f64, widen_per_sec: f64) -> Self {
+        Self { ratings, band, widen_per_sec, starved_after: None }
+    }
+
+    /// Pairs a player who has waited `slo` with anyone, whatever the rating gap.
+    pub fn with_starvation_slo(mut self, slo: Duration) -> Self {
+        self.starved_after = Some(slo);
+        self
+    }
+}
+
+impl PairingStrategy for RatingBandPairing {
+    fn name(&self) -> &str {
+        "ratingBand"
+    }
+
+    fn pair(&self, joining: &str, waiting: &[QueuedCandidate], opponents_needed: usize) -> Option<Vec<usize>> {
+        if opponents_needed != 1 {
+            return FifoPairing.pair(joining, waiting, opponents_needed);
+        }
+
+        let rating = self.ratings.rating(joining);
+        waiting
+            .iter()
+            .enumerate()
+            .map(|(index, candidate)| (index, (self.ratings.rating(&candidate.player_id) - rating).abs(), candidate))
+            .filter(|(_, gap, candidate)| {
+                let starved = self.starved_after.is_some_and(|slo| candidate.waited >= slo);
+                starved || *gap <= self.band + self.widen_per_sec * candidate.waited.as_secs_f64()
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _, _)| vec![index])
+    }
+}
+
+/// How often the shadow strategy agreed with live matchmaking, per join decision.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ShadowReport {
+    pub strategy: String,
+    pub decisions: u64,
+    pub agreed: u64,
+    pub shadow_matched_live_waited: u64,
+    pub live_matched_shadow_waited: u64,
+    pub different_opponents: u64,
+    pub divergence_rate: f64,
+    pub live_matches: u64,
+    pub shadow_matches: u64,
+    pub avg_live_wait_ms: f64,   // Queue time of matched players
+    pub avg_shadow_wait_ms: f64,
+    pub shadow_waiting: usize,   // Still unmatched in the shadow queues
+}
+
+#[derive(Default)]
+struct ShadowState {
+    queues: HashMap<&'static str, Vec<(String, Instant)>>, // Queue name -> hypothetical queue, oldest first
+    live_queued_at: HashMap<String, Instant>,
+    report: ShadowReport,
+    live_wait_total: Duration,
+    live_waits: u64,
+    shadow_wait_total: Duration,
+    shadow_waits: u64,
+}
+
+/// Replays every live queue join through a second strategy against its own hypothetical
+/// queues and compares the outcomes. Nothing it decides reaches a player.
+pub struct ShadowMatchmaker {
+    strategy: Box<dyn PairingStrategy>,
+    state: Mutex<ShadowState>,
+}
+
+impl ShadowMatchmaker {
+    pub fn new(strategy: Box<dyn PairingStrategy>) -> Self {
+        let state = ShadowState {
+            report: ShadowReport {
+                strategy: strategy.name().to_string(),
+                ..ShadowReport::default()
+            },
+            ..ShadowState::default()
+        };
+        Self {
+            strategy,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Records one live join decision: `live_opponents` is who the player was matched with,
+    /// or `None` when they were queued.
+    pub fn observe_join(&self, queue: &'static str, player_id: &str, opponents_needed: usize, live_opponents: Option<&[String]>) {
+        let now = Instant::now();
+        let mut state = self.state.lock();
+        let state = &mut *state;
+
+        // Live side
+        match live_opponents {
+            Some(opponents) => {
+                state.report.live_matches += 1;
+                for opponent in opponents {
+                    if let Some(queued_at) = state.live_queued_at.remove(opponent) {
+                        state.live_wait_total += now - queued_at;
+                        state.live_waits += 1;
+                    }
+                }
+            }
+            None => {
+                state.live_queued_at.insert(player_id.to_string(), now);
+            }
+        }
+
+        // Shadow side; a player already waiting in the shadow queue keeps their place
+        let shadow_queue = state.queues.entry(queue).or_default();
+        let shadow_opponents = if shadow_queue.iter().any(|(id, _)| id == player_id) {
+            None
+        } else {
+            let waiting: Vec<QueuedCandidate> = shadow_queue
+                .iter()
+                .map(|(id, queued_at)| QueuedCandidate { player_id: id.clone(), waited: now - *queued_at })
+                .collect();
+            match self.strategy.pair(player_id, &waiting, opponents_needed) {
+                Some(mut picked) => {
+                    picked.sort_unstable();
+                    picked.dedup();
+                    picked.retain(|&index| index < shadow_queue.len());
+                    let mut opponents = Vec::with_capacity(picked.len());
+                    for index in picked.into_iter().rev() {
+                        let (id, queued_at) = shadow_queue.remove(index);
+                        state.shadow_wait_total += now - queued_at;
+                        state.shadow_waits += 1;
+                        opponents.push(id);
+                    }
+                    state.report.shadow_matches += 1;
+                    Some(opponents)
+                }
+                None => {
+                    shadow_queue.push((player_id.to_string(), now));
+                    None
+                }
+            }
+        };
+
+        let report = &mut state.report;
+        report.decisions += 1;
+        match (live_opponents, shadow_opponents) {
+            (None, None) => report.agreed += 1,
+            (Some(_), None) => report.live_matched_shadow_waited += 1,
+            (None, Some(_)) => report.shadow_matched_live_waited += 1,
+            (Some(live), Some(mut shadow)) => {
+                let mut live = live.to_vec();
+                live.sort();
+                shadow.sort();
+                if live == shadow {
+                    report.agreed += 1;
+                } else {
+                    report.different_opponents += 1;
+                }
+            }
+        }
+    }
+
+    /// The player left matchmaking without a live match, e.g. they disconnected.
+    pub fn observe_leave(&self, player_id: &str) {
+        let mut state = self.state.lock();
+        state.live_queued_at.remove(player_id);
+        for queue in state.queues.values_mut() {
+            queue.retain(|(id, _)| id != player_id);
+        }
+    }
+
+    pub fn report(&self) -> ShadowReport {
+        let state = self.state.lock();
+        let mut report = state.report.clone();
+        if report.decisions > 0 {
+            report.divergence_rate = 1.0 - report.agreed as f64 / report.decisions as f64;
+        }
+        if state.live_waits > 0 {
+            report.avg_live_wait_ms = state.live_wait_total.as_secs_f64() * 1000.0 / state.live_waits as f64;
+        }
+        if state.shadow_waits > 0 {
+            report.avg_shadow_wait_ms = state.shadow_wait_total.as_secs_f64() * 1000.0 / state.shadow_waits as f64;
+        }
+        report.shadow_waiting = state.queues.values().map(Vec::len).sum();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::application::{EloRatings, FifoPairing, GameManager, PairingStrategy, RatedGame, RatingBandPairing, ShadowMatchmaker};
+    use crate::domain::{GameConfig, GameEndReason, Player, ServerMessage};
+
+    #[tokio::test]
+    async fn test_shadow_matchmaking_reports_divergence_without_affecting_matches() {
+        let elo = Arc::new(EloRatings::new(400.0));
+        // p1 beat p2 once: 1400 against 1000, with p3 unrated at 1200
+        elo.apply(&RatedGame {
+            game_id: "g".to_string(),
+            room_id: "r".to_string(),
+            players: vec!["p1".to_string(), "p2".to_string()],
+            teams: Default::default(),
+            winner: Some("p1".to_string()),
+            reason: GameEndReason::Completed,
+        });
+
+        let run = |strategy: Box<dyn PairingStrategy>| async move {
+            let manager = GameManager::new(GameConfig::default()).with_shadow_matchmaking(Arc::new(ShadowMatchmaker::new(strategy)));
+            let mut receivers = Vec::new();
+            let mut live = Vec::new();
+            for id in ["p1", "p2", "p3"] {
+                let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+                receivers.push(rx);
+                match manager.find_match(Arc::new(Player::new(id.to_string(), tx))).await.unwrap() {
+                    ServerMessage::Matchmaking { matched, .. } => live.push(matched),
+                    other => panic!("unexpected {:?}", other),
+                }
+            }
+            let report = manager.shadow_report().unwrap();
+            manager.remove_player("p2").await.unwrap();
+            manager.remove_player("p3").await.unwrap();
+            (live, report, manager.shadow_report().unwrap().shadow_waiting)
+        };
+
+        let (live, report, _) = run(Box::new(FifoPairing)).await;
+        assert_eq!(live, [false, true, false]);
+        assert_eq!((report.decisions, report.agreed, report.divergence_rate), (3, 3, 0.0));
+
+        let (live, report, waiting_after_leaving) = run(Box::new(RatingBandPairing::new(elo, 250.0, 0.0))).await;
+        assert_eq!(live, [false, true, false]);
+        assert_eq!(report.strategy, "ratingBand");
+        assert_eq!(report.live_matched_shadow_waited, 1); // p2 is too far below p1
+        assert_eq!(report.shadow_matched_live_waited, 1); // p3 sits between them
+        assert!((report.divergence_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.shadow_waiting, 1);
+        assert_eq!(waiting_after_leaving, 0);
+    }
+}
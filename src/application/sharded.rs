@@ -0,0 +1,36 @@
+// Fixed seeds: a key lands on the same shard for the whole life of the process
+const SEEDS: [u64; 4] = [0x5851_f42d_4c95_7f2d, 0x1405_7b7e_f767_814f, 0x2545_f491_4f6c_dd1d, 0x9e37_79b9_7f4a_7c15];
+
+/// A value split into a fixed number of shards, each picked by hashing a key, so
+/// operations on unrelated keys take different locks.
+pub struct Sharded<T> {
+    shards: Box<[T]>,
+    hasher: ahash::RandomState,
+}
+
+impl<T> Sharded<T> {
+    /// `count` shards, at least one, each built by `make`.
+    pub fn new(count: usize, make: impl FnMut() -> T) -> Self {
+        Self {
+            shards: std::iter::repeat_with(make).take(count.max(1)).collect(),
+            hasher: ahash::RandomState::with_seeds(SEEDS[0], SEEDS[1], SEEDS[2], SEEDS[3]),
+        }
+    }
+
+    /// The shard that owns `key`.
+    pub fn get(&self, key: &str) -> &T {
+        &self.shards[self.index(key)]
+    }
+
+    pub fn index(&self, key: &str) -> usize {
+        (self.hasher.hash_one(key) % self.shards.len() as u64) as usize
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.shards.iter()
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+}
@@ -0,0 +1,143 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use super::rate_limiter::RateLimiter;
+use crate::config::SpamGuardConfig;
+
+/// Player-initiated requests aimed at other players, which a spammer can flood.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SpamAction {
+    Rematch,
+    Challenge,
+    Chat,
+}
+
+impl SpamAction {
+    const ALL: [SpamAction; 3] = [SpamAction::Rematch, SpamAction::Challenge, SpamAction::Chat];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SpamAction::Rematch => "rematch",
+            SpamAction::Challenge => "challenge",
+            SpamAction::Chat => "chat",
+        }
+    }
+}
+
+/// Why a request was refused: the player is muted for `remaining`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Muted {
+    pub remaining: Duration,
+    pub strikes: u32,
+}
+
+impl Muted {
+    /// Remaining mute in whole seconds, rounded up, for the `muted` message.
+    pub fn seconds(&self) -> u64 {
+        self.remaining.as_millis().div_ceil(1000) as u64
+    }
+}
+
+impl std::fmt::Display for Muted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Muted for {}s after {} spam strike(s)", self.seconds(), self.strikes)
+    }
+}
+
+impl std::error::Error for Muted {}
+
+#[derive(Default)]
+struct SpamRecord {
+    muted_until: Option<Instant>,
+    strikes: u32,
+    last_strike: Option<Instant>,
+}
+
+/// One mute state per player, shared by every subsystem that sends requests to other
+/// players: flooding any of them mutes all of them. Each mute doubles the previous one
+/// until the player stays clean for the strike decay period.
+pub struct SpamGuard {
+    config: SpamGuardConfig,
+    limiter: RateLimiter<(String, SpamAction)>,
+    records: Mutex<HashMap<String, SpamRecord>>, // Only players with a mute or strikes
+}
+
+impl SpamGuard {
+    pub fn new(config: SpamGuardConfig) -> Self {
+        Self {
+            limiter: RateLimiter::new(config.max_per_window, Duration::from_millis(config.window_ms)),
+            config,
+            records: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts one `action` by the player, or refuses it while they are muted. Going over
+    /// the per-window limit refuses this request and starts a new mute.
+    pub fn check(&self, player_id: &str, action: SpamAction) -> Result<(), Muted> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut records = self.records.lock();
+        if let Some(record) = records.get_mut(player_id) {
+            if let Some(until) = record.muted_until {
+                if until > now {
+                    return Err(Muted { remaining: until - now, strikes: record.strikes });
+                }
+                record.muted_until = None;
+            }
+            if record.last_strike.is_some_and(|at| now - at >= Duration::from_millis(self.config.strike_decay_ms)) {
+                record.strikes = 0;
+                record.last_strike = None;
+            }
+        }
+
+        if self.limiter.check_at((player_id.to_string(), action), now).is_ok() {
+            return Ok(());
+        }
+
+        let record = records.entry(player_id.to_string()).or_default();
+        let mute = Duration::from_millis(self.config.base_mute_ms)
+            .saturating_mul(2u32.saturating_pow(record.strikes))
+            .min(Duration::from_millis(self.config.max_mute_ms));
+        record.strikes += 1;
+        record.last_strike = Some(now);
+        record.muted_until = Some(now + mute);
+        for action in SpamAction::ALL {
+            self.limiter.reset(&(player_id.to_string(), action));
+        }
+        info!("Player {} muted for {:?} after {} spam (strike {})", player_id, mute, action.as_str(), record.strikes);
+        Err(Muted { remaining: mute, strikes: record.strikes })
+    }
+
+    /// Remaining mute, without counting a request.
+    pub fn muted(&self, player_id: &str) -> Option<Duration> {
+        let now = Instant::now();
+        let until = self.records.lock().get(player_id)?.muted_until?;
+        (until > now).then(|| until - now)
+    }
+
+    /// Lifts a mute early, e.g. by a moderator. Strikes are kept.
+    pub fn unmute(&self, player_id: &str) -> bool {
+        self.records
+            .lock()
+            .get_mut(player_id)
+            .and_then(|record| record.muted_until.take())
+            .is_some()
+    }
+
+    /// Forgets lapsed mutes and strikes and request counts that have left the window,
+    /// returning how many entries went.
+    pub fn prune(&self) -> usize {
+        let now = Instant::now();
+        let mut records = self.records.lock();
+        let before = records.len();
+        records.retain(|_, record| record.strikes > 0 || record.muted_until.is_some_and(|until| until > now));
+        before - records.len() + self.limiter.prune()
+    }
+}
@@ -0,0 +1,160 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+// Finished waits kept per queue for its distribution
+const RECENT_WAITS: usize = 256;
+
+/// Wait times in one matchmaking queue.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueWaitStats {
+    pub queue: String,
+    pub waiting: usize,
+    pub starved: usize, // Waiting past the SLO right now
+    pub longest_wait_ms: u64,
+    pub p50_ms: u64, // Over the queue's recent finished waits
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+}
+
+/// Matchmaking waits against the SLO, for `/ultra-metrics`.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StarvationReport {
+    pub slo_ms: u64,
+    pub starved_total: u64, // Players who went past the SLO since startup
+    pub relaxed_total: u64, // Of those, let out of a pool that kept them apart
+    pub queues: Vec<QueueWaitStats>,
+}
+
+/// A player who has just gone past the SLO; reported once per stay in the queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StarvedPlayer {
+    pub player_id: String,
+    pub queue: &'static str,
+    pub waited: Duration,
+}
+
+struct Waiting {
+    queue: &'static str,
+    since: Instant,
+    starved: bool,
+    relaxed: bool,
+}
+
+#[derive(Default)]
+struct WaitState {
+    waiting: HashMap<String, Waiting>,
+    recent: HashMap<&'static str, VecDeque<Duration>>, // Oldest first
+    starved_total: u64,
+    relaxed_total: u64,
+}
+
+/// How long each queued player has waited, by queue. Feeds the starvation alerts and
+/// the wait distribution; a player moved to another queue keeps their original start.
+#[derive(Default)]
+pub struct QueueWaits {
+    state: Mutex<WaitState>,
+}
+
+impl QueueWaits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn queued(&self, player_id: &str, queue: &'static str, now: Instant) {
+        let mut state = self.state.lock();
+        let waiting = state.waiting.entry(player_id.to_string()).or_insert(Waiting {
+            queue,
+            since: now,
+            starved: false,
+            relaxed: false,
+        });
+        waiting.queue = queue;
+    }
+
+    /// The player left the queue, matched or not; their wait joins the distribution.
+    pub fn left(&self, player_id: &str, now: Instant) {
+        let mut state = self.state.lock();
+        let Some(waiting) = state.waiting.remove(player_id) else {
+            return;
+        };
+        let recent = state.recent.entry(waiting.queue).or_default();
+        if recent.len() == RECENT_WAITS {
+            recent.pop_front();
+        }
+        recent.push_back(now.saturating_duration_since(waiting.since));
+    }
+
+    /// Players who went past `slo` since the last call.
+    pub fn newly_starved(&self, slo: Duration, now: Instant) -> Vec<StarvedPlayer> {
+        let mut state = self.state.lock();
+        let mut starved = Vec::new();
+        for (player_id, waiting) in state.waiting.iter_mut() {
+            let waited = now.saturating_duration_since(waiting.since);
+            if !waiting.starved && waited >= slo {
+                waiting.starved = true;
+                starved.push(StarvedPlayer {
+                    player_id: player_id.clone(),
+                    queue: waiting.queue,
+                    waited,
+                });
+            }
+        }
+        state.starved_total += starved.len() as u64;
+        starved
+    }
+
+    /// Lets a starved player out of the pool that kept them apart, until they leave the queue.
+    pub fn relax(&self, player_id: &str) {
+        let mut state = self.state.lock();
+        if let Some(waiting) = state.waiting.get_mut(player_id).filter(|waiting| !waiting.relaxed) {
+            waiting.relaxed = true;
+            state.relaxed_total += 1;
+        }
+    }
+
+    pub fn is_relaxed(&self, player_id: &str) -> bool {
+        self.state.lock().waiting.get(player_id).is_some_and(|waiting| waiting.relaxed)
+    }
+
+    pub fn report(&self, slo: Duration, now: Instant) -> StarvationReport {
+        let state = self.state.lock();
+        let mut queues: BTreeMap<&str, QueueWaitStats> = BTreeMap::new();
+        for (queue, recent) in &state.recent {
+            let mut waits: Vec<Duration> = recent.iter().copied().collect();
+            waits.sort();
+            let stats = queues.entry(queue).or_default();
+            stats.p50_ms = percentile_ms(&waits, 0.5);
+            stats.p90_ms = percentile_ms(&waits, 0.9);
+            stats.p99_ms = percentile_ms(&waits, 0.99);
+        }
+        for waiting in state.waiting.values() {
+            let waited = now.saturating_duration_since(waiting.since);
+            let stats = queues.entry(waiting.queue).or_default();
+            stats.waiting += 1;
+            stats.starved += usize::from(waited >= slo);
+            stats.longest_wait_ms = stats.longest_wait_ms.max(waited.as_millis() as u64);
+        }
+
+        StarvationReport {
+            slo_ms: slo.as_millis() as u64,
+            starved_total: state.starved_total,
+            relaxed_total: state.relaxed_total,
+            queues: queues
+                .into_iter()
+                .map(|(queue, stats)| QueueWaitStats { queue: queue.to_string(), ..stats })
+                .collect(),
+        }
+    }
+}
+
+fn percentile_ms(sorted: &[Duration], quantile: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let index = ((sorted.len() as f64 * quantile).ceil() as usize).clamp(1, sorted.len()) - 1;
+    sorted[index].as_millis() as u64
+}
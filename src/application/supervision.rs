@@ -0,0 +1,45 @@
+use std::any::Any;
+
+/// The text a task panicked with, for logging what `catch_unwind` caught.
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+#[cfg(test)]
+mod tests {
+    /// Every `panic` setting in the `[profile.*]` sections of the manifest, by profile.
+    fn profile_panic_strategies(manifest: &str) -> Vec<(String, String)> {
+        let mut profile = None;
+        let mut strategies = Vec::new();
+        for line in manifest.lines().map(|line| line.split('#').next().unwrap_or("").trim()) {
+            if let Some(section) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                profile = section.strip_prefix("profile.").map(str::to_string);
+            } else if let (Some(profile), Some(value)) = (&profile, line.strip_prefix("panic")) {
+                let value = value.trim_start().trim_start_matches('=').trim().trim_matches('"');
+                strategies.push((profile.clone(), value.to_string()));
+            }
+        }
+        strategies
+    }
+
+    // Tests always build with unwinding whatever the profile says, so the shipped profiles are
+    // checked here; with `panic = "abort"` every `catch_unwind` would be dead code
+    #[test]
+    fn test_shipped_profiles_unwind_so_panics_stay_isolated() {
+        let strategies = profile_panic_strategies(include_str!("../../Cargo.toml"));
+        for shipped in ["release", "max-perf"] {
+            assert!(
+                strategies.iter().any(|(profile, strategy)| profile == shipped && strategy == "unwind"),
+                "profile.{} must set panic = \"unwind\"",
+                shipped
+            );
+        }
+        for (profile, strategy) in &strategies {
+            assert_eq!(strategy, "unwind", "profile.{} sets panic = {:?}", profile, strategy);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::Result;
+use clap::{Arg, Command};
+use std::hint::black_box;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tracing::{info, Level};
+
+use rps_server::config::JsonParser;
+use rps_server::infrastructure::{MessageCodec, CODEC_FALLBACKS};
+
+/// Frames as clients send them during a game, weighted toward moves.
+const FRAMES: [&str; 6] = [
+    r#"{"type":"playerMove","choice":"rock"}"#,
+    r#"{"type":"playerMove","choice":"paper"}"#,
+    r#"{"type":"playerMove","choice":"scissors"}"#,
+    r#"{"type":"findMatch","mode":"solo"}"#,
+    r#"{"type":"connect","playerId":"3f2b9c1e-8d4a-4c6b-9e1f-0a7d5b2c4e6f","locale":"pt-BR","clientVersion":"rps-web/2.4.1","protocolVersion":2}"#,
+    r#"{"type":"ackNotifications","ids":["n-1","n-2","n-3","n-4","n-5","n-6","n-7","n-8"]}"#,
+];
+
+fn run(iterations: usize, codec: &mut MessageCodec) -> Duration {
+    let started = Instant::now();
+    for i in 0..iterations {
+        black_box(codec.decode(black_box(FRAMES[i % FRAMES.len()])).unwrap());
+    }
+    started.elapsed()
+}
+
+/// Times each `websocket.json_parser` on the same frames, to pick one for the hardware
+/// the server runs on. Build with `--release`; debug timings say little.
+fn main() -> Result<()> {
+    tracing_subscriber::fmt().with_max_level(Level::INFO).init();
+
+    let matches = Command::new("RPS Codec Benchmark")
+        .about("Compares the inbound JSON parsers on typical client frames")
+        .arg(
+            Arg::new("iterations")
+                .short('n')
+                .long("iterations")
+                .value_name("NUMBER")
+                .help("Frames parsed per parser")
+                .default_value("1000000"),
+        )
+        .get_matches();
+    let iterations: usize = matches.get_one::<String>("iterations").unwrap().parse()?;
+
+    let mut serde = MessageCodec::new(JsonParser::SerdeJson);
+    let mut simd = MessageCodec::new(JsonParser::SimdJson);
+
+    // Both parsers must agree before their speed means anything
+    for frame in FRAMES {
+        let expected = serde_json::to_value(serde.decode(frame)?)?;
+        anyhow::ensure!(serde_json::to_value(simd.decode(frame)?)? == expected, "simd-json disagrees on {}", frame);
+    }
+    anyhow::ensure!(CODEC_FALLBACKS.load(Ordering::Relaxed) == 0, "simd-json fell back to serde_json");
+
+    // Warm up caches and the codecs' buffers
+    run(iterations / 10, &mut serde);
+    run(iterations / 10, &mut simd);
+
+    let serde_time = run(iterations, &mut serde);
+    let simd_time = run(iterations, &mut simd);
+
+    let per_frame = |elapsed: Duration| elapsed.as_nanos() as f64 / iterations as f64;
+    info!("serdeJson: {:>8.1} ns/frame", per_frame(serde_time));
+    info!("simdJson:  {:>8.1} ns/frame", per_frame(simd_time));
+    info!("simdJson speedup: {:.2}x", serde_time.as_secs_f64() / simd_time.as_secs_f64());
+    Ok(())
+}
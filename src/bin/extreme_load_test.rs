@@ -1,15 +1,16 @@
 use anyhow::Result;
-use clap::{Parser, Arg, Command};
-use futures_util::{SinkExt, StreamExt};
-use serde_json::json;
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::net::TcpStream;
-use tokio::time::timeout;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio::time::{timeout, MissedTickBehavior};
 use tracing::{error, info, warn};
 
+use rps_server::client::{ClientOptions, GameClient};
+use rps_server::domain::{ClientMessage, DrawPolicy, GameChoice, GameEndReason, GameMode, GameType, RuleSet, ServerMessage};
+
 #[derive(Parser, Debug)]
 #[command(name = "extreme-load-test")]
 #[command(about = "Extreme load testing for RPS Game Server")]
@@ -21,13 +22,22 @@ struct Args {
     server: String,
     
     #[arg(short, long, default_value = "progressive")]
-    test_type: String, // progressive, burst, sustained, extreme
+    test_type: String, // progressive, burst, sustained, extreme, open-loop, scenario
     
     #[arg(short, long, default_value = "60")]
     duration: u64, // seconds
     
     #[arg(long, default_value = "false")]
     find_max: bool, // Find maximum capacity
+
+    #[arg(long, default_value = "2.0")]
+    rate: f64, // open-loop: moves per second per client
+
+    #[arg(long)]
+    scenario: Option<String>, // scenario: JSON file of load phases
+
+    #[arg(long)]
+    report: Option<String>, // scenario: write per-phase results here as JSON
 }
 
 #[derive(Debug, Clone)]
@@ -37,15 +47,14 @@ struct ExtremeTestMetrics {
     failed_connections: u32,
     peak_concurrent: u32,
     successful_matches: u32,
-    completed_games: u32,
+    completed_games: u32, // Coherent games played to GameEnd, counted once per player
+    abandoned_games: u32, // Opponent left, or the game ended on time or forfeit
+    protocol_violations: ViolationCounts,
     total_messages_sent: u64,
     total_messages_received: u64,
     average_connection_time: Duration,
     average_response_time: Duration,
     connection_drops: u32,
-    memory_usage_mb: f64,
-    cpu_usage_percent: f64,
-    errors: Vec<String>,
 }
 
 #[tokio::main]
@@ -67,6 +76,8 @@ async fn main() -> Result<()> {
         "sustained" => run_sustained_test(&args).await?,
         "extreme" => run_extreme_test(&args).await?,
         "find-max" => find_maximum_capacity(&args).await?,
+        "open-loop" => run_open_loop_test(&args).await?,
+        "scenario" => run_scenario_test(&args).await?,
         _ => {
             error!("Unknown test type: {}", args.test_type);
             return Ok(());
@@ -190,22 +201,81 @@ async fn find_maximum_capacity(args: &Args) -> Result<()> {
     Ok(())
 }
 
+/// Counters shared by every client of a run.
+#[derive(Debug, Default)]
+struct ClientCounters {
+    successful_connections: AtomicU32,
+    failed_connections: AtomicU32,
+    current_connections: AtomicU32,
+    peak_concurrent: AtomicU32,
+    successful_matches: AtomicU32,
+    completed_games: AtomicU32,
+    abandoned_games: AtomicU32,
+    violations: ProtocolViolations,
+    total_messages_sent: AtomicU64,
+    total_messages_received: AtomicU64,
+    connection_drops: AtomicU32,
+    total_connection_time: AtomicU64,
+    total_response_time: AtomicU64,
+    response_count: AtomicU32,
+}
+
+impl ClientCounters {
+    fn metrics(&self, target_connections: u32) -> ExtremeTestMetrics {
+        let successful_connections = self.successful_connections.load(Ordering::Relaxed);
+        ExtremeTestMetrics {
+            target_connections,
+            successful_connections,
+            failed_connections: self.failed_connections.load(Ordering::Relaxed),
+            peak_concurrent: self.peak_concurrent.load(Ordering::Relaxed),
+            successful_matches: self.successful_matches.load(Ordering::Relaxed),
+            completed_games: self.completed_games.load(Ordering::Relaxed),
+            abandoned_games: self.abandoned_games.load(Ordering::Relaxed),
+            protocol_violations: self.violations.snapshot(),
+            total_messages_sent: self.total_messages_sent.load(Ordering::Relaxed),
+            total_messages_received: self.total_messages_received.load(Ordering::Relaxed),
+            connection_drops: self.connection_drops.load(Ordering::Relaxed),
+            average_connection_time: Duration::from_millis(
+                self.total_connection_time.load(Ordering::Relaxed) / std::cmp::max(1, successful_connections) as u64,
+            ),
+            average_response_time: Duration::from_millis(
+                self.total_response_time.load(Ordering::Relaxed)
+                    / std::cmp::max(1, self.response_count.load(Ordering::Relaxed)) as u64,
+            ),
+        }
+    }
+}
+
+/// Runs one client until `keep_playing` says stop, recording how it went.
+fn spawn_client(
+    client_id: u32,
+    server_url: String,
+    counters: Arc<ClientCounters>,
+    keep_playing: impl Fn() -> bool + Send + Sync + 'static,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let connection_start = Instant::now();
+
+        match run_single_client(client_id, &server_url, &counters, keep_playing).await {
+            Ok(_) => {
+                counters.successful_connections.fetch_add(1, Ordering::Relaxed);
+                let connection_time = connection_start.elapsed().as_millis() as u64;
+                counters.total_connection_time.fetch_add(connection_time, Ordering::Relaxed);
+            }
+            Err(e) => {
+                counters.failed_connections.fetch_add(1, Ordering::Relaxed);
+                if client_id.is_multiple_of(1000) {
+                    error!("Client {} failed: {}", client_id, e);
+                }
+            }
+        }
+    })
+}
+
 async fn run_connection_test(connections: u32, server_url: &str, duration_secs: u64) -> Result<ExtremeTestMetrics> {
     let start_time = Instant::now();
-    
-    // Metrics
-    let successful_connections = Arc::new(AtomicU32::new(0));
-    let failed_connections = Arc::new(AtomicU32::new(0));
-    let peak_concurrent = Arc::new(AtomicU32::new(0));
-    let current_connections = Arc::new(AtomicU32::new(0));
-    let successful_matches = Arc::new(AtomicU32::new(0));
-    let completed_games = Arc::new(AtomicU32::new(0));
-    let total_messages_sent = Arc::new(AtomicU64::new(0));
-    let total_messages_received = Arc::new(AtomicU64::new(0));
-    let connection_drops = Arc::new(AtomicU32::new(0));
-    let total_connection_time = Arc::new(AtomicU64::new(0));
-    let total_response_time = Arc::new(AtomicU64::new(0));
-    let response_count = Arc::new(AtomicU32::new(0));
+    let counters = Arc::new(ClientCounters::default());
+    let end_time = Instant::now() + Duration::from_secs(duration_secs);
     
     // Spawn connections with controlled rate
     let mut tasks = Vec::new();
@@ -221,52 +291,7 @@ async fn run_connection_test(connections: u32, server_url: &str, duration_secs:
         }
         
         for i in batch_start..batch_end {
-            let server_url = server_url.to_string();
-            let successful_connections = successful_connections.clone();
-            let failed_connections = failed_connections.clone();
-            let current_connections = current_connections.clone();
-            let peak_concurrent = peak_concurrent.clone();
-            let successful_matches = successful_matches.clone();
-            let completed_games = completed_games.clone();
-            let total_messages_sent = total_messages_sent.clone();
-            let total_messages_received = total_messages_received.clone();
-            let connection_drops = connection_drops.clone();
-            let total_connection_time = total_connection_time.clone();
-            let total_response_time = total_response_time.clone();
-            let response_count = response_count.clone();
-            
-            let task = tokio::spawn(async move {
-                let connection_start = Instant::now();
-                
-                match run_single_client(
-                    i,
-                    &server_url,
-                    duration_secs,
-                    current_connections.clone(),
-                    peak_concurrent.clone(),
-                    successful_matches.clone(),
-                    completed_games.clone(),
-                    total_messages_sent.clone(),
-                    total_messages_received.clone(),
-                    connection_drops.clone(),
-                    total_response_time.clone(),
-                    response_count.clone(),
-                ).await {
-                    Ok(_) => {
-                        successful_connections.fetch_add(1, Ordering::Relaxed);
-                        let connection_time = connection_start.elapsed().as_millis() as u64;
-                        total_connection_time.fetch_add(connection_time, Ordering::Relaxed);
-                    }
-                    Err(e) => {
-                        failed_connections.fetch_add(1, Ordering::Relaxed);
-                        if i % 1000 == 0 {
-                            error!("Client {} failed: {}", i, e);
-                        }
-                    }
-                }
-            });
-            
-            tasks.push(task);
+            tasks.push(spawn_client(i, server_url.to_string(), counters.clone(), move || Instant::now() < end_time));
         }
         
         // Small delay between batches to avoid overwhelming
@@ -280,29 +305,7 @@ async fn run_connection_test(connections: u32, server_url: &str, duration_secs:
     let _ = timeout(timeout_duration, futures_util::future::join_all(tasks)).await;
     
     let total_time = start_time.elapsed();
-    
-    let metrics = ExtremeTestMetrics {
-        target_connections: connections,
-        successful_connections: successful_connections.load(Ordering::Relaxed),
-        failed_connections: failed_connections.load(Ordering::Relaxed),
-        peak_concurrent: peak_concurrent.load(Ordering::Relaxed),
-        successful_matches: successful_matches.load(Ordering::Relaxed),
-        completed_games: completed_games.load(Ordering::Relaxed),
-        total_messages_sent: total_messages_sent.load(Ordering::Relaxed),
-        total_messages_received: total_messages_received.load(Ordering::Relaxed),
-        connection_drops: connection_drops.load(Ordering::Relaxed),
-        average_connection_time: Duration::from_millis(
-            total_connection_time.load(Ordering::Relaxed) / 
-            std::cmp::max(1, successful_connections.load(Ordering::Relaxed)) as u64
-        ),
-        average_response_time: Duration::from_millis(
-            total_response_time.load(Ordering::Relaxed) / 
-            std::cmp::max(1, response_count.load(Ordering::Relaxed)) as u64
-        ),
-        memory_usage_mb: 0.0, // Would need system monitoring
-        cpu_usage_percent: 0.0, // Would need system monitoring
-        errors: Vec::new(),
-    };
+    let metrics = counters.metrics(connections);
     
     info!("Load test completed in {:.2}s", total_time.as_secs_f64());
     
@@ -323,110 +326,682 @@ async fn run_extreme_connection_test(connections: u32, server_url: &str, duratio
     run_connection_test(connections, server_url, duration_secs).await
 }
 
+/// Plays whole games against other test clients for as long as `keep_playing` holds;
+/// a game already under way is finished so the opponent doesn't see us leave.
 async fn run_single_client(
     client_id: u32,
     server_url: &str,
-    duration_secs: u64,
-    current_connections: Arc<AtomicU32>,
-    peak_concurrent: Arc<AtomicU32>,
-    successful_matches: Arc<AtomicU32>,
-    completed_games: Arc<AtomicU32>,
-    total_messages_sent: Arc<AtomicU64>,
-    total_messages_received: Arc<AtomicU64>,
-    connection_drops: Arc<AtomicU32>,
-    total_response_time: Arc<AtomicU64>,
-    response_count: Arc<AtomicU32>,
+    counters: &ClientCounters,
+    keep_playing: impl Fn() -> bool,
 ) -> Result<()> {
-    let (ws_stream, _) = timeout(
-        Duration::from_secs(10),
-        connect_async(server_url)
-    ).await??;
-    
-    let (mut write, mut read) = ws_stream.split();
-    
+    let response_start = Instant::now();
+    let mut client = GameClient::connect(ClientOptions {
+        url: server_url.to_string(),
+        player_id: Some(format!("extreme_client_{}", client_id)),
+        client_version: Some(format!("extreme-load-test/{}", env!("CARGO_PKG_VERSION"))),
+        connect_timeout: Duration::from_secs(10),
+        response_timeout: Duration::from_secs(10),
+        ..ClientOptions::default()
+    })
+    .await?;
+    counters.total_messages_sent.fetch_add(1, Ordering::Relaxed);
+    counters.total_messages_received.fetch_add(1, Ordering::Relaxed);
+    counters.total_response_time.fetch_add(response_start.elapsed().as_millis() as u64, Ordering::Relaxed);
+    counters.response_count.fetch_add(1, Ordering::Relaxed);
+
     // Update connection tracking
-    let current = current_connections.fetch_add(1, Ordering::Relaxed) + 1;
-    let peak = peak_concurrent.load(Ordering::Relaxed);
-    if current > peak {
-        peak_concurrent.store(current, Ordering::Relaxed);
+    let current = counters.current_connections.fetch_add(1, Ordering::Relaxed) + 1;
+    counters.peak_concurrent.fetch_max(current, Ordering::Relaxed);
+
+    let choices = [GameChoice::Rock, GameChoice::Paper, GameChoice::Scissors];
+    let mut moves_played = client_id as usize;
+
+    'games: while keep_playing() {
+        let matchmaking = client.find_match(GameMode::Solo).await;
+        counters.total_messages_sent.fetch_add(1, Ordering::Relaxed);
+        if matchmaking.is_err() {
+            break;
+        }
+        counters.total_messages_received.fetch_add(1, Ordering::Relaxed);
+
+        // Wait for an opponent; the last unpaired client just waits until it's stopped
+        let mut game = loop {
+            let waiting_since = Instant::now();
+            let event = match client.next_event_within(OPPONENT_POLL).await {
+                Ok(event) => event,
+                // A quiet poll; an early error means the connection is gone
+                Err(_) if waiting_since.elapsed() >= OPPONENT_POLL && keep_playing() => continue,
+                Err(_) => break 'games,
+            };
+            counters.total_messages_received.fetch_add(1, Ordering::Relaxed);
+            if let ServerMessage::GameStart { players, max_rounds, draw_policy, rules, .. } = event {
+                let players = players.into_iter().map(|player| player.id).collect();
+                break GameCheck::new(client.player_id().to_string(), players, max_rounds, draw_policy, rules.unwrap_or_default());
+            }
+        };
+        counters.successful_matches.fetch_add(1, Ordering::Relaxed);
+
+        loop {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            let choice = choices[moves_played % choices.len()].clone();
+            moves_played += 1;
+            if client.play(choice.clone()).await.is_err() {
+                counters.connection_drops.fetch_add(1, Ordering::Relaxed);
+                break 'games;
+            }
+            counters.total_messages_sent.fetch_add(1, Ordering::Relaxed);
+
+            // Read until this round is resolved
+            let mut wait = GAME_EVENT_TIMEOUT;
+            loop {
+                let Ok(event) = client.next_event_within(wait).await else {
+                    counters.violations.record(Violation::Stalled);
+                    break 'games;
+                };
+                counters.total_messages_received.fetch_add(1, Ordering::Relaxed);
+                let checked = match event {
+                    ServerMessage::RoundResult { round, winner, moves, scores, replay, .. } => {
+                        game.round_result(round, winner.as_deref(), &moves, &scores, replay, &choice)
+                    }
+                    ServerMessage::NextRound { round, .. } => {
+                        counters.violations.check(game.next_round(round));
+                        break;
+                    }
+                    ServerMessage::GameEnd { winner, final_scores, reason, rounds, .. } => {
+                        let checked = game.end(winner.as_deref(), &final_scores, &reason, rounds.len());
+                        match checked {
+                            Ok(()) if reason == GameEndReason::Completed => counters.completed_games.fetch_add(1, Ordering::Relaxed),
+                            Ok(()) => counters.abandoned_games.fetch_add(1, Ordering::Relaxed),
+                            Err(violation) => {
+                                counters.violations.record(violation);
+                                0
+                            }
+                        };
+                        continue 'games;
+                    }
+                    ServerMessage::PlayerLeft { .. } | ServerMessage::RoomClosed { .. } => {
+                        counters.abandoned_games.fetch_add(1, Ordering::Relaxed);
+                        continue 'games;
+                    }
+                    ServerMessage::GamePaused { resume_within_ms, .. } => {
+                        // An opponent that went away forfeits once the reconnect grace runs out
+                        wait = GAME_EVENT_TIMEOUT + Duration::from_millis(resume_within_ms);
+                        Ok(())
+                    }
+                    ServerMessage::Error { .. } => Err(Violation::RejectedMove),
+                    _ => Ok(()),
+                };
+                counters.violations.check(checked);
+            }
+        }
     }
-    
-    // Connect message
-    let connect_msg = json!({
-        "Connect": {
-            "player_id": format!("extreme_client_{}", client_id)
+
+    counters.current_connections.fetch_sub(1, Ordering::Relaxed);
+    Ok(())
+}
+
+// How often a client waiting for an opponent checks whether it should stop
+const OPPONENT_POLL: Duration = Duration::from_secs(1);
+
+// Longest wait for the next game message once a move is in; above the server's move timeout
+const GAME_EVENT_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Ways a game can go against the protocol, as seen by one of its players.
+#[derive(Debug, Clone, Copy)]
+enum Violation {
+    OutOfOrder,         // Round numbers skipped or repeated
+    MoveMismatch,       // A round result that doesn't show the players' or our own move
+    WrongWinner,        // A round or game winner the moves or scores don't support
+    ScoreMismatch,      // Round scores that don't follow from the previous round
+    FinalScoreMismatch, // GameEnd scores or round history differing from the rounds played
+    RejectedMove,       // A move answered with an error although it was our turn
+    Stalled,            // No game message within GAME_EVENT_TIMEOUT of a move, or of a pause running out
+}
+
+#[derive(Debug, Default)]
+struct ProtocolViolations {
+    out_of_order: AtomicU32,
+    move_mismatch: AtomicU32,
+    wrong_winner: AtomicU32,
+    score_mismatch: AtomicU32,
+    final_score_mismatch: AtomicU32,
+    rejected_moves: AtomicU32,
+    stalled: AtomicU32,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ViolationCounts {
+    out_of_order: u32,
+    move_mismatch: u32,
+    wrong_winner: u32,
+    score_mismatch: u32,
+    final_score_mismatch: u32,
+    rejected_moves: u32,
+    stalled: u32,
+}
+
+impl ProtocolViolations {
+    fn record(&self, violation: Violation) {
+        let counter = match violation {
+            Violation::OutOfOrder => &self.out_of_order,
+            Violation::MoveMismatch => &self.move_mismatch,
+            Violation::WrongWinner => &self.wrong_winner,
+            Violation::ScoreMismatch => &self.score_mismatch,
+            Violation::FinalScoreMismatch => &self.final_score_mismatch,
+            Violation::RejectedMove => &self.rejected_moves,
+            Violation::Stalled => &self.stalled,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn check(&self, checked: Result<(), Violation>) {
+        if let Err(violation) = checked {
+            self.record(violation);
         }
-    });
-    
-    let response_start = Instant::now();
-    write.send(Message::Text(connect_msg.to_string())).await?;
-    total_messages_sent.fetch_add(1, Ordering::Relaxed);
-    
-    // Wait for connect response
-    if let Some(msg) = timeout(Duration::from_secs(5), read.next()).await? {
-        match msg? {
-            Message::Text(_) => {
-                total_messages_received.fetch_add(1, Ordering::Relaxed);
-                let response_time = response_start.elapsed().as_millis() as u64;
-                total_response_time.fetch_add(response_time, Ordering::Relaxed);
-                response_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> ViolationCounts {
+        ViolationCounts {
+            out_of_order: self.out_of_order.load(Ordering::Relaxed),
+            move_mismatch: self.move_mismatch.load(Ordering::Relaxed),
+            wrong_winner: self.wrong_winner.load(Ordering::Relaxed),
+            score_mismatch: self.score_mismatch.load(Ordering::Relaxed),
+            final_score_mismatch: self.final_score_mismatch.load(Ordering::Relaxed),
+            rejected_moves: self.rejected_moves.load(Ordering::Relaxed),
+            stalled: self.stalled.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl ViolationCounts {
+    fn total(&self) -> u32 {
+        self.out_of_order
+            + self.move_mismatch
+            + self.wrong_winner
+            + self.score_mismatch
+            + self.final_score_mismatch
+            + self.rejected_moves
+            + self.stalled
+    }
+}
+
+/// Replays a game from one player's messages and checks each against what the rules
+/// and earlier rounds imply. After a mismatch it adopts the server's figures, so one
+/// fault is reported once rather than in every later round.
+struct GameCheck {
+    me: String,
+    players: Vec<String>,
+    max_rounds: u32,
+    draw_policy: DrawPolicy,
+    rules: RuleSet,
+    round: u32,
+    replaying: bool,
+    scores: HashMap<String, u32>,
+    rounds_played: usize,
+}
+
+impl GameCheck {
+    fn new(me: String, players: Vec<String>, max_rounds: u32, draw_policy: DrawPolicy, rules: RuleSet) -> Self {
+        let scores = players.iter().map(|player| (player.clone(), 0)).collect();
+        Self {
+            me,
+            players,
+            max_rounds,
+            draw_policy,
+            rules,
+            round: 1,
+            replaying: false,
+            scores,
+            rounds_played: 0,
+        }
+    }
+
+    fn round_result(
+        &mut self,
+        round: u32,
+        winner: Option<&str>,
+        moves: &HashMap<String, GameChoice>,
+        scores: &HashMap<String, u32>,
+        replay: bool,
+        my_choice: &GameChoice,
+    ) -> Result<(), Violation> {
+        self.rounds_played += 1;
+        let mut checked = Ok(());
+        if round != self.round || round > self.max_rounds {
+            checked = checked.and(Err(Violation::OutOfOrder));
+        }
+        self.replaying = replay;
+
+        let all_moved = moves.len() == self.players.len() && self.players.iter().all(|player| moves.contains_key(player));
+        if !all_moved || moves.get(&self.me) != Some(my_choice) {
+            self.scores = scores.clone();
+            return checked.and(Err(Violation::MoveMismatch));
+        }
+
+        let (first, second) = (&moves[&self.players[0]], &moves[&self.players[1]]);
+        let expected = if first == second {
+            None
+        } else if self.rules.beats(first, second) {
+            Some(self.players[0].as_str())
+        } else {
+            Some(self.players[1].as_str())
+        };
+        if winner != expected || replay != (expected.is_none() && self.draw_policy == DrawPolicy::Replay) {
+            checked = checked.and(Err(Violation::WrongWinner));
+        }
+
+        match (winner, self.draw_policy) {
+            (Some(winner), _) => *self.scores.entry(winner.to_string()).or_default() += 1,
+            (None, DrawPolicy::BothScore) => self.scores.values_mut().for_each(|score| *score += 1),
+            (None, _) => {}
+        }
+        if *scores != self.scores {
+            self.scores = scores.clone();
+            checked = checked.and(Err(Violation::ScoreMismatch));
+        }
+        checked
+    }
+
+    fn next_round(&mut self, round: u32) -> Result<(), Violation> {
+        let expected = if self.replaying { self.round } else { self.round + 1 };
+        self.round = round;
+        if round == expected {
+            Ok(())
+        } else {
+            Err(Violation::OutOfOrder)
+        }
+    }
+
+    fn end(&self, winner: Option<&str>, final_scores: &HashMap<String, u32>, reason: &GameEndReason, rounds: usize) -> Result<(), Violation> {
+        if *final_scores != self.scores || rounds != self.rounds_played {
+            return Err(Violation::FinalScoreMismatch);
+        }
+        if *reason == GameEndReason::Completed {
+            let best = self.scores.values().copied().max().unwrap_or(0);
+            let mut leaders = self.scores.iter().filter(|(_, &score)| score == best).map(|(player, _)| player.as_str());
+            let expected = match (leaders.next(), leaders.next()) {
+                (Some(leader), None) => Some(leader),
+                _ => None, // Tie game
+            };
+            if winner != expected {
+                return Err(Violation::WrongWinner);
             }
-            _ => {}
         }
+        Ok(())
     }
-    
-    // Find match
-    let find_match_msg = json!({"FindMatch": {}});
-    write.send(Message::Text(find_match_msg.to_string())).await?;
-    total_messages_sent.fetch_add(1, Ordering::Relaxed);
-    
-    // Wait for match response
-    if let Some(msg) = timeout(Duration::from_secs(10), read.next()).await? {
-        match msg? {
-            Message::Text(text) => {
-                total_messages_received.fetch_add(1, Ordering::Relaxed);
-                if text.contains("\"matched\":true") {
-                    successful_matches.fetch_add(1, Ordering::Relaxed);
-                }
+}
+
+/// A reproducible capacity run: phases applied in order to the number of connected
+/// clients, e.g. ramp to 5000 over 60s, hold for 120s, then spike to 10000.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Scenario {
+    name: String,
+    #[serde(default)]
+    server: Option<String>, // Overrides --server
+    phases: Vec<Phase>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Phase {
+    // Reach `clients` at an even pace over the duration; lower targets stop clients
+    #[serde(rename_all = "camelCase")]
+    Ramp { clients: u32, duration_secs: u64 },
+    #[serde(rename_all = "camelCase")]
+    Hold { duration_secs: u64 },
+    // Jump to `clients` at once, then wait out the duration
+    #[serde(rename_all = "camelCase")]
+    Spike {
+        clients: u32,
+        #[serde(default)]
+        duration_secs: u64,
+    },
+}
+
+impl Phase {
+    fn name(&self) -> String {
+        match self {
+            Phase::Ramp { clients, duration_secs } => format!("ramp to {} over {}s", clients, duration_secs),
+            Phase::Hold { duration_secs } => format!("hold {}s", duration_secs),
+            Phase::Spike { clients, duration_secs } => format!("spike to {} for {}s", clients, duration_secs),
+        }
+    }
+}
+
+/// What happened during one phase; the counts cover that phase only.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhaseReport {
+    phase: String,
+    target_clients: u32,
+    connected_at_end: u32,
+    duration_secs: f64,
+    failed_connections: u32,
+    connection_drops: u32,
+    completed_games: u32,
+    abandoned_games: u32,
+    protocol_violations: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScenarioReport {
+    scenario: String,
+    server: String,
+    phases: Vec<PhaseReport>,
+}
+
+async fn run_scenario_test(args: &Args) -> Result<()> {
+    let Some(path) = &args.scenario else {
+        error!("-t scenario needs --scenario <file>");
+        return Ok(());
+    };
+    let scenario: Scenario = serde_json::from_str(&std::fs::read_to_string(path)?)
+        .map_err(|e| anyhow::anyhow!("Invalid scenario {}: {}", path, e))?;
+    let server_url = scenario.server.clone().unwrap_or_else(|| args.server.clone());
+    info!("📜 Running Scenario {} ({} phases) against {}", scenario.name, scenario.phases.len(), server_url);
+
+    let counters = Arc::new(ClientCounters::default());
+    // Running clients, newest last, each with the flag that stops it
+    let mut clients: Vec<(Arc<AtomicBool>, tokio::task::JoinHandle<()>)> = Vec::new();
+    let mut stopping = Vec::new();
+    let mut next_id = 0u32;
+    let mut phases = Vec::with_capacity(scenario.phases.len());
+
+    for phase in &scenario.phases {
+        info!("▶️  Phase: {}", phase.name());
+        let started = Instant::now();
+        let before = counters.metrics(0);
+
+        let (target, over) = match *phase {
+            Phase::Ramp { clients, duration_secs } => (clients, Duration::from_secs(duration_secs)),
+            Phase::Hold { duration_secs } => (clients.len() as u32, Duration::from_secs(duration_secs)),
+            Phase::Spike { clients, duration_secs } => (clients, Duration::from_secs(duration_secs)),
+        };
+        let steps = target.abs_diff(clients.len() as u32);
+        let pace = match phase {
+            Phase::Ramp { .. } if steps > 0 => over / steps,
+            _ => Duration::ZERO,
+        };
+        for _ in 0..steps {
+            if (clients.len() as u32) < target {
+                let stop = Arc::new(AtomicBool::new(false));
+                let keep_playing = {
+                    let stop = stop.clone();
+                    move || !stop.load(Ordering::Relaxed)
+                };
+                clients.push((stop, spawn_client(next_id, server_url.clone(), counters.clone(), keep_playing)));
+                next_id += 1;
+            } else if let Some((stop, task)) = clients.pop() {
+                // The client finishes the game it is in before disconnecting
+                stop.store(true, Ordering::Relaxed);
+                stopping.push(task);
+            }
+            if !pace.is_zero() {
+                tokio::time::sleep(pace).await;
+            } else if next_id.is_multiple_of(100) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
             }
-            _ => {}
         }
+        tokio::time::sleep(over.saturating_sub(started.elapsed())).await;
+
+        let after = counters.metrics(0);
+        let report = PhaseReport {
+            phase: phase.name(),
+            target_clients: target,
+            connected_at_end: counters.current_connections.load(Ordering::Relaxed),
+            duration_secs: started.elapsed().as_secs_f64(),
+            failed_connections: after.failed_connections - before.failed_connections,
+            connection_drops: after.connection_drops - before.connection_drops,
+            completed_games: after.completed_games - before.completed_games,
+            abandoned_games: after.abandoned_games - before.abandoned_games,
+            protocol_violations: after.protocol_violations.total() - before.protocol_violations.total(),
+        };
+        info!(
+            "⏹️  {}: {} connected, {} failed, {} games completed, {} violations",
+            report.phase, report.connected_at_end, report.failed_connections, report.completed_games, report.protocol_violations
+        );
+        phases.push(report);
     }
-    
-    // Keep connection alive for duration
-    let end_time = Instant::now() + Duration::from_secs(duration_secs);
-    
-    while Instant::now() < end_time {
-        // Send periodic moves
-        let move_msg = json!({
-            "PlayerMove": {
-                "choice": match client_id % 3 {
-                    0 => "Rock",
-                    1 => "Paper", 
-                    _ => "Scissors"
+
+    // Let every client finish its game and disconnect
+    stopping.extend(clients.into_iter().map(|(stop, task)| {
+        stop.store(true, Ordering::Relaxed);
+        task
+    }));
+    let _ = timeout(Duration::from_secs(30), futures_util::future::join_all(stopping)).await;
+
+    info!("📊 Scenario {} Results:", scenario.name);
+    print_metrics(&counters.metrics(next_id));
+    print_scenario_summary(&phases);
+
+    if let Some(report_path) = &args.report {
+        let report = ScenarioReport {
+            scenario: scenario.name,
+            server: server_url,
+            phases,
+        };
+        std::fs::write(report_path, serde_json::to_string_pretty(&report)?)?;
+        info!("💾 Phase report written to {}", report_path);
+    }
+    Ok(())
+}
+
+fn print_scenario_summary(phases: &[PhaseReport]) {
+    println!("\n📜 SCENARIO PHASES");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("{:<24} | {:>9} | {:>7} | {:>6} | {:>6} | {:>10}", "Phase", "Connected", "Failed", "Drops", "Games", "Violations");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    for phase in phases {
+        println!(
+            "{:<24} | {:>9} | {:>7} | {:>6} | {:>6} | {:>10}",
+            phase.phase, phase.connected_at_end, phase.failed_connections, phase.connection_drops, phase.completed_games, phase.protocol_violations
+        );
+    }
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+}
+
+// How long a move may go unanswered before it counts as lost
+const OPEN_LOOP_REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Open-loop results: latency runs from when each move was *scheduled*, so a server
+/// that falls behind shows up in the tail instead of quietly slowing the senders down.
+#[derive(Debug, Default)]
+struct OpenLoopReport {
+    clients: u32,
+    failed_clients: u32,
+    connection_drops: u32,
+    scheduled: u64,
+    replies: u64,
+    rejected: u64,  // Moves answered with an error, e.g. sent between games
+    timed_out: u64, // No reply within OPEN_LOOP_REPLY_TIMEOUT
+    games: u64,
+    max_send_lag: Duration, // How far the generator itself fell behind its schedule
+    latencies_us: Vec<u64>,
+}
+
+impl OpenLoopReport {
+    fn merge(&mut self, other: OpenLoopReport) {
+        self.clients += other.clients;
+        self.connection_drops += other.connection_drops;
+        self.scheduled += other.scheduled;
+        self.replies += other.replies;
+        self.rejected += other.rejected;
+        self.timed_out += other.timed_out;
+        self.games += other.games;
+        self.max_send_lag = self.max_send_lag.max(other.max_send_lag);
+        self.latencies_us.extend(other.latencies_us);
+    }
+
+    fn percentile_ms(sorted_us: &[u64], percentile: f64) -> f64 {
+        if sorted_us.is_empty() {
+            return 0.0;
+        }
+        let rank = ((percentile / 100.0) * sorted_us.len() as f64).ceil() as usize;
+        sorted_us[rank.clamp(1, sorted_us.len()) - 1] as f64 / 1000.0
+    }
+}
+
+async fn run_open_loop_test(args: &Args) -> Result<()> {
+    if args.rate <= 0.0 {
+        error!("--rate must be above zero");
+        return Ok(());
+    }
+    info!(
+        "🎯 Running Open-Loop Test - {} clients at {:.1} moves/s each for {}s",
+        args.connections, args.rate, args.duration
+    );
+
+    let interval = Duration::from_secs_f64(1.0 / args.rate);
+    let duration = Duration::from_secs(args.duration);
+    let mut tasks = Vec::with_capacity(args.connections as usize);
+    for i in 0..args.connections {
+        let server_url = args.server.clone();
+        tasks.push(tokio::spawn(run_open_loop_client(i, server_url, interval, duration)));
+        if i % 100 == 99 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    let mut report = OpenLoopReport::default();
+    for task in tasks {
+        match task.await {
+            Ok(Ok(client)) => report.merge(client),
+            Ok(Err(e)) => {
+                if report.failed_clients % 1000 == 0 {
+                    error!("Open-loop client failed: {}", e);
                 }
+                report.failed_clients += 1;
             }
-        });
-        
-        if write.send(Message::Text(move_msg.to_string())).await.is_err() {
-            connection_drops.fetch_add(1, Ordering::Relaxed);
+            Err(e) => {
+                error!("Open-loop client panicked: {}", e);
+                report.failed_clients += 1;
+            }
+        }
+    }
+
+    print_open_loop_report(&mut report, args);
+    Ok(())
+}
+
+/// Plays bot games, sending a move every `interval` whether or not earlier moves were
+/// answered. Replies are matched to moves in order.
+async fn run_open_loop_client(client_id: u32, server_url: String, interval: Duration, duration: Duration) -> Result<OpenLoopReport> {
+    let mut client = GameClient::connect(ClientOptions {
+        url: server_url,
+        player_id: Some(format!("open_loop_client_{}", client_id)),
+        client_version: Some(format!("extreme-load-test/{}", env!("CARGO_PKG_VERSION"))),
+        ..ClientOptions::default()
+    })
+    .await?;
+    client.find_match(GameMode::Bot).await?;
+
+    let mut report = OpenLoopReport {
+        clients: 1,
+        ..OpenLoopReport::default()
+    };
+    let mut in_flight: VecDeque<tokio::time::Instant> = VecDeque::new();
+    let choices = [GameChoice::Rock, GameChoice::Paper, GameChoice::Scissors];
+
+    // Spread clients across the interval so they don't all send on the same tick
+    let offset = interval.mul_f64((client_id % 100) as f64 / 100.0);
+    let start = tokio::time::Instant::now() + offset;
+    let end = start + duration;
+    let mut ticker = tokio::time::interval_at(start, interval);
+    // Missed sends go out as soon as possible, still timed from their original slot
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Burst);
+
+    loop {
+        let now = tokio::time::Instant::now();
+        while in_flight.front().is_some_and(|scheduled| now - *scheduled > OPEN_LOOP_REPLY_TIMEOUT) {
+            in_flight.pop_front();
+            report.timed_out += 1;
+        }
+        let sending = now < end;
+        if !sending && in_flight.is_empty() {
             break;
         }
-        total_messages_sent.fetch_add(1, Ordering::Relaxed);
-        
-        // Try to read response
-        match timeout(Duration::from_millis(100), read.next()).await {
-            Ok(Some(Ok(Message::Text(_)))) => {
-                total_messages_received.fetch_add(1, Ordering::Relaxed);
+
+        let wait = end.saturating_duration_since(now) + OPEN_LOOP_REPLY_TIMEOUT;
+        tokio::select! {
+            scheduled = ticker.tick(), if sending => {
+                if scheduled >= end {
+                    continue;
+                }
+                report.scheduled += 1;
+                report.max_send_lag = report.max_send_lag.max(tokio::time::Instant::now() - scheduled);
+                let choice = choices[(report.scheduled % 3) as usize].clone();
+                if client.play(choice).await.is_err() {
+                    report.connection_drops += 1;
+                    break;
+                }
+                in_flight.push_back(scheduled);
             }
-            _ => {}
+            event = client.next_event_within(wait) => match event {
+                Ok(ServerMessage::RoundResult { .. }) => record_open_loop_reply(&mut report, &mut in_flight, false),
+                Ok(ServerMessage::Error { .. }) => record_open_loop_reply(&mut report, &mut in_flight, true),
+                Ok(ServerMessage::GameEnd { .. }) => {
+                    report.games += 1;
+                    // Not find_match: waiting for its reply here would stall the schedule
+                    if client.send(&ClientMessage::FindMatch { mode: GameMode::Bot, game: GameType::default(), template: None }).await.is_err() {
+                        report.connection_drops += 1;
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    report.connection_drops += 1;
+                    break;
+                }
+            },
         }
-        
-        tokio::time::sleep(Duration::from_millis(500)).await;
     }
-    
-    current_connections.fetch_sub(1, Ordering::Relaxed);
-    Ok(())
+
+    // Whatever was still unanswered when the connection went away never got a reply
+    report.timed_out += in_flight.len() as u64;
+    let _ = client.close().await;
+    Ok(report)
+}
+
+fn record_open_loop_reply(report: &mut OpenLoopReport, in_flight: &mut VecDeque<tokio::time::Instant>, rejected: bool) {
+    // An error with nothing in flight answered something other than a move
+    let Some(scheduled) = in_flight.pop_front() else { return };
+    report.replies += 1;
+    if rejected {
+        report.rejected += 1;
+    }
+    report.latencies_us.push(scheduled.elapsed().as_micros() as u64);
+}
+
+fn print_open_loop_report(report: &mut OpenLoopReport, args: &Args) {
+    report.latencies_us.sort_unstable();
+    let latencies = &report.latencies_us;
+    let target_rate = args.connections as f64 * args.rate;
+    let achieved_rate = report.replies as f64 / args.duration.max(1) as f64;
+
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🎯 OPEN-LOOP LOAD TEST RESULTS");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("👥 Clients: {} ok, {} failed, {} dropped", report.clients, report.failed_clients, report.connection_drops);
+    println!("📅 Target Rate: {:.0} moves/s", target_rate);
+    println!("📤 Moves Scheduled: {}", report.scheduled);
+    println!("📥 Replies: {} ({:.0}/s), {} rejected", report.replies, achieved_rate, report.rejected);
+    println!("⌛ Timed Out: {}", report.timed_out);
+    println!("🏁 Games Finished: {}", report.games);
+    println!(
+        "⏱️  Latency p50 {:.2}ms | p90 {:.2}ms | p99 {:.2}ms | p99.9 {:.2}ms | max {:.2}ms",
+        OpenLoopReport::percentile_ms(latencies, 50.0),
+        OpenLoopReport::percentile_ms(latencies, 90.0),
+        OpenLoopReport::percentile_ms(latencies, 99.0),
+        OpenLoopReport::percentile_ms(latencies, 99.9),
+        OpenLoopReport::percentile_ms(latencies, 100.0)
+    );
+    println!("🐢 Max Send Lag: {:.2}ms", report.max_send_lag.as_secs_f64() * 1000.0);
+    if report.max_send_lag > Duration::from_secs_f64(1.0 / args.rate) {
+        println!("⚠️  The load generator fell behind its own schedule; latencies include its backlog");
+    }
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 }
 
 fn print_metrics(metrics: &ExtremeTestMetrics) {
@@ -440,7 +1015,18 @@ fn print_metrics(metrics: &ExtremeTestMetrics) {
     println!("❌ Failed: {}", metrics.failed_connections);
     println!("📈 Peak Concurrent: {}", metrics.peak_concurrent);
     println!("🎮 Successful Matches: {}", metrics.successful_matches);
-    println!("🏁 Completed Games: {}", metrics.completed_games);
+    println!("🏁 Completed Games: {} (Abandoned: {})", metrics.completed_games, metrics.abandoned_games);
+    let violations = &metrics.protocol_violations;
+    println!("🚨 Protocol Violations: {}", violations.total());
+    if violations.total() > 0 {
+        println!("   Out of order: {}", violations.out_of_order);
+        println!("   Move mismatch: {}", violations.move_mismatch);
+        println!("   Wrong winner: {}", violations.wrong_winner);
+        println!("   Score mismatch: {}", violations.score_mismatch);
+        println!("   Final score mismatch: {}", violations.final_score_mismatch);
+        println!("   Rejected moves: {}", violations.rejected_moves);
+        println!("   Stalled: {}", violations.stalled);
+    }
     println!("📤 Messages Sent: {}", metrics.total_messages_sent);
     println!("📥 Messages Received: {}", metrics.total_messages_received);
     println!("💔 Connection Drops: {}", metrics.connection_drops);
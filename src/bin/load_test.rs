@@ -2,7 +2,6 @@ use anyhow::Result;
 use clap::{Arg, Command};
 use std::time::Duration;
 use tracing::{info, Level};
-use tracing_subscriber;
 
 use rps_server::tests::{test_concurrent_connections, test_connection_limits, LoadTestConfig, LoadTestRunner};
 
@@ -63,12 +62,12 @@ async fn main() -> Result<()> {
     match test_type.as_str() {
         "concurrent" => {
             info!("Testing {} concurrent connections", connections);
-            let metrics = test_concurrent_connections(connections).await?;
+            let metrics = test_concurrent_connections(&server_url, connections).await?;
             print_metrics(&metrics);
         }
         "limits" => {
             info!("Testing connection limits");
-            let results = test_connection_limits().await?;
+            let results = test_connection_limits(&server_url).await?;
             print_limit_results(&results);
         }
         "sustained" => {
@@ -76,8 +75,7 @@ async fn main() -> Result<()> {
             let config = LoadTestConfig {
                 concurrent_connections: connections,
                 test_duration: Duration::from_secs(duration),
-                server_url,
-                ..Default::default()
+                ..LoadTestConfig::new(server_url)
             };
             let runner = LoadTestRunner::new(config);
             let metrics = runner.run_load_test().await?;
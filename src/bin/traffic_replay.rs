@@ -0,0 +1,296 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+use rps_server::client::{ClientOptions, GameClient};
+use rps_server::domain::{ClientMessage, ServerMessage};
+use rps_server::infrastructure::RecordedFrame;
+
+#[derive(Parser, Debug)]
+#[command(name = "traffic-replay")]
+#[command(about = "Replays recorded client traffic against an RPS Game Server")]
+struct Args {
+    #[arg(short, long)]
+    recording: String, // JSON-lines file written by traffic_recording.path
+
+    #[arg(short, long, default_value = "ws://127.0.0.1:8080")]
+    server: String,
+
+    #[arg(long, default_value = "1.0")]
+    speed: f64, // 2.0 replays twice as fast as recorded
+
+    #[arg(long, default_value = "1000")]
+    linger_ms: u64, // How long each connection keeps reading after its last message
+
+    #[arg(long)]
+    report: Option<String>, // Write the results here as JSON
+
+    #[arg(long)]
+    baseline: Option<String>, // A report from an earlier run to compare against
+}
+
+/// What the server did with the replayed traffic. Two runs of the same recording
+/// against different builds should match in `replies` and `errors`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplayReport {
+    connections: usize,
+    failed_connections: usize,
+    messages_sent: u64,
+    replies: BTreeMap<String, u64>, // Server messages received, by type
+    errors: BTreeMap<String, u64>,  // Error messages, by code
+    latency_p50_ms: f64,            // From a message sent to the next one received
+    latency_p99_ms: f64,
+    latency_max_ms: f64,
+    wall_time_secs: f64,
+    #[serde(skip)]
+    latencies_us: Vec<u64>,
+}
+
+impl ReplayReport {
+    fn merge(&mut self, other: ReplayReport) {
+        self.connections += other.connections;
+        self.failed_connections += other.failed_connections;
+        self.messages_sent += other.messages_sent;
+        for (kind, count) in other.replies {
+            *self.replies.entry(kind).or_default() += count;
+        }
+        for (code, count) in other.errors {
+            *self.errors.entry(code).or_default() += count;
+        }
+        self.latencies_us.extend(other.latencies_us);
+    }
+
+    fn finish(&mut self, wall_time: Duration) {
+        self.latencies_us.sort_unstable();
+        let percentile = |p: f64| {
+            if self.latencies_us.is_empty() {
+                return 0.0;
+            }
+            let rank = ((p / 100.0) * self.latencies_us.len() as f64).ceil() as usize;
+            self.latencies_us[rank.clamp(1, self.latencies_us.len()) - 1] as f64 / 1000.0
+        };
+        self.latency_p50_ms = percentile(50.0);
+        self.latency_p99_ms = percentile(99.0);
+        self.latency_max_ms = percentile(100.0);
+        self.wall_time_secs = wall_time.as_secs_f64();
+    }
+
+    fn record_reply(&mut self, message: &ServerMessage) {
+        let kind = serde_json::to_value(message)
+            .ok()
+            .and_then(|value| value.get("type").and_then(|kind| kind.as_str()).map(str::to_string))
+            .unwrap_or_else(|| "unknown".to_string());
+        *self.replies.entry(kind).or_default() += 1;
+        if let ServerMessage::Error { code, .. } = message {
+            *self.errors.entry(code.clone().unwrap_or_else(|| "none".to_string())).or_default() += 1;
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt().with_max_level(tracing::Level::INFO).init();
+    let args = Args::parse();
+    if args.speed <= 0.0 {
+        anyhow::bail!("--speed must be above zero");
+    }
+
+    let connections = load_recording(&args.recording)?;
+    info!("🎬 Replaying {} connections from {} at {}x against {}", connections.len(), args.recording, args.speed, args.server);
+
+    let started = Instant::now();
+    let linger = Duration::from_millis(args.linger_ms);
+    let tasks: Vec<_> = connections
+        .into_iter()
+        .map(|(connection, frames)| {
+            let server = args.server.clone();
+            let speed = args.speed;
+            tokio::spawn(async move { replay_connection(connection, server, frames, started, speed, linger).await })
+        })
+        .collect();
+
+    let mut report = ReplayReport::default();
+    for task in tasks {
+        match task.await {
+            Ok(connection) => report.merge(connection),
+            Err(e) => error!("Replay task panicked: {}", e),
+        }
+    }
+    report.finish(started.elapsed());
+
+    print_report(&report);
+    if let Some(baseline) = &args.baseline {
+        let baseline: ReplayReport = serde_json::from_str(&std::fs::read_to_string(baseline)?)
+            .with_context(|| format!("Invalid baseline report {}", baseline))?;
+        print_comparison(&baseline, &report);
+    }
+    if let Some(path) = &args.report {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+        info!("💾 Report written to {}", path);
+    }
+    Ok(())
+}
+
+/// Frames grouped per connection, each list in recorded order.
+fn load_recording(path: &str) -> Result<BTreeMap<u64, Vec<RecordedFrame>>> {
+    let file = std::fs::File::open(path).with_context(|| format!("Opening recording {}", path))?;
+    let mut connections: BTreeMap<u64, Vec<RecordedFrame>> = BTreeMap::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<RecordedFrame>(&line) {
+            Ok(frame) => connections.entry(frame.connection).or_default().push(frame),
+            Err(e) => warn!("Skipping line {} of {}: {}", number + 1, path, e),
+        }
+    }
+    Ok(connections)
+}
+
+/// Opens a socket when the connection's first message is due and sends each message
+/// at its recorded offset, reading replies in between.
+async fn replay_connection(
+    connection: u64,
+    server: String,
+    frames: Vec<RecordedFrame>,
+    started: Instant,
+    speed: f64,
+    linger: Duration,
+) -> ReplayReport {
+    let mut report = ReplayReport {
+        connections: 1,
+        ..ReplayReport::default()
+    };
+    let due = |frame: &RecordedFrame| started + Duration::from_secs_f64(frame.at_ms as f64 / 1000.0 / speed);
+    let Some(first) = frames.first() else { return report };
+    tokio::time::sleep_until(due(first)).await;
+
+    // `open`, not `connect`: the recording carries the client's own Connect message
+    let mut client = match GameClient::open(ClientOptions {
+        url: server,
+        ..ClientOptions::default()
+    })
+    .await
+    {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Connection {} failed to open: {}", connection, e);
+            report.failed_connections = 1;
+            return report;
+        }
+    };
+
+    let mut awaiting_reply: Option<Instant> = None;
+    let mut frames = frames.into_iter().peekable();
+    let mut closing_at = None;
+    loop {
+        let next_due = match (frames.peek(), closing_at) {
+            (Some(frame), _) => due(frame),
+            (None, Some(closing_at)) => closing_at,
+            (None, None) => {
+                let at = Instant::now() + linger;
+                closing_at = Some(at);
+                at
+            }
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep_until(next_due) => {
+                let Some(frame) = frames.next() else { break };
+                let Some(message) = frame.message else {
+                    // Recorded disconnect; anything after it belongs to a reused number
+                    break;
+                };
+                if client.send(&message).await.is_err() {
+                    break;
+                }
+                report.messages_sent += 1;
+                awaiting_reply.get_or_insert_with(Instant::now);
+                if matches!(message, ClientMessage::Connect { .. }) {
+                    // Let Connected arrive before the next message, as the client did
+                    continue;
+                }
+            }
+            event = client.next_event_within(Duration::from_secs(3600)) => match event {
+                Ok(message) => {
+                    if let Some(sent) = awaiting_reply.take() {
+                        report.latencies_us.push(sent.elapsed().as_micros() as u64);
+                    }
+                    report.record_reply(&message);
+                }
+                Err(_) => break,
+            },
+        }
+    }
+
+    let _ = client.close().await;
+    report
+}
+
+fn print_report(report: &ReplayReport) {
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🎬 TRAFFIC REPLAY RESULTS");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🔗 Connections: {} ({} failed)", report.connections, report.failed_connections);
+    println!("📤 Messages Sent: {}", report.messages_sent);
+    for (kind, count) in &report.replies {
+        println!("📥 {}: {}", kind, count);
+    }
+    for (code, count) in &report.errors {
+        println!("❌ Error {}: {}", code, count);
+    }
+    println!(
+        "⏱️  Reply Latency p50 {:.2}ms | p99 {:.2}ms | max {:.2}ms",
+        report.latency_p50_ms, report.latency_p99_ms, report.latency_max_ms
+    );
+    println!("🕐 Wall Time: {:.1}s", report.wall_time_secs);
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+}
+
+/// Side by side with an earlier run; differing reply counts point at behavior changes.
+fn print_comparison(baseline: &ReplayReport, current: &ReplayReport) {
+    println!("\n🔍 COMPARED WITH BASELINE");
+    println!("{:<24} | {:>10} | {:>10} | {:>8}", "Reply", "Baseline", "Current", "Change");
+    let mut kinds: Vec<&String> = baseline.replies.keys().chain(current.replies.keys()).collect();
+    kinds.sort();
+    kinds.dedup();
+    let mut changed = 0;
+    for kind in kinds {
+        let before = baseline.replies.get(kind).copied().unwrap_or(0);
+        let after = current.replies.get(kind).copied().unwrap_or(0);
+        if before != after {
+            changed += 1;
+        }
+        println!("{:<24} | {:>10} | {:>10} | {:>+8}", kind, before, after, after as i64 - before as i64);
+    }
+    let errors: HashMap<&String, (u64, u64)> = baseline
+        .errors
+        .iter()
+        .map(|(code, count)| (code, (*count, current.errors.get(code).copied().unwrap_or(0))))
+        .chain(current.errors.iter().map(|(code, count)| (code, (baseline.errors.get(code).copied().unwrap_or(0), *count))))
+        .collect();
+    let mut errors: Vec<_> = errors.into_iter().collect();
+    errors.sort();
+    for (code, (before, after)) in errors {
+        if before != after {
+            changed += 1;
+        }
+        println!("{:<24} | {:>10} | {:>10} | {:>+8}", format!("error {}", code), before, after, after as i64 - before as i64);
+    }
+    println!(
+        "{:<24} | {:>9.2}ms | {:>9.2}ms |",
+        "latency p99", baseline.latency_p99_ms, current.latency_p99_ms
+    );
+    if changed == 0 {
+        println!("✅ Same replies as the baseline");
+    } else {
+        println!("⚠️  {} reply counts differ from the baseline", changed);
+    }
+}
@@ -0,0 +1,345 @@
+use anyhow::{bail, Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::{timeout, timeout_at, Instant};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::{debug, warn};
+
+use crate::domain::{ClientMessage, GameChoice, GameMode, GameType, RuleSet, ServerMessage};
+
+type Stream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    pub url: String,
+    pub player_id: Option<String>, // Server assigns one when None; kept for reconnects either way
+    pub locale: Option<String>,
+    pub display_name: Option<String>,
+    pub client_version: Option<String>,
+    pub connect_timeout: Duration,  // Socket upgrade plus the `Connected` reply
+    pub response_timeout: Duration, // Longest wait in `next_event` and for direct replies
+    pub reconnect_attempts: u32,
+    pub reconnect_delay: Duration, // Doubles after each failed attempt
+    pub auto_reconnect: bool,      // Reconnect inside `next_event` when the socket drops or the room migrates
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            url: "ws://127.0.0.1:8080".to_string(),
+            player_id: None,
+            locale: None,
+            display_name: None,
+            client_version: Some(format!("rps-client/{}", env!("CARGO_PKG_VERSION"))),
+            connect_timeout: Duration::from_secs(10),
+            response_timeout: Duration::from_secs(30),
+            reconnect_attempts: 3,
+            reconnect_delay: Duration::from_millis(500),
+            auto_reconnect: false,
+        }
+    }
+}
+
+/// An `error` message from the server, returned by the request methods.
+/// Downcast the `anyhow::Error` to inspect the code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerError {
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "server error {}: {}", code, self.message),
+            None => write!(f, "server error: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ServerError {}
+
+/// Typed WebSocket client for the game protocol.
+///
+/// Messages that arrive while a request method waits for its reply are kept and
+/// handed out by `next_event` in arrival order, so nothing is lost.
+pub struct GameClient {
+    options: ClientOptions,
+    stream: Stream,
+    player_id: String,
+    pending: VecDeque<ServerMessage>,
+    reconnect_token: Option<String>, // Latest `reconnectToken` from the server
+    resume_token: Option<String>,    // From `Connected`; proves who we are when there is no reconnect token
+    node_url: Option<String>,        // Node that holds our game, when it advertises one
+}
+
+impl GameClient {
+    /// Opens the socket and completes the `Connect` handshake.
+    pub async fn connect(options: ClientOptions) -> Result<Self> {
+        let (stream, player_id, resume_token) = Self::handshake(&options, &options.url, options.player_id.clone(), None).await?;
+        Ok(Self {
+            options,
+            stream,
+            player_id,
+            pending: VecDeque::new(),
+            reconnect_token: None,
+            resume_token,
+            node_url: None,
+        })
+    }
+
+    /// Opens the socket without sending `Connect`, for driving the protocol by hand,
+    /// e.g. to check how the server answers messages sent out of order.
+    pub async fn open(options: ClientOptions) -> Result<Self> {
+        let (stream, _) = timeout(options.connect_timeout, connect_async(&options.url))
+            .await
+            .with_context(|| format!("Connecting to {} timed out", options.url))??;
+        Ok(Self {
+            player_id: options.player_id.clone().unwrap_or_default(),
+            options,
+            stream,
+            pending: VecDeque::new(),
+            reconnect_token: None,
+            resume_token: None,
+            node_url: None,
+        })
+    }
+
+    pub fn player_id(&self) -> &str {
+        &self.player_id
+    }
+
+    pub async fn send(&mut self, message: &ClientMessage) -> Result<()> {
+        self.send_text(serde_json::to_string(message)?).await
+    }
+
+    /// Sends a text frame as is; for messages `ClientMessage` can't express, like malformed JSON.
+    pub async fn send_text(&mut self, text: String) -> Result<()> {
+        self.stream.send(Message::Text(text)).await.context("Failed to send message")
+    }
+
+    /// Joins matchmaking and returns the `matchmaking` reply.
+    pub async fn find_match(&mut self, mode: GameMode) -> Result<ServerMessage> {
+        self.find_game(mode, GameType::default()).await
+    }
+
+    /// Like [`Self::find_match`], for a game other than rock-paper-scissors.
+    pub async fn find_game(&mut self, mode: GameMode, game: GameType) -> Result<ServerMessage> {
+        self.send(&ClientMessage::FindMatch { mode, game, template: None }).await?;
+        self.reply(|message| matches!(message, ServerMessage::Matchmaking { .. })).await
+    }
+
+    /// Like [`Self::find_match`], on the operator's room template `template`.
+    pub async fn find_on_template(&mut self, template: &str) -> Result<ServerMessage> {
+        let template = Some(template.to_string());
+        self.send(&ClientMessage::FindMatch { mode: GameMode::default(), game: GameType::default(), template }).await?;
+        self.reply(|message| matches!(message, ServerMessage::Matchmaking { .. })).await
+    }
+
+    /// Opens a room for this player and a guest, returning the `invite` reply with its link.
+    pub async fn create_invite(&mut self) -> Result<ServerMessage> {
+        self.send(&ClientMessage::CreateInvite { template: None, rules: None }).await?;
+        self.reply(|message| matches!(message, ServerMessage::Invite { .. })).await
+    }
+
+    /// Like [`Self::create_invite`], for a room played by `rules` instead of the classic ones.
+    pub async fn create_invite_with_rules(&mut self, rules: RuleSet) -> Result<ServerMessage> {
+        self.send(&ClientMessage::CreateInvite { template: None, rules: Some(rules) }).await?;
+        self.reply(|message| matches!(message, ServerMessage::Invite { .. })).await
+    }
+
+    /// Joins a pre-created room by id or join code and returns the `matchmaking` reply.
+    pub async fn join_room(&mut self, room: &str) -> Result<ServerMessage> {
+        self.send(&ClientMessage::JoinRoom { room: room.to_string() }).await?;
+        self.reply(|message| matches!(message, ServerMessage::Matchmaking { .. })).await
+    }
+
+    /// Submits a move. The outcome arrives later as a `roundResult` from `next_event`.
+    pub async fn play(&mut self, choice: GameChoice) -> Result<()> {
+        self.send(&ClientMessage::PlayerMove { choice }).await
+    }
+
+    pub async fn request_pause(&mut self) -> Result<()> {
+        self.send(&ClientMessage::PauseRequest).await
+    }
+
+    pub async fn request_resume(&mut self) -> Result<()> {
+        self.send(&ClientMessage::ResumeRequest).await
+    }
+
+    /// Answers a `backfillOffer`; the `matchmaking` reply arrives from `next_event`.
+    pub async fn answer_backfill(&mut self, accept: bool) -> Result<()> {
+        self.send(&ClientMessage::BackfillResponse { accept }).await
+    }
+
+    /// Sends a chat line to the room; it comes back, maybe masked, as a `chat` from `next_event`.
+    pub async fn chat(&mut self, text: &str) -> Result<()> {
+        self.send(&ClientMessage::Chat { text: text.to_string() }).await
+    }
+
+    /// Asks for the server's view of this player and returns the `state` reply.
+    pub async fn request_state(&mut self) -> Result<ServerMessage> {
+        self.send(&ClientMessage::RequestState).await?;
+        self.reply(|message| matches!(message, ServerMessage::State { .. })).await
+    }
+
+    pub async fn ack_notifications(&mut self, ids: Vec<String>) -> Result<()> {
+        self.send(&ClientMessage::AckNotifications { ids }).await
+    }
+
+    /// The next server message, waiting up to the response timeout.
+    pub async fn next_event(&mut self) -> Result<ServerMessage> {
+        let wait = self.options.response_timeout;
+        self.next_event_within(wait).await
+    }
+
+    pub async fn next_event_within(&mut self, wait: Duration) -> Result<ServerMessage> {
+        if let Some(message) = self.pending.pop_front() {
+            return Ok(message);
+        }
+
+        let deadline = Instant::now() + wait;
+        loop {
+            match timeout_at(deadline, read_message(&mut self.stream)).await {
+                Ok(Ok(message)) => {
+                    self.observe(&message);
+                    if matches!(message, ServerMessage::RoomMigrated { .. }) && self.options.auto_reconnect {
+                        // Follow the game to its new node before this one goes away
+                        self.reconnect().await?;
+                    }
+                    return Ok(message);
+                }
+                Ok(Err(e)) if self.options.auto_reconnect => {
+                    warn!("Connection lost ({}); reconnecting as {}", e, self.player_id);
+                    self.reconnect().await?;
+                }
+                Ok(Err(e)) => return Err(e),
+                Err(_) => bail!("No message from server within {:?}", wait),
+            }
+        }
+    }
+
+    /// Opens a fresh socket under the same player id, so the server resumes a paused game.
+    /// In cluster mode this goes straight to the node named by the last reconnect token,
+    /// falling back to the configured URL when that node can't be reached.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        // Wait for the server to let go of the old socket; otherwise its disconnect
+        // handling could pause the game again after the new socket resumed it
+        let _ = self.stream.close(None).await;
+        let drained = async { while let Some(Ok(_)) = self.stream.next().await {} };
+        let _ = timeout(self.options.connect_timeout, drained).await;
+
+        let mut delay = self.options.reconnect_delay;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let token = self.reconnect_token.clone().or_else(|| self.resume_token.clone());
+            let mut result = Err(anyhow::anyhow!("No node to reconnect to"));
+            if let Some(node_url) = self.node_url.clone() {
+                result = Self::handshake(&self.options, &node_url, Some(self.player_id.clone()), token.clone()).await;
+                if let Err(e) = &result {
+                    debug!("Node {} unreachable ({}); falling back to {}", node_url, e, self.options.url);
+                }
+            }
+            if result.is_err() {
+                result = Self::handshake(&self.options, &self.options.url, Some(self.player_id.clone()), token).await;
+            }
+            match result {
+                Ok((stream, _, resume_token)) => {
+                    self.stream = stream;
+                    self.resume_token = resume_token;
+                    return Ok(());
+                }
+                Err(e) if attempt > self.options.reconnect_attempts => {
+                    return Err(e.context(format!("Reconnect failed after {} attempts", attempt)));
+                }
+                Err(e) => {
+                    debug!("Reconnect attempt {} failed: {}", attempt, e);
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+            }
+        }
+    }
+
+    pub async fn close(mut self) -> Result<()> {
+        self.stream.close(None).await.context("Failed to close connection")
+    }
+
+    /// The token the next reconnect presents, if the server has issued one.
+    pub fn reconnect_token(&self) -> Option<&str> {
+        self.reconnect_token.as_deref()
+    }
+
+    fn observe(&mut self, message: &ServerMessage) {
+        if let ServerMessage::ReconnectToken { token, node_url, .. } = message {
+            self.reconnect_token = Some(token.clone());
+            self.node_url = node_url.clone();
+        }
+    }
+
+    async fn handshake(
+        options: &ClientOptions,
+        url: &str,
+        player_id: Option<String>,
+        reconnect_token: Option<String>,
+    ) -> Result<(Stream, String, Option<String>)> {
+        let handshake = async {
+            let (mut stream, _) = connect_async(url).await?;
+            let connect = ClientMessage::Connect {
+                player_id,
+                locale: options.locale.clone(),
+                client_version: options.client_version.clone(),
+                protocol_version: None, // Reads one message per frame
+                reconnect_token,
+                display_name: options.display_name.clone(),
+                region: None,
+                experiments: Vec::new(),
+                tenant: None,
+                invite: None,
+            };
+            stream.send(Message::Text(serde_json::to_string(&connect)?)).await?;
+
+            match read_message(&mut stream).await? {
+                ServerMessage::Connected { player_id, resume_token, .. } => Ok((stream, player_id, resume_token)),
+                ServerMessage::Error { code, message, .. } => Err(ServerError { code, message }.into()),
+                other => bail!("Expected connected, got {:?}", other),
+            }
+        };
+        timeout(options.connect_timeout, handshake)
+            .await
+            .with_context(|| format!("Connecting to {} timed out", url))?
+    }
+
+    /// Waits for the first message matching `is_reply`, queueing everything else.
+    /// An `error` message fails the request instead.
+    async fn reply(&mut self, is_reply: impl Fn(&ServerMessage) -> bool) -> Result<ServerMessage> {
+        let wait = self.options.response_timeout;
+        let read = async {
+            loop {
+                let message = read_message(&mut self.stream).await?;
+                self.observe(&message);
+                match message {
+                    ServerMessage::Error { code, message, .. } => return Err(ServerError { code, message }.into()),
+                    message if is_reply(&message) => return Ok(message),
+                    message => self.pending.push_back(message),
+                }
+            }
+        };
+        timeout(wait, read).await.with_context(|| format!("No reply from server within {:?}", wait))?
+    }
+}
+
+async fn read_message(stream: &mut Stream) -> Result<ServerMessage> {
+    loop {
+        match stream.next().await.context("Connection closed")?? {
+            Message::Text(text) => return serde_json::from_str(&text).with_context(|| format!("Unexpected message: {}", text)),
+            Message::Close(_) => bail!("Connection closed by server"),
+            _ => continue, // Pings are answered by tungstenite
+        }
+    }
+}
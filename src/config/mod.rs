@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::collections::HashMap;
+
+pub mod secrets;
+
+pub use secrets::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
@@ -7,6 +11,62 @@ pub struct ServerConfig {
     pub rest_api: RestApiConfig,
     pub game: GameConfig,
     pub performance: PerformanceConfig,
+    pub admin: AdminConfig,
+    pub secrets: SecretsConfig,
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+    #[serde(default)]
+    pub presence: PresenceConfig,
+    #[serde(default)]
+    pub bot_detection: BotDetectionConfig,
+    #[serde(default)]
+    pub i18n: I18nConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub long_poll: LongPollConfig,
+    #[serde(default)]
+    pub matchmaking: MatchmakingConfig,
+    #[serde(default)]
+    pub game_history: GameHistoryConfig,
+    #[serde(default)]
+    pub ratings: RatingsConfig,
+    #[serde(default)]
+    pub seasons: SeasonsConfig,
+    #[serde(default)]
+    pub spam_guard: SpamGuardConfig,
+    #[serde(default)]
+    pub capacity: CapacityConfig,
+    #[serde(default)]
+    pub traffic_recording: TrafficRecordingConfig,
+    #[serde(default)]
+    pub cluster: ClusterConfig,
+    #[serde(default)]
+    pub stats_export: StatsExportConfig,
+    #[serde(default)]
+    pub blitz: BlitzConfig,
+    #[serde(default)]
+    pub series: SeriesConfig,
+    #[serde(default)]
+    pub titles: TitlesConfig,
+    #[serde(default)]
+    pub player_stats: PlayerStatsConfig,
+    #[serde(default)]
+    pub scripted_rules: ScriptedRulesConfig,
+    #[serde(default)]
+    pub room_templates: RoomTemplatesConfig,
+    #[serde(default)]
+    pub activity: ActivityConfig,
+    #[serde(default)]
+    pub profanity: ProfanityConfig,
+    #[serde(default)]
+    pub lifetime_stats: LifetimeStatsConfig,
+    #[serde(default)]
+    pub quotas: QuotasConfig,
+    #[serde(default)]
+    pub quit_penalty: QuitPenaltyConfig,
+    #[serde(default)]
+    pub invites: InvitesConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,12 +79,160 @@ pub struct WebSocketConfig {
     pub keepalive_interval_ms: u64,
     pub max_frame_size: usize,
     pub max_message_size: usize,
+    pub slow_client_max_queue: usize, // Outbound messages queued before a client counts as slow
+    pub slow_client_timeout_ms: u64,  // How long a client may stay slow before eviction
+    #[serde(default)]
+    pub socket_io_compat: bool, // Speak Socket.IO framing on /socket.io/ upgrades (WebSocket transport only)
+    #[serde(default)]
+    pub listeners: Vec<ListenerConfig>, // Empty: a single plaintext listener on host:port
+    #[serde(default)]
+    pub outbound_batch: OutboundBatchConfig, // Only for native clients on protocol version 2
+    #[serde(default)]
+    pub json_parser: JsonParser, // Inbound message parser; compare on your hardware with `codec_bench`
+    #[serde(default)]
+    pub writer_shards: Option<usize>, // Tasks writing to sockets, each serving many connections; None: one per runtime worker
+    #[serde(default)]
+    pub fd_limit: FdLimitPolicy, // What to do when max_connections exceeds the open file limit
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FdLimitPolicy {
+    #[default]
+    Adjust, // Raise the soft limit to the hard one, then lower connection caps that still don't fit
+    Warn,   // Log the mismatch and keep the configured caps
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JsonParser {
+    #[default]
+    SerdeJson,
+    SimdJson, // Falls back to serde_json for any frame it can't decode
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutboundBatchConfig {
+    pub max_messages: usize, // Messages per batched frame; 1 turns batching off
+    pub max_delay_ms: u64,   // How long a backed-up queue may wait to fill a batch; 0 sends what's queued
+}
+
+impl Default for OutboundBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_messages: 32,
+            max_delay_ms: 2,
+        }
+    }
+}
+
+impl WebSocketConfig {
+    /// Configured listeners, or the single default one on `host:port`.
+    pub fn effective_listeners(&self) -> Vec<ListenerConfig> {
+        if !self.listeners.is_empty() {
+            return self.listeners.clone();
+        }
+        vec![ListenerConfig {
+            name: "default".to_string(),
+            host: self.host.clone(),
+            port: self.port,
+            max_connections: None,
+            tls: None,
+        }]
+    }
+}
+
+/// One WebSocket listening socket; every listener feeds the same `GameManager`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerConfig {
+    pub name: String, // Label in logs and metrics, e.g. "public" or "internal"
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub max_connections: Option<usize>, // Defaults to websocket.max_connections
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub cert_path: String, // PEM certificate chain
+    pub key_path: String,  // PEM private key (PKCS#8, RSA or SEC1)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RestApiConfig {
     pub host: String,
     pub port: u16,
+    #[serde(default = "enabled")]
+    pub serve_demo_client: bool, // Bundled browser client at /demo/
+    #[serde(default)]
+    pub share_websocket_port: bool, // Serve on the WebSocket listeners instead of `port`, for hosts exposing one port
+    #[serde(default = "enabled")]
+    pub http2: bool, // Accept HTTP/2 (cleartext, prior knowledge) as well as HTTP/1.1
+    #[serde(default = "default_keep_alive_timeout_ms")]
+    pub keep_alive_timeout_ms: u64, // Idle HTTP/1.1 connections are closed after this long; 0 turns keep-alive off
+    #[serde(default = "default_max_concurrent_streams")]
+    pub max_concurrent_streams: u32, // Per HTTP/2 connection
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: u64, // Requests declaring a bigger body get 413
+    #[serde(default = "default_cache_ttl_ms")]
+    pub cache_ttl_ms: u64, // How long /stats and the current leaderboard are reused; 0 computes every request
+    #[serde(default)]
+    pub rate_limit: RestRateLimitConfig,
+}
+
+/// Per-client request limits on the REST API. A client is its API key when it presents a
+/// valid one, otherwise its IP address; over the limit it gets 429 with Retry-After.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestRateLimitConfig {
+    pub enabled: bool,
+    pub window_ms: u64,
+    pub max_requests: usize,           // Per client per window, across every route
+    pub max_expensive_requests: usize, // Per client per window, across `expensive_paths`
+    pub expensive_paths: Vec<String>,  // Path prefixes of routes that walk stats, leaderboards or replays
+    pub trust_forwarded_for: bool,     // Take the client IP from X-Forwarded-For, behind a trusted proxy only
+    #[serde(default = "trusted_proxy_hops")]
+    pub trusted_proxy_hops: usize, // Proxies in front that each append to X-Forwarded-For; the client is that many entries from the right
+}
+
+fn trusted_proxy_hops() -> usize {
+    1
+}
+
+impl Default for RestRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_ms: 60_000,
+            max_requests: 600,
+            max_expensive_requests: 120,
+            expensive_paths: vec!["/stats".to_string(), "/seasons".to_string(), "/games".to_string(), "/players".to_string()],
+            trust_forwarded_for: false,
+            trusted_proxy_hops: trusted_proxy_hops(),
+        }
+    }
+}
+
+fn enabled() -> bool {
+    true
+}
+
+fn default_keep_alive_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_max_concurrent_streams() -> u32 {
+    64
+}
+
+fn default_max_body_bytes() -> u64 {
+    64 * 1024
+}
+
+fn default_cache_ttl_ms() -> u64 {
+    1_000
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +242,662 @@ pub struct GameConfig {
     pub max_players: usize,
     pub move_timeout_ms: u64,
     pub cleanup_interval_ms: u64,
+    pub match_time_limit_ms: Option<u64>,
+    pub reconnect_grace_ms: u64,
+    pub max_pause_ms: u64,
+    #[serde(default)]
+    pub draw_policy: crate::domain::DrawPolicy,
+    #[serde(default)]
+    pub move_changes: crate::domain::MoveChangePolicy, // Whether a second move in a round replaces the first
+    #[serde(default)]
+    pub rules: crate::domain::RuleSet,
+    #[serde(default = "crate::domain::max_latency_compensation_ms")]
+    pub max_latency_compensation_ms: u64, // Higher round trips are only compensated up to this
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminConfig {
+    pub audit_log_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhooksConfig {
+    pub endpoints: Vec<WebhookEndpointConfig>,
+    pub max_retries: u32,
+    pub initial_backoff_ms: u64, // Doubles after every failed attempt
+    pub request_timeout_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpointConfig {
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<String>, // Event kinds to deliver, e.g. "GameEnded"; empty = all
+    pub signing_secret: SecretSource,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            max_retries: 5,
+            initial_backoff_ms: 500,
+            request_timeout_ms: 5000,
+        }
+    }
+}
+
+/// External rich-presence service that receives batched player state changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresenceConfig {
+    pub push_url: Option<String>, // None disables pushing
+    pub auth_token: SecretSource, // Sent as a bearer token
+    pub flush_interval_ms: u64,   // At most one request per interval
+    pub max_batch_size: usize,
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        Self {
+            push_url: None,
+            auth_token: SecretSource {
+                env: Some("RPS_PRESENCE_TOKEN".to_string()),
+                file: None,
+            },
+            flush_interval_ms: 2000,
+            max_batch_size: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BotDetectionConfig {
+    pub enabled: bool,
+    pub min_samples: usize,         // Moves needed before a signal is scored
+    pub window: usize,              // Most recent moves kept per player
+    pub human_timing_cv: f64,       // Reaction time variation at or above this scores 0
+    pub suspicion_threshold: f64,   // Score at which a player is flagged
+    pub separate_pool: bool,        // Match flagged players only with each other
+}
+
+impl Default for BotDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_samples: 10,
+            window: 50,
+            human_timing_cv: 0.15,
+            suspicion_threshold: 0.8,
+            separate_pool: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatchmakingConfig {
+    pub abandoned_match: AbandonedMatchPolicy, // For players whose opponent left mid-game
+    pub shadow: ShadowMatchmakingConfig,
+    pub backfill_reserved_rooms: bool, // Offer a reserved room's lost seat to the waiting queue, with the seated players' consent
+    pub max_queue_len: usize,          // Players each matchmaking queue holds
+    pub queue_overflow: QueueOverflowPolicy,
+    pub starvation: StarvationConfig,
+}
+
+impl Default for MatchmakingConfig {
+    fn default() -> Self {
+        Self {
+            abandoned_match: AbandonedMatchPolicy::default(),
+            shadow: ShadowMatchmakingConfig::default(),
+            backfill_reserved_rooms: false,
+            max_queue_len: 10000,
+            queue_overflow: QueueOverflowPolicy::default(),
+            starvation: StarvationConfig::default(),
+        }
+    }
+}
+
+/// When a queued player counts as starved, and what's done about it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StarvationConfig {
+    pub slo_ms: u64,         // Longest acceptable wait in a matchmaking queue; past it a `QueueStarved` event goes out
+    pub relax_pairing: bool, // Let starved players out of the pools that keep them apart, e.g. the suspected-bot pool
+}
+
+impl Default for StarvationConfig {
+    fn default() -> Self {
+        Self {
+            slo_ms: 30000,
+            relax_pairing: true,
+        }
+    }
+}
+
+/// What a full matchmaking queue does with one more player.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueueOverflowPolicy {
+    #[default]
+    Reject,         // Refuse the newcomer with ServerBusy
+    BotMatchOldest, // Queue the newcomer and move the longest-waiting player into a bot game
+}
+
+/// A second pairing strategy evaluated against live traffic without affecting real matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShadowMatchmakingConfig {
+    pub strategy: Option<ShadowStrategy>, // None disables the experiment
+    pub rating_band: f64,                 // Largest ELO gap `ratingBand` pairs right away
+    pub rating_band_widen_per_sec: f64,   // Added to the band per second the opponent has waited
+    pub report_interval_ms: u64,          // How often divergence metrics are logged
+}
+
+impl Default for ShadowMatchmakingConfig {
+    fn default() -> Self {
+        Self {
+            strategy: None,
+            rating_band: 100.0,
+            rating_band_widen_per_sec: 10.0,
+            report_interval_ms: 60000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShadowStrategy {
+    Fifo,       // Same as live matchmaking; a sanity check that should never diverge
+    RatingBand, // Closest built-in ELO rating within a widening band
+}
+
+/// What happens to the players left behind when an opponent's disconnect ends a game.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AbandonedMatchPolicy {
+    #[default]
+    Off,     // They queue again themselves
+    Requeue, // Back into their queue ahead of everyone else
+    Bot,     // Straight into a bot game
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameHistoryConfig {
+    pub max_finished_games: usize, // Oldest records are dropped past this; 0 keeps none
+    #[serde(default)]
+    pub archive: Option<ReplayArchiveConfig>, // Where dropped and aged-out games go instead
+}
+
+impl Default for GameHistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_finished_games: 10000,
+            archive: None,
+        }
+    }
+}
+
+/// Finished games moved out of memory into compressed batches in a bucket, still served
+/// by `GET /games/{id}` and its replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayArchiveConfig {
+    pub object_store: ObjectStoreConfig,
+    #[serde(default = "archive_after_days")]
+    pub archive_after_days: u32, // Games that ended longer ago leave memory even below capacity
+    #[serde(default = "archive_batch_size")]
+    pub batch_size: usize, // Games per uploaded object
+    #[serde(default = "archive_sweep_interval_ms")]
+    pub sweep_interval_ms: u64,
+}
+
+fn archive_after_days() -> u32 {
+    7
+}
+
+fn archive_batch_size() -> usize {
+    1000
+}
+
+fn archive_sweep_interval_ms() -> u64 {
+    600_000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RatingsConfig {
+    pub provider_url: Option<String>, // External rating service; None uses the built-in ELO
+    pub auth_token: SecretSource,     // Sent as a bearer token to the external service
+    pub request_timeout_ms: u64,
+    pub elo_k_factor: f64,
+    pub max_pending: usize,           // Games queued while the provider is down; oldest dropped past this
+    pub initial_backoff_ms: u64,      // Doubles after each failed attempt
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RatingsConfig {
+    fn default() -> Self {
+        Self {
+            provider_url: None,
+            auth_token: SecretSource {
+                env: Some("RPS_RATINGS_TOKEN".to_string()),
+                file: None,
+            },
+            request_timeout_ms: 5000,
+            elo_k_factor: 32.0,
+            max_pending: 10000,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 60000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeasonsConfig {
+    pub length_days: u32,             // Built-in ratings reset after this long; 0 never rolls over
+    pub archive_path: Option<String>, // JSON-lines file of finished seasons; None keeps them in memory only
+    pub leaderboard_size: usize,      // Rows served for the current season
+}
+
+impl Default for SeasonsConfig {
+    fn default() -> Self {
+        Self {
+            length_days: 30,
+            archive_path: Some("data/seasons.jsonl".to_string()),
+            leaderboard_size: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerStatsConfig {
+    pub max_batch: usize, // Player ids accepted by one `POST /players/stats:batch`
+}
+
+impl Default for PlayerStatsConfig {
+    fn default() -> Self {
+        Self { max_batch: 200 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapacityConfig {
+    pub max_rooms: usize,           // Live rooms, waiting and running; FindMatch is refused beyond it
+    pub max_players: Option<usize>, // Players queued or seated in a room; defaults to websocket.max_connections
+    pub retry_after_ms: u64,        // Suggested back-off sent with ServerBusy
+}
+
+/// Inbound client messages captured for replay against another build with `traffic_replay`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrafficRecordingConfig {
+    pub path: Option<String>, // JSON-lines file; None records nothing
+    #[serde(default)]
+    pub max_frames: Option<u64>, // Recording stops after this many frames
+}
+
+/// Periodic dumps of finished games and per-day aggregates, for analysts who don't get
+/// access to the server itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsExportConfig {
+    pub path: Option<String>, // Local directory; None with no object store exports nothing
+    #[serde(default)]
+    pub object_store: Option<ObjectStoreConfig>, // Upload here instead of writing to `path`
+    pub format: ExportFormat,
+    pub interval_ms: u64,
+}
+
+impl Default for StatsExportConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            object_store: None,
+            format: ExportFormat::default(),
+            interval_ms: 3_600_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    JsonLines,
+    Parquet,
+}
+
+/// A bucket on S3 or anything speaking its API (MinIO, R2, ...), addressed path-style.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectStoreConfig {
+    pub endpoint: String, // e.g. "https://s3.eu-west-1.amazonaws.com" or "http://minio:9000"
+    pub bucket: String,
+    pub region: String,
+    #[serde(default)]
+    pub prefix: String, // Prepended to every object key
+    pub access_key_id: SecretSource,
+    pub secret_access_key: SecretSource,
+    #[serde(default = "object_store_timeout_ms")]
+    pub request_timeout_ms: u64,
+}
+
+fn object_store_timeout_ms() -> u64 {
+    30000
+}
+
+/// Other instances of this server, sharing presence and matchmaking. Peers come from a
+/// static list; every node should list every other one, since each only sends over the
+/// links it dialed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterConfig {
+    pub node_id: Option<String>,   // Generated at startup when unset
+    pub peers: Vec<String>,        // Peer WebSocket URLs, e.g. "ws://10.0.0.2:8080"; empty runs standalone
+    pub secret: SecretSource,      // Token presented when dialing a peer; peers accept any loaded key
+    pub heartbeat_interval_ms: u64,
+    pub peer_timeout_ms: u64,      // A peer silent this long is treated as down
+    pub rejoin_window_ms: u64,     // How long a disconnected player's game stays routed to its host node
+    #[serde(default)]
+    pub advertise_url: Option<String>, // This node's client-facing URL, handed out in reconnect tokens
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            node_id: None,
+            peers: Vec::new(),
+            secret: SecretSource {
+                env: Some("RPS_CLUSTER_SECRET".to_string()),
+                file: None,
+            },
+            heartbeat_interval_ms: 1000,
+            peer_timeout_ms: 5000,
+            rejoin_window_ms: 60000,
+            advertise_url: None,
+        }
+    }
+}
+
+impl Default for CapacityConfig {
+    fn default() -> Self {
+        Self {
+            max_rooms: 10000,
+            max_players: None,
+            retry_after_ms: 5000,
+        }
+    }
+}
+
+/// Blitz rooms, which override the game's round count and play against a round timer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlitzConfig {
+    pub rounds: u32,
+    pub round_window_ms: u64, // A player who hasn't moved when it closes loses the round
+}
+
+impl Default for BlitzConfig {
+    fn default() -> Self {
+        Self {
+            rounds: 15,
+            round_window_ms: 3000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesConfig {
+    pub max_best_of: u32,
+    pub next_game_delay_ms: u64, // Between one game's end and the next game's start
+    pub max_finished_series: usize, // Kept for lookups; the oldest are forgotten first
+}
+
+impl Default for SeriesConfig {
+    fn default() -> Self {
+        Self {
+            max_best_of: 9,
+            next_game_delay_ms: 5000,
+            max_finished_series: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpamGuardConfig {
+    pub enabled: bool,
+    pub max_per_window: usize, // Rematch, challenge, or chat requests of one kind allowed per window
+    pub window_ms: u64,
+    pub base_mute_ms: u64,     // First mute; each further strike doubles it
+    pub max_mute_ms: u64,
+    pub strike_decay_ms: u64,  // Strikes are forgiven after this long without a new one
+}
+
+impl Default for SpamGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_per_window: 5,
+            window_ms: 60000,
+            base_mute_ms: 60000,
+            max_mute_ms: 3600000,
+            strike_decay_ms: 86400000,
+        }
+    }
+}
+
+/// What happens to a display name or chat message containing a listed word.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProfanityAction {
+    Reject,     // Refused with an error
+    #[default]
+    Mask,       // Listed words replaced with asterisks
+    ShadowMute, // Accepted, but nobody except the sender sees it
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfanityConfig {
+    #[serde(default)]
+    pub words: Vec<String>,
+    #[serde(default)]
+    pub word_list_path: Option<String>, // One word per line, added to `words`
+    #[serde(default)]
+    pub action: ProfanityAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct I18nConfig {
+    pub default_locale: String,       // Served when the client's locale isn't in the catalog
+    pub catalog_dir: Option<String>,  // Extra `<locale>.json` catalogs; English is built in
+}
+
+impl Default for I18nConfig {
+    fn default() -> Self {
+        Self {
+            default_locale: "en".to_string(),
+            catalog_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    pub store_path: Option<String>,     // JSON-lines file; None keeps inboxes in memory only
+    pub max_pending_per_player: usize,  // Oldest unacknowledged notifications are dropped past this
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            store_path: Some("data/notifications.jsonl".to_string()),
+            max_pending_per_player: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TitlesConfig {
+    pub store_path: Option<String>, // JSON-lines file; None keeps titles in memory only
+}
+
+impl Default for TitlesConfig {
+    fn default() -> Self {
+        Self {
+            store_path: Some("data/titles.jsonl".to_string()),
+        }
+    }
+}
+
+/// Shares of the server for each community it hosts and each game mode, checked at
+/// FindMatch. A player is held to their tenant's limits and their mode's, whichever
+/// is hit first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuotasConfig {
+    pub tenants: HashMap<String, QuotaLimits>, // Keyed by the `tenant` players name at Connect
+    pub modes: HashMap<crate::domain::GameMode, QuotaLimits>,
+}
+
+/// Limits for one tenant or mode; unset ones don't apply.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuotaLimits {
+    pub max_rooms: Option<usize>,         // Live rooms with at least one of its players, or in its mode
+    pub max_queued_players: Option<usize>,
+    pub max_games_per_player_per_hour: Option<u32>,
+}
+
+/// A matchmaking cooldown for players who keep leaving games before they end.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuitPenaltyConfig {
+    pub enabled: bool,
+    pub window_ms: u64,       // Games started longer ago stop counting, so the penalty wears off
+    pub min_games: usize,     // Games in the window before the rate is judged
+    pub max_quit_rate: f64,   // Share of those games a player may leave early
+    pub cooldown_ms: u64,     // Kept out of matchmaking this long after each quit above the rate
+}
+
+impl Default for QuitPenaltyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_ms: 86400000,
+            min_games: 5,
+            max_quit_rate: 0.3,
+            cooldown_ms: 120000,
+        }
+    }
+}
+
+/// Shareable links that seat whoever opens them in the inviting player's room.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InvitesConfig {
+    pub secret: SecretSource,          // Signs invite tokens; a per-process key when empty, so links die on restart
+    pub ttl_ms: u64,                   // How long a link stays valid
+    pub public_url: Option<String>,    // REST base URL put in front of `/join/{token}`, e.g. "https://rps.example.com"
+    pub websocket_url: Option<String>, // Where invited players connect, given out with the instructions
+}
+
+impl Default for InvitesConfig {
+    fn default() -> Self {
+        Self {
+            secret: SecretSource {
+                env: Some("RPS_INVITE_SECRET".to_string()),
+                file: None,
+            },
+            ttl_ms: 900000,
+            public_url: None,
+            websocket_url: None,
+        }
+    }
+}
+
+/// Games played, connections and peak concurrency summed across restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifetimeStatsConfig {
+    pub store_path: Option<String>, // JSON file; None forgets the totals on restart
+    pub persist_interval_ms: u64,   // Also written on shutdown
+}
+
+impl Default for LifetimeStatsConfig {
+    fn default() -> Self {
+        Self {
+            store_path: Some("data/lifetime_stats.json".to_string()),
+            persist_interval_ms: 60000,
+        }
+    }
+}
+
+/// Operator Rhai scripts that room templates can resolve rounds with. A script defines
+/// `validate(choice)`, returning whether a move is legal, and `resolve(first, second)`,
+/// returning the winning seat (0 or 1) or anything else for a draw.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptedRulesConfig {
+    pub scripts: Vec<RuleScriptConfig>,
+    pub max_operations: u64, // Per call; a script that runs longer is stopped
+    pub time_limit_ms: u64,  // Per call, however few operations it took
+}
+
+impl Default for ScriptedRulesConfig {
+    fn default() -> Self {
+        Self {
+            scripts: Vec::new(),
+            max_operations: 100_000,
+            time_limit_ms: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleScriptConfig {
+    pub name: String,        // Set as a room template's `script`
+    pub script_path: String, // Rhai source
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomTemplatesConfig {
+    pub store_path: Option<String>, // JSON-lines file; None keeps templates in memory only
+}
+
+impl Default for RoomTemplatesConfig {
+    fn default() -> Self {
+        Self {
+            store_path: Some("data/room_templates.jsonl".to_string()),
+        }
+    }
+}
+
+/// Per-player timelines behind `GET /players/{id}/activity`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityConfig {
+    pub max_entries_per_player: usize, // Oldest entries are dropped past this
+    pub max_players: usize,            // The longest-quiet player is forgotten past this
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        Self {
+            max_entries_per_player: 200,
+            max_players: 100_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LongPollConfig {
+    pub enabled: bool,                // Serve /poll/* for clients that can't open a WebSocket
+    pub poll_timeout_ms: u64,         // How long GET /poll/recv waits for a message
+    pub session_idle_timeout_ms: u64, // Sessions without any request for this long are closed
+    pub max_buffered_messages: usize, // Oldest unacknowledged messages are dropped past this
+}
+
+impl Default for LongPollConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_timeout_ms: 25000,
+            session_idle_timeout_ms: 60000,
+            max_buffered_messages: 512,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +907,55 @@ pub struct PerformanceConfig {
     pub thread_stack_size: usize,
     pub channel_buffer_size: usize,
     pub gc_interval_ms: u64,
+    #[serde(default)]
+    pub buffer_pool: BufferPoolConfig,
+    #[serde(default = "manager_shards")]
+    pub manager_shards: usize, // Partitions of the game manager's rooms and player maps; 1 keeps one lock each
+}
+
+/// Game manager shards when `performance.manager_shards` isn't set.
+pub const DEFAULT_MANAGER_SHARDS: usize = 16;
+
+fn manager_shards() -> usize {
+    DEFAULT_MANAGER_SHARDS
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BufferPoolConfig {
+    pub max_per_worker: usize,   // Idle frame buffers each runtime worker keeps
+    pub max_buffer_bytes: usize, // Larger buffers are freed rather than pooled
+}
+
+impl Default for BufferPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_per_worker: 64,
+            max_buffer_bytes: 64 * 1024,
+        }
+    }
+}
+
+impl PerformanceConfig {
+    /// Multi-threaded runtime sized from this config; without `worker_threads`, one worker per available core.
+    pub fn build_runtime(&self) -> anyhow::Result<tokio::runtime::Runtime> {
+        if self.worker_threads == Some(0) {
+            anyhow::bail!("performance.worker_threads must be at least 1");
+        }
+        if self.max_blocking_threads == 0 {
+            anyhow::bail!("performance.max_blocking_threads must be at least 1");
+        }
+
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        builder
+            .enable_all()
+            .max_blocking_threads(self.max_blocking_threads)
+            .thread_stack_size(self.thread_stack_size);
+        if let Some(workers) = self.worker_threads {
+            builder.worker_threads(workers);
+        }
+        Ok(builder.build()?)
+    }
 }
 
 impl Default for ServerConfig {
@@ -57,10 +970,26 @@ impl Default for ServerConfig {
                 keepalive_interval_ms: 10000, // More frequent keepalive
                 max_frame_size: 16 * 1024,   // Smaller frames for efficiency
                 max_message_size: 256 * 1024, // Smaller messages
+                slow_client_max_queue: 256,
+                slow_client_timeout_ms: 5000,
+                socket_io_compat: false,
+                listeners: Vec::new(),
+                outbound_batch: OutboundBatchConfig::default(),
+                json_parser: JsonParser::default(),
+                writer_shards: None,
+                fd_limit: FdLimitPolicy::default(),
             },
             rest_api: RestApiConfig {
                 host: "0.0.0.0".to_string(),
                 port: 8081,
+                serve_demo_client: true,
+                share_websocket_port: false,
+                http2: true,
+                keep_alive_timeout_ms: default_keep_alive_timeout_ms(),
+                max_concurrent_streams: default_max_concurrent_streams(),
+                max_body_bytes: default_max_body_bytes(),
+                cache_ttl_ms: default_cache_ttl_ms(),
+                rate_limit: RestRateLimitConfig::default(),
             },
             game: GameConfig {
                 max_rounds: 3,
@@ -68,14 +997,54 @@ impl Default for ServerConfig {
                 max_players: 2,
                 move_timeout_ms: 15000,
                 cleanup_interval_ms: 30000,
+                match_time_limit_ms: Some(300000), // 5 minute hard cap per game
+                reconnect_grace_ms: 30000,
+                max_pause_ms: 120000,
+                draw_policy: crate::domain::DrawPolicy::NoPoint,
+                move_changes: crate::domain::MoveChangePolicy::Reject,
+                rules: crate::domain::RuleSet::classic(),
+                max_latency_compensation_ms: crate::domain::max_latency_compensation_ms(),
             },
             performance: PerformanceConfig {
-                worker_threads: Some(16), // More worker threads
+                worker_threads: None, // One per available core
                 max_blocking_threads: 2048, // More blocking threads
                 thread_stack_size: 1024 * 1024, // Smaller stack for more threads
                 channel_buffer_size: 4096, // Larger buffers
                 gc_interval_ms: 10000, // More frequent GC
+                buffer_pool: BufferPoolConfig::default(),
+                manager_shards: manager_shards(),
             },
+            admin: AdminConfig {
+                audit_log_path: Some("data/admin_audit.jsonl".to_string()),
+            },
+            secrets: SecretsConfig::default(),
+            webhooks: WebhooksConfig::default(),
+            presence: PresenceConfig::default(),
+            bot_detection: BotDetectionConfig::default(),
+            i18n: I18nConfig::default(),
+            notifications: NotificationsConfig::default(),
+            long_poll: LongPollConfig::default(),
+            matchmaking: MatchmakingConfig::default(),
+            game_history: GameHistoryConfig::default(),
+            ratings: RatingsConfig::default(),
+            seasons: SeasonsConfig::default(),
+            spam_guard: SpamGuardConfig::default(),
+            capacity: CapacityConfig::default(),
+            traffic_recording: TrafficRecordingConfig::default(),
+            cluster: ClusterConfig::default(),
+            stats_export: StatsExportConfig::default(),
+            blitz: BlitzConfig::default(),
+            series: SeriesConfig::default(),
+            titles: TitlesConfig::default(),
+            player_stats: PlayerStatsConfig::default(),
+            scripted_rules: ScriptedRulesConfig::default(),
+            room_templates: RoomTemplatesConfig::default(),
+            activity: ActivityConfig::default(),
+            profanity: ProfanityConfig::default(),
+            lifetime_stats: LifetimeStatsConfig::default(),
+            quotas: QuotasConfig::default(),
+            quit_penalty: QuitPenaltyConfig::default(),
+            invites: InvitesConfig::default(),
         }
     }
 }
@@ -86,6 +1055,37 @@ impl From<GameConfig> for crate::domain::GameConfig {
             max_rounds: config.max_rounds,
             min_players: config.min_players,
             max_players: config.max_players,
+            match_time_limit_ms: config.match_time_limit_ms,
+            reconnect_grace_ms: config.reconnect_grace_ms,
+            max_pause_ms: config.max_pause_ms,
+            draw_policy: config.draw_policy,
+            move_changes: config.move_changes,
+            mode: crate::domain::GameMode::Solo,
+            rules: config.rules,
+            max_latency_compensation_ms: config.max_latency_compensation_ms,
+            round_window_ms: None,
+            game: crate::domain::GameType::RockPaperScissors,
+            template: None,
+            script: None,
+            chat_enabled: true,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{PerformanceConfig, ServerConfig};
+
+    #[test]
+    fn test_runtime_sized_from_performance_config() {
+        let mut performance = PerformanceConfig {
+            worker_threads: Some(2),
+            ..ServerConfig::default().performance
+        };
+        let runtime = performance.build_runtime().unwrap();
+        assert_eq!(runtime.metrics().num_workers(), 2);
+
+        performance.worker_threads = Some(0);
+        assert!(performance.build_runtime().is_err());
+    }
+}
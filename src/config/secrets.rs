@@ -0,0 +1,303 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// A secret value that is wiped from memory on drop and never printed.
+#[derive(Clone)]
+pub struct Secret {
+    id: String,
+    value: Zeroizing<String>,
+}
+
+impl Secret {
+    pub fn new(id: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            value: Zeroizing::new(value.into()),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.value
+    }
+
+    // Constant-time comparison so key checks don't leak prefix matches
+    pub fn matches(&self, candidate: &str) -> bool {
+        let a = self.value.as_bytes();
+        let b = candidate.as_bytes();
+        if a.len() != b.len() {
+            return false;
+        }
+        a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Secret").field("id", &self.id).field("value", &"<redacted>").finish()
+    }
+}
+
+/// What an API key may do; each role can do everything the roles before it can.
+/// Viewers read admin endpoints, operators also act on players and rooms, and admins
+/// also manage keys and run cluster-wide or fault-injection operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Operator,
+    Admin,
+}
+
+/// An API key issued at runtime. Only the SHA-256 of the key itself is ever kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub id: String,
+    pub role: Role,
+    pub key_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub created_by: String,
+}
+
+/// Hex SHA-256 of an API key, as issued keys are stored and looked up.
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Where to find a set of keys. Only locations live in `ServerConfig`, never the keys themselves.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecretSource {
+    pub env: Option<String>,
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretsConfig {
+    pub admin_api_keys: SecretSource,
+    pub jwt_signing_keys: SecretSource,
+    #[serde(default = "result_signing_keys")]
+    pub result_signing_keys: SecretSource, // Hex Ed25519 seeds; none configured leaves results unsigned
+    #[serde(default = "api_key_store_path")]
+    pub api_key_store_path: Option<String>, // JSON-lines file of issued keys, hashed; None keeps them in memory only
+    #[serde(default = "resume_keys")]
+    pub resume_keys: SecretSource, // Sign `resumeToken`s; a per-process key when empty, so sessions can't resume across restarts
+}
+
+fn api_key_store_path() -> Option<String> {
+    Some("data/api_keys.jsonl".to_string())
+}
+
+fn resume_keys() -> SecretSource {
+    SecretSource {
+        env: Some("RPS_RESUME_KEYS".to_string()),
+        file: None,
+    }
+}
+
+fn result_signing_keys() -> SecretSource {
+    SecretSource {
+        env: Some("RPS_RESULT_SIGNING_KEYS".to_string()),
+        file: None,
+    }
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            admin_api_keys: SecretSource {
+                env: Some("RPS_ADMIN_API_KEYS".to_string()),
+                file: None,
+            },
+            jwt_signing_keys: SecretSource {
+                env: Some("RPS_JWT_SIGNING_KEYS".to_string()),
+                file: None,
+            },
+            result_signing_keys: result_signing_keys(),
+            api_key_store_path: api_key_store_path(),
+            resume_keys: resume_keys(),
+        }
+    }
+}
+
+impl SecretSource {
+    /// Reads keys separated by newlines or commas. Entries may be `id:value`;
+    /// bare values get positional ids. The first key is the active one.
+    pub fn load(&self) -> Result<Vec<Secret>> {
+        let mut raw = Zeroizing::new(String::new());
+        if let Some(path) = &self.file {
+            let contents = Zeroizing::new(
+                std::fs::read_to_string(path).with_context(|| format!("Failed to read secrets file {}", path))?,
+            );
+            raw.push_str(&contents);
+            raw.push('\n');
+        }
+        if let Some(var) = &self.env {
+            if let Ok(value) = std::env::var(var) {
+                raw.push_str(&Zeroizing::new(value));
+            }
+        }
+
+        Ok(raw
+            .split(['\n', ','])
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty() && !entry.starts_with('#'))
+            .enumerate()
+            .map(|(i, entry)| match entry.split_once(':') {
+                Some((id, value)) => Secret::new(id.trim(), value.trim()),
+                None => Secret::new(format!("key-{}", i), entry),
+            })
+            .collect())
+    }
+}
+
+/// Holds the currently accepted keys; `reload` swaps them in place for rotation.
+/// Configured admin API keys carry the admin role; keys issued at runtime carry their own.
+pub struct SecretStore {
+    config: SecretsConfig,
+    admin_api_keys: RwLock<Vec<Secret>>,
+    issued_api_keys: RwLock<HashMap<String, ApiKey>>, // By key hash
+    jwt_signing_keys: RwLock<Vec<Secret>>,
+    result_signing_keys: RwLock<Vec<Secret>>,
+}
+
+impl SecretStore {
+    pub fn load(config: SecretsConfig) -> Result<Self> {
+        let store = Self {
+            admin_api_keys: RwLock::new(config.admin_api_keys.load()?),
+            issued_api_keys: RwLock::new(HashMap::new()),
+            jwt_signing_keys: RwLock::new(config.jwt_signing_keys.load()?),
+            result_signing_keys: RwLock::new(config.result_signing_keys.load()?),
+            config,
+        };
+        Ok(store)
+    }
+
+    pub fn from_keys(admin_api_keys: Vec<Secret>, jwt_signing_keys: Vec<Secret>) -> Self {
+        Self {
+            config: SecretsConfig::default(),
+            admin_api_keys: RwLock::new(admin_api_keys),
+            issued_api_keys: RwLock::new(HashMap::new()),
+            jwt_signing_keys: RwLock::new(jwt_signing_keys),
+            result_signing_keys: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn with_result_signing_keys(self, keys: Vec<Secret>) -> Self {
+        *self.result_signing_keys.write() = keys;
+        self
+    }
+
+    pub fn reload(&self) -> Result<()> {
+        let admin = self.config.admin_api_keys.load()?;
+        let jwt = self.config.jwt_signing_keys.load()?;
+        let results = self.config.result_signing_keys.load()?;
+        *self.admin_api_keys.write() = admin;
+        *self.jwt_signing_keys.write() = jwt;
+        *self.result_signing_keys.write() = results;
+        Ok(())
+    }
+
+    pub fn has_admin_keys(&self) -> bool {
+        !self.admin_api_keys.read().is_empty() || self.issued_api_keys.read().values().any(|key| key.role == Role::Admin)
+    }
+
+    /// The id and role of the configured or issued key that matches, if any.
+    pub fn verify_api_key(&self, candidate: &str) -> Option<(String, Role)> {
+        if let Some(key) = self.admin_api_keys.read().iter().find(|key| key.matches(candidate)) {
+            return Some((key.id().to_string(), Role::Admin));
+        }
+        self.issued_api_keys
+            .read()
+            .get(&hash_api_key(candidate))
+            .map(|key| (key.id.clone(), key.role))
+    }
+
+    /// Returns the id of the matching key if it carries the admin role.
+    pub fn verify_admin_key(&self, candidate: &str) -> Option<String> {
+        self.verify_api_key(candidate)
+            .filter(|(_, role)| *role == Role::Admin)
+            .map(|(id, _)| id)
+    }
+
+    /// Whether a configured or issued key already has this id.
+    pub fn has_key_id(&self, id: &str) -> bool {
+        self.admin_api_keys.read().iter().any(|key| key.id() == id)
+            || self.issued_api_keys.read().values().any(|key| key.id == id)
+    }
+
+    /// Issued keys, oldest first.
+    pub fn issued_api_keys(&self) -> Vec<ApiKey> {
+        let mut keys: Vec<_> = self.issued_api_keys.read().values().cloned().collect();
+        keys.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        keys
+    }
+
+    /// Starts accepting an issued key; one with the same id is replaced.
+    pub fn install_api_key(&self, key: ApiKey) {
+        let mut keys = self.issued_api_keys.write();
+        keys.retain(|_, existing| existing.id != key.id);
+        keys.insert(key.key_hash.clone(), key);
+    }
+
+    /// False if no issued key has this id; configured keys can't be revoked at runtime.
+    pub fn revoke_api_key(&self, id: &str) -> bool {
+        let mut keys = self.issued_api_keys.write();
+        let before = keys.len();
+        keys.retain(|_, key| key.id != id);
+        keys.len() != before
+    }
+
+    /// The key new tokens should be signed with.
+    pub fn active_jwt_key(&self) -> Option<Secret> {
+        self.jwt_signing_keys.read().first().cloned()
+    }
+
+    /// Any configured key is still accepted for verification during rotation.
+    pub fn jwt_key(&self, id: &str) -> Option<Secret> {
+        self.jwt_signing_keys.read().iter().find(|key| key.id() == id).cloned()
+    }
+
+    /// The first key signs results; the others stay published so older signatures still verify.
+    pub fn result_signing_keys(&self) -> Vec<Secret> {
+        self.result_signing_keys.read().clone()
+    }
+}
+
+impl fmt::Debug for SecretStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretStore")
+            .field("admin_api_keys", &self.admin_api_keys.read().len())
+            .field("issued_api_keys", &self.issued_api_keys.read().len())
+            .field("jwt_signing_keys", &self.jwt_signing_keys.read().len())
+            .field("result_signing_keys", &self.result_signing_keys.read().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Secret, SecretStore};
+
+    #[test]
+    fn test_secret_store_accepts_rotated_keys_and_redacts() {
+        let store = SecretStore::from_keys(
+            vec![Secret::new("current", "new-key"), Secret::new("previous", "old-key")],
+            vec![],
+        );
+
+        assert_eq!(store.verify_admin_key("new-key").as_deref(), Some("current"));
+        assert_eq!(store.verify_admin_key("old-key").as_deref(), Some("previous"));
+        assert!(store.verify_admin_key("new-ke").is_none());
+        assert!(!format!("{:?}", Secret::new("current", "new-key")).contains("new-key"));
+    }
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// Which build of the server is running, as recorded by `build.rs` at compile time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildInfo {
+    pub version: String,
+    pub git_hash: String, // "unknown" when built outside a git checkout
+    pub rustc: String,
+    pub profile: String,
+    pub features: Vec<String>,
+    pub built_at: Option<DateTime<Utc>>,
+}
+
+static CURRENT: Lazy<BuildInfo> = Lazy::new(|| BuildInfo {
+    version: env!("CARGO_PKG_VERSION").to_string(),
+    git_hash: env!("RPS_GIT_HASH").to_string(),
+    rustc: env!("RPS_RUSTC_VERSION").to_string(),
+    profile: env!("RPS_BUILD_PROFILE").to_string(),
+    features: env!("RPS_BUILD_FEATURES").split(',').filter(|feature| !feature.is_empty()).map(str::to_string).collect(),
+    built_at: env!("RPS_BUILT_AT").parse().ok().and_then(|secs| DateTime::from_timestamp(secs, 0)),
+});
+
+impl BuildInfo {
+    /// This binary's build.
+    pub fn current() -> &'static BuildInfo {
+        &CURRENT
+    }
+}
@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{GameEndReason, ResultSignature};
+
+/// Lifecycle events published on the internal event bus.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GameEvent {
+    PlayerConnected {
+        #[serde(rename = "playerId")]
+        player_id: String,
+    },
+    PlayerQueued {
+        #[serde(rename = "playerId")]
+        player_id: String,
+    },
+    MatchReserved {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        players: Vec<String>, // Those holding a seat so far; invite rooms fill the rest later
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tournament: Option<String>, // Set by the organizer who reserved it
+    },
+    MatchCreated {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        players: Vec<String>,
+        #[serde(rename = "maxRounds")]
+        max_rounds: u32,
+    },
+    RoundStarted {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        players: Vec<String>,
+        round: u32,
+        #[serde(rename = "maxRounds")]
+        max_rounds: u32,
+    },
+    GameEnded {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "gameId", default)]
+        game_id: String,
+        winner: Option<String>,
+        #[serde(rename = "finalScores")]
+        final_scores: HashMap<String, u32>,
+        reason: GameEndReason,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        teams: HashMap<String, String>, // playerId -> teamId; scores and winner are per team when set
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signature: Option<Box<ResultSignature>>, // Same signature as the GameEnd message
+    },
+    PlayerDisconnected {
+        #[serde(rename = "playerId")]
+        player_id: String,
+    },
+    PlayerKicked {
+        #[serde(rename = "playerId")]
+        player_id: String,
+        reason: String,
+    },
+    PlayerBanned {
+        #[serde(rename = "playerId")]
+        player_id: String,
+        reason: String,
+    },
+    QueueStarved {
+        #[serde(rename = "playerId")]
+        player_id: String,
+        queue: String, // Matchmaking queue they wait in, e.g. "waiting" or "teams"
+        #[serde(rename = "waitedMs")]
+        waited_ms: u64,
+    },
+    SeriesEnded {
+        #[serde(rename = "seriesId")]
+        series_id: String,
+        players: Vec<String>,
+        winner: Option<String>,
+        scores: HashMap<String, u32>, // Games won
+        games: Vec<String>,           // Game ids in play order
+        reason: GameEndReason,        // Forfeit when a game was forfeited or the next one couldn't start
+    },
+}
+
+impl GameEvent {
+    /// Stable name used for subscription filters, e.g. `"GameEnded"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GameEvent::PlayerConnected { .. } => "PlayerConnected",
+            GameEvent::PlayerQueued { .. } => "PlayerQueued",
+            GameEvent::MatchReserved { .. } => "MatchReserved",
+            GameEvent::MatchCreated { .. } => "MatchCreated",
+            GameEvent::RoundStarted { .. } => "RoundStarted",
+            GameEvent::GameEnded { .. } => "GameEnded",
+            GameEvent::PlayerDisconnected { .. } => "PlayerDisconnected",
+            GameEvent::PlayerKicked { .. } => "PlayerKicked",
+            GameEvent::PlayerBanned { .. } => "PlayerBanned",
+            GameEvent::QueueStarved { .. } => "QueueStarved",
+            GameEvent::SeriesEnded { .. } => "SeriesEnded",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventEnvelope {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: GameEvent,
+}
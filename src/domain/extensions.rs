@@ -0,0 +1,71 @@
+use std::any::{Any, TypeId};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+/// Typed metadata about a connection, one value per type: filled in at `Connect` and
+/// carried by every `Player` the connection creates, so matchmaking, rooms and logging
+/// can read what they need without it being passed through each handler.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `value`, replacing any earlier value of the same type.
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.values.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.values.get(&TypeId::of::<T>()).and_then(|value| value.downcast_ref())
+    }
+
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn remove<T: Send + Sync + 'static>(&mut self) -> bool {
+        self.values.remove(&TypeId::of::<T>()).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions").field("len", &self.values.len()).finish()
+    }
+}
+
+/// The client metrics bucket of the connection's client, as settled at `Connect`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientVersion(pub String);
+
+/// Where the client says it is playing from, e.g. "eu-west".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region(pub String);
+
+/// The community the player belongs to when one server hosts several; quotas are kept per tenant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant(pub String);
+
+/// Experiments the client opted into at `Connect`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExperimentFlags(pub BTreeSet<String>);
+
+impl ExperimentFlags {
+    pub fn is_enabled(&self, flag: &str) -> bool {
+        self.0.contains(flag)
+    }
+}
@@ -2,15 +2,29 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
+use super::games::GameType;
+use super::rules::RuleSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
 pub enum GameChoice {
     Rock,
     Paper,
     Scissors,
+    Custom(String), // Any other choice name; only valid under a custom RuleSet
 }
 
 impl GameChoice {
+    pub fn name(&self) -> &str {
+        match self {
+            GameChoice::Rock => "rock",
+            GameChoice::Paper => "paper",
+            GameChoice::Scissors => "scissors",
+            GameChoice::Custom(name) => name,
+        }
+    }
+
+    /// Classic rock-paper-scissors; rooms with a custom `RuleSet` use `RuleSet::beats`.
     pub fn beats(&self, other: &GameChoice) -> bool {
         matches!(
             (self, other),
@@ -21,18 +35,128 @@ impl GameChoice {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+// Read in any case, and by their initials, since clients often send "Rock" or "r";
+// custom choice names are kept as sent, and a room matches them against its rules in
+// any case, taking the rules' spelling
+impl From<String> for GameChoice {
+    fn from(name: String) -> Self {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "rock" | "r" => GameChoice::Rock,
+            "paper" | "p" => GameChoice::Paper,
+            "scissors" | "s" => GameChoice::Scissors,
+            _ => GameChoice::Custom(name),
+        }
+    }
+}
+
+impl From<GameChoice> for String {
+    fn from(choice: GameChoice) -> Self {
+        choice.name().to_string()
+    }
+}
+
+impl GameMode {
+    pub fn players_per_room(&self) -> usize {
+        match self {
+            GameMode::Solo | GameMode::Bot | GameMode::Blitz => 2,
+            GameMode::Teams => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum GameStatus {
     Waiting,
     Playing,
+    Paused,
     Finished,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum GameEndReason {
+    Completed,
+    TimeLimit,
+    Forfeit,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum GameMode {
+    #[default]
+    Solo,  // 1v1
+    Teams, // 2v2, rounds decided by aggregate cross-team wins
+    Bot,   // 1v1 against a server bot with a committed move sequence
+    Blitz, // 1v1, many short rounds on a server timer; a missed round is lost
+}
+
+/// How a drawn round is scored.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum DrawPolicy {
+    #[default]
+    NoPoint,   // Round is consumed, nobody scores
+    BothScore, // Round is consumed, every player scores
+    Replay,    // Round is replayed without consuming the round count
+}
+
+/// What a second move from the same player in one round does.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MoveChangePolicy {
+    #[default]
+    Reject,              // The first move stands; the repeat is refused with MoveAlreadySubmitted
+    ChangeUntilRevealed, // The latest move counts until every seat has moved and the round resolves
+}
+
+impl MoveChangePolicy {
+    pub fn is_default(&self) -> bool {
+        *self == MoveChangePolicy::default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PauseReason {
+    PlayerDisconnected,
+    MutualConsent,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerMove {
     pub choice: GameChoice,
-    pub timestamp: DateTime<Utc>,
+    pub timestamp: DateTime<Utc>, // When the server received it
+    #[serde(default)]
+    pub response_ms: Option<u64>, // Since the round started, less the player's round trip; None for bots
+    #[serde(default)]
+    pub decision_ms: Option<u64>, // Since the round started, as the server timed it; None for bots
+}
+
+/// One resolved round, as listed in the `GameEnd` match summary.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoundSummary {
+    pub round: u32,
+    pub moves: HashMap<String, GameChoice>,
+    pub winner: Option<String>, // Player, or team in team mode; None for a draw
+    #[serde(default)]
+    pub replayed: bool, // Drawn round that was played again under DrawPolicy::Replay
+    #[serde(rename = "responseMs", default, skip_serializing_if = "HashMap::is_empty")]
+    pub response_ms: HashMap<String, u64>, // Latency-compensated time to move, human players only
+    #[serde(rename = "decisionMs", default, skip_serializing_if = "HashMap::is_empty")]
+    pub decision_ms: HashMap<String, u64>, // Round start to the move arriving, uncompensated; human players only
+}
+
+/// Server signature over a result, so platforms that ingest it elsewhere can check it
+/// came from this server. Verify `signature` against `payload` exactly as sent, using the
+/// published public key with id `key_id`, then trust only what `payload` says.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultSignature {
+    pub key_id: String,
+    pub algorithm: String, // Always "ed25519"
+    pub payload: String,   // The signed JSON
+    pub signature: String, // Hex
 }
 
 #[derive(Debug, Clone)]
@@ -43,11 +167,40 @@ pub struct GameResult {
     pub scores: HashMap<String, u32>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameConfig {
     pub max_rounds: u32,
     pub min_players: usize,
     pub max_players: usize,
+    pub match_time_limit_ms: Option<u64>, // Total wall-clock budget per game
+    pub reconnect_grace_ms: u64,           // 0 = tear the room down immediately
+    pub max_pause_ms: u64,                 // Upper bound on a consent pause
+    pub draw_policy: DrawPolicy,
+    #[serde(skip_serializing_if = "MoveChangePolicy::is_default", default)]
+    pub move_changes: MoveChangePolicy,
+    pub mode: GameMode,
+    #[serde(skip_serializing_if = "RuleSet::is_classic", default = "RuleSet::classic")]
+    pub rules: RuleSet,
+    #[serde(default = "max_latency_compensation_ms")]
+    pub max_latency_compensation_ms: u64, // Cap on the round trip taken off a move's response time
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub round_window_ms: Option<u64>, // Rounds resolve when this runs out, moved or not; blitz only
+    #[serde(skip_serializing_if = "GameType::is_default", default)]
+    pub game: GameType, // Round rules; anything but rock-paper-scissors is solo only
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub template: Option<String>, // Room template the room was set up from
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub script: Option<String>, // Rule script resolving rounds in place of `game`
+    #[serde(default = "chat_enabled")]
+    pub chat_enabled: bool,
+}
+
+pub fn max_latency_compensation_ms() -> u64 {
+    250
+}
+
+pub fn chat_enabled() -> bool {
+    true
 }
 
 impl Default for GameConfig {
@@ -56,6 +209,88 @@ impl Default for GameConfig {
             max_rounds: 3,
             min_players: 2,
             max_players: 2,
+            match_time_limit_ms: None,
+            reconnect_grace_ms: 30000,
+            max_pause_ms: 120000,
+            draw_policy: DrawPolicy::NoPoint,
+            move_changes: MoveChangePolicy::Reject,
+            mode: GameMode::Solo,
+            rules: RuleSet::classic(),
+            max_latency_compensation_ms: max_latency_compensation_ms(),
+            round_window_ms: None,
+            game: GameType::RockPaperScissors,
+            template: None,
+            script: None,
+            chat_enabled: true,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::{ClientMessage, GameChoice, RuleSet};
+
+    #[test]
+    fn test_game_choice_beats() {
+        assert!(GameChoice::Rock.beats(&GameChoice::Scissors));
+        assert!(GameChoice::Paper.beats(&GameChoice::Rock));
+        assert!(GameChoice::Scissors.beats(&GameChoice::Paper));
+        
+        assert!(!GameChoice::Rock.beats(&GameChoice::Paper));
+        assert!(!GameChoice::Paper.beats(&GameChoice::Scissors));
+        assert!(!GameChoice::Scissors.beats(&GameChoice::Rock));
+        
+        assert!(!GameChoice::Rock.beats(&GameChoice::Rock));
+    }
+
+    #[test]
+    fn test_game_choice_reads_any_case_and_initials_but_writes_lowercase() {
+        for (sent, choice) in [
+            ("rock", GameChoice::Rock),
+            ("Rock", GameChoice::Rock),
+            ("ROCK", GameChoice::Rock),
+            ("r", GameChoice::Rock),
+            ("P", GameChoice::Paper),
+            (" paper ", GameChoice::Paper),
+            ("Scissors", GameChoice::Scissors),
+            ("s", GameChoice::Scissors),
+        ] {
+            let read: GameChoice = serde_json::from_value(serde_json::json!(sent)).unwrap();
+            assert_eq!(read, choice, "{:?}", sent);
+            let written = serde_json::to_value(&read).unwrap();
+            assert_eq!(written, serde_json::json!(choice.name()));
+            assert_eq!(serde_json::from_value::<GameChoice>(written).unwrap(), choice);
+        }
+
+        // Custom choices round-trip untouched, case included
+        let lizard: GameChoice = serde_json::from_value(serde_json::json!("Lizard")).unwrap();
+        assert_eq!(lizard, GameChoice::Custom("Lizard".to_string()));
+        assert_eq!(serde_json::to_value(&lizard).unwrap(), serde_json::json!("Lizard"));
+
+        let message: ClientMessage = serde_json::from_str(r#"{"type":"playerMove","choice":"Rock"}"#).unwrap();
+        assert!(matches!(message, ClientMessage::PlayerMove { choice: GameChoice::Rock }));
+    }
+
+    #[test]
+    fn test_custom_choices_match_their_rules_in_any_case() {
+        let spock = GameChoice::Custom("Spock".to_string());
+        let rules = RuleSet {
+            choices: vec![GameChoice::Rock, spock.clone()],
+            matrix: vec![vec![0, -1], vec![1, 0]],
+        };
+
+        // Sent in another case, a custom choice comes back in the rules' spelling
+        let read: GameChoice = serde_json::from_value(serde_json::json!("sPOCK")).unwrap();
+        let canonical = rules.canonical(&read).unwrap();
+        assert_eq!(canonical, &spock);
+        assert_eq!(serde_json::to_value(canonical).unwrap(), serde_json::json!("Spock"));
+        assert!(rules.beats(&read, &GameChoice::Rock));
+        assert!(rules.canonical(&GameChoice::Paper).is_none());
+
+        let clash = RuleSet {
+            choices: vec![spock, GameChoice::Custom("spock".to_string())],
+            matrix: vec![vec![0, -1], vec![1, 0]],
+        };
+        assert!(clash.validate().is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{GameChoice, PlayerMove, RuleSet};
+
+/// Which game a queue or room plays. Every type is played in simultaneous-move rounds
+/// between two seats, so rooms, scoring, timers and matchmaking are shared; only the
+/// round rules come from the [`Game`] it builds.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum GameType {
+    #[default]
+    RockPaperScissors,
+    OddsAndEvens, // Both show 1-5 fingers; seat 0 wins an odd total, seat 1 an even one
+    Morra,        // Both show 1-5 fingers and guess the total as "fingers:guess"; a lone right guess wins
+}
+
+impl GameType {
+    pub const ALL: [GameType; 3] = [GameType::RockPaperScissors, GameType::OddsAndEvens, GameType::Morra];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameType::RockPaperScissors => "rockPaperScissors",
+            GameType::OddsAndEvens => "oddsAndEvens",
+            GameType::Morra => "morra",
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == GameType::default()
+    }
+
+    /// The round rules for this game; `rules` only shapes rock-paper-scissors.
+    pub fn build(&self, rules: &RuleSet) -> Box<dyn Game> {
+        match self {
+            GameType::RockPaperScissors => Box::new(RockPaperScissors { rules: rules.clone() }),
+            GameType::OddsAndEvens => Box::new(OddsAndEvens),
+            GameType::Morra => Box::new(Morra),
+        }
+    }
+}
+
+/// Round rules of a hosted game. The room asks it whether a move is legal, hands it each
+/// accepted move, and resolves the round once it is complete (or its window runs out).
+/// Built from a [`GameType`], or from an operator script for rooms made from a template.
+pub trait Game: Send + Sync {
+    fn validate_move(&self, choice: &GameChoice) -> bool;
+
+    /// The game's own spelling of a move, which may have been sent in any case.
+    fn canonical(&self, choice: GameChoice) -> GameChoice {
+        choice
+    }
+
+    /// Adds an accepted move to the round in progress.
+    fn apply_move(&self, moves: &mut HashMap<String, PlayerMove>, player_id: &str, player_move: PlayerMove) {
+        moves.insert(player_id.to_string(), player_move);
+    }
+
+    /// Whether the round can be resolved; by default once every seat has moved.
+    fn is_complete(&self, moves: &HashMap<String, PlayerMove>, seats: &[String]) -> bool {
+        seats.iter().all(|seat| moves.contains_key(seat))
+    }
+
+    /// The winning seat of a round both seats moved in, or `None` for a draw.
+    fn result(&self, moves: [&GameChoice; 2]) -> Option<usize>;
+}
+
+pub struct RockPaperScissors {
+    rules: RuleSet,
+}
+
+impl Game for RockPaperScissors {
+    fn validate_move(&self, choice: &GameChoice) -> bool {
+        self.rules.allows(choice)
+    }
+
+    fn canonical(&self, choice: GameChoice) -> GameChoice {
+        self.rules.canonical(&choice).cloned().unwrap_or(choice)
+    }
+
+    fn result(&self, [first, second]: [&GameChoice; 2]) -> Option<usize> {
+        if first == second {
+            None
+        } else if self.rules.beats(first, second) {
+            Some(0)
+        } else {
+            Some(1)
+        }
+    }
+}
+
+pub struct OddsAndEvens;
+
+impl Game for OddsAndEvens {
+    fn validate_move(&self, choice: &GameChoice) -> bool {
+        fingers(choice.name()).is_some()
+    }
+
+    fn result(&self, [first, second]: [&GameChoice; 2]) -> Option<usize> {
+        let total = fingers(first.name())? + fingers(second.name())?;
+        Some(if total % 2 == 1 { 0 } else { 1 })
+    }
+}
+
+pub struct Morra;
+
+impl Morra {
+    fn parse(choice: &GameChoice) -> Option<(u32, u32)> {
+        let (shown, guess) = choice.name().split_once(':')?;
+        let guess = guess.parse().ok().filter(|guess| (2..=10).contains(guess))?;
+        Some((fingers(shown)?, guess))
+    }
+}
+
+impl Game for Morra {
+    fn validate_move(&self, choice: &GameChoice) -> bool {
+        Self::parse(choice).is_some()
+    }
+
+    fn result(&self, [first, second]: [&GameChoice; 2]) -> Option<usize> {
+        let ((shown_a, guess_a), (shown_b, guess_b)) = (Self::parse(first)?, Self::parse(second)?);
+        let total = shown_a + shown_b;
+        match (guess_a == total, guess_b == total) {
+            (true, false) => Some(0),
+            (false, true) => Some(1),
+            _ => None, // Both right or both wrong
+        }
+    }
+}
+
+fn fingers(shown: &str) -> Option<u32> {
+    shown.parse().ok().filter(|fingers| (1..=5).contains(fingers))
+}
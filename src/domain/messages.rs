@@ -1,7 +1,15 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use super::{GameChoice, PlayerInfo};
+use super::{
+    chat_enabled, BuildInfo, DrawPolicy, GameChoice, GameEndReason, GameMode, GameStatus, GameType, Notification, PauseReason, PlayerInfo, PlayerPhase,
+    ResultSignature, RoundSummary, RuleSet,
+};
+
+/// Wire protocol revision this server speaks. Clients declare theirs at `Connect`;
+/// from version 2 a frame may carry a JSON array of server messages.
+pub const PROTOCOL_VERSION: u32 = 2;
+pub const BATCHED_FRAMES_VERSION: u32 = 2;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
@@ -9,9 +17,64 @@ pub enum ClientMessage {
     Connect {
         #[serde(rename = "playerId")]
         player_id: Option<String>,
+        #[serde(default)]
+        locale: Option<String>, // e.g. "en" or "pt-BR"; human-readable text is served in it when available
+        #[serde(rename = "clientVersion", default)]
+        client_version: Option<String>, // Overrides the version taken from upgrade headers
+        #[serde(rename = "protocolVersion", default)]
+        protocol_version: Option<u32>, // 1 when omitted
+        #[serde(rename = "reconnectToken", skip_serializing_if = "Option::is_none", default)]
+        reconnect_token: Option<String>, // A `resumeToken` from `Connected`, or a cluster `reconnectToken` naming the game's node
+        #[serde(rename = "displayName", skip_serializing_if = "Option::is_none", default)]
+        display_name: Option<String>, // Shown to opponents in `GameStart`; screened by the profanity filter
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        region: Option<String>, // Where the client plays from; kept in the connection's `Extensions`
+        #[serde(skip_serializing_if = "Vec::is_empty", default)]
+        experiments: Vec<String>, // Experiment flags the client opts into; likewise
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        tenant: Option<String>, // Community the player belongs to; likewise, and counted against its quotas
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        invite: Option<String>, // Token from an invite link; seats the player in the inviting room
+    },
+    FindMatch {
+        #[serde(default)]
+        mode: GameMode,
+        #[serde(default)]
+        game: GameType,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        template: Option<String>, // Room template to be matched on; `mode` and `game` come from it
+    },
+    JoinRoom { room: String }, // Room id or join code of a pre-created match
+    CreateInvite {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        template: Option<String>, // Room template to play on; a 1v1 game when omitted
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        rules: Option<RuleSet>, // Choices and dominance matrix to play with; the classic rules when omitted
     },
-    FindMatch,
     PlayerMove { choice: GameChoice },
+    PauseRequest,
+    ResumeRequest,
+    AckNotifications { ids: Vec<String> },
+    BackfillResponse { accept: bool }, // Answer to `backfillOffer`
+    Chat { text: String },             // To everyone in the player's room
+    RequestState,                      // Answered with `state`, the server's view to reconcile against
+}
+
+impl ClientMessage {
+    /// Every `type` tag a client may send.
+    pub const TYPES: [&'static str; 11] = [
+        "connect",
+        "findMatch",
+        "joinRoom",
+        "createInvite",
+        "playerMove",
+        "pauseRequest",
+        "resumeRequest",
+        "ackNotifications",
+        "backfillResponse",
+        "chat",
+        "requestState",
+    ];
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +83,15 @@ pub enum ServerMessage {
     Connected {
         #[serde(rename = "playerId")]
         player_id: String,
+        locale: String, // Locale actually served, after fallback
+        #[serde(rename = "protocolVersion", skip_serializing_if = "Option::is_none", default)]
+        protocol_version: Option<u32>, // Negotiated version, only when the client declared one
+        #[serde(rename = "displayName", skip_serializing_if = "Option::is_none", default)]
+        display_name: Option<String>, // As accepted, which may be masked
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        build: Option<BuildInfo>, // The server build the client reached
+        #[serde(rename = "resumeToken", skip_serializing_if = "Option::is_none", default)]
+        resume_token: Option<String>, // Sent back as `reconnectToken` to resume this player's seat and inbox
     },
     Matchmaking {
         matched: bool,
@@ -28,27 +100,359 @@ pub enum ServerMessage {
         room_id: Option<String>,
     },
     GameStart {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>, // Per-room broadcast counter; a jump means messages were missed
         #[serde(rename = "roomId")]
         room_id: String,
+        #[serde(rename = "gameId")]
+        game_id: String, // Look the game up at GET /games/{gameId}, also after it ends
         players: Vec<PlayerInfo>,
         #[serde(rename = "maxRounds")]
         max_rounds: u32,
+        #[serde(rename = "drawPolicy")]
+        draw_policy: DrawPolicy,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        teams: Option<HashMap<String, String>>, // playerId -> teamId in team mode
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        rules: Option<RuleSet>, // Only sent when the room doesn't use classic rules
+        #[serde(rename = "fairnessCommitment", skip_serializing_if = "Option::is_none", default)]
+        fairness_commitment: Option<String>, // Bot games: hex sha256 of the seed revealed at GameEnd
+        #[serde(rename = "roundWindowMs", skip_serializing_if = "Option::is_none", default)]
+        round_window_ms: Option<u64>, // Blitz: each round resolves this long after it starts
+        #[serde(skip_serializing_if = "GameType::is_default", default)]
+        game: GameType,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        template: Option<String>, // Room template the game was set up from
+        #[serde(rename = "chatEnabled", skip_serializing_if = "Clone::clone", default = "chat_enabled")]
+        chat_enabled: bool, // Only sent, as false, in rooms whose template disables chat
     },
     RoundResult {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
         round: u32,
         winner: Option<String>,
         moves: HashMap<String, GameChoice>,
         scores: HashMap<String, u32>,
+        replay: bool, // Drawn round will be replayed under DrawPolicy::Replay
+        #[serde(rename = "decisionMs", skip_serializing_if = "HashMap::is_empty", default)]
+        decision_ms: HashMap<String, u64>, // Round start to the move arriving at the server; human players only
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        teams: Option<HashMap<String, String>>,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        signature: Option<ResultSignature>, // Only when the server has a result signing key
+    },
+    NextRound {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        round: u32,
+    },
+    /// A player's first move of the round is in; which move stays hidden until the result.
+    OpponentMoved {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        #[serde(rename = "playerId")]
+        player_id: String,
+    },
+    Chat {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        #[serde(rename = "playerId")]
+        player_id: String,
+        text: String,
+    },
+    /// Blitz's whole round in one frame: the result and, unless the game is over, the next
+    /// round's start. Lists are in `GameStart` player order; a null move was missed.
+    BlitzRound {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        round: u32,
+        winner: Option<usize>, // Seat index; None for a draw
+        moves: Vec<Option<GameChoice>>,
+        scores: Vec<u32>,
+        #[serde(rename = "decisionMs", default)]
+        decision_ms: Vec<Option<u64>>, // Null for a missed move or a bot
+        #[serde(rename = "nextRound", skip_serializing_if = "Option::is_none", default)]
+        next_round: Option<u32>,
+    },
+    GamePaused {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        #[serde(rename = "roomId")]
+        room_id: String,
+        reason: PauseReason,
+        #[serde(rename = "playerId")]
+        player_id: Option<String>,
+        #[serde(rename = "resumeWithinMs")]
+        resume_within_ms: u64,
+    },
+    PauseRequested {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        #[serde(rename = "playerId")]
+        player_id: String,
+    },
+    ResumeRequested {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        #[serde(rename = "playerId")]
+        player_id: String,
+    },
+    GameResumed {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        #[serde(rename = "roomId")]
+        room_id: String,
+        round: u32,
+        scores: HashMap<String, u32>,
     },
-    NextRound { round: u32 },
     GameEnd {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        #[serde(rename = "gameId")]
+        game_id: String,
         winner: Option<String>,
         #[serde(rename = "finalScores")]
         final_scores: HashMap<String, u32>,
+        reason: GameEndReason,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        teams: Option<HashMap<String, String>>,
+        #[serde(rename = "fairnessSeed", skip_serializing_if = "Option::is_none", default)]
+        fairness_seed: Option<String>, // Bot games: hex seed that generated every bot move
+        #[serde(default)]
+        rounds: Vec<RoundSummary>, // Every resolved round in order, replayed draws included
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        signature: Option<ResultSignature>,
+    },
+    /// Sent after each game of a series that doesn't decide it; the next game starts on its own.
+    SeriesUpdate {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        #[serde(rename = "seriesId")]
+        series_id: String,
+        #[serde(rename = "bestOf")]
+        best_of: u32,
+        scores: HashMap<String, u32>, // Games won so far
+        #[serde(rename = "nextGame")]
+        next_game: u32, // 1-based
+        #[serde(rename = "startsInMs")]
+        starts_in_ms: u64,
+    },
+    SeriesEnd {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        #[serde(rename = "seriesId")]
+        series_id: String,
+        winner: Option<String>, // None on a tied or abandoned series
+        scores: HashMap<String, u32>,
+        games: Vec<String>, // Game ids in play order
+        reason: GameEndReason,
     },
     PlayerLeft {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
         #[serde(rename = "playerId")]
         player_id: String,
     },
-    Error { message: String },
-}
\ No newline at end of file
+    RoomClosed {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>,
+        #[serde(rename = "roomId")]
+        room_id: String,
+        reason: String,
+    },
+    /// Answer to `requestState`. Room fields are left out when the player has no room.
+    State {
+        phase: PlayerPhase,
+        #[serde(rename = "roomId", skip_serializing_if = "Option::is_none", default)]
+        room_id: Option<String>,
+        #[serde(rename = "gameId", skip_serializing_if = "Option::is_none", default)]
+        game_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        status: Option<GameStatus>,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        round: Option<u32>,
+        #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+        scores: HashMap<String, u32>,
+        #[serde(rename = "awaitingMoves", skip_serializing_if = "Vec::is_empty", default)]
+        awaiting_moves: Vec<String>, // Players yet to move this round, in seat order
+        #[serde(rename = "yourMove", skip_serializing_if = "Option::is_none", default)]
+        your_move: Option<GameChoice>, // Already submitted this round
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        seq: Option<u64>, // Room broadcasts up to this one are reflected; drop any older still arriving
+    },
+    BackfillOffer {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "playerId")]
+        player_id: String, // The reserved player who left; answer with `backfillResponse`
+    },
+    Kicked { reason: String },
+    ServerBusy {
+        #[serde(rename = "retryAfterMs")]
+        retry_after_ms: u64, // FindMatch was refused at capacity; try again after this long
+    },
+    Notifications { notifications: Vec<Notification> }, // Unacknowledged inbox, oldest first
+    ReconnectToken {
+        token: String, // Send back at the next Connect; starts with the node id, so a load balancer can route on it
+        #[serde(rename = "nodeId")]
+        node_id: String, // Node holding the player's game state
+        #[serde(rename = "nodeUrl", skip_serializing_if = "Option::is_none", default)]
+        node_url: Option<String>, // Where to reconnect directly, when that node advertises one
+        #[serde(rename = "expiresAtMs")]
+        expires_at_ms: i64,
+    },
+    Invite {
+        #[serde(rename = "roomId")]
+        room_id: String, // Already holds the inviting player, who waits there for the guests
+        token: String,   // Send as `invite` at Connect
+        url: String,     // Shareable link; `GET` it for connection instructions
+        #[serde(rename = "expiresAtMs")]
+        expires_at_ms: i64,
+    },
+    RoomMigrated {
+        #[serde(rename = "roomId")]
+        room_id: String,
+        #[serde(rename = "nodeId")]
+        node_id: String, // The game now lives here; reconnect with the latest reconnect token
+        #[serde(rename = "nodeUrl", skip_serializing_if = "Option::is_none", default)]
+        node_url: Option<String>,
+    },
+    Warning {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        code: Option<String>,
+        message: String,
+    },
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        code: Option<String>, // Stable machine-readable code; `message` is localized
+        message: String,
+        #[serde(rename = "requestId", skip_serializing_if = "Option::is_none", default)]
+        request_id: Option<String>,
+    },
+}
+
+impl ServerMessage {
+    pub fn error(message: impl Into<String>) -> Self {
+        ServerMessage::Error {
+            code: None,
+            message: message.into(),
+            request_id: None,
+        }
+    }
+
+    /// Sequence number of a message its room broadcast, once stamped; for `State`, the last one it reflects.
+    pub fn seq(&self) -> Option<u64> {
+        match self {
+            ServerMessage::GameStart { seq, .. }
+            | ServerMessage::RoundResult { seq, .. }
+            | ServerMessage::NextRound { seq, .. }
+            | ServerMessage::OpponentMoved { seq, .. }
+            | ServerMessage::Chat { seq, .. }
+            | ServerMessage::BlitzRound { seq, .. }
+            | ServerMessage::GamePaused { seq, .. }
+            | ServerMessage::PauseRequested { seq, .. }
+            | ServerMessage::ResumeRequested { seq, .. }
+            | ServerMessage::GameResumed { seq, .. }
+            | ServerMessage::GameEnd { seq, .. }
+            | ServerMessage::SeriesUpdate { seq, .. }
+            | ServerMessage::SeriesEnd { seq, .. }
+            | ServerMessage::PlayerLeft { seq, .. }
+            | ServerMessage::RoomClosed { seq, .. }
+            | ServerMessage::State { seq, .. } => *seq,
+            _ => None,
+        }
+    }
+
+    /// Where rooms stamp the sequence number; None for messages rooms don't broadcast.
+    pub fn seq_mut(&mut self) -> Option<&mut Option<u64>> {
+        match self {
+            ServerMessage::GameStart { seq, .. }
+            | ServerMessage::RoundResult { seq, .. }
+            | ServerMessage::NextRound { seq, .. }
+            | ServerMessage::OpponentMoved { seq, .. }
+            | ServerMessage::Chat { seq, .. }
+            | ServerMessage::BlitzRound { seq, .. }
+            | ServerMessage::GamePaused { seq, .. }
+            | ServerMessage::PauseRequested { seq, .. }
+            | ServerMessage::ResumeRequested { seq, .. }
+            | ServerMessage::GameResumed { seq, .. }
+            | ServerMessage::GameEnd { seq, .. }
+            | ServerMessage::SeriesUpdate { seq, .. }
+            | ServerMessage::SeriesEnd { seq, .. }
+            | ServerMessage::PlayerLeft { seq, .. }
+            | ServerMessage::RoomClosed { seq, .. } => Some(seq),
+            _ => None,
+        }
+    }
+
+    /// Stamps the connection/request correlation id onto error messages.
+    pub fn with_request_id(mut self, id: &str) -> Self {
+        if let ServerMessage::Error { request_id, .. } = &mut self {
+            *request_id = Some(id.to_string());
+        }
+        self
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each variant's position in `ClientMessage::TYPES`; a new variant won't compile until it has one
+    fn type_index(message: &ClientMessage) -> usize {
+        match message {
+            ClientMessage::Connect { .. } => 0,
+            ClientMessage::FindMatch { .. } => 1,
+            ClientMessage::JoinRoom { .. } => 2,
+            ClientMessage::CreateInvite { .. } => 3,
+            ClientMessage::PlayerMove { .. } => 4,
+            ClientMessage::PauseRequest => 5,
+            ClientMessage::ResumeRequest => 6,
+            ClientMessage::AckNotifications { .. } => 7,
+            ClientMessage::BackfillResponse { .. } => 8,
+            ClientMessage::Chat { .. } => 9,
+            ClientMessage::RequestState => 10,
+        }
+    }
+
+    #[test]
+    fn test_client_message_types_match_the_serialized_tags() {
+        let messages = [
+            ClientMessage::Connect {
+                player_id: None,
+                locale: None,
+                client_version: None,
+                protocol_version: None,
+                reconnect_token: None,
+                display_name: None,
+                region: None,
+                experiments: Vec::new(),
+                tenant: None,
+                invite: None,
+            },
+            ClientMessage::FindMatch {
+                mode: GameMode::default(),
+                game: GameType::default(),
+                template: None,
+            },
+            ClientMessage::JoinRoom { room: "room".to_string() },
+            ClientMessage::CreateInvite { template: None, rules: None },
+            ClientMessage::PlayerMove { choice: GameChoice::Rock },
+            ClientMessage::PauseRequest,
+            ClientMessage::ResumeRequest,
+            ClientMessage::AckNotifications { ids: Vec::new() },
+            ClientMessage::BackfillResponse { accept: true },
+            ClientMessage::Chat { text: "gg".to_string() },
+            ClientMessage::RequestState,
+        ];
+        assert_eq!(messages.len(), ClientMessage::TYPES.len());
+
+        let mut seen = [false; ClientMessage::TYPES.len()];
+        for message in &messages {
+            let index = type_index(message);
+            let json = serde_json::to_value(message).unwrap();
+            assert_eq!(json["type"], ClientMessage::TYPES[index], "{:?}", message);
+            seen[index] = true;
+        }
+        assert!(seen.iter().all(|&seen| seen), "a variant is missing from the samples");
+    }
+}
@@ -1,7 +1,23 @@
 pub mod game;
 pub mod player;
 pub mod messages;
+pub mod events;
+pub mod rules;
+pub mod notification;
+pub mod title;
+pub mod games;
+pub mod room_template;
+pub mod extensions;
+pub mod build_info;
 
 pub use game::*;
 pub use player::*;
-pub use messages::*;
\ No newline at end of file
+pub use messages::*;
+pub use events::*;
+pub use rules::*;
+pub use notification::*;
+pub use title::*;
+pub use games::*;
+pub use room_template::*;
+pub use extensions::*;
+pub use build_info::*;
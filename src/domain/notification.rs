@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationKind {
+    ChallengeReceived,
+    TournamentStarting,
+    AchievementUnlocked,
+    SeasonEnded,
+}
+
+/// Something that happened while a player was away, held until they acknowledge it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Notification {
+    pub id: String,
+    #[serde(rename = "playerId")]
+    pub player_id: String,
+    pub kind: NotificationKind,
+    #[serde(default)]
+    pub data: serde_json::Value, // Kind-specific details, e.g. the challenger or tournament id
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
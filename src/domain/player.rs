@@ -1,22 +1,66 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+use super::extensions::Extensions;
 use super::messages::ServerMessage;
 
+pub const MAX_DISPLAY_NAME_CHARS: usize = 32;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerInfo {
     pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub title: Option<String>, // Name of the player's equipped title
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub name: Option<String>, // Display name from `Connect`
+}
+
+/// Where a player stands with matchmaking, as far as the server is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlayerPhase {
+    Idle,
+    Queued,   // In a matchmaking queue, or seated in a reserved room that is still filling
+    InGame,   // Playing or paused
+    PostGame, // Their last room has finished
 }
 
 pub struct Player {
     pub id: String,
     pub sender: mpsc::UnboundedSender<ServerMessage>,
+    pub latency: Arc<LatencyEstimate>, // Measured by the connection's keepalive pings
+    pub display_name: Option<String>,  // Already screened; None when shadow-muted
+    pub extensions: Extensions,        // The connection's metadata from `Connect`
 }
 
 impl Player {
     pub fn new(id: String, sender: mpsc::UnboundedSender<ServerMessage>) -> Self {
-        Self { id, sender }
+        Self {
+            id,
+            sender,
+            latency: Arc::new(LatencyEstimate::new()),
+            display_name: None,
+            extensions: Extensions::new(),
+        }
+    }
+
+    pub fn with_latency(mut self, latency: Arc<LatencyEstimate>) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn with_display_name(mut self, display_name: Option<String>) -> Self {
+        self.display_name = display_name;
+        self
+    }
+
+    pub fn with_extensions(mut self, extensions: Extensions) -> Self {
+        self.extensions = extensions;
+        self
     }
 
     pub async fn send_message(&self, message: &ServerMessage) -> Result<()> {
@@ -25,9 +69,19 @@ impl Player {
             .map_err(|_| anyhow::anyhow!("Failed to send message to player {}", self.id))?;
         Ok(())
     }
+
+    /// False once the connection behind this player has gone away.
+    pub fn is_connected(&self) -> bool {
+        !self.sender.is_closed()
+    }
+
+    /// Whether both handles deliver to the same connection.
+    pub fn same_session(&self, other: &Player) -> bool {
+        self.sender.same_channel(&other.sender)
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct PlayerStats {
     pub wins: u32,
     pub losses: u32,
@@ -35,13 +89,63 @@ pub struct PlayerStats {
     pub total_games: u32,
 }
 
-impl Default for PlayerStats {
+/// Smoothed round-trip time of one connection, from ping/pong pairs. Timestamps travel in the
+/// ping payload where the transport echoes it; otherwise the last ping sent is used.
+#[derive(Debug)]
+pub struct LatencyEstimate {
+    epoch: Instant,
+    last_ping_us: AtomicU64, // Since `epoch`
+    srtt_us: AtomicU64,      // 0 until the first pong
+}
+
+impl Default for LatencyEstimate {
     fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyEstimate {
+    pub fn new() -> Self {
         Self {
-            wins: 0,
-            losses: 0,
-            draws: 0,
-            total_games: 0,
+            epoch: Instant::now(),
+            last_ping_us: AtomicU64::new(0),
+            srtt_us: AtomicU64::new(0),
         }
     }
-}
\ No newline at end of file
+
+    /// Call when sending a ping; returns the payload to put in it.
+    pub fn ping_sent(&self) -> Vec<u8> {
+        let now = (self.epoch.elapsed().as_micros() as u64).max(1);
+        self.last_ping_us.store(now, Ordering::Relaxed);
+        now.to_be_bytes().to_vec()
+    }
+
+    /// Call on a pong, with its payload when the transport echoes one.
+    pub fn pong_received(&self, payload: Option<&[u8]>) {
+        let sent = match payload.and_then(|payload| <[u8; 8]>::try_from(payload).ok()) {
+            Some(bytes) => u64::from_be_bytes(bytes),
+            None => self.last_ping_us.load(Ordering::Relaxed),
+        };
+        let now = self.epoch.elapsed().as_micros() as u64;
+        if sent == 0 || sent > now {
+            return;
+        }
+        self.observe(Duration::from_micros(now - sent));
+    }
+
+    /// Folds in one sample with the same 1/8 gain TCP uses for its smoothed RTT.
+    pub fn observe(&self, rtt: Duration) {
+        let sample = (rtt.as_micros() as u64).max(1);
+        let _ = self.srtt_us.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |srtt| {
+            Some(if srtt == 0 { sample } else { srtt - srtt / 8 + sample / 8 })
+        });
+    }
+
+    /// None until a pong has come back.
+    pub fn rtt(&self) -> Option<Duration> {
+        match self.srtt_us.load(Ordering::Relaxed) {
+            0 => None,
+            us => Some(Duration::from_micros(us)),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{chat_enabled, DrawPolicy, GameConfig, GameMode, GameType, MoveChangePolicy};
+
+/// A named room setup that players can ask to be matched on. Unset fields keep the
+/// server's defaults for the mode.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomTemplate {
+    #[serde(default)]
+    pub name: String, // Taken from the path when saved through the admin API
+    #[serde(default)]
+    pub mode: GameMode,
+    #[serde(default)]
+    pub game: GameType,
+    #[serde(default)]
+    pub script: Option<String>, // Operator rule script resolving rounds in place of `game`
+    #[serde(default)]
+    pub max_rounds: Option<u32>,
+    #[serde(default)]
+    pub draw_policy: Option<DrawPolicy>,
+    #[serde(default)]
+    pub move_changes: Option<MoveChangePolicy>,
+    #[serde(default)]
+    pub match_time_limit_ms: Option<u64>,
+    #[serde(default)]
+    pub reconnect_grace_ms: Option<u64>,
+    #[serde(default)]
+    pub round_window_ms: Option<u64>, // Turns every round into a timed one
+    #[serde(default = "chat_enabled")]
+    pub chat_enabled: bool,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RoomTemplate {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty() || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            bail!("Template names are letters, digits, '-' and '_'");
+        }
+        if self.max_rounds == Some(0) {
+            bail!("A template needs at least one round");
+        }
+        let custom_rules = !self.game.is_default() || self.script.is_some();
+        if custom_rules && !matches!(self.mode, GameMode::Solo | GameMode::Blitz) {
+            bail!("Only rock-paper-scissors is played in {:?} mode", self.mode);
+        }
+        Ok(())
+    }
+
+    /// Overrides `config`, already set up for the template's mode, with what the template sets.
+    pub fn apply(&self, config: &mut GameConfig) {
+        config.template = Some(self.name.clone());
+        config.script = self.script.clone();
+        config.chat_enabled = self.chat_enabled;
+        if let Some(max_rounds) = self.max_rounds {
+            config.max_rounds = max_rounds;
+        }
+        if let Some(draw_policy) = self.draw_policy {
+            config.draw_policy = draw_policy;
+        }
+        if let Some(move_changes) = self.move_changes {
+            config.move_changes = move_changes;
+        }
+        if let Some(limit) = self.match_time_limit_ms {
+            config.match_time_limit_ms = Some(limit);
+        }
+        if let Some(grace) = self.reconnect_grace_ms {
+            config.reconnect_grace_ms = grace;
+        }
+        if let Some(window) = self.round_window_ms {
+            config.round_window_ms = Some(window);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::GameChoice;
+
+/// Choice set plus a dominance matrix: `matrix[i][j]` is 1 when choice `i`
+/// beats choice `j`, -1 when it loses and 0 on the diagonal.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RuleSet {
+    pub choices: Vec<GameChoice>,
+    pub matrix: Vec<Vec<i8>>,
+}
+
+impl RuleSet {
+    pub fn classic() -> Self {
+        Self {
+            choices: vec![GameChoice::Rock, GameChoice::Paper, GameChoice::Scissors],
+            matrix: vec![vec![0, -1, 1], vec![1, 0, -1], vec![-1, 1, 0]],
+        }
+    }
+
+    pub fn is_classic(&self) -> bool {
+        *self == Self::classic()
+    }
+
+    /// A usable matrix is square, has a zero diagonal, decides every pair
+    /// (complete) and never lets both sides of a pair win (antisymmetric).
+    pub fn validate(&self) -> Result<()> {
+        let n = self.choices.len();
+        if n < 2 {
+            bail!("Rule set needs at least two choices");
+        }
+
+        let mut seen = HashSet::new();
+        for choice in &self.choices {
+            if choice.name().is_empty() || !seen.insert(choice.name().to_ascii_lowercase()) {
+                bail!("Choice names must be unique and non-empty: {:?}", choice.name());
+            }
+        }
+
+        if self.matrix.len() != n || self.matrix.iter().any(|row| row.len() != n) {
+            bail!("Dominance matrix must be {}x{}", n, n);
+        }
+
+        for i in 0..n {
+            if self.matrix[i][i] != 0 {
+                bail!("{} must not beat itself", self.choices[i].name());
+            }
+            for j in (i + 1)..n {
+                let (a, b) = (self.matrix[i][j], self.matrix[j][i]);
+                if a == 0 || b == 0 {
+                    bail!("Matrix is incomplete: {} vs {} is undecided", self.choices[i].name(), self.choices[j].name());
+                }
+                if !matches!((a, b), (1, -1) | (-1, 1)) {
+                    bail!("Matrix is not antisymmetric at {} vs {}", self.choices[i].name(), self.choices[j].name());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn allows(&self, choice: &GameChoice) -> bool {
+        self.index_of(choice).is_some()
+    }
+
+    /// The rule set's own spelling of `choice`; names match in any case.
+    pub fn canonical(&self, choice: &GameChoice) -> Option<&GameChoice> {
+        self.index_of(choice).map(|i| &self.choices[i])
+    }
+
+    pub fn beats(&self, a: &GameChoice, b: &GameChoice) -> bool {
+        match (self.index_of(a), self.index_of(b)) {
+            (Some(i), Some(j)) => self.matrix[i][j] > 0,
+            _ => false,
+        }
+    }
+
+    fn index_of(&self, choice: &GameChoice) -> Option<usize> {
+        self.choices.iter().position(|c| c.name().eq_ignore_ascii_case(choice.name()))
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::application::{GameRoom, MoveRefused};
+    use crate::domain::{GameChoice, GameConfig, Player, RuleSet};
+
+    #[tokio::test]
+    async fn test_custom_rule_matrix() {
+        let names = ["rock", "paper", "scissors", "spock", "lizard"];
+        // Rock-paper-scissors-Spock-lizard: i beats j when (i - j) mod 5 is odd
+        let matrix: Vec<Vec<i8>> = (0..5)
+            .map(|i| (0..5).map(|j| match (i + 5 - j) % 5 { 0 => 0, 1 | 3 => 1, _ => -1 }).collect())
+            .collect();
+        let rules = RuleSet {
+            choices: names.iter().map(|name| GameChoice::from(name.to_string())).collect(),
+            matrix,
+        };
+        rules.validate().unwrap();
+        let lizard = GameChoice::from("lizard".to_string());
+        let spock = GameChoice::from("spock".to_string());
+        assert!(rules.beats(&lizard, &spock));
+        assert!(rules.beats(&spock, &GameChoice::Rock));
+        assert!(rules.beats(&GameChoice::Rock, &lizard));
+        assert!(!rules.beats(&spock, &lizard));
+
+        let mut room = GameRoom::new("room".to_string(), GameConfig { rules: rules.clone(), ..GameConfig::default() });
+        let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+        let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+        room.add_player(Arc::new(Player::new("p1".to_string(), tx1))).unwrap();
+        room.add_player(Arc::new(Player::new("p2".to_string(), tx2))).unwrap();
+        let refused = room.submit_move("p1", GameChoice::from("dynamite".to_string())).unwrap_err();
+        // Dynamite isn't in the rule set, so it's refused and the round still waits on p1
+        assert_eq!(refused.downcast_ref::<MoveRefused>(), Some(&MoveRefused::InvalidChoice));
+        assert!(!room.submit_move("p2", spock).unwrap());
+        // Taken in the rule set's spelling, whatever the case it was sent in
+        assert!(room.submit_move("p1", GameChoice::from("Lizard".to_string())).unwrap());
+        assert_eq!(room.moves["p1"].choice, lizard);
+        room.process_round().await.unwrap();
+        assert_eq!((room.scores["p1"], room.scores["p2"]), (1, 0));
+
+        let mut broken = rules.clone();
+        broken.matrix[0][1] = 1; // rock and paper both "win"
+        assert!(broken.validate().is_err());
+        let mut out_of_range = rules.clone();
+        out_of_range.matrix[1][0] = i8::MIN; // Negating it would overflow
+        assert!(out_of_range.validate().is_err());
+        let mut incomplete = rules;
+        incomplete.matrix[3][4] = 0;
+        incomplete.matrix[4][3] = 0;
+        assert!(incomplete.validate().is_err());
+        assert!(RuleSet { choices: vec![GameChoice::Rock, GameChoice::Rock], matrix: vec![vec![0, 1], vec![-1, 0]] }
+            .validate()
+            .is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TitleSource {
+    Achievement,
+    Tournament,
+    Season,
+}
+
+/// A cosmetic title or badge a player has earned. Purely for display.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Title {
+    pub id: String,   // Stable key, e.g. "tournament-2026-spring-champion"
+    pub name: String, // Shown next to the player, e.g. "Spring Champion"
+    pub source: TitleSource,
+    pub earned_at: DateTime<Utc>,
+}
+
+/// Every title a player holds, earliest first, and the one they show.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerTitles {
+    pub titles: Vec<Title>,
+    pub equipped: Option<String>, // Title id
+}
+
+impl PlayerTitles {
+    pub fn equipped_title(&self) -> Option<&Title> {
+        let equipped = self.equipped.as_ref()?;
+        self.titles.iter().find(|title| title.id == *equipped)
+    }
+}
@@ -0,0 +1,407 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+use warp::http::StatusCode;
+use warp::Filter;
+
+#[cfg(feature = "chaos")]
+use crate::application::{ChaosSettings, CHAOS};
+use crate::application::{GameManager, SeriesManager};
+use crate::config::{Role, SecretStore};
+use crate::domain::NotificationKind;
+use super::audit_log::{AdminAction, AuditLog, AuditQuery};
+use super::notification_inbox::NotificationInbox;
+
+#[derive(Debug)]
+pub struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+/// A valid API key whose role doesn't allow the route.
+#[derive(Debug)]
+pub struct Forbidden;
+
+impl warp::reject::Reject for Forbidden {}
+
+#[derive(Serialize)]
+pub struct AdminActionResponse {
+    pub action: AdminAction,
+    pub target: String,
+    pub success: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotifyRequest {
+    pub kind: NotificationKind,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateMatchRequest {
+    pub players: Vec<String>,
+    #[serde(default)]
+    pub template: Option<String>, // Room template the room is set up from
+    #[serde(default)]
+    pub tournament: Option<String>, // Streams the match on `/tournaments/{id}/stream`
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSeriesRequest {
+    pub players: Vec<String>,
+    #[serde(rename = "bestOf", default = "default_best_of")]
+    pub best_of: u32,
+}
+
+fn default_best_of() -> u32 {
+    5
+}
+
+#[cfg(feature = "chaos")]
+#[derive(Debug, Deserialize)]
+pub struct KillRoomsRequest {
+    pub count: usize,
+}
+
+pub fn create_admin_routes(
+    game_manager: Arc<GameManager>,
+    audit_log: Arc<AuditLog>,
+    notifications: Arc<NotificationInbox>,
+    secrets: Arc<SecretStore>,
+    series: Arc<SeriesManager>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let kick = warp::path!("admin" / "players" / String / "kick")
+        .and(warp::post())
+        .and(with_role(secrets.clone(), Role::Operator))
+        .and(with_game_manager(game_manager.clone()))
+        .and(with_audit_log(audit_log.clone()))
+        .and_then(kick_handler);
+
+    let notify = warp::path!("admin" / "players" / String / "notifications")
+        .and(warp::post())
+        .and(with_role(secrets.clone(), Role::Operator))
+        .and(warp::body::json::<NotifyRequest>())
+        .and(warp::any().map(move || notifications.clone()))
+        .and(with_audit_log(audit_log.clone()))
+        .and_then(notify_handler);
+
+    // Lets tournament organizers pair players; they then enter with JoinRoom
+    let create_match = warp::path!("matches")
+        .and(warp::post())
+        .and(with_role(secrets.clone(), Role::Operator))
+        .and(warp::body::json::<CreateMatchRequest>())
+        .and(with_game_manager(game_manager.clone()))
+        .and(with_audit_log(audit_log.clone()))
+        .and_then(create_match_handler);
+
+    // Same as a match, but the players stay paired until one of them takes the series
+    let create_series = warp::path!("series")
+        .and(warp::post())
+        .and(with_role(secrets.clone(), Role::Operator))
+        .and(warp::body::json::<CreateSeriesRequest>())
+        .and(with_series(series.clone()))
+        .and(with_audit_log(audit_log.clone()))
+        .and_then(create_series_handler);
+
+    let show_series = warp::path!("series" / String)
+        .and(warp::get())
+        .and(with_role(secrets.clone(), Role::Viewer))
+        .and(with_series(series))
+        .and_then(show_series_handler);
+
+    let suspicion = warp::path!("admin" / "players" / String / "suspicion")
+        .and(warp::get())
+        .and(with_role(secrets.clone(), Role::Viewer))
+        .and(with_game_manager(game_manager.clone()))
+        .and_then(suspicion_handler);
+
+    let suspects = warp::path!("admin" / "suspects")
+        .and(warp::get())
+        .and(with_role(secrets.clone(), Role::Viewer))
+        .and(with_game_manager(game_manager.clone()))
+        .and_then(suspects_handler);
+
+    // Walks every room; the public /stats reads counters instead
+    let stats = warp::path!("admin" / "stats")
+        .and(warp::get())
+        .and(with_role(secrets.clone(), Role::Viewer))
+        .and(with_game_manager(game_manager.clone()))
+        .and_then(deep_stats_handler);
+
+    let close_room = warp::path!("admin" / "rooms" / String / "close")
+        .and(warp::post())
+        .and(with_role(secrets.clone(), Role::Operator))
+        .and(with_game_manager(game_manager.clone()))
+        .and(with_audit_log(audit_log.clone()))
+        .and_then(close_room_handler);
+
+    #[cfg(feature = "chaos")]
+    let chaos = create_chaos_routes(game_manager, audit_log.clone(), secrets.clone());
+
+    let audit = warp::path!("admin" / "audit")
+        .and(warp::get())
+        .and(with_role(secrets, Role::Viewer))
+        .and(warp::query::<AuditQuery>())
+        .and(with_audit_log(audit_log))
+        .and_then(audit_handler);
+
+    let routes = kick
+        .or(notify)
+        .or(create_match)
+        .or(create_series)
+        .or(show_series)
+        .or(suspicion)
+        .or(suspects)
+        .or(stats)
+        .or(close_room)
+        .or(audit);
+    #[cfg(feature = "chaos")]
+    let routes = routes.or(chaos);
+    routes
+}
+
+// Fault injection: GET shows the faults and what they have done, PUT replaces them,
+// DELETE turns them all off
+#[cfg(feature = "chaos")]
+fn create_chaos_routes(
+    game_manager: Arc<GameManager>,
+    audit_log: Arc<AuditLog>,
+    secrets: Arc<SecretStore>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let show = warp::path!("admin" / "chaos")
+        .and(warp::get())
+        .and(with_role(secrets.clone(), Role::Viewer))
+        .map(|_actor: String| warp::reply::json(&CHAOS.snapshot()));
+
+    let set = warp::path!("admin" / "chaos")
+        .and(warp::put())
+        .and(with_role(secrets.clone(), Role::Admin))
+        .and(warp::body::json::<ChaosSettings>())
+        .and(with_audit_log(audit_log.clone()))
+        .map(|actor: String, settings: ChaosSettings, audit_log: Arc<AuditLog>| {
+            CHAOS.set(settings);
+            let target = serde_json::to_string(&settings).unwrap_or_default();
+            audit_log.record_or_log(&actor, AdminAction::Chaos, &target, true);
+            warp::reply::json(&CHAOS.snapshot())
+        });
+
+    let clear = warp::path!("admin" / "chaos")
+        .and(warp::delete())
+        .and(with_role(secrets.clone(), Role::Admin))
+        .and(with_audit_log(audit_log.clone()))
+        .map(|actor: String, audit_log: Arc<AuditLog>| {
+            CHAOS.clear();
+            audit_log.record_or_log(&actor, AdminAction::Chaos, "clear", true);
+            warp::reply::json(&CHAOS.snapshot())
+        });
+
+    let kill_rooms = warp::path!("admin" / "chaos" / "kill-rooms")
+        .and(warp::post())
+        .and(with_role(secrets, Role::Admin))
+        .and(warp::body::json::<KillRoomsRequest>())
+        .and(with_game_manager(game_manager))
+        .and(with_audit_log(audit_log))
+        .and_then(kill_rooms_handler);
+
+    show.or(set).or(clear).or(kill_rooms)
+}
+
+// Authenticates the caller against the API keys and checks the key carries at least `role`;
+// the actor is the matching key id, optionally annotated with the X-Admin-Actor display name
+pub(crate) fn with_role(
+    secrets: Arc<SecretStore>,
+    role: Role,
+) -> impl Filter<Extract = (String,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::header::optional::<String>("x-api-key"))
+        .and(warp::header::optional::<String>("x-admin-actor"))
+        .and_then(move |authorization: Option<String>, api_key: Option<String>, name: Option<String>| {
+            let secrets = secrets.clone();
+            async move {
+                let presented = api_key.or_else(|| {
+                    authorization.and_then(|value| value.strip_prefix("Bearer ").map(str::to_string))
+                });
+                let (key_id, key_role) = presented
+                    .and_then(|key| secrets.verify_api_key(&key))
+                    .ok_or_else(|| warp::reject::custom(Unauthorized))?;
+                if key_role < role {
+                    return Err(warp::reject::custom(Forbidden));
+                }
+
+                Ok::<_, warp::Rejection>(match name {
+                    Some(name) => format!("{} ({})", key_id, name),
+                    None => key_id,
+                })
+            }
+        })
+}
+
+fn with_game_manager(
+    game_manager: Arc<GameManager>,
+) -> impl Filter<Extract = (Arc<GameManager>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || game_manager.clone())
+}
+
+fn with_series(series: Arc<SeriesManager>) -> impl Filter<Extract = (Arc<SeriesManager>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || series.clone())
+}
+
+pub(crate) fn with_audit_log(
+    audit_log: Arc<AuditLog>,
+) -> impl Filter<Extract = (Arc<AuditLog>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || audit_log.clone())
+}
+
+fn audited_reply(audit_log: &AuditLog, actor: &str, action: AdminAction, target: String, success: bool) -> impl warp::Reply {
+    audit_log.record_or_log(actor, action, &target, success);
+
+    let status = if success { StatusCode::OK } else { StatusCode::NOT_FOUND };
+    let response = AdminActionResponse { action, target, success };
+    warp::reply::with_status(warp::reply::json(&response), status)
+}
+
+async fn kick_handler(
+    player_id: String,
+    actor: String,
+    game_manager: Arc<GameManager>,
+    audit_log: Arc<AuditLog>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let success = game_manager
+        .kick_player(&player_id, "Removed by administrator")
+        .await
+        .unwrap_or_else(|e| {
+            error!("Kick failed for {}: {}", player_id, e);
+            false
+        });
+
+    Ok(audited_reply(&audit_log, &actor, AdminAction::Kick, player_id, success))
+}
+
+async fn notify_handler(
+    player_id: String,
+    actor: String,
+    request: NotifyRequest,
+    notifications: Arc<NotificationInbox>,
+    audit_log: Arc<AuditLog>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let notification = notifications.push(&player_id, request.kind, request.data);
+    audit_log.record_or_log(&actor, AdminAction::Notify, &player_id, notification.is_ok());
+
+    match notification {
+        Ok(notification) => Ok(warp::reply::with_status(warp::reply::json(&notification), StatusCode::CREATED)),
+        Err(e) => {
+            error!("Failed to store notification for {}: {}", player_id, e);
+            Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "error": "Failed to store notification" })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ))
+        }
+    }
+}
+
+async fn create_match_handler(
+    actor: String,
+    request: CreateMatchRequest,
+    game_manager: Arc<GameManager>,
+    audit_log: Arc<AuditLog>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let target = request.players.join(",");
+    let template = request.template.as_deref();
+    let reserved = match &request.tournament {
+        Some(tournament) => game_manager.reserve_tournament_match(tournament, request.players, template).await,
+        None => game_manager.reserve_match_from(request.players, template).await,
+    };
+    audit_log.record_or_log(&actor, AdminAction::CreateMatch, &target, reserved.is_ok());
+
+    // Bad pairings (wrong count, duplicates, players already seated) are the caller's to fix
+    Ok(match reserved {
+        Ok(reserved) => warp::reply::with_status(warp::reply::json(&reserved), StatusCode::CREATED),
+        Err(e) => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "error": e.to_string() })),
+            StatusCode::CONFLICT,
+        ),
+    })
+}
+
+async fn create_series_handler(
+    actor: String,
+    request: CreateSeriesRequest,
+    series: Arc<SeriesManager>,
+    audit_log: Arc<AuditLog>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let target = request.players.join(",");
+    let created = series.create(request.players, request.best_of).await;
+    audit_log.record_or_log(&actor, AdminAction::CreateSeries, &target, created.is_ok());
+
+    Ok(match created {
+        Ok(created) => warp::reply::with_status(warp::reply::json(&created), StatusCode::CREATED),
+        Err(e) => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "error": e.to_string() })),
+            StatusCode::CONFLICT,
+        ),
+    })
+}
+
+async fn show_series_handler(series_id: String, _actor: String, series: Arc<SeriesManager>) -> Result<impl warp::Reply, warp::Rejection> {
+    match series.get(&series_id) {
+        Some(series) => Ok(warp::reply::json(&series)),
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+async fn close_room_handler(
+    room_id: String,
+    actor: String,
+    game_manager: Arc<GameManager>,
+    audit_log: Arc<AuditLog>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let success = game_manager
+        .close_room(&room_id, "Closed by administrator")
+        .await
+        .unwrap_or_else(|e| {
+            error!("Close room failed for {}: {}", room_id, e);
+            false
+        });
+
+    Ok(audited_reply(&audit_log, &actor, AdminAction::CloseRoom, room_id, success))
+}
+
+#[cfg(feature = "chaos")]
+async fn kill_rooms_handler(
+    actor: String,
+    request: KillRoomsRequest,
+    game_manager: Arc<GameManager>,
+    audit_log: Arc<AuditLog>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let killed = game_manager.kill_random_rooms(request.count).await;
+    let target = format!("kill-rooms:{}", killed.join(","));
+    audit_log.record_or_log(&actor, AdminAction::Chaos, &target, true);
+    Ok(warp::reply::json(&serde_json::json!({ "killed": killed })))
+}
+
+async fn audit_handler(
+    _actor: String,
+    query: AuditQuery,
+    audit_log: Arc<AuditLog>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&audit_log.query(&query)))
+}
+
+async fn suspicion_handler(
+    player_id: String,
+    _actor: String,
+    game_manager: Arc<GameManager>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match game_manager.bot_detector().report(&player_id) {
+        Some(report) => Ok(warp::reply::json(&report)),
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+async fn suspects_handler(_actor: String, game_manager: Arc<GameManager>) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&game_manager.bot_detector().suspects()))
+}
+
+async fn deep_stats_handler(_actor: String, game_manager: Arc<GameManager>) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&game_manager.deep_stats().await))
+}
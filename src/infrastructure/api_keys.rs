@@ -0,0 +1,282 @@
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::error;
+use uuid::Uuid;
+use warp::http::StatusCode;
+use warp::Filter;
+
+use super::admin_api::{with_audit_log, with_role};
+use super::audit_log::{AdminAction, AuditLog};
+use super::jsonl_store::JsonlStore;
+use crate::config::{hash_api_key, ApiKey, Role, SecretStore};
+
+// One line of the keys file; a revocation removes every earlier record for the id
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum ApiKeyRecord {
+    Issued { key: ApiKey },
+    Revoked { id: String },
+}
+
+/// API keys issued at runtime, installed in the `SecretStore` and mirrored (hashed) to a
+/// JSON-lines file so they survive restarts. Configured admin keys are never written here.
+pub struct ApiKeyStore {
+    secrets: Arc<SecretStore>,
+    log: JsonlStore<ApiKeyRecord>,
+}
+
+impl ApiKeyStore {
+    pub fn in_memory(secrets: Arc<SecretStore>) -> Self {
+        Self {
+            secrets,
+            log: JsonlStore::in_memory(),
+        }
+    }
+
+    /// Opens (or creates) the keys file and installs the keys it still holds.
+    pub fn open(path: impl AsRef<Path>, secrets: Arc<SecretStore>) -> Result<Self> {
+        let mut store = Self::in_memory(secrets);
+        store.log = JsonlStore::open(path, "API key record", |record| store.apply(record))?;
+        Ok(store)
+    }
+
+    pub fn list(&self) -> Vec<ApiKey> {
+        self.secrets.issued_api_keys()
+    }
+
+    /// Issues a key with `role`, returning it with the only copy of the key itself.
+    /// None if `id` is already taken by a configured or issued key.
+    pub fn issue(&self, id: Option<String>, role: Role, created_by: &str) -> Result<Option<(ApiKey, String)>> {
+        let id = id.unwrap_or_else(|| format!("key-{}", &Uuid::new_v4().simple().to_string()[..8]));
+        if self.secrets.has_key_id(&id) {
+            return Ok(None);
+        }
+
+        let secret = format!("rps_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let key = ApiKey {
+            id,
+            role,
+            key_hash: hash_api_key(&secret),
+            created_at: Utc::now(),
+            created_by: created_by.to_string(),
+        };
+        let record = ApiKeyRecord::Issued { key: key.clone() };
+        self.log.persist(&record)?;
+        self.apply(record);
+        Ok(Some((key, secret)))
+    }
+
+    /// False if no issued key has this id.
+    pub fn revoke(&self, id: &str) -> Result<bool> {
+        if !self.secrets.issued_api_keys().iter().any(|key| key.id == id) {
+            return Ok(false);
+        }
+
+        let record = ApiKeyRecord::Revoked { id: id.to_string() };
+        self.log.persist(&record)?;
+        self.apply(record);
+        Ok(true)
+    }
+
+    fn apply(&self, record: ApiKeyRecord) {
+        match record {
+            ApiKeyRecord::Issued { key } => self.secrets.install_api_key(key),
+            ApiKeyRecord::Revoked { id } => {
+                self.secrets.revoke_api_key(&id);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IssueApiKeyRequest {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub role: Role,
+}
+
+// What listing shows of a key; the hash stays on the server
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiKeySummary<'a> {
+    id: &'a str,
+    role: Role,
+    created_at: chrono::DateTime<Utc>,
+    created_by: &'a str,
+}
+
+impl<'a> From<&'a ApiKey> for ApiKeySummary<'a> {
+    fn from(key: &'a ApiKey) -> Self {
+        Self {
+            id: &key.id,
+            role: key.role,
+            created_at: key.created_at,
+            created_by: &key.created_by,
+        }
+    }
+}
+
+/// Re-reads the configured admin, JWT and result signing keys from their sources and
+/// records who asked. Keys issued at runtime are untouched.
+pub fn reload_secrets(secrets: &SecretStore, audit_log: &AuditLog, actor: &str) -> Result<()> {
+    let reloaded = secrets.reload();
+    audit_log.record_or_log(actor, AdminAction::ConfigReload, "secrets", reloaded.is_ok());
+    reloaded
+}
+
+/// `GET /admin/keys`, `POST /admin/keys` and `DELETE /admin/keys/{id}`: runtime API keys,
+/// admin role only. A POST answers with the key itself, which is not retrievable afterwards.
+/// `POST /admin/secrets/reload` picks up rotated configured keys, as SIGHUP does.
+pub fn create_api_key_routes(
+    keys: Arc<ApiKeyStore>,
+    audit_log: Arc<AuditLog>,
+    secrets: Arc<SecretStore>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let list = warp::path!("admin" / "keys")
+        .and(warp::get())
+        .and(with_role(secrets.clone(), Role::Admin))
+        .and(with_keys(keys.clone()))
+        .map(|_actor: String, keys: Arc<ApiKeyStore>| {
+            let issued = keys.list();
+            warp::reply::json(&issued.iter().map(ApiKeySummary::from).collect::<Vec<_>>())
+        });
+
+    let issue = warp::path!("admin" / "keys")
+        .and(warp::post())
+        .and(with_role(secrets.clone(), Role::Admin))
+        .and(warp::body::json::<IssueApiKeyRequest>())
+        .and(with_keys(keys.clone()))
+        .and(with_audit_log(audit_log.clone()))
+        .map(|actor: String, request: IssueApiKeyRequest, keys: Arc<ApiKeyStore>, audit_log: Arc<AuditLog>| {
+            let target = request.id.clone().unwrap_or_default();
+            match keys.issue(request.id, request.role, &actor) {
+                Ok(Some((key, secret))) => {
+                    audit_log.record_or_log(&actor, AdminAction::IssueApiKey, &format!("{} ({:?})", key.id, key.role), true);
+                    let mut body = serde_json::to_value(ApiKeySummary::from(&key)).unwrap_or_default();
+                    body["key"] = serde_json::Value::String(secret);
+                    warp::reply::with_status(warp::reply::json(&body), StatusCode::CREATED)
+                }
+                Ok(None) => {
+                    audit_log.record_or_log(&actor, AdminAction::IssueApiKey, &target, false);
+                    warp::reply::with_status(
+                        warp::reply::json(&serde_json::json!({ "error": "A key with this id already exists" })),
+                        StatusCode::CONFLICT,
+                    )
+                }
+                Err(e) => {
+                    audit_log.record_or_log(&actor, AdminAction::IssueApiKey, &target, false);
+                    store_error(&target, e)
+                }
+            }
+        });
+
+    let revoke = warp::path!("admin" / "keys" / String)
+        .and(warp::delete())
+        .and(with_role(secrets.clone(), Role::Admin))
+        .and(with_keys(keys))
+        .and(with_audit_log(audit_log.clone()))
+        .map(|id: String, actor: String, keys: Arc<ApiKeyStore>, audit_log: Arc<AuditLog>| {
+            let revoked = keys.revoke(&id);
+            audit_log.record_or_log(&actor, AdminAction::RevokeApiKey, &id, matches!(revoked, Ok(true)));
+            match revoked {
+                Ok(true) => warp::reply::with_status(warp::reply::json(&serde_json::json!({ "revoked": id })), StatusCode::OK),
+                Ok(false) => warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({ "error": "No such issued key" })),
+                    StatusCode::NOT_FOUND,
+                ),
+                Err(e) => store_error(&id, e),
+            }
+        });
+
+    let reload = warp::path!("admin" / "secrets" / "reload")
+        .and(warp::post())
+        .and(with_role(secrets.clone(), Role::Admin))
+        .and(with_audit_log(audit_log))
+        .map(move |actor: String, audit_log: Arc<AuditLog>| match reload_secrets(&secrets, &audit_log, &actor) {
+            Ok(()) => warp::reply::with_status(warp::reply::json(&serde_json::json!({ "reloaded": true })), StatusCode::OK),
+            Err(e) => {
+                error!("Failed to reload secrets: {:#}", e);
+                warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({ "error": "Failed to reload secrets" })),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                )
+            }
+        });
+
+    list.or(issue).or(revoke).or(reload)
+}
+
+fn with_keys(keys: Arc<ApiKeyStore>) -> impl Filter<Extract = (Arc<ApiKeyStore>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || keys.clone())
+}
+
+fn store_error(id: &str, e: anyhow::Error) -> warp::reply::WithStatus<warp::reply::Json> {
+    error!("Failed to store API key {}: {}", id, e);
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": "Failed to store API keys" })),
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{SecretSource, SecretsConfig};
+    use crate::infrastructure::AuditQuery;
+
+    #[tokio::test]
+    async fn test_reload_rotates_configured_admin_keys() {
+        let dir = std::env::temp_dir().join(format!("rps-secrets-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let keys_file = dir.join("admin_keys");
+        std::fs::write(&keys_file, "ops:old-key\n").unwrap();
+        let secrets = Arc::new(
+            SecretStore::load(SecretsConfig {
+                admin_api_keys: SecretSource {
+                    env: None,
+                    file: Some(keys_file.to_string_lossy().into_owned()),
+                },
+                jwt_signing_keys: SecretSource::default(),
+                result_signing_keys: SecretSource::default(),
+                api_key_store_path: None,
+                resume_keys: SecretSource::default(),
+            })
+            .unwrap(),
+        );
+        let audit_log = Arc::new(AuditLog::in_memory());
+        let routes = create_api_key_routes(Arc::new(ApiKeyStore::in_memory(secrets.clone())), audit_log.clone(), secrets.clone());
+
+        // Rotating the file changes nothing until a reload
+        std::fs::write(&keys_file, "ops:new-key\n").unwrap();
+        assert!(secrets.verify_admin_key("new-key").is_none());
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/admin/secrets/reload")
+            .header("x-api-key", "old-key")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(secrets.verify_admin_key("old-key").is_none());
+        assert_eq!(secrets.verify_admin_key("new-key").as_deref(), Some("ops"));
+
+        let entries = audit_log.query(&AuditQuery {
+            action: Some(AdminAction::ConfigReload),
+            ..Default::default()
+        });
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].actor, "ops");
+        assert!(entries[0].success);
+
+        // A source that can no longer be read keeps the keys already loaded
+        std::fs::remove_file(&keys_file).unwrap();
+        assert!(reload_secrets(&secrets, &audit_log, "SIGHUP").is_err());
+        assert_eq!(secrets.verify_admin_key("new-key").as_deref(), Some("ops"));
+        assert_eq!(audit_log.len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::error;
+
+use super::jsonl_store::JsonlStore;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AdminAction {
+    Kick,
+    CloseRoom,
+    Drain,
+    ConfigReload,
+    Notify,
+    CreateMatch,
+    CreateSeries,
+    GrantTitle,
+    EquipTitle,
+    SaveRoomTemplate,
+    DeleteRoomTemplate,
+    DeletePlayerData,
+    UpdateWordList,
+    Chaos,
+    IssueApiKey,
+    RevokeApiKey,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub actor: String,
+    pub action: AdminAction,
+    pub target: String,
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub action: Option<AdminAction>,
+    pub target: Option<String>,
+    pub limit: Option<usize>,
+}
+
+// Append-only audit trail of admin actions, mirrored to a JSON-lines file
+pub struct AuditLog {
+    entries: RwLock<Vec<AuditEntry>>,
+    log: JsonlStore<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn in_memory() -> Self {
+        Self {
+            entries: RwLock::new(Vec::new()),
+            log: JsonlStore::in_memory(),
+        }
+    }
+
+    /// Opens (or creates) the log file and loads previously recorded entries.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut entries = Vec::new();
+        let log = JsonlStore::open(path, "audit entry", |entry| entries.push(entry))?;
+        Ok(Self {
+            entries: RwLock::new(entries),
+            log,
+        })
+    }
+
+    pub fn record(&self, actor: &str, action: AdminAction, target: &str, success: bool) -> Result<AuditEntry> {
+        let entry = AuditEntry {
+            timestamp: Utc::now(),
+            actor: actor.to_string(),
+            action,
+            target: target.to_string(),
+            success,
+        };
+
+        self.log.persist(&entry)?;
+        self.entries.write().push(entry.clone());
+        Ok(entry)
+    }
+
+    /// Records an action that has already been carried out. A failed write is logged
+    /// rather than returned, since the action stands either way.
+    pub fn record_or_log(&self, actor: &str, action: AdminAction, target: &str, success: bool) {
+        if let Err(e) = self.record(actor, action, target, success) {
+            error!("Failed to write audit entry: {}", e);
+        }
+    }
+
+    /// Returns matching entries, newest first.
+    pub fn query(&self, query: &AuditQuery) -> Vec<AuditEntry> {
+        let entries = self.entries.read();
+        entries
+            .iter()
+            .rev()
+            .filter(|e| query.actor.as_ref().is_none_or(|a| &e.actor == a))
+            .filter(|e| query.action.is_none_or(|a| e.action == a))
+            .filter(|e| query.target.as_ref().is_none_or(|t| &e.target == t))
+            .take(query.limit.unwrap_or(100))
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.read().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::{AdminAction, AuditLog, AuditQuery};
+
+    #[test]
+    fn test_audit_log_persists_across_reopen() {
+        let path = std::env::temp_dir().join(format!("rps-audit-{}.jsonl", uuid::Uuid::new_v4()));
+
+        let log = AuditLog::open(&path).unwrap();
+        log.record("ops", AdminAction::Kick, "player-1", true).unwrap();
+        log.record("ops", AdminAction::CloseRoom, "room-1", false).unwrap();
+        drop(log);
+
+        let reopened = AuditLog::open(&path).unwrap();
+        assert_eq!(reopened.len(), 2);
+        let kicks = reopened.query(&AuditQuery {
+            action: Some(AdminAction::Kick),
+            ..AuditQuery::default()
+        });
+        assert_eq!(kicks.len(), 1);
+        assert_eq!(kicks[0].target, "player-1");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
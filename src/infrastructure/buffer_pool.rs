@@ -0,0 +1,160 @@
+use bytes::{BufMut, BytesMut};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::config::BufferPoolConfig;
+
+static MAX_PER_WORKER: AtomicUsize = AtomicUsize::new(64);
+static MAX_BUFFER_BYTES: AtomicUsize = AtomicUsize::new(64 * 1024);
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+static RETURNED: AtomicU64 = AtomicU64::new(0);
+static DISCARDED: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static POOL: RefCell<Vec<BytesMut>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferPoolStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f64,
+    pub returned: u64,
+    pub discarded: u64, // Over the size cap, or the worker's pool was full
+}
+
+/// Frame buffers reused per runtime worker, so high message rates don't churn the
+/// allocator. A buffer goes back to the pool of whichever worker drops it.
+pub struct BufferPool;
+
+impl BufferPool {
+    pub fn configure(config: &BufferPoolConfig) {
+        MAX_PER_WORKER.store(config.max_per_worker, Ordering::Relaxed);
+        MAX_BUFFER_BYTES.store(config.max_buffer_bytes, Ordering::Relaxed);
+    }
+
+    /// An empty buffer with room for at least `capacity` bytes.
+    pub fn take(capacity: usize) -> PooledBuffer {
+        let pooled = POOL.with(|pool| pool.borrow_mut().pop());
+        let buffer = match pooled {
+            Some(mut buffer) => {
+                HITS.fetch_add(1, Ordering::Relaxed);
+                buffer.reserve(capacity);
+                buffer
+            }
+            None => {
+                MISSES.fetch_add(1, Ordering::Relaxed);
+                BytesMut::with_capacity(capacity.max(256))
+            }
+        };
+        PooledBuffer(Some(buffer))
+    }
+
+    pub fn stats() -> BufferPoolStats {
+        let hits = HITS.load(Ordering::Relaxed);
+        let misses = MISSES.load(Ordering::Relaxed);
+        BufferPoolStats {
+            hits,
+            misses,
+            hit_rate: if hits + misses > 0 { hits as f64 / (hits + misses) as f64 } else { 0.0 },
+            returned: RETURNED.load(Ordering::Relaxed),
+            discarded: DISCARDED.load(Ordering::Relaxed),
+        }
+    }
+
+    fn put(mut buffer: BytesMut) {
+        if buffer.capacity() > MAX_BUFFER_BYTES.load(Ordering::Relaxed) {
+            DISCARDED.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        buffer.clear();
+        let kept = POOL.with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < MAX_PER_WORKER.load(Ordering::Relaxed) {
+                pool.push(buffer);
+                true
+            } else {
+                false
+            }
+        });
+        if kept {
+            RETURNED.fetch_add(1, Ordering::Relaxed);
+        } else {
+            DISCARDED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Serializes `value` as JSON through a pooled buffer. The returned string is the one
+    /// allocation, sized exactly, instead of serde_json growing its own as it writes.
+    pub fn to_json<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+        let mut buffer = Self::take(256);
+        serde_json::to_writer((&mut *buffer).writer(), value)?;
+        // serde_json only writes UTF-8
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+/// A pooled buffer; returned to the current worker's pool on drop.
+pub struct PooledBuffer(Option<BytesMut>);
+
+impl Deref for PooledBuffer {
+    type Target = BytesMut;
+
+    fn deref(&self) -> &BytesMut {
+        self.0.as_ref().expect("buffer present until drop")
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut BytesMut {
+        self.0.as_mut().expect("buffer present until drop")
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.0.take() {
+            BufferPool::put(buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::ServerMessage;
+    use crate::infrastructure::BufferPool;
+
+    #[test]
+    fn test_buffer_pool_reuses_buffers_on_the_same_worker() {
+        // Runs on its own thread, so the thread-local pool starts empty
+        std::thread::spawn(|| {
+            let before = BufferPool::stats();
+            let json = BufferPool::to_json(&ServerMessage::ServerBusy { retry_after_ms: 5000 }).unwrap();
+            assert_eq!(json, serde_json::to_string(&ServerMessage::ServerBusy { retry_after_ms: 5000 }).unwrap());
+            for _ in 0..3 {
+                let mut buffer = BufferPool::take(64);
+                assert!(buffer.is_empty());
+                buffer.extend_from_slice(b"frame");
+            }
+
+            // The stats are process-wide; other tests may add to them, never take away
+            let after = BufferPool::stats();
+            assert!(after.misses > before.misses);
+            assert!(after.hits >= before.hits + 3);
+            assert!(after.returned >= before.returned + 4);
+            assert!(after.hit_rate > 0.0);
+
+            let discarded = BufferPool::stats().discarded;
+            let oversized = BufferPool::take(1024 * 1024);
+            assert!(oversized.capacity() >= 1024 * 1024);
+            drop(oversized);
+            assert!(BufferPool::stats().discarded > discarded);
+        })
+        .join()
+        .unwrap();
+    }
+}
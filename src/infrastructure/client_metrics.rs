@@ -0,0 +1,156 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Per-client-version connection stats, for spotting broken client releases.
+pub static CLIENT_METRICS: Lazy<ClientMetrics> = Lazy::new(ClientMetrics::new);
+
+pub const UNKNOWN_CLIENT_VERSION: &str = "unknown";
+
+// Client-supplied strings, so both their length and how many we track are capped
+const MAX_VERSION_LEN: usize = 64;
+const MAX_TRACKED_VERSIONS: usize = 200;
+const OTHER_CLIENT_VERSIONS: &str = "other";
+
+#[derive(Debug, Default, Clone)]
+struct VersionCounters {
+    connections: u64,
+    active: u64,
+    messages: u64,
+    errors: u64,
+    latency_total_us: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ClientVersionStats {
+    pub version: String,
+    pub connections: u64,
+    pub active_connections: u64,
+    pub messages: u64,
+    pub errors: u64,
+    pub error_rate: f64,     // Errors per handled message
+    pub avg_latency_ms: f64, // Mean time to handle one client message
+}
+
+pub struct ClientMetrics {
+    versions: Mutex<HashMap<String, VersionCounters>>,
+}
+
+impl ClientMetrics {
+    pub fn new() -> Self {
+        Self {
+            versions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Normalized bucket name for a declared version or user agent.
+    pub fn bucket(declared: Option<&str>) -> String {
+        match declared.map(str::trim).filter(|v| !v.is_empty()) {
+            Some(version) => version.chars().take(MAX_VERSION_LEN).collect(),
+            None => UNKNOWN_CLIENT_VERSION.to_string(),
+        }
+    }
+
+    pub fn connected(&self, version: &str) {
+        self.with_counters(version, |c| {
+            c.connections += 1;
+            c.active += 1;
+        });
+    }
+
+    pub fn disconnected(&self, version: &str) {
+        self.with_counters(version, |c| c.active = c.active.saturating_sub(1));
+    }
+
+    /// Moves a live connection to another bucket, e.g. once `Connect` declares a version.
+    pub fn reattribute(&self, from: &str, to: &str) {
+        if from == to {
+            return;
+        }
+        self.with_counters(from, |c| {
+            c.connections = c.connections.saturating_sub(1);
+            c.active = c.active.saturating_sub(1);
+        });
+        self.connected(to);
+    }
+
+    pub fn message(&self, version: &str, latency: Duration, error: bool) {
+        self.with_counters(version, |c| {
+            c.messages += 1;
+            c.latency_total_us += latency.as_micros() as u64;
+            if error {
+                c.errors += 1;
+            }
+        });
+    }
+
+    /// Stats per version, most connections first.
+    pub fn snapshot(&self) -> Vec<ClientVersionStats> {
+        let versions = self.versions.lock();
+        let mut stats: Vec<_> = versions
+            .iter()
+            .map(|(version, c)| ClientVersionStats {
+                version: version.clone(),
+                connections: c.connections,
+                active_connections: c.active,
+                messages: c.messages,
+                errors: c.errors,
+                error_rate: if c.messages > 0 { c.errors as f64 / c.messages as f64 } else { 0.0 },
+                avg_latency_ms: if c.messages > 0 {
+                    c.latency_total_us as f64 / c.messages as f64 / 1000.0
+                } else {
+                    0.0
+                },
+            })
+            .collect();
+        stats.sort_by(|a, b| b.connections.cmp(&a.connections).then_with(|| a.version.cmp(&b.version)));
+        stats
+    }
+
+    fn with_counters(&self, version: &str, update: impl FnOnce(&mut VersionCounters)) {
+        let mut versions = self.versions.lock();
+        let key = if versions.contains_key(version) || versions.len() < MAX_TRACKED_VERSIONS {
+            version
+        } else {
+            OTHER_CLIENT_VERSIONS
+        };
+        update(versions.entry(key.to_string()).or_default());
+    }
+}
+
+impl Default for ClientMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::ClientMetrics;
+
+    #[test]
+    fn test_client_metrics_aggregate_per_version() {
+        let metrics = ClientMetrics::new();
+        let header = ClientMetrics::bucket(Some("Mozilla/5.0"));
+        metrics.connected(&header);
+        metrics.connected(&ClientMetrics::bucket(Some("  ")));
+
+        let declared = ClientMetrics::bucket(Some("ios/2.3.1"));
+        metrics.reattribute(&header, &declared);
+        metrics.message(&declared, std::time::Duration::from_millis(4), false);
+        metrics.message(&declared, std::time::Duration::from_millis(2), true);
+        metrics.disconnected(&declared);
+
+        let stats = metrics.snapshot();
+        assert_eq!(stats.len(), 3);
+        let ios = stats.iter().find(|s| s.version == "ios/2.3.1").unwrap();
+        assert_eq!((ios.connections, ios.active_connections, ios.messages, ios.errors), (1, 0, 2, 1));
+        assert_eq!(ios.error_rate, 0.5);
+        assert_eq!(ios.avg_latency_ms, 3.0);
+        let browser = stats.iter().find(|s| s.version == "Mozilla/5.0").unwrap();
+        assert_eq!((browser.connections, browser.active_connections), (0, 0));
+        assert!(stats.iter().any(|s| s.version == "unknown" && s.active_connections == 1));
+    }
+}
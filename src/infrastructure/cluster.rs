@@ -0,0 +1,1033 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use futures_util::{SinkExt, StreamExt};
+use hmac::{Hmac, Mac};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::{timeout, Instant};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+use warp::Filter;
+
+use super::admin_api::{with_role, with_audit_log};
+use super::audit_log::{AdminAction, AuditLog};
+use super::i18n::MessageKey;
+use super::protocol_state::ConnectionSession;
+use super::websocket::WebSocketHandler;
+use crate::application::{GameManager, RoomState};
+use crate::config::{ClusterConfig, Role, Secret, SecretStore};
+use crate::domain::{ClientMessage, GameMode, GameType, Player, ServerMessage};
+
+/// Path peers dial on the regular WebSocket listeners, presenting `CLUSTER_TOKEN_HEADER`.
+pub const CLUSTER_PATH: &str = "/cluster";
+pub const CLUSTER_TOKEN_HEADER: &str = "x-cluster-token";
+
+const REDIAL_BACKOFF_MIN: Duration = Duration::from_millis(500);
+const REDIAL_BACKOFF_MAX: Duration = Duration::from_secs(10);
+
+type PeerSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// What nodes tell each other over a peer link, one JSON text frame each.
+///
+/// A player's *home* node holds their connection; their *host* node holds their queue
+/// entry and game. `Host`, `Client`, `Release` and `Rejoin` go home to host, `Deliver`
+/// comes back.
+///
+/// A draining node moves a room with `Handover`; once the new host answers with
+/// `HandoverAck`, each player's home learns of the move through `Rehost` and rejoins
+/// there, and players connected to the draining node are told to reconnect.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum PeerMessage {
+    Hello {
+        node_id: String,
+        #[serde(default)]
+        url: Option<String>, // The sender's advertised client URL
+    },
+    Heartbeat { solo_waiting: usize, teams_waiting: usize, players: usize },
+    Presence { players: Vec<String> }, // Everyone connected to the sender; first thing on a new link
+    PlayerOnline { player_id: String },
+    PlayerOffline { player_id: String },
+    Host { player_id: String, mode: GameMode, locale: String, request_id: String },
+    Client { player_id: String, request_id: String, message: ClientMessage },
+    Release { player_id: String }, // The player's connection closed
+    Rejoin { player_id: String, locale: String, request_id: String },
+    Deliver { player_id: String, message: ServerMessage },
+    Handover { room: Box<RoomState> },
+    HandoverAck { room_id: String, accepted: bool },
+    Rehost { player_id: String, node_id: String }, // The player's game moved to `node_id`
+}
+
+#[derive(Default)]
+struct Peer {
+    url: Option<String>,                              // Where its clients connect, from its hello
+    link: Option<mpsc::UnboundedSender<PeerMessage>>, // Our dialed connection to it
+    last_seen: Option<Instant>,                       // Last frame on its dialed connection to us
+    solo_waiting: usize,
+    teams_waiting: usize,
+    players: usize,
+}
+
+impl Peer {
+    fn alive(&self, peer_timeout: Duration) -> bool {
+        self.link.is_some() && self.last_seen.is_some_and(|seen| seen.elapsed() < peer_timeout)
+    }
+
+    fn waiting(&self, mode: GameMode) -> usize {
+        match mode {
+            GameMode::Solo => self.solo_waiting,
+            GameMode::Teams => self.teams_waiting,
+            GameMode::Bot | GameMode::Blitz => 0,
+        }
+    }
+}
+
+/// A player whose connection is on this node.
+struct LocalPlayer {
+    tx: mpsc::UnboundedSender<ServerMessage>,
+    request_id: String,
+    locale: String,
+    host: Option<String>,         // Peer holding their queue entry or game; None plays here
+    detached_at: Option<Instant>, // Disconnected, with `host` kept for `rejoin_window_ms`
+}
+
+/// A peer's player whose queue entry or game lives on this node.
+struct HostedPlayer {
+    session: ConnectionSession,
+    locale: RwLock<String>,
+    tx: mpsc::UnboundedSender<ServerMessage>, // Delivers to the home node
+}
+
+/// A room sent to `target` and not yet acknowledged; restored here if refused.
+struct PendingHandover {
+    target: String,
+    state: RoomState,
+    players: Vec<Arc<Player>>,
+}
+
+/// What a reconnect token vouches for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconnectClaims {
+    pub player_id: String,
+    pub node_id: String, // Node holding the player's game state when the token was issued
+    pub expires_at_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerSnapshot {
+    pub node_id: String,
+    pub alive: bool,
+    pub solo_waiting: usize,
+    pub teams_waiting: usize,
+    pub players: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterSnapshot {
+    pub node_id: String,
+    pub peers: Vec<PeerSnapshot>,
+    pub local_players: usize,
+    pub remote_players: usize,  // Connected to peers, from their presence updates
+    pub hosted_remotely: usize, // Local players whose game is on a peer
+    pub hosted_here: usize,     // Peers' players whose game is here
+    pub handoffs: u64,
+    pub forwarded: u64,
+    pub delivered: u64,
+    pub rooms_handed_over: u64, // Live rooms moved to a peer by draining
+    pub rooms_taken_over: u64,  // Live rooms a draining peer moved here
+}
+
+/// Membership and routing for a cluster of server instances with a static peer list.
+///
+/// Each node dials every peer and sends only over links it dialed; what a peer says
+/// arrives over the link the peer dialed in. Nodes share which players they hold and
+/// how many are waiting per queue. A FindMatch with nobody waiting locally goes to a peer
+/// that has someone waiting, which then hosts the player through a proxy `Player` whose
+/// messages are delivered back here.
+pub struct Cluster {
+    node_id: String,
+    config: ClusterConfig,
+    keys: Vec<Secret>, // The first is presented when dialing; any is accepted
+    peers: RwLock<HashMap<String, Peer>>,
+    local: RwLock<HashMap<String, LocalPlayer>>,
+    directory: RwLock<HashMap<String, String>>, // Player connected to a peer -> that peer
+    hosted: RwLock<HashMap<String, String>>,    // Player hosted here -> their home node
+    handovers: RwLock<HashMap<String, PendingHandover>>, // Room id -> handover awaiting its ack
+    handoffs: AtomicU64,
+    forwarded: AtomicU64,
+    delivered: AtomicU64,
+    rooms_handed_over: AtomicU64,
+    rooms_taken_over: AtomicU64,
+}
+
+impl Cluster {
+    /// None unless `config.peers` lists someone.
+    pub fn new(config: &ClusterConfig) -> Result<Option<Self>> {
+        if config.peers.is_empty() {
+            return Ok(None);
+        }
+        let keys = config.secret.load()?;
+        if keys.is_empty() {
+            bail!("cluster.peers is set but no cluster secret is configured");
+        }
+
+        Ok(Some(Self {
+            node_id: config.node_id.clone().unwrap_or_else(|| Uuid::new_v4().to_string()),
+            config: config.clone(),
+            keys,
+            peers: RwLock::new(HashMap::new()),
+            local: RwLock::new(HashMap::new()),
+            directory: RwLock::new(HashMap::new()),
+            hosted: RwLock::new(HashMap::new()),
+            handovers: RwLock::new(HashMap::new()),
+            handoffs: AtomicU64::new(0),
+            forwarded: AtomicU64::new(0),
+            delivered: AtomicU64::new(0),
+            rooms_handed_over: AtomicU64::new(0),
+            rooms_taken_over: AtomicU64::new(0),
+        }))
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    pub fn authorize(&self, token: Option<&str>) -> bool {
+        token.is_some_and(|token| self.keys.iter().any(|key| key.matches(token)))
+    }
+
+    /// Dials every peer and starts heartbeats; `handler` serves the players this node hosts.
+    pub fn spawn(self: Arc<Self>, handler: WebSocketHandler) {
+        for url in &self.config.peers {
+            tokio::spawn(self.clone().dial(url.clone(), handler.clone()));
+        }
+        tokio::spawn(self.heartbeat(handler));
+    }
+
+    /// A player finished Connect here. A game still hosted on a peer is rejoined: the one
+    /// this node remembers, else the one `resume_on` names from a reconnect token. False
+    /// when that node can't be reached, so the game is lost. Either way the player gets a
+    /// fresh token.
+    pub fn connected(
+        &self,
+        player_id: &str,
+        request_id: &str,
+        locale: &str,
+        tx: &mpsc::UnboundedSender<ServerMessage>,
+        resume_on: Option<&str>,
+    ) -> bool {
+        let known = self.local.read().get(player_id).and_then(|player| player.host.clone());
+        let wanted = known.or_else(|| resume_on.filter(|node| *node != self.node_id).map(str::to_string));
+        let reachable = wanted.as_ref().is_none_or(|node| self.alive(node));
+        let host = wanted.filter(|_| reachable);
+
+        let player = LocalPlayer {
+            tx: tx.clone(),
+            request_id: request_id.to_string(),
+            locale: locale.to_string(),
+            host: host.clone(),
+            detached_at: None,
+        };
+        self.local.write().insert(player_id.to_string(), player);
+
+        self.broadcast(PeerMessage::PlayerOnline { player_id: player_id.to_string() });
+        if let Some(host) = &host {
+            info!("Player {} rejoining their game on node {}", player_id, host);
+            let rejoin = PeerMessage::Rejoin {
+                player_id: player_id.to_string(),
+                locale: locale.to_string(),
+                request_id: request_id.to_string(),
+            };
+            self.send(host, rejoin);
+        }
+        let _ = tx.send(self.reconnect_token(player_id, host.as_deref().unwrap_or(&self.node_id)));
+        reachable
+    }
+
+    /// The player reconnected straight to the node hosting their game for a peer. True if
+    /// they were hosted here, so the proxy for their old home must be dropped first; the
+    /// old home's later `Release` is then ignored.
+    pub fn adopt(&self, player_id: &str) -> bool {
+        self.hosted.write().remove(player_id).is_some()
+    }
+
+    /// A signed `{nodeId}.{claims}.{mac}` token naming the node that holds the player's
+    /// game, valid for `rejoin_window_ms`. The node id comes first so a load balancer can
+    /// route on it without checking the signature.
+    pub fn reconnect_token(&self, player_id: &str, node_id: &str) -> ServerMessage {
+        let claims = ReconnectClaims {
+            player_id: player_id.to_string(),
+            node_id: node_id.to_string(),
+            expires_at_ms: Utc::now().timestamp_millis() + self.config.rejoin_window_ms as i64,
+        };
+        let encoded = serde_json::to_vec(&claims).expect("claims serialize");
+        let payload = format!("{}.{}", node_id, hex::encode(encoded));
+        let mac = token_mac(&self.keys[0], &payload).finalize().into_bytes();
+
+        ServerMessage::ReconnectToken {
+            token: format!("{}.{}", payload, hex::encode(mac)),
+            node_id: claims.node_id,
+            node_url: self.node_url(node_id),
+            expires_at_ms: claims.expires_at_ms,
+        }
+    }
+
+    /// The claims of an unexpired token signed with any cluster key.
+    pub fn verify_token(&self, token: &str) -> Option<ReconnectClaims> {
+        let (payload, mac) = token.rsplit_once('.')?;
+        let mac = hex::decode(mac).ok()?;
+        if !self.keys.iter().any(|key| token_mac(key, payload).verify_slice(&mac).is_ok()) {
+            return None;
+        }
+        let (node_id, claims) = payload.rsplit_once('.')?;
+        let claims: ReconnectClaims = serde_json::from_slice(&hex::decode(claims).ok()?).ok()?;
+        (claims.node_id == node_id && claims.expires_at_ms > Utc::now().timestamp_millis()).then_some(claims)
+    }
+
+    /// The connection behind `tx` closed; ignored if a newer one has taken over the id.
+    pub fn disconnected(&self, player_id: &str, tx: &mpsc::UnboundedSender<ServerMessage>) {
+        let host = {
+            let mut local = self.local.write();
+            let Some(player) = local.get_mut(player_id) else { return };
+            if !player.tx.same_channel(tx) {
+                return;
+            }
+            match player.host.clone() {
+                Some(host) => {
+                    player.detached_at = Some(Instant::now());
+                    Some(host)
+                }
+                None => {
+                    local.remove(player_id);
+                    None
+                }
+            }
+        };
+
+        self.broadcast(PeerMessage::PlayerOffline { player_id: player_id.to_string() });
+        if let Some(host) = host {
+            self.send(&host, PeerMessage::Release { player_id: player_id.to_string() });
+        }
+    }
+
+    /// Sends the message on to the node hosting this player; false if it's handled here.
+    /// Connect and notification acks always stay with the connection.
+    pub fn forward(&self, player_id: &str, request_id: &str, message: &ClientMessage) -> bool {
+        if matches!(message, ClientMessage::Connect { .. } | ClientMessage::AckNotifications { .. }) {
+            return false;
+        }
+        let Some(host) = self.local.read().get(player_id).and_then(|player| player.host.clone()) else {
+            return false;
+        };
+
+        let client = PeerMessage::Client {
+            player_id: player_id.to_string(),
+            request_id: request_id.to_string(),
+            message: message.clone(),
+        };
+        if !self.send(&host, client) {
+            return false;
+        }
+        self.forwarded.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Queues the player on a peer with players waiting for `mode` when nobody waits
+    /// here, so lone players on different nodes still meet. The peer answers through
+    /// the player's delivered messages.
+    pub fn host_remotely(&self, player_id: &str, mode: GameMode, waiting_here: usize) -> bool {
+        if matches!(mode, GameMode::Bot | GameMode::Blitz) || waiting_here > 0 || self.hosted.read().contains_key(player_id) {
+            return false;
+        }
+        match self.peer_with_waiting(mode, |_| true) {
+            Some(node) => self.hand_off(player_id, &node, mode),
+            None => false,
+        }
+    }
+
+    /// Which node the player is connected to, as far as this node knows.
+    pub fn locate(&self, player_id: &str) -> Option<String> {
+        let here = self.local.read().get(player_id).is_some_and(|player| player.detached_at.is_none());
+        if here {
+            return Some(self.node_id.clone());
+        }
+        self.directory.read().get(player_id).cloned()
+    }
+
+    pub fn snapshot(&self) -> ClusterSnapshot {
+        let peer_timeout = self.peer_timeout();
+        let mut peers: Vec<PeerSnapshot> = self
+            .peers
+            .read()
+            .iter()
+            .map(|(node_id, peer)| PeerSnapshot {
+                node_id: node_id.clone(),
+                alive: peer.alive(peer_timeout),
+                solo_waiting: peer.solo_waiting,
+                teams_waiting: peer.teams_waiting,
+                players: peer.players,
+            })
+            .collect();
+        peers.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+        let local = self.local.read();
+
+        ClusterSnapshot {
+            node_id: self.node_id.clone(),
+            peers,
+            local_players: local.values().filter(|player| player.detached_at.is_none()).count(),
+            remote_players: self.directory.read().len(),
+            hosted_remotely: local.values().filter(|player| player.host.is_some()).count(),
+            hosted_here: self.hosted.read().len(),
+            handoffs: self.handoffs.load(Ordering::Relaxed),
+            forwarded: self.forwarded.load(Ordering::Relaxed),
+            delivered: self.delivered.load(Ordering::Relaxed),
+            rooms_handed_over: self.rooms_handed_over.load(Ordering::Relaxed),
+            rooms_taken_over: self.rooms_taken_over.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Hands every live room to the live peer with the fewest players, to take this node
+    /// out of service. Returns the ids of the rooms sent; a room the peer refuses comes
+    /// back here. Queued players and new games are left alone.
+    pub async fn drain(&self, manager: &GameManager) -> Vec<String> {
+        let peer_timeout = self.peer_timeout();
+        let target = self
+            .peers
+            .read()
+            .iter()
+            .filter(|(_, peer)| peer.alive(peer_timeout))
+            .min_by(|(a_id, a), (b_id, b)| a.players.cmp(&b.players).then_with(|| a_id.cmp(b_id)))
+            .map(|(node_id, _)| node_id.clone());
+        let Some(target) = target else {
+            warn!("🕸️  Nowhere to drain rooms to: no live peers");
+            return Vec::new();
+        };
+
+        let mut sent = Vec::new();
+        for room_id in manager.live_room_ids().await {
+            let Some((state, players)) = manager.take_room(&room_id).await else { continue };
+            let handover = PeerMessage::Handover { room: Box::new(state.clone()) };
+            self.handovers.write().insert(room_id.clone(), PendingHandover { target: target.clone(), state, players });
+            if self.send(&target, handover) {
+                sent.push(room_id);
+                continue;
+            }
+            let pending = self.handovers.write().remove(&room_id);
+            if let Some(pending) = pending {
+                Self::restore_here(pending, manager).await;
+            }
+        }
+        info!("🕸️  Draining: {} rooms sent to node {}", sent.len(), target);
+        sent
+    }
+
+    fn peer_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.peer_timeout_ms)
+    }
+
+    fn alive(&self, node_id: &str) -> bool {
+        let peer_timeout = self.peer_timeout();
+        self.peers.read().get(node_id).is_some_and(|peer| peer.alive(peer_timeout))
+    }
+
+    fn node_url(&self, node_id: &str) -> Option<String> {
+        if node_id == self.node_id {
+            return self.config.advertise_url.clone();
+        }
+        self.peers.read().get(node_id).and_then(|peer| peer.url.clone())
+    }
+
+    /// The live peer with the most players waiting for `mode`, among those `eligible` accepts.
+    fn peer_with_waiting(&self, mode: GameMode, eligible: impl Fn(&str) -> bool) -> Option<String> {
+        let peer_timeout = self.peer_timeout();
+        self.peers
+            .read()
+            .iter()
+            .filter(|(node_id, peer)| eligible(node_id) && peer.alive(peer_timeout) && peer.waiting(mode) > 0)
+            .max_by(|(a_id, a), (b_id, b)| a.waiting(mode).cmp(&b.waiting(mode)).then_with(|| b_id.cmp(a_id)))
+            .map(|(node_id, _)| node_id.clone())
+    }
+
+    fn hand_off(&self, player_id: &str, node: &str, mode: GameMode) -> bool {
+        let (host, tx) = {
+            let mut local = self.local.write();
+            let Some(player) = local.get_mut(player_id) else { return false };
+            player.host = Some(node.to_string());
+            let host = PeerMessage::Host {
+                player_id: player_id.to_string(),
+                mode,
+                locale: player.locale.clone(),
+                request_id: player.request_id.clone(),
+            };
+            (host, player.tx.clone())
+        };
+        if !self.send(node, host) {
+            if let Some(player) = self.local.write().get_mut(player_id) {
+                player.host = None;
+            }
+            return false;
+        }
+
+        self.handoffs.fetch_add(1, Ordering::Relaxed);
+        info!("🕸️  Player {} handed to node {} for a {:?} match", player_id, node, mode);
+        let _ = tx.send(self.reconnect_token(player_id, node));
+        true
+    }
+
+    fn send(&self, node: &str, message: PeerMessage) -> bool {
+        self.peers
+            .read()
+            .get(node)
+            .and_then(|peer| peer.link.as_ref())
+            .is_some_and(|link| link.send(message).is_ok())
+    }
+
+    fn broadcast(&self, message: PeerMessage) {
+        for link in self.peers.read().values().filter_map(|peer| peer.link.as_ref()) {
+            let _ = link.send(message.clone());
+        }
+    }
+
+    async fn heartbeat(self: Arc<Self>, handler: WebSocketHandler) {
+        let mut interval = tokio::time::interval(Duration::from_millis(self.config.heartbeat_interval_ms.max(1)));
+        let rejoin_window = Duration::from_millis(self.config.rejoin_window_ms);
+        loop {
+            interval.tick().await;
+            let manager = handler.game_manager();
+            let players = self.local.read().values().filter(|player| player.detached_at.is_none()).count();
+            self.broadcast(PeerMessage::Heartbeat {
+                solo_waiting: manager.waiting_players(GameMode::Solo),
+                teams_waiting: manager.waiting_players(GameMode::Teams),
+                players,
+            });
+            self.rebalance(manager).await;
+            self.local
+                .write()
+                .retain(|_, player| player.detached_at.is_none_or(|detached| detached.elapsed() < rejoin_window));
+        }
+    }
+
+    /// Players who queued on two nodes before either heard about the other would wait
+    /// forever. The node with the higher id hands its waiting players to a lower one that
+    /// has players waiting too, so queues drain toward one node and never bounce back.
+    async fn rebalance(&self, manager: &GameManager) {
+        for mode in [GameMode::Solo, GameMode::Teams] {
+            if manager.waiting_players(mode) == 0 {
+                continue;
+            }
+            let Some(node) = self.peer_with_waiting(mode, |node_id| node_id < self.node_id.as_str()) else {
+                continue;
+            };
+
+            // Only players connected here; peers' players stay where their home sent them
+            let movable = |player: &Player| {
+                self.local
+                    .read()
+                    .get(&player.id)
+                    .is_some_and(|local| local.host.is_none() && local.tx.same_channel(&player.sender))
+            };
+            while let Some(player) = manager.take_waiting(mode, movable).await {
+                if !self.hand_off(&player.id, &node, mode) {
+                    // The link dropped in between; back in line here
+                    if let Err(e) = manager.find_match_in_mode(player, mode).await {
+                        error!("Failed to requeue player after a failed handoff: {}", e);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn dial(self: Arc<Self>, url: String, handler: WebSocketHandler) {
+        let mut backoff = REDIAL_BACKOFF_MIN;
+        loop {
+            match self.open_link(&url).await {
+                Ok((node_id, socket)) => {
+                    backoff = REDIAL_BACKOFF_MIN;
+                    info!("🕸️  Linked to cluster peer {} at {}", node_id, url);
+                    self.run_link(&node_id, socket).await;
+                    warn!("🕸️  Link to cluster peer {} closed", node_id);
+                    self.link_lost(&node_id, &handler);
+                    self.handovers_lost(&node_id, handler.game_manager()).await;
+                }
+                Err(e) => debug!("Cluster peer {} unreachable: {}", url, e),
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(REDIAL_BACKOFF_MAX);
+        }
+    }
+
+    async fn open_link(&self, url: &str) -> Result<(String, PeerSocket)> {
+        let mut request = format!("{}{}", url.trim_end_matches('/'), CLUSTER_PATH).into_client_request()?;
+        request
+            .headers_mut()
+            .insert(CLUSTER_TOKEN_HEADER, HeaderValue::from_str(self.keys[0].expose())?);
+        let (mut socket, _) = timeout(self.peer_timeout(), tokio_tungstenite::connect_async(request)).await??;
+
+        socket.send(Message::Text(serde_json::to_string(&self.hello())?)).await?;
+        let (node_id, peer_url) = match timeout(self.peer_timeout(), socket.next()).await? {
+            Some(Ok(Message::Text(text))) => match serde_json::from_str(&text)? {
+                PeerMessage::Hello { node_id, url } => (node_id, url),
+                other => bail!("Expected hello, got {:?}", other),
+            },
+            other => bail!("Expected hello, got {:?}", other),
+        };
+        if node_id == self.node_id {
+            bail!("{} is this node", url);
+        }
+        self.peers.write().entry(node_id.clone()).or_default().url = peer_url;
+        Ok((node_id, socket))
+    }
+
+    fn hello(&self) -> PeerMessage {
+        PeerMessage::Hello {
+            node_id: self.node_id.clone(),
+            url: self.config.advertise_url.clone(),
+        }
+    }
+
+    /// Sends everything queued for the peer until either side closes the link.
+    async fn run_link(&self, node_id: &str, socket: PeerSocket) {
+        let (link, mut outgoing) = mpsc::unbounded_channel();
+        // The peer's directory starts complete instead of filling in one change at a time
+        let players = self
+            .local
+            .read()
+            .iter()
+            .filter(|(_, player)| player.detached_at.is_none())
+            .map(|(player_id, _)| player_id.clone())
+            .collect();
+        let _ = link.send(PeerMessage::Presence { players });
+        self.peers.write().entry(node_id.to_string()).or_default().link = Some(link);
+
+        let (mut sink, mut stream) = socket.split();
+        loop {
+            tokio::select! {
+                message = outgoing.recv() => {
+                    let Some(message) = message else { break };
+                    let text = match serde_json::to_string(&message) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            error!("Failed to encode message for cluster peer {}: {}", node_id, e);
+                            continue;
+                        }
+                    };
+                    if sink.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                frame = stream.next() => match frame {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    }
+
+    /// Our players hosted on that node lost their game with the link.
+    fn link_lost(&self, node_id: &str, handler: &WebSocketHandler) {
+        if let Some(peer) = self.peers.write().get_mut(node_id) {
+            peer.link = None;
+        }
+        let mut orphaned = Vec::new();
+        self.local.write().retain(|player_id, player| {
+            if player.host.as_deref() != Some(node_id) {
+                return true;
+            }
+            player.host = None;
+            if player.detached_at.is_some() {
+                return false;
+            }
+            let error = handler.error(&player.locale, MessageKey::HostUnavailable);
+            let _ = player.tx.send(error.with_request_id(&player.request_id));
+            orphaned.push((player_id.clone(), player.tx.clone()));
+            true
+        });
+        for (player_id, tx) in orphaned {
+            let _ = tx.send(self.reconnect_token(&player_id, &self.node_id));
+        }
+    }
+
+    /// Serves a link a peer dialed in, after the upgrade was authorized: hellos, then
+    /// everything the peer says until it closes or goes silent.
+    pub(crate) async fn serve_peer<S>(self: Arc<Self>, socket: WebSocketStream<S>, handler: &WebSocketHandler) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let peer_timeout = self.peer_timeout();
+        let (mut sink, mut stream) = socket.split();
+        let node_id = match timeout(peer_timeout, stream.next()).await {
+            Ok(Some(Ok(Message::Text(text)))) => match serde_json::from_str(&text) {
+                Ok(PeerMessage::Hello { node_id, url }) => {
+                    self.peers.write().entry(node_id.clone()).or_default().url = url;
+                    node_id
+                }
+                _ => bail!("Cluster peer didn't start with hello"),
+            },
+            _ => bail!("Cluster peer didn't start with hello"),
+        };
+        sink.send(Message::Text(serde_json::to_string(&self.hello())?)).await?;
+        info!("🕸️  Cluster peer {} linked in", node_id);
+
+        let mut hosted = HashMap::new();
+        loop {
+            let frame = match timeout(peer_timeout, stream.next()).await {
+                Ok(Some(Ok(frame))) => frame,
+                Ok(_) => break,
+                Err(_) => {
+                    warn!("🕸️  Cluster peer {} went silent", node_id);
+                    break;
+                }
+            };
+            match frame {
+                Message::Text(text) => match serde_json::from_str(&text) {
+                    Ok(message) => self.receive(&node_id, message, &mut hosted, handler).await,
+                    Err(e) => warn!("Bad message from cluster peer {}: {}", node_id, e),
+                },
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+
+        self.peer_gone(&node_id, hosted, handler).await;
+        Ok(())
+    }
+
+    async fn receive(
+        self: &Arc<Self>,
+        node_id: &str,
+        message: PeerMessage,
+        hosted: &mut HashMap<String, HostedPlayer>,
+        handler: &WebSocketHandler,
+    ) {
+        self.peers.write().entry(node_id.to_string()).or_default().last_seen = Some(Instant::now());
+
+        match message {
+            PeerMessage::Hello { .. } => {}
+            PeerMessage::Heartbeat { solo_waiting, teams_waiting, players } => {
+                if let Some(peer) = self.peers.write().get_mut(node_id) {
+                    peer.solo_waiting = solo_waiting;
+                    peer.teams_waiting = teams_waiting;
+                    peer.players = players;
+                }
+            }
+            PeerMessage::Presence { players } => {
+                let mut directory = self.directory.write();
+                directory.retain(|_, node| node != node_id);
+                directory.extend(players.into_iter().map(|player_id| (player_id, node_id.to_string())));
+            }
+            PeerMessage::PlayerOnline { player_id } => {
+                self.directory.write().insert(player_id, node_id.to_string());
+            }
+            PeerMessage::PlayerOffline { player_id } => {
+                let mut directory = self.directory.write();
+                if directory.get(&player_id).is_some_and(|node| node == node_id) {
+                    directory.remove(&player_id);
+                }
+            }
+            PeerMessage::Host { player_id, mode, locale, request_id } => {
+                let player = self.host_player(node_id, &player_id, locale);
+                self.hosted.write().insert(player_id.clone(), node_id.to_string());
+                let player = hosted.entry(player_id).insert_entry(player).into_mut();
+                player.handle(handler, &request_id, ClientMessage::FindMatch { mode, game: GameType::default(), template: None }).await;
+            }
+            PeerMessage::Client { player_id, request_id, message } => match hosted.get_mut(&player_id) {
+                Some(player) => player.handle(handler, &request_id, message).await,
+                None => debug!("Message from node {} for {}, who isn't hosted here", node_id, player_id),
+            },
+            PeerMessage::Release { player_id } => {
+                if hosted.remove(&player_id).is_some() && self.unhost(node_id, &player_id) {
+                    if let Err(e) = handler.game_manager().disconnect_player(&player_id).await {
+                        error!("Failed to release hosted player {}: {}", player_id, e);
+                    }
+                }
+            }
+            PeerMessage::Rejoin { player_id, .. } if self.is_connected_here(&player_id) => {
+                // Reconnected straight here after their game moved in
+                debug!("Ignoring rejoin from node {} for {}, who is connected here", node_id, player_id);
+            }
+            PeerMessage::Rejoin { player_id, locale, request_id } => {
+                let player = self.host_player(node_id, &player_id, locale);
+                let proxy = Arc::new(Player::new(player_id.clone(), player.tx.clone()));
+                let previous = self.hosted.write().insert(player_id.clone(), node_id.to_string());
+                hosted.insert(player_id.clone(), player);
+                let manager = handler.game_manager();
+                if previous.is_some_and(|home| home != node_id) {
+                    // Came back through another node before their old home released them
+                    if let Err(e) = manager.disconnect_player(&player_id).await {
+                        error!("Failed to drop the previous proxy for {}: {}", player_id, e);
+                    }
+                }
+                match manager.reconnect_player(proxy).await {
+                    Ok(Some(room_id)) => info!("Player {} from node {} resumed room {}", player_id, node_id, room_id),
+                    Ok(None) => debug!("Player {} from node {} had nothing to resume ({})", player_id, node_id, request_id),
+                    Err(e) => error!("Failed to resume hosted player {}: {}", player_id, e),
+                }
+            }
+            PeerMessage::Deliver { player_id, message } => {
+                let local = self.local.read();
+                if let Some(player) = local.get(&player_id).filter(|player| player.detached_at.is_none()) {
+                    if player.tx.send(message).is_ok() {
+                        self.delivered.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
+            PeerMessage::Handover { room } => {
+                let room_id = room.id.clone();
+                let accepted = self.take_over(node_id, *room, handler.game_manager()).await;
+                self.send(node_id, PeerMessage::HandoverAck { room_id, accepted });
+            }
+            PeerMessage::HandoverAck { room_id, accepted } => {
+                let pending = self.handovers.write().remove(&room_id);
+                match pending {
+                    Some(pending) if accepted => self.handed_over(pending),
+                    Some(pending) => {
+                        warn!("🕸️  Node {} refused room {}; keeping it", node_id, room_id);
+                        Self::restore_here(pending, handler.game_manager()).await;
+                    }
+                    None => debug!("Handover ack from node {} for unknown room {}", node_id, room_id),
+                }
+            }
+            PeerMessage::Rehost { player_id, node_id: new_host } => self.rehost(node_id, &player_id, &new_host),
+        }
+    }
+
+    fn is_connected_here(&self, player_id: &str) -> bool {
+        self.local.read().get(player_id).is_some_and(|player| player.detached_at.is_none() && player.host.is_none())
+    }
+
+    /// Opens a room a draining peer sent. Players connected here whose game that peer
+    /// hosted are seated at once; the rest rejoin through their homes.
+    async fn take_over(&self, from: &str, state: RoomState, manager: &GameManager) -> bool {
+        let room_id = state.id.clone();
+        let connections: HashMap<String, Arc<Player>> = {
+            let local = self.local.read();
+            state
+                .players
+                .iter()
+                .filter_map(|id| {
+                    let player = local.get(id).filter(|player| player.detached_at.is_none() && player.host.as_deref() == Some(from))?;
+                    Some((id.clone(), Arc::new(Player::new(id.clone(), player.tx.clone()))))
+                })
+                .collect()
+        };
+        if let Err(e) = manager.restore_room(state, &connections).await {
+            warn!("🕸️  Can't take over room {} from node {}: {}", room_id, from, e);
+            return false;
+        }
+
+        for player_id in connections.keys() {
+            let tx = {
+                let mut local = self.local.write();
+                let Some(player) = local.get_mut(player_id) else { continue };
+                player.host = None;
+                player.tx.clone()
+            };
+            let _ = tx.send(self.reconnect_token(player_id, &self.node_id));
+        }
+        self.rooms_taken_over.fetch_add(1, Ordering::Relaxed);
+        info!("🕸️  Took over room {} from node {}", room_id, from);
+        true
+    }
+
+    /// The peer opened the room: point every player's home at it. Players connected here
+    /// are proxied there until they reconnect to it directly.
+    fn handed_over(&self, pending: PendingHandover) {
+        let PendingHandover { target, state, .. } = pending;
+        let bot_id = state.bot.as_ref().map(|bot| bot.id.as_str());
+        for player_id in state.players.iter().filter(|id| Some(id.as_str()) != bot_id) {
+            if let Some(home) = self.hosted.write().remove(player_id) {
+                if home != target {
+                    self.send(&home, PeerMessage::Rehost { player_id: player_id.clone(), node_id: target.clone() });
+                }
+                continue;
+            }
+
+            let rejoin = {
+                let mut local = self.local.write();
+                let Some(player) = local.get_mut(player_id) else { continue };
+                player.host = Some(target.clone());
+                let rejoin = PeerMessage::Rejoin {
+                    player_id: player_id.clone(),
+                    locale: player.locale.clone(),
+                    request_id: player.request_id.clone(),
+                };
+                player.detached_at.is_none().then(|| (rejoin, player.tx.clone()))
+            };
+            if let Some((rejoin, tx)) = rejoin {
+                self.send(&target, rejoin);
+                let _ = tx.send(self.reconnect_token(player_id, &target));
+                let _ = tx.send(ServerMessage::RoomMigrated {
+                    room_id: state.id.clone(),
+                    node_id: target.clone(),
+                    node_url: self.node_url(&target),
+                });
+            }
+        }
+        self.rooms_handed_over.fetch_add(1, Ordering::Relaxed);
+        info!("🕸️  Room {} handed over to node {}", state.id, target);
+    }
+
+    /// A peer moved the player's game from `from` to `new_host`.
+    fn rehost(&self, from: &str, player_id: &str, new_host: &str) {
+        let rejoin = {
+            let mut local = self.local.write();
+            let Some(player) = local.get_mut(player_id).filter(|player| player.host.as_deref() == Some(from)) else {
+                return;
+            };
+            player.host = Some(new_host.to_string());
+            let rejoin = PeerMessage::Rejoin {
+                player_id: player_id.to_string(),
+                locale: player.locale.clone(),
+                request_id: player.request_id.clone(),
+            };
+            // A detached player rejoins when they reconnect
+            player.detached_at.is_none().then(|| (rejoin, player.tx.clone()))
+        };
+        if let Some((rejoin, tx)) = rejoin {
+            self.send(new_host, rejoin);
+            let _ = tx.send(self.reconnect_token(player_id, new_host));
+        }
+    }
+
+    async fn restore_here(pending: PendingHandover, manager: &GameManager) {
+        let room_id = pending.state.id.clone();
+        let connections = pending.players.into_iter().map(|player| (player.id.clone(), player)).collect();
+        if let Err(e) = manager.restore_room(pending.state, &connections).await {
+            error!("Failed to restore room {} after a failed handover: {}", room_id, e);
+        }
+    }
+
+    /// Rooms sent to a peer whose link dropped before it answered come back here.
+    async fn handovers_lost(&self, node_id: &str, manager: &GameManager) {
+        let lost: Vec<PendingHandover> = {
+            let mut handovers = self.handovers.write();
+            let room_ids: Vec<String> = handovers.iter().filter(|(_, pending)| pending.target == node_id).map(|(id, _)| id.clone()).collect();
+            room_ids.iter().filter_map(|room_id| handovers.remove(room_id)).collect()
+        };
+        for pending in lost {
+            Self::restore_here(pending, manager).await;
+        }
+    }
+
+    /// A proxy session whose messages are delivered to the player's home node.
+    fn host_player(self: &Arc<Self>, home: &str, player_id: &str, locale: String) -> HostedPlayer {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let cluster = self.clone();
+        let home = home.to_string();
+        let id = player_id.to_string();
+        tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                cluster.send(&home, PeerMessage::Deliver { player_id: id.clone(), message });
+            }
+        });
+
+        HostedPlayer {
+            session: ConnectionSession {
+                player_id: Some(player_id.to_string()),
+                ..ConnectionSession::default()
+            },
+            locale: RwLock::new(locale),
+            tx,
+        }
+    }
+
+    /// Stops hosting the player for `home`; false if they've since moved to another home,
+    /// or reconnected here directly.
+    fn unhost(&self, home: &str, player_id: &str) -> bool {
+        let mut hosted = self.hosted.write();
+        if hosted.get(player_id).is_some_and(|node| node == home) {
+            hosted.remove(player_id);
+            return true;
+        }
+        false
+    }
+
+    /// The peer's players lost their connections along with the link.
+    async fn peer_gone(&self, node_id: &str, hosted: HashMap<String, HostedPlayer>, handler: &WebSocketHandler) {
+        if let Some(peer) = self.peers.write().get_mut(node_id) {
+            peer.last_seen = None;
+        }
+        self.directory.write().retain(|_, node| node != node_id);
+        for player_id in hosted.keys().filter(|player_id| self.unhost(node_id, player_id)) {
+            if let Err(e) = handler.game_manager().disconnect_player(player_id).await {
+                error!("Failed to disconnect hosted player {}: {}", player_id, e);
+            }
+        }
+        warn!("🕸️  Cluster peer {} gone; {} hosted players disconnected", node_id, hosted.len());
+    }
+}
+
+fn token_mac(key: &Secret, payload: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.expose().as_bytes()).expect("HMAC accepts any key length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+impl HostedPlayer {
+    async fn handle(&mut self, handler: &WebSocketHandler, request_id: &str, message: ClientMessage) {
+        if let Err(e) = handler
+            .handle_message(message, request_id, &mut self.session, &self.locale, &self.tx)
+            .await
+        {
+            error!("Error handling message for hosted player: {}", e);
+            let error = handler.error(&self.locale.read(), MessageKey::InternalError);
+            let _ = self.tx.send(error.with_request_id(request_id));
+        }
+    }
+}
+
+/// `GET /cluster`: this node's view of the cluster, null when running standalone.
+/// `GET /cluster/players/{id}`: which node a player is connected to.
+/// `POST /admin/cluster/drain`: hands this node's live rooms to a peer; admin keys only.
+pub fn create_cluster_routes(
+    cluster: Option<Arc<Cluster>>,
+    game_manager: Arc<GameManager>,
+    audit_log: Arc<AuditLog>,
+    secrets: Arc<SecretStore>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let drain_cluster = cluster.clone();
+    let drain = warp::path!("admin" / "cluster" / "drain")
+        .and(warp::post())
+        .and(with_role(secrets, Role::Admin))
+        .and(with_audit_log(audit_log))
+        .and_then(move |actor: String, audit_log: Arc<AuditLog>| {
+            let cluster = drain_cluster.clone();
+            let game_manager = game_manager.clone();
+            async move {
+                let Some(cluster) = cluster else {
+                    return Err(warp::reject::not_found());
+                };
+                let rooms = cluster.drain(&game_manager).await;
+                audit_log.record_or_log(&actor, AdminAction::Drain, cluster.node_id(), true);
+                Ok(warp::reply::json(&serde_json::json!({ "nodeId": cluster.node_id(), "rooms": rooms })))
+            }
+        });
+
+    let snapshot_cluster = cluster.clone();
+    let snapshot = warp::path!("cluster").and(warp::get()).map(move || {
+        warp::reply::json(&snapshot_cluster.as_ref().map(|cluster| cluster.snapshot()))
+    });
+
+    let locate = warp::path!("cluster" / "players" / String).and(warp::get()).map(move |player_id: String| {
+        let node_id = cluster.as_ref().and_then(|cluster| cluster.locate(&player_id));
+        warp::reply::json(&serde_json::json!({ "playerId": player_id, "nodeId": node_id }))
+    });
+
+    snapshot.or(locate).or(drain)
+}
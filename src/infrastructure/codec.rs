@@ -0,0 +1,260 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::buffer_pool::BufferPool;
+use crate::config::JsonParser;
+use crate::domain::ClientMessage;
+
+/// Frames simd-json couldn't decode that serde_json then parsed (or rejected).
+pub static CODEC_FALLBACKS: AtomicU64 = AtomicU64::new(0);
+
+/// Frames refused by `preflight`, by why.
+pub static PREFLIGHT_REJECTS: PreflightRejects = PreflightRejects::new();
+
+/// Why a client frame was refused before being parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameRejected {
+    TooLarge { size: usize, max: usize },
+    InvalidUtf8,
+    NotAnObject, // Not a JSON object at the top level
+    MissingType, // An object without a string `type` of its own
+    UnknownType, // A `type` no client message has
+}
+
+pub struct PreflightRejects {
+    too_large: AtomicU64,
+    invalid_utf8: AtomicU64,
+    not_an_object: AtomicU64,
+    missing_type: AtomicU64,
+    unknown_type: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct PreflightRejectStats {
+    pub too_large: u64,
+    pub invalid_utf8: u64,
+    pub not_an_object: u64,
+    pub missing_type: u64,
+    pub unknown_type: u64,
+}
+
+impl PreflightRejects {
+    const fn new() -> Self {
+        Self {
+            too_large: AtomicU64::new(0),
+            invalid_utf8: AtomicU64::new(0),
+            not_an_object: AtomicU64::new(0),
+            missing_type: AtomicU64::new(0),
+            unknown_type: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record(&self, rejected: FrameRejected) {
+        let counter = match rejected {
+            FrameRejected::TooLarge { .. } => &self.too_large,
+            FrameRejected::InvalidUtf8 => &self.invalid_utf8,
+            FrameRejected::NotAnObject => &self.not_an_object,
+            FrameRejected::MissingType => &self.missing_type,
+            FrameRejected::UnknownType => &self.unknown_type,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> PreflightRejectStats {
+        PreflightRejectStats {
+            too_large: self.too_large.load(Ordering::Relaxed),
+            invalid_utf8: self.invalid_utf8.load(Ordering::Relaxed),
+            not_an_object: self.not_an_object.load(Ordering::Relaxed),
+            missing_type: self.missing_type.load(Ordering::Relaxed),
+            unknown_type: self.unknown_type.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Cheap checks on a client frame before it is parsed: its size, UTF-8, and that it is a
+/// JSON object whose `type` is one a client may send. Scans only as far as that `type`,
+/// without allocating, so junk traffic costs little; the full parse still does the rest.
+pub fn preflight(frame: &[u8], max_size: usize) -> Result<&str, FrameRejected> {
+    if frame.len() > max_size {
+        return Err(FrameRejected::TooLarge { size: frame.len(), max: max_size });
+    }
+    let text = std::str::from_utf8(frame).map_err(|_| FrameRejected::InvalidUtf8)?;
+    match top_level_type(text.as_bytes())? {
+        Some(tag) if ClientMessage::TYPES.contains(&tag) => Ok(text),
+        Some(_) => Err(FrameRejected::UnknownType),
+        None => Err(FrameRejected::MissingType),
+    }
+}
+
+// The value of the object's own `type` key, skipping over nested values and strings
+fn top_level_type(bytes: &[u8]) -> Result<Option<&str>, FrameRejected> {
+    let mut i = bytes.iter().position(|b| !b.is_ascii_whitespace()).ok_or(FrameRejected::NotAnObject)?;
+    if bytes[i] != b'{' {
+        return Err(FrameRejected::NotAnObject);
+    }
+    let mut depth = 0usize;
+    let mut previous = 0u8; // Last structural byte, telling keys from values
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let (start, end) = string_at(bytes, i)?;
+                i = end;
+                if depth == 1 && matches!(previous, b'{' | b',') {
+                    let rest = skip_whitespace(bytes, i + 1);
+                    if &bytes[start..end] == b"type" && bytes.get(rest) == Some(&b':') {
+                        let value = skip_whitespace(bytes, rest + 1);
+                        if bytes.get(value) != Some(&b'"') {
+                            return Ok(None);
+                        }
+                        let (start, end) = string_at(bytes, value)?;
+                        return Ok(std::str::from_utf8(&bytes[start..end]).ok());
+                    }
+                }
+                previous = b'"';
+            }
+            open @ (b'{' | b'[') => {
+                depth += 1;
+                previous = open;
+            }
+            b'}' | b']' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return Ok(None);
+                }
+                previous = bytes[i];
+            }
+            b',' | b':' => previous = bytes[i],
+            _ => {}
+        }
+        i += 1;
+    }
+    Err(FrameRejected::NotAnObject)
+}
+
+// The contents of the string opening at `open`, as (start, index of the closing quote)
+fn string_at(bytes: &[u8], open: usize) -> Result<(usize, usize), FrameRejected> {
+    let mut i = open + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Ok((open + 1, i)),
+            _ => i += 1,
+        }
+    }
+    Err(FrameRejected::NotAnObject)
+}
+
+fn skip_whitespace(bytes: &[u8], from: usize) -> usize {
+    from + bytes[from.min(bytes.len())..].iter().take_while(|b| b.is_ascii_whitespace()).count()
+}
+
+/// Parses client messages for one connection with the configured parser. simd-json
+/// rewrites its input, so each frame is copied into a pooled scratch buffer; the parser's
+/// own working buffers are kept for the connection's lifetime. Frames it can't handle
+/// are retried with serde_json, which also gives the clearer error for malformed input.
+pub struct MessageCodec {
+    parser: JsonParser,
+    buffers: simd_json::Buffers,
+}
+
+impl MessageCodec {
+    pub fn new(parser: JsonParser) -> Self {
+        Self {
+            parser,
+            buffers: simd_json::Buffers::new(0),
+        }
+    }
+
+    pub fn parser(&self) -> JsonParser {
+        self.parser
+    }
+
+    pub fn decode(&mut self, text: &str) -> Result<ClientMessage> {
+        if self.parser == JsonParser::SimdJson {
+            let mut scratch = BufferPool::take(text.len());
+            scratch.extend_from_slice(text.as_bytes());
+            if let Ok(message) = simd_json::serde::from_slice_with_buffers(&mut scratch, &mut self.buffers) {
+                return Ok(message);
+            }
+            CODEC_FALLBACKS.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(serde_json::from_str(text)?)
+    }
+}
+
+impl Default for MessageCodec {
+    fn default() -> Self {
+        Self::new(JsonParser::default())
+    }
+}
+
+impl fmt::Debug for MessageCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageCodec").field("parser", &self.parser).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::JsonParser;
+    use crate::infrastructure::MessageCodec;
+
+    #[test]
+    fn test_preflight_sheds_malformed_frames_before_parsing() {
+        use crate::infrastructure::{preflight, FrameRejected};
+
+        for frame in [
+            r#"{"type":"connect","playerId":"p1"}"#,
+            r#" { "playerId" : "p1", "type" : "connect" } "#,
+            r#"{"meta":{"type":"teleport"},"text":"\"type\":\"x\"","type":"chat"}"#,
+        ] {
+            assert_eq!(preflight(frame.as_bytes(), 1024), Ok(frame), "{}", frame);
+        }
+        let rejected = [
+            (&b"{\"type\":\"chat\",\"text\":\"\xff\"}"[..], FrameRejected::InvalidUtf8),
+            (b"[\"connect\"]", FrameRejected::NotAnObject),
+            (b"   ", FrameRejected::NotAnObject),
+            (b"{\"type\":\"conn", FrameRejected::NotAnObject),
+            (b"{\"playerId\":\"p1\"}", FrameRejected::MissingType),
+            (b"{\"data\":{\"type\":\"connect\"}}", FrameRejected::MissingType),
+            (b"{\"type\":7}", FrameRejected::MissingType),
+            (b"{\"type\":\"teleport\"}", FrameRejected::UnknownType),
+        ];
+        for (frame, expected) in rejected {
+            assert_eq!(preflight(frame, 1024), Err(expected), "{}", String::from_utf8_lossy(frame));
+        }
+        assert_eq!(
+            preflight(&[b' '; 2048], 1024),
+            Err(FrameRejected::TooLarge { size: 2048, max: 1024 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_simd_json_codec_matches_serde_json_and_falls_back() {
+        let frames = [
+            r#"{"type":"connect","playerId":"p1","locale":"pt-BR","protocolVersion":2}"#,
+            r#"{"type":"findMatch"}"#,
+            r#"{"type":"playerMove","choice":"lizard"}"#,
+            r#"{"type":"backfillResponse","accept":true}"#,
+            r#"{"type":"ackNotifications","ids":["a","b"]}"#,
+        ];
+        let mut serde = MessageCodec::new(JsonParser::SerdeJson);
+        let mut simd = MessageCodec::new(JsonParser::SimdJson);
+        for frame in frames {
+            assert_eq!(
+                serde_json::to_value(simd.decode(frame).unwrap()).unwrap(),
+                serde_json::to_value(serde.decode(frame).unwrap()).unwrap(),
+                "{}",
+                frame
+            );
+        }
+
+        // Both reject what serde_json rejects, with serde_json's error
+        let simd_error = simd.decode(r#"{"type":"playerMove","choice":"spock""#).unwrap_err().to_string();
+        let serde_error = serde.decode(r#"{"type":"playerMove","choice":"spock""#).unwrap_err().to_string();
+        assert_eq!(simd_error, serde_error);
+        assert!(simd.decode(r#"{"type":"teleport"}"#).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use include_dir::{include_dir, Dir};
+use warp::http::{header, StatusCode, Uri};
+use warp::Filter;
+
+// Bundled into the binary so the demo works from any working directory
+static DEMO_CLIENT: Dir = include_dir!("$CARGO_MANIFEST_DIR/demo");
+
+/// `GET /demo/`: a bundled browser client for manual play and QA. Not found when disabled.
+pub fn create_demo_routes(
+    enabled: bool,
+    websocket_port: u16,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let enabled = warp::any()
+        .and_then(move || async move { if enabled { Ok(()) } else { Err(warp::reject::not_found()) } })
+        .untuple_one();
+
+    // Relative asset paths only resolve under the trailing slash
+    let index_redirect = warp::path!("demo")
+        .and(warp::path::full())
+        .and_then(|full: warp::path::FullPath| async move {
+            match full.as_str() {
+                "/demo" => Ok(warp::redirect::permanent(Uri::from_static("/demo/"))),
+                _ => Err(warp::reject::not_found()),
+            }
+        });
+
+    let config = warp::path!("demo" / "config.json")
+        .map(move || warp::reply::json(&serde_json::json!({ "websocketPort": websocket_port })));
+
+    let files = warp::path("demo")
+        .and(warp::path::tail())
+        .and_then(|tail: warp::path::Tail| async move { demo_file(tail.as_str()).ok_or_else(warp::reject::not_found) });
+
+    enabled.and(warp::get()).and(index_redirect.or(config).or(files))
+}
+
+fn demo_file(path: &str) -> Option<warp::reply::Response> {
+    let path = if path.is_empty() { "index.html" } else { path };
+    let file = DEMO_CLIENT.get_file(path)?;
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+
+    let mut response = warp::reply::Response::new(file.contents().into());
+    *response.status_mut() = StatusCode::OK;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, mime.as_ref().parse().ok()?);
+    Some(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::create_demo_routes;
+
+    #[tokio::test]
+    async fn test_demo_client_routes() {
+        let routes = create_demo_routes(true, 9090);
+
+        let index = warp::test::request().path("/demo/").reply(&routes).await;
+        assert_eq!(index.status(), 200);
+        assert!(index.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+        assert!(String::from_utf8_lossy(index.body()).contains("app.js"));
+
+        let script = warp::test::request().path("/demo/app.js").reply(&routes).await;
+        assert!(script.headers()["content-type"].to_str().unwrap().contains("javascript"));
+
+        let config = warp::test::request().path("/demo/config.json").reply(&routes).await;
+        let config: serde_json::Value = serde_json::from_slice(config.body()).unwrap();
+        assert_eq!(config["websocketPort"], 9090);
+
+        assert_eq!(warp::test::request().path("/demo").reply(&routes).await.status(), 308);
+        assert_eq!(warp::test::request().path("/demo/missing.js").reply(&routes).await.status(), 404);
+
+        let disabled = create_demo_routes(false, 9090);
+        assert_eq!(warp::test::request().path("/demo/").reply(&disabled).await.status(), 404);
+    }
+}
@@ -0,0 +1,140 @@
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::config::{FdLimitPolicy, WebSocketConfig};
+
+/// Descriptors kept back from WebSocket clients for listening sockets, REST clients,
+/// log and audit files, outbound HTTP, and the runtime itself.
+pub const RESERVED_FDS: usize = 256;
+
+/// The process's open file limit (`RLIMIT_NOFILE`), which caps connections long
+/// before memory does on a default ulimit of 1024.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FdLimits {
+    pub soft: u64,
+    pub hard: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct FdUsage {
+    pub soft_limit: Option<u64>,
+    pub hard_limit: Option<u64>,
+    pub open: Option<usize>, // Descriptors open right now, from /proc on Linux
+}
+
+impl FdLimits {
+    pub fn detect() -> Option<Self> {
+        let mut limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+        // SAFETY: getrlimit only writes to the struct it is given
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+            return None;
+        }
+        #[allow(clippy::unnecessary_cast)] // rlim_t is only u64 on some platforms
+        Some(Self {
+            soft: limit.rlim_cur as u64,
+            hard: limit.rlim_max as u64,
+        })
+    }
+
+    /// Raises the soft limit as far as the hard limit allows; unprivileged processes may.
+    pub fn raise_soft_to_hard(self) -> Self {
+        if self.soft >= self.hard {
+            return self;
+        }
+        let limit = libc::rlimit {
+            rlim_cur: self.hard as libc::rlim_t,
+            rlim_max: self.hard as libc::rlim_t,
+        };
+        // SAFETY: setrlimit only reads the struct it is given
+        if unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &limit) } != 0 {
+            return self;
+        }
+        Self::detect().unwrap_or(self)
+    }
+
+    /// WebSocket clients the soft limit leaves room for, after `RESERVED_FDS`.
+    pub fn connection_budget(&self) -> usize {
+        usize::try_from(self.soft).unwrap_or(usize::MAX).saturating_sub(RESERVED_FDS)
+    }
+
+    /// Checks `config`'s connection caps against the limit at startup. With
+    /// `FdLimitPolicy::Adjust` the soft limit is raised first, and caps that still
+    /// don't fit are lowered to the budget; with `Warn` they are only logged.
+    pub fn apply(config: &mut WebSocketConfig) -> Option<Self> {
+        let Some(mut limits) = Self::detect() else {
+            warn!("📂 Could not read the open file limit; connection caps are unchecked");
+            return None;
+        };
+        let wanted = config
+            .effective_listeners()
+            .iter()
+            .map(|listener| listener.max_connections.unwrap_or(config.max_connections))
+            .fold(0usize, usize::saturating_add);
+        if config.fd_limit == FdLimitPolicy::Adjust && wanted > limits.connection_budget() {
+            limits = limits.raise_soft_to_hard();
+        }
+        info!("📂 Open File Limit: {} (hard {})", limits.soft, limits.hard);
+
+        let budget = limits.connection_budget();
+        if wanted <= budget {
+            return Some(limits);
+        }
+        match config.fd_limit {
+            FdLimitPolicy::Warn => warn!(
+                "📂 Listeners allow {} connections but the open file limit of {} fits about {}; raise `ulimit -n`",
+                wanted, limits.soft, budget
+            ),
+            FdLimitPolicy::Adjust => {
+                config.max_connections = config.max_connections.min(budget);
+                for listener in &mut config.listeners {
+                    listener.max_connections = listener.max_connections.map(|max| max.min(budget));
+                }
+                warn!(
+                    "📂 Listeners allowed {} connections but the open file limit of {} fits about {}; capped at {} per listener. Raise `ulimit -n` for more",
+                    wanted, limits.soft, budget, budget
+                );
+            }
+        }
+        Some(limits)
+    }
+}
+
+impl FdUsage {
+    pub fn sample() -> Self {
+        let limits = FdLimits::detect();
+        Self {
+            soft_limit: limits.map(|limits| limits.soft),
+            hard_limit: limits.map(|limits| limits.hard),
+            open: std::fs::read_dir("/proc/self/fd").ok().map(|entries| entries.count()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{FdLimitPolicy, ServerConfig};
+    use crate::infrastructure::{FdLimits, FdUsage, RESERVED_FDS};
+
+    #[test]
+    fn test_connection_caps_are_fitted_to_the_open_file_limit() {
+        let limits = FdLimits { soft: 1024, hard: 4096 };
+        assert_eq!(limits.connection_budget(), 1024 - RESERVED_FDS);
+        assert_eq!(FdLimits { soft: 100, hard: 100 }.connection_budget(), 0);
+
+        // Far beyond any real limit: Warn keeps the caps, Adjust lowers them to the budget
+        let mut config = ServerConfig::default().websocket;
+        config.max_connections = usize::MAX / 2;
+        config.fd_limit = FdLimitPolicy::Warn;
+        FdLimits::apply(&mut config).unwrap();
+        assert_eq!(config.max_connections, usize::MAX / 2);
+
+        config.fd_limit = FdLimitPolicy::Adjust;
+        let limits = FdLimits::apply(&mut config).unwrap();
+        assert_eq!(config.max_connections, limits.connection_budget());
+        assert!(limits.soft <= limits.hard);
+
+        let usage = FdUsage::sample();
+        assert_eq!(usage.soft_limit, Some(limits.soft));
+        assert!(usage.open.unwrap() > 0);
+    }
+}
@@ -0,0 +1,332 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::config::I18nConfig;
+use crate::domain::ServerMessage;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Human-readable strings the server sends. `code()` is the stable,
+/// machine-readable identifier; only the rendered text varies by locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKey {
+    ConnectTimeout,
+    InternalError,
+    MessageTooLarge,
+    NotConnected,
+    FindMatchFailed,
+    RoomNotFound,
+    JoinRoomFailed,
+    InvalidMove,
+    InvalidRules,
+    SubmitMoveFailed,
+    CannotPause,
+    NotPaused,
+    PauseRequestFailed,
+    SlowConnection,
+    UnsupportedFrame,
+    AlreadyConnected,
+    AlreadyQueued,
+    AlreadyInGame,
+    NotInGame,
+    Muted,
+    NoBackfillOffer,
+    HostUnavailable,
+    InvalidDisplayName,
+    InvalidChatMessage,
+    ChatDisabled,
+    InappropriateLanguage,
+    MalformedMessage,
+    UnknownMessageType,
+    QuotaExceeded,
+    QuitCooldown,
+    InviteInvalid,
+    MoveAlreadySubmitted,
+    GamePaused,
+    RoundClosed,
+}
+
+impl MessageKey {
+    pub const ALL: [MessageKey; 34] = [
+        MessageKey::ConnectTimeout,
+        MessageKey::InternalError,
+        MessageKey::MessageTooLarge,
+        MessageKey::NotConnected,
+        MessageKey::FindMatchFailed,
+        MessageKey::RoomNotFound,
+        MessageKey::JoinRoomFailed,
+        MessageKey::InvalidMove,
+        MessageKey::InvalidRules,
+        MessageKey::SubmitMoveFailed,
+        MessageKey::CannotPause,
+        MessageKey::NotPaused,
+        MessageKey::PauseRequestFailed,
+        MessageKey::SlowConnection,
+        MessageKey::UnsupportedFrame,
+        MessageKey::AlreadyConnected,
+        MessageKey::AlreadyQueued,
+        MessageKey::AlreadyInGame,
+        MessageKey::NotInGame,
+        MessageKey::Muted,
+        MessageKey::NoBackfillOffer,
+        MessageKey::HostUnavailable,
+        MessageKey::InvalidDisplayName,
+        MessageKey::InvalidChatMessage,
+        MessageKey::ChatDisabled,
+        MessageKey::InappropriateLanguage,
+        MessageKey::MalformedMessage,
+        MessageKey::UnknownMessageType,
+        MessageKey::QuotaExceeded,
+        MessageKey::QuitCooldown,
+        MessageKey::InviteInvalid,
+        MessageKey::MoveAlreadySubmitted,
+        MessageKey::GamePaused,
+        MessageKey::RoundClosed,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            MessageKey::ConnectTimeout => "connect_timeout",
+            MessageKey::InternalError => "internal_error",
+            MessageKey::MessageTooLarge => "message_too_large",
+            MessageKey::NotConnected => "not_connected",
+            MessageKey::FindMatchFailed => "find_match_failed",
+            MessageKey::RoomNotFound => "room_not_found",
+            MessageKey::JoinRoomFailed => "join_room_failed",
+            MessageKey::InvalidMove => "invalid_move",
+            MessageKey::InvalidRules => "invalid_rules",
+            MessageKey::SubmitMoveFailed => "submit_move_failed",
+            MessageKey::CannotPause => "cannot_pause",
+            MessageKey::NotPaused => "not_paused",
+            MessageKey::PauseRequestFailed => "pause_request_failed",
+            MessageKey::SlowConnection => "slow_connection",
+            MessageKey::UnsupportedFrame => "unsupported_frame",
+            MessageKey::AlreadyConnected => "already_connected",
+            MessageKey::AlreadyQueued => "already_queued",
+            MessageKey::AlreadyInGame => "already_in_game",
+            MessageKey::NotInGame => "not_in_game",
+            MessageKey::Muted => "muted",
+            MessageKey::NoBackfillOffer => "no_backfill_offer",
+            MessageKey::HostUnavailable => "host_unavailable",
+            MessageKey::InvalidDisplayName => "invalid_display_name",
+            MessageKey::InvalidChatMessage => "invalid_chat_message",
+            MessageKey::ChatDisabled => "chat_disabled",
+            MessageKey::InappropriateLanguage => "inappropriate_language",
+            MessageKey::MalformedMessage => "malformed_message",
+            MessageKey::UnknownMessageType => "unknown_message_type",
+            MessageKey::QuotaExceeded => "quota_exceeded",
+            MessageKey::QuitCooldown => "quit_cooldown",
+            MessageKey::InviteInvalid => "invite_invalid",
+            MessageKey::MoveAlreadySubmitted => "move_already_submitted",
+            MessageKey::GamePaused => "game_paused",
+            MessageKey::RoundClosed => "round_closed",
+        }
+    }
+
+    /// Built-in English text, also the last-resort fallback for every locale.
+    fn english(self) -> &'static str {
+        match self {
+            MessageKey::ConnectTimeout => "Connect timeout",
+            MessageKey::InternalError => "Internal server error",
+            MessageKey::MessageTooLarge => "Message too large: {size} bytes (max {max})",
+            MessageKey::NotConnected => "Not connected",
+            MessageKey::FindMatchFailed => "Failed to find match",
+            MessageKey::RoomNotFound => "No room with that id or code is waiting for you",
+            MessageKey::JoinRoomFailed => "Failed to join room",
+            MessageKey::InvalidMove => "Invalid move",
+            MessageKey::InvalidRules => "These rules can't be played: {reason}",
+            MessageKey::SubmitMoveFailed => "Failed to submit move",
+            MessageKey::CannotPause => "Cannot pause now",
+            MessageKey::NotPaused => "Game is not paused",
+            MessageKey::PauseRequestFailed => "Failed to process pause request",
+            MessageKey::SlowConnection => "Connection is falling behind; messages are queuing up",
+            MessageKey::UnsupportedFrame => "Binary frames are not supported; send JSON text frames",
+            MessageKey::AlreadyConnected => "Already connected",
+            MessageKey::AlreadyQueued => "Already waiting for a match",
+            MessageKey::AlreadyInGame => "Already in a game",
+            MessageKey::NotInGame => "Not in a game",
+            MessageKey::Muted => "Too many requests; you can send challenges, rematches, and chat again in {seconds}s",
+            MessageKey::NoBackfillOffer => "No open seat in your room to fill",
+            MessageKey::HostUnavailable => "The server hosting your game went away; find a new match",
+            MessageKey::InvalidDisplayName => "Display names are 1 to 32 characters",
+            MessageKey::InvalidChatMessage => "Chat messages are 1 to 200 characters",
+            MessageKey::ChatDisabled => "Chat is turned off in this room",
+            MessageKey::InappropriateLanguage => "That contains language this server doesn't allow",
+            MessageKey::MalformedMessage => "Messages are JSON objects with a \"type\" field",
+            MessageKey::UnknownMessageType => "Unknown message type",
+            MessageKey::QuotaExceeded => "This server's limit on {quota} ({limit}) has been reached; try again later",
+            MessageKey::QuitCooldown => "You left too many games early; you can look for a match again in {seconds}s",
+            MessageKey::InviteInvalid => "This invite is invalid, has expired, or its game has no seat left",
+            MessageKey::MoveAlreadySubmitted => "You already moved this round",
+            MessageKey::GamePaused => "The game is paused; moves are taken again once it resumes",
+            MessageKey::RoundClosed => "No round is open for moves right now",
+        }
+    }
+}
+
+/// Where extra locales come from. Each entry maps a locale tag to
+/// `code -> template`, with `{name}` placeholders for arguments.
+pub trait CatalogSource {
+    fn load(&self) -> Result<HashMap<String, HashMap<String, String>>>;
+}
+
+/// One `<locale>.json` file per locale holding a flat `{ "code": "template" }` object.
+pub struct JsonDirSource {
+    dir: PathBuf,
+}
+
+impl JsonDirSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl CatalogSource for JsonDirSource {
+    fn load(&self) -> Result<HashMap<String, HashMap<String, String>>> {
+        let mut locales = HashMap::new();
+        let entries = std::fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to read message catalog dir {}", self.dir.display()))?;
+
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(locale) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let messages = serde_json::from_str(&contents)
+                .with_context(|| format!("Invalid message catalog {}", path.display()))?;
+            locales.insert(locale.to_string(), messages);
+        }
+
+        Ok(locales)
+    }
+}
+
+/// Localized templates per locale. Lookups fall back from `pt-BR` to `pt`,
+/// then to the default locale, then to the built-in English text.
+pub struct Catalog {
+    default_locale: String,
+    locales: HashMap<String, HashMap<String, String>>,
+}
+
+impl Catalog {
+    /// English only.
+    pub fn builtin() -> Self {
+        let english = MessageKey::ALL
+            .iter()
+            .map(|key| (key.code().to_string(), key.english().to_string()))
+            .collect();
+
+        Self {
+            default_locale: DEFAULT_LOCALE.to_string(),
+            locales: HashMap::from([(DEFAULT_LOCALE.to_string(), english)]),
+        }
+    }
+
+    pub fn from_config(config: &I18nConfig) -> Result<Self> {
+        let mut catalog = Self::builtin();
+        if let Some(dir) = &config.catalog_dir {
+            catalog.extend(&JsonDirSource::new(dir))?;
+        }
+        catalog.default_locale = normalize(&config.default_locale);
+        Ok(catalog)
+    }
+
+    /// Merges another source's locales over the current ones.
+    pub fn extend(&mut self, source: &dyn CatalogSource) -> Result<()> {
+        for (locale, messages) in source.load()? {
+            self.locales.entry(normalize(&locale)).or_default().extend(messages);
+        }
+        Ok(())
+    }
+
+    /// The locale a client asking for `requested` will be served.
+    pub fn negotiate(&self, requested: Option<&str>) -> String {
+        requested
+            .map(normalize)
+            .into_iter()
+            .flat_map(|locale| {
+                let language = locale.split('-').next().unwrap_or_default().to_string();
+                [locale, language]
+            })
+            .find(|locale| self.locales.contains_key(locale))
+            .unwrap_or_else(|| self.default_locale.clone())
+    }
+
+    pub fn render(&self, locale: &str, key: MessageKey, args: &[(&str, String)]) -> String {
+        let template = [locale, self.default_locale.as_str(), DEFAULT_LOCALE]
+            .iter()
+            .find_map(|locale| self.locales.get(*locale)?.get(key.code()))
+            .map(String::as_str)
+            .unwrap_or_else(|| key.english());
+
+        args.iter().fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+    }
+
+    pub fn error(&self, locale: &str, key: MessageKey, args: &[(&str, String)]) -> ServerMessage {
+        ServerMessage::Error {
+            code: Some(key.code().to_string()),
+            message: self.render(locale, key, args),
+            request_id: None,
+        }
+    }
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+// Locale tags are matched case-insensitively with `-` separators: `pt_br` -> `pt-br`
+fn normalize(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::{Catalog, CatalogSource, MessageKey};
+
+    #[test]
+    fn test_message_catalog_localizes_text_but_not_codes() {
+        struct Spanish;
+        impl CatalogSource for Spanish {
+            fn load(&self) -> anyhow::Result<std::collections::HashMap<String, std::collections::HashMap<String, String>>> {
+                let messages = [
+                    ("not_connected", "No conectado"),
+                    ("message_too_large", "Mensaje demasiado grande: {size} bytes (máx. {max})"),
+                ];
+                let messages = messages.iter().map(|(code, text)| (code.to_string(), text.to_string())).collect();
+                Ok([("es".to_string(), messages)].into())
+            }
+        }
+
+        let mut catalog = Catalog::builtin();
+        catalog.extend(&Spanish).unwrap();
+
+        assert_eq!(catalog.negotiate(Some("es_MX")), "es");
+        assert_eq!(catalog.negotiate(Some("fr")), "en");
+        assert_eq!(catalog.negotiate(None), "en");
+
+        let args = [("size", "300000".to_string()), ("max", "262144".to_string())];
+        assert_eq!(
+            catalog.render("es", MessageKey::MessageTooLarge, &args),
+            "Mensaje demasiado grande: 300000 bytes (máx. 262144)"
+        );
+        // Missing translations fall back to English
+        assert_eq!(catalog.render("es", MessageKey::InvalidMove, &[]), "Invalid move");
+
+        let english = serde_json::to_value(catalog.error("en", MessageKey::NotConnected, &[])).unwrap();
+        let spanish = serde_json::to_value(catalog.error("es", MessageKey::NotConnected, &[])).unwrap();
+        assert_eq!(english["code"], "not_connected");
+        assert_eq!(english["code"], spanish["code"]);
+        assert_eq!(spanish["message"], "No conectado");
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::Result;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::sync::Arc;
+use tracing::info;
+use warp::http::StatusCode;
+use warp::Filter;
+
+use crate::application::{GameManager, ReservedMatch};
+use crate::config::{InvitesConfig, Secret};
+use crate::domain::{ClientMessage, ServerMessage};
+
+/// What a valid invite token names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InviteClaims {
+    pub join_code: String,
+    pub expires_at_ms: i64,
+}
+
+/// Why an invite token was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InviteRejected {
+    Invalid, // Malformed, or not signed with a key this server holds
+    Expired,
+}
+
+/// How to take an invite's seat, as returned by `GET /join/{token}`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JoinInstructions {
+    pub room_code: String,
+    pub open_seats: usize,
+    pub expires_at_ms: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub websocket_url: Option<String>,
+    pub connect: ClientMessage, // Send as the first message; the server seats the player after `connected`
+}
+
+/// Issues and checks signed `{joinCode}.{expiresAtMs}.{mac}` invite tokens. Tokens are
+/// signed with the first configured key and accepted under any of them, so keys rotate
+/// like the cluster's.
+pub struct Invites {
+    config: InvitesConfig,
+    keys: Vec<Secret>,
+}
+
+impl Invites {
+    pub fn new(config: InvitesConfig) -> Result<Self> {
+        let keys = config.secret.load()?;
+        Ok(Self::with_keys(config, keys))
+    }
+
+    /// Signs with `keys` instead of loading them; an empty list gets a per-process key.
+    pub fn with_keys(config: InvitesConfig, mut keys: Vec<Secret>) -> Self {
+        if keys.is_empty() {
+            info!("No invite secret configured; invite links stop working on restart");
+            let key = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+            keys.push(Secret::new("process", key));
+        }
+        Self { config, keys }
+    }
+
+    /// A signed link to `reserved`'s room, valid for `ttl_ms`.
+    pub fn issue(&self, reserved: &ReservedMatch) -> ServerMessage {
+        let expires_at_ms = Utc::now().timestamp_millis() + self.config.ttl_ms as i64;
+        let payload = format!("{}.{}", reserved.join_code, expires_at_ms);
+        let mac = invite_mac(&self.keys[0], &payload).finalize().into_bytes();
+        let token = format!("{}.{}", payload, hex::encode(mac));
+
+        ServerMessage::Invite {
+            room_id: reserved.room_id.clone(),
+            url: format!("{}/join/{}", self.config.public_url.as_deref().unwrap_or("").trim_end_matches('/'), token),
+            token,
+            expires_at_ms,
+        }
+    }
+
+    /// The claims of a token signed with any invite key, if it hasn't expired by `now_ms`.
+    pub fn verify(&self, token: &str, now_ms: i64) -> Result<InviteClaims, InviteRejected> {
+        let (payload, mac) = token.rsplit_once('.').ok_or(InviteRejected::Invalid)?;
+        let mac = hex::decode(mac).map_err(|_| InviteRejected::Invalid)?;
+        if !self.keys.iter().any(|key| invite_mac(key, payload).verify_slice(&mac).is_ok()) {
+            return Err(InviteRejected::Invalid);
+        }
+        let (join_code, expires_at_ms) = payload.split_once('.').ok_or(InviteRejected::Invalid)?;
+        let expires_at_ms = expires_at_ms.parse::<i64>().map_err(|_| InviteRejected::Invalid)?;
+        if expires_at_ms <= now_ms {
+            return Err(InviteRejected::Expired);
+        }
+        Ok(InviteClaims {
+            join_code: join_code.to_string(),
+            expires_at_ms,
+        })
+    }
+
+    fn instructions(&self, token: &str, claims: InviteClaims, open_seats: usize) -> JoinInstructions {
+        JoinInstructions {
+            room_code: claims.join_code,
+            open_seats,
+            expires_at_ms: claims.expires_at_ms,
+            websocket_url: self.config.websocket_url.clone(),
+            connect: ClientMessage::Connect {
+                player_id: None,
+                locale: None,
+                client_version: None,
+                protocol_version: None,
+                reconnect_token: None,
+                display_name: None,
+                region: None,
+                experiments: Vec::new(),
+                tenant: None,
+                invite: Some(token.to_string()),
+            },
+        }
+    }
+}
+
+fn invite_mac(key: &Secret, payload: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.expose().as_bytes()).expect("HMAC accepts any key length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// `GET /join/{token}`: checks an invite link and answers with how to take its seat.
+/// 404 for a token this server didn't sign or a room that has no seat left, 410 once
+/// the link has expired.
+pub fn create_invite_routes(
+    invites: Arc<Invites>,
+    game_manager: Arc<GameManager>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("join" / String)
+        .and(warp::get())
+        .and(warp::any().map(move || invites.clone()))
+        .and(warp::any().map(move || game_manager.clone()))
+        .and_then(join_handler)
+}
+
+async fn join_handler(
+    token: String,
+    invites: Arc<Invites>,
+    game_manager: Arc<GameManager>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let error = |message: &str, status| warp::reply::with_status(warp::reply::json(&serde_json::json!({ "error": message })), status);
+    let claims = match invites.verify(&token, Utc::now().timestamp_millis()) {
+        Ok(claims) => claims,
+        Err(InviteRejected::Invalid) => return Ok(error("Unknown invite", StatusCode::NOT_FOUND)),
+        Err(InviteRejected::Expired) => return Ok(error("This invite has expired", StatusCode::GONE)),
+    };
+    match game_manager.reservation(&claims.join_code).await {
+        Some(reserved) if reserved.open_seats > 0 => {
+            let instructions = invites.instructions(&token, claims, reserved.open_seats);
+            Ok(warp::reply::with_status(warp::reply::json(&instructions), StatusCode::OK))
+        }
+        _ => Ok(error("This game has no seat left", StatusCode::NOT_FOUND)),
+    }
+}
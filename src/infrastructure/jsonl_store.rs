@@ -0,0 +1,140 @@
+use anyhow::Result;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::borrow::Borrow;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// A JSON-lines file of `R` records backing an in-memory store: replayed once on open,
+/// appended to as the store changes, and rewritten whole when something has to go.
+/// An in-memory store keeps no file and every write is a no-op.
+pub struct JsonlStore<R> {
+    file: Mutex<Option<(PathBuf, File)>>,
+    records: PhantomData<fn(R)>,
+}
+
+impl<R: Serialize + DeserializeOwned> JsonlStore<R> {
+    pub fn in_memory() -> Self {
+        Self {
+            file: Mutex::new(None),
+            records: PhantomData,
+        }
+    }
+
+    /// Opens (or creates) the file at `path`, handing each record in it to `replay` in
+    /// order. Lines that don't parse are logged as a malformed `what` and skipped.
+    pub fn open(path: impl AsRef<Path>, what: &str, mut replay: impl FnMut(R)) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        if path.exists() {
+            for line in BufReader::new(File::open(path)?).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<R>(&line) {
+                    Ok(record) => replay(record),
+                    Err(e) => warn!("Skipping malformed {}: {}", what, e),
+                }
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(Some((path.to_path_buf(), file))),
+            records: PhantomData,
+        })
+    }
+
+    /// Appends one record.
+    pub fn persist(&self, record: &R) -> Result<()> {
+        if let Some((_, file)) = self.file.lock().as_mut() {
+            writeln!(file, "{}", serde_json::to_string(record)?)?;
+            file.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the whole file with `records`. They are written and synced to a temporary
+    /// file that is then renamed over the original, so a crash or failed write leaves the
+    /// old contents in place. Callers change their in-memory state only once this succeeds.
+    pub fn rewrite<I>(&self, records: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<R>,
+    {
+        let mut file = self.file.lock();
+        let Some((path, current)) = file.as_mut() else {
+            return Ok(());
+        };
+
+        let mut temp_path = path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+        let written = (|| -> Result<()> {
+            let mut temp = BufWriter::new(File::create(&temp_path)?);
+            for record in records {
+                writeln!(temp, "{}", serde_json::to_string(record.borrow())?)?;
+            }
+            temp.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            std::fs::rename(&temp_path, &*path)?;
+            Ok(())
+        })();
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e.context(format!("Failed to rewrite {}", path.display())));
+        }
+
+        // The rename is only durable once the directory entry is
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir)?.sync_all()?;
+        }
+        *current = OpenOptions::new().append(true).open(&*path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::TitleSource;
+    use crate::infrastructure::TitleStore;
+
+    #[test]
+    fn test_failed_rewrite_leaves_the_file_and_the_store_as_they_were() {
+        let dir = std::env::temp_dir().join(format!("rps-jsonl-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("titles.jsonl");
+        let blocker = dir.join("titles.jsonl.tmp");
+        let store = TitleStore::open(&path).unwrap();
+        store.grant("p1", "champ", "Champion", TitleSource::Tournament).unwrap();
+        store.grant("p2", "champ", "Champion", TitleSource::Tournament).unwrap();
+        let before = std::fs::read_to_string(&path).unwrap();
+
+        // A directory where the temporary file goes fails the rewrite before anything is replaced
+        std::fs::create_dir(&blocker).unwrap();
+        assert!(store.forget("p1").is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+        assert_eq!(store.titles("p1").titles.len(), 1);
+
+        std::fs::remove_dir(&blocker).unwrap();
+        assert!(store.forget("p1").unwrap());
+        assert!(store.titles("p1").titles.is_empty());
+        assert!(!blocker.exists());
+
+        // Later appends go to the rewritten file
+        store.grant("p3", "champ", "Champion", TitleSource::Season).unwrap();
+        drop(store);
+        let reopened = TitleStore::open(&path).unwrap();
+        assert!(reopened.titles("p1").titles.is_empty());
+        assert_eq!(reopened.titles("p2").titles.len(), 1);
+        assert_eq!(reopened.titles("p3").titles.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
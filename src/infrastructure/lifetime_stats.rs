@@ -0,0 +1,132 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+
+use super::listener::{CONNECTIONS_ACCEPTED, PEAK_CONNECTIONS};
+use crate::application::GameManager;
+
+/// Server-wide totals, either since this process booted or summed over every run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerTotals {
+    pub games_played: u64,
+    pub connections: u64,
+    pub peak_connections: u64, // Highest concurrency, not a sum
+}
+
+impl ServerTotals {
+    /// This process's totals so far.
+    pub fn since_boot(game_manager: &GameManager) -> Self {
+        Self {
+            games_played: game_manager.live_stats().games_finished,
+            connections: CONNECTIONS_ACCEPTED.load(Ordering::Relaxed),
+            peak_connections: PEAK_CONNECTIONS.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Totals carried over from earlier runs, restored at boot from a JSON file and written back
+/// periodically and on shutdown, so a restart doesn't reset them to zero.
+pub struct LifetimeStats {
+    before_boot: ServerTotals,
+    path: Option<PathBuf>,
+}
+
+impl LifetimeStats {
+    pub fn in_memory() -> Self {
+        Self {
+            before_boot: ServerTotals::default(),
+            path: None,
+        }
+    }
+
+    /// Reads the totals left by earlier runs; a missing file starts them at zero.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let before_boot = if path.exists() {
+            let body = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_slice(&body).unwrap_or_else(|e| {
+                warn!("Ignoring malformed lifetime stats in {}: {}", path.display(), e);
+                ServerTotals::default()
+            })
+        } else {
+            ServerTotals::default()
+        };
+
+        Ok(Self {
+            before_boot,
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    /// Earlier runs' totals plus `since_boot`.
+    pub fn lifetime(&self, since_boot: ServerTotals) -> ServerTotals {
+        ServerTotals {
+            games_played: self.before_boot.games_played + since_boot.games_played,
+            connections: self.before_boot.connections + since_boot.connections,
+            peak_connections: self.before_boot.peak_connections.max(since_boot.peak_connections),
+        }
+    }
+
+    /// Writes the lifetime totals, replacing the file in one rename so a crash mid-write
+    /// leaves the previous totals intact.
+    pub fn persist(&self, since_boot: ServerTotals) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&self.lifetime(since_boot))?).with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Persists every `interval`.
+    pub fn spawn(self: Arc<Self>, game_manager: Arc<GameManager>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.persist(ServerTotals::since_boot(&game_manager)) {
+                    error!("Failed to persist lifetime stats: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_lifetime_stats_survive_a_restart_and_keep_the_highest_peak() {
+        use crate::infrastructure::{LifetimeStats, ServerTotals};
+
+        let path = std::env::temp_dir().join(format!("rps-lifetime-{}.json", uuid::Uuid::new_v4()));
+        let first_run = LifetimeStats::open(&path).unwrap();
+        let since_boot = ServerTotals { games_played: 3, connections: 10, peak_connections: 6 };
+        assert_eq!(first_run.lifetime(since_boot), since_boot);
+        first_run.persist(since_boot).unwrap();
+
+        let second_run = LifetimeStats::open(&path).unwrap();
+        let since_boot = ServerTotals { games_played: 2, connections: 4, peak_connections: 3 };
+        assert_eq!(second_run.lifetime(since_boot), ServerTotals { games_played: 5, connections: 14, peak_connections: 6 });
+        second_run.persist(since_boot).unwrap();
+        let third_run = LifetimeStats::open(&path).unwrap();
+        assert_eq!(third_run.lifetime(ServerTotals::default()).games_played, 5);
+
+        // A corrupt file starts the totals over rather than blocking boot
+        std::fs::write(&path, "{not json").unwrap();
+        assert_eq!(LifetimeStats::open(&path).unwrap().lifetime(ServerTotals::default()), ServerTotals::default());
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(LifetimeStats::in_memory().lifetime(since_boot), since_boot);
+    }
+}
@@ -0,0 +1,499 @@
+use anyhow::{Context, Result};
+use futures_util::FutureExt;
+use hyper::server::conn::Http;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::SocketAddr;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+use tokio_rustls::rustls;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, error, info, warn};
+use warp::filters::BoxedFilter;
+use warp::Reply;
+
+use super::rest_server::routes_service;
+use super::websocket::{WebSocketHandler, CONNECTION_PANICS};
+use crate::application::panic_message;
+use crate::config::{ListenerConfig, TlsConfig};
+
+/// Open connections across every listener.
+pub static TOTAL_CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Highest value `TOTAL_CONNECTIONS` has reached.
+pub static PEAK_CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Connections accepted across every listener since boot.
+pub static CONNECTIONS_ACCEPTED: AtomicU64 = AtomicU64::new(0);
+
+// Backoff while out of file descriptors or memory, and between restarts of a failed accept loop
+const EXHAUSTED_BACKOFF_MIN: Duration = Duration::from_millis(10);
+const EXHAUSTED_BACKOFF_MAX: Duration = Duration::from_secs(1);
+const RESTART_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+// Longest request head read while telling a WebSocket upgrade from a plain HTTP request
+const MAX_SNIFFED_HEAD: usize = 8 * 1024;
+
+/// REST routes a listener answers next to WebSocket upgrades when it shares its port.
+pub type HttpRoutes = BoxedFilter<(Box<dyn Reply>,)>;
+
+/// How the accept loop treats an `accept()` error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcceptErrorKind {
+    Transient, // Concerns only the connection being accepted; take the next one
+    Exhausted, // Out of file descriptors or kernel memory; back off until some are freed
+    Fatal,     // The listening socket itself is broken; restart the accept loop
+}
+
+pub fn classify_accept_error(error: &io::Error) -> AcceptErrorKind {
+    match error.raw_os_error() {
+        Some(libc::EMFILE | libc::ENFILE | libc::ENOBUFS | libc::ENOMEM) => return AcceptErrorKind::Exhausted,
+        Some(libc::ECONNABORTED | libc::ECONNRESET | libc::EINTR | libc::EAGAIN | libc::EPROTO | libc::EPERM) => {
+            return AcceptErrorKind::Transient
+        }
+        _ => {}
+    }
+    match error.kind() {
+        io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::Interrupted
+        | io::ErrorKind::WouldBlock
+        | io::ErrorKind::TimedOut => AcceptErrorKind::Transient,
+        io::ErrorKind::OutOfMemory => AcceptErrorKind::Exhausted,
+        _ => AcceptErrorKind::Fatal,
+    }
+}
+
+#[derive(Debug, Default)]
+struct ListenerStats {
+    active: AtomicU64,
+    accepted: AtomicU64,
+    rejected: AtomicU64, // Refused because the listener was at its connection cap
+    tls_failures: AtomicU64,
+    accept_errors: AtomicU64,
+    fd_exhaustions: AtomicU64, // Times accept() ran out of file descriptors or memory
+    fd_exhausted: AtomicBool,  // Alarm: set while backing off, cleared by the next accepted client
+    restarts: AtomicU64,       // Accept loop restarts after a fatal socket error
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ListenerSnapshot {
+    pub name: String,
+    pub address: String,
+    pub tls: bool,
+    pub max_connections: usize,
+    pub active_connections: u64,
+    pub accepted: u64,
+    pub rejected: u64,
+    pub tls_failures: u64,
+    pub accept_errors: u64,
+    pub fd_exhaustions: u64,
+    pub fd_exhausted: bool,
+    pub restarts: u64,
+}
+
+/// A WebSocket listening socket with its own connection cap and counters.
+pub struct WsListener {
+    name: String,
+    address: String,
+    max_connections: usize,
+    tls: Option<TlsAcceptor>,
+    stats: ListenerStats,
+    http: OnceLock<(HttpRoutes, Http)>, // Set in single-port mode; requests that aren't upgrades go here
+}
+
+impl WsListener {
+    /// Loads TLS material up front so a bad certificate fails startup, not the first client.
+    pub fn from_config(config: &ListenerConfig, default_max_connections: usize) -> Result<Self> {
+        let tls = match &config.tls {
+            Some(tls) => Some(load_tls(tls).with_context(|| format!("Invalid TLS config for listener {}", config.name))?),
+            None => None,
+        };
+
+        Ok(Self {
+            name: config.name.clone(),
+            address: format!("{}:{}", config.host, config.port),
+            max_connections: config.max_connections.unwrap_or(default_max_connections),
+            tls,
+            stats: ListenerStats::default(),
+            http: OnceLock::new(),
+        })
+    }
+
+    /// Answers plain HTTP requests on this listener with `routes`, for hosts that expose a
+    /// single port. Which one a client gets is decided by its first request: a WebSocket
+    /// upgrade goes to the game, anything else is served by `routes` for the connection's life.
+    /// Plain HTTP connections count against the listener's cap while they are open.
+    pub fn share_port(&self, routes: HttpRoutes, http: Http) {
+        if self.http.set((routes, http)).is_err() {
+            warn!("Listener {} already shares its port", self.name);
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn snapshot(&self) -> ListenerSnapshot {
+        ListenerSnapshot {
+            name: self.name.clone(),
+            address: self.address.clone(),
+            tls: self.tls.is_some(),
+            max_connections: self.max_connections,
+            active_connections: self.stats.active.load(Ordering::Relaxed),
+            accepted: self.stats.accepted.load(Ordering::Relaxed),
+            rejected: self.stats.rejected.load(Ordering::Relaxed),
+            tls_failures: self.stats.tls_failures.load(Ordering::Relaxed),
+            accept_errors: self.stats.accept_errors.load(Ordering::Relaxed),
+            fd_exhaustions: self.stats.fd_exhaustions.load(Ordering::Relaxed),
+            fd_exhausted: self.stats.fd_exhausted.load(Ordering::Relaxed),
+            restarts: self.stats.restarts.load(Ordering::Relaxed),
+        }
+    }
+
+    pub async fn bind(&self) -> Result<TcpListener> {
+        let listener = TcpListener::bind(&self.address)
+            .await
+            .with_context(|| format!("Failed to bind listener {} on {}", self.name, self.address))?;
+
+        // Ultra-performance TCP settings
+        listener.set_ttl(128)?;
+
+        let scheme = if self.tls.is_some() { "wss" } else { "ws" };
+        info!("⚡ WebSocket Listener {}: {}://{} (max {} connections)", self.name, scheme, self.address, self.max_connections);
+        Ok(listener)
+    }
+
+    /// Accepts clients for as long as the server runs, handing each one to `handler`. If
+    /// the listening socket fails, it is bound again and accepting resumes.
+    pub async fn serve(self: Arc<Self>, listener: TcpListener, handler: WebSocketHandler) -> Result<()> {
+        let mut listener = Some(listener);
+        let mut backoff = RESTART_BACKOFF_MIN;
+        loop {
+            let socket = match listener.take() {
+                Some(socket) => socket,
+                None => match self.bind().await {
+                    Ok(socket) => socket,
+                    Err(e) => {
+                        error!("Listener {} could not rebind: {:#}; retrying in {:?}", self.name, e, backoff);
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(RESTART_BACKOFF_MAX);
+                        continue;
+                    }
+                },
+            };
+            backoff = RESTART_BACKOFF_MIN;
+
+            let e = self.accept_loop(&socket, &handler).await;
+            drop(socket);
+            self.stats.restarts.fetch_add(1, Ordering::Relaxed);
+            error!("Listener {} accept loop failed: {}; restarting in {:?}", self.name, e, backoff);
+            tokio::time::sleep(backoff).await;
+        }
+    }
+
+    /// Accepts until the socket fails for good, and returns why.
+    async fn accept_loop(self: &Arc<Self>, listener: &TcpListener, handler: &WebSocketHandler) -> io::Error {
+        let tls_handshake_timeout = Duration::from_millis(handler.config().connection_timeout_ms);
+        let mut exhausted_backoff = EXHAUSTED_BACKOFF_MIN;
+
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    self.stats.accept_errors.fetch_add(1, Ordering::Relaxed);
+                    match classify_accept_error(&e) {
+                        AcceptErrorKind::Transient => debug!("Listener {} accept error: {}", self.name, e),
+                        AcceptErrorKind::Exhausted => {
+                            if !self.stats.fd_exhausted.swap(true, Ordering::Relaxed) {
+                                self.stats.fd_exhaustions.fetch_add(1, Ordering::Relaxed);
+                                error!("🚨 Listener {} out of file descriptors or memory: {}", self.name, e);
+                            }
+                            tokio::time::sleep(exhausted_backoff).await;
+                            exhausted_backoff = (exhausted_backoff * 2).min(EXHAUSTED_BACKOFF_MAX);
+                        }
+                        AcceptErrorKind::Fatal => return e,
+                    }
+                    continue;
+                }
+            };
+            if self.stats.fd_exhausted.swap(false, Ordering::Relaxed) {
+                info!("Listener {} accepting again after running out of file descriptors", self.name);
+            }
+            exhausted_backoff = EXHAUSTED_BACKOFF_MIN;
+
+            if self.stats.active.load(Ordering::Relaxed) >= self.max_connections as u64 {
+                self.stats.rejected.fetch_add(1, Ordering::Relaxed);
+                warn!("Listener {} at capacity ({}); refusing connection", self.name, self.max_connections);
+                continue;
+            }
+
+            self.stats.active.fetch_add(1, Ordering::Relaxed);
+            self.stats.accepted.fetch_add(1, Ordering::Relaxed);
+            CONNECTIONS_ACCEPTED.fetch_add(1, Ordering::Relaxed);
+            let current = TOTAL_CONNECTIONS.fetch_add(1, Ordering::Relaxed) + 1;
+            PEAK_CONNECTIONS.fetch_max(current, Ordering::Relaxed);
+
+            // Ultra-performance TCP settings
+            if let Err(e) = stream.set_nodelay(true) {
+                warn!("Failed to set TCP_NODELAY: {}", e);
+            }
+
+            let this = self.clone();
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let serve = async {
+                    match &this.tls {
+                        Some(acceptor) => match timeout(tls_handshake_timeout, acceptor.accept(stream)).await {
+                            Ok(Ok(stream)) => this.dispatch(stream, peer, &handler, tls_handshake_timeout).await,
+                            Ok(Err(e)) => {
+                                this.stats.tls_failures.fetch_add(1, Ordering::Relaxed);
+                                Err(anyhow::anyhow!("TLS handshake failed: {}", e))
+                            }
+                            Err(_) => {
+                                this.stats.tls_failures.fetch_add(1, Ordering::Relaxed);
+                                Err(anyhow::anyhow!("TLS handshake timed out"))
+                            }
+                        },
+                        None => this.dispatch(stream, peer, &handler, tls_handshake_timeout).await,
+                    }
+                };
+                // A panic anywhere in the connection must still release its slot below
+                match AssertUnwindSafe(serve).catch_unwind().await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => error!("Connection error on {}: {}", this.name, e),
+                    Err(panic) => {
+                        CONNECTION_PANICS.fetch_add(1, Ordering::Relaxed);
+                        error!("Connection task from {} on {} panicked: {}", peer, this.name, panic_message(panic.as_ref()));
+                    }
+                }
+
+                this.stats.active.fetch_sub(1, Ordering::Relaxed);
+                TOTAL_CONNECTIONS.fetch_sub(1, Ordering::Relaxed);
+            });
+        }
+    }
+
+    /// Hands the client to the game, unless this listener shares its port and the client's
+    /// first request isn't a WebSocket upgrade.
+    async fn dispatch<S>(&self, mut stream: S, peer: SocketAddr, handler: &WebSocketHandler, head_timeout: Duration) -> anyhow::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let Some((routes, http)) = self.http.get() else {
+            return handler.handle_connection(stream).await;
+        };
+        let head = timeout(head_timeout, read_request_head(&mut stream))
+            .await
+            .context("Request head timed out")??;
+        let upgrade = is_websocket_upgrade(&head);
+        let stream = Rewind { prefix: head, read: 0, inner: stream };
+        if upgrade {
+            return handler.handle_connection(stream).await;
+        }
+        http.serve_connection(stream, routes_service(routes.clone(), peer))
+            .await
+            .context("HTTP connection failed")
+    }
+}
+
+/// Reads until the end of the first request head, or as much of it as `MAX_SNIFFED_HEAD` allows.
+async fn read_request_head<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") && head.len() < MAX_SNIFFED_HEAD {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&chunk[..read]);
+    }
+    Ok(head)
+}
+
+/// Whether an HTTP request head asks to upgrade to WebSocket.
+pub fn is_websocket_upgrade(head: &[u8]) -> bool {
+    String::from_utf8_lossy(head).lines().skip(1).any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("upgrade") && value.split(',').any(|token| token.trim().eq_ignore_ascii_case("websocket"))
+        })
+    })
+}
+
+/// A stream with the bytes already read off it put back in front.
+struct Rewind<S> {
+    prefix: Vec<u8>,
+    read: usize,
+    inner: S,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Rewind<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        if self.read < self.prefix.len() {
+            let len = (self.prefix.len() - self.read).min(buf.remaining());
+            let start = self.read;
+            buf.put_slice(&self.prefix[start..start + len]);
+            self.read += len;
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Rewind<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>, bufs: &[io::IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+fn load_tls(config: &TlsConfig) -> Result<TlsAcceptor> {
+    let mut cert_reader = BufReader::new(File::open(&config.cert_path).with_context(|| config.cert_path.clone())?);
+    let certs: Vec<_> = rustls_pemfile::certs(&mut cert_reader)?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+    if certs.is_empty() {
+        anyhow::bail!("No certificates in {}", config.cert_path);
+    }
+
+    let mut key_reader = BufReader::new(File::open(&config.key_path).with_context(|| config.key_path.clone())?);
+    let key = rustls_pemfile::read_all(&mut key_reader)?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key) | rustls_pemfile::Item::RSAKey(key) | rustls_pemfile::Item::ECKey(key) => {
+                Some(rustls::PrivateKey(key))
+            }
+            _ => None,
+        })
+        .with_context(|| format!("No private key in {}", config.key_path))?;
+
+    let server_config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use warp::Filter;
+
+    use crate::application::GameManager;
+    use crate::config::{ListenerConfig, ServerConfig};
+    use crate::domain::GameConfig;
+    use crate::infrastructure::{classify_accept_error, http_options, AcceptErrorKind, WebSocketHandler, WsListener};
+
+    #[tokio::test]
+    async fn test_listener_enforces_its_connection_cap() {
+        let config = ListenerConfig {
+            name: "internal".to_string(),
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            max_connections: Some(1),
+            tls: None,
+        };
+        let listener = Arc::new(WsListener::from_config(&config, 100).unwrap());
+        let socket = listener.bind().await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let handler = WebSocketHandler::new(
+            Arc::new(GameManager::new(GameConfig::default())),
+            ServerConfig::default().websocket,
+        );
+        tokio::spawn(listener.clone().serve(socket, handler));
+
+        let (_first, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+        assert!(tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.is_err());
+
+        let snapshot = listener.snapshot();
+        assert_eq!((snapshot.name.as_str(), snapshot.max_connections), ("internal", 1));
+        assert_eq!((snapshot.active_connections, snapshot.accepted, snapshot.rejected), (1, 1, 1));
+
+        let missing_cert = ListenerConfig {
+            tls: Some(crate::config::TlsConfig {
+                cert_path: "/nonexistent/cert.pem".to_string(),
+                key_path: "/nonexistent/key.pem".to_string(),
+            }),
+            ..config
+        };
+        assert!(WsListener::from_config(&missing_cert, 100).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shared_port_listener_serves_rest_and_websocket() {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let config = ListenerConfig {
+            name: "public".to_string(),
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            max_connections: None,
+            tls: None,
+        };
+        let listener = Arc::new(WsListener::from_config(&config, 100).unwrap());
+        let routes = warp::path("health").map(|| "ok");
+        let routes = routes.map(|reply| Box::new(reply) as Box<dyn warp::Reply>).boxed();
+        listener.share_port(routes, http_options(&ServerConfig::default().rest_api));
+        let socket = listener.bind().await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let handler = WebSocketHandler::new(
+            Arc::new(GameManager::new(GameConfig::default())),
+            ServerConfig::default().websocket,
+        );
+        tokio::spawn(listener.clone().serve(socket, handler));
+
+        let mut http = tokio::net::TcpStream::connect(addr).await.unwrap();
+        http.write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        http.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.ends_with("ok"));
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/", addr)).await.unwrap();
+        ws.send(Message::Text(r#"{"type":"connect","playerId":"alice"}"#.to_string())).await.unwrap();
+        let reply = ws.next().await.unwrap().unwrap().into_text().unwrap();
+        assert!(reply.contains(r#""type":"connected""#), "{}", reply);
+    }
+
+    #[test]
+    fn test_accept_errors_are_classified_for_retry_backoff_or_restart() {
+        use std::io::{Error, ErrorKind};
+
+        for errno in [libc::EMFILE, libc::ENFILE, libc::ENOBUFS, libc::ENOMEM] {
+            assert_eq!(classify_accept_error(&Error::from_raw_os_error(errno)), AcceptErrorKind::Exhausted);
+        }
+        for errno in [libc::ECONNABORTED, libc::EINTR, libc::EPROTO] {
+            assert_eq!(classify_accept_error(&Error::from_raw_os_error(errno)), AcceptErrorKind::Transient);
+        }
+        assert_eq!(classify_accept_error(&Error::from(ErrorKind::ConnectionReset)), AcceptErrorKind::Transient);
+        assert_eq!(classify_accept_error(&Error::from_raw_os_error(libc::EBADF)), AcceptErrorKind::Fatal);
+        assert_eq!(classify_accept_error(&Error::from_raw_os_error(libc::EINVAL)), AcceptErrorKind::Fatal);
+    }
+}
@@ -0,0 +1,329 @@
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex as AsyncMutex};
+use tokio::time::timeout;
+use tracing::{error, info, info_span, Instrument};
+use uuid::Uuid;
+use warp::Filter;
+
+use super::client_metrics::{ClientMetrics, CLIENT_METRICS};
+use super::protocol_state::ConnectionSession;
+use super::websocket::{new_correlation_id, WebSocketHandler};
+use crate::config::LongPollConfig;
+use crate::domain::ServerMessage;
+
+#[derive(Debug, Deserialize)]
+pub struct PollSendQuery {
+    pub session: Option<String>, // Omitted on the first request, which opens a session
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PollRecvQuery {
+    pub session: String,
+    #[serde(default)]
+    pub cursor: u64, // First sequence number not yet seen; everything before it is acknowledged
+}
+
+#[derive(Debug, Serialize)]
+pub struct PollSendResponse {
+    pub session: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PollRecvResponse {
+    pub session: String,
+    pub cursor: u64, // Pass back on the next poll
+    pub messages: Vec<ServerMessage>,
+}
+
+/// HTTP long-polling sessions for clients behind proxies that block WebSockets.
+///
+/// Client messages go through the same handler as WebSocket frames. Server messages are
+/// numbered and kept in a per-session buffer until a later poll's cursor acknowledges them,
+/// so a poll lost in transit is simply repeated with the same cursor.
+pub struct LongPollSessions {
+    handler: WebSocketHandler,
+    config: LongPollConfig,
+    sessions: Mutex<HashMap<String, Arc<PollSession>>>,
+}
+
+struct PollSession {
+    connection_id: String,
+    state: AsyncMutex<ConnectionSession>, // Held while a message is handled, so a session's messages stay ordered
+    locale: RwLock<String>,
+    tx: mpsc::UnboundedSender<ServerMessage>,
+    outbound: AsyncMutex<Outbound>,
+    last_seen: Mutex<Instant>,
+}
+
+struct Outbound {
+    rx: mpsc::UnboundedReceiver<ServerMessage>,
+    next_seq: u64,
+    buffered: VecDeque<(u64, ServerMessage)>,
+}
+
+impl Outbound {
+    fn drain(&mut self, max_buffered: usize) {
+        while let Ok(message) = self.rx.try_recv() {
+            self.push(message, max_buffered);
+        }
+    }
+
+    fn push(&mut self, message: ServerMessage, max_buffered: usize) {
+        if self.buffered.len() >= max_buffered {
+            self.buffered.pop_front();
+        }
+        self.buffered.push_back((self.next_seq, message));
+        self.next_seq += 1;
+    }
+}
+
+impl LongPollSessions {
+    pub fn new(handler: WebSocketHandler, config: LongPollConfig) -> Self {
+        Self {
+            handler,
+            config,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Opens a session; `client` is the declared version or user agent, for client metrics.
+    pub fn open(&self, client: Option<&str>) -> String {
+        let id = Uuid::new_v4().simple().to_string();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let client_version = ClientMetrics::bucket(client);
+        CLIENT_METRICS.connected(&client_version);
+
+        let session = Arc::new(PollSession {
+            connection_id: new_correlation_id(),
+            state: AsyncMutex::new(ConnectionSession::new(client_version, self.handler.config().json_parser)),
+            locale: RwLock::new(self.handler.default_locale()),
+            tx,
+            outbound: AsyncMutex::new(Outbound { rx, next_seq: 0, buffered: VecDeque::new() }),
+            last_seen: Mutex::new(Instant::now()),
+        });
+        info!(conn = %session.connection_id, "New long-poll session");
+        self.sessions.lock().insert(id.clone(), session);
+        id
+    }
+
+    /// Handles one client message. Returns false if the session doesn't exist.
+    pub async fn send(&self, session_id: &str, text: &str) -> bool {
+        let Some(session) = self.session(session_id) else {
+            return false;
+        };
+
+        let span = info_span!("poll", conn = %session.connection_id);
+        let mut state = session.state.lock().await;
+        self.handler
+            .process_text(text, &session.connection_id, &mut state, &session.locale, &session.tx)
+            .instrument(span)
+            .await;
+        session.touch();
+        true
+    }
+
+    /// Messages from `cursor` on, waiting up to the poll timeout if there are none yet.
+    /// Returns None if the session doesn't exist.
+    pub async fn recv(&self, session_id: &str, cursor: u64) -> Option<PollRecvResponse> {
+        let session = self.session(session_id)?;
+        session.touch();
+        let max_buffered = self.config.max_buffered_messages.max(1);
+
+        let mut outbound = session.outbound.lock().await;
+        outbound.buffered.retain(|(seq, _)| *seq >= cursor);
+        outbound.drain(max_buffered);
+        if outbound.buffered.is_empty() {
+            let wait = Duration::from_millis(self.config.poll_timeout_ms);
+            if let Ok(Some(message)) = timeout(wait, outbound.rx.recv()).await {
+                outbound.push(message, max_buffered);
+                outbound.drain(max_buffered);
+            }
+        }
+        session.touch();
+
+        let messages: Vec<_> = outbound.buffered.iter().map(|(_, message)| message.clone()).collect();
+        let cursor = outbound.buffered.back().map_or(cursor.min(outbound.next_seq), |(seq, _)| seq + 1);
+        Some(PollRecvResponse {
+            session: session_id.to_string(),
+            cursor,
+            messages,
+        })
+    }
+
+    /// Closes sessions that saw no request within the idle timeout, returning how many.
+    pub async fn expire_idle(&self) -> usize {
+        let idle = Duration::from_millis(self.config.session_idle_timeout_ms);
+        let expired: Vec<_> = {
+            let mut sessions = self.sessions.lock();
+            let ids: Vec<_> = sessions
+                .iter()
+                .filter(|(_, session)| session.last_seen.lock().elapsed() >= idle)
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.iter().filter_map(|id| sessions.remove(id)).collect()
+        };
+
+        for session in &expired {
+            let state = session.state.lock().await;
+            CLIENT_METRICS.disconnected(&state.client_version);
+            if let Some(id) = &state.player_id {
+                info!(conn = %session.connection_id, "Long-poll session for {} expired", id);
+                if let Err(e) = self.handler.player_disconnected(id, &session.tx).await {
+                    error!("Failed to remove player {}: {}", id, e);
+                }
+            }
+        }
+        expired.len()
+    }
+
+    pub fn spawn_reaper(self: &Arc<Self>) {
+        let sessions = self.clone();
+        let period = Duration::from_millis((self.config.session_idle_timeout_ms / 2).max(1000));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                sessions.expire_idle().await;
+            }
+        });
+    }
+
+    fn session(&self, session_id: &str) -> Option<Arc<PollSession>> {
+        self.sessions.lock().get(session_id).cloned()
+    }
+}
+
+impl PollSession {
+    fn touch(&self) {
+        *self.last_seen.lock() = Instant::now();
+    }
+}
+
+/// `POST /poll/send` and `GET /poll/recv`: the long-polling transport. Not found when disabled.
+pub fn create_long_poll_routes(
+    sessions: Arc<LongPollSessions>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let max_message_size = sessions.handler.config().max_message_size as u64;
+    let enabled = sessions.config.enabled;
+    let enabled = warp::any()
+        .and_then(move || async move { if enabled { Ok(()) } else { Err(warp::reject::not_found()) } })
+        .untuple_one();
+
+    let send = warp::path!("poll" / "send")
+        .and(warp::post())
+        .and(warp::query::<PollSendQuery>())
+        .and(warp::header::optional::<String>("x-client-version"))
+        .and(warp::header::optional::<String>("user-agent"))
+        .and(warp::body::content_length_limit(max_message_size))
+        .and(warp::body::bytes())
+        .and(with_sessions(sessions.clone()))
+        .and_then(send_handler);
+
+    let recv = warp::path!("poll" / "recv")
+        .and(warp::get())
+        .and(warp::query::<PollRecvQuery>())
+        .and(with_sessions(sessions))
+        .and_then(recv_handler);
+
+    enabled.and(send.or(recv))
+}
+
+fn with_sessions(
+    sessions: Arc<LongPollSessions>,
+) -> impl Filter<Extract = (Arc<LongPollSessions>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || sessions.clone())
+}
+
+async fn send_handler(
+    query: PollSendQuery,
+    client_version: Option<String>,
+    user_agent: Option<String>,
+    body: bytes::Bytes,
+    sessions: Arc<LongPollSessions>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let session = match query.session {
+        Some(session) if sessions.session(&session).is_some() => session,
+        Some(_) => return Err(warp::reject::not_found()),
+        None => sessions.open(client_version.or(user_agent).as_deref()),
+    };
+
+    // An empty body just opens the session; a session expiring mid-request reads as not found
+    if !body.is_empty() && !sessions.send(&session, &String::from_utf8_lossy(&body)).await {
+        return Err(warp::reject::not_found());
+    }
+    Ok(warp::reply::json(&PollSendResponse { session }))
+}
+
+async fn recv_handler(query: PollRecvQuery, sessions: Arc<LongPollSessions>) -> Result<impl warp::Reply, warp::Rejection> {
+    match sessions.recv(&query.session, query.cursor).await {
+        Some(response) => Ok(warp::reply::json(&response)),
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::application::GameManager;
+    use crate::config::{LongPollConfig, ServerConfig};
+    use crate::domain::GameConfig;
+    use crate::infrastructure::{create_long_poll_routes, LongPollSessions, WebSocketHandler};
+
+    #[tokio::test]
+    async fn test_long_poll_session_round_trip() {
+        let handler = WebSocketHandler::new(
+            Arc::new(GameManager::new(GameConfig::default())),
+            ServerConfig::default().websocket,
+        );
+        let config = LongPollConfig {
+            poll_timeout_ms: 50,
+            session_idle_timeout_ms: 0,
+            ..LongPollConfig::default()
+        };
+        let sessions = Arc::new(LongPollSessions::new(handler, config));
+        let routes = create_long_poll_routes(sessions.clone());
+
+        let opened = warp::test::request()
+            .method("POST")
+            .path("/poll/send")
+            .body(r#"{"type":"connect","playerId":"poller"}"#)
+            .reply(&routes)
+            .await;
+        assert_eq!(opened.status(), 200);
+        let session = serde_json::from_slice::<serde_json::Value>(opened.body()).unwrap()["session"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let recv = |cursor: u64| {
+            warp::test::request().path(&format!("/poll/recv?session={}&cursor={}", session, cursor)).reply(&routes)
+        };
+        let first: serde_json::Value = serde_json::from_slice(recv(0).await.body()).unwrap();
+        assert_eq!(first["messages"][0]["type"], "connected");
+        assert_eq!(first["messages"][0]["playerId"], "poller");
+
+        // Unacknowledged messages are repeated; a later cursor acknowledges them
+        let repeated: serde_json::Value = serde_json::from_slice(recv(0).await.body()).unwrap();
+        assert_eq!(repeated["messages"], first["messages"]);
+        let cursor = first["cursor"].as_u64().unwrap();
+        let empty: serde_json::Value = serde_json::from_slice(recv(cursor).await.body()).unwrap();
+        assert_eq!(empty["messages"].as_array().unwrap().len(), 0);
+        assert_eq!(empty["cursor"], cursor);
+
+        assert_eq!(sessions.expire_idle().await, 1);
+        assert_eq!(recv(cursor).await.status(), 404);
+    }
+}
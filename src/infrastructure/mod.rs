@@ -2,8 +2,92 @@ pub mod websocket;
 pub mod rest_api;
 pub mod ultra_message_processor;
 pub mod ultra_connection_pool;
+pub mod admin_api;
+pub mod audit_log;
+pub mod request_id;
+pub mod webhooks;
+pub mod presence_push;
+pub mod rating_service;
+pub mod seasons;
+pub mod i18n;
+pub mod notification_inbox;
+pub mod client_metrics;
+pub mod process_metrics;
+pub mod long_poll;
+pub mod socket_io;
+pub mod demo_client;
+pub mod listener;
+pub mod protocol_state;
+pub mod codec;
+pub mod buffer_pool;
+pub mod writer_pool;
+pub mod fd_limits;
+pub mod traffic_recorder;
+pub mod cluster;
+pub mod object_store;
+pub mod stats_export;
+pub mod replay_archive;
+pub mod titles;
+pub mod player_stats;
+pub mod scripted_rules;
+pub mod room_templates;
+pub mod player_auth;
+pub mod player_activity;
+pub mod player_data;
+pub mod profanity_admin;
+pub mod rest_server;
+pub mod response_cache;
+pub mod api_keys;
+pub mod rest_rate_limit;
+pub mod lifetime_stats;
+pub mod invites;
+pub mod tournament_stream;
+pub mod resume_tokens;
+pub mod jsonl_store;
 
 pub use websocket::*;
 pub use rest_api::*;
 pub use ultra_message_processor::*;
-pub use ultra_connection_pool::*;
\ No newline at end of file
+pub use ultra_connection_pool::*;
+pub use admin_api::*;
+pub use audit_log::*;
+pub use request_id::*;
+pub use webhooks::*;
+pub use presence_push::*;
+pub use rating_service::*;
+pub use seasons::*;
+pub use i18n::*;
+pub use notification_inbox::*;
+pub use client_metrics::*;
+pub use process_metrics::*;
+pub use long_poll::*;
+pub use socket_io::*;
+pub use demo_client::*;
+pub use listener::*;
+pub use protocol_state::*;
+pub use codec::*;
+pub use buffer_pool::*;
+pub use writer_pool::*;
+pub use fd_limits::*;
+pub use traffic_recorder::*;
+pub use cluster::*;
+pub use object_store::*;
+pub use stats_export::*;
+pub use replay_archive::*;
+pub use titles::*;
+pub use player_stats::*;
+pub use scripted_rules::*;
+pub use room_templates::*;
+pub use player_auth::*;
+pub use player_activity::*;
+pub use player_data::*;
+pub use profanity_admin::*;
+pub use rest_server::*;
+pub use response_cache::*;
+pub use api_keys::*;
+pub use rest_rate_limit::*;
+pub use lifetime_stats::*;
+pub use invites::*;
+pub use tournament_stream::*;
+pub use resume_tokens::*;
+pub use jsonl_store::*;
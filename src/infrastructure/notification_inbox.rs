@@ -0,0 +1,161 @@
+use anyhow::Result;
+use chrono::Utc;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+use super::jsonl_store::JsonlStore;
+use crate::domain::{Notification, NotificationKind};
+
+// One line of the inbox file; replaying them in order rebuilds every inbox
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum InboxRecord {
+    Added { notification: Notification },
+    Acked {
+        #[serde(rename = "playerId")]
+        player_id: String,
+        ids: Vec<String>,
+    },
+}
+
+/// Per-player notifications held until acknowledged, mirrored to a JSON-lines file.
+pub struct NotificationInbox {
+    inboxes: RwLock<HashMap<String, Vec<Notification>>>,
+    log: JsonlStore<InboxRecord>,
+    max_pending: usize,
+}
+
+impl NotificationInbox {
+    pub fn in_memory(max_pending: usize) -> Self {
+        Self {
+            inboxes: RwLock::new(HashMap::new()),
+            log: JsonlStore::in_memory(),
+            max_pending: max_pending.max(1),
+        }
+    }
+
+    /// Opens (or creates) the inbox file and replays it.
+    pub fn open(path: impl AsRef<Path>, max_pending: usize) -> Result<Self> {
+        let mut inbox = Self::in_memory(max_pending);
+        inbox.log = JsonlStore::open(path, "inbox record", |record| inbox.apply(record))?;
+        Ok(inbox)
+    }
+
+    pub fn push(&self, player_id: &str, kind: NotificationKind, data: serde_json::Value) -> Result<Notification> {
+        let notification = Notification {
+            id: Uuid::new_v4().to_string(),
+            player_id: player_id.to_string(),
+            kind,
+            data,
+            created_at: Utc::now(),
+        };
+
+        self.log.persist(&InboxRecord::Added { notification: notification.clone() })?;
+        self.apply(InboxRecord::Added { notification: notification.clone() });
+        Ok(notification)
+    }
+
+    /// Unacknowledged notifications, oldest first. Reading does not acknowledge them.
+    pub fn pending(&self, player_id: &str) -> Vec<Notification> {
+        self.inboxes.read().get(player_id).cloned().unwrap_or_default()
+    }
+
+    /// Marks notifications as read so they are not delivered again; returns how many were pending.
+    pub fn ack(&self, player_id: &str, ids: &[String]) -> Result<usize> {
+        let pending = {
+            let inboxes = self.inboxes.read();
+            let Some(inbox) = inboxes.get(player_id) else {
+                return Ok(0);
+            };
+            inbox.iter().filter(|n| ids.contains(&n.id)).count()
+        };
+        if pending == 0 {
+            return Ok(0);
+        }
+
+        let record = InboxRecord::Acked {
+            player_id: player_id.to_string(),
+            ids: ids.to_vec(),
+        };
+        self.log.persist(&record)?;
+        self.apply(record);
+        Ok(pending)
+    }
+
+    /// Deletes the player's inbox, rewriting the file without it. Returns how many
+    /// notifications were dropped. On error both the file and memory still hold them.
+    pub fn forget(&self, player_id: &str) -> Result<usize> {
+        let mut inboxes = self.inboxes.write();
+        let Some(dropped) = inboxes.get(player_id).map(Vec::len) else {
+            return Ok(0);
+        };
+
+        let remaining = inboxes.iter().filter(|(id, _)| id.as_str() != player_id).flat_map(|(_, inbox)| inbox);
+        self.log.rewrite(remaining.map(|notification| InboxRecord::Added { notification: notification.clone() }))?;
+        inboxes.remove(player_id);
+        Ok(dropped)
+    }
+
+    fn apply(&self, record: InboxRecord) {
+        let mut inboxes = self.inboxes.write();
+        match record {
+            InboxRecord::Added { notification } => {
+                let inbox = inboxes.entry(notification.player_id.clone()).or_default();
+                inbox.push(notification);
+                if inbox.len() > self.max_pending {
+                    let overflow = inbox.len() - self.max_pending;
+                    inbox.drain(..overflow);
+                }
+            }
+            InboxRecord::Acked { player_id, ids } => {
+                let ids: HashSet<String> = ids.into_iter().collect();
+                if let Some(inbox) = inboxes.get_mut(&player_id) {
+                    inbox.retain(|n| !ids.contains(&n.id));
+                    if inbox.is_empty() {
+                        inboxes.remove(&player_id);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::NotificationKind;
+    use crate::infrastructure::NotificationInbox;
+
+    #[test]
+    fn test_notification_inbox_survives_restart_until_acked() {
+        let path = std::env::temp_dir().join(format!("rps-inbox-{}.jsonl", uuid::Uuid::new_v4()));
+
+        let inbox = NotificationInbox::open(&path, 2).unwrap();
+        let challenge = inbox
+            .push("p1", NotificationKind::ChallengeReceived, serde_json::json!({ "from": "p2" }))
+            .unwrap();
+        inbox.push("p1", NotificationKind::TournamentStarting, serde_json::Value::Null).unwrap();
+        inbox.push("p1", NotificationKind::AchievementUnlocked, serde_json::Value::Null).unwrap();
+        drop(inbox);
+
+        let reopened = NotificationInbox::open(&path, 2).unwrap();
+        let pending = reopened.pending("p1");
+        // Over the cap, the oldest notification is dropped
+        assert_eq!(pending.len(), 2);
+        assert!(pending.iter().all(|n| n.id != challenge.id));
+        assert_eq!(pending[0].kind, NotificationKind::TournamentStarting);
+
+        // Reading alone doesn't consume; acking does, and persists
+        assert_eq!(reopened.pending("p1").len(), 2);
+        assert_eq!(reopened.ack("p1", &[pending[0].id.clone(), "unknown".to_string()]).unwrap(), 1);
+        assert_eq!(reopened.ack("p2", &[pending[1].id.clone()]).unwrap(), 0);
+        drop(reopened);
+
+        let reopened = NotificationInbox::open(&path, 2).unwrap();
+        assert_eq!(reopened.pending("p1"), vec![pending[1].clone()]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,42 @@
+use serde::Deserialize;
+use std::sync::Arc;
+use warp::http::StatusCode;
+use warp::Filter;
+
+use super::player_auth::{with_caller, Caller};
+use crate::application::PlayerActivity;
+use crate::config::SecretStore;
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    pub limit: Option<usize>,
+}
+
+/// `GET /players/{id}/activity?limit=`: the player's recent connections, queue joins and
+/// games, newest first. Open to admins and to the player themselves with their token.
+pub fn create_player_activity_routes(
+    activity: Arc<PlayerActivity>,
+    secrets: Arc<SecretStore>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("players" / String / "activity")
+        .and(warp::get())
+        .and(with_caller(secrets))
+        .and(warp::query::<ActivityQuery>())
+        .map(move |player_id: String, caller: Caller, query: ActivityQuery| {
+            if !caller.may_view(&player_id) {
+                return warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({ "error": "Players can only see their own activity" })),
+                    StatusCode::FORBIDDEN,
+                );
+            }
+            let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+            let entries = activity.timeline(&player_id, limit);
+            warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "playerId": player_id, "entries": entries })),
+                StatusCode::OK,
+            )
+        })
+}
@@ -0,0 +1,81 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::sync::Arc;
+use warp::Filter;
+
+use super::admin_api::Unauthorized;
+use crate::config::SecretStore;
+
+#[derive(Deserialize)]
+struct TokenHeader {
+    alg: String,
+    kid: Option<String>, // Signing key id; the active key when absent
+}
+
+#[derive(Deserialize)]
+struct TokenClaims {
+    sub: String, // Player id
+    exp: i64,    // Unix seconds
+}
+
+/// Who is calling a player-scoped endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Caller {
+    Admin(String),  // Admin key id
+    Player(String), // Player id from their token
+}
+
+impl Caller {
+    /// Admins may look at anyone; players only at themselves.
+    pub fn may_view(&self, player_id: &str) -> bool {
+        match self {
+            Caller::Admin(_) => true,
+            Caller::Player(id) => id == player_id,
+        }
+    }
+}
+
+/// The player an HS256 JWT issued by the platform is for, if it is signed with one of
+/// the JWT signing keys and hasn't expired.
+pub fn verify_player_token(secrets: &SecretStore, token: &str) -> Option<String> {
+    let (signed, signature) = token.rsplit_once('.')?;
+    let (header, claims) = signed.split_once('.')?;
+    let header: TokenHeader = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header).ok()?).ok()?;
+    if header.alg != "HS256" {
+        return None;
+    }
+    let key = match &header.kid {
+        Some(kid) => secrets.jwt_key(kid)?,
+        None => secrets.active_jwt_key()?,
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.expose().as_bytes()).ok()?;
+    mac.update(signed.as_bytes());
+    mac.verify_slice(&URL_SAFE_NO_PAD.decode(signature).ok()?).ok()?;
+
+    let claims: TokenClaims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(claims).ok()?).ok()?;
+    (claims.exp > Utc::now().timestamp()).then_some(claims.sub)
+}
+
+// Accepts an admin API key (X-Api-Key or bearer) or a player's bearer JWT
+pub(crate) fn with_caller(secrets: Arc<SecretStore>) -> impl Filter<Extract = (Caller,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::header::optional::<String>("x-api-key"))
+        .and_then(move |authorization: Option<String>, api_key: Option<String>| {
+            let secrets = secrets.clone();
+            async move {
+                let bearer = authorization.as_deref().and_then(|value| value.strip_prefix("Bearer "));
+                if let Some(key_id) = api_key.as_deref().or(bearer).and_then(|key| secrets.verify_admin_key(key)) {
+                    return Ok(Caller::Admin(key_id));
+                }
+                bearer
+                    .and_then(|token| verify_player_token(&secrets, token))
+                    .map(Caller::Player)
+                    .ok_or_else(|| warp::reject::custom(Unauthorized))
+            }
+        })
+}
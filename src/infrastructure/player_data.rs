@@ -0,0 +1,213 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::error;
+use warp::http::StatusCode;
+use warp::Filter;
+
+use super::admin_api::with_audit_log;
+use super::audit_log::{AdminAction, AuditLog};
+use super::notification_inbox::NotificationInbox;
+use super::player_auth::{with_caller, Caller};
+use super::replay_archive::ReplayArchive;
+use super::seasons::Seasons;
+use super::titles::TitleStore;
+use crate::application::{ActivityEntry, EloRatings, GameHistory, GameRecord, PlayerActivity, Standing};
+use crate::config::SecretStore;
+use crate::domain::{Notification, PlayerTitles};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeasonPlacing {
+    pub season: u32,
+    pub standing: Standing,
+}
+
+/// Everything the server holds about one player.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerDataExport {
+    pub player_id: String,
+    pub exported_at: DateTime<Utc>,
+    pub rating: Option<Standing>, // Built-in ratings only; an external rating service keeps its own
+    pub seasons: Vec<SeasonPlacing>,
+    pub games: Vec<GameRecord>, // Recent and archived, with their rounds for replay
+    pub titles: PlayerTitles,
+    pub notifications: Vec<Notification>,
+    pub activity: Vec<ActivityEntry>,
+}
+
+/// What a deletion touched. Games and season standings are kept for the other players
+/// with the deleted player renamed to `alias`; the rest is dropped.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerDataDeletion {
+    pub player_id: String,
+    pub alias: String,
+    pub rating_removed: bool,
+    pub games_anonymized: usize,
+    pub archived_games_anonymized: usize,
+    pub season_standings_anonymized: usize,
+    pub titles_removed: bool,
+    pub notifications_removed: usize,
+    pub activity_removed: bool,
+}
+
+/// Exports and erases a player's data across the stores that key on their id.
+pub struct PlayerDataService {
+    elo: Arc<EloRatings>,
+    history: Arc<GameHistory>,
+    archive: Option<Arc<ReplayArchive>>,
+    titles: Arc<TitleStore>,
+    notifications: Arc<NotificationInbox>,
+    seasons: Arc<Seasons>,
+    activity: Arc<PlayerActivity>,
+}
+
+impl PlayerDataService {
+    pub fn new(
+        elo: Arc<EloRatings>,
+        history: Arc<GameHistory>,
+        titles: Arc<TitleStore>,
+        notifications: Arc<NotificationInbox>,
+        seasons: Arc<Seasons>,
+        activity: Arc<PlayerActivity>,
+    ) -> Self {
+        Self { elo, history, archive: None, titles, notifications, seasons, activity }
+    }
+
+    pub fn with_archive(mut self, archive: Arc<ReplayArchive>) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    pub async fn export(&self, player_id: &str) -> Result<PlayerDataExport> {
+        let mut games = self.history.games_of(player_id, usize::MAX);
+        if let Some(archive) = &self.archive {
+            games.extend(archive.games_of(player_id).await?);
+        }
+        games.sort_by_key(|record| std::cmp::Reverse(record.created_at));
+
+        Ok(PlayerDataExport {
+            player_id: player_id.to_string(),
+            exported_at: Utc::now(),
+            rating: self.elo.standings_of(&[player_id.to_string()]).pop().flatten(),
+            seasons: self
+                .seasons
+                .placings_of(player_id)
+                .into_iter()
+                .map(|(season, standing)| SeasonPlacing { season, standing })
+                .collect(),
+            games,
+            titles: self.titles.titles(player_id),
+            notifications: self.notifications.pending(player_id),
+            activity: self.activity.timeline(player_id, usize::MAX),
+        })
+    }
+
+    /// Removes the player from the ratings (and so the leaderboard), anonymizes their
+    /// games and season standings, and drops their titles, inbox and activity. Each store
+    /// is erased whole or not at all; after an error, repeating the request finishes the rest.
+    pub async fn delete(&self, player_id: &str) -> Result<PlayerDataDeletion> {
+        let alias = format!("deleted-{}", uuid::Uuid::new_v4().simple());
+        let mut deletion = PlayerDataDeletion {
+            player_id: player_id.to_string(),
+            alias: alias.clone(),
+            rating_removed: self.elo.remove(player_id),
+            games_anonymized: self.history.anonymize(player_id, &alias),
+            activity_removed: self.activity.forget(player_id),
+            ..Default::default()
+        };
+        if let Some(archive) = &self.archive {
+            deletion.archived_games_anonymized = archive.anonymize(player_id, &alias).await?;
+        }
+        deletion.season_standings_anonymized = self.seasons.anonymize(player_id, &alias)?;
+        deletion.titles_removed = self.titles.forget(player_id)?;
+        deletion.notifications_removed = self.notifications.forget(player_id)?;
+        Ok(deletion)
+    }
+}
+
+/// `GET /players/{id}/data` exports everything held about the player as JSON;
+/// `DELETE /players/{id}/data` erases it. Open to admins and to the player themselves.
+pub fn create_player_data_routes(
+    service: Arc<PlayerDataService>,
+    audit_log: Arc<AuditLog>,
+    secrets: Arc<SecretStore>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let export = warp::path!("players" / String / "data")
+        .and(warp::get())
+        .and(with_caller(secrets.clone()))
+        .and(with_service(service.clone()))
+        .and_then(export_handler);
+
+    let delete = warp::path!("players" / String / "data")
+        .and(warp::delete())
+        .and(with_caller(secrets))
+        .and(with_service(service))
+        .and(with_audit_log(audit_log))
+        .and_then(delete_handler);
+
+    export.or(delete)
+}
+
+fn with_service(
+    service: Arc<PlayerDataService>,
+) -> impl Filter<Extract = (Arc<PlayerDataService>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || service.clone())
+}
+
+async fn export_handler(
+    player_id: String,
+    caller: Caller,
+    service: Arc<PlayerDataService>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !caller.may_view(&player_id) {
+        return Ok(forbidden());
+    }
+    Ok(match service.export(&player_id).await {
+        Ok(export) => warp::reply::with_status(warp::reply::json(&export), StatusCode::OK),
+        Err(e) => {
+            error!("Failed to export data of {}: {}", player_id, e);
+            failed("Failed to export player data")
+        }
+    })
+}
+
+async fn delete_handler(
+    player_id: String,
+    caller: Caller,
+    service: Arc<PlayerDataService>,
+    audit_log: Arc<AuditLog>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !caller.may_view(&player_id) {
+        return Ok(forbidden());
+    }
+    let deleted = service.delete(&player_id).await;
+    let actor = match &caller {
+        Caller::Admin(key_id) => key_id.as_str(),
+        Caller::Player(_) => "player",
+    };
+    // The audit trail names the alias, never the erased id
+    let target = deleted.as_ref().map_or("unknown", |deletion| deletion.alias.as_str());
+    audit_log.record_or_log(actor, AdminAction::DeletePlayerData, target, deleted.is_ok());
+    Ok(match deleted {
+        Ok(deletion) => warp::reply::with_status(warp::reply::json(&deletion), StatusCode::OK),
+        Err(e) => {
+            error!("Failed to delete player data: {}", e);
+            failed("Failed to delete player data; some of it may be gone already, retry the request")
+        }
+    })
+}
+
+fn forbidden() -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": "Players can only manage their own data" })),
+        StatusCode::FORBIDDEN,
+    )
+}
+
+fn failed(message: &str) -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(warp::reply::json(&serde_json::json!({ "error": message })), StatusCode::INTERNAL_SERVER_ERROR)
+}
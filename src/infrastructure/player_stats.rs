@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use warp::http::StatusCode;
+use warp::Filter;
+
+use crate::application::{EloRatings, FastestPlayer, GameHistory, PlayerResults, QuitPenalties, QuitPenaltyStatus, DEFAULT_RATING};
+use crate::config::PlayerStatsConfig;
+
+const DEFAULT_FASTEST: usize = 20;
+const MAX_FASTEST: usize = 100;
+// Timed moves a player needs before they are ranked, so one lucky click doesn't top the board
+const MIN_RANKED_DECISIONS: u32 = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct StatsBatchRequest {
+    #[serde(rename = "playerIds")]
+    pub player_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FastestQuery {
+    pub limit: Option<usize>,
+}
+
+/// One player's row in a batch lookup.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerStatsEntry {
+    pub player_id: String,
+    pub rating: f64,
+    pub rank: Option<usize>, // None until the player has a rated game
+    pub rated_games: u32,
+    pub recent: PlayerResults, // Over the games still in the game history
+    pub quit_penalty: QuitPenaltyStatus,
+}
+
+/// Looks up ratings and recent results for many players with one query against each store.
+pub struct PlayerStatsService {
+    ratings: Arc<EloRatings>,
+    history: Arc<GameHistory>,
+    penalties: Option<Arc<QuitPenalties>>,
+    max_batch: usize,
+}
+
+impl PlayerStatsService {
+    pub fn new(ratings: Arc<EloRatings>, history: Arc<GameHistory>, config: &PlayerStatsConfig) -> Self {
+        Self {
+            ratings,
+            history,
+            penalties: None,
+            max_batch: config.max_batch,
+        }
+    }
+
+    pub fn with_quit_penalties(mut self, penalties: Arc<QuitPenalties>) -> Self {
+        self.penalties = Some(penalties);
+        self
+    }
+
+    /// Stats in the order asked, with repeated ids answered once.
+    pub fn lookup(&self, mut player_ids: Vec<String>) -> Vec<PlayerStatsEntry> {
+        let mut seen = HashSet::new();
+        player_ids.retain(|id| seen.insert(id.clone()));
+
+        let standings = self.ratings.standings_of(&player_ids);
+        let results = self.history.results_of(&player_ids);
+        let now = std::time::Instant::now();
+        player_ids
+            .into_iter()
+            .zip(standings)
+            .zip(results)
+            .map(|((player_id, standing), recent)| PlayerStatsEntry {
+                quit_penalty: self.penalties.as_ref().map(|penalties| penalties.status(&player_id, now)).unwrap_or_default(),
+                player_id,
+                rating: standing.as_ref().map_or(DEFAULT_RATING, |standing| standing.rating),
+                rank: standing.as_ref().map(|standing| standing.rank),
+                rated_games: standing.map_or(0, |standing| standing.games),
+                recent,
+            })
+            .collect()
+    }
+
+    /// The fastest-finger leaderboard over the games still in the history: a ranking of
+    /// recent play that forgets older games as the history turns over.
+    pub fn fastest(&self, limit: usize) -> Vec<FastestPlayer> {
+        self.history.fastest_players(limit, MIN_RANKED_DECISIONS)
+    }
+}
+
+/// `POST /players/stats:batch`: stats for up to `max_batch` players in one call, for
+/// tournament pages that would otherwise fetch each player on its own.
+/// `GET /players/fastest?limit=`: players by average decision time over recent games, quickest first.
+pub fn create_player_stats_routes(
+    stats: Arc<PlayerStatsService>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let fastest_stats = stats.clone();
+    let fastest = warp::path!("players" / "fastest")
+        .and(warp::get())
+        .and(warp::query::<FastestQuery>())
+        .map(move |query: FastestQuery| {
+            let players = fastest_stats.fastest(query.limit.unwrap_or(DEFAULT_FASTEST).clamp(1, MAX_FASTEST));
+            warp::reply::json(&serde_json::json!({ "players": players }))
+        });
+
+    let batch = warp::path!("players" / "stats:batch")
+        .and(warp::post())
+        .and(warp::body::json::<StatsBatchRequest>())
+        .map(move |request: StatsBatchRequest| {
+            if request.player_ids.is_empty() || request.player_ids.len() > stats.max_batch {
+                let error = format!("Between 1 and {} player ids are accepted", stats.max_batch);
+                return warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({ "error": error })),
+                    StatusCode::BAD_REQUEST,
+                );
+            }
+            let players = stats.lookup(request.player_ids);
+            warp::reply::with_status(warp::reply::json(&serde_json::json!({ "players": players })), StatusCode::OK)
+        });
+
+    fastest.or(batch)
+}
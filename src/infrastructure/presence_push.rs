@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request, Uri};
+use hyper_rustls::HttpsConnector;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use super::webhooks::outbound_client;
+use crate::application::{Presence, PresenceRegistry};
+use crate::config::{PresenceConfig, Secret};
+
+/// Pushes presence changes to an external service. Changes are coalesced per player
+/// and flushed at most once per interval, so bursts never exceed that request rate.
+pub struct PresencePusher {
+    url: Uri,
+    token: Option<Secret>,
+    client: Client<HttpsConnector<HttpConnector>>,
+    flush_interval: Duration,
+    max_batch_size: usize,
+}
+
+impl PresencePusher {
+    /// Returns `None` when no push URL is configured.
+    pub fn new(config: &PresenceConfig) -> Result<Option<Self>> {
+        let Some(url) = &config.push_url else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            url: url.parse().with_context(|| format!("Invalid presence push URL {}", url))?,
+            token: config.auth_token.load()?.into_iter().next(),
+            client: outbound_client(),
+            flush_interval: Duration::from_millis(config.flush_interval_ms),
+            max_batch_size: config.max_batch_size.max(1),
+        }))
+    }
+
+    pub fn spawn(self, registry: &PresenceRegistry) -> JoinHandle<()> {
+        let mut changes = registry.subscribe();
+
+        tokio::spawn(async move {
+            let mut pending: HashMap<String, Presence> = HashMap::new();
+            let mut interval = tokio::time::interval(self.flush_interval);
+
+            loop {
+                interval.tick().await;
+
+                loop {
+                    match changes.try_recv() {
+                        Ok(presence) => {
+                            pending.insert(presence.player_id.clone(), presence);
+                        }
+                        Err(TryRecvError::Lagged(skipped)) => warn!("Presence pusher lagged; {} changes dropped", skipped),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Closed) if pending.is_empty() => return,
+                        Err(TryRecvError::Closed) => break,
+                    }
+                }
+
+                if pending.is_empty() {
+                    continue;
+                }
+
+                let keys: Vec<String> = pending.keys().take(self.max_batch_size).cloned().collect();
+                let batch: Vec<Presence> = keys.iter().filter_map(|key| pending.remove(key)).collect();
+
+                if let Err(e) = self.push(&batch).await {
+                    warn!("Presence push of {} updates failed: {}", batch.len(), e);
+                    // Retry next tick unless a newer state arrived meanwhile
+                    for presence in batch {
+                        pending.entry(presence.player_id.clone()).or_insert(presence);
+                    }
+                } else {
+                    info!("Pushed {} presence updates", batch.len());
+                }
+            }
+        })
+    }
+
+    async fn push(&self, batch: &[Presence]) -> Result<()> {
+        let body = serde_json::to_vec(&serde_json::json!({ "updates": batch }))?;
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(self.url.clone())
+            .header("content-type", "application/json");
+        if let Some(token) = &self.token {
+            request = request.header("authorization", format!("Bearer {}", token.expose()));
+        }
+
+        let response = tokio::time::timeout(self.flush_interval, self.client.request(request.body(Body::from(body))?))
+            .await
+            .context("Presence push timed out")??;
+        if !response.status().is_success() {
+            anyhow::bail!("presence service returned {}", response.status());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Scheduler lag, task counts and CPU use, refreshed by [`RuntimeHealth::spawn`].
+pub static RUNTIME_HEALTH: Lazy<RuntimeHealth> = Lazy::new(RuntimeHealth::new);
+
+// Lag samples kept for the rolling maximum
+const LAG_WINDOW: usize = 120;
+
+/// Process-wide memory figures as reported by mimalloc.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct MemoryUsage {
+    pub rss_bytes: usize, // From /proc on Linux; mimalloc's estimate elsewhere
+    pub peak_rss_bytes: usize,
+    pub committed_bytes: usize, // Memory mimalloc has committed for allocations
+    pub peak_committed_bytes: usize,
+    pub page_faults: usize,
+}
+
+impl MemoryUsage {
+    pub fn sample() -> Self {
+        let mut usage = Self::default();
+        let (mut elapsed_ms, mut user_ms, mut system_ms) = (0, 0, 0);
+        mi_process_info(&mut elapsed_ms, &mut user_ms, &mut system_ms, &mut usage);
+        if let Some((rss, peak_rss)) = proc_status_rss() {
+            usage.rss_bytes = rss;
+            usage.peak_rss_bytes = peak_rss;
+        }
+        usage
+    }
+}
+
+/// Runtime saturation figures, for correlating "server feels slow" reports.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct RuntimeHealthSnapshot {
+    pub scheduler_lag_ms: f64,     // How late the canary task woke up, last sample
+    pub max_scheduler_lag_ms: f64, // Worst lag over the recent window
+    pub alive_tasks: usize,
+    pub workers: usize,
+    pub cpu_percent: f64, // Process CPU over the last sample period; 100 per fully busy core
+}
+
+pub struct RuntimeHealth {
+    lags: Mutex<VecDeque<Duration>>,
+    latest: Mutex<RuntimeHealthSnapshot>,
+}
+
+impl RuntimeHealth {
+    pub fn new() -> Self {
+        Self {
+            lags: Mutex::new(VecDeque::with_capacity(LAG_WINDOW)),
+            latest: Mutex::new(RuntimeHealthSnapshot::default()),
+        }
+    }
+
+    /// Runs a canary task that sleeps for `period` and records how late it wakes.
+    pub fn spawn(&'static self, period: Duration) {
+        tokio::spawn(async move {
+            let mut cpu_before = process_cpu_time();
+            loop {
+                let started = Instant::now();
+                tokio::time::sleep(period).await;
+                let elapsed = started.elapsed();
+
+                let cpu_after = process_cpu_time();
+                let cpu_percent =
+                    cpu_after.saturating_sub(cpu_before).as_secs_f64() / elapsed.as_secs_f64() * 100.0;
+                cpu_before = cpu_after;
+
+                self.record(elapsed.saturating_sub(period), cpu_percent);
+            }
+        });
+    }
+
+    pub fn record(&self, lag: Duration, cpu_percent: f64) {
+        let max_lag = {
+            let mut lags = self.lags.lock();
+            if lags.len() == LAG_WINDOW {
+                lags.pop_front();
+            }
+            lags.push_back(lag);
+            lags.iter().max().copied().unwrap_or_default()
+        };
+
+        let metrics = tokio::runtime::Handle::try_current().ok().map(|handle| handle.metrics());
+        *self.latest.lock() = RuntimeHealthSnapshot {
+            scheduler_lag_ms: lag.as_secs_f64() * 1000.0,
+            max_scheduler_lag_ms: max_lag.as_secs_f64() * 1000.0,
+            alive_tasks: metrics.as_ref().map_or(0, |m| m.num_alive_tasks()),
+            workers: metrics.as_ref().map_or(0, |m| m.num_workers()),
+            cpu_percent,
+        };
+    }
+
+    pub fn snapshot(&self) -> RuntimeHealthSnapshot {
+        *self.latest.lock()
+    }
+}
+
+impl Default for RuntimeHealth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// User plus system CPU time consumed by the process so far.
+pub fn process_cpu_time() -> Duration {
+    let (mut elapsed_ms, mut user_ms, mut system_ms) = (0, 0, 0);
+    mi_process_info(&mut elapsed_ms, &mut user_ms, &mut system_ms, &mut MemoryUsage::default());
+    Duration::from_millis((user_ms + system_ms) as u64)
+}
+
+fn mi_process_info(elapsed_ms: &mut usize, user_ms: &mut usize, system_ms: &mut usize, usage: &mut MemoryUsage) {
+    // SAFETY: every pointer refers to a live, writable usize for the duration of the call
+    unsafe {
+        libmimalloc_sys::mi_process_info(
+            elapsed_ms,
+            user_ms,
+            system_ms,
+            &mut usage.rss_bytes,
+            &mut usage.peak_rss_bytes,
+            &mut usage.committed_bytes,
+            &mut usage.peak_committed_bytes,
+            &mut usage.page_faults,
+        );
+    }
+}
+
+// mimalloc approximates current RSS with committed memory, so prefer the kernel's numbers
+fn proc_status_rss() -> Option<(usize, usize)> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let field = |name: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|rest| rest.trim().trim_end_matches("kB").trim().parse::<usize>().ok())
+            .map(|kib| kib * 1024)
+    };
+    Some((field("VmRSS:")?, field("VmHWM:")?))
+}
+
+/// Bytes rendered as MiB with one decimal, for log lines.
+pub fn format_mib(bytes: usize) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::application::GameManager;
+    use crate::domain::{GameConfig, Player};
+    use crate::infrastructure::{MemoryUsage, RuntimeHealth};
+
+    #[tokio::test]
+    async fn test_memory_usage_and_estimates() {
+        let memory = MemoryUsage::sample();
+        assert!(memory.rss_bytes > 0);
+        assert!(memory.peak_rss_bytes >= memory.rss_bytes);
+
+        let manager = GameManager::new(GameConfig::default());
+        let empty = manager.memory_estimate().await;
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        manager.find_match(Arc::new(Player::new("p1".to_string(), tx))).await.unwrap();
+        let queued = manager.memory_estimate().await;
+        assert!(queued.queues_bytes > empty.queues_bytes);
+
+        let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+        manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+        let playing = manager.memory_estimate().await;
+        assert!(playing.rooms_bytes > 0);
+    }
+
+    #[tokio::test]
+    async fn test_runtime_health_tracks_rolling_max_lag() {
+        let health = RuntimeHealth::new();
+        health.record(std::time::Duration::from_millis(40), 12.5);
+        health.record(std::time::Duration::from_millis(5), 30.0);
+
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.scheduler_lag_ms, 5.0);
+        assert_eq!(snapshot.max_scheduler_lag_ms, 40.0);
+        assert_eq!(snapshot.cpu_percent, 30.0);
+        assert!(snapshot.workers >= 1);
+    }
+}
@@ -0,0 +1,51 @@
+use serde::Deserialize;
+use std::sync::Arc;
+use warp::Filter;
+
+use super::admin_api::{with_role, with_audit_log};
+use super::audit_log::{AdminAction, AuditLog};
+use crate::application::ProfanityFilter;
+use crate::config::{ProfanityAction, Role, SecretStore};
+
+#[derive(Debug, Deserialize)]
+pub struct WordListUpdate {
+    pub words: Vec<String>,
+    #[serde(default)]
+    pub action: Option<ProfanityAction>, // Unchanged when omitted
+}
+
+/// `GET /admin/profanity` shows the word list and action; `PUT /admin/profanity` replaces
+/// them. Changes apply to the next name or chat line and last until the next restart,
+/// which reloads the configured list.
+pub fn create_profanity_routes(
+    filter: Arc<ProfanityFilter>,
+    audit_log: Arc<AuditLog>,
+    secrets: Arc<SecretStore>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let show_filter = filter.clone();
+    let show = warp::path!("admin" / "profanity")
+        .and(warp::get())
+        .and(with_role(secrets.clone(), Role::Viewer))
+        .map(move |_actor: String| warp::reply::json(&word_list(&show_filter)));
+
+    let update = warp::path!("admin" / "profanity")
+        .and(warp::put())
+        .and(with_role(secrets, Role::Operator))
+        .and(warp::body::json::<WordListUpdate>())
+        .and(with_audit_log(audit_log))
+        .map(move |actor: String, update: WordListUpdate, audit_log: Arc<AuditLog>| {
+            filter.set_words(update.words);
+            if let Some(action) = update.action {
+                filter.set_action(action);
+            }
+            let target = format!("{} words", filter.words().len());
+            audit_log.record_or_log(&actor, AdminAction::UpdateWordList, &target, true);
+            warp::reply::json(&word_list(&filter))
+        });
+
+    show.or(update)
+}
+
+fn word_list(filter: &ProfanityFilter) -> serde_json::Value {
+    serde_json::json!({ "action": filter.action(), "words": filter.words() })
+}
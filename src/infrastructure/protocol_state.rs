@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use super::codec::MessageCodec;
+use super::i18n::MessageKey;
+use crate::config::JsonParser;
+use crate::domain::{ClientMessage, Extensions, LatencyEstimate, PlayerPhase};
+
+/// Where a connection is in the protocol; decides which client messages it may send.
+///
+/// `Connecting` only moves on with `Connect`. The later states follow the player's
+/// standing in the `GameManager`, which matchmaking and game end change behind the
+/// connection's back, so they are refreshed before every message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConnectionState {
+    #[default]
+    Connecting,
+    Connected,
+    Queued,
+    InGame,
+    PostGame,
+}
+
+impl ConnectionState {
+    /// The typed error for a message this state doesn't accept.
+    pub fn check(self, message: &ClientMessage) -> Result<(), MessageKey> {
+        use ConnectionState::*;
+
+        match (self, message) {
+            (Connecting, ClientMessage::Connect { .. }) => Ok(()),
+            (Connecting, _) => Err(MessageKey::NotConnected),
+            (_, ClientMessage::Connect { .. }) => Err(MessageKey::AlreadyConnected),
+            (_, ClientMessage::AckNotifications { .. } | ClientMessage::RequestState) => Ok(()),
+            (Connected | PostGame, ClientMessage::FindMatch { .. } | ClientMessage::JoinRoom { .. } | ClientMessage::CreateInvite { .. }) => Ok(()),
+            (Queued, ClientMessage::FindMatch { .. } | ClientMessage::JoinRoom { .. } | ClientMessage::CreateInvite { .. }) => Err(MessageKey::AlreadyQueued),
+            (InGame, ClientMessage::FindMatch { .. } | ClientMessage::JoinRoom { .. } | ClientMessage::CreateInvite { .. }) => Err(MessageKey::AlreadyInGame),
+            (Queued, ClientMessage::BackfillResponse { .. }) => Ok(()),
+            (_, ClientMessage::BackfillResponse { .. }) => Err(MessageKey::NoBackfillOffer),
+            (InGame, _) => Ok(()),
+            (_, ClientMessage::PlayerMove { .. } | ClientMessage::PauseRequest | ClientMessage::ResumeRequest | ClientMessage::Chat { .. }) => {
+                Err(MessageKey::NotInGame)
+            }
+        }
+    }
+}
+
+impl From<PlayerPhase> for ConnectionState {
+    fn from(phase: PlayerPhase) -> Self {
+        match phase {
+            PlayerPhase::Idle => ConnectionState::Connected,
+            PlayerPhase::Queued => ConnectionState::Queued,
+            PlayerPhase::InGame => ConnectionState::InGame,
+            PlayerPhase::PostGame => ConnectionState::PostGame,
+        }
+    }
+}
+
+/// Everything a transport tracks for one client between messages.
+#[derive(Debug, Default)]
+pub struct ConnectionSession {
+    pub player_id: Option<String>,
+    pub display_name: Option<String>, // As shown to opponents; None when unset or shadow-muted
+    pub client_version: String, // Client metrics bucket
+    pub protocol_version: Option<u32>, // Declared at Connect, capped at what the server speaks
+    pub state: ConnectionState,
+    pub codec: MessageCodec, // Parse buffers reused across this connection's frames
+    pub latency: Arc<LatencyEstimate>, // Fed by keepalive pongs; shared with the players this session creates
+    pub extensions: Extensions, // Filled in at Connect; copied onto the players this session creates
+}
+
+impl ConnectionSession {
+    pub fn new(client_version: String, json_parser: JsonParser) -> Self {
+        Self {
+            client_version,
+            codec: MessageCodec::new(json_parser),
+            ..Self::default()
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+use futures_util::future::BoxFuture;
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request, Uri};
+use hyper_rustls::HttpsConnector;
+use std::time::Duration;
+
+use super::webhooks::outbound_client;
+use crate::application::{RatedGame, RatingProvider};
+use crate::config::{RatingsConfig, Secret};
+
+/// POSTs each finished game as JSON to an external rating service. Any non-2xx
+/// answer counts as a failure, so the recorder queues the game and retries it.
+pub struct HttpRatingProvider {
+    url: Uri,
+    token: Option<Secret>,
+    client: Client<HttpsConnector<HttpConnector>>,
+    request_timeout: Duration,
+}
+
+impl HttpRatingProvider {
+    /// Returns `None` when no provider URL is configured.
+    pub fn new(config: &RatingsConfig) -> Result<Option<Self>> {
+        let Some(url) = &config.provider_url else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            url: url.parse().with_context(|| format!("Invalid rating provider URL {}", url))?,
+            token: config.auth_token.load()?.into_iter().next(),
+            client: outbound_client(),
+            request_timeout: Duration::from_millis(config.request_timeout_ms),
+        }))
+    }
+
+    async fn post(&self, game: &RatedGame) -> Result<()> {
+        let body = serde_json::to_vec(game)?;
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri(self.url.clone())
+            .header("content-type", "application/json");
+        if let Some(token) = &self.token {
+            request = request.header("authorization", format!("Bearer {}", token.expose()));
+        }
+
+        let response = tokio::time::timeout(self.request_timeout, self.client.request(request.body(Body::from(body))?))
+            .await
+            .context("Rating request timed out")??;
+        if !response.status().is_success() {
+            anyhow::bail!("rating service returned {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+impl RatingProvider for HttpRatingProvider {
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    fn record_game<'a>(&'a self, game: &'a RatedGame) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.post(game))
+    }
+}
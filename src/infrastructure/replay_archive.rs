@@ -0,0 +1,323 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use super::object_store::ObjectStore;
+use crate::application::{GameArchive, GameHistory, GameRecord};
+use crate::config::ReplayArchiveConfig;
+
+const INDEX_KEY: &str = "replays/index.json";
+const MAX_PENDING_BATCHES: usize = 20; // Past this many unsent batches, the oldest games are dropped
+
+type Batch = Arc<HashMap<String, GameRecord>>;
+
+/// Moves finished games out of the in-memory [`GameHistory`] into gzipped JSON Lines
+/// batches in a bucket, with one index object mapping game ids to batches.
+pub struct ReplayArchive {
+    store: ObjectStore,
+    archive_after: chrono::Duration,
+    batch_size: usize,
+    sweep_interval: Duration,
+    index: RwLock<HashMap<String, String>>, // gameId -> batch key
+    pending: Mutex<Vec<GameRecord>>,        // Taken from the history, not yet uploaded
+    recent: Mutex<Option<(String, Batch)>>, // Last batch fetched; replays are often browsed together
+    index_dirty: AtomicBool,                // Batches were indexed but the index upload failed
+}
+
+impl ReplayArchive {
+    /// Loads the index from the bucket, or starts an empty one.
+    pub async fn open(config: &ReplayArchiveConfig) -> Result<Self> {
+        let store = ObjectStore::new(&config.object_store)?;
+        let index = match store.get(INDEX_KEY).await? {
+            Some(bytes) => serde_json::from_slice(&bytes).context("Corrupt replay archive index")?,
+            None => HashMap::new(),
+        };
+        Ok(Self {
+            store,
+            archive_after: chrono::Duration::days(config.archive_after_days.into()),
+            batch_size: config.batch_size.max(1),
+            sweep_interval: Duration::from_millis(config.sweep_interval_ms),
+            index: RwLock::new(index),
+            pending: Mutex::new(Vec::new()),
+            recent: Mutex::new(None),
+            index_dirty: AtomicBool::new(false),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes aged-out and evicted games from `history` and uploads them. Returns the
+    /// number of games archived; on failure they stay pending for the next sweep.
+    pub async fn sweep(&self, history: &GameHistory) -> Result<usize> {
+        let cutoff = Utc::now() - self.archive_after;
+        let batches: Vec<Vec<GameRecord>> = {
+            let mut pending = self.pending.lock();
+            pending.extend(history.take_archivable(cutoff));
+            let limit = self.batch_size * MAX_PENDING_BATCHES;
+            if pending.len() > limit {
+                let dropped = pending.len() - limit;
+                warn!("Replay archive backlog full; dropping {} oldest games", dropped);
+                pending.drain(..dropped);
+            }
+            pending.chunks(self.batch_size).map(<[GameRecord]>::to_vec).collect()
+        };
+
+        let mut archived = 0;
+        let mut result = Ok(());
+        for batch in batches {
+            if let Err(e) = self.upload(&batch).await {
+                result = Err(e);
+                break;
+            }
+            archived += batch.len();
+        }
+
+        // Batches uploaded before a failure are still indexed
+        if archived > 0 || self.index_dirty.load(Ordering::Relaxed) {
+            self.index_dirty.store(true, Ordering::Relaxed);
+            let index = serde_json::to_vec(&*self.index.read())?;
+            self.store.put(INDEX_KEY, index, "application/json").await?;
+            self.index_dirty.store(false, Ordering::Relaxed);
+        }
+        result.map(|_| archived)
+    }
+
+    async fn upload(&self, batch: &[GameRecord]) -> Result<()> {
+        let key = format!("replays/{}-{}.jsonl.gz", Utc::now().format("%Y%m%dT%H%M%S%.3fZ"), uuid::Uuid::new_v4().simple());
+        self.store.put(&key, compress(batch)?, "application/gzip").await?;
+
+        let ids: Vec<_> = batch.iter().map(|record| record.game_id.clone()).collect();
+        {
+            let mut index = self.index.write();
+            for id in &ids {
+                index.insert(id.clone(), key.clone());
+            }
+        }
+        self.pending.lock().retain(|record| !ids.contains(&record.game_id));
+        Ok(())
+    }
+
+    pub fn spawn(self: Arc<Self>, history: Arc<GameHistory>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.sweep_interval);
+            loop {
+                interval.tick().await;
+                match self.sweep(&history).await {
+                    Ok(0) => {}
+                    Ok(archived) => info!("Archived {} games to bucket {}", archived, self.store.bucket()),
+                    Err(e) => error!("Replay archive sweep failed: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Every archived game the player was in, pending or uploaded. Reads each batch
+    /// in the index, so this is for rare requests like a data export.
+    pub async fn games_of(&self, player_id: &str) -> Result<Vec<GameRecord>> {
+        let mut games: Vec<GameRecord> = self.pending.lock().iter().filter(|record| record.involves(player_id)).cloned().collect();
+        for key in self.batch_keys() {
+            let bytes = self.store.get(&key).await?.with_context(|| format!("Archived batch {} is missing", key))?;
+            games.extend(decompress(&bytes)?.into_iter().filter(|record| record.involves(player_id)));
+        }
+        Ok(games)
+    }
+
+    /// Replaces the player's id with `alias` in every archived game, re-uploading the
+    /// batches that mention them under the same keys. Returns how many games changed.
+    pub async fn anonymize(&self, player_id: &str, alias: &str) -> Result<usize> {
+        let mut count = self.pending.lock().iter_mut().map(|record| record.anonymize(player_id, alias)).filter(|changed| *changed).count();
+        for key in self.batch_keys() {
+            let bytes = self.store.get(&key).await?.with_context(|| format!("Archived batch {} is missing", key))?;
+            let mut batch = decompress(&bytes)?;
+            let changed = batch.iter_mut().map(|record| record.anonymize(player_id, alias)).filter(|changed| *changed).count();
+            if changed > 0 {
+                self.store.put(&key, compress(&batch)?, "application/gzip").await?;
+                count += changed;
+            }
+        }
+        *self.recent.lock() = None;
+        Ok(count)
+    }
+
+    fn batch_keys(&self) -> Vec<String> {
+        let keys: std::collections::BTreeSet<String> = self.index.read().values().cloned().collect();
+        keys.into_iter().collect()
+    }
+
+    async fn lookup(&self, game_id: &str) -> Result<Option<GameRecord>> {
+        if let Some(record) = self.pending.lock().iter().find(|record| record.game_id == game_id) {
+            return Ok(Some(record.clone()));
+        }
+        let Some(key) = self.index.read().get(game_id).cloned() else {
+            return Ok(None);
+        };
+
+        let cached = self.recent.lock().as_ref().filter(|(recent, _)| *recent == key).map(|(_, batch)| batch.clone());
+        let batch = match cached {
+            Some(batch) => batch,
+            None => {
+                let bytes = self.store.get(&key).await?.with_context(|| format!("Archived batch {} is missing", key))?;
+                let batch: Batch = Arc::new(decompress(&bytes)?.into_iter().map(|record| (record.game_id.clone(), record)).collect());
+                *self.recent.lock() = Some((key, batch.clone()));
+                batch
+            }
+        };
+        Ok(batch.get(game_id).cloned())
+    }
+}
+
+impl GameArchive for ReplayArchive {
+    fn fetch<'a>(&'a self, game_id: &'a str) -> BoxFuture<'a, Result<Option<GameRecord>>> {
+        self.lookup(game_id).boxed()
+    }
+}
+
+fn compress(records: &[GameRecord]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for record in records {
+        serde_json::to_writer(&mut encoder, record)?;
+        encoder.write_all(b"\n")?;
+    }
+    Ok(encoder.finish()?)
+}
+
+fn decompress(bytes: &[u8]) -> Result<Vec<GameRecord>> {
+    BufReader::new(GzDecoder::new(bytes))
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use warp::Filter;
+
+    use crate::application::GameManager;
+    use crate::config::{GameHistoryConfig, SecretSource};
+    use crate::domain::{GameConfig, GameEndReason};
+    use crate::infrastructure::create_game_routes;
+
+    #[tokio::test]
+    async fn test_replay_archive_moves_old_games_to_a_bucket() {
+        use crate::application::GameRecord;
+        use crate::config::{ObjectStoreConfig, ReplayArchiveConfig};
+        use crate::domain::{GameMode, GameStatus};
+        use crate::infrastructure::ReplayArchive;
+
+        // An in-memory stand-in for an S3 bucket
+        let objects = Arc::new(parking_lot::Mutex::new(std::collections::HashMap::<String, Vec<u8>>::new()));
+        let bucket = {
+            let objects = objects.clone();
+            warp::method()
+                .and(warp::path::full())
+                .and(warp::header::<String>("authorization"))
+                .and(warp::body::bytes())
+                .map(move |method: warp::http::Method, path: warp::path::FullPath, auth: String, body: bytes::Bytes| {
+                    assert!(auth.starts_with("AWS4-HMAC-SHA256 Credential=test-key/"));
+                    let mut objects = objects.lock();
+                    match method {
+                        warp::http::Method::PUT => {
+                            objects.insert(path.as_str().to_string(), body.to_vec());
+                            warp::http::Response::builder().status(200).body(Vec::new())
+                        }
+                        _ => match objects.get(path.as_str()) {
+                            Some(object) => warp::http::Response::builder().status(200).body(object.clone()),
+                            None => warp::http::Response::builder().status(404).body(Vec::new()),
+                        },
+                    }
+                })
+        };
+        let (addr, server) = warp::serve(bucket).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        std::env::set_var("RPS_TEST_ARCHIVE_KEY", "test-key");
+        std::env::set_var("RPS_TEST_ARCHIVE_SECRET", "test-secret");
+        let source = |var: &str| SecretSource { env: Some(var.to_string()), file: None };
+        let config = ReplayArchiveConfig {
+            object_store: ObjectStoreConfig {
+                endpoint: format!("http://{}", addr),
+                bucket: "games".to_string(),
+                region: "us-east-1".to_string(),
+                prefix: "node-a".to_string(),
+                access_key_id: source("RPS_TEST_ARCHIVE_KEY"),
+                secret_access_key: source("RPS_TEST_ARCHIVE_SECRET"),
+                request_timeout_ms: 5000,
+            },
+            archive_after_days: 1,
+            batch_size: 1,
+            sweep_interval_ms: 60_000,
+        };
+        let archive = Arc::new(ReplayArchive::open(&config).await.unwrap());
+        let manager = Arc::new(
+            GameManager::new(GameConfig::default())
+                .with_game_history(GameHistoryConfig { max_finished_games: 3, ..GameHistoryConfig::default() })
+                .with_game_archive(archive.clone()),
+        );
+
+        let record = |game_id: &str, days_ago: i64| {
+            let ended_at = chrono::Utc::now() - chrono::Duration::days(days_ago);
+            GameRecord {
+                game_id: game_id.to_string(),
+                room_id: "room".to_string(),
+                mode: GameMode::Solo,
+                status: GameStatus::Finished,
+                players: vec!["p1".to_string(), "p2".to_string()],
+                teams: Default::default(),
+                winner: Some("p1".to_string()),
+                scores: Default::default(),
+                reason: Some(GameEndReason::Completed),
+                rounds: Vec::new(),
+                created_at: ended_at - chrono::Duration::minutes(1),
+                ended_at: Some(ended_at),
+            }
+        };
+        // g1 is evicted for capacity, g2 has aged out, g3 and g4 stay in memory
+        let history = manager.game_history();
+        for (game_id, days_ago) in [("g1", 3), ("g2", 2), ("g3", 0), ("g4", 0)] {
+            history.record(record(game_id, days_ago));
+        }
+        assert_eq!(archive.sweep(history).await.unwrap(), 2);
+        assert_eq!(history.len(), 2);
+        assert_eq!(archive.sweep(history).await.unwrap(), 0);
+
+        let keys: Vec<_> = objects.lock().keys().cloned().collect();
+        assert_eq!(keys.len(), 3);
+        assert!(keys.iter().all(|key| key.starts_with("/games/node-a/replays/")));
+        let batch = keys.iter().find(|key| key.ends_with(".jsonl.gz")).unwrap();
+        assert_eq!(objects.lock()[batch][..2], [0x1f, 0x8b]);
+
+        let routes = create_game_routes(manager.clone());
+        for game_id in ["g1", "g2", "g3"] {
+            let response = warp::test::request().path(&format!("/games/{}/replay", game_id)).reply(&routes).await;
+            assert_eq!(response.status(), 200, "{}", game_id);
+            let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(body["game_id"], game_id);
+        }
+        let missing = warp::test::request().path("/games/g9").reply(&routes).await;
+        assert_eq!(missing.status(), 404);
+
+        // The index survives a restart
+        let reopened = ReplayArchive::open(&config).await.unwrap();
+        assert_eq!(reopened.len(), 2);
+    }
+}
@@ -0,0 +1,116 @@
+use std::convert::Infallible;
+use tracing::{info, warn};
+use warp::http::header::RETRY_AFTER;
+use warp::http::{HeaderValue, StatusCode};
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+use super::admin_api::{Forbidden, Unauthorized};
+use super::rest_rate_limit::RateLimited;
+use super::rest_server::BodyTooLarge;
+use super::websocket::new_correlation_id;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// Marks a response produced from a rejection so its body can be rebuilt with the request id
+#[derive(Clone)]
+struct RejectionInfo {
+    message: &'static str,
+}
+
+/// Wraps REST routes so every response carries an `X-Request-Id` (client-supplied or
+/// generated), error bodies include it, and every request is logged with it.
+pub fn with_request_id<F, R>(routes: F) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    let routes = routes
+        .map(|reply: R| reply.into_response())
+        .recover(|rejection: Rejection| async move { Ok::<_, Infallible>(rejection_response(rejection)) })
+        .unify();
+
+    warp::header::optional::<String>(REQUEST_ID_HEADER)
+        .map(|id: Option<String>| id.filter(|id| is_valid_request_id(id)).unwrap_or_else(new_correlation_id))
+        .and(warp::method())
+        .and(warp::path::full())
+        .and(routes)
+        .map(|request_id: String, method: warp::http::Method, path: warp::path::FullPath, mut response: Response| {
+            if let Some(info) = response.extensions().get::<RejectionInfo>().cloned() {
+                let status = response.status();
+                let headers = std::mem::take(response.headers_mut()); // e.g. Retry-After
+                let body = serde_json::json!({ "error": info.message, "requestId": request_id });
+                response = warp::reply::with_status(warp::reply::json(&body), status).into_response();
+                response.headers_mut().extend(headers);
+            }
+
+            if let Ok(value) = HeaderValue::from_str(&request_id) {
+                response.headers_mut().insert(REQUEST_ID_HEADER, value);
+            }
+
+            let status = response.status();
+            if status.is_client_error() || status.is_server_error() {
+                warn!(request_id = %request_id, %method, path = path.as_str(), status = status.as_u16(), "REST request failed");
+            } else {
+                info!(request_id = %request_id, %method, path = path.as_str(), status = status.as_u16(), "REST request");
+            }
+            response
+        })
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+fn rejection_response(rejection: Rejection) -> Response {
+    let retry_after = rejection.find::<RateLimited>().map(RateLimited::retry_after_secs);
+    let (status, message) = if rejection.is_not_found() {
+        (StatusCode::NOT_FOUND, "not found")
+    } else if rejection.find::<Unauthorized>().is_some() {
+        (StatusCode::UNAUTHORIZED, "unauthorized")
+    } else if rejection.find::<Forbidden>().is_some() {
+        (StatusCode::FORBIDDEN, "forbidden")
+    } else if retry_after.is_some() {
+        (StatusCode::TOO_MANY_REQUESTS, "too many requests")
+    } else if rejection.find::<warp::reject::MethodNotAllowed>().is_some() {
+        (StatusCode::METHOD_NOT_ALLOWED, "method not allowed")
+    } else if rejection.find::<warp::reject::PayloadTooLarge>().is_some() || rejection.find::<BodyTooLarge>().is_some() {
+        (StatusCode::PAYLOAD_TOO_LARGE, "payload too large")
+    } else if rejection.find::<warp::reject::InvalidQuery>().is_some() {
+        (StatusCode::BAD_REQUEST, "invalid query")
+    } else if rejection.find::<warp::reject::InvalidHeader>().is_some()
+        || rejection.find::<warp::reject::MissingHeader>().is_some()
+    {
+        (StatusCode::BAD_REQUEST, "invalid header")
+    } else {
+        (StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
+    };
+
+    let mut response = warp::reply::with_status(warp::reply(), status).into_response();
+    response.extensions_mut().insert(RejectionInfo { message });
+    if let Some(seconds) = retry_after {
+        response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(seconds));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use warp::Filter;
+
+    use crate::infrastructure::with_request_id;
+
+    #[tokio::test]
+    async fn test_request_id_on_success_and_error_responses() {
+        let routes = with_request_id(warp::path("ping").map(|| "pong"));
+
+        let ok = warp::test::request().path("/ping").header("x-request-id", "client-id-1").reply(&routes).await;
+        assert_eq!(ok.headers()["x-request-id"], "client-id-1");
+
+        let missing = warp::test::request().path("/nope").reply(&routes).await;
+        assert_eq!(missing.status(), 404);
+        let header_id = missing.headers()["x-request-id"].to_str().unwrap().to_string();
+        let body: serde_json::Value = serde_json::from_slice(missing.body()).unwrap();
+        assert_eq!(body["requestId"], header_id);
+    }
+}
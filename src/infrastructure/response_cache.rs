@@ -0,0 +1,120 @@
+use bytes::Bytes;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::error;
+use warp::http::header::{HeaderValue, CONTENT_TYPE};
+use warp::http::StatusCode;
+use warp::reply::Response;
+use warp::{Filter, Reply};
+
+use crate::config::SecretStore;
+
+type Slot = Arc<tokio::sync::Mutex<Option<(Instant, Bytes)>>>;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct ResponseCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub bypasses: u64, // Admin requests that asked for a fresh copy
+}
+
+/// Serialized bodies of hot read-only endpoints, each reused for `ttl` so a crowd of
+/// dashboard pollers costs one computation per endpoint per `ttl`. A zero `ttl` caches nothing.
+pub struct ResponseCache {
+    ttl: Duration,
+    slots: Mutex<HashMap<String, Slot>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    bypasses: AtomicU64,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            slots: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            bypasses: AtomicU64::new(0),
+        }
+    }
+
+    /// What `compute` returns, as a JSON response; a copy younger than the TTL is served
+    /// instead when there is one. Concurrent misses on a key wait for one computation.
+    /// `bypass` always recomputes, and the result replaces the cached copy.
+    pub async fn json<T, F, Fut>(&self, key: &str, bypass: bool, compute: F) -> Response
+    where
+        T: Serialize,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        if self.ttl.is_zero() {
+            return match serde_json::to_vec(&compute().await) {
+                Ok(body) => json_response(Bytes::from(body)),
+                Err(e) => serialize_failed(key, e),
+            };
+        }
+
+        let slot = self.slots.lock().entry(key.to_string()).or_default().clone();
+        let mut slot = slot.lock().await;
+        if let Some((cached_at, body)) = slot.as_ref().filter(|_| !bypass) {
+            if cached_at.elapsed() < self.ttl {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return json_response(body.clone());
+            }
+        }
+
+        let counter = if bypass { &self.bypasses } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        match serde_json::to_vec(&compute().await) {
+            Ok(body) => {
+                let body = Bytes::from(body);
+                *slot = Some((Instant::now(), body.clone()));
+                json_response(body)
+            }
+            Err(e) => serialize_failed(key, e),
+        }
+    }
+
+    pub fn stats(&self) -> ResponseCacheStats {
+        ResponseCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            bypasses: self.bypasses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn json_response(body: Bytes) -> Response {
+    let mut response = Response::new(body.into());
+    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+fn serialize_failed(key: &str, e: serde_json::Error) -> Response {
+    error!("Failed to serialize cached response {}: {}", key, e);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FreshQuery {
+    #[serde(default)]
+    fresh: bool,
+}
+
+/// Whether the request skips response caches: `?fresh=true` with an admin API key.
+/// Without a valid key the flag is ignored, so pollers can't use it to defeat the cache.
+pub fn with_cache_bypass(secrets: Arc<SecretStore>) -> impl Filter<Extract = (bool,), Error = warp::Rejection> + Clone {
+    warp::query::<FreshQuery>()
+        .and(warp::header::optional::<String>("x-api-key"))
+        .and(warp::header::optional::<String>("authorization"))
+        .map(move |query: FreshQuery, api_key: Option<String>, authorization: Option<String>| {
+            let presented = api_key.or_else(|| authorization.and_then(|value| value.strip_prefix("Bearer ").map(str::to_string)));
+            query.fresh && presented.is_some_and(|key| secrets.verify_admin_key(&key).is_some())
+        })
+}
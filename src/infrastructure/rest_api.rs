@@ -3,7 +3,8 @@ use serde::Serialize;
 use std::sync::Arc;
 use warp::Filter;
 
-use crate::application::GameManager;
+use crate::application::{GameManager, GameRecord, ResultSigner};
+use crate::domain::RoundSummary;
 
 #[derive(Serialize)]
 pub struct HealthResponse {
@@ -20,6 +21,20 @@ pub struct StatsResponse {
     pub waiting_players: usize,
 }
 
+#[derive(Serialize)]
+pub struct GameResponse {
+    #[serde(flatten)]
+    pub game: GameRecord,
+    pub replay: String, // Path of the round-by-round replay
+}
+
+#[derive(Serialize)]
+pub struct ReplayResponse {
+    pub game_id: String,
+    pub players: Vec<String>,
+    pub rounds: Vec<RoundSummary>,
+}
+
 pub fn create_routes(
     game_manager: Arc<GameManager>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -30,10 +45,52 @@ pub fn create_routes(
 
     let stats = warp::path("stats")
         .and(warp::get())
-        .and(with_game_manager(game_manager))
+        .and(with_game_manager(game_manager.clone()))
         .and_then(stats_handler);
 
-    health.or(stats)
+    health
+        .or(stats)
+        .or(create_room_routes(game_manager.clone()))
+        .or(create_game_routes(game_manager))
+}
+
+/// `GET /rooms/{id}`: public state of a single room.
+pub fn create_room_routes(
+    game_manager: Arc<GameManager>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("rooms" / String)
+        .and(warp::get())
+        .and(with_game_manager(game_manager))
+        .and_then(room_handler)
+}
+
+/// `GET /games/{id}`: outcome and players of a live or recently finished game.
+/// `GET /games/{id}/replay`: its rounds in order.
+pub fn create_game_routes(
+    game_manager: Arc<GameManager>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let game = warp::path!("games" / String)
+        .and(warp::get())
+        .and(with_game_manager(game_manager.clone()))
+        .and_then(game_handler);
+
+    let replay = warp::path!("games" / String / "replay")
+        .and(warp::get())
+        .and(with_game_manager(game_manager))
+        .and_then(replay_handler);
+
+    game.or(replay)
+}
+
+/// `GET /results/keys`: public keys for checking signed round and game results.
+/// Empty when results aren't signed.
+pub fn create_result_key_routes(
+    signer: Option<Arc<ResultSigner>>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("results" / "keys").and(warp::get()).map(move || {
+        let keys = signer.as_ref().map(|signer| signer.public_keys()).unwrap_or_default();
+        warp::reply::json(&serde_json::json!({ "keys": keys }))
+    })
 }
 
 fn with_game_manager(
@@ -67,4 +124,32 @@ async fn stats_handler(game_manager: Arc<GameManager>) -> Result<impl warp::Repl
     };
 
     Ok(warp::reply::json(&response))
-}
\ No newline at end of file
+}
+
+async fn room_handler(room_id: String, game_manager: Arc<GameManager>) -> Result<impl warp::Reply, warp::Rejection> {
+    match game_manager.room_snapshot(&room_id).await {
+        Some(snapshot) => Ok(warp::reply::json(&snapshot)),
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+async fn game_handler(game_id: String, game_manager: Arc<GameManager>) -> Result<impl warp::Reply, warp::Rejection> {
+    match game_manager.game_record(&game_id).await {
+        Some(game) => Ok(warp::reply::json(&GameResponse {
+            replay: format!("/games/{}/replay", game.game_id),
+            game,
+        })),
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+async fn replay_handler(game_id: String, game_manager: Arc<GameManager>) -> Result<impl warp::Reply, warp::Rejection> {
+    match game_manager.game_record(&game_id).await {
+        Some(game) => Ok(warp::reply::json(&ReplayResponse {
+            game_id: game.game_id,
+            players: game.players,
+            rounds: game.rounds,
+        })),
+        None => Err(warp::reject::not_found()),
+    }
+}
@@ -0,0 +1,198 @@
+use serde::Serialize;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use warp::path::FullPath;
+use warp::{Filter, Rejection};
+
+use super::rest_server::ClientAddr;
+use crate::application::RateLimiter;
+use crate::config::{RestRateLimitConfig, SecretStore};
+
+/// A client over its REST request limit; answered with 429 and `Retry-After`.
+#[derive(Debug)]
+pub struct RateLimited {
+    pub retry_after: Duration,
+}
+
+impl warp::reject::Reject for RateLimited {}
+
+impl RateLimited {
+    /// Whole seconds, rounded up, for the `Retry-After` header.
+    pub fn retry_after_secs(&self) -> u64 {
+        self.retry_after.as_millis().div_ceil(1000).max(1) as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct RestRateLimitStats {
+    pub limited: u64,
+    pub limited_expensive: u64, // Refused by the tighter limit on expensive paths
+}
+
+/// Per-client REST limits: one across every route and a tighter one across the paths that
+/// walk stats, leaderboards or replays. Uses the same sliding window as the spam guard.
+pub struct RestRateLimiter {
+    config: RestRateLimitConfig,
+    secrets: Arc<SecretStore>,
+    all: RateLimiter<String>,
+    expensive: RateLimiter<String>,
+    limited: AtomicU64,
+    limited_expensive: AtomicU64,
+}
+
+impl RestRateLimiter {
+    pub fn new(config: RestRateLimitConfig, secrets: Arc<SecretStore>) -> Self {
+        let window = Duration::from_millis(config.window_ms);
+        Self {
+            all: RateLimiter::new(config.max_requests, window),
+            expensive: RateLimiter::new(config.max_expensive_requests, window),
+            config,
+            secrets,
+            limited: AtomicU64::new(0),
+            limited_expensive: AtomicU64::new(0),
+        }
+    }
+
+    /// Counts one request to `path` by `client`, or refuses it.
+    pub fn check(&self, client: &str, path: &str) -> Result<(), RateLimited> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        if self.config.expensive_paths.iter().any(|prefix| path.starts_with(prefix.as_str())) {
+            if let Err(retry_after) = self.expensive.check(client.to_string()) {
+                self.limited_expensive.fetch_add(1, Ordering::Relaxed);
+                return Err(RateLimited { retry_after });
+            }
+        }
+        self.all.check(client.to_string()).map_err(|retry_after| {
+            self.limited.fetch_add(1, Ordering::Relaxed);
+            RateLimited { retry_after }
+        })
+    }
+
+    /// Who a request counts against: its API key's id if it presents a valid one, else its
+    /// IP address. None when neither is known, and such requests aren't limited.
+    fn client(&self, api_key: Option<&str>, forwarded_for: Option<&str>, peer: Option<ClientAddr>) -> Option<String> {
+        if let Some((key_id, _)) = api_key.and_then(|key| self.secrets.verify_api_key(key)) {
+            return Some(format!("key:{}", key_id));
+        }
+        // Entries left of the one our own proxies appended are whatever the client sent
+        let forwarded = forwarded_for
+            .filter(|_| self.config.trust_forwarded_for)
+            .and_then(|value| value.rsplit(',').nth(self.config.trusted_proxy_hops.max(1) - 1))
+            .and_then(|ip| ip.trim().parse::<IpAddr>().ok());
+        forwarded.or(peer.map(|ClientAddr(addr)| addr.ip())).map(|ip| format!("ip:{}", ip))
+    }
+
+    /// Forgets clients with no requests left in the window.
+    pub fn prune(&self) -> usize {
+        self.all.prune() + self.expensive.prune()
+    }
+
+    pub fn stats(&self) -> RestRateLimitStats {
+        RestRateLimitStats {
+            limited: self.limited.load(Ordering::Relaxed),
+            limited_expensive: self.limited_expensive.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Refuses requests from clients over their limit before any route runs.
+pub fn with_rate_limit(limiter: Arc<RestRateLimiter>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::path::full()
+        .and(warp::header::optional::<String>("x-api-key"))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .and(warp::ext::optional::<ClientAddr>())
+        .and_then(
+            move |path: FullPath, api_key: Option<String>, authorization: Option<String>, forwarded_for: Option<String>, peer: Option<ClientAddr>| {
+                let limiter = limiter.clone();
+                async move {
+                    let presented = api_key.or_else(|| authorization.and_then(|value| value.strip_prefix("Bearer ").map(str::to_string)));
+                    match limiter.client(presented.as_deref(), forwarded_for.as_deref(), peer) {
+                        Some(client) => limiter.check(&client, path.as_str()).map_err(warp::reject::custom),
+                        None => Ok(()),
+                    }
+                }
+            },
+        )
+        .untuple_one()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Secret;
+    use crate::infrastructure::with_request_id;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    async fn test_spoofed_forwarded_for_prefixes_are_still_limited() {
+        let config = RestRateLimitConfig {
+            max_requests: 2,
+            trust_forwarded_for: true,
+            ..RestRateLimitConfig::default()
+        };
+        let limiter = Arc::new(RestRateLimiter::new(config, Arc::new(SecretStore::from_keys(vec![], vec![]))));
+        let routes = with_request_id(with_rate_limit(limiter.clone()).map(|| "ok"));
+        let proxy = ClientAddr(std::net::SocketAddr::from(([10, 0, 0, 9], 40000)));
+        let status = |forwarded_for: String| {
+            let request = warp::test::request().path("/health").header("x-forwarded-for", forwarded_for).extension(proxy);
+            let routes = routes.clone();
+            async move { request.reply(&routes).await.status() }
+        };
+
+        // The proxy appends the address it saw; anything in front of it is the client's own text
+        for spoofed in ["1.1.1.1", "2.2.2.2"] {
+            assert_eq!(status(format!("{}, 203.0.113.7", spoofed)).await, StatusCode::OK);
+        }
+        assert_eq!(status("3.3.3.3, 203.0.113.7".to_string()).await, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(status("203.0.113.8".to_string()).await, StatusCode::OK);
+
+        // Behind two proxies the client is the second entry from the right
+        let config = RestRateLimitConfig {
+            trust_forwarded_for: true,
+            trusted_proxy_hops: 2,
+            ..RestRateLimitConfig::default()
+        };
+        let limiter = RestRateLimiter::new(config, Arc::new(SecretStore::from_keys(vec![], vec![])));
+        assert_eq!(limiter.client(None, Some("1.1.1.1, 203.0.113.7, 10.0.0.8"), Some(proxy)).as_deref(), Some("ip:203.0.113.7"));
+        // A chain shorter than the proxies in front didn't come through them; the peer counts
+        assert_eq!(limiter.client(None, Some("203.0.113.7"), Some(proxy)).as_deref(), Some("ip:10.0.0.9"));
+    }
+
+    #[tokio::test]
+    async fn test_rest_clients_over_their_limit_get_429_with_retry_after() {
+        use crate::config::RestRateLimitConfig;
+        use crate::infrastructure::{with_rate_limit, ClientAddr, RestRateLimiter};
+
+        let config = RestRateLimitConfig {
+            max_requests: 3,
+            max_expensive_requests: 1,
+            expensive_paths: vec!["/stats".to_string()],
+            ..RestRateLimitConfig::default()
+        };
+        let secrets = Arc::new(SecretStore::from_keys(vec![Secret::new("ops", "admin-key")], vec![]));
+        let limiter = Arc::new(RestRateLimiter::new(config, secrets));
+        let routes = with_request_id(with_rate_limit(limiter.clone()).and(warp::path::full()).map(|_path: warp::path::FullPath| "ok"));
+        let from = |ip: [u8; 4], path: &str| warp::test::request().path(path).extension(ClientAddr(std::net::SocketAddr::from((ip, 40000))));
+
+        assert_eq!(from([10, 0, 0, 1], "/stats").reply(&routes).await.status(), 200);
+        let limited = from([10, 0, 0, 1], "/stats").reply(&routes).await;
+        assert_eq!(limited.status(), 429);
+        let retry_after: u64 = limited.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+        assert!((1..=60).contains(&retry_after));
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(limited.body()).unwrap()["error"], "too many requests");
+
+        // Cheap routes have their own, looser budget; other addresses and API keys count separately
+        assert_eq!(from([10, 0, 0, 1], "/health").reply(&routes).await.status(), 200);
+        assert_eq!(from([10, 0, 0, 1], "/health").reply(&routes).await.status(), 200);
+        assert_eq!(from([10, 0, 0, 1], "/health").reply(&routes).await.status(), 429);
+        assert_eq!(from([10, 0, 0, 2], "/stats").reply(&routes).await.status(), 200);
+        assert_eq!(from([10, 0, 0, 1], "/stats").header("x-api-key", "admin-key").reply(&routes).await.status(), 200);
+        assert_eq!(limiter.stats().limited_expensive, 1);
+        assert_eq!(limiter.stats().limited, 1);
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use hyper::server::conn::{AddrIncoming, AddrStream, Http};
+use hyper::service::{make_service_fn, service_fn, Service};
+use hyper::{Body, Request};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+use crate::config::RestApiConfig;
+
+/// A request whose declared body is bigger than `RestApiConfig::max_body_bytes`.
+#[derive(Debug)]
+pub struct BodyTooLarge;
+
+impl warp::reject::Reject for BodyTooLarge {}
+
+/// The peer a REST request arrived from, attached to the request for filters that need it
+/// (`warp::ext`), since routes served through `warp::service` don't see the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientAddr(pub SocketAddr);
+
+/// `routes` as a hyper service for one connection from `peer`.
+pub fn routes_service<F>(
+    routes: F,
+    peer: SocketAddr,
+) -> impl Service<Request<Body>, Response = Response, Error = Infallible, Future = impl Future<Output = Result<Response, Infallible>> + Send> + Clone + Send
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    F::Extract: Reply,
+{
+    let service = warp::service(routes);
+    service_fn(move |mut request: Request<Body>| {
+        request.extensions_mut().insert(ClientAddr(peer));
+        service.clone().call(request)
+    })
+}
+
+/// Connection options from `config`, for the REST port and for listeners sharing theirs.
+pub fn http_options(config: &RestApiConfig) -> Http {
+    let mut http = Http::new();
+    http.http1_only(!config.http2)
+        .http1_keep_alive(config.keep_alive_timeout_ms > 0)
+        .http2_max_concurrent_streams(config.max_concurrent_streams);
+    // Also runs while a kept-alive connection waits for its next request
+    if config.keep_alive_timeout_ms > 0 {
+        http.http1_header_read_timeout(Duration::from_millis(config.keep_alive_timeout_ms));
+    }
+    http
+}
+
+/// Refuses requests declaring a body over `max_bytes` before any route reads it.
+pub fn body_limit(max_bytes: u64) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<u64>("content-length")
+        .and_then(move |length: Option<u64>| async move {
+            match length {
+                Some(length) if length > max_bytes => Err(warp::reject::custom(BodyTooLarge)),
+                _ => Ok(()),
+            }
+        })
+        .untuple_one()
+}
+
+/// Binds the REST port from `config`; port 0 picks a free one, read back with `local_addr`.
+pub fn bind_rest(config: &RestApiConfig) -> Result<AddrIncoming> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+    let mut incoming = AddrIncoming::bind(&addr).with_context(|| format!("Failed to bind REST API on {}", addr))?;
+    incoming.set_nodelay(true);
+    Ok(incoming)
+}
+
+/// Serves `routes` on the port `bind_rest` bound, with the tuning from `config`, until the server fails.
+pub async fn serve_rest<F>(routes: F, incoming: AddrIncoming, config: &RestApiConfig) -> Result<()>
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    F::Extract: Reply,
+{
+    let make_service = make_service_fn(move |connection: &AddrStream| {
+        let service = routes_service(routes.clone(), connection.remote_addr());
+        async move { Ok::<_, Infallible>(service) }
+    });
+    hyper::server::Builder::new(incoming, http_options(config))
+        .serve(make_service)
+        .await
+        .context("REST API server failed")
+}
+
+#[cfg(test)]
+mod tests {
+    use warp::Filter;
+
+    use crate::infrastructure::{body_limit, with_request_id};
+
+    #[tokio::test]
+    async fn test_oversized_request_bodies_are_refused_before_routing() {
+        let routes = with_request_id(body_limit(16).and(warp::path("echo")).and(warp::body::bytes()).map(|body: bytes::Bytes| body.len().to_string()));
+
+        let small = warp::test::request().method("POST").path("/echo").body("hello").reply(&routes).await;
+        assert_eq!((small.status().as_u16(), small.body().as_ref()), (200, b"5".as_ref()));
+        let large = warp::test::request().method("POST").path("/echo").body("x".repeat(17)).reply(&routes).await;
+        assert_eq!(large.status(), 413);
+        let body: serde_json::Value = serde_json::from_slice(large.body()).unwrap();
+        assert_eq!(body["error"], "payload too large");
+        // Bodiless requests carry no length and pass
+        assert_eq!(warp::test::request().path("/nope").reply(&routes).await.status(), 404);
+    }
+}
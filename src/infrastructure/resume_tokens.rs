@@ -0,0 +1,49 @@
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tracing::info;
+
+use crate::config::{Secret, SecretSource};
+
+/// Proves a connection is the player an id was handed to. `Connected` carries a
+/// `resumeToken`, a MAC of the player id; a later `Connect` presents it as its
+/// `reconnectToken` to take back that player's paused seat and notification inbox.
+/// Tokens are signed with the first configured key and accepted under any of them.
+pub struct ResumeTokens {
+    keys: Vec<Secret>,
+}
+
+impl ResumeTokens {
+    pub fn new(source: &SecretSource) -> Result<Self> {
+        Ok(Self::with_keys(source.load()?))
+    }
+
+    /// Signs with `keys` instead of loading them; an empty list gets a per-process key.
+    pub fn with_keys(mut keys: Vec<Secret>) -> Self {
+        if keys.is_empty() {
+            info!("No resume key configured; players can't resume a session across a restart");
+            let key = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+            keys.push(Secret::new("process", key));
+        }
+        Self { keys }
+    }
+
+    pub fn issue(&self, player_id: &str) -> String {
+        hex::encode(resume_mac(&self.keys[0], player_id).finalize().into_bytes())
+    }
+
+    /// Whether `token` was issued for `player_id` under any resume key.
+    pub fn verify(&self, player_id: &str, token: &str) -> bool {
+        let Ok(mac) = hex::decode(token) else {
+            return false;
+        };
+        self.keys.iter().any(|key| resume_mac(key, player_id).verify_slice(&mac).is_ok())
+    }
+}
+
+fn resume_mac(key: &Secret, player_id: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.expose().as_bytes()).expect("HMAC accepts any key length");
+    mac.update(b"resume:");
+    mac.update(player_id.as_bytes());
+    mac
+}
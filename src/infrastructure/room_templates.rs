@@ -0,0 +1,186 @@
+use anyhow::Result;
+use chrono::Utc;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::error;
+use warp::http::StatusCode;
+use warp::Filter;
+
+use super::admin_api::{with_role, with_audit_log};
+use super::audit_log::{AdminAction, AuditLog};
+use super::jsonl_store::JsonlStore;
+use crate::application::RoomTemplates;
+use crate::config::{Role, SecretStore};
+use crate::domain::RoomTemplate;
+
+// One line of the templates file; the last record for a name wins
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum TemplateRecord {
+    Saved { template: RoomTemplate },
+    Deleted { name: String },
+}
+
+/// Room templates by name, mirrored to a JSON-lines file. Edits take effect from the
+/// next room made from the template; rooms already playing keep the settings they got.
+pub struct RoomTemplateStore {
+    templates: RwLock<BTreeMap<String, RoomTemplate>>,
+    log: JsonlStore<TemplateRecord>,
+}
+
+impl RoomTemplateStore {
+    pub fn in_memory() -> Self {
+        Self {
+            templates: RwLock::new(BTreeMap::new()),
+            log: JsonlStore::in_memory(),
+        }
+    }
+
+    /// Opens (or creates) the templates file and replays it.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut store = Self::in_memory();
+        store.log = JsonlStore::open(path, "room template record", |record| store.apply(record))?;
+        Ok(store)
+    }
+
+    pub fn list(&self) -> Vec<RoomTemplate> {
+        self.templates.read().values().cloned().collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<RoomTemplate> {
+        self.templates.read().get(name).cloned()
+    }
+
+    /// Creates or replaces the template with `template.name`, returning it as stored.
+    pub fn save(&self, mut template: RoomTemplate) -> Result<RoomTemplate> {
+        template.validate()?;
+        template.updated_at = Utc::now();
+
+        let record = TemplateRecord::Saved { template: template.clone() };
+        self.log.persist(&record)?;
+        self.apply(record);
+        Ok(template)
+    }
+
+    /// False if there was no such template.
+    pub fn delete(&self, name: &str) -> Result<bool> {
+        if !self.templates.read().contains_key(name) {
+            return Ok(false);
+        }
+
+        let record = TemplateRecord::Deleted { name: name.to_string() };
+        self.log.persist(&record)?;
+        self.apply(record);
+        Ok(true)
+    }
+
+    fn apply(&self, record: TemplateRecord) {
+        let mut templates = self.templates.write();
+        match record {
+            TemplateRecord::Saved { template } => {
+                templates.insert(template.name.clone(), template);
+            }
+            TemplateRecord::Deleted { name } => {
+                templates.remove(&name);
+            }
+        }
+    }
+}
+
+impl RoomTemplates for RoomTemplateStore {
+    fn template(&self, name: &str) -> Option<RoomTemplate> {
+        self.get(name)
+    }
+}
+
+/// `GET /admin/templates` and `GET`/`PUT`/`DELETE /admin/templates/{name}`: room templates
+/// players can be matched on with `findMatch { template }`. A PUT body is the template
+/// without its name, which comes from the path.
+pub fn create_room_template_routes(
+    templates: Arc<RoomTemplateStore>,
+    audit_log: Arc<AuditLog>,
+    secrets: Arc<SecretStore>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let list = warp::path!("admin" / "templates")
+        .and(warp::get())
+        .and(with_role(secrets.clone(), Role::Viewer))
+        .and(with_templates(templates.clone()))
+        .map(|_actor: String, templates: Arc<RoomTemplateStore>| warp::reply::json(&templates.list()));
+
+    let get = warp::path!("admin" / "templates" / String)
+        .and(warp::get())
+        .and(with_role(secrets.clone(), Role::Viewer))
+        .and(with_templates(templates.clone()))
+        .map(|name: String, _actor: String, templates: Arc<RoomTemplateStore>| match templates.get(&name) {
+            Some(template) => warp::reply::with_status(warp::reply::json(&template), StatusCode::OK),
+            None => not_found(),
+        });
+
+    let save = warp::path!("admin" / "templates" / String)
+        .and(warp::put())
+        .and(with_role(secrets.clone(), Role::Operator))
+        .and(warp::body::json::<RoomTemplate>())
+        .and(with_templates(templates.clone()))
+        .and(with_audit_log(audit_log.clone()))
+        .map(|name: String, actor: String, mut template: RoomTemplate, templates: Arc<RoomTemplateStore>, audit_log: Arc<AuditLog>| {
+            template.name = name.clone();
+            if let Err(e) = template.validate() {
+                audit_log.record_or_log(&actor, AdminAction::SaveRoomTemplate, &name, false);
+                return warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({ "error": e.to_string() })),
+                    StatusCode::BAD_REQUEST,
+                );
+            }
+            let existed = templates.get(&name).is_some();
+            let saved = templates.save(template);
+            audit_log.record_or_log(&actor, AdminAction::SaveRoomTemplate, &name, saved.is_ok());
+            match saved {
+                Ok(template) => {
+                    let status = if existed { StatusCode::OK } else { StatusCode::CREATED };
+                    warp::reply::with_status(warp::reply::json(&template), status)
+                }
+                Err(e) => store_error(&name, e),
+            }
+        });
+
+    let delete = warp::path!("admin" / "templates" / String)
+        .and(warp::delete())
+        .and(with_role(secrets, Role::Operator))
+        .and(with_templates(templates))
+        .and(with_audit_log(audit_log))
+        .map(|name: String, actor: String, templates: Arc<RoomTemplateStore>, audit_log: Arc<AuditLog>| {
+            let deleted = templates.delete(&name);
+            audit_log.record_or_log(&actor, AdminAction::DeleteRoomTemplate, &name, matches!(deleted, Ok(true)));
+            match deleted {
+                Ok(true) => warp::reply::with_status(warp::reply::json(&serde_json::json!({ "deleted": name })), StatusCode::OK),
+                Ok(false) => not_found(),
+                Err(e) => store_error(&name, e),
+            }
+        });
+
+    list.or(get).or(save).or(delete)
+}
+
+fn with_templates(
+    templates: Arc<RoomTemplateStore>,
+) -> impl Filter<Extract = (Arc<RoomTemplateStore>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || templates.clone())
+}
+
+fn store_error(name: &str, e: anyhow::Error) -> warp::reply::WithStatus<warp::reply::Json> {
+    error!("Failed to store room template {}: {}", name, e);
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": "Failed to store room templates" })),
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
+
+fn not_found() -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": "No such room template" })),
+        StatusCode::NOT_FOUND,
+    )
+}
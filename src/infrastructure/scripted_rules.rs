@@ -0,0 +1,142 @@
+use anyhow::{bail, Context, Result};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST, INT};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::application::RuleScripts;
+use crate::config::ScriptedRulesConfig;
+use crate::domain::{Game, GameChoice};
+
+thread_local! {
+    // Deadline of the script call running on this thread; calls never nest or yield
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Compiled rule scripts and the sandboxed engine they run in. Scripts can't
+/// import modules, `eval`, or print, and every call is cut off by operation count and time.
+pub struct ScriptedRules {
+    engine: Arc<Engine>,
+    scripts: HashMap<String, Arc<AST>>,
+    time_limit: Duration,
+}
+
+impl ScriptedRules {
+    /// Reads and compiles every configured script.
+    pub fn new(config: &ScriptedRulesConfig) -> Result<Self> {
+        let sources = config
+            .scripts
+            .iter()
+            .map(|script| {
+                let source = std::fs::read_to_string(&script.script_path)
+                    .with_context(|| format!("Failed to read {} for script {}", script.script_path, script.name))?;
+                Ok((script.name.clone(), source))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::from_sources(config, sources)
+    }
+
+    pub fn from_sources(config: &ScriptedRulesConfig, sources: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        let engine = sandboxed_engine(config.max_operations);
+        let mut scripts = HashMap::new();
+        for (name, source) in sources {
+            let ast = engine.compile(&source).with_context(|| format!("Script {} doesn't compile", name))?;
+            for (function, params) in [("validate", 1), ("resolve", 2)] {
+                if !ast.iter_functions().any(|f| f.name == function && f.params.len() == params) {
+                    bail!("Script {} must define {}() with {} parameters", name, function, params);
+                }
+            }
+            scripts.insert(name, Arc::new(ast));
+        }
+
+        Ok(Self {
+            engine: Arc::new(engine),
+            scripts,
+            time_limit: Duration::from_millis(config.time_limit_ms),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.scripts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+}
+
+impl RuleScripts for ScriptedRules {
+    fn game(&self, script: &str) -> Option<Box<dyn Game>> {
+        let ast = self.scripts.get(script)?.clone();
+        Some(Box::new(ScriptedGame {
+            script: script.to_string(),
+            engine: self.engine.clone(),
+            ast,
+            time_limit: self.time_limit,
+        }))
+    }
+}
+
+fn sandboxed_engine(max_operations: u64) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(max_operations.max(1));
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(1024);
+    engine.set_max_array_size(1024);
+    engine.set_max_map_size(256);
+    engine.set_max_modules(0);
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.on_print(|_| {});
+    engine.on_debug(|_, _, _| {});
+    engine.on_progress(|_| {
+        let expired = DEADLINE.with(|deadline| deadline.get().is_some_and(|deadline| Instant::now() >= deadline));
+        expired.then_some(Dynamic::UNIT)
+    });
+    engine
+}
+
+struct ScriptedGame {
+    script: String,
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+    time_limit: Duration,
+}
+
+impl ScriptedGame {
+    fn call<T: Clone + Send + Sync + 'static>(&self, function: &str, args: impl rhai::FuncArgs) -> Result<T, Box<EvalAltResult>> {
+        DEADLINE.with(|deadline| deadline.set(Some(Instant::now() + self.time_limit)));
+        let result = self.engine.call_fn(&mut Scope::new(), &self.ast, function, args);
+        DEADLINE.with(|deadline| deadline.set(None));
+        result
+    }
+}
+
+impl Game for ScriptedGame {
+    // A script that fails to answer rejects the move
+    fn validate_move(&self, choice: &GameChoice) -> bool {
+        match self.call::<bool>("validate", (choice.name().to_string(),)) {
+            Ok(valid) => valid,
+            Err(e) => {
+                warn!("Script {} failed to validate {:?}: {}", self.script, choice.name(), e);
+                false
+            }
+        }
+    }
+
+    // ... and one that fails to resolve the round draws it
+    fn result(&self, [first, second]: [&GameChoice; 2]) -> Option<usize> {
+        match self.call::<INT>("resolve", (first.name().to_string(), second.name().to_string())) {
+            Ok(seat @ 0..=1) => Some(seat as usize),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Script {} failed to resolve {} vs {}: {}", self.script, first.name(), second.name(), e);
+                None
+            }
+        }
+    }
+}
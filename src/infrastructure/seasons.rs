@@ -0,0 +1,338 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use warp::Filter;
+
+use super::jsonl_store::JsonlStore;
+use super::notification_inbox::NotificationInbox;
+use super::response_cache::{with_cache_bypass, ResponseCache};
+use crate::application::{EloRatings, Standing};
+use crate::config::{SecretStore, SeasonsConfig};
+use crate::domain::NotificationKind;
+
+const ROLLOVER_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// A finished season with its final standings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeasonArchive {
+    pub season: u32,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub standings: Vec<Standing>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeasonSummary {
+    pub season: u32,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>, // Scheduled end for the current season, if any
+    pub players: usize,
+    pub champion: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentSeason {
+    #[serde(flatten)]
+    pub summary: SeasonSummary,
+    pub leaderboard: Vec<Standing>,
+}
+
+/// Rolls the built-in ratings over into numbered seasons. Finished seasons are archived
+/// to a JSON-lines file, and every ranked player is sent their final placing.
+pub struct Seasons {
+    ratings: Arc<EloRatings>,
+    length: Option<Duration>,
+    leaderboard_size: usize,
+    current: RwLock<(u32, DateTime<Utc>)>, // Season number, start
+    archive: RwLock<Vec<SeasonArchive>>,
+    log: JsonlStore<SeasonArchive>,
+}
+
+impl Seasons {
+    pub fn in_memory(ratings: Arc<EloRatings>, config: &SeasonsConfig) -> Self {
+        Self {
+            ratings,
+            length: (config.length_days > 0).then(|| Duration::days(config.length_days.into())),
+            leaderboard_size: config.leaderboard_size,
+            current: RwLock::new((1, Utc::now())),
+            archive: RwLock::new(Vec::new()),
+            log: JsonlStore::in_memory(),
+        }
+    }
+
+    /// Opens (or creates) the archive file; the current season follows the last archived one.
+    pub fn open(path: impl AsRef<Path>, ratings: Arc<EloRatings>, config: &SeasonsConfig) -> Result<Self> {
+        let mut seasons = Self::in_memory(ratings, config);
+        let mut archive = Vec::new();
+        seasons.log = JsonlStore::open(path, "season record", |season| archive.push(season))?;
+        if let Some(last) = archive.last() {
+            *seasons.current.write() = (last.season + 1, last.ended_at);
+        }
+        *seasons.archive.write() = archive;
+        Ok(seasons)
+    }
+
+    pub fn current(&self) -> CurrentSeason {
+        let (season, started_at) = *self.current.read();
+        let leaderboard = self.ratings.leaderboard(self.leaderboard_size);
+        CurrentSeason {
+            summary: SeasonSummary {
+                season,
+                started_at,
+                ended_at: self.length.map(|length| started_at + length),
+                players: self.ratings.len(),
+                champion: leaderboard.first().map(|standing| standing.player_id.clone()),
+            },
+            leaderboard,
+        }
+    }
+
+    pub fn archived(&self, season: u32) -> Option<SeasonArchive> {
+        self.archive.read().iter().find(|archived| archived.season == season).cloned()
+    }
+
+    /// Finished seasons, newest first.
+    pub fn history(&self) -> Vec<SeasonSummary> {
+        self.archive
+            .read()
+            .iter()
+            .rev()
+            .map(|archived| SeasonSummary {
+                season: archived.season,
+                started_at: archived.started_at,
+                ended_at: Some(archived.ended_at),
+                players: archived.standings.len(),
+                champion: archived.standings.first().map(|standing| standing.player_id.clone()),
+            })
+            .collect()
+    }
+
+    /// The player's final standing in every finished season they were ranked in.
+    pub fn placings_of(&self, player_id: &str) -> Vec<(u32, Standing)> {
+        self.archive
+            .read()
+            .iter()
+            .filter_map(|archived| {
+                let standing = archived.standings.iter().find(|standing| standing.player_id == player_id)?;
+                Some((archived.season, standing.clone()))
+            })
+            .collect()
+    }
+
+    /// Replaces the player's id with `alias` in finished seasons, keeping everyone's rank,
+    /// and rewrites the archive file. Returns how many seasons mentioned them. On error the
+    /// file and memory both still name them.
+    pub fn anonymize(&self, player_id: &str, alias: &str) -> Result<usize> {
+        let mut archive = self.archive.write();
+        let mut anonymized = archive.clone();
+        let standings = anonymized.iter_mut().flat_map(|archived| archived.standings.iter_mut());
+        let count = standings.filter(|standing| standing.player_id == player_id).map(|standing| standing.player_id = alias.to_string()).count();
+        if count == 0 {
+            return Ok(0);
+        }
+
+        self.log.rewrite(&anonymized)?;
+        *archive = anonymized;
+        Ok(count)
+    }
+
+    /// Ends the current season now: archives the standings, resets ratings, and notifies
+    /// every ranked player of where they finished.
+    pub fn roll_over(&self, notifications: &NotificationInbox) -> Result<SeasonArchive> {
+        let mut current = self.current.write();
+        let (season, started_at) = *current;
+        let ended_at = Utc::now();
+        let archived = SeasonArchive {
+            season,
+            started_at,
+            ended_at,
+            standings: self.ratings.reset(),
+        };
+
+        self.log.persist(&archived)?;
+        self.archive.write().push(archived.clone());
+        *current = (season + 1, ended_at);
+        drop(current);
+
+        let players = archived.standings.len();
+        for standing in &archived.standings {
+            let summary = serde_json::json!({
+                "season": season,
+                "rank": standing.rank,
+                "players": players,
+                "rating": standing.rating,
+                "games": standing.games,
+            });
+            if let Err(e) = notifications.push(&standing.player_id, NotificationKind::SeasonEnded, summary) {
+                warn!("Failed to notify {} of the season end: {}", standing.player_id, e);
+            }
+        }
+        info!("🏁 Season {} ended with {} ranked players", season, players);
+        Ok(archived)
+    }
+
+    /// Rolls over when the current season's scheduled end has passed.
+    pub fn roll_over_if_due(&self, notifications: &NotificationInbox) -> Result<Option<SeasonArchive>> {
+        let Some(length) = self.length else {
+            return Ok(None);
+        };
+        let started_at = self.current.read().1;
+        if Utc::now() < started_at + length {
+            return Ok(None);
+        }
+        self.roll_over(notifications).map(Some)
+    }
+
+    pub fn spawn(self: Arc<Self>, notifications: Arc<NotificationInbox>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ROLLOVER_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.roll_over_if_due(&notifications) {
+                    error!("Season rollover failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// `GET /seasons`: the current season and finished ones, newest first.
+/// `GET /seasons/current`: the current leaderboard, served through `cache`. `GET /seasons/{n}`: a finished season.
+pub fn create_season_routes(
+    seasons: Arc<Seasons>,
+    cache: Arc<ResponseCache>,
+    secrets: Arc<SecretStore>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let list = warp::path!("seasons")
+        .and(warp::get())
+        .and(with_seasons(seasons.clone()))
+        .map(|seasons: Arc<Seasons>| {
+            warp::reply::json(&serde_json::json!({
+                "current": seasons.current().summary,
+                "archived": seasons.history(),
+            }))
+        });
+
+    let current = warp::path!("seasons" / "current")
+        .and(warp::get())
+        .and(with_seasons(seasons.clone()))
+        .and(with_cache_bypass(secrets))
+        .and_then(move |seasons: Arc<Seasons>, fresh: bool| {
+            let cache = cache.clone();
+            async move { Ok::<_, warp::Rejection>(cache.json("leaderboard", fresh, || async move { seasons.current() }).await) }
+        });
+
+    let archived = warp::path!("seasons" / u32)
+        .and(warp::get())
+        .and(with_seasons(seasons))
+        .and_then(|season: u32, seasons: Arc<Seasons>| async move {
+            match seasons.archived(season) {
+                Some(archived) => Ok(warp::reply::json(&archived)),
+                None => Err(warp::reject::not_found()),
+            }
+        });
+
+    list.or(current).or(archived)
+}
+
+fn with_seasons(seasons: Arc<Seasons>) -> impl Filter<Extract = (Arc<Seasons>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || seasons.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::application::{EloRatings, RatedGame};
+    use crate::config::{SeasonsConfig, Secret, SecretStore};
+    use crate::domain::{GameEndReason, NotificationKind};
+    use crate::infrastructure::{create_season_routes, NotificationInbox, ResponseCache, ResponseCacheStats, Seasons};
+
+    #[tokio::test]
+    async fn test_season_rollover_archives_standings_and_resets_ratings() {
+        let path = std::env::temp_dir().join(format!("rps-seasons-{}.jsonl", uuid::Uuid::new_v4()));
+        let elo = Arc::new(EloRatings::new(32.0));
+        let seasons = Arc::new(Seasons::open(&path, elo.clone(), &SeasonsConfig::default()).unwrap());
+        let inbox = NotificationInbox::in_memory(10);
+        for winner in ["p1", "p1", "p2"] {
+            elo.apply(&RatedGame {
+                game_id: uuid::Uuid::new_v4().to_string(),
+                room_id: "r".to_string(),
+                players: vec!["p1".to_string(), "p2".to_string()],
+                teams: Default::default(),
+                winner: Some(winner.to_string()),
+                reason: GameEndReason::Completed,
+            });
+        }
+        assert!(seasons.roll_over_if_due(&inbox).unwrap().is_none());
+
+        let archived = seasons.roll_over(&inbox).unwrap();
+        assert_eq!(archived.season, 1);
+        assert_eq!(archived.standings.iter().map(|s| s.player_id.as_str()).collect::<Vec<_>>(), ["p1", "p2"]);
+        assert!(elo.is_empty());
+        let notice = &inbox.pending("p2")[0];
+        assert_eq!(notice.kind, NotificationKind::SeasonEnded);
+        assert_eq!((notice.data["season"].as_u64(), notice.data["rank"].as_u64()), (Some(1), Some(2)));
+
+        let secrets = Arc::new(SecretStore::from_keys(Vec::new(), Vec::new()));
+        let routes = create_season_routes(seasons, Arc::new(ResponseCache::new(std::time::Duration::ZERO)), secrets);
+        let list: serde_json::Value = serde_json::from_slice(warp::test::request().path("/seasons").reply(&routes).await.body()).unwrap();
+        assert_eq!(list["current"]["season"], 2);
+        assert_eq!(list["archived"][0]["champion"], "p1");
+        let first = warp::test::request().path("/seasons/1").reply(&routes).await;
+        assert_eq!(first.status(), 200);
+        assert_eq!(warp::test::request().path("/seasons/2").reply(&routes).await.status(), 404);
+
+        // The archive survives a restart and numbering carries on
+        let reopened = Seasons::open(&path, Arc::new(EloRatings::new(32.0)), &SeasonsConfig::default()).unwrap();
+        assert_eq!(reopened.current().summary.season, 2);
+        assert_eq!(reopened.archived(1).unwrap().standings.len(), 2);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_current_leaderboard_is_cached_with_an_admin_bypass() {
+        let path = std::env::temp_dir().join(format!("rps-seasons-{}.jsonl", uuid::Uuid::new_v4()));
+        let elo = Arc::new(EloRatings::new(32.0));
+        let seasons = Arc::new(Seasons::open(&path, elo.clone(), &SeasonsConfig::default()).unwrap());
+        let cache = Arc::new(ResponseCache::new(std::time::Duration::from_secs(60)));
+        let secrets = Arc::new(SecretStore::from_keys(vec![Secret::new("ops", "admin-key")], Vec::new()));
+        let routes = create_season_routes(seasons, cache.clone(), secrets);
+        let leaders = |path: &str, key: Option<&str>| {
+            let mut request = warp::test::request().path(path);
+            if let Some(key) = key {
+                request = request.header("x-api-key", key);
+            }
+            let routes = routes.clone();
+            async move {
+                let body: serde_json::Value = serde_json::from_slice(request.reply(&routes).await.body()).unwrap();
+                body["leaderboard"].as_array().unwrap().len()
+            }
+        };
+
+        assert_eq!(leaders("/seasons/current", None).await, 0);
+        elo.apply(&RatedGame {
+            game_id: "g1".to_string(),
+            room_id: "r".to_string(),
+            players: vec!["p1".to_string(), "p2".to_string()],
+            teams: Default::default(),
+            winner: Some("p1".to_string()),
+            reason: GameEndReason::Completed,
+        });
+        assert_eq!(leaders("/seasons/current", None).await, 0);
+        // Only admins may skip the cache; for anyone else the flag is ignored
+        assert_eq!(leaders("/seasons/current?fresh=true", Some("wrong-key")).await, 0);
+        assert_eq!(leaders("/seasons/current?fresh=true", Some("admin-key")).await, 2);
+        assert_eq!(leaders("/seasons/current", None).await, 2);
+        assert_eq!(cache.stats(), ResponseCacheStats { hits: 3, misses: 1, bypasses: 1 });
+        let _ = std::fs::remove_file(&path);
+    }
+}
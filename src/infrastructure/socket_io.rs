@@ -0,0 +1,108 @@
+use serde_json::Value;
+
+use crate::domain::ServerMessage;
+
+/// Upgrade path used by Socket.IO clients, e.g. `/socket.io/?EIO=4&transport=websocket`.
+pub const SOCKET_IO_PATH: &str = "/socket.io/";
+
+/// How messages are framed on one connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    Native,   // One JSON `ClientMessage`/`ServerMessage` per text frame
+    SocketIo, // Engine.IO v4 / Socket.IO v5 packets, default namespace only
+}
+
+/// A decoded Engine.IO/Socket.IO packet from the client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SocketIoInbound {
+    Message(String), // A `ClientMessage` as JSON, ready for the normal handler
+    Disconnect,
+    Pong,
+    Ignored, // Unsupported namespaces and packet types
+}
+
+/// Engine.IO `open` packet, sent right after the upgrade.
+pub fn open_packet(sid: &str, ping_interval_ms: u64, max_payload: usize) -> String {
+    let handshake = serde_json::json!({
+        "sid": sid,
+        "upgrades": [],
+        "pingInterval": ping_interval_ms,
+        "pingTimeout": ping_interval_ms,
+        "maxPayload": max_payload,
+    });
+    format!("0{}", handshake)
+}
+
+/// Engine.IO ping; clients answer with a pong and drop the connection if pings stop.
+pub const PING_PACKET: &str = "2";
+
+/// Translates one client packet. The namespace `CONNECT` becomes a `connect` message whose
+/// fields come from the auth payload; events become messages whose `type` is the event name,
+/// or pass through unchanged when sent as a `message` event.
+pub fn decode(packet: &str) -> SocketIoInbound {
+    match packet.as_bytes().first() {
+        Some(b'1') => return SocketIoInbound::Disconnect,
+        Some(b'3') => return SocketIoInbound::Pong,
+        Some(b'4') => {}
+        _ => return SocketIoInbound::Ignored,
+    }
+
+    let packet = &packet[1..];
+    let Some(kind) = packet.chars().next() else {
+        return SocketIoInbound::Ignored;
+    };
+    let rest = &packet[1..];
+    if rest.starts_with('/') && !rest.starts_with("/,") {
+        return SocketIoInbound::Ignored; // Only the default namespace is served
+    }
+    let rest = rest.strip_prefix("/,").unwrap_or(rest);
+
+    match kind {
+        '0' => {
+            let mut connect = match serde_json::from_str::<Value>(rest) {
+                Ok(Value::Object(auth)) => auth,
+                _ => serde_json::Map::new(),
+            };
+            connect.insert("type".to_string(), Value::from("connect"));
+            SocketIoInbound::Message(Value::Object(connect).to_string())
+        }
+        '1' => SocketIoInbound::Disconnect,
+        '2' => {
+            // Acknowledgement ids aren't supported, so they are skipped
+            let payload = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+            match serde_json::from_str::<Vec<Value>>(payload) {
+                Ok(args) => event_to_message(args).map_or(SocketIoInbound::Ignored, SocketIoInbound::Message),
+                Err(_) => SocketIoInbound::Ignored,
+            }
+        }
+        _ => SocketIoInbound::Ignored,
+    }
+}
+
+fn event_to_message(args: Vec<Value>) -> Option<String> {
+    let mut args = args.into_iter();
+    let event = args.next()?.as_str()?.to_string();
+    let data = args.next();
+    if event == "message" {
+        return data.map(|data| data.to_string());
+    }
+
+    let mut message = match data {
+        Some(Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    };
+    message.insert("type".to_string(), Value::from(event));
+    Some(Value::Object(message).to_string())
+}
+
+/// Encodes a server message as frames. `Connected` also acknowledges the namespace connect.
+pub fn encode(message: &ServerMessage) -> serde_json::Result<Vec<String>> {
+    let value = serde_json::to_value(message)?;
+    let event = value.get("type").and_then(Value::as_str).unwrap_or("message").to_string();
+    let mut frames = Vec::with_capacity(2);
+    if let ServerMessage::Connected { player_id, .. } = message {
+        frames.push(format!("40{}", serde_json::json!({ "sid": player_id })));
+    }
+    frames.push(format!("42{}", serde_json::to_string(&(event, value))?));
+    Ok(frames)
+}
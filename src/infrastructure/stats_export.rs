@@ -0,0 +1,466 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use super::object_store::ObjectStore;
+use crate::application::{GameManager, GameRecord};
+use crate::config::{ExportFormat, StatsExportConfig};
+use crate::domain::{GameEndReason, GameEvent, GameMode};
+
+enum ExportSink {
+    Directory(PathBuf),
+    Bucket(Box<ObjectStore>),
+}
+
+/// Totals for one UTC day of finished games.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DailyStats {
+    pub date: String,
+    pub games: u64,
+    pub solo: u64,
+    pub teams: u64,
+    pub bot: u64,
+    pub blitz: u64,
+    pub completed: u64,
+    pub time_limit: u64,
+    pub forfeit: u64,
+    pub draws: u64,
+    pub rounds: u64,
+    pub players: u64, // Distinct player ids
+    pub avg_duration_ms: u64,
+    #[serde(skip)]
+    player_ids: HashSet<String>,
+    #[serde(skip)]
+    total_duration_ms: u64,
+}
+
+impl DailyStats {
+    fn add(&mut self, record: &GameRecord) {
+        self.games += 1;
+        match record.mode {
+            GameMode::Solo => self.solo += 1,
+            GameMode::Teams => self.teams += 1,
+            GameMode::Bot => self.bot += 1,
+            GameMode::Blitz => self.blitz += 1,
+        }
+        match record.reason {
+            Some(GameEndReason::Completed) => self.completed += 1,
+            Some(GameEndReason::TimeLimit) => self.time_limit += 1,
+            Some(GameEndReason::Forfeit) => self.forfeit += 1,
+            None => {}
+        }
+        if record.winner.is_none() {
+            self.draws += 1;
+        }
+        self.rounds += record.rounds.len() as u64;
+        self.player_ids.extend(record.players.iter().cloned());
+        self.players = self.player_ids.len() as u64;
+        self.total_duration_ms += duration_ms(record);
+        self.avg_duration_ms = self.total_duration_ms / self.games;
+    }
+}
+
+#[derive(Default)]
+struct Pending {
+    games: Vec<GameRecord>,
+    days: BTreeMap<NaiveDate, DailyStats>, // Kept until the day is over, so each flush rewrites the full day
+}
+
+/// Collects finished games from the event bus and periodically writes them, plus running
+/// per-day aggregates, to a directory or bucket for offline analysis.
+pub struct StatsExporter {
+    sink: ExportSink,
+    format: ExportFormat,
+    interval: Duration,
+    pending: Mutex<Pending>,
+}
+
+impl StatsExporter {
+    /// None when neither a path nor an object store is configured.
+    pub fn new(config: &StatsExportConfig) -> Result<Option<Self>> {
+        let sink = match (&config.object_store, &config.path) {
+            (Some(store), _) => ExportSink::Bucket(Box::new(ObjectStore::new(store)?)),
+            (None, Some(path)) => {
+                std::fs::create_dir_all(path).with_context(|| format!("Failed to create export directory {}", path))?;
+                ExportSink::Directory(PathBuf::from(path))
+            }
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(Self {
+            sink,
+            format: config.format,
+            interval: Duration::from_millis(config.interval_ms.max(1000)),
+            pending: Mutex::new(Pending::default()),
+        }))
+    }
+
+    pub fn destination(&self) -> String {
+        match &self.sink {
+            ExportSink::Directory(path) => path.display().to_string(),
+            ExportSink::Bucket(store) => format!("bucket {}", store.bucket()),
+        }
+    }
+
+    pub fn add(&self, record: GameRecord) {
+        let day = record.ended_at.unwrap_or(record.created_at).date_naive();
+        let mut pending = self.pending.lock();
+        pending
+            .days
+            .entry(day)
+            .or_insert_with(|| DailyStats {
+                date: day.to_string(),
+                ..DailyStats::default()
+            })
+            .add(&record);
+        pending.games.push(record);
+    }
+
+    /// Writes games collected since the last flush and the aggregates for every day they
+    /// touched. Returns the number of games written.
+    pub async fn flush(&self) -> Result<usize> {
+        let (games, days) = {
+            let mut pending = self.pending.lock();
+            if pending.games.is_empty() {
+                return Ok(0);
+            }
+            let games = std::mem::take(&mut pending.games);
+            let touched: HashSet<_> = games.iter().map(|g| g.ended_at.unwrap_or(g.created_at).date_naive()).collect();
+            let days: Vec<_> = pending.days.iter().filter(|(day, _)| touched.contains(day)).map(|(_, s)| s.clone()).collect();
+            (games, days)
+        };
+
+        let extension = self.extension();
+        let stamp = Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+        let result = async {
+            self.write(&format!("games-{}.{}", stamp, extension), self.encode_games(&games)?).await?;
+            for day in &days {
+                self.write(&format!("daily-{}.{}", day.date, extension), self.encode_days(day)?).await?;
+            }
+            anyhow::Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            // Put the games back so the next flush retries them
+            let mut pending = self.pending.lock();
+            let newer = std::mem::replace(&mut pending.games, games);
+            pending.games.extend(newer);
+            return Err(e);
+        }
+        // Earlier days can't receive more games
+        let today = Utc::now().date_naive();
+        self.pending.lock().days.retain(|day, _| *day >= today);
+        Ok(games.len())
+    }
+
+    /// Collects games from `manager` and flushes on the configured interval until the bus closes.
+    pub fn spawn(self, manager: Arc<GameManager>) -> JoinHandle<()> {
+        let exporter = Arc::new(self);
+        let mut receiver = manager.events().subscribe();
+
+        let flusher = exporter.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(flusher.interval);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                match flusher.flush().await {
+                    Ok(0) => {}
+                    Ok(written) => info!("Exported {} games to {}", written, flusher.destination()),
+                    Err(e) => error!("Stats export to {} failed: {}", flusher.destination(), e),
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(envelope) => {
+                        let GameEvent::GameEnded { game_id, .. } = envelope.event else {
+                            continue;
+                        };
+                        match manager.game_record(&game_id).await {
+                            Some(record) => exporter.add(record),
+                            None => warn!("Finished game {} missing from history; not exported", game_id),
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => warn!("Stats exporter lagged; {} events dropped", skipped),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            if let Err(e) = exporter.flush().await {
+                error!("Final stats export to {} failed: {}", exporter.destination(), e);
+            }
+        })
+    }
+
+    fn extension(&self) -> &'static str {
+        match self.format {
+            ExportFormat::Csv => "csv",
+            ExportFormat::JsonLines => "jsonl",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self.format {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::JsonLines => "application/x-ndjson",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    async fn write(&self, name: &str, body: Vec<u8>) -> Result<()> {
+        match &self.sink {
+            ExportSink::Directory(dir) => {
+                // Written aside and renamed so readers never see a partial file
+                let tmp = dir.join(format!(".{}.tmp", name));
+                tokio::fs::write(&tmp, &body).await.with_context(|| format!("Failed to write {}", tmp.display()))?;
+                tokio::fs::rename(&tmp, dir.join(name)).await?;
+                Ok(())
+            }
+            ExportSink::Bucket(store) => store.put(name, body, self.content_type()).await,
+        }
+    }
+
+    fn encode_games(&self, games: &[GameRecord]) -> Result<Vec<u8>> {
+        if self.format == ExportFormat::JsonLines {
+            return Ok(json_lines(games));
+        }
+        let text = |f: fn(&GameRecord) -> String| Column::Text(games.iter().map(f).collect());
+        let int = |f: fn(&GameRecord) -> i64| Column::Int(games.iter().map(f).collect());
+        let timestamp = |f: fn(&GameRecord) -> i64| Column::Timestamp(games.iter().map(f).collect());
+        self.encode_table(&[
+            ("game_id", text(|g| g.game_id.clone())),
+            ("room_id", text(|g| g.room_id.clone())),
+            ("mode", text(|g| label(&g.mode))),
+            ("reason", text(|g| g.reason.as_ref().map(label).unwrap_or_default())),
+            ("winner", text(|g| g.winner.clone().unwrap_or_default())),
+            ("players", text(|g| g.players.join(";"))),
+            ("scores", text(|g| {
+                let mut scores: Vec<_> = g.scores.iter().map(|(id, score)| format!("{}={}", id, score)).collect();
+                scores.sort();
+                scores.join(";")
+            })),
+            ("rounds", int(|g| g.rounds.len() as i64)),
+            ("created_at", timestamp(|g| g.created_at.timestamp_millis())),
+            ("ended_at", timestamp(|g| g.ended_at.unwrap_or(g.created_at).timestamp_millis())),
+            ("duration_ms", int(|g| duration_ms(g) as i64)),
+        ])
+    }
+
+    fn encode_days(&self, day: &DailyStats) -> Result<Vec<u8>> {
+        if self.format == ExportFormat::JsonLines {
+            return Ok(json_lines(std::slice::from_ref(day)));
+        }
+        let int = |value: u64| Column::Int(vec![value as i64]);
+        self.encode_table(&[
+            ("date", Column::Text(vec![day.date.clone()])),
+            ("games", int(day.games)),
+            ("solo", int(day.solo)),
+            ("teams", int(day.teams)),
+            ("bot", int(day.bot)),
+            ("blitz", int(day.blitz)),
+            ("completed", int(day.completed)),
+            ("time_limit", int(day.time_limit)),
+            ("forfeit", int(day.forfeit)),
+            ("draws", int(day.draws)),
+            ("rounds", int(day.rounds)),
+            ("players", int(day.players)),
+            ("avg_duration_ms", int(day.avg_duration_ms)),
+        ])
+    }
+
+    fn encode_table(&self, columns: &[(&str, Column)]) -> Result<Vec<u8>> {
+        match self.format {
+            ExportFormat::Parquet => parquet_table(columns),
+            _ => Ok(csv_table(columns)),
+        }
+    }
+}
+
+enum Column {
+    Text(Vec<String>),
+    Int(Vec<i64>),
+    Timestamp(Vec<i64>), // Milliseconds since the epoch, UTC
+}
+
+impl Column {
+    fn len(&self) -> usize {
+        match self {
+            Column::Text(values) => values.len(),
+            Column::Int(values) | Column::Timestamp(values) => values.len(),
+        }
+    }
+}
+
+fn duration_ms(record: &GameRecord) -> u64 {
+    record
+        .ended_at
+        .map(|ended| (ended - record.created_at).num_milliseconds().max(0) as u64)
+        .unwrap_or(0)
+}
+
+// The serde name, e.g. "timeLimit", so every format agrees
+fn label<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn json_lines<T: Serialize>(items: &[T]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for item in items {
+        out.extend(serde_json::to_vec(item).unwrap_or_default());
+        out.push(b'\n');
+    }
+    out
+}
+
+fn csv_table(columns: &[(&str, Column)]) -> Vec<u8> {
+    let mut out = columns.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(",");
+    out.push('\n');
+    for row in 0..columns.first().map_or(0, |(_, column)| column.len()) {
+        for (i, (_, column)) in columns.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let field = match column {
+                Column::Text(values) => values[row].clone(),
+                Column::Int(values) => values[row].to_string(),
+                Column::Timestamp(values) => DateTime::<Utc>::from_timestamp_millis(values[row])
+                    .map(|t| t.to_rfc3339_opts(SecondsFormat::Millis, true))
+                    .unwrap_or_default(),
+            };
+            if field.contains([',', '"', '\n', '\r']) {
+                out.push('"');
+                out.push_str(&field.replace('"', "\"\""));
+                out.push('"');
+            } else {
+                out.push_str(&field);
+            }
+        }
+        out.push('\n');
+    }
+    out.into_bytes()
+}
+
+// One row group, uncompressed; files are small and written once per interval
+fn parquet_table(columns: &[(&str, Column)]) -> Result<Vec<u8>> {
+    let fields: String = columns
+        .iter()
+        .map(|(name, column)| match column {
+            Column::Text(_) => format!("REQUIRED BYTE_ARRAY {} (UTF8);", name),
+            Column::Int(_) => format!("REQUIRED INT64 {};", name),
+            Column::Timestamp(_) => format!("REQUIRED INT64 {} (TIMESTAMP(MILLIS,true));", name),
+        })
+        .collect();
+    let schema = Arc::new(parse_message_type(&format!("message export {{ {} }}", fields))?);
+    let mut writer = SerializedFileWriter::new(Vec::new(), schema, Arc::new(WriterProperties::builder().build()))?;
+
+    let mut row_group = writer.next_row_group()?;
+    for (_, column) in columns {
+        let mut column_writer = row_group.next_column()?.context("Parquet schema has fewer columns than the table")?;
+        match column {
+            Column::Text(values) => {
+                let values: Vec<ByteArray> = values.iter().map(|v| ByteArray::from(v.as_str())).collect();
+                column_writer.typed::<ByteArrayType>().write_batch(&values, None, None)?;
+            }
+            Column::Int(values) | Column::Timestamp(values) => {
+                column_writer.typed::<Int64Type>().write_batch(values, None, None)?;
+            }
+        }
+        column_writer.close()?;
+    }
+    row_group.close()?;
+    Ok(writer.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::GameEndReason;
+
+    #[tokio::test]
+    async fn test_stats_export_writes_games_and_daily_aggregates() {
+        use crate::application::GameRecord;
+        use crate::config::{ExportFormat, StatsExportConfig};
+        use crate::domain::{GameMode, GameStatus};
+        use crate::infrastructure::StatsExporter;
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let dir = std::env::temp_dir().join(format!("rps-export-{}", uuid::Uuid::new_v4()));
+        let config = StatsExportConfig { path: Some(dir.display().to_string()), ..StatsExportConfig::default() };
+        assert_eq!(config.format, ExportFormat::Csv);
+        let exporter = StatsExporter::new(&config).unwrap().unwrap();
+        assert!(StatsExporter::new(&StatsExportConfig::default()).unwrap().is_none());
+
+        let ended = chrono::Utc::now();
+        let record = |game_id: &str, players: [&str; 2], winner: Option<&str>, reason| GameRecord {
+            game_id: game_id.to_string(),
+            room_id: format!("room-{}", game_id),
+            mode: GameMode::Solo,
+            status: GameStatus::Finished,
+            players: players.iter().map(|p| p.to_string()).collect(),
+            teams: Default::default(),
+            winner: winner.map(str::to_string),
+            scores: players.iter().map(|p| (p.to_string(), 1)).collect(),
+            reason: Some(reason),
+            rounds: Vec::new(),
+            created_at: ended - chrono::Duration::seconds(30),
+            ended_at: Some(ended),
+        };
+        exporter.add(record("g1", ["alice", "bob,the builder"], Some("alice"), GameEndReason::Completed));
+        exporter.add(record("g2", ["alice", "carol"], None, GameEndReason::TimeLimit));
+        assert_eq!(exporter.flush().await.unwrap(), 2);
+        assert_eq!(exporter.flush().await.unwrap(), 0);
+
+        let mut files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name().into_string().unwrap()).collect();
+        files.sort();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0], format!("daily-{}.csv", ended.date_naive()));
+        assert!(files[1].starts_with("games-") && files[1].ends_with(".csv"));
+
+        let games = std::fs::read_to_string(dir.join(&files[1])).unwrap();
+        let lines: Vec<_> = games.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("game_id,room_id,mode,reason,winner,players"));
+        assert!(lines[1].starts_with("g1,room-g1,solo,completed,alice,\"alice;bob,the builder\","));
+        assert!(lines[2].starts_with("g2,room-g2,solo,timeLimit,,alice;carol,"));
+        assert!(lines[2].ends_with(",30000"));
+
+        let daily = std::fs::read_to_string(dir.join(&files[0])).unwrap();
+        assert_eq!(daily.lines().nth(1).unwrap(), format!("{},2,2,0,0,0,1,1,0,1,0,3,30000", ended.date_naive()));
+
+        // A later game rewrites the day's totals rather than appending a second row
+        exporter.add(record("g3", ["dave", "erin"], Some("erin"), GameEndReason::Forfeit));
+        exporter.flush().await.unwrap();
+        let daily = std::fs::read_to_string(dir.join(&files[0])).unwrap();
+        assert_eq!(daily.lines().count(), 2);
+        assert!(daily.lines().nth(1).unwrap().starts_with(&format!("{},3,3,0,0,0,1,1,1,1,0,5,", ended.date_naive())));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let config = StatsExportConfig { format: ExportFormat::Parquet, ..config };
+        let exporter = StatsExporter::new(&config).unwrap().unwrap();
+        exporter.add(record("g4", ["alice", "bob"], Some("bob"), GameEndReason::Completed));
+        exporter.flush().await.unwrap();
+        let games = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).find(|p| p.to_string_lossy().contains("games-")).unwrap();
+        assert_eq!(games.extension().unwrap(), "parquet");
+        let reader = SerializedFileReader::new(std::fs::File::open(&games).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 1);
+        assert_eq!(reader.metadata().file_metadata().schema_descr().num_columns(), 11);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::Result;
+use chrono::Utc;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::error;
+use warp::http::StatusCode;
+use warp::Filter;
+
+use super::admin_api::{with_role, with_audit_log};
+use super::audit_log::{AdminAction, AuditLog};
+use super::jsonl_store::JsonlStore;
+use crate::application::TitleLookup;
+use crate::config::{Role, SecretStore};
+use crate::domain::{PlayerTitles, Title, TitleSource};
+
+// One line of the titles file; replaying them in order rebuilds every player's titles
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+enum TitleRecord {
+    Granted {
+        #[serde(rename = "playerId")]
+        player_id: String,
+        title: Title,
+    },
+    Equipped {
+        #[serde(rename = "playerId")]
+        player_id: String,
+        #[serde(rename = "titleId")]
+        title_id: Option<String>,
+    },
+}
+
+/// Titles each player has earned and the one they have equipped, mirrored to a JSON-lines file.
+pub struct TitleStore {
+    players: RwLock<HashMap<String, PlayerTitles>>,
+    log: JsonlStore<TitleRecord>,
+}
+
+impl TitleStore {
+    pub fn in_memory() -> Self {
+        Self {
+            players: RwLock::new(HashMap::new()),
+            log: JsonlStore::in_memory(),
+        }
+    }
+
+    /// Opens (or creates) the titles file and replays it.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut store = Self::in_memory();
+        store.log = JsonlStore::open(path, "title record", |record| store.apply(record))?;
+        Ok(store)
+    }
+
+    pub fn titles(&self, player_id: &str) -> PlayerTitles {
+        self.players.read().get(player_id).cloned().unwrap_or_default()
+    }
+
+    /// Awards a title; returns it, or `None` if the player already holds one with that id.
+    pub fn grant(&self, player_id: &str, id: &str, name: &str, source: TitleSource) -> Result<Option<Title>> {
+        if self.titles(player_id).titles.iter().any(|title| title.id == id) {
+            return Ok(None);
+        }
+        let title = Title {
+            id: id.to_string(),
+            name: name.to_string(),
+            source,
+            earned_at: Utc::now(),
+        };
+
+        let record = TitleRecord::Granted {
+            player_id: player_id.to_string(),
+            title: title.clone(),
+        };
+        self.log.persist(&record)?;
+        self.apply(record);
+        Ok(Some(title))
+    }
+
+    /// Shows `title_id` next to the player, or nothing for `None`. False if they haven't earned it.
+    pub fn equip(&self, player_id: &str, title_id: Option<&str>) -> Result<bool> {
+        if let Some(title_id) = title_id {
+            if !self.titles(player_id).titles.iter().any(|title| title.id == title_id) {
+                return Ok(false);
+            }
+        }
+
+        let record = TitleRecord::Equipped {
+            player_id: player_id.to_string(),
+            title_id: title_id.map(str::to_string),
+        };
+        self.log.persist(&record)?;
+        self.apply(record);
+        Ok(true)
+    }
+
+    /// Deletes everything held about the player, rewriting the file without them.
+    /// False if they had no titles. On error both the file and memory still hold them.
+    pub fn forget(&self, player_id: &str) -> Result<bool> {
+        let mut players = self.players.write();
+        if !players.contains_key(player_id) {
+            return Ok(false);
+        }
+
+        let records = players.iter().filter(|(id, _)| id.as_str() != player_id).flat_map(|(id, titles)| {
+            let granted = titles.titles.iter().map(|title| TitleRecord::Granted { player_id: id.clone(), title: title.clone() });
+            let equipped = titles.equipped.clone().map(|title_id| TitleRecord::Equipped { player_id: id.clone(), title_id: Some(title_id) });
+            granted.chain(equipped)
+        });
+        self.log.rewrite(records)?;
+        players.remove(player_id);
+        Ok(true)
+    }
+
+    fn apply(&self, record: TitleRecord) {
+        let mut players = self.players.write();
+        match record {
+            TitleRecord::Granted { player_id, title } => players.entry(player_id).or_default().titles.push(title),
+            TitleRecord::Equipped { player_id, title_id } => players.entry(player_id).or_default().equipped = title_id,
+        }
+    }
+}
+
+impl TitleLookup for TitleStore {
+    fn equipped_title(&self, player_id: &str) -> Option<String> {
+        self.players.read().get(player_id)?.equipped_title().map(|title| title.name.clone())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GrantTitleRequest {
+    pub id: String,
+    pub name: String,
+    pub source: TitleSource,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EquipTitleRequest {
+    #[serde(rename = "titleId")]
+    pub title_id: Option<String>, // null unequips
+}
+
+/// `GET /players/{id}/titles`: a player's titles. Granting (`POST /admin/players/{id}/titles`)
+/// and equipping (`PUT /players/{id}/titles/equipped`) are for the platform backend, so both
+/// take an admin key.
+pub fn create_title_routes(
+    titles: Arc<TitleStore>,
+    audit_log: Arc<AuditLog>,
+    secrets: Arc<SecretStore>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let list = warp::path!("players" / String / "titles")
+        .and(warp::get())
+        .and(with_titles(titles.clone()))
+        .map(|player_id: String, titles: Arc<TitleStore>| warp::reply::json(&titles.titles(&player_id)));
+
+    let grant = warp::path!("admin" / "players" / String / "titles")
+        .and(warp::post())
+        .and(with_role(secrets.clone(), Role::Operator))
+        .and(warp::body::json::<GrantTitleRequest>())
+        .and(with_titles(titles.clone()))
+        .and(with_audit_log(audit_log.clone()))
+        .map(|player_id: String, actor: String, request: GrantTitleRequest, titles: Arc<TitleStore>, audit_log: Arc<AuditLog>| {
+            let granted = titles.grant(&player_id, &request.id, &request.name, request.source);
+            audit_log.record_or_log(&actor, AdminAction::GrantTitle, &format!("{}:{}", player_id, request.id), granted.is_ok());
+            match granted {
+                Ok(Some(title)) => warp::reply::with_status(warp::reply::json(&title), StatusCode::CREATED),
+                Ok(None) => warp::reply::with_status(warp::reply::json(&titles.titles(&player_id)), StatusCode::OK),
+                Err(e) => store_error(&player_id, e),
+            }
+        });
+
+    let equip = warp::path!("players" / String / "titles" / "equipped")
+        .and(warp::put())
+        .and(with_role(secrets, Role::Operator))
+        .and(warp::body::json::<EquipTitleRequest>())
+        .and(with_titles(titles))
+        .and(with_audit_log(audit_log))
+        .map(|player_id: String, actor: String, request: EquipTitleRequest, titles: Arc<TitleStore>, audit_log: Arc<AuditLog>| {
+            let equipped = titles.equip(&player_id, request.title_id.as_deref());
+            let target = request.title_id.as_deref().unwrap_or("none");
+            audit_log.record_or_log(&actor, AdminAction::EquipTitle, &format!("{}:{}", player_id, target), matches!(equipped, Ok(true)));
+            match equipped {
+                Ok(true) => warp::reply::with_status(warp::reply::json(&titles.titles(&player_id)), StatusCode::OK),
+                Ok(false) => warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({ "error": "Title not earned" })),
+                    StatusCode::NOT_FOUND,
+                ),
+                Err(e) => store_error(&player_id, e),
+            }
+        });
+
+    list.or(grant).or(equip)
+}
+
+fn with_titles(titles: Arc<TitleStore>) -> impl Filter<Extract = (Arc<TitleStore>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || titles.clone())
+}
+
+fn store_error(player_id: &str, e: anyhow::Error) -> warp::reply::WithStatus<warp::reply::Json> {
+    error!("Failed to store titles for {}: {}", player_id, e);
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "error": "Failed to store titles" })),
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
@@ -0,0 +1,229 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use warp::Filter;
+
+use crate::application::EventBus;
+use crate::domain::{GameEndReason, GameEvent};
+
+// Updates a subscriber may fall behind by before it skips ahead to a fresh bracket
+const UPDATE_BUFFER: usize = 256;
+
+// Comment lines sent on an idle stream, so proxies don't close it
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BracketMatchStatus {
+    Reserved, // Waiting for its players to join
+    Playing,
+    Finished,
+}
+
+/// One match of a tournament's bracket as it stands.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BracketMatch {
+    pub room_id: String,
+    pub players: Vec<String>,
+    pub status: BracketMatchStatus,
+    pub round: u32, // Round being played; the last one once finished
+    pub max_rounds: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winner: Option<String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub scores: HashMap<String, u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<GameEndReason>,
+}
+
+/// Something that changed in a tournament, as sent on its stream.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TournamentUpdate {
+    Bracket { matches: Vec<BracketMatch> }, // The whole bracket; first on every stream
+    MatchUpdated {
+        #[serde(rename = "match")]
+        bracket_match: BracketMatch,
+    },
+}
+
+/// Updates to every tournament, each tagged with its tournament's id.
+pub type TournamentUpdates = broadcast::Receiver<(String, TournamentUpdate)>;
+
+impl TournamentUpdate {
+    fn event_name(&self) -> &'static str {
+        match self {
+            TournamentUpdate::Bracket { .. } => "bracket",
+            TournamentUpdate::MatchUpdated { .. } => "matchUpdated",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Brackets {
+    matches: HashMap<String, Vec<BracketMatch>>, // tournamentId -> matches, in the order they were reserved
+    rooms: HashMap<String, String>,              // roomId -> tournamentId
+}
+
+/// Live brackets for tournaments whose matches were reserved with a tournament id, kept
+/// up to date from the event bus and streamed to overlays at `/tournaments/{id}/stream`.
+pub struct TournamentFeed {
+    brackets: Mutex<Brackets>,
+    updates: broadcast::Sender<(String, TournamentUpdate)>, // tournamentId, update
+}
+
+impl Default for TournamentFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TournamentFeed {
+    pub fn new() -> Self {
+        Self {
+            brackets: Mutex::new(Brackets::default()),
+            updates: broadcast::channel(UPDATE_BUFFER).0,
+        }
+    }
+
+    /// Follows the event bus until it closes.
+    pub fn spawn(self: Arc<Self>, events: &EventBus) -> JoinHandle<()> {
+        let mut receiver = events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(envelope) => self.apply(&envelope.event),
+                    Err(RecvError::Lagged(skipped)) => warn!("Tournament feed lagged; {} events dropped", skipped),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    pub fn apply(&self, event: &GameEvent) {
+        let mut brackets = self.brackets.lock();
+        let (tournament, room_id) = match event {
+            GameEvent::MatchReserved { room_id, players, tournament: Some(tournament) } => {
+                brackets.rooms.insert(room_id.clone(), tournament.clone());
+                brackets.matches.entry(tournament.clone()).or_default().push(BracketMatch {
+                    room_id: room_id.clone(),
+                    players: players.clone(),
+                    status: BracketMatchStatus::Reserved,
+                    round: 0,
+                    max_rounds: 0,
+                    game_id: None,
+                    winner: None,
+                    scores: HashMap::new(),
+                    reason: None,
+                });
+                info!("Tournament {}: match {} reserved", tournament, room_id);
+                (tournament.clone(), room_id)
+            }
+            GameEvent::MatchCreated { room_id, max_rounds, .. } | GameEvent::RoundStarted { room_id, max_rounds, .. } => {
+                let Some(bracket_match) = Self::bracket_match(&mut brackets, room_id) else {
+                    return;
+                };
+                bracket_match.status = BracketMatchStatus::Playing;
+                bracket_match.max_rounds = *max_rounds;
+                bracket_match.round = match event {
+                    GameEvent::RoundStarted { round, .. } => *round,
+                    _ => 1,
+                };
+                (brackets.rooms[room_id].clone(), room_id)
+            }
+            GameEvent::GameEnded { room_id, game_id, winner, final_scores, reason, .. } => {
+                let Some(bracket_match) = Self::bracket_match(&mut brackets, room_id) else {
+                    return;
+                };
+                bracket_match.status = BracketMatchStatus::Finished;
+                bracket_match.game_id = Some(game_id.clone());
+                bracket_match.winner = winner.clone();
+                bracket_match.scores = final_scores.clone();
+                bracket_match.reason = Some(reason.clone());
+                (brackets.rooms.remove(room_id).expect("room was just found"), room_id)
+            }
+            _ => return,
+        };
+
+        // Sent under the lock, so a new stream's bracket and its first update never overlap.
+        // Nobody listening is fine; the bracket is still there for the next stream.
+        if let Some(bracket_match) = brackets.matches[&tournament].iter().find(|m| m.room_id == *room_id).cloned() {
+            let _ = self.updates.send((tournament, TournamentUpdate::MatchUpdated { bracket_match }));
+        }
+    }
+
+    fn bracket_match<'a>(brackets: &'a mut Brackets, room_id: &str) -> Option<&'a mut BracketMatch> {
+        let tournament = brackets.rooms.get(room_id)?;
+        brackets.matches.get_mut(tournament)?.iter_mut().find(|m| m.room_id == room_id)
+    }
+
+    /// The tournament's matches, or `None` if none was ever reserved for it.
+    pub fn bracket(&self, tournament: &str) -> Option<Vec<BracketMatch>> {
+        self.brackets.lock().matches.get(tournament).cloned()
+    }
+
+    /// The current bracket, then every update to it as it happens.
+    pub fn subscribe(&self, tournament: &str) -> Option<(Vec<BracketMatch>, TournamentUpdates)> {
+        let brackets = self.brackets.lock();
+        let matches = brackets.matches.get(tournament)?.clone();
+        Some((matches, self.updates.subscribe()))
+    }
+}
+
+/// `GET /tournaments/{id}/stream`: server-sent events with the tournament's bracket, then
+/// each match as it is reserved, starts a round or ends. Not found until a match has been
+/// reserved for the tournament.
+pub fn create_tournament_routes(
+    feed: Arc<TournamentFeed>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("tournaments" / String / "stream")
+        .and(warp::get())
+        .and(warp::any().map(move || feed.clone()))
+        .and_then(stream_handler)
+}
+
+async fn stream_handler(tournament: String, feed: Arc<TournamentFeed>) -> Result<impl warp::Reply, warp::Rejection> {
+    let Some((matches, receiver)) = feed.subscribe(&tournament) else {
+        return Err(warp::reject::not_found());
+    };
+
+    let first = Some(TournamentUpdate::Bracket { matches });
+    let updates = futures_util::stream::unfold((first, receiver), move |(first, mut receiver)| {
+        let feed = feed.clone();
+        let tournament = tournament.clone();
+        async move {
+            if let Some(first) = first {
+                return Some((sse_event(&first), (None, receiver)));
+            }
+            loop {
+                let update = match receiver.recv().await {
+                    Ok((id, update)) if id == tournament => update,
+                    Ok(_) => continue,
+                    // Too far behind to catch up update by update; start over from the bracket
+                    Err(RecvError::Lagged(_)) => TournamentUpdate::Bracket {
+                        matches: feed.bracket(&tournament).unwrap_or_default(),
+                    },
+                    Err(RecvError::Closed) => return None,
+                };
+                return Some((sse_event(&update), (None, receiver)));
+            }
+        }
+    });
+    Ok(warp::sse::reply(warp::sse::keep_alive().interval(KEEP_ALIVE).stream(updates)))
+}
+
+fn sse_event(update: &TournamentUpdate) -> Result<warp::sse::Event, Infallible> {
+    Ok(warp::sse::Event::default()
+        .event(update.event_name())
+        .json_data(update)
+        .expect("tournament updates serialize"))
+}
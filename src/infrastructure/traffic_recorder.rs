@@ -0,0 +1,215 @@
+use anyhow::Result;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+use crate::config::TrafficRecordingConfig;
+use crate::domain::ClientMessage;
+
+/// One line of a recording: a message a client sent, or the connection closing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedFrame {
+    pub connection: u64, // Numbered in order of first message, not the server's correlation id
+    pub at_ms: u64,      // Since the recording started
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub message: Option<ClientMessage>, // None: the client disconnected
+}
+
+#[derive(Default)]
+struct Pseudonyms {
+    connections: HashMap<String, u64>,
+    players: HashMap<String, String>,
+    rooms: HashMap<String, String>,
+    notifications: HashMap<String, String>,
+}
+
+impl Pseudonyms {
+    fn alias(names: &mut HashMap<String, String>, prefix: &str, real: &str) -> String {
+        let next = names.len() + 1;
+        names.entry(real.to_string()).or_insert_with(|| format!("{}-{}", prefix, next)).clone()
+    }
+
+    /// The message with every player, room and notification id swapped for a stable pseudonym.
+    fn anonymize(&mut self, message: &ClientMessage) -> ClientMessage {
+        let mut message = message.clone();
+        match &mut message {
+            ClientMessage::Connect { player_id, reconnect_token, display_name, invite, .. } => {
+                if let Some(id) = player_id {
+                    *id = Self::alias(&mut self.players, "player", id);
+                }
+                *reconnect_token = None; // Carries the real player id
+                *invite = None;          // Names a real room
+                *display_name = None;
+            }
+            ClientMessage::Chat { text } => *text = "x".repeat(text.chars().count()),
+            ClientMessage::JoinRoom { room } => *room = Self::alias(&mut self.rooms, "room", room),
+            ClientMessage::AckNotifications { ids } => {
+                for id in ids {
+                    *id = Self::alias(&mut self.notifications, "notification", id);
+                }
+            }
+            _ => {}
+        }
+        message
+    }
+}
+
+/// Appends every client message to a JSON-lines file from a background thread, with
+/// ids replaced by pseudonyms and nothing about the client's address. Server replies
+/// aren't recorded; replaying the inputs reproduces them.
+pub struct TrafficRecorder {
+    started: Instant,
+    pseudonyms: Mutex<Pseudonyms>,
+    frames: mpsc::Sender<RecordedFrame>,
+    recorded: AtomicU64,
+    max_frames: u64,
+}
+
+impl TrafficRecorder {
+    /// None unless `config.path` is set.
+    pub fn new(config: &TrafficRecordingConfig) -> Result<Option<Self>> {
+        let Some(path) = &config.path else { return Ok(None) };
+        let path = Path::new(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (frames, received) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("traffic-recorder".to_string())
+            .spawn(move || write_frames(BufWriter::new(file), received))?;
+        info!("🎙️  Recording client traffic to {}", path.display());
+
+        Ok(Some(Self {
+            started: Instant::now(),
+            pseudonyms: Mutex::new(Pseudonyms::default()),
+            frames,
+            recorded: AtomicU64::new(0),
+            max_frames: config.max_frames.unwrap_or(u64::MAX),
+        }))
+    }
+
+    pub fn record(&self, connection_id: &str, message: &ClientMessage) {
+        if !self.reserve() {
+            return;
+        }
+        let (connection, message) = {
+            let mut pseudonyms = self.pseudonyms.lock();
+            let next = pseudonyms.connections.len() as u64 + 1;
+            let connection = *pseudonyms.connections.entry(connection_id.to_string()).or_insert(next);
+            (connection, pseudonyms.anonymize(message))
+        };
+        self.send(connection, Some(message));
+    }
+
+    pub fn closed(&self, connection_id: &str) {
+        let connection = self.pseudonyms.lock().connections.get(connection_id).copied();
+        if let Some(connection) = connection {
+            // Still written past max_frames, so every recorded connection has an end
+            self.send(connection, None);
+        }
+    }
+
+    fn reserve(&self) -> bool {
+        self.recorded.fetch_add(1, Ordering::Relaxed) < self.max_frames
+    }
+
+    fn send(&self, connection: u64, message: Option<ClientMessage>) {
+        let frame = RecordedFrame {
+            connection,
+            at_ms: self.started.elapsed().as_millis() as u64,
+            message,
+        };
+        // Only fails once the writer thread has died, which it already logged
+        let _ = self.frames.send(frame);
+    }
+}
+
+fn write_frames(mut file: BufWriter<std::fs::File>, frames: mpsc::Receiver<RecordedFrame>) {
+    loop {
+        let frame = match frames.recv_timeout(Duration::from_secs(1)) {
+            Ok(frame) => frame,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let _ = file.flush();
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        let written = serde_json::to_writer(&mut file, &frame)
+            .map_err(std::io::Error::from)
+            .and_then(|_| file.write_all(b"\n"));
+        if let Err(e) = written {
+            error!("Traffic recording stopped: {}", e);
+            return;
+        }
+    }
+    let _ = file.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::TrafficRecordingConfig;
+    use crate::domain::{ClientMessage, GameMode};
+    use crate::infrastructure::{RecordedFrame, TrafficRecorder};
+
+    #[test]
+    fn test_traffic_recorder_writes_anonymized_frames_per_connection() {
+        let path = std::env::temp_dir().join(format!("rps-traffic-{}.jsonl", uuid::Uuid::new_v4()));
+        assert!(TrafficRecorder::new(&TrafficRecordingConfig::default()).unwrap().is_none());
+
+        let recorder = TrafficRecorder::new(&TrafficRecordingConfig {
+            path: Some(path.to_string_lossy().into_owned()),
+            max_frames: Some(4),
+        })
+        .unwrap()
+        .unwrap();
+        let connect = |player: &str| ClientMessage::Connect {
+            player_id: Some(player.to_string()),
+            locale: None,
+            client_version: None,
+            protocol_version: None,
+            reconnect_token: Some("node-a.claims.mac".to_string()),
+            display_name: None,
+            region: None,
+            experiments: Vec::new(),
+            tenant: None,
+            invite: None,
+        };
+        recorder.record("conn-a", &connect("alice@example.com"));
+        recorder.record("conn-b", &connect("bob@example.com"));
+        recorder.record("conn-a", &ClientMessage::JoinRoom { room: "secret-room".to_string() });
+        recorder.record("conn-b", &ClientMessage::FindMatch { mode: GameMode::default(), game: Default::default(), template: None });
+        recorder.record("conn-b", &ClientMessage::PauseRequest); // Past max_frames
+        recorder.closed("conn-a");
+        recorder.closed("conn-unknown");
+        drop(recorder);
+
+        // The writer thread flushes once the recorder is gone
+        let mut frames = Vec::new();
+        for _ in 0..50 {
+            let text = std::fs::read_to_string(&path).unwrap_or_default();
+            frames = text.lines().map(|line| serde_json::from_str::<RecordedFrame>(line).unwrap()).collect();
+            if frames.len() == 5 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames.iter().map(|frame| frame.connection).collect::<Vec<_>>(), vec![1, 2, 1, 2, 1]);
+        assert!(frames.windows(2).all(|pair| pair[0].at_ms <= pair[1].at_ms));
+        assert!(matches!(&frames[0].message, Some(ClientMessage::Connect { player_id: Some(id), reconnect_token: None, .. }) if id == "player-1"));
+        assert!(matches!(&frames[1].message, Some(ClientMessage::Connect { player_id: Some(id), .. }) if id == "player-2"));
+        assert!(matches!(&frames[2].message, Some(ClientMessage::JoinRoom { room }) if room == "room-1"));
+        assert!(matches!(frames[3].message, Some(ClientMessage::FindMatch { .. })));
+        assert!(frames[4].message.is_none());
+    }
+}
@@ -1,15 +1,12 @@
 use anyhow::Result;
 use crossbeam::queue::SegQueue;
 use dashmap::DashMap;
-use flume::{Receiver, Sender};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
-use tokio::net::TcpStream;
 use tokio::sync::Semaphore;
 use tokio::time::interval;
-use tracing::{info, warn};
-use uuid::Uuid;
+use tracing::info;
 
 // Ultra-fast connection pool with zero-allocation design
 pub struct UltraConnectionPool {
@@ -175,7 +172,7 @@ impl UltraConnectionPool {
                 let mut cleaned_up = 0;
                 
                 // Process cleanup queue
-                while let Some(connection_id) = cleanup_queue.pop() {
+                while cleanup_queue.pop().is_some() {
                     // Connection already removed, just count it
                     cleaned_up += 1;
                 }
@@ -72,6 +72,12 @@ static MESSAGE_POOL: Lazy<Arc<SegQueue<MessageFrame>>> = Lazy::new(|| {
     pool
 });
 
+impl Default for UltraMessageProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UltraMessageProcessor {
     pub fn new() -> Self {
         let (broadcast_sender, broadcast_receiver) = flume::unbounded();
@@ -128,6 +134,11 @@ impl UltraMessageProcessor {
                 // Ultra-fast connect processing
                 Ok(Some(ServerMessage::Connected {
                     player_id: uuid::Uuid::new_v4().to_string(),
+                    locale: crate::infrastructure::DEFAULT_LOCALE.to_string(),
+                    protocol_version: None,
+                    display_name: None,
+                    build: None,
+                    resume_token: None,
                 }))
             }
             MessageType::FindMatch => {
@@ -209,20 +220,14 @@ impl UltraMessageProcessor {
         let processed = self.processed_messages.load(Ordering::Relaxed);
         let total_time_ns = self.processing_time_ns.load(Ordering::Relaxed);
         
-        let avg_processing_time_ns = if processed > 0 {
-            total_time_ns / processed
-        } else {
-            0
-        };
+        let avg_processing_time_ns = total_time_ns.checked_div(processed).unwrap_or(0);
         
         UltraProcessorMetrics {
             processed_messages: processed,
             average_processing_time_ns: avg_processing_time_ns,
-            messages_per_second: if total_time_ns > 0 {
-                (processed * 1_000_000_000) / total_time_ns
-            } else {
-                0
-            },
+            messages_per_second: (processed * 1_000_000_000)
+                .checked_div(total_time_ns)
+                .unwrap_or(0),
             queue_sizes: QueueSizes {
                 incoming: self.incoming_queue.len(),
                 outgoing: self.outgoing_queue.len(),
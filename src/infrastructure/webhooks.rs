@@ -0,0 +1,269 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request, StatusCode, Uri};
+use hyper_rustls::HttpsConnector;
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::application::EventBus;
+use crate::config::{Secret, WebhooksConfig};
+use crate::domain::EventEnvelope;
+
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-rps-signature";
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "x-rps-timestamp";
+pub const WEBHOOK_EVENT_HEADER: &str = "x-rps-event";
+pub const WEBHOOK_DELIVERY_HEADER: &str = "x-rps-delivery";
+
+struct WebhookEndpoint {
+    url: Uri,
+    events: Vec<String>,
+    secret: Secret,
+}
+
+impl WebhookEndpoint {
+    fn accepts(&self, kind: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|event| event == kind)
+    }
+}
+
+/// POSTs event bus traffic to configured endpoints, signed and retried with exponential backoff.
+pub struct WebhookDispatcher {
+    endpoints: Vec<Arc<WebhookEndpoint>>,
+    client: Client<HttpsConnector<HttpConnector>>,
+    max_retries: u32,
+    initial_backoff: Duration,
+    request_timeout: Duration,
+}
+
+impl WebhookDispatcher {
+    pub fn new(config: &WebhooksConfig) -> Result<Self> {
+        let endpoints = config
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                let url = endpoint
+                    .url
+                    .parse()
+                    .with_context(|| format!("Invalid webhook URL {}", endpoint.url))?;
+                let secret = endpoint
+                    .signing_secret
+                    .load()?
+                    .into_iter()
+                    .next()
+                    .with_context(|| format!("No signing secret configured for webhook {}", endpoint.url))?;
+                Ok(Arc::new(WebhookEndpoint {
+                    url,
+                    events: endpoint.events.clone(),
+                    secret,
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            endpoints,
+            client: outbound_client(),
+            max_retries: config.max_retries,
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            request_timeout: Duration::from_millis(config.request_timeout_ms),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+
+    /// Delivers every matching event until the bus is dropped.
+    pub fn spawn(self, events: &EventBus) -> JoinHandle<()> {
+        let mut receiver = events.subscribe();
+        let dispatcher = Arc::new(self);
+
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(envelope) => dispatcher.dispatch(envelope),
+                    Err(RecvError::Lagged(skipped)) => warn!("Webhook dispatcher lagged; {} events dropped", skipped),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    fn dispatch(self: &Arc<Self>, envelope: EventEnvelope) {
+        let kind = envelope.event.kind();
+        let body = match serde_json::to_vec(&envelope) {
+            Ok(body) => Arc::new(body),
+            Err(e) => {
+                warn!("Failed to serialize {} event: {}", kind, e);
+                return;
+            }
+        };
+
+        // Each endpoint retries independently so one slow receiver can't delay the others
+        for endpoint in self.endpoints.iter().filter(|endpoint| endpoint.accepts(kind)) {
+            let dispatcher = self.clone();
+            let endpoint = endpoint.clone();
+            let envelope_id = envelope.id.clone();
+            let body = body.clone();
+            tokio::spawn(async move { dispatcher.deliver(&endpoint, kind, &envelope_id, &body).await });
+        }
+    }
+
+    async fn deliver(&self, endpoint: &WebhookEndpoint, kind: &str, delivery_id: &str, body: &[u8]) {
+        let mut backoff = self.initial_backoff;
+
+        for attempt in 0..=self.max_retries {
+            match self.send(endpoint, kind, delivery_id, body).await {
+                Ok(status) if status.is_success() => {
+                    info!("Webhook {} delivered {} {}", endpoint.url, kind, delivery_id);
+                    return;
+                }
+                Ok(status) if !is_retryable(status) => {
+                    warn!("Webhook {} rejected {} {} with {}", endpoint.url, kind, delivery_id, status);
+                    return;
+                }
+                Ok(status) => warn!("Webhook {} returned {} (attempt {})", endpoint.url, status, attempt + 1),
+                Err(e) => warn!("Webhook {} failed: {} (attempt {})", endpoint.url, e, attempt + 1),
+            }
+
+            if attempt < self.max_retries {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+
+        warn!(
+            "Webhook {} gave up on {} {} after {} attempts",
+            endpoint.url,
+            kind,
+            delivery_id,
+            self.max_retries + 1
+        );
+    }
+
+    async fn send(&self, endpoint: &WebhookEndpoint, kind: &str, delivery_id: &str, body: &[u8]) -> Result<StatusCode> {
+        // Signed per attempt so receivers can enforce a freshness window on the timestamp
+        let timestamp = Utc::now().timestamp().to_string();
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(endpoint.url.clone())
+            .header("content-type", "application/json")
+            .header(WEBHOOK_EVENT_HEADER, kind)
+            .header(WEBHOOK_DELIVERY_HEADER, delivery_id)
+            .header(WEBHOOK_TIMESTAMP_HEADER, &timestamp)
+            .header(WEBHOOK_SIGNATURE_HEADER, sign_webhook(&endpoint.secret, &timestamp, body))
+            .body(Body::from(body.to_vec()))?;
+
+        let response = tokio::time::timeout(self.request_timeout, self.client.request(request))
+            .await
+            .context("Webhook request timed out")??;
+        Ok(response.status())
+    }
+}
+
+/// HTTP(S) client for calls to external services.
+pub(crate) fn outbound_client() -> Client<HttpsConnector<HttpConnector>> {
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_webpki_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    Client::builder().build(connector)
+}
+
+/// `sha256=<hex>` HMAC over `"{timestamp}.{body}"`.
+pub fn sign_webhook(secret: &Secret, timestamp: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.expose().as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::REQUEST_TIMEOUT
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use warp::Filter;
+
+    use crate::application::EventBus;
+    use crate::config::{Secret, SecretSource, WebhookEndpointConfig, WebhooksConfig};
+    use crate::domain::{GameEndReason, GameEvent};
+    use crate::infrastructure::{sign_webhook, WebhookDispatcher};
+
+    #[tokio::test]
+    async fn test_webhooks_are_signed_filtered_and_retried() {
+        let attempts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (delivered_tx, mut delivered_rx) = tokio::sync::mpsc::unbounded_channel();
+        let receiver = {
+            let attempts = attempts.clone();
+            warp::post()
+                .and(warp::header::<String>("x-rps-event"))
+                .and(warp::header::<String>("x-rps-timestamp"))
+                .and(warp::header::<String>("x-rps-signature"))
+                .and(warp::body::bytes())
+                .map(move |event: String, timestamp: String, signature: String, body: bytes::Bytes| {
+                    // Fail the first attempt to exercise the retry path
+                    if attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                        return warp::http::StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    let _ = delivered_tx.send((event, timestamp, signature, body));
+                    warp::http::StatusCode::OK
+                })
+        };
+        let (addr, server) = warp::serve(receiver).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        std::env::set_var("RPS_TEST_WEBHOOK_SECRET", "hook-secret");
+        let config = WebhooksConfig {
+            endpoints: vec![WebhookEndpointConfig {
+                url: format!("http://{}/hook", addr),
+                events: vec!["GameEnded".to_string()],
+                signing_secret: SecretSource {
+                    env: Some("RPS_TEST_WEBHOOK_SECRET".to_string()),
+                    file: None,
+                },
+            }],
+            initial_backoff_ms: 10,
+            ..WebhooksConfig::default()
+        };
+        let bus = EventBus::new();
+        WebhookDispatcher::new(&config).unwrap().spawn(&bus);
+
+        bus.publish(GameEvent::MatchCreated {
+            room_id: "room-1".to_string(),
+            players: vec!["p1".to_string(), "p2".to_string()],
+            max_rounds: 3,
+        });
+        bus.publish(GameEvent::GameEnded {
+            room_id: "room-1".to_string(),
+            game_id: "game-1".to_string(),
+            winner: Some("p1".to_string()),
+            final_scores: Default::default(),
+            reason: GameEndReason::Completed,
+            teams: Default::default(),
+            signature: None,
+        });
+
+        let (event, timestamp, signature, body) =
+            tokio::time::timeout(std::time::Duration::from_secs(5), delivered_rx.recv()).await.unwrap().unwrap();
+        assert_eq!(event, "GameEnded");
+        assert_eq!(signature, sign_webhook(&Secret::new("hook", "hook-secret"), &timestamp, &body));
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["type"], "gameEnded");
+        assert_eq!(payload["roomId"], "room-1");
+    }
+}
@@ -1,68 +1,344 @@
 use anyhow::Result;
-use futures_util::{SinkExt, StreamExt};
+use chrono::Utc;
+use futures_util::stream::SplitSink;
+use futures_util::{FutureExt, SinkExt, StreamExt};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::net::TcpStream;
-use tokio::sync::mpsc;
-use tokio_tungstenite::{accept_async, tungstenite::Message};
-use tracing::{error, info};
+use std::time::Duration;
+use parking_lot::RwLock;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio::time::{timeout, timeout_at, Instant};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::{accept_hdr_async_with_config, WebSocketStream};
+use tokio_tungstenite::tungstenite::error::{CapacityError, Error as WsError};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::{CloseFrame, WebSocketConfig as ProtocolConfig};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, info, info_span, warn, Instrument};
 use uuid::Uuid;
 
-use crate::application::GameManager;
-use crate::domain::{ClientMessage, Player, ServerMessage};
+use super::buffer_pool::BufferPool;
+use super::client_metrics::{ClientMetrics, CLIENT_METRICS};
+use super::codec::{preflight, FrameRejected, PREFLIGHT_REJECTS};
+use super::cluster::{Cluster, CLUSTER_PATH, CLUSTER_TOKEN_HEADER};
+use super::i18n::{Catalog, MessageKey};
+use super::invites::Invites;
+use super::notification_inbox::NotificationInbox;
+use super::protocol_state::ConnectionSession;
+use super::resume_tokens::ResumeTokens;
+use super::socket_io::{self, Framing, SocketIoInbound, SOCKET_IO_PATH};
+use super::traffic_recorder::TrafficRecorder;
+use super::writer_pool::WriterPool;
+use crate::application::{panic_message, ChatRefused, GameManager, InvalidRules, MoveAlreadySubmitted, MoveRefused, QuitCooldown, QuotaExceeded, Screened};
+use crate::config::{InvitesConfig, NotificationsConfig, OutboundBatchConfig, WebSocketConfig};
+use crate::domain::{
+    BuildInfo, ClientMessage, ClientVersion, ExperimentFlags, GameEvent, GameMode, GameType, LatencyEstimate, Player, PlayerPhase, Region, RuleSet, ServerMessage, Tenant, BATCHED_FRAMES_VERSION,
+    MAX_DISPLAY_NAME_CHARS, PROTOCOL_VERSION,
+};
+
+/// Connections closed because the upgrade or the first `Connect` took too long.
+pub static STALLED_HANDSHAKES: AtomicU64 = AtomicU64::new(0);
+
+/// Connections dropped because they couldn't keep up with outbound traffic.
+pub static SLOW_CLIENT_EVICTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Binary frames received; the protocol is JSON text only, so each one is answered with an error.
+pub static UNEXPECTED_FRAMES: AtomicU64 = AtomicU64::new(0);
+
+/// Outbound frames that carried several messages, and the messages they carried.
+pub static BATCHED_FRAMES: AtomicU64 = AtomicU64::new(0);
+pub static BATCHED_MESSAGES: AtomicU64 = AtomicU64::new(0);
+
+/// Connection tasks that panicked. One that panics handling a message is closed and its
+/// player removed from their room and queue, as on any disconnect.
+pub static CONNECTION_PANICS: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Clone)]
 pub struct WebSocketHandler {
     game_manager: Arc<GameManager>,
+    config: WebSocketConfig,
+    catalog: Arc<Catalog>,
+    notifications: Arc<NotificationInbox>,
+    writers: Arc<WriterPool>,
+    recorder: Option<Arc<TrafficRecorder>>,
+    cluster: Option<Arc<Cluster>>,
+    invites: Arc<Invites>,
+    resume_tokens: Arc<ResumeTokens>,
 }
 
 impl WebSocketHandler {
-    pub fn new(game_manager: Arc<GameManager>) -> Self {
-        Self { game_manager }
+    pub fn new(game_manager: Arc<GameManager>, config: WebSocketConfig) -> Self {
+        Self {
+            game_manager,
+            catalog: Arc::new(Catalog::builtin()),
+            notifications: Arc::new(NotificationInbox::in_memory(
+                NotificationsConfig::default().max_pending_per_player,
+            )),
+            writers: Arc::new(WriterPool::new(config.writer_shards)),
+            recorder: None,
+            cluster: None,
+            invites: Arc::new(Invites::with_keys(InvitesConfig::default(), Vec::new())),
+            resume_tokens: Arc::new(ResumeTokens::with_keys(Vec::new())),
+            config,
+        }
+    }
+
+    pub fn with_catalog(mut self, catalog: Arc<Catalog>) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    pub fn with_notifications(mut self, notifications: Arc<NotificationInbox>) -> Self {
+        self.notifications = notifications;
+        self
+    }
+
+    pub fn with_recorder(mut self, recorder: Arc<TrafficRecorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Shares presence and matchmaking with peers, and accepts their links on `CLUSTER_PATH`.
+    pub fn with_cluster(mut self, cluster: Arc<Cluster>) -> Self {
+        self.cluster = Some(cluster);
+        self
+    }
+
+    /// Signs invite links with the configured keys rather than a per-process one.
+    pub fn with_invites(mut self, invites: Arc<Invites>) -> Self {
+        self.invites = invites;
+        self
+    }
+
+    /// Signs `resumeToken`s with the configured keys rather than a per-process one.
+    pub fn with_resume_tokens(mut self, resume_tokens: Arc<ResumeTokens>) -> Self {
+        self.resume_tokens = resume_tokens;
+        self
     }
 
-    pub async fn handle_connection(&self, raw_stream: TcpStream) -> Result<()> {
-        let ws_stream = accept_async(raw_stream).await?;
+    /// Serves one client over any byte stream: plain TCP, or TLS from a `WsListener`.
+    pub async fn handle_connection<S>(&self, raw_stream: S) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        // Correlation id for this connection: on every log line and on error messages
+        let connection_id = new_correlation_id();
+        let span = info_span!("ws", conn = %connection_id);
+
+        self.run_connection(raw_stream, connection_id).instrument(span).await
+    }
+
+    async fn run_connection<S>(&self, raw_stream: S, connection_id: String) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        // The same budget covers the upgrade and the first Connect message
+        let handshake_timeout = Duration::from_millis(self.config.connection_timeout_ms);
+        let connect_deadline = Instant::now() + handshake_timeout;
+
+        let mut header_version = None;
+        let mut framing = Framing::Native;
+        let mut peer_link = false;
+        // The error type is fixed by tungstenite's handshake callback
+        #[allow(clippy::result_large_err)]
+        let capture_version = |request: &Request, response: Response| {
+            if self.config.socket_io_compat && request.uri().path().starts_with(SOCKET_IO_PATH) {
+                framing = Framing::SocketIo;
+            }
+            let headers = request.headers();
+            if let Some(cluster) = self.cluster.as_ref().filter(|_| request.uri().path() == CLUSTER_PATH) {
+                let token = headers.get(CLUSTER_TOKEN_HEADER).and_then(|value| value.to_str().ok());
+                if !cluster.authorize(token) {
+                    warn!("Rejected cluster link with a missing or unknown token");
+                    let mut rejection = ErrorResponse::new(Some("unknown cluster token".to_string()));
+                    *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+                    return Err(rejection);
+                }
+                peer_link = true;
+            }
+            header_version = headers
+                .get("x-client-version")
+                .or_else(|| headers.get("user-agent"))
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            Ok(response)
+        };
+        let accept = accept_hdr_async_with_config(raw_stream, capture_version, Some(self.protocol_config()));
+        let ws_stream = match timeout(handshake_timeout, accept).await {
+            Ok(ws_stream) => ws_stream?,
+            Err(_) => {
+                STALLED_HANDSHAKES.fetch_add(1, Ordering::Relaxed);
+                warn!("WebSocket upgrade not completed within {:?}; closing", handshake_timeout);
+                return Ok(());
+            }
+        };
+        if let Some(cluster) = self.cluster.as_ref().filter(|_| peer_link) {
+            return cluster.clone().serve_peer(ws_stream, self).await;
+        }
+
+        // May be replaced by the version declared at Connect
+        let mut session = ConnectionSession::new(
+            ClientMetrics::bucket(header_version.as_deref()),
+            self.config.json_parser,
+        );
+        CLIENT_METRICS.connected(&session.client_version);
         let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
-        let mut player_id: Option<String> = None;
+        let ping_interval = Duration::from_millis(self.config.keepalive_interval_ms);
+        if framing == Framing::SocketIo {
+            let open = socket_io::open_packet(&connection_id, ping_interval.as_millis() as u64, self.config.max_message_size);
+            ws_sender.send(Message::Text(open)).await?;
+        }
+
+        // Default until the client declares one at Connect; shared with the sender for system text
+        let locale = Arc::new(RwLock::new(self.catalog.negotiate(None)));
 
         // Create a channel for sending messages to this client
-        let (tx, mut rx) = mpsc::unbounded_channel::<ServerMessage>();
+        let (tx, rx) = mpsc::unbounded_channel::<ServerMessage>();
 
         info!("New WebSocket client connected");
 
-        // Spawn a task to handle outgoing messages
-        let sender_task = tokio::spawn(async move {
-            while let Some(message) = rx.recv().await {
-                let json = match serde_json::to_string(&message) {
-                    Ok(json) => json,
-                    Err(e) => {
-                        error!("Failed to serialize message: {}", e);
-                        continue;
-                    }
-                };
+        // Lets the receive loop close the socket with a specific code once queued messages are flushed
+        let (close_tx, close_rx) = oneshot::channel::<CloseFrame<'static>>();
 
-                if let Err(e) = ws_sender.send(Message::Text(json)).await {
-                    error!("Failed to send WebSocket message: {}", e);
-                    break;
-                }
-            }
-        });
+        // Set by the sender task when the client can't keep up
+        let evicted = Arc::new(Notify::new());
+
+        // Set once the client declares a protocol version that reads batched frames
+        let batching = Arc::new(AtomicBool::new(false));
+
+        // Outgoing messages are written by one of the pool's shared writer tasks
+        let mut sender_task = self.writers.spawn(
+            run_sender(
+                ws_sender,
+                framing,
+                ping_interval,
+                session.latency.clone(),
+                rx,
+                close_rx,
+                SlowClientMonitor::new(&self.config),
+                evicted.clone(),
+                self.catalog.clone(),
+                locale.clone(),
+                self.config.outbound_batch.clone(),
+                batching.clone(),
+            )
+            .in_current_span(),
+        );
 
         // Handle incoming messages
-        while let Some(message) = ws_receiver.next().await {
+        loop {
+            let receive = async {
+                match session.player_id {
+                    Some(_) => Ok(ws_receiver.next().await),
+                    None => timeout_at(connect_deadline, ws_receiver.next()).await,
+                }
+            };
+            let next = tokio::select! {
+                next = receive => next,
+                _ = evicted.notified() => break,
+            };
+            let next = match next {
+                Ok(next) => next,
+                Err(_) => {
+                    STALLED_HANDSHAKES.fetch_add(1, Ordering::Relaxed);
+                    warn!("No Connect received within {:?}; closing", handshake_timeout);
+                    let error_msg = self.error(&locale.read(), MessageKey::ConnectTimeout);
+                    let _ = tx.send(error_msg.with_request_id(&connection_id));
+
+                    // Nothing else holds the sender yet, so this lets the error flush and the socket close
+                    drop(tx);
+                    let _ = timeout(Duration::from_secs(1), sender_task).await;
+                    return Ok(());
+                }
+            };
+            let Some(message) = next else {
+                break;
+            };
+
             match message {
                 Ok(Message::Text(text)) => {
-                    if let Err(e) = self.handle_text_message(&text, &mut player_id, &tx).await {
-                        error!("Error handling message: {}", e);
-                        let error_msg = ServerMessage::Error {
-                            message: "Internal server error".to_string(),
-                        };
+                    let text = match framing {
+                        Framing::Native => text,
+                        Framing::SocketIo => match socket_io::decode(&text) {
+                            SocketIoInbound::Message(text) => text,
+                            SocketIoInbound::Disconnect => {
+                                info!("Client disconnected: {:?}", session.player_id);
+                                break;
+                            }
+                            SocketIoInbound::Pong => {
+                                session.latency.pong_received(None);
+                                continue;
+                            }
+                            SocketIoInbound::Ignored => continue,
+                        },
+                    };
+                    let handled = AssertUnwindSafe(self.process_text(&text, &connection_id, &mut session, &locale, &tx))
+                        .catch_unwind()
+                        .await;
+                    if let Err(panic) = handled {
+                        CONNECTION_PANICS.fetch_add(1, Ordering::Relaxed);
+                        error!(
+                            player = ?session.player_id,
+                            "Handler panicked on a message: {}; closing the connection",
+                            panic_message(panic.as_ref())
+                        );
+                        let error_msg = self.error(&locale.read(), MessageKey::InternalError).with_request_id(&connection_id);
                         let _ = tx.send(error_msg);
+                        let _ = close_tx.send(CloseFrame {
+                            code: CloseCode::Error,
+                            reason: "internal error".into(),
+                        });
+                        if timeout(Duration::from_secs(1), &mut sender_task).await.is_err() {
+                            sender_task.abort();
+                        }
+                        break;
                     }
+                    if framing == Framing::Native
+                        && self.config.outbound_batch.max_messages > 1
+                        && session.protocol_version.is_some_and(|version| version >= BATCHED_FRAMES_VERSION)
+                    {
+                        batching.store(true, Ordering::Relaxed);
+                    }
+                }
+                Ok(Message::Binary(data)) => {
+                    UNEXPECTED_FRAMES.fetch_add(1, Ordering::Relaxed);
+                    warn!("Rejected binary frame of {} bytes", data.len());
+                    let error_msg = self
+                        .error(&locale.read(), MessageKey::UnsupportedFrame)
+                        .with_request_id(&connection_id);
+                    let _ = tx.send(error_msg);
                 }
+                Ok(Message::Pong(payload)) => session.latency.pong_received(Some(&payload)),
                 Ok(Message::Close(_)) => {
-                    info!("Client disconnected: {:?}", player_id);
+                    info!("Client disconnected: {:?}", session.player_id);
+                    break;
+                }
+                Err(WsError::Capacity(CapacityError::MessageTooLong { size, max_size })) => {
+                    PREFLIGHT_REJECTS.record(FrameRejected::TooLarge { size, max: max_size });
+                    warn!("Rejected oversized message: {} > {} bytes", size, max_size);
+                    let args = [("size", size.to_string()), ("max", max_size.to_string())];
+                    let error_msg = self
+                        .catalog
+                        .error(&locale.read(), MessageKey::MessageTooLarge, &args)
+                        .with_request_id(&connection_id);
+                    let _ = tx.send(error_msg);
+                    let _ = close_tx.send(CloseFrame {
+                        code: CloseCode::Size,
+                        reason: "message too large".into(),
+                    });
+                    if timeout(Duration::from_secs(1), &mut sender_task).await.is_err() {
+                        sender_task.abort();
+                    }
+                    break;
+                }
+                Err(WsError::Utf8) => {
+                    // Tungstenite can't carry on after a text frame that isn't UTF-8
+                    PREFLIGHT_REJECTS.record(FrameRejected::InvalidUtf8);
+                    warn!("Closing after a text frame that isn't valid UTF-8");
                     break;
                 }
                 Err(e) => {
@@ -73,9 +349,14 @@ impl WebSocketHandler {
             }
         }
 
+        CLIENT_METRICS.disconnected(&session.client_version);
+        if let Some(recorder) = &self.recorder {
+            recorder.closed(&connection_id);
+        }
+
         // Clean up on disconnect
-        if let Some(id) = player_id {
-            if let Err(e) = self.game_manager.remove_player(&id).await {
+        if let Some(id) = session.player_id {
+            if let Err(e) = self.player_disconnected(&id, &tx).await {
                 error!("Failed to remove player {}: {}", id, e);
             }
         }
@@ -86,95 +367,773 @@ impl WebSocketHandler {
         Ok(())
     }
 
+    /// The connection behind `tx` closed.
+    pub(crate) async fn player_disconnected(&self, player_id: &str, tx: &mpsc::UnboundedSender<ServerMessage>) -> Result<()> {
+        if let Some(cluster) = &self.cluster {
+            cluster.disconnected(player_id, tx);
+        }
+        self.game_manager.disconnect_player(player_id).await
+    }
+
+    pub(crate) fn error(&self, locale: &str, key: MessageKey) -> ServerMessage {
+        self.catalog.error(locale, key, &[])
+    }
+
+    fn rejection(&self, locale: &str, rejected: FrameRejected) -> ServerMessage {
+        match rejected {
+            FrameRejected::TooLarge { size, max } => {
+                let args = [("size", size.to_string()), ("max", max.to_string())];
+                self.catalog.error(locale, MessageKey::MessageTooLarge, &args)
+            }
+            FrameRejected::UnknownType => self.error(locale, MessageKey::UnknownMessageType),
+            FrameRejected::InvalidUtf8 | FrameRejected::NotAnObject | FrameRejected::MissingType => {
+                self.error(locale, MessageKey::MalformedMessage)
+            }
+        }
+    }
+
+    fn protocol_config(&self) -> ProtocolConfig {
+        ProtocolConfig {
+            max_frame_size: Some(self.config.max_frame_size),
+            max_message_size: Some(self.config.max_message_size),
+            ..ProtocolConfig::default()
+        }
+    }
+
+    /// Handles one client message, answering internal failures with an error message and
+    /// recording it in the per-version client metrics. Shared by every transport.
+    pub(crate) async fn process_text(
+        &self,
+        text: &str,
+        connection_id: &str,
+        session: &mut ConnectionSession,
+        locale: &RwLock<String>,
+        tx: &mpsc::UnboundedSender<ServerMessage>,
+    ) {
+        let started = std::time::Instant::now();
+        if let Err(rejected) = preflight(text.as_bytes(), self.config.max_message_size) {
+            PREFLIGHT_REJECTS.record(rejected);
+            debug!("Rejected frame before parsing: {:?}", rejected);
+            let _ = tx.send(self.rejection(&locale.read(), rejected).with_request_id(connection_id));
+            CLIENT_METRICS.message(&session.client_version, started.elapsed(), true);
+            return;
+        }
+        let result = self
+            .handle_text_message(text, connection_id, session, locale, tx)
+            .await;
+        let failed = match result {
+            Ok(responded_with_error) => responded_with_error,
+            Err(e) => {
+                error!("Error handling message: {}", e);
+                let error_msg = self
+                    .error(&locale.read(), MessageKey::InternalError)
+                    .with_request_id(connection_id);
+                let _ = tx.send(error_msg);
+                true
+            }
+        };
+        CLIENT_METRICS.message(&session.client_version, started.elapsed(), failed);
+    }
+
+    pub(crate) fn default_locale(&self) -> String {
+        self.catalog.negotiate(None)
+    }
+
+    pub(crate) fn config(&self) -> &WebSocketConfig {
+        &self.config
+    }
+
+    pub(crate) fn game_manager(&self) -> &GameManager {
+        &self.game_manager
+    }
+
     async fn handle_text_message(
         &self,
         text: &str,
-        player_id: &mut Option<String>,
+        connection_id: &str,
+        session: &mut ConnectionSession,
+        locale: &RwLock<String>,
         tx: &mpsc::UnboundedSender<ServerMessage>,
-    ) -> Result<()> {
-        let client_msg: ClientMessage = serde_json::from_str(text)
+    ) -> Result<bool> {
+        let client_msg = session
+            .codec
+            .decode(text)
             .map_err(|e| anyhow::anyhow!("Failed to parse message: {}", e))?;
 
         info!("Received: {:?}", client_msg);
+        if let Some(recorder) = &self.recorder {
+            recorder.record(connection_id, &client_msg);
+        }
+
+        // A player whose game is hosted on a peer is served there, states and all
+        let forwarded = self
+            .cluster
+            .as_ref()
+            .zip(session.player_id.as_deref())
+            .is_some_and(|(cluster, id)| cluster.forward(id, connection_id, &client_msg));
+        if forwarded {
+            return Ok(false);
+        }
+
+        self.handle_message(client_msg, connection_id, session, locale, tx).await
+    }
+
+    /// Handles one decoded message for a session: checked against the session's state,
+    /// then answered through `tx`. Returns whether the answer was an error.
+    pub(crate) async fn handle_message(
+        &self,
+        client_msg: ClientMessage,
+        connection_id: &str,
+        session: &mut ConnectionSession,
+        locale: &RwLock<String>,
+        tx: &mpsc::UnboundedSender<ServerMessage>,
+    ) -> Result<bool> {
+        // Matchmaking and game end move the player on without a message from this connection
+        if let Some(id) = &session.player_id {
+            session.state = self.game_manager.player_phase(id).await.into();
+        }
+        if let Err(key) = session.state.check(&client_msg) {
+            debug!("Rejected {:?} in state {:?}", client_msg, session.state);
+            let error_msg = self.error(&locale.read(), key).with_request_id(connection_id);
+            tx.send(error_msg)
+                .map_err(|_| anyhow::anyhow!("Failed to send response"))?;
+            return Ok(true);
+        }
+
+        if let ClientMessage::Connect { locale: requested, client_version: declared, protocol_version, region, experiments, tenant, .. } = &client_msg {
+            *locale.write() = self.catalog.negotiate(requested.as_deref());
+            session.protocol_version = protocol_version.map(|version| version.min(PROTOCOL_VERSION));
+            if declared.is_some() {
+                let declared = ClientMetrics::bucket(declared.as_deref());
+                CLIENT_METRICS.reattribute(&session.client_version, &declared);
+                session.client_version = declared;
+            }
+            session.extensions.insert(ClientVersion(session.client_version.clone()));
+            if let Some(region) = region.as_deref().map(str::trim).filter(|region| !region.is_empty()) {
+                session.extensions.insert(Region(region.to_string()));
+            }
+            if !experiments.is_empty() {
+                session.extensions.insert(ExperimentFlags(experiments.iter().cloned().collect()));
+            }
+            if let Some(tenant) = tenant.as_deref().map(str::trim).filter(|tenant| !tenant.is_empty()) {
+                session.extensions.insert(Tenant(tenant.to_string()));
+            }
+        }
+        let locale = locale.read().clone();
+        let player_id = &session.player_id;
 
         let response = match client_msg {
-            ClientMessage::Connect { player_id: requested_id } => {
-                self.handle_connect(requested_id, player_id).await?
+            ClientMessage::Connect { player_id: requested_id, reconnect_token, display_name: requested_name, invite, .. } => {
+                match self.screen_display_name(requested_name) {
+                    Ok(name) => {
+                        let token = reconnect_token.as_deref();
+                        self.handle_connect(requested_id, token, name, session, connection_id, &locale, tx).await?;
+                        match invite {
+                            Some(invite) => self.handle_invite(self.session_player(session, tx), &invite, &locale).await?,
+                            None => None,
+                        }
+                    }
+                    Err(key) => Some(self.error(&locale, key)),
+                }
+            }
+            ClientMessage::FindMatch { mode, game, template } => {
+                let player = self.session_player(session, tx);
+                self.handle_find_match(player, mode, game, template.as_deref(), &locale).await?
             }
-            ClientMessage::FindMatch => {
-                self.handle_find_match(player_id, tx).await?
+            ClientMessage::JoinRoom { room } => {
+                let player = self.session_player(session, tx);
+                self.handle_join_room(player, &room, &locale).await?
+            }
+            ClientMessage::CreateInvite { template, rules } => {
+                let player = self.session_player(session, tx);
+                self.handle_create_invite(player, template.as_deref(), rules, &locale).await?
             }
             ClientMessage::PlayerMove { choice } => {
-                self.handle_player_move(player_id, choice).await?
+                self.handle_player_move(player_id, choice, &locale).await?
+            }
+            ClientMessage::PauseRequest => {
+                self.handle_pause_vote(player_id, true, &locale).await?
+            }
+            ClientMessage::ResumeRequest => {
+                self.handle_pause_vote(player_id, false, &locale).await?
+            }
+            ClientMessage::AckNotifications { ids } => {
+                self.handle_ack_notifications(player_id, &ids, &locale)
+            }
+            ClientMessage::BackfillResponse { accept } => {
+                self.handle_backfill_response(player_id, accept, &locale).await?
             }
+            ClientMessage::Chat { text } => {
+                self.handle_chat(player_id, &text, &locale).await
+            }
+            ClientMessage::RequestState => match player_id {
+                Some(id) => Some(self.game_manager.player_state(id).await),
+                None => Some(self.error(&locale, MessageKey::NotConnected)),
+            },
         };
 
+        if let Some(id) = &session.player_id {
+            session.state = self.game_manager.player_phase(id).await.into();
+        }
+
+        let is_error = matches!(response, Some(ServerMessage::Error { .. }));
         if let Some(response) = response {
-            tx.send(response)
+            tx.send(response.with_request_id(connection_id))
                 .map_err(|_| anyhow::anyhow!("Failed to send response"))?;
         }
 
-        Ok(())
+        Ok(is_error)
     }
 
+    /// A display name from `Connect`, trimmed and screened. Refused names fail the Connect.
+    fn screen_display_name(&self, name: Option<String>) -> Result<Option<Screened>, MessageKey> {
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_DISPLAY_NAME_CHARS || name.chars().any(char::is_control) {
+            return Err(MessageKey::InvalidDisplayName);
+        }
+        match self.game_manager.profanity_filter().screen(name) {
+            Screened::Rejected => Err(MessageKey::InappropriateLanguage),
+            screened => Ok(Some(screened)),
+        }
+    }
+
+    /// The session's player, once it has connected as one.
+    fn session_player(&self, session: &ConnectionSession, tx: &mpsc::UnboundedSender<ServerMessage>) -> Option<Arc<Player>> {
+        let id = session.player_id.as_ref()?;
+        let player = Player::new(id.clone(), tx.clone())
+            .with_latency(session.latency.clone())
+            .with_display_name(session.display_name.clone())
+            .with_extensions(session.extensions.clone());
+        Some(Arc::new(player))
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn handle_connect(
         &self,
         requested_id: Option<String>,
-        player_id: &mut Option<String>,
+        reconnect_token: Option<&str>,
+        requested_name: Option<Screened>,
+        session: &mut ConnectionSession,
+        connection_id: &str,
+        locale: &str,
+        tx: &mpsc::UnboundedSender<ServerMessage>,
     ) -> Result<Option<ServerMessage>> {
-        let id = requested_id.unwrap_or_else(|| Uuid::new_v4().to_string());
-        *player_id = Some(id.clone());
-        info!("Player connected with ID: {}", id);
+        // Only counts for the player it was issued to; resumes the game on the node it names
+        let claims = reconnect_token
+            .and_then(|token| self.cluster.as_ref()?.verify_token(token))
+            .filter(|claims| requested_id.as_ref().is_none_or(|id| *id == claims.player_id));
+        // An id with a seat or an inbox behind it is only handed to a connection proving it owns it
+        let proven = |id: &str| claims.is_some() || reconnect_token.is_some_and(|token| self.resume_tokens.verify(id, token));
+        let requested_id = match requested_id {
+            Some(id) if !proven(&id) && self.has_session_state(&id).await => {
+                warn!("Connect as {} without its resume token; connecting as a new player", id);
+                None
+            }
+            requested_id => requested_id,
+        };
+        let id = requested_id
+            .or_else(|| claims.as_ref().map(|claims| claims.player_id.clone()))
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        session.player_id = Some(id.clone());
+        if let Some(claims) = &claims {
+            session.extensions.insert(claims.clone());
+        }
+        // A shadow-muted name is echoed back as if accepted but never shown to anyone else
+        let accepted_name = match requested_name {
+            Some(Screened::Allowed(name)) => {
+                session.display_name = Some(name.clone());
+                Some(name)
+            }
+            Some(Screened::ShadowMuted(name)) => Some(name),
+            Some(Screened::Rejected) | None => None,
+        };
+        info!(
+            region = session.extensions.get::<Region>().map(|region| region.0.as_str()),
+            "Player connected with ID: {}", id
+        );
+        self.game_manager.events().publish(GameEvent::PlayerConnected { player_id: id.clone() });
+
+        // Acknowledge first so the client sees Connected before GameResumed
+        tx.send(ServerMessage::Connected {
+            player_id: id.clone(),
+            locale: locale.to_string(),
+            protocol_version: session.protocol_version,
+            display_name: accepted_name,
+            build: Some(BuildInfo::current().clone()),
+            resume_token: Some(self.resume_tokens.issue(&id)),
+        })
+            .map_err(|_| anyhow::anyhow!("Failed to send response"))?;
+
+        if let Some(cluster) = &self.cluster {
+            if cluster.adopt(&id) {
+                // Reconnected straight to the node hosting their game; the proxy goes first
+                self.game_manager.disconnect_player(&id).await?;
+            }
+            let resume_on = claims.as_ref().map(|claims| claims.node_id.as_str());
+            if !cluster.connected(&id, connection_id, locale, tx, resume_on) {
+                let error = self.error(locale, MessageKey::HostUnavailable).with_request_id(connection_id);
+                tx.send(error).map_err(|_| anyhow::anyhow!("Failed to send response"))?;
+            }
+        }
+        let player = self.session_player(session, tx).expect("player id was just set");
+        if let Some(room_id) = self.game_manager.reconnect_player(player).await? {
+            info!("Player {} resumed room {}", id, room_id);
+        }
+
+        // Stays in the inbox until the client acknowledges it, so a dropped connection redelivers
+        let notifications = self.notifications.pending(&id);
+        if !notifications.is_empty() {
+            info!("Delivering {} notifications to {}", notifications.len(), id);
+            tx.send(ServerMessage::Notifications { notifications })
+                .map_err(|_| anyhow::anyhow!("Failed to send response"))?;
+        }
 
-        Ok(Some(ServerMessage::Connected { player_id: id }))
+        Ok(None)
     }
 
-    async fn handle_find_match(
+    /// Whether `player_id` is queued, seated or has notifications waiting, so connecting as it
+    /// would take over someone's session.
+    async fn has_session_state(&self, player_id: &str) -> bool {
+        self.game_manager.player_phase(player_id).await != PlayerPhase::Idle || !self.notifications.pending(player_id).is_empty()
+    }
+
+    fn handle_ack_notifications(
         &self,
         player_id: &Option<String>,
-        tx: &mpsc::UnboundedSender<ServerMessage>,
+        ids: &[String],
+        locale: &str,
+    ) -> Option<ServerMessage> {
+        let Some(id) = player_id else {
+            return Some(self.error(locale, MessageKey::NotConnected));
+        };
+
+        match self.notifications.ack(id, ids) {
+            Ok(_) => None,
+            Err(e) => {
+                error!("Notification ack error: {}", e);
+                Some(self.error(locale, MessageKey::InternalError))
+            }
+        }
+    }
+
+    async fn handle_find_match(
+        &self,
+        player: Option<Arc<Player>>,
+        mode: GameMode,
+        game: GameType,
+        template: Option<&str>,
+        locale: &str,
     ) -> Result<Option<ServerMessage>> {
-        if let Some(ref id) = player_id {
-            let player = Arc::new(Player::new(id.clone(), tx.clone()));
+        if let Some(player) = player {
+            // Peers only pair rock-paper-scissors; other games and templates are always hosted here
+            let waiting = self.game_manager.waiting_players(mode);
+            if game.is_default() && template.is_none() && self.cluster.as_ref().is_some_and(|cluster| cluster.host_remotely(&player.id, mode, waiting)) {
+                return Ok(None); // The peer's answer is delivered like any other message
+            }
 
-            match self.game_manager.find_match(player).await {
+            let found = match template {
+                Some(template) => self.game_manager.find_match_on_template(player, template).await,
+                None => self.game_manager.find_match_for(player, mode, game).await,
+            };
+            match found {
                 Ok(msg) => Ok(Some(msg)),
                 Err(e) => {
+                    if let Some(quota) = e.downcast_ref::<QuotaExceeded>() {
+                        let args = [("quota", quota.quota().to_string()), ("limit", quota.limit().to_string())];
+                        return Ok(Some(self.catalog.error(locale, MessageKey::QuotaExceeded, &args)));
+                    }
+                    if let Some(cooldown) = e.downcast_ref::<QuitCooldown>() {
+                        let args = [("seconds", cooldown.seconds().to_string())];
+                        return Ok(Some(self.catalog.error(locale, MessageKey::QuitCooldown, &args)));
+                    }
                     error!("Find match error: {}", e);
-                    Ok(Some(ServerMessage::Error {
-                        message: "Failed to find match".to_string(),
-                    }))
+                    Ok(Some(self.error(locale, MessageKey::FindMatchFailed)))
                 }
             }
         } else {
-            Ok(Some(ServerMessage::Error {
-                message: "Not connected".to_string(),
-            }))
+            Ok(Some(self.error(locale, MessageKey::NotConnected)))
+        }
+    }
+
+    async fn handle_join_room(&self, player: Option<Arc<Player>>, room: &str, locale: &str) -> Result<Option<ServerMessage>> {
+        let Some(player) = player else {
+            return Ok(Some(self.error(locale, MessageKey::NotConnected)));
+        };
+
+        match self.game_manager.join_room(player, room).await {
+            Ok(Some(msg)) => Ok(Some(msg)),
+            Ok(None) => Ok(Some(self.error(locale, MessageKey::RoomNotFound))),
+            Err(e) => {
+                error!("Join room error: {}", e);
+                Ok(Some(self.error(locale, MessageKey::JoinRoomFailed)))
+            }
         }
     }
 
+    async fn handle_create_invite(
+        &self,
+        player: Option<Arc<Player>>,
+        template: Option<&str>,
+        rules: Option<RuleSet>,
+        locale: &str,
+    ) -> Result<Option<ServerMessage>> {
+        let Some(player) = player else {
+            return Ok(Some(self.error(locale, MessageKey::NotConnected)));
+        };
+
+        match self.game_manager.reserve_invite(player, template, rules).await {
+            Ok(reserved) => Ok(Some(self.invites.issue(&reserved))),
+            Err(e) => {
+                if let Some(invalid) = e.downcast_ref::<InvalidRules>() {
+                    return Ok(Some(self.catalog.error(locale, MessageKey::InvalidRules, &[("reason", invalid.reason.clone())])));
+                }
+                if let Some(quota) = e.downcast_ref::<QuotaExceeded>() {
+                    let args = [("quota", quota.quota().to_string()), ("limit", quota.limit().to_string())];
+                    return Ok(Some(self.catalog.error(locale, MessageKey::QuotaExceeded, &args)));
+                }
+                error!("Create invite error: {}", e);
+                Ok(Some(self.error(locale, MessageKey::JoinRoomFailed)))
+            }
+        }
+    }
+
+    /// Seats a player who connected with an invite token in the room it names.
+    async fn handle_invite(&self, player: Option<Arc<Player>>, token: &str, locale: &str) -> Result<Option<ServerMessage>> {
+        let Some(player) = player else {
+            return Ok(Some(self.error(locale, MessageKey::NotConnected)));
+        };
+        let Ok(claims) = self.invites.verify(token, Utc::now().timestamp_millis()) else {
+            return Ok(Some(self.error(locale, MessageKey::InviteInvalid)));
+        };
+
+        match self.game_manager.claim_invite_seat(player, &claims.join_code).await {
+            Ok(Some(msg)) => Ok(Some(msg)),
+            Ok(None) => Ok(Some(self.error(locale, MessageKey::InviteInvalid))),
+            Err(e) => {
+                error!("Invite error: {}", e);
+                Ok(Some(self.error(locale, MessageKey::JoinRoomFailed)))
+            }
+        }
+    }
+
+    async fn handle_backfill_response(
+        &self,
+        player_id: &Option<String>,
+        accept: bool,
+        locale: &str,
+    ) -> Result<Option<ServerMessage>> {
+        let Some(id) = player_id else {
+            return Ok(Some(self.error(locale, MessageKey::NotConnected)));
+        };
+
+        match self.game_manager.answer_backfill(id, accept).await {
+            Ok(Some(msg)) => Ok(Some(msg)),
+            Ok(None) => Ok(Some(self.error(locale, MessageKey::NoBackfillOffer))),
+            Err(e) => {
+                error!("Backfill response error: {}", e);
+                Ok(Some(self.error(locale, MessageKey::JoinRoomFailed)))
+            }
+        }
+    }
+
+    async fn handle_chat(&self, player_id: &Option<String>, text: &str, locale: &str) -> Option<ServerMessage> {
+        let Some(id) = player_id else {
+            return Some(self.error(locale, MessageKey::NotConnected));
+        };
+
+        let key = match self.game_manager.send_chat(id, text).await {
+            Ok(()) => return None,
+            Err(ChatRefused::Muted(muted)) => {
+                return Some(self.catalog.error(locale, MessageKey::Muted, &[("seconds", muted.seconds().to_string())]));
+            }
+            Err(ChatRefused::Invalid) => MessageKey::InvalidChatMessage,
+            Err(ChatRefused::NotInGame) => MessageKey::NotInGame,
+            Err(ChatRefused::Disabled) => MessageKey::ChatDisabled,
+            Err(ChatRefused::Inappropriate) => MessageKey::InappropriateLanguage,
+        };
+        Some(self.error(locale, key))
+    }
+
     async fn handle_player_move(
         &self,
         player_id: &Option<String>,
         choice: crate::domain::GameChoice,
+        locale: &str,
     ) -> Result<Option<ServerMessage>> {
         if let Some(ref id) = player_id {
             match self.game_manager.submit_move(id, choice).await {
                 Ok(true) => Ok(None), // Move processed successfully
-                Ok(false) => Ok(Some(ServerMessage::Error {
-                    message: "Invalid move".to_string(),
-                })),
+                Ok(false) => Ok(Some(self.error(locale, MessageKey::InvalidMove))),
+                Err(e) if e.is::<MoveAlreadySubmitted>() => Ok(Some(self.error(locale, MessageKey::MoveAlreadySubmitted))),
                 Err(e) => {
+                    if let Some(refused) = e.downcast_ref::<MoveRefused>() {
+                        let key = match refused {
+                            MoveRefused::NotSeated => MessageKey::NotInGame,
+                            MoveRefused::Paused => MessageKey::GamePaused,
+                            MoveRefused::RoundClosed => MessageKey::RoundClosed,
+                            MoveRefused::InvalidChoice => MessageKey::InvalidMove,
+                        };
+                        return Ok(Some(self.error(locale, key)));
+                    }
                     error!("Submit move error: {}", e);
-                    Ok(Some(ServerMessage::Error {
-                        message: "Failed to submit move".to_string(),
-                    }))
+                    Ok(Some(self.error(locale, MessageKey::SubmitMoveFailed)))
                 }
             }
         } else {
-            Ok(Some(ServerMessage::Error {
-                message: "Not connected".to_string(),
-            }))
+            Ok(Some(self.error(locale, MessageKey::NotConnected)))
+        }
+    }
+
+    async fn handle_pause_vote(
+        &self,
+        player_id: &Option<String>,
+        pause: bool,
+        locale: &str,
+    ) -> Result<Option<ServerMessage>> {
+        let Some(id) = player_id else {
+            return Ok(Some(self.error(locale, MessageKey::NotConnected)));
+        };
+
+        let result = if pause {
+            self.game_manager.request_pause(id).await
+        } else {
+            self.game_manager.request_resume(id).await
+        };
+
+        match result {
+            Ok(true) => Ok(None),
+            Ok(false) if pause => Ok(Some(self.error(locale, MessageKey::CannotPause))),
+            Ok(false) => Ok(Some(self.error(locale, MessageKey::NotPaused))),
+            Err(e) => {
+                error!("Pause vote error: {}", e);
+                Ok(Some(self.error(locale, MessageKey::PauseRequestFailed)))
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+type WsSink<S> = SplitSink<WebSocketStream<S>, Message>;
+
+#[allow(clippy::too_many_arguments)]
+async fn run_sender<S: AsyncRead + AsyncWrite + Unpin>(
+    mut ws_sender: WsSink<S>,
+    framing: Framing,
+    ping_interval: Duration,
+    latency: Arc<LatencyEstimate>,
+    mut rx: mpsc::UnboundedReceiver<ServerMessage>,
+    mut close_rx: oneshot::Receiver<CloseFrame<'static>>,
+    mut monitor: SlowClientMonitor,
+    evicted: Arc<Notify>,
+    catalog: Arc<Catalog>,
+    locale: Arc<RwLock<String>>,
+    batch: OutboundBatchConfig,
+    batching: Arc<AtomicBool>,
+) {
+    // Socket.IO clients drop the connection when the server stops pinging. Native clients
+    // get a WebSocket ping right away, so round trips are known before the first game.
+    let first_ping = match framing {
+        Framing::Native => Instant::now(),
+        Framing::SocketIo => Instant::now() + ping_interval,
+    };
+    let mut ping = tokio::time::interval_at(first_ping, ping_interval);
+    loop {
+        let message = tokio::select! {
+            biased;
+            _ = ping.tick() => {
+                let payload = latency.ping_sent();
+                let ping = match framing {
+                    Framing::Native => Message::Ping(payload),
+                    Framing::SocketIo => Message::Text(socket_io::PING_PACKET.to_string()),
+                };
+                if ws_sender.send(ping).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            message = rx.recv() => match message {
+                Some(message) => message,
+                None => break,
+            },
+            frame = &mut close_rx => {
+                if let Ok(frame) = frame {
+                    let _ = ws_sender.send(Message::Close(Some(frame))).await;
+                }
+                break;
+            }
+        };
+
+        let warning = match monitor.observe(rx.len(), std::time::Instant::now()) {
+            SlowClientAction::Ok => None,
+            SlowClientAction::Warn => Some(ServerMessage::Warning {
+                code: Some(MessageKey::SlowConnection.code().to_string()),
+                message: catalog.render(&locale.read(), MessageKey::SlowConnection, &[]),
+            }),
+            SlowClientAction::Evict => {
+                evict_slow_client(&evicted, "outbound queue stayed over the limit");
+                break;
+            }
+        };
+
+        let mut messages: Vec<ServerMessage> = warning.into_iter().chain(std::iter::once(message)).collect();
+        let batched = batching.load(Ordering::Relaxed);
+        if batched {
+            fill_batch(&mut messages, &mut rx, &batch).await;
+        }
+        #[cfg(feature = "chaos")]
+        {
+            messages.retain(|_| !crate::application::CHAOS.drop_outbound());
+            if messages.is_empty() {
+                continue;
+            }
+        }
+
+        let mut failed = false;
+        let mut frames = Vec::new();
+        if batched && messages.len() > 1 {
+            match BufferPool::to_json(&messages) {
+                Ok(json) => {
+                    BATCHED_FRAMES.fetch_add(1, Ordering::Relaxed);
+                    BATCHED_MESSAGES.fetch_add(messages.len() as u64, Ordering::Relaxed);
+                    frames.push(json);
+                }
+                Err(e) => error!("Failed to serialize message batch: {}", e),
+            }
+        } else {
+            for message in &messages {
+                let encoded = match framing {
+                    Framing::Native => BufferPool::to_json(message).map(|json| vec![json]),
+                    Framing::SocketIo => socket_io::encode(message),
+                };
+                match encoded {
+                    Ok(encoded) => frames.extend(encoded),
+                    Err(e) => error!("Failed to serialize message: {}", e),
+                }
+            }
+        }
+
+        for frame in frames {
+            // A send that can't complete within the window means the socket is stuck
+            match timeout(monitor.timeout, ws_sender.send(Message::Text(frame))).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    error!("Failed to send WebSocket message: {}", e);
+                    failed = true;
+                    break;
+                }
+                Err(_) => {
+                    evict_slow_client(&evicted, "send blocked");
+                    failed = true;
+                    break;
+                }
+            }
+        }
+        if failed {
+            break;
+        }
+    }
+    let _ = timeout(Duration::from_secs(1), ws_sender.close()).await;
+}
+
+/// Adds what is already queued behind the first message, up to the batch size. When the
+/// queue was backed up, waits up to `max_delay_ms` for more so busy rooms share frames.
+async fn fill_batch(messages: &mut Vec<ServerMessage>, rx: &mut mpsc::UnboundedReceiver<ServerMessage>, batch: &OutboundBatchConfig) {
+    let first = messages.len();
+    while messages.len() < batch.max_messages {
+        match rx.try_recv() {
+            Ok(message) => messages.push(message),
+            Err(_) => break,
+        }
+    }
+    if messages.len() == first || batch.max_delay_ms == 0 {
+        return;
+    }
+
+    let deadline = Instant::now() + Duration::from_millis(batch.max_delay_ms);
+    while messages.len() < batch.max_messages {
+        match timeout_at(deadline, rx.recv()).await {
+            Ok(Some(message)) => messages.push(message),
+            _ => break,
+        }
+    }
+}
+
+fn evict_slow_client(evicted: &Notify, why: &str) {
+    SLOW_CLIENT_EVICTIONS.fetch_add(1, Ordering::Relaxed);
+    warn!("Evicting slow client: {}", why);
+    evicted.notify_one();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlowClientAction {
+    Ok,
+    Warn,
+    Evict,
+}
+
+/// Tracks how long a connection's outbound queue has stayed over the limit.
+#[derive(Debug, Clone)]
+pub struct SlowClientMonitor {
+    max_queue: usize,
+    timeout: Duration,
+    behind_since: Option<std::time::Instant>,
+}
+
+impl SlowClientMonitor {
+    pub fn new(config: &WebSocketConfig) -> Self {
+        Self {
+            max_queue: config.slow_client_max_queue,
+            timeout: Duration::from_millis(config.slow_client_timeout_ms),
+            behind_since: None,
+        }
+    }
+
+    /// Warns the first time the queue goes over the limit and evicts if it
+    /// is still over once the timeout has passed.
+    pub fn observe(&mut self, queue_depth: usize, now: std::time::Instant) -> SlowClientAction {
+        if queue_depth <= self.max_queue {
+            self.behind_since = None;
+            return SlowClientAction::Ok;
+        }
+
+        match self.behind_since {
+            None => {
+                self.behind_since = Some(now);
+                SlowClientAction::Warn
+            }
+            Some(since) if now.duration_since(since) >= self.timeout => SlowClientAction::Evict,
+            Some(_) => SlowClientAction::Ok,
+        }
+    }
+}
+
+/// Short random id that users can quote and operators can grep for.
+pub fn new_correlation_id() -> String {
+    Uuid::new_v4().simple().to_string()[..12].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::ServerConfig;
+    use crate::infrastructure::{SlowClientAction, SlowClientMonitor};
+
+    #[test]
+    fn test_slow_client_monitor_warns_then_evicts() {
+        let mut config = ServerConfig::default().websocket;
+        config.slow_client_max_queue = 10;
+        config.slow_client_timeout_ms = 100;
+        let mut monitor = SlowClientMonitor::new(&config);
+        let start = std::time::Instant::now();
+        let at = |ms| start + std::time::Duration::from_millis(ms);
+
+        assert_eq!(monitor.observe(5, at(0)), SlowClientAction::Ok);
+        assert_eq!(monitor.observe(20, at(10)), SlowClientAction::Warn);
+        assert_eq!(monitor.observe(20, at(50)), SlowClientAction::Ok);
+        // Catching up resets the window
+        assert_eq!(monitor.observe(3, at(60)), SlowClientAction::Ok);
+        assert_eq!(monitor.observe(20, at(70)), SlowClientAction::Warn);
+        assert_eq!(monitor.observe(20, at(170)), SlowClientAction::Evict);
+    }
+}
@@ -0,0 +1,206 @@
+use futures_util::future::{AbortHandle, Abortable, BoxFuture};
+use futures_util::stream::FuturesUnordered;
+use futures_util::{FutureExt, StreamExt};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll};
+use tokio::sync::{mpsc, oneshot};
+use tracing::error;
+
+use super::websocket::CONNECTION_PANICS;
+use crate::application::panic_message;
+
+/// Writer tasks running, and the connection writers they are multiplexing.
+pub static WRITER_SHARDS: AtomicU64 = AtomicU64::new(0);
+pub static ACTIVE_WRITERS: AtomicU64 = AtomicU64::new(0);
+
+type Writer = BoxFuture<'static, ()>;
+
+struct WriterShard {
+    register: mpsc::UnboundedSender<Writer>,
+    writers: Arc<AtomicUsize>,
+}
+
+/// Runs every connection's outbound writer on a few long-lived tasks instead of one
+/// task per connection. Each shard polls its writers from a single task, and only the
+/// writers that were woken; a connection joins the shard with the fewest writers.
+/// Shards start with the first connection, on that connection's runtime.
+pub struct WriterPool {
+    shard_count: Option<usize>,
+    shards: OnceLock<Vec<WriterShard>>,
+}
+
+impl WriterPool {
+    /// `shard_count` of `None` starts one shard per runtime worker.
+    pub fn new(shard_count: Option<usize>) -> Self {
+        Self {
+            shard_count,
+            shards: OnceLock::new(),
+        }
+    }
+
+    /// Hands `writer` to a shard. The handle resolves once it finishes and can cancel it.
+    pub fn spawn<F>(&self, writer: F) -> WriterHandle
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (abort, registration) = AbortHandle::new_pair();
+        let (done_tx, done_rx) = oneshot::channel();
+        let shard = self
+            .shards()
+            .iter()
+            .min_by_key(|shard| shard.writers.load(Ordering::Relaxed))
+            .expect("at least one writer shard");
+
+        let slot = WriterSlot::new(shard.writers.clone());
+        let writer = async move {
+            let _slot = slot;
+            let _ = Abortable::new(writer, registration).await;
+            let _ = done_tx.send(());
+        };
+
+        // The shard is gone only if its runtime shut down; a task of our own still works
+        if let Err(mpsc::error::SendError(writer)) = shard.register.send(Box::pin(writer)) {
+            tokio::spawn(writer);
+        }
+        WriterHandle { abort, done: done_rx }
+    }
+
+    /// Writers on each shard, in shard order; empty until the first connection.
+    pub fn shard_loads(&self) -> Vec<usize> {
+        self.shards
+            .get()
+            .map(|shards| shards.iter().map(|shard| shard.writers.load(Ordering::Relaxed)).collect())
+            .unwrap_or_default()
+    }
+
+    fn shards(&self) -> &[WriterShard] {
+        self.shards.get_or_init(|| {
+            let count = self
+                .shard_count
+                .unwrap_or_else(|| tokio::runtime::Handle::current().metrics().num_workers())
+                .max(1);
+            (0..count)
+                .map(|_| {
+                    let (register, registrations) = mpsc::unbounded_channel();
+                    WRITER_SHARDS.fetch_add(1, Ordering::Relaxed);
+                    tokio::spawn(run_shard(registrations));
+                    WriterShard {
+                        register,
+                        writers: Arc::new(AtomicUsize::new(0)),
+                    }
+                })
+                .collect()
+        })
+    }
+}
+
+async fn run_shard(mut registrations: mpsc::UnboundedReceiver<Writer>) {
+    let mut writers = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            writer = registrations.recv() => match writer {
+                // A panicking writer must not take the rest of the shard down with it
+                Some(writer) => writers.push(AssertUnwindSafe(writer).catch_unwind()),
+                None => break,
+            },
+            Some(result) = writers.next(), if !writers.is_empty() => {
+                if let Err(panic) = result {
+                    CONNECTION_PANICS.fetch_add(1, Ordering::Relaxed);
+                    error!("Connection writer panicked: {}", panic_message(panic.as_ref()));
+                }
+            }
+        }
+    }
+
+    // The pool was dropped; let the connections it still serves finish
+    while writers.next().await.is_some() {}
+    WRITER_SHARDS.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Counts a writer against its shard until dropped, even if the writer panics.
+struct WriterSlot(Arc<AtomicUsize>);
+
+impl WriterSlot {
+    fn new(writers: Arc<AtomicUsize>) -> Self {
+        writers.fetch_add(1, Ordering::Relaxed);
+        ACTIVE_WRITERS.fetch_add(1, Ordering::Relaxed);
+        Self(writers)
+    }
+}
+
+impl Drop for WriterSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+        ACTIVE_WRITERS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A connection's writer on its shard; resolves when the writer finishes.
+pub struct WriterHandle {
+    abort: AbortHandle,
+    done: oneshot::Receiver<()>,
+}
+
+impl WriterHandle {
+    /// Drops the writer without flushing what it still has queued.
+    pub fn abort(&self) {
+        self.abort.abort();
+    }
+}
+
+impl Future for WriterHandle {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        Pin::new(&mut self.done).poll(cx).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::WriterPool;
+
+    #[tokio::test]
+    async fn test_writer_pool_multiplexes_connections_onto_shards() {
+        let pool = WriterPool::new(Some(2));
+        assert!(pool.shard_loads().is_empty());
+
+        let mut releases = Vec::new();
+        let mut handles = Vec::new();
+        for _ in 0..4 {
+            let (release, released) = tokio::sync::oneshot::channel::<()>();
+            releases.push(release);
+            handles.push(pool.spawn(async move {
+                let _ = released.await;
+            }));
+        }
+        assert_eq!(pool.shard_loads(), vec![2, 2]);
+
+        // A finished writer resolves its handle and frees its slot
+        releases.remove(0).send(()).unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(1), handles.remove(0)).await.unwrap();
+        assert_eq!(pool.shard_loads().iter().sum::<usize>(), 3);
+
+        // So does an aborted one, without being released
+        let aborted = handles.remove(0);
+        releases.remove(0);
+        aborted.abort();
+        tokio::time::timeout(std::time::Duration::from_secs(1), aborted).await.unwrap();
+        assert_eq!(pool.shard_loads().iter().sum::<usize>(), 2);
+
+        // A panicking writer leaves the other writers on its shard running
+        let panicked = pool.spawn(async { panic!("writer bug") });
+        tokio::time::timeout(std::time::Duration::from_secs(1), panicked).await.unwrap();
+        for release in releases {
+            release.send(()).unwrap();
+        }
+        for handle in handles {
+            tokio::time::timeout(std::time::Duration::from_secs(1), handle).await.unwrap();
+        }
+        assert_eq!(pool.shard_loads(), vec![0, 0]);
+    }
+}
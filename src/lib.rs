@@ -2,7 +2,11 @@ pub mod application;
 pub mod config;
 pub mod domain;
 pub mod infrastructure;
+pub mod test_support;
 
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "client")]
 pub mod tests;
 
 pub use application::*;
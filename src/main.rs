@@ -3,31 +3,30 @@ use mimalloc::MiMalloc;
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-mod application;
-mod config;
-mod domain;
-mod infrastructure;
-
-#[cfg(test)]
-mod tests;
+// Connection handlers and room timers catch their own panics; under abort one would take the server down
+#[cfg(panic = "abort")]
+compile_error!("rps-server must be built with panic = \"unwind\"");
 
 use anyhow::Result;
+use clap::{Arg, ArgAction, Command};
 use std::sync::Arc;
-use tokio::net::TcpListener;
 use tracing::{error, info, warn};
 use warp::Filter;
 use once_cell::sync::Lazy;
-use crossbeam::atomic::AtomicCell;
-use std::sync::atomic::{AtomicU64, Ordering};
-
-use application::GameManager;
-use config::ServerConfig;
-use infrastructure::{rest_api, WebSocketHandler};
+use std::sync::atomic::Ordering;
 
-// Global performance counters
-static TOTAL_CONNECTIONS: AtomicU64 = AtomicU64::new(0);
-static PEAK_CONNECTIONS: AtomicU64 = AtomicU64::new(0);
-static TOTAL_MESSAGES: AtomicU64 = AtomicU64::new(0);
+use rps_server::application::{
+    EloRatings, FifoPairing, GameManager, PairingStrategy, PlayerActivity, PresenceRegistry, ProfanityFilter, RatingBandPairing, RatingProvider, RatingRecorder,
+    ResultSigner, SeriesManager, ShadowMatchmaker,
+};
+use rps_server::config::{SecretStore, ServerConfig, ShadowStrategy};
+use rps_server::domain::BuildInfo;
+use rps_server::infrastructure::{
+    create_admin_routes, create_api_key_routes, create_cluster_routes, create_demo_routes, create_game_routes, create_invite_routes, create_long_poll_routes, create_player_activity_routes, create_player_stats_routes, create_result_key_routes,
+    create_player_data_routes, create_profanity_routes, create_room_routes, create_room_template_routes, create_season_routes, create_title_routes, create_tournament_routes, body_limit, format_mib, with_cache_bypass, with_rate_limit, bind_rest, http_options, reload_secrets, serve_rest, with_request_id, AuditLog, BufferPool, Catalog, Cluster, FdLimits, FdUsage, HttpRatingProvider, Invites, LongPollSessions, MemoryUsage, NotificationInbox, PlayerDataService, PlayerStatsService,
+    ApiKeyStore, LifetimeStats, PresencePusher, ReplayArchive, ResponseCache, RestRateLimiter, ResumeTokens, RoomTemplateStore, ScriptedRules, Seasons, StatsExporter, TitleStore, TournamentFeed, TrafficRecorder, WebSocketHandler, WebhookDispatcher, WsListener, ACTIVE_WRITERS, BATCHED_FRAMES, BATCHED_MESSAGES, CLIENT_METRICS, CODEC_FALLBACKS, CONNECTION_PANICS, PREFLIGHT_REJECTS, PEAK_CONNECTIONS, RUNTIME_HEALTH, SLOW_CLIENT_EVICTIONS,
+    STALLED_HANDSHAKES, ServerTotals, TOTAL_CONNECTIONS, UNEXPECTED_FRAMES, WRITER_SHARDS,
+};
 
 // Lazy-initialized configuration for ultra-fast startup
 static CONFIG: Lazy<ServerConfig> = Lazy::new(|| {
@@ -50,13 +49,42 @@ static CONFIG: Lazy<ServerConfig> = Lazy::new(|| {
     config.performance.channel_buffer_size = 2048;   // Larger buffers
     config.performance.gc_interval_ms = 15000;       // More frequent GC
     
+    // Capture client traffic for `traffic_replay` only when asked to
+    config.traffic_recording.path = std::env::var("RPS_RECORD_TRAFFIC").ok();
+
+    // Cluster mode: comma-separated peer URLs; the shared token comes from RPS_CLUSTER_SECRET
+    if let Ok(peers) = std::env::var("RPS_CLUSTER_PEERS") {
+        config.cluster.peers = peers.split(',').map(str::trim).filter(|peer| !peer.is_empty()).map(str::to_string).collect();
+    }
+    config.cluster.node_id = std::env::var("RPS_NODE_ID").ok();
+    config.cluster.advertise_url = std::env::var("RPS_NODE_URL").ok(); // Handed out in reconnect tokens
+    
     config
 });
 
 
 
-#[tokio::main(flavor = "multi_thread", worker_threads = 16)]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
+    let matches = Command::new("rps-server")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Multiplayer Rock Paper Scissors game server")
+        .arg(
+            Arg::new("self-check")
+                .long("self-check")
+                .help("Boot on ephemeral local ports, play a scripted game against this server, and exit")
+                .action(ArgAction::SetTrue),
+        )
+        .get_matches();
+
+    // Sized from config rather than a fixed thread count, so small containers stay small
+    let runtime = CONFIG.performance.build_runtime()?;
+    if matches.get_flag("self-check") {
+        return runtime.block_on(self_check());
+    }
+    runtime.block_on(run())
+}
+
+fn init_tracing() {
     // Ultra-fast tracing initialization
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::INFO)
@@ -65,125 +93,496 @@ async fn main() -> Result<()> {
         .with_ansi(true)
         .compact()
         .init();
+}
 
+async fn run() -> Result<()> {
+    init_tracing();
     // Use lazy-initialized config for faster startup
-    let config = CONFIG.clone();
-    
+    serve(CONFIG.clone(), None).await
+}
+
+/// The ports `serve` is listening on, with any configured as 0 resolved.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(feature = "client"), allow(dead_code))] // Only `--self-check` reads them
+struct BoundPorts {
+    websocket: u16, // The first plain (non-TLS) listener
+    rest: u16,
+}
+
+/// Builds every service from `config` and serves until a listener fails. Once every port
+/// is bound, they are sent on `bound`.
+async fn serve(mut config: ServerConfig, bound: Option<tokio::sync::oneshot::Sender<BoundPorts>>) -> Result<()> {
     info!("🚀 EXTREME-CAPACITY RPS Server Starting...");
     info!("Memory Allocator: MiMalloc");
+    // Fit connection caps to the open file limit before anything is sized from them
+    FdLimits::apply(&mut config.websocket);
     info!("Max Connections: {}", config.websocket.max_connections);
-    info!("Worker Threads: 16");
-    info!("Blocking Threads: 2048");
+    let runtime_metrics = tokio::runtime::Handle::current().metrics();
+    info!("Worker Threads: {}", runtime_metrics.num_workers());
+    info!("Blocking Threads: {}", config.performance.max_blocking_threads);
+    BufferPool::configure(&config.performance.buffer_pool);
     
+    config.game.rules.validate().map_err(|e| e.context("Invalid game.rules"))?;
+
+    // Admin API keys and JWT and result signing keys come from env/files, never from ServerConfig
+    let secrets = Arc::new(SecretStore::load(config.secrets.clone())?);
+    // Viewer, operator and admin keys issued through `/admin/keys`, stored hashed
+    let api_keys = Arc::new(match &config.secrets.api_key_store_path {
+        Some(path) => ApiKeyStore::open(path, secrets.clone())?,
+        None => ApiKeyStore::in_memory(secrets.clone()),
+    });
+    if !secrets.has_admin_keys() {
+        warn!("🔒 No admin API keys configured; admin endpoints will reject all requests");
+    }
+    let result_signer = ResultSigner::new(secrets.clone())?.map(Arc::new);
+    if result_signer.is_some() {
+        info!("🔏 Result Signing: enabled");
+    }
+
+    // Built-in ratings; the `ratingBand` shadow experiment pairs by them too
+    let elo = Arc::new(EloRatings::new(config.ratings.elo_k_factor));
+
+    // Earned titles; the equipped one is shown next to the player in GameStart
+    let titles = Arc::new(match &config.titles.store_path {
+        Some(path) => TitleStore::open(path)?,
+        None => TitleStore::in_memory(),
+    });
+
+    // Room templates players can be matched on; edited at runtime through the admin API
+    let room_templates = Arc::new(match &config.room_templates.store_path {
+        Some(path) => RoomTemplateStore::open(path)?,
+        None => RoomTemplateStore::in_memory(),
+    });
+
+    // Word list screening display names and chat; replaced at runtime through the admin API
+    let profanity = Arc::new(ProfanityFilter::new(&config.profanity)?);
+
     // Initialize ultra-optimized game manager
-    let game_manager = Arc::new(GameManager::new(config.game.clone().into()));
+    let mut game_manager = GameManager::new(config.game.clone().into())
+        .with_shards(config.performance.manager_shards)
+        .with_bot_detection(config.bot_detection.clone())
+        .with_matchmaking(config.matchmaking.clone())
+        .with_blitz(config.blitz.clone())
+        .with_game_history(config.game_history.clone())
+        .with_spam_guard(config.spam_guard.clone())
+        .with_titles(titles.clone())
+        .with_room_templates(room_templates.clone())
+        .with_profanity_filter(profanity.clone())
+        .with_capacity(config.capacity.clone(), config.websocket.max_connections)
+        .with_quotas(config.quotas.clone())
+        .with_quit_penalty(config.quit_penalty.clone());
+    let shadow_config = &config.matchmaking.shadow;
+    if let Some(strategy) = shadow_config.strategy {
+        let strategy: Box<dyn PairingStrategy> = match strategy {
+            ShadowStrategy::Fifo => Box::new(FifoPairing),
+            ShadowStrategy::RatingBand => {
+                if config.ratings.provider_url.is_some() {
+                    warn!("🧪 Shadow ratingBand uses the built-in ELO, which an external rating provider leaves unrated");
+                }
+                let pairing = RatingBandPairing::new(elo.clone(), shadow_config.rating_band, shadow_config.rating_band_widen_per_sec);
+                let starvation = &config.matchmaking.starvation;
+                if starvation.relax_pairing {
+                    Box::new(pairing.with_starvation_slo(std::time::Duration::from_millis(starvation.slo_ms)))
+                } else {
+                    Box::new(pairing)
+                }
+            }
+        };
+        info!("🧪 Shadow Matchmaking: {}", strategy.name());
+        game_manager = game_manager.with_shadow_matchmaking(Arc::new(ShadowMatchmaker::new(strategy)));
+    }
+    if let Some(signer) = &result_signer {
+        game_manager = game_manager.with_result_signer(signer.clone());
+    }
+    if !config.scripted_rules.scripts.is_empty() {
+        let scripts = ScriptedRules::new(&config.scripted_rules)?;
+        info!("📜 Scripted Rules: {} scripts", scripts.len());
+        game_manager = game_manager.with_rule_scripts(Arc::new(scripts));
+    }
+    let replay_archive = match &config.game_history.archive {
+        Some(archive_config) => {
+            let archive = Arc::new(ReplayArchive::open(archive_config).await?);
+            info!("🗄️ Replay Archive: {} games in bucket {}", archive.len(), archive_config.object_store.bucket);
+            game_manager = game_manager.with_game_archive(archive.clone());
+            Some(archive)
+        }
+        None => None,
+    };
+    let game_manager = Arc::new(game_manager);
+    if let Some(archive) = &replay_archive {
+        archive.clone().spawn(game_manager.game_history().clone());
+    }
+
+    // Games played, connections and peak concurrency from earlier runs, carried into /stats
+    let lifetime_stats = Arc::new(match &config.lifetime_stats.store_path {
+        Some(path) => LifetimeStats::open(path)?,
+        None => LifetimeStats::in_memory(),
+    });
+    let persist_every = std::time::Duration::from_millis(config.lifetime_stats.persist_interval_ms.max(1000));
+    lifetime_stats.clone().spawn(game_manager.clone(), persist_every);
     
+    // Append-only audit trail for admin actions
+    let audit_log = Arc::new(match &config.admin.audit_log_path {
+        Some(path) => AuditLog::open(path)?,
+        None => AuditLog::in_memory(),
+    });
+    info!("📝 Audit Log: {} entries loaded", audit_log.len());
+
+    // Offline notifications, delivered on the player's next Connect
+    let max_pending = config.notifications.max_pending_per_player;
+    let notifications = Arc::new(match &config.notifications.store_path {
+        Some(path) => NotificationInbox::open(path, max_pending)?,
+        None => NotificationInbox::in_memory(max_pending),
+    });
+
+    // Seasons: the built-in ratings roll over on schedule into an archive
+    let seasons = Arc::new(match &config.seasons.archive_path {
+        Some(path) => Seasons::open(path, elo.clone(), &config.seasons)?,
+        None => Seasons::in_memory(elo.clone(), &config.seasons),
+    });
+    info!("🏁 Season {} ({} archived)", seasons.current().summary.season, seasons.history().len());
+    seasons.clone().spawn(notifications.clone());
+
+    // Outbound webhooks for lifecycle events
+    let webhooks = WebhookDispatcher::new(&config.webhooks)?;
+    if !webhooks.is_empty() {
+        info!("🪝 Webhooks: {} endpoints", webhooks.len());
+        webhooks.spawn(game_manager.events());
+    }
+
+    // Finished games and daily aggregates for offline analysis
+    if let Some(exporter) = StatsExporter::new(&config.stats_export)? {
+        info!("📦 Stats export: {} every {}ms", exporter.destination(), config.stats_export.interval_ms);
+        exporter.spawn(game_manager.clone());
+    }
+
+    // Presence tracking, optionally mirrored to an external rich-presence service
+    let presence = Arc::new(PresenceRegistry::new());
+    presence.clone().spawn(game_manager.events());
+    if let Some(pusher) = PresencePusher::new(&config.presence)? {
+        info!("🟢 Presence Push: {}", config.presence.push_url.as_deref().unwrap_or_default());
+        pusher.spawn(&presence);
+    }
+
+    // Per-player timelines for support, at GET /players/{id}/activity
+    let activity = Arc::new(PlayerActivity::new(game_manager.game_history().clone(), &config.activity));
+    activity.clone().spawn(game_manager.events());
+
+    // Batch player lookups for tournament pages, from the built-in ratings and recent games
+    let player_stats = Arc::new(
+        PlayerStatsService::new(elo.clone(), game_manager.game_history().clone(), &config.player_stats)
+            .with_quit_penalties(game_manager.quit_penalties()),
+    );
+
+    // Self-service export and erasure at GET/DELETE /players/{id}/data
+    let mut player_data = PlayerDataService::new(
+        elo.clone(),
+        game_manager.game_history().clone(),
+        titles.clone(),
+        notifications.clone(),
+        seasons.clone(),
+        activity.clone(),
+    );
+    if let Some(archive) = replay_archive {
+        player_data = player_data.with_archive(archive);
+    }
+    let player_data = Arc::new(player_data);
+
+    // Ratings for finished games: an external service when configured, built-in ELO otherwise
+    let rating_provider: Arc<dyn RatingProvider> = match HttpRatingProvider::new(&config.ratings)? {
+        Some(provider) => Arc::new(provider),
+        None => elo,
+    };
+    info!("🏆 Ratings: {}", rating_provider.name());
+    RatingRecorder::new(rating_provider, &config.ratings).spawn(game_manager.events());
+
+    // Best-of series, created through the admin API and carried from game to game
+    let series = Arc::new(SeriesManager::new(game_manager.clone(), config.series.clone()));
+    series.clone().spawn();
+
+    // Live brackets for tournament overlays, from matches reserved with a tournament id
+    let tournaments = Arc::new(TournamentFeed::new());
+    tournaments.clone().spawn(game_manager.events());
+
     // Create ultra-optimized WebSocket handler
-    let ws_handler = WebSocketHandler::new(game_manager.clone());
+    let catalog = Arc::new(Catalog::from_config(&config.i18n)?);
+    let invites = Arc::new(Invites::new(config.invites.clone())?);
+    let mut ws_handler = WebSocketHandler::new(game_manager.clone(), config.websocket.clone())
+        .with_catalog(catalog)
+        .with_notifications(notifications.clone())
+        .with_invites(invites.clone())
+        .with_resume_tokens(Arc::new(ResumeTokens::new(&config.secrets.resume_keys)?));
+    if let Some(recorder) = TrafficRecorder::new(&config.traffic_recording)? {
+        ws_handler = ws_handler.with_recorder(Arc::new(recorder));
+    }
+    let cluster = Cluster::new(&config.cluster)?.map(Arc::new);
+    if let Some(cluster) = &cluster {
+        info!("🕸️  Cluster: node {} with {} peers", cluster.node_id(), config.cluster.peers.len());
+        ws_handler = ws_handler.with_cluster(cluster.clone());
+        cluster.clone().spawn(ws_handler.clone());
+    }
+
+    // HTTP long-polling fallback, sharing the WebSocket message handling
+    let long_poll = Arc::new(LongPollSessions::new(ws_handler.clone(), config.long_poll.clone()));
+    if config.long_poll.enabled {
+        long_poll.spawn_reaper();
+    }
     
     // Start ultra-performance monitoring
+    RUNTIME_HEALTH.spawn(std::time::Duration::from_millis(500));
     start_ultra_performance_monitor(game_manager.clone());
+
+    // Enforce per-game time budgets
+    start_game_clock(game_manager.clone());
+    if shadow_config.strategy.is_some() {
+        start_shadow_reports(game_manager.clone(), shadow_config.report_interval_ms);
+    }
+    #[cfg(unix)]
+    start_secret_reloader(secrets.clone(), audit_log.clone())?;
     
-    // Ultra-optimized WebSocket server
+    // Ultra-optimized WebSocket listeners, all feeding the same GameManager
     let ws_config = config.websocket.clone();
-    let ws_server = async move {
-        let addr = format!("{}:{}", ws_config.host, ws_config.port);
-        let listener = TcpListener::bind(&addr).await?;
-        
-        // Ultra-performance TCP settings
-        listener.set_ttl(128)?;
-        
-        info!("⚡ Ultra-Fast WebSocket Server: ws://{}", addr);
-        info!("🔥 Max Capacity: {} connections", ws_config.max_connections);
-        info!("⏱️  Message Timeout: {}ms", ws_config.message_timeout_ms);
-        
-        // Pre-allocate connection tracking
-        let connection_pool = Arc::new(crossbeam::queue::SegQueue::new());
-        
-        while let Ok((stream, _addr)) = listener.accept().await {
-            // Ultra-fast connection tracking
-            let current = TOTAL_CONNECTIONS.fetch_add(1, Ordering::Relaxed);
-            let peak = PEAK_CONNECTIONS.load(Ordering::Relaxed);
-            if current > peak {
-                PEAK_CONNECTIONS.store(current, Ordering::Relaxed);
-            }
-            
-            // Ultra-performance TCP settings
-            if let Err(e) = stream.set_nodelay(true) {
-                warn!("Failed to set TCP_NODELAY: {}", e);
-            }
-            
-            let handler = ws_handler.clone();
-            let pool = connection_pool.clone();
-            
-            // Spawn with ultra-fast task
-            tokio::spawn(async move {
-                if let Err(e) = handler.handle_connection(stream).await {
-                    error!("Connection error: {}", e);
-                }
-                
-                // Decrement connection count
-                TOTAL_CONNECTIONS.fetch_sub(1, Ordering::Relaxed);
-                pool.push(());
-            });
+    let listeners = ws_config
+        .effective_listeners()
+        .iter()
+        .map(|listener| WsListener::from_config(listener, ws_config.max_connections).map(Arc::new))
+        .collect::<Result<Vec<_>>>()?;
+    let mut ws_servers = Vec::with_capacity(listeners.len());
+    let mut plain_port = None;
+    for listener in &listeners {
+        let socket = listener.bind().await?;
+        if !listener.snapshot().tls {
+            plain_port = plain_port.or(Some(socket.local_addr()?.port()));
         }
-
-        Ok::<(), anyhow::Error>(())
-    };
+        let server = listener.clone().serve(socket, ws_handler.clone());
+        let name = listener.name().to_string();
+        ws_servers.push(async move { server.await.map_err(|e| anyhow::anyhow!("WebSocket listener {} error: {}", name, e)) });
+    }
+    info!("🔥 Max Capacity: {} connections", ws_config.max_connections);
+    info!("⏱️  Message Timeout: {}ms", ws_config.message_timeout_ms);
+    let ws_server = futures_util::future::try_join_all(ws_servers);
+    let listeners = Arc::new(listeners);
+    let shared_listeners = listeners.clone();
 
     // Ultra-optimized REST API server
     let rest_config = config.rest_api.clone();
-    let routes = create_ultra_optimized_routes(game_manager);
-    let rest_server = warp::serve(routes)
-        .run(([0, 0, 0, 0], rest_config.port));
+    let demo_port = plain_port.unwrap_or(config.websocket.port);
+    let demo = create_demo_routes(rest_config.serve_demo_client, demo_port);
+    let response_cache = Arc::new(ResponseCache::new(std::time::Duration::from_millis(rest_config.cache_ttl_ms)));
+    let rate_limiter = Arc::new(RestRateLimiter::new(rest_config.rate_limit.clone(), secrets.clone()));
+    // Clients that went quiet are forgotten once per window
+    let pruned_limiter = rate_limiter.clone();
+    let prune_every = std::time::Duration::from_millis(rest_config.rate_limit.window_ms.max(1000));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(prune_every);
+        loop {
+            interval.tick().await;
+            pruned_limiter.prune();
+        }
+    });
+    let routes = with_request_id(
+        body_limit(rest_config.max_body_bytes).and(with_rate_limit(rate_limiter.clone())).and(create_ultra_optimized_routes(game_manager.clone(), long_poll, listeners, ws_config.max_connections, seasons, lifetime_stats.clone(), response_cache, rate_limiter, secrets.clone())
+            .or(create_admin_routes(game_manager.clone(), audit_log.clone(), notifications, secrets.clone(), series))
+            .or(create_title_routes(titles, audit_log.clone(), secrets.clone()))
+            .or(create_room_template_routes(room_templates, audit_log.clone(), secrets.clone()))
+            .or(create_player_stats_routes(player_stats))
+            .or(create_player_activity_routes(activity, secrets.clone()))
+            .or(create_player_data_routes(player_data, audit_log.clone(), secrets.clone()))
+            .or(create_profanity_routes(profanity, audit_log.clone(), secrets.clone()))
+            .or(create_result_key_routes(result_signer))
+            .or(create_invite_routes(invites, game_manager.clone()))
+            .or(create_tournament_routes(tournaments))
+            .or(create_api_key_routes(api_keys, audit_log.clone(), secrets.clone()))
+            .or(create_cluster_routes(cluster, game_manager.clone(), audit_log, secrets))
+            .or(demo)),
+    );
+    // Single-port mode: the WebSocket listeners answer REST requests too
+    let (rest_server, rest_port) = if rest_config.share_websocket_port {
+        let routes = routes.map(|reply| Box::new(reply) as Box<dyn warp::Reply>).boxed();
+        for listener in shared_listeners.iter() {
+            listener.share_port(routes.clone(), http_options(&rest_config));
+        }
+        (None, demo_port)
+    } else {
+        let incoming = bind_rest(&rest_config)?;
+        let rest_port = incoming.local_addr().port();
+        (Some(serve_rest(routes, incoming, &rest_config)), rest_port)
+    };
+    if let Some(bound) = bound {
+        let websocket = plain_port.ok_or_else(|| anyhow::anyhow!("No plain WebSocket listener to report"))?;
+        let _ = bound.send(BoundPorts { websocket, rest: rest_port });
+    }
 
-    info!("🏥 Health Check: http://{}:{}/health", rest_config.host, rest_config.port);
-    info!("📊 Stats: http://{}:{}/stats", rest_config.host, rest_config.port);
-    info!("⚡ Ultra Metrics: http://{}:{}/ultra-metrics", rest_config.host, rest_config.port);
+    info!("🏥 Health Check: http://{}:{}/health", rest_config.host, rest_port);
+    info!("📊 Stats: http://{}:{}/stats", rest_config.host, rest_port);
+    info!("🎮 Room Details: http://{}:{}/rooms/{{id}}", rest_config.host, rest_port);
+    info!("⚡ Ultra Metrics: http://{}:{}/ultra-metrics", rest_config.host, rest_port);
+    if rest_config.serve_demo_client {
+        info!("🕹️  Demo Client: http://{}:{}/demo/", rest_config.host, rest_port);
+    }
+    if config.long_poll.enabled {
+        info!("📮 Long-Poll Fallback: http://{}:{}/poll/{{send,recv}}", rest_config.host, rest_port);
+    }
 
-    // Run both servers with ultra-performance
-    tokio::try_join!(
-        async { ws_server.await.map_err(|e| anyhow::anyhow!("WebSocket server error: {}", e)) },
-        async { rest_server.await; Ok(()) }
-    )?;
+    // Run both servers with ultra-performance, until one fails or we're asked to stop
+    let servers = async {
+        tokio::try_join!(
+            ws_server,
+            async {
+                match rest_server {
+                    Some(rest_server) => rest_server.await,
+                    None => Ok(()),
+                }
+            }
+        )
+    };
+    let result = tokio::select! {
+        result = servers => result.map(|_| ()),
+        _ = tokio::signal::ctrl_c() => {
+            info!("🛑 Shutting down");
+            Ok(())
+        }
+    };
 
-    Ok(())
+    if let Err(e) = lifetime_stats.persist(ServerTotals::since_boot(&game_manager)) {
+        error!("Failed to persist lifetime stats: {}", e);
+    }
+    result
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::domain::{GameChoice, GameConfig};
-    use crate::application::GameRoom;
-
-    #[test]
-    fn test_game_choice_beats() {
-        assert!(GameChoice::Rock.beats(&GameChoice::Scissors));
-        assert!(GameChoice::Paper.beats(&GameChoice::Rock));
-        assert!(GameChoice::Scissors.beats(&GameChoice::Paper));
-        
-        assert!(!GameChoice::Rock.beats(&GameChoice::Paper));
-        assert!(!GameChoice::Paper.beats(&GameChoice::Scissors));
-        assert!(!GameChoice::Scissors.beats(&GameChoice::Rock));
-        
-        assert!(!GameChoice::Rock.beats(&GameChoice::Rock));
+// How long `--self-check` may take from boot to the final result
+#[cfg(feature = "client")]
+const SELF_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// `--self-check`: a deployment smoke test. Boots the full server on ephemeral local ports
+/// with nothing persisted, plays a scripted two-player game against it with the typed
+/// client, checks the outcome and the REST API, and returns an error (non-zero exit) on failure.
+#[cfg(feature = "client")]
+async fn self_check() -> Result<()> {
+    init_tracing();
+    let result = tokio::time::timeout(SELF_CHECK_TIMEOUT, async {
+        let (server, ws_url, rest_url) = spawn_self_check_server(self_check_config()).await?;
+        info!("🩺 Self-check: booted on {} and {}", ws_url, rest_url);
+        let result = run_self_check(&ws_url, &rest_url).await;
+        server.abort();
+        result
+    })
+    .await;
+    match result {
+        Ok(Ok(())) => {
+            info!("✅ Self-check passed");
+            Ok(())
+        }
+        Ok(Err(e)) => {
+            error!("❌ Self-check failed: {:#}", e);
+            Err(e)
+        }
+        Err(_) => {
+            error!("❌ Self-check timed out after {:?}", SELF_CHECK_TIMEOUT);
+            anyhow::bail!("Self-check timed out after {:?}", SELF_CHECK_TIMEOUT)
+        }
     }
+}
+
+#[cfg(not(feature = "client"))]
+async fn self_check() -> Result<()> {
+    anyhow::bail!("--self-check needs the typed client; rebuild with the `client` feature")
+}
+
+// The production config on ports the OS picks, minus everything that would touch the outside world
+#[cfg(feature = "client")]
+fn self_check_config() -> ServerConfig {
+    let mut config = CONFIG.clone();
+    config.websocket.host = "127.0.0.1".to_string();
+    config.websocket.port = 0;
+    config.websocket.listeners.clear();
+    config.rest_api.host = "127.0.0.1".to_string();
+    config.rest_api.port = 0;
+    config.rest_api.share_websocket_port = false;
+    config.admin.audit_log_path = None;
+    config.notifications.store_path = None;
+    config.seasons.archive_path = None;
+    config.titles.store_path = None;
+    config.lifetime_stats.store_path = None;
+    config.room_templates.store_path = None;
+    config.secrets.api_key_store_path = None;
+    config.game_history.archive = None;
+    config.traffic_recording.path = None;
+    config.cluster.peers.clear();
+    config
+}
 
-    #[test]
-    fn test_game_room_creation() {
-        let config = GameConfig::default();
-        let room = GameRoom::new("test-room".to_string(), config);
-        assert_eq!(room.id, "test-room");
-        assert_eq!(room.status, crate::domain::GameStatus::Waiting);
-        assert_eq!(room.current_round, 1);
-        assert_eq!(room.config.max_rounds, 3);
+/// Starts `serve` in the background and waits for its ports, returning it with its
+/// WebSocket and REST URLs.
+#[cfg(feature = "client")]
+async fn spawn_self_check_server(config: ServerConfig) -> Result<(tokio::task::JoinHandle<Result<()>>, String, String)> {
+    let (bound_tx, bound_rx) = tokio::sync::oneshot::channel();
+    let server = tokio::spawn(serve(config, Some(bound_tx)));
+    let Ok(bound) = bound_rx.await else {
+        // Nothing was reported, so serve has already returned
+        server.await??;
+        anyhow::bail!("Server stopped during startup");
+    };
+    let ws_url = format!("ws://127.0.0.1:{}", bound.websocket);
+    let rest_url = format!("http://127.0.0.1:{}", bound.rest);
+    Ok((server, ws_url, rest_url))
+}
+
+#[cfg(feature = "client")]
+async fn run_self_check(ws_url: &str, rest_url: &str) -> Result<()> {
+    use anyhow::{bail, Context};
+    use rps_server::client::{ClientOptions, GameClient};
+    use rps_server::domain::{GameChoice, GameMode, ServerMessage};
+
+    let options = |id: &str| ClientOptions {
+        url: ws_url.to_string(),
+        player_id: Some(format!("self-check-{}", id)),
+        connect_timeout: std::time::Duration::from_secs(2),
+        response_timeout: std::time::Duration::from_secs(10),
+        ..ClientOptions::default()
+    };
+
+    let mut alice = GameClient::connect(options("alice")).await.context("First player couldn't connect")?;
+    let mut bob = GameClient::connect(options("bob")).await.context("Second player couldn't connect")?;
+
+    alice.find_match(GameMode::Solo).await.context("First player couldn't queue")?;
+    let matched = bob.find_match(GameMode::Solo).await.context("Second player couldn't queue")?;
+    if !matches!(matched, ServerMessage::Matchmaking { matched: true, .. }) {
+        bail!("Players weren't matched: {:?}", matched);
     }
+
+    // Rock beats scissors every round, so alice must take the game
+    let (winner, rounds) = loop {
+        match alice.next_event().await.context("Game stalled")? {
+            ServerMessage::GameStart { .. } | ServerMessage::NextRound { .. } => {
+                alice.play(GameChoice::Rock).await?;
+                bob.play(GameChoice::Scissors).await?;
+            }
+            ServerMessage::GameEnd { winner, rounds, .. } => break (winner, rounds),
+            ServerMessage::Error { message, .. } => bail!("Server error during the game: {}", message),
+            _ => {}
+        }
+    };
+    if winner.as_deref() != Some(alice.player_id()) || rounds.is_empty() {
+        bail!("Wrong result: winner {:?} after {} rounds", winner, rounds.len());
+    }
+    info!("🩺 Self-check: game finished after {} rounds, won by {}", rounds.len(), alice.player_id());
+
+    let health: hyper::Uri = format!("{}/health", rest_url).parse()?;
+    let response = hyper::Client::new().get(health).await.context("REST API unreachable")?;
+    if !response.status().is_success() {
+        bail!("GET /health answered {}", response.status());
+    }
+
+    alice.close().await?;
+    bob.close().await?;
+    Ok(())
 }
+
 // Ultra-performance monitoring with SIMD optimizations
 fn start_ultra_performance_monitor(game_manager: Arc<GameManager>) {
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(10));
-        let mut last_messages = 0u64;
         
         loop {
             interval.tick().await;
@@ -196,15 +595,129 @@ fn start_ultra_performance_monitor(game_manager: Arc<GameManager>) {
             info!("  🎮 Total Rooms: {}", total_rooms);
             info!("  🏃 Active Games: {}", active_games);
             info!("  ⏳ Waiting Players: {}", waiting_players);
-            info!("  💾 Memory Usage: Optimized with MiMalloc");
+            let memory = MemoryUsage::sample();
+            let estimate = game_manager.memory_estimate().await;
+            info!(
+                "  💾 Memory Usage: RSS {} (Peak: {}), Committed {}",
+                format_mib(memory.rss_bytes),
+                format_mib(memory.peak_rss_bytes),
+                format_mib(memory.committed_bytes)
+            );
+            info!(
+                "  🧮 Estimated: Rooms {}, Queues {}, Move History {}",
+                format_mib(estimate.rooms_bytes),
+                format_mib(estimate.queues_bytes),
+                format_mib(estimate.move_history_bytes)
+            );
+            let health = RUNTIME_HEALTH.snapshot();
+            info!(
+                "  🩺 Scheduler Lag: {:.1}ms (Max: {:.1}ms), Tasks: {}, CPU: {:.0}%",
+                health.scheduler_lag_ms, health.max_scheduler_lag_ms, health.alive_tasks, health.cpu_percent
+            );
+        }
+    });
+}
+
+/// Logs how the shadow matchmaking strategy diverges from live matchmaking.
+fn start_shadow_reports(game_manager: Arc<GameManager>, interval_ms: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_millis(interval_ms.max(1)));
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            let Some(report) = game_manager.shadow_report() else { break };
+            info!(
+                "🧪 Shadow {}: {} decisions, {:.1}% divergent ({} different opponents, {} shadow-only matches, {} live-only matches), avg wait live {:.0}ms vs shadow {:.0}ms, {} waiting in shadow",
+                report.strategy,
+                report.decisions,
+                report.divergence_rate * 100.0,
+                report.different_opponents,
+                report.shadow_matched_live_waited,
+                report.live_matched_shadow_waited,
+                report.avg_live_wait_ms,
+                report.avg_shadow_wait_ms,
+                report.shadow_waiting
+            );
+        }
+    });
+}
+
+/// Re-reads the configured keys on SIGHUP, so they can be rotated without a restart.
+#[cfg(unix)]
+fn start_secret_reloader(secrets: Arc<SecretStore>, audit_log: Arc<AuditLog>) -> Result<()> {
+    let mut hangups = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match reload_secrets(&secrets, &audit_log, "SIGHUP") {
+                Ok(()) => info!("🔑 Secrets reloaded"),
+                Err(e) => error!("Failed to reload secrets, keeping the current keys: {:#}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+// How long a round may sit with every move in before the watchdog steps in
+const STUCK_ROUND_GRACE: std::time::Duration = std::time::Duration::from_secs(5);
+
+// Game clock ticks (seconds) between sweeps of quit records that aged out
+const QUIT_PENALTY_PRUNE_TICKS: u64 = 60;
+
+// Server-side game clock: ends games that exceed their match time limit or reconnect grace,
+// and drops queue entries left behind by dead connections
+fn start_game_clock(game_manager: Arc<GameManager>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+
+        for tick in 1u64.. {
+            interval.tick().await;
+
+            match game_manager.enforce_time_limits().await {
+                Ok(expired) if expired > 0 => info!("⏰ Ended {} games on time limit", expired),
+                Ok(_) => {}
+                Err(e) => error!("Game clock error: {}", e),
+            }
+
+            match game_manager.expire_pauses().await {
+                Ok(resumed) if resumed > 0 => info!("▶️  Resumed {} games after max pause", resumed),
+                Ok(_) => {}
+                Err(e) => error!("Pause expiry error: {}", e),
+            }
+
+            match game_manager.expire_reconnect_grace().await {
+                Ok(forfeited) if forfeited > 0 => info!("🔌 Forfeited {} games after reconnect grace", forfeited),
+                Ok(_) => {}
+                Err(e) => error!("Reconnect grace error: {}", e),
+            }
+
+            let recovered = game_manager.recover_stuck_rooms(STUCK_ROUND_GRACE).await;
+            if recovered > 0 {
+                warn!("🩹 Recovered {} rooms stuck on a fully played round or left by a panicked timer", recovered);
+            }
+
+            game_manager.evict_stale_queue_entries().await;
+            game_manager.check_starvation().await;
+            if tick % QUIT_PENALTY_PRUNE_TICKS == 0 {
+                game_manager.prune_quit_penalties();
+            }
         }
     });
 }
 
 // Ultra-optimized routes with SIMD JSON processing
+#[allow(clippy::too_many_arguments)]
 fn create_ultra_optimized_routes(
     game_manager: Arc<GameManager>,
-) -> impl warp::Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    long_poll: Arc<LongPollSessions>,
+    listeners: Arc<Vec<Arc<WsListener>>>,
+    max_connections: usize, // After fitting to the open file limit
+    seasons: Arc<Seasons>,
+    lifetime_stats: Arc<LifetimeStats>,
+    cache: Arc<ResponseCache>,
+    rate_limiter: Arc<RestRateLimiter>,
+    secrets: Arc<SecretStore>,
+) -> impl warp::Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
     let health = warp::path("health")
         .and(warp::get())
         .and(with_game_manager(game_manager.clone()))
@@ -213,18 +726,32 @@ fn create_ultra_optimized_routes(
     let stats = warp::path("stats")
         .and(warp::get())
         .and(with_game_manager(game_manager.clone()))
+        .and(warp::any().map(move || lifetime_stats.clone()))
+        .and(with_response_cache(cache.clone()))
+        .and(with_cache_bypass(secrets.clone()))
         .and_then(ultra_stats_handler);
 
     let metrics = warp::path("ultra-metrics")
         .and(warp::get())
         .and(with_game_manager(game_manager.clone()))
+        .and(warp::any().map(move || listeners.clone()))
+        .and(warp::any().map(move || max_connections))
+        .and(with_response_cache(cache.clone()))
+        .and(warp::any().map(move || rate_limiter.clone()))
         .and_then(ultra_metrics_handler);
         
     let system_info = warp::path("system")
         .and(warp::get())
+        .and(warp::any().map(move || max_connections))
         .and_then(system_info_handler);
 
-    health.or(stats).or(metrics).or(system_info)
+    let rooms = create_room_routes(game_manager.clone());
+    let games = create_game_routes(game_manager.clone());
+    let seasons = create_season_routes(seasons, cache, secrets);
+
+    let poll = create_long_poll_routes(long_poll);
+
+    health.or(stats).or(metrics).or(system_info).or(rooms).or(games).or(seasons).or(poll)
 }
 
 fn with_game_manager(
@@ -233,6 +760,12 @@ fn with_game_manager(
     warp::any().map(move || game_manager.clone())
 }
 
+fn with_response_cache(
+    cache: Arc<ResponseCache>,
+) -> impl warp::Filter<Extract = (Arc<ResponseCache>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || cache.clone())
+}
+
 // Ultra-fast health handler with SIMD JSON
 async fn ultra_health_handler(
     game_manager: Arc<GameManager>,
@@ -257,16 +790,26 @@ async fn ultra_health_handler(
     Ok(warp::reply::json(&response))
 }
 
-// Ultra-fast stats handler
+// Ultra-fast stats handler, reused for the cache TTL across pollers
 async fn ultra_stats_handler(
     game_manager: Arc<GameManager>,
+    lifetime_stats: Arc<LifetimeStats>,
+    cache: Arc<ResponseCache>,
+    fresh: bool,
 ) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(cache.json("stats", fresh, || stats_body(game_manager, lifetime_stats)).await)
+}
+
+async fn stats_body(game_manager: Arc<GameManager>, lifetime_stats: Arc<LifetimeStats>) -> serde_json::Value {
     let (total_rooms, active_games, waiting_players) = game_manager.get_stats().await;
+    let since_boot = ServerTotals::since_boot(&game_manager);
     
-    let response = serde_json::json!({
+    serde_json::json!({
         "total_rooms": total_rooms,
         "active_games": active_games,
         "waiting_players": waiting_players,
+        "since_boot": since_boot,
+        "lifetime": lifetime_stats.lifetime(since_boot),
         "performance_optimizations": [
             "mimalloc_allocator",
             "dashmap_concurrent_hashmap", 
@@ -276,14 +819,16 @@ async fn ultra_stats_handler(
             "tcp_nodelay_enabled",
             "fat_lto_optimization"
         ]
-    });
-    
-    Ok(warp::reply::json(&response))
+    })
 }
 
 // Ultra-detailed metrics handler
 async fn ultra_metrics_handler(
     game_manager: Arc<GameManager>,
+    listeners: Arc<Vec<Arc<WsListener>>>,
+    max_connections: usize,
+    cache: Arc<ResponseCache>,
+    rate_limiter: Arc<RestRateLimiter>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let (total_rooms, active_games, waiting_players) = game_manager.get_stats().await;
     let current_connections = TOTAL_CONNECTIONS.load(Ordering::Relaxed);
@@ -293,12 +838,36 @@ async fn ultra_metrics_handler(
         "game_metrics": {
             "total_rooms": total_rooms,
             "active_games": active_games,
-            "waiting_players": waiting_players
+            "waiting_players": waiting_players,
+            "counters": game_manager.live_stats(),
+            "shards": game_manager.shard_stats().await,
+            "queue_waits": game_manager.starvation_report()
         },
         "connection_metrics": {
             "current_connections": current_connections,
             "peak_connections": peak_connections,
-            "connection_utilization": (current_connections as f64 / 5000.0) * 100.0
+            "stalled_handshakes": STALLED_HANDSHAKES.load(Ordering::Relaxed),
+            "slow_client_evictions": SLOW_CLIENT_EVICTIONS.load(Ordering::Relaxed),
+            "connection_panics": CONNECTION_PANICS.load(Ordering::Relaxed),
+            "unexpected_frames": UNEXPECTED_FRAMES.load(Ordering::Relaxed),
+            "batched_frames": BATCHED_FRAMES.load(Ordering::Relaxed),
+            "batched_messages": BATCHED_MESSAGES.load(Ordering::Relaxed),
+            "codec_fallbacks": CODEC_FALLBACKS.load(Ordering::Relaxed),
+            "preflight_rejects": PREFLIGHT_REJECTS.snapshot(),
+            "writer_shards": WRITER_SHARDS.load(Ordering::Relaxed),
+            "active_writers": ACTIVE_WRITERS.load(Ordering::Relaxed),
+            "file_descriptors": FdUsage::sample(),
+            "connection_utilization": (current_connections as f64 / max_connections.max(1) as f64) * 100.0,
+            "listeners": listeners.iter().map(|listener| listener.snapshot()).collect::<Vec<_>>()
+        },
+        "client_versions": CLIENT_METRICS.snapshot(),
+        "runtime_health": RUNTIME_HEALTH.snapshot(),
+        "buffer_pool": BufferPool::stats(),
+        "response_cache": cache.stats(),
+        "rest_rate_limit": rate_limiter.stats(),
+        "memory_metrics": {
+            "process": MemoryUsage::sample(),
+            "estimated": game_manager.memory_estimate().await
         },
         "optimization_features": {
             "memory_allocator": "mimalloc",
@@ -308,11 +877,11 @@ async fn ultra_metrics_handler(
             "json_processing": "simd_optimized",
             "tcp_optimization": "nodelay_enabled",
             "compiler_optimization": "fat_lto",
-            "runtime": "multi_thread_8_workers"
+            "runtime": format!("multi_thread_{}_workers", tokio::runtime::Handle::current().metrics().num_workers())
         },
         "capacity_info": {
-            "max_connections": 5000,
-            "max_blocking_threads": 1024,
+            "max_connections": max_connections,
+            "max_blocking_threads": CONFIG.performance.max_blocking_threads,
             "channel_buffer_size": 2048,
             "frame_size_kb": 32,
             "message_size_kb": 512
@@ -323,25 +892,19 @@ async fn ultra_metrics_handler(
 }
 
 // System information handler
-async fn system_info_handler() -> Result<impl warp::Reply, warp::Rejection> {
+async fn system_info_handler(max_connections: usize) -> Result<impl warp::Reply, warp::Rejection> {
+    let build = BuildInfo::current();
     let response = serde_json::json!({
-        "server_version": "ultra-optimized-v2.0",
-        "rust_version": "1.75+",
-        "build_profile": "ultra-performance",
-        "features": [
-            "mimalloc_global_allocator",
-            "multi_thread_tokio_runtime", 
-            "fat_lto_optimization",
-            "simd_json_processing",
-            "crossbeam_lock_free_data_structures",
-            "flume_ultra_fast_channels",
-            "dashmap_concurrent_hashmap",
-            "tcp_nodelay_optimization",
-            "overflow_checks_disabled",
-            "debug_assertions_disabled"
-        ],
+        "server_version": build.version,
+        "git_hash": build.git_hash,
+        "rust_version": build.rustc,
+        "build_profile": build.profile,
+        "built_at": build.built_at,
+        "features": build.features,
+        "debug_assertions": cfg!(debug_assertions),
+        "memory_allocator": "mimalloc",
         "performance_targets": {
-            "max_connections": 5000,
+            "max_connections": max_connections,
             "target_latency_ms": "<1ms",
             "target_throughput": ">10000_msg/sec",
             "memory_efficiency": "ultra_high"
@@ -349,4 +912,16 @@ async fn system_info_handler() -> Result<impl warp::Reply, warp::Rejection> {
     });
     
     Ok(warp::reply::json(&response))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_self_check_plays_a_game_against_the_full_server() {
+        let (server, ws_url, rest_url) = super::spawn_self_check_server(super::self_check_config()).await.unwrap();
+        let result = super::run_self_check(&ws_url, &rest_url).await;
+        server.abort();
+        result.unwrap();
+    }
+}
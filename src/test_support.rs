@@ -0,0 +1,204 @@
+//! An in-process server on ephemeral local ports, for integration tests here and in
+//! crates embedding this one, so nothing has to be launched at ws://127.0.0.1:8080 first.
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! let server = rps_server::test_support::spawn_test_server().await?;
+//! println!("{} {}", server.ws_url(), server.rest_url());
+//! server.shutdown().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{Context, Result};
+use hyper::server::conn::{AddrIncoming, AddrStream};
+use hyper::service::make_service_fn;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tracing::error;
+use warp::Filter;
+
+use crate::application::GameManager;
+use crate::config::{ListenerConfig, ServerConfig};
+use crate::infrastructure::{
+    create_long_poll_routes, create_routes, http_options, routes_service, with_request_id, LongPollSessions, WebSocketHandler, WsListener,
+};
+
+/// A running test server. Dropping it stops the servers too; sockets already open stay
+/// open until their clients close them.
+pub struct TestServer {
+    ws_addr: SocketAddr,
+    rest_addr: SocketAddr,
+    game_manager: Arc<GameManager>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl TestServer {
+    /// Where game clients connect, e.g. `ws://127.0.0.1:41234`.
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}", self.ws_addr)
+    }
+
+    /// Base URL of the REST API (`/health`, `/stats`, `/rooms/{id}`, `/games/{id}`, `/poll/...`).
+    pub fn rest_url(&self) -> String {
+        format!("http://{}", self.rest_addr)
+    }
+
+    /// The server's game state, for setting up or inspecting a test directly.
+    pub fn game_manager(&self) -> Arc<GameManager> {
+        self.game_manager.clone()
+    }
+
+    /// Stops accepting connections and waits for both servers to wind down.
+    pub async fn shutdown(mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// A server with the default configuration.
+pub async fn spawn_test_server() -> Result<TestServer> {
+    spawn_test_server_with(|_| {}).await
+}
+
+/// A server with the default configuration as changed by `configure`. The WebSocket and
+/// REST ports are always ephemeral on 127.0.0.1, whatever `configure` sets them to.
+pub async fn spawn_test_server_with(configure: impl FnOnce(&mut ServerConfig)) -> Result<TestServer> {
+    let mut config = ServerConfig::default();
+    configure(&mut config);
+    config.websocket.listeners = vec![ListenerConfig {
+        name: "test".to_string(),
+        host: "127.0.0.1".to_string(),
+        port: 0,
+        max_connections: None,
+        tls: None,
+    }];
+
+    let game_manager = Arc::new(
+        GameManager::new(config.game.clone().into())
+            .with_bot_detection(config.bot_detection.clone())
+            .with_matchmaking(config.matchmaking.clone())
+            .with_blitz(config.blitz.clone())
+            .with_game_history(config.game_history.clone())
+            .with_spam_guard(config.spam_guard.clone())
+            .with_capacity(config.capacity.clone(), config.websocket.max_connections),
+    );
+    let handler = WebSocketHandler::new(game_manager.clone(), config.websocket.clone());
+
+    let listener = Arc::new(WsListener::from_config(&config.websocket.listeners[0], config.websocket.max_connections)?);
+    let socket = listener.bind().await?;
+    let ws_addr = socket.local_addr()?;
+    let long_poll = Arc::new(LongPollSessions::new(handler.clone(), config.long_poll.clone()));
+    let ws_server = tokio::spawn(async move {
+        if let Err(e) = listener.serve(socket, handler).await {
+            error!("Test WebSocket server failed: {}", e);
+        }
+    });
+
+    let routes = with_request_id(create_routes(game_manager.clone()).or(create_long_poll_routes(long_poll)));
+    let mut incoming = AddrIncoming::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).context("Failed to bind test REST API")?;
+    incoming.set_nodelay(true);
+    let rest_addr = incoming.local_addr();
+    let make_service = make_service_fn(move |connection: &AddrStream| {
+        let service = routes_service(routes.clone(), connection.remote_addr());
+        async move { Ok::<_, Infallible>(service) }
+    });
+    let rest = hyper::server::Builder::new(incoming, http_options(&config.rest_api)).serve(make_service);
+    let rest_server = tokio::spawn(async move {
+        if let Err(e) = rest.await {
+            error!("Test REST API failed: {}", e);
+        }
+    });
+
+    Ok(TestServer {
+        ws_addr,
+        rest_addr,
+        game_manager,
+        tasks: vec![ws_server, rest_server],
+    })
+}
+
+/// A bare listener on an ephemeral 127.0.0.1 port, for tests that drive a
+/// `WebSocketHandler` of their own instead of a whole server.
+pub struct TestListener {
+    listener: TcpListener,
+    addr: SocketAddr,
+}
+
+impl TestListener {
+    pub async fn bind() -> Result<Self> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await.context("Failed to bind test listener")?;
+        let addr = listener.local_addr()?;
+        Ok(Self { listener, addr })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    /// The next incoming connection, for a test that hands it to a handler itself.
+    pub async fn accept(&self) -> Result<TcpStream> {
+        Ok(self.listener.accept().await?.0)
+    }
+
+    /// Hands every incoming connection to `handler` until the returned server is dropped.
+    pub fn serve(self, handler: WebSocketHandler) -> TestWsServer {
+        let addr = self.addr;
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = self.listener.accept().await {
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handler.handle_connection(stream).await {
+                        error!("Test WebSocket connection failed: {}", e);
+                    }
+                });
+            }
+        });
+        TestWsServer { addr, task }
+    }
+}
+
+/// Connections served by one `WebSocketHandler`; see [`spawn_ws_server`].
+pub struct TestWsServer {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl TestWsServer {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+}
+
+impl Drop for TestWsServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Serves `handler` on an ephemeral local port, for tests that need a custom game
+/// manager or WebSocket config but no REST API.
+pub async fn spawn_ws_server(handler: WebSocketHandler) -> Result<TestWsServer> {
+    Ok(TestListener::bind().await?.serve(handler))
+}
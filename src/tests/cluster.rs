@@ -0,0 +1,240 @@
+use std::sync::Arc;
+
+use crate::application::GameManager;
+use crate::config::{SecretSource, ServerConfig};
+use crate::domain::{GameChoice, GameConfig, GameMode, GameStatus, Player, ServerMessage};
+use crate::infrastructure::WebSocketHandler;
+use crate::test_support::TestListener;
+
+/// Two linked nodes, `node-a` and `node-b`, each serving WebSockets on its own port.
+#[cfg(feature = "client")]
+async fn spawn_cluster() -> (Vec<crate::test_support::TestWsServer>, Vec<Arc<crate::infrastructure::Cluster>>, Vec<Arc<GameManager>>) {
+    use crate::config::ClusterConfig;
+    use crate::infrastructure::Cluster;
+
+    std::env::set_var("RPS_TEST_CLUSTER_SECRET", "cluster-key");
+    let listeners = [TestListener::bind().await.unwrap(), TestListener::bind().await.unwrap()];
+    let urls: Vec<String> = listeners.iter().map(TestListener::ws_url).collect();
+    let mut servers = Vec::new();
+    let mut nodes = Vec::new();
+    let mut managers = Vec::new();
+    for (i, listener) in listeners.into_iter().enumerate() {
+        let config = ClusterConfig {
+            node_id: Some(["node-a", "node-b"][i].to_string()),
+            peers: vec![urls[1 - i].clone()],
+            secret: SecretSource {
+                env: Some("RPS_TEST_CLUSTER_SECRET".to_string()),
+                file: None,
+            },
+            heartbeat_interval_ms: 50,
+            advertise_url: Some(urls[i].clone()),
+            ..ClusterConfig::default()
+        };
+        let cluster = Arc::new(Cluster::new(&config).unwrap().unwrap());
+        let manager = Arc::new(GameManager::new(GameConfig::default()));
+        let handler = WebSocketHandler::new(manager.clone(), ServerConfig::default().websocket).with_cluster(cluster.clone());
+        cluster.clone().spawn(handler.clone());
+        managers.push(manager);
+        servers.push(listener.serve(handler));
+        nodes.push(cluster);
+    }
+    eventually(|| nodes.iter().all(|node| node.snapshot().peers.iter().any(|peer| peer.alive))).await;
+    (servers, nodes, managers)
+}
+
+#[cfg(feature = "client")]
+async fn eventually(check: impl Fn() -> bool) {
+    for _ in 0..100 {
+        if check() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("condition not met within 2s");
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn test_cluster_matches_players_across_nodes() {
+    use crate::client::{ClientOptions, GameClient};
+    use crate::config::ClusterConfig;
+    use crate::infrastructure::Cluster;
+
+    let (servers, nodes, _managers) = spawn_cluster().await;
+    let urls: Vec<String> = servers.iter().map(|server| server.ws_url()).collect();
+    assert!(Cluster::new(&ClusterConfig::default()).unwrap().is_none());
+
+    // Peer links need the shared token
+    assert!(tokio_tungstenite::connect_async(format!("{}/cluster", urls[0])).await.is_err());
+
+    let options = |url: &str, id: &str| ClientOptions {
+        url: url.to_string(),
+        player_id: Some(id.to_string()),
+        response_timeout: std::time::Duration::from_secs(2),
+        ..ClientOptions::default()
+    };
+    let mut alice = GameClient::connect(options(&urls[0], "alice")).await.unwrap();
+    let waiting = alice.find_match(GameMode::Solo).await.unwrap();
+    assert!(matches!(waiting, ServerMessage::Matchmaking { matched: false, .. }));
+    eventually(|| nodes[1].snapshot().peers[0].solo_waiting == 1).await;
+
+    // Nobody waits on node b, so bob is queued on node a and served from there
+    let mut bob = GameClient::connect(options(&urls[1], "bob")).await.unwrap();
+    let matched = bob.find_match(GameMode::Solo).await.unwrap();
+    assert!(matches!(matched, ServerMessage::Matchmaking { matched: true, .. }));
+    assert_eq!(nodes[1].snapshot().handoffs, 1);
+    assert_eq!(nodes[0].snapshot().hosted_here, 1);
+    eventually(|| nodes[0].locate("bob").as_deref() == Some("node-b")).await;
+    assert_eq!(nodes[1].locate("alice").as_deref(), Some("node-a"));
+
+    while !matches!(alice.next_event().await.unwrap(), ServerMessage::GameStart { .. }) {}
+    alice.play(GameChoice::Rock).await.unwrap();
+    bob.play(GameChoice::Scissors).await.unwrap();
+    for client in [&mut alice, &mut bob] {
+        let winner = loop {
+            if let ServerMessage::RoundResult { winner, .. } = client.next_event().await.unwrap() {
+                break winner;
+            }
+        };
+        assert_eq!(winner.as_deref(), Some("alice"));
+    }
+    assert!(nodes[1].snapshot().forwarded >= 1);
+
+    // Bob's token names node a, which holds the game, so he reconnects straight there
+    let token = bob.reconnect_token().unwrap().to_string();
+    assert!(token.starts_with("node-a."));
+    assert_eq!(nodes[1].verify_token(&token).unwrap().player_id, "bob");
+    assert!(nodes[1].verify_token(&token.replacen("node-a", "node-b", 1)).is_none());
+    bob.reconnect().await.unwrap();
+    eventually(|| nodes[0].snapshot().hosted_here == 0).await;
+    alice.play(GameChoice::Paper).await.unwrap();
+    bob.play(GameChoice::Scissors).await.unwrap();
+    let winner = loop {
+        if let ServerMessage::RoundResult { winner, .. } = bob.next_event().await.unwrap() {
+            break winner;
+        }
+    };
+    assert_eq!(winner.as_deref(), Some("bob"));
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn test_draining_node_hands_live_rooms_to_a_peer() {
+    use crate::application::RoomState;
+    use crate::client::{ClientOptions, GameClient};
+
+    // The state format carries the round in progress, moves made so far included
+    let manager = GameManager::new(GameConfig::default());
+    let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, mut rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+    manager.find_match(Arc::new(Player::new("p2".to_string(), tx2.clone()))).await.unwrap();
+    manager.submit_move("p1", GameChoice::Rock).await.unwrap();
+    let room_id = manager.live_room_ids().await.pop().unwrap();
+    let (state, _) = manager.take_room(&room_id).await.unwrap();
+    assert!(manager.room_snapshot(&room_id).await.is_none());
+    let state: RoomState = serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+
+    let peer = GameManager::new(GameConfig::default());
+    let p2 = Arc::new(Player::new("p2".to_string(), tx2));
+    let connections = [("p2".to_string(), p2.clone())].into_iter().collect();
+    peer.restore_room(state.clone(), &connections).await.unwrap();
+    assert!(peer.restore_room(state, &connections).await.is_err());
+    let snapshot = peer.room_snapshot(&room_id).await.unwrap();
+    assert_eq!(snapshot.status, GameStatus::Paused); // p1 hasn't reconnected yet
+    assert!(!snapshot.players.iter().find(|p| p.id == "p1").unwrap().connected);
+
+    let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+    assert_eq!(peer.reconnect_player(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap(), Some(room_id));
+    peer.submit_move("p2", GameChoice::Scissors).await.unwrap();
+    let winner = std::iter::from_fn(|| rx2.try_recv().ok())
+        .find_map(|message| match message {
+            ServerMessage::RoundResult { winner, .. } => Some(winner),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(winner.as_deref(), Some("p1"));
+
+    // Players connected to the draining node follow their game to the peer
+    let (servers, nodes, managers) = spawn_cluster().await;
+    let urls: Vec<String> = servers.iter().map(|server| server.ws_url()).collect();
+    let options = |id: &str| ClientOptions {
+        url: urls[0].clone(),
+        player_id: Some(id.to_string()),
+        response_timeout: std::time::Duration::from_secs(2),
+        auto_reconnect: true,
+        ..ClientOptions::default()
+    };
+    let mut alice = GameClient::connect(options("alice")).await.unwrap();
+    let mut bob = GameClient::connect(options("bob")).await.unwrap();
+    alice.find_match(GameMode::Solo).await.unwrap();
+    bob.find_match(GameMode::Solo).await.unwrap();
+    assert_eq!(nodes[0].snapshot().handoffs + nodes[1].snapshot().handoffs, 0);
+
+    let drained = nodes[0].drain(&managers[0]).await;
+    assert_eq!(drained.len(), 1);
+    for client in [&mut alice, &mut bob] {
+        while !matches!(client.next_event().await.unwrap(), ServerMessage::RoomMigrated { .. }) {}
+        assert!(client.reconnect_token().unwrap().starts_with("node-b."));
+    }
+    assert_eq!(nodes[0].snapshot().rooms_handed_over, 1);
+    assert_eq!(nodes[1].snapshot().rooms_taken_over, 1);
+    assert!(managers[0].live_room_ids().await.is_empty());
+    eventually(|| nodes[1].locate("bob").as_deref() == Some("node-b")).await;
+
+    while !matches!(bob.next_event().await.unwrap(), ServerMessage::GameResumed { .. }) {}
+    alice.play(GameChoice::Paper).await.unwrap();
+    bob.play(GameChoice::Rock).await.unwrap();
+    let winner = loop {
+        if let ServerMessage::RoundResult { winner, .. } = bob.next_event().await.unwrap() {
+            break winner;
+        }
+    };
+    assert_eq!(winner.as_deref(), Some("alice"));
+}
+
+#[cfg(feature = "chaos")]
+#[tokio::test]
+async fn test_chaos_drops_messages_and_kills_random_rooms() {
+    use crate::application::{Chaos, ChaosSettings};
+
+    // A private instance: the global one would drop messages in every other test
+    let chaos = Chaos::new();
+    assert!(!chaos.drop_outbound());
+    assert_eq!(chaos.broadcast_delay(), None);
+    chaos.set(ChaosSettings { drop_outbound_percent: 250.0, broadcast_delay_ms: 40 });
+    assert_eq!(chaos.snapshot().settings.drop_outbound_percent, 100.0);
+    assert!((0..100).all(|_| chaos.drop_outbound()));
+    assert_eq!(chaos.broadcast_delay(), Some(std::time::Duration::from_millis(40)));
+    chaos.clear();
+    assert!(!chaos.drop_outbound());
+    assert_eq!(chaos.snapshot().dropped_messages, 100);
+
+    let mut picked = chaos.pick((0..10).collect(), 4);
+    picked.sort();
+    picked.dedup();
+    assert_eq!(picked.len(), 4);
+    assert_eq!(chaos.pick(vec![1, 2], 5).len(), 2);
+
+    let manager = GameManager::new(GameConfig::default());
+    let mut receivers = Vec::new();
+    for i in 0..6 {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        receivers.push(rx);
+        manager.find_match(Arc::new(Player::new(format!("p{}", i), tx))).await.unwrap();
+    }
+    assert_eq!(manager.get_stats().await.0, 3);
+
+    let killed = manager.kill_random_rooms(2).await;
+    assert_eq!(killed.len(), 2);
+    assert_eq!(manager.get_stats().await.0, 1);
+    for room_id in &killed {
+        assert!(manager.room_snapshot(room_id).await.is_none());
+    }
+    let closed = receivers
+        .iter_mut()
+        .map(|rx| std::iter::from_fn(|| rx.try_recv().ok()).any(|m| matches!(m, ServerMessage::RoomClosed { .. })))
+        .filter(|&closed| closed)
+        .count();
+    assert_eq!(closed, 4);
+}
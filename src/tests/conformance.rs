@@ -0,0 +1,338 @@
+//! Wire protocol conformance: scripted message sequences with the exact replies a
+//! server must send. Doubles as a reference for client implementers.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::client::{ClientOptions, GameClient};
+use crate::domain::{ClientMessage, GameChoice, GameMode, GameType};
+use crate::test_support::spawn_test_server;
+
+/// Matches any value in an expected message, e.g. generated room ids.
+pub const ANY: &str = "*";
+
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub enum Step {
+    Send(usize, ClientMessage), // Client index, message
+    SendRaw(usize, &'static str),
+    Expect(usize, Value), // The client's next message must equal this, with `ANY` as a wildcard
+}
+
+pub struct Scenario {
+    pub name: &'static str,
+    pub clients: usize,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Default)]
+pub struct ConformanceReport {
+    pub passed: Vec<&'static str>,
+    pub failed: Vec<(&'static str, String)>,
+}
+
+/// Runs every scenario against its own fresh server.
+pub async fn run_conformance_suite() -> ConformanceReport {
+    let mut report = ConformanceReport::default();
+    for scenario in scenarios() {
+        match run_scenario(&scenario).await {
+            Ok(()) => report.passed.push(scenario.name),
+            Err(e) => {
+                error!("❌ {}: {:#}", scenario.name, e);
+                report.failed.push((scenario.name, format!("{:#}", e)));
+            }
+        }
+    }
+    info!("Protocol conformance: {} passed, {} failed", report.passed.len(), report.failed.len());
+    report
+}
+
+pub async fn run_scenario(scenario: &Scenario) -> Result<()> {
+    let server = spawn_test_server().await?;
+    let url = server.ws_url();
+    let mut clients = Vec::with_capacity(scenario.clients);
+    for _ in 0..scenario.clients {
+        let options = ClientOptions {
+            url: url.clone(),
+            response_timeout: REPLY_TIMEOUT,
+            ..ClientOptions::default()
+        };
+        clients.push(GameClient::open(options).await?);
+    }
+
+    for (index, step) in scenario.steps.iter().enumerate() {
+        let step_result = match step {
+            Step::Send(client, message) => clients[*client].send(message).await,
+            Step::SendRaw(client, text) => clients[*client].send_text(text.to_string()).await,
+            Step::Expect(client, expected) => {
+                let actual = serde_json::to_value(clients[*client].next_event().await?)?;
+                if !matches_pattern(expected, &actual) {
+                    bail!("client {} expected {} but got {}", client, expected, actual);
+                }
+                Ok(())
+            }
+        };
+        step_result.with_context(|| format!("step {}", index + 1))?;
+    }
+    Ok(())
+}
+
+/// Exact equality, except that `ANY` in the pattern matches whatever is in its place.
+pub fn matches_pattern(pattern: &Value, actual: &Value) -> bool {
+    match (pattern, actual) {
+        (Value::String(any), _) if any == ANY => true,
+        (Value::Object(pattern), Value::Object(actual)) => {
+            pattern.len() == actual.len()
+                && pattern
+                    .iter()
+                    .all(|(key, value)| actual.get(key).is_some_and(|actual| matches_pattern(value, actual)))
+        }
+        (Value::Array(pattern), Value::Array(actual)) => {
+            pattern.len() == actual.len() && pattern.iter().zip(actual).all(|(p, a)| matches_pattern(p, a))
+        }
+        _ => pattern == actual,
+    }
+}
+
+fn connect(player_id: &str) -> ClientMessage {
+    ClientMessage::Connect {
+        player_id: Some(player_id.to_string()),
+        locale: None,
+        client_version: None,
+        protocol_version: None,
+        reconnect_token: None,
+        display_name: None,
+        region: None,
+        experiments: Vec::new(),
+        tenant: None,
+        invite: None,
+    }
+}
+
+fn connected(player_id: &str) -> Value {
+    json!({ "type": "connected", "playerId": player_id, "locale": "en", "build": ANY, "resumeToken": ANY })
+}
+
+fn find_match() -> ClientMessage {
+    ClientMessage::FindMatch { mode: GameMode::Solo, game: GameType::default(), template: None }
+}
+
+fn play(choice: GameChoice) -> ClientMessage {
+    ClientMessage::PlayerMove { choice }
+}
+
+fn error(code: &str, message: &str) -> Value {
+    json!({ "type": "error", "code": code, "message": message, "requestId": ANY })
+}
+
+fn waiting() -> Value {
+    json!({ "type": "matchmaking", "matched": false, "waiting": true, "roomId": null })
+}
+
+/// The protocol, one scenario per rule.
+pub fn scenarios() -> Vec<Scenario> {
+    use Step::*;
+
+    vec![
+        Scenario {
+            name: "connect echoes the requested player id and served locale",
+            clients: 1,
+            steps: vec![Send(0, connect("alice")), Expect(0, connected("alice"))],
+        },
+        Scenario {
+            name: "messages before connect are rejected",
+            clients: 1,
+            steps: vec![
+                Send(0, find_match()),
+                Expect(0, error("not_connected", "Not connected")),
+                Send(0, connect("alice")),
+                Expect(0, connected("alice")),
+            ],
+        },
+        Scenario {
+            name: "a second connect is rejected",
+            clients: 1,
+            steps: vec![
+                Send(0, connect("alice")),
+                Expect(0, connected("alice")),
+                Send(0, connect("mallory")),
+                Expect(0, error("already_connected", "Already connected")),
+            ],
+        },
+        Scenario {
+            name: "moves and pauses need a game",
+            clients: 1,
+            steps: vec![
+                Send(0, connect("alice")),
+                Expect(0, connected("alice")),
+                Send(0, play(GameChoice::Rock)),
+                Expect(0, error("not_in_game", "Not in a game")),
+                Send(0, ClientMessage::PauseRequest),
+                Expect(0, error("not_in_game", "Not in a game")),
+            ],
+        },
+        Scenario {
+            name: "a queued player can't queue again",
+            clients: 1,
+            steps: vec![
+                Send(0, connect("alice")),
+                Expect(0, connected("alice")),
+                Send(0, find_match()),
+                Expect(0, waiting()),
+                Send(0, find_match()),
+                Expect(0, error("already_queued", "Already waiting for a match")),
+            ],
+        },
+        Scenario {
+            name: "malformed messages are refused with a typed error and keep the connection",
+            clients: 1,
+            steps: vec![
+                SendRaw(0, r#"{"type":"teleport"}"#),
+                Expect(0, error("unknown_message_type", "Unknown message type")),
+                SendRaw(0, r#"["connect"]"#),
+                Expect(0, error("malformed_message", r#"Messages are JSON objects with a "type" field"#)),
+                SendRaw(0, r#"{"type":"connect","playerId":7}"#),
+                Expect(0, error("internal_error", "Internal server error")),
+                Send(0, connect("alice")),
+                Expect(0, connected("alice")),
+            ],
+        },
+        Scenario {
+            name: "unknown rooms are reported",
+            clients: 1,
+            steps: vec![
+                Send(0, connect("alice")),
+                Expect(0, connected("alice")),
+                Send(0, ClientMessage::JoinRoom { room: "NOSUCHRM".to_string() }),
+                Expect(0, error("room_not_found", "No room with that id or code is waiting for you")),
+            ],
+        },
+        Scenario {
+            name: "requested state follows the player from idle to mid-round",
+            clients: 2,
+            steps: request_state(),
+        },
+        Scenario {
+            name: "a best-of-three match from queue to game end",
+            clients: 2,
+            steps: full_match(),
+        },
+    ]
+}
+
+fn request_state() -> Vec<Step> {
+    use Step::*;
+
+    vec![
+        Send(0, connect("alice")),
+        Expect(0, connected("alice")),
+        Send(1, connect("bob")),
+        Expect(1, connected("bob")),
+        Send(0, ClientMessage::RequestState),
+        Expect(0, json!({ "type": "state", "phase": "idle" })),
+        Send(0, find_match()),
+        Expect(0, waiting()),
+        Send(0, ClientMessage::RequestState),
+        Expect(0, json!({ "type": "state", "phase": "queued" })),
+        Send(1, find_match()),
+        Expect(0, json!({ "type": "gameStart", "seq": 1, "roomId": ANY, "gameId": ANY, "players": ANY, "maxRounds": 3, "drawPolicy": "noPoint" })),
+        Send(0, play(GameChoice::Rock)),
+        Expect(0, json!({ "type": "opponentMoved", "seq": 2, "playerId": "alice" })),
+        Send(0, play(GameChoice::Paper)),
+        Expect(0, error("move_already_submitted", "You already moved this round")),
+        Send(0, ClientMessage::RequestState),
+        // Alice sees her own first move but only the fact that Bob hasn't moved
+        Expect(
+            0,
+            json!({
+                "type": "state",
+                "phase": "inGame",
+                "roomId": ANY,
+                "gameId": ANY,
+                "status": "playing",
+                "round": 1,
+                "scores": { "alice": 0, "bob": 0 },
+                "awaitingMoves": ["bob"],
+                "yourMove": "rock",
+                "seq": 2,
+            }),
+        ),
+    ]
+}
+
+fn full_match() -> Vec<Step> {
+    use Step::*;
+
+    let game_start = json!({
+        "type": "gameStart",
+        "seq": 1, // Counts the room's broadcasts from 1
+        "roomId": ANY,
+        "gameId": ANY,
+        "players": [{ "id": "alice" }, { "id": "bob" }],
+        "maxRounds": 3,
+        "drawPolicy": "noPoint",
+    });
+    let round = |seq: u64, round: u32, alice: &str, bob: &str, winner: &str, scores: (u32, u32)| {
+        json!({
+            "type": "roundResult",
+            "seq": seq,
+            "round": round,
+            "winner": winner,
+            "moves": { "alice": alice, "bob": bob },
+            "scores": { "alice": scores.0, "bob": scores.1 },
+            "replay": false,
+            "decisionMs": { "alice": ANY, "bob": ANY },
+        })
+    };
+    let moved = |seq: u64| json!({ "type": "opponentMoved", "seq": seq, "playerId": "alice" });
+    let round_one = round(3, 1, "rock", "scissors", "alice", (1, 0));
+    let round_two = round(6, 2, "paper", "rock", "alice", (2, 0));
+    let game_end = json!({
+        "type": "gameEnd",
+        "seq": 7,
+        "gameId": ANY,
+        "winner": "alice",
+        "finalScores": { "alice": 2, "bob": 0 },
+        "reason": "completed",
+        "rounds": [
+            { "round": 1, "moves": { "alice": "rock", "bob": "scissors" }, "winner": "alice", "replayed": false, "responseMs": ANY, "decisionMs": ANY },
+            { "round": 2, "moves": { "alice": "paper", "bob": "rock" }, "winner": "alice", "replayed": false, "responseMs": ANY, "decisionMs": ANY },
+        ],
+    });
+
+    let mut steps = vec![
+        Send(0, connect("alice")),
+        Expect(0, connected("alice")),
+        Send(1, connect("bob")),
+        Expect(1, connected("bob")),
+        Send(0, find_match()),
+        Expect(0, waiting()),
+        Send(1, find_match()),
+        Expect(0, game_start.clone()),
+        Expect(1, game_start),
+        Expect(1, json!({ "type": "matchmaking", "matched": true, "waiting": null, "roomId": ANY })),
+        Send(1, find_match()),
+        Expect(1, error("already_in_game", "Already in a game")),
+        Send(0, play(GameChoice::Rock)),
+        Send(1, play(GameChoice::Scissors)),
+    ];
+    // Both hear that Alice moved first, without her choice
+    for client in 0..2 {
+        steps.push(Expect(client, moved(2)));
+        steps.push(Expect(client, round_one.clone()));
+        steps.push(Expect(client, json!({ "type": "nextRound", "seq": 4, "round": 2 })));
+    }
+    steps.push(Send(0, play(GameChoice::Paper)));
+    steps.push(Send(1, play(GameChoice::Rock)));
+    for client in 0..2 {
+        steps.push(Expect(client, moved(5)));
+        steps.push(Expect(client, round_two.clone()));
+        steps.push(Expect(client, game_end.clone()));
+    }
+    // Finished players may queue again
+    steps.push(Send(0, find_match()));
+    steps.push(Expect(0, waiting()));
+    steps
+}
@@ -0,0 +1,1085 @@
+use std::sync::Arc;
+
+use crate::application::{bot_move, GameManager, GameRoom, MoveRefused};
+use crate::config::{GameHistoryConfig, LongPollConfig, Secret, SecretStore, ServerConfig};
+use crate::domain::{DrawPolicy, GameChoice, GameConfig, GameEndReason, GameEvent, GameMode, GameStatus, Player, PlayerPhase, RuleSet, ServerMessage};
+use crate::infrastructure::{create_game_routes, create_room_routes, AuditLog, LongPollSessions, WebSocketHandler};
+
+#[tokio::test]
+async fn test_disconnect_pauses_then_reconnect_or_forfeit() {
+    use crate::application::MockClock;
+
+    let config = GameConfig {
+        reconnect_grace_ms: 60_000,
+        ..GameConfig::default()
+    };
+    let clock = Arc::new(MockClock::new());
+    let manager = GameManager::new(config).with_clock(clock.clone());
+    let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, mut rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+    manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+
+    manager.disconnect_player("p1").await.unwrap();
+    let (total_rooms, _, _) = manager.get_stats().await;
+    assert_eq!(total_rooms, 1);
+
+    let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+    let resumed = manager.reconnect_player(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+    assert!(resumed.is_some());
+
+    manager.disconnect_player("p1").await.unwrap();
+    clock.advance(std::time::Duration::from_secs(59));
+    assert_eq!(manager.expire_reconnect_grace().await.unwrap(), 0);
+    clock.advance(std::time::Duration::from_secs(1));
+    assert_eq!(manager.expire_reconnect_grace().await.unwrap(), 1);
+
+    let mut saw_pause = false;
+    let mut saw_resume = false;
+    let mut forfeit_winner = None;
+    while let Ok(message) = rx2.try_recv() {
+        match message {
+            ServerMessage::GamePaused { .. } => saw_pause = true,
+            ServerMessage::GameResumed { .. } => saw_resume = true,
+            ServerMessage::GameEnd { winner, reason, .. } => {
+                assert_eq!(reason, GameEndReason::Forfeit);
+                forfeit_winner = winner;
+            }
+            _ => {}
+        }
+    }
+    assert!(saw_pause && saw_resume);
+    assert_eq!(forfeit_winner.as_deref(), Some("p2"));
+    assert_eq!(manager.get_stats().await.0, 0);
+}
+
+#[tokio::test]
+async fn test_mutual_pause_blocks_moves_until_both_resume() {
+    let mut room = GameRoom::new("pause-room".to_string(), GameConfig::default());
+    let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, mut rx2) = tokio::sync::mpsc::unbounded_channel();
+    room.add_player(Arc::new(Player::new("p1".to_string(), tx1))).unwrap();
+    room.add_player(Arc::new(Player::new("p2".to_string(), tx2))).unwrap();
+
+    assert!(room.request_pause("p1").await.unwrap());
+    assert_eq!(room.status, GameStatus::Playing);
+    assert!(room.request_pause("p2").await.unwrap());
+    assert_eq!(room.status, GameStatus::Paused);
+    let refused = room.submit_move("p1", GameChoice::Rock).unwrap_err();
+    assert_eq!(refused.downcast_ref::<MoveRefused>(), Some(&MoveRefused::Paused));
+
+    assert!(room.request_resume("p1").await.unwrap());
+    assert_eq!(room.status, GameStatus::Paused);
+    assert!(room.request_resume("p2").await.unwrap());
+    assert_eq!(room.status, GameStatus::Playing);
+
+    let mut kinds = Vec::new();
+    while let Ok(message) = rx2.try_recv() {
+        kinds.push(match message {
+            ServerMessage::PauseRequested { .. } => "pauseRequested",
+            ServerMessage::GamePaused { .. } => "gamePaused",
+            ServerMessage::ResumeRequested { .. } => "resumeRequested",
+            ServerMessage::GameResumed { .. } => "gameResumed",
+            _ => "other",
+        });
+    }
+    assert_eq!(kinds, ["pauseRequested", "gamePaused", "resumeRequested", "gameResumed"]);
+}
+
+#[tokio::test]
+async fn test_watchdog_resolves_rounds_left_with_every_move_in() {
+    use crate::application::MockClock;
+    use std::time::Duration;
+
+    let clock = Arc::new(MockClock::new());
+    let mut room = GameRoom::new("stuck".to_string(), GameConfig::default()).with_clock(clock.clone());
+    let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    let p1 = Arc::new(Player::new("p1".to_string(), tx1));
+    let p2 = Arc::new(Player::new("p2".to_string(), tx2));
+    room.add_player(p1.clone()).unwrap();
+    room.add_player(p2.clone()).unwrap();
+    room.start_game().await.unwrap();
+    // Both moves in, but the round is never resolved, as when resolving it failed
+    room.submit_move("p1", GameChoice::Paper).unwrap();
+    assert!(room.submit_move("p2", GameChoice::Rock).unwrap());
+    assert!(!room.is_stuck(Duration::from_secs(60)));
+    clock.advance(Duration::from_secs(30));
+    assert!(room.is_stuck(Duration::from_secs(30)));
+    assert!(!room.is_stuck(Duration::from_secs(60)));
+
+    let manager = GameManager::new(GameConfig::default()).with_clock(clock.clone());
+    let connections = std::collections::HashMap::from([("p1".to_string(), p1), ("p2".to_string(), p2)]);
+    manager.restore_room(room.state(), &connections).await.unwrap();
+    while rx1.try_recv().is_ok() {}
+
+    assert_eq!(manager.recover_stuck_rooms(Duration::from_secs(60)).await, 0);
+    clock.advance(Duration::from_secs(30));
+    assert_eq!(manager.recover_stuck_rooms(Duration::from_secs(60)).await, 1);
+    let winner = loop {
+        if let ServerMessage::RoundResult { winner, .. } = rx1.recv().await.unwrap() {
+            break winner;
+        }
+    };
+    assert_eq!(winner.as_deref(), Some("p1"));
+    assert_eq!(manager.recover_stuck_rooms(Duration::ZERO).await, 0);
+    assert_eq!(manager.live_stats().rooms_recovered, 1);
+}
+
+#[tokio::test]
+async fn test_room_snapshot_endpoint_hides_pending_moves() {
+    let manager = Arc::new(GameManager::new(GameConfig::default()));
+    let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+    let room_id = match manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap() {
+        ServerMessage::Matchmaking { room_id, .. } => room_id.unwrap(),
+        other => panic!("unexpected {:?}", other),
+    };
+    manager.submit_move("p1", GameChoice::Paper).await.unwrap();
+
+    let routes = create_room_routes(manager);
+    let response = warp::test::request().path(&format!("/rooms/{}", room_id)).reply(&routes).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["current_round"], 1);
+    assert_eq!(body["players"].as_array().unwrap().len(), 2);
+    assert_eq!(body["spectator_count"], 0);
+    assert!(!String::from_utf8_lossy(response.body()).contains("paper"));
+
+    let missing = warp::test::request().path("/rooms/nope").reply(&routes).await;
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+async fn test_team_mode_scores_by_aggregate_wins() {
+    let manager = GameManager::new(GameConfig::default());
+    let mut receivers = Vec::new();
+    for id in ["a1", "a2", "b1", "b2"] {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        manager.find_match_in_mode(Arc::new(Player::new(id.to_string(), tx)), GameMode::Teams).await.unwrap();
+        receivers.push(rx);
+    }
+
+    // a1/a2 rock vs b1 scissors / b2 paper: each side wins two of the four cross matchups
+    for (id, choice) in [("a1", GameChoice::Rock), ("a2", GameChoice::Rock), ("b1", GameChoice::Scissors), ("b2", GameChoice::Paper)] {
+        manager.submit_move(id, choice).await.unwrap();
+    }
+    // Both rock vs both scissors: team-a sweeps
+    for (id, choice) in [("a1", GameChoice::Rock), ("a2", GameChoice::Rock), ("b1", GameChoice::Scissors), ("b2", GameChoice::Scissors)] {
+        manager.submit_move(id, choice).await.unwrap();
+    }
+
+    let mut results = Vec::new();
+    while let Ok(message) = receivers[3].try_recv() {
+        if let ServerMessage::RoundResult { winner, scores, teams, .. } = message {
+            assert_eq!(teams.unwrap()["b2"], "team-b");
+            results.push((winner, scores["team-a"], scores["team-b"]));
+        }
+    }
+    assert_eq!(results, [(None, 0, 0), (Some("team-a".to_string()), 1, 0)]);
+}
+
+#[tokio::test]
+async fn test_reserved_match_starts_when_both_players_join() {
+    let manager = GameManager::new(GameConfig::default());
+    let reserved = manager.reserve_match(vec!["p1".to_string(), "p2".to_string()]).await.unwrap();
+    assert!(manager.reserve_match(vec!["p2".to_string(), "p3".to_string()]).await.is_err());
+    assert!(manager.reserve_match(vec!["p4".to_string(), "p4".to_string()]).await.is_err());
+
+    let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, mut rx2) = tokio::sync::mpsc::unbounded_channel();
+    let (tx3, _rx3) = tokio::sync::mpsc::unbounded_channel();
+    let outsider = Arc::new(Player::new("p3".to_string(), tx3));
+    assert!(manager.join_room(outsider, &reserved.room_id).await.unwrap().is_none());
+
+    let first = manager
+        .join_room(Arc::new(Player::new("p1".to_string(), tx1)), &reserved.join_code.to_lowercase())
+        .await
+        .unwrap();
+    assert!(matches!(first, Some(ServerMessage::Matchmaking { matched: false, .. })));
+
+    let second = manager
+        .join_room(Arc::new(Player::new("p2".to_string(), tx2)), &reserved.room_id)
+        .await
+        .unwrap();
+    assert!(matches!(second, Some(ServerMessage::Matchmaking { matched: true, .. })));
+    assert!(matches!(rx2.recv().await, Some(ServerMessage::GameStart { .. })));
+    assert!(manager.submit_move("p1", GameChoice::Rock).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_bot_game_reveals_seed_matching_commitment() {
+    use sha2::{Digest, Sha256};
+
+    let manager = GameManager::new(GameConfig::default());
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let reply = manager.find_match_in_mode(Arc::new(Player::new("human".to_string(), tx)), GameMode::Bot).await;
+    assert!(matches!(reply.unwrap(), ServerMessage::Matchmaking { matched: true, .. }));
+
+    let commitment = match rx.recv().await {
+        Some(ServerMessage::GameStart { fairness_commitment: Some(commitment), .. }) => commitment,
+        other => panic!("unexpected {:?}", other),
+    };
+
+    let mut bot_moves = Vec::new();
+    manager.submit_move("human", GameChoice::Rock).await.unwrap();
+    let seed = loop {
+        match rx.recv().await.unwrap() {
+            ServerMessage::RoundResult { moves, .. } => {
+                bot_moves.extend(moves.into_iter().filter(|(id, _)| id != "human").map(|(_, choice)| choice));
+            }
+            ServerMessage::NextRound { .. } => {
+                manager.submit_move("human", GameChoice::Rock).await.unwrap();
+            }
+            ServerMessage::GameEnd { fairness_seed, .. } => break fairness_seed.unwrap(),
+            other => panic!("unexpected {:?}", other),
+        }
+    };
+    assert!(!bot_moves.is_empty());
+
+    let seed = hex::decode(seed).unwrap();
+    assert_eq!(hex::encode(Sha256::digest(&seed)), commitment);
+    for (index, choice) in bot_moves.iter().enumerate() {
+        assert_eq!(&bot_move(&seed, index as u32, &RuleSet::classic()), choice);
+    }
+}
+
+#[tokio::test]
+async fn test_game_end_lists_every_round() {
+    let config = GameConfig { draw_policy: DrawPolicy::Replay, ..GameConfig::default() };
+    let mut room = GameRoom::new("summary-room".to_string(), config);
+    let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    room.add_player(Arc::new(Player::new("p1".to_string(), tx1))).unwrap();
+    room.add_player(Arc::new(Player::new("p2".to_string(), tx2))).unwrap();
+
+    for (first, second) in [
+        (GameChoice::Rock, GameChoice::Rock),
+        (GameChoice::Paper, GameChoice::Rock),
+        (GameChoice::Scissors, GameChoice::Paper),
+    ] {
+        room.submit_move("p1", first).unwrap();
+        room.submit_move("p2", second).unwrap();
+        room.process_round().await.unwrap();
+    }
+
+    let rounds = loop {
+        match rx1.try_recv().unwrap() {
+            ServerMessage::GameEnd { rounds, .. } => break rounds,
+            _ => continue,
+        }
+    };
+    let outcomes: Vec<_> = rounds.iter().map(|r| (r.round, r.winner.as_deref(), r.replayed)).collect();
+    assert_eq!(outcomes, [(1, None, true), (1, Some("p1"), false), (2, Some("p1"), false)]);
+    assert_eq!(rounds[2].moves["p2"], GameChoice::Paper);
+}
+
+#[tokio::test]
+async fn test_finished_games_are_looked_up_by_game_id() {
+    let manager = Arc::new(GameManager::new(GameConfig::default()).with_game_history(GameHistoryConfig { max_finished_games: 1, ..GameHistoryConfig::default() }));
+    let play_game = |manager: Arc<GameManager>| async move {
+        let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+        let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+        manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+        manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+        let game_id = loop {
+            if let ServerMessage::GameStart { game_id, .. } = rx1.try_recv().unwrap() {
+                break game_id;
+            }
+        };
+        for _ in 0..2 {
+            manager.submit_move("p1", GameChoice::Rock).await.unwrap();
+            manager.submit_move("p2", GameChoice::Scissors).await.unwrap();
+        }
+        let ended = std::iter::from_fn(|| rx1.try_recv().ok()).find_map(|message| match message {
+            ServerMessage::GameEnd { game_id, .. } => Some(game_id),
+            _ => None,
+        });
+        assert_eq!(ended.as_ref(), Some(&game_id));
+        manager.remove_player("p1").await.unwrap();
+        manager.remove_player("p2").await.unwrap();
+        game_id
+    };
+
+    let first = play_game(manager.clone()).await;
+    let routes = create_game_routes(manager.clone());
+    let response = warp::test::request().path(&format!("/games/{}", first)).reply(&routes).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["status"], "finished");
+    assert_eq!(body["winner"], "p1");
+    assert_eq!(body["reason"], "completed");
+    assert_eq!(body["players"], serde_json::json!(["p1", "p2"]));
+    assert_eq!(body["replay"], format!("/games/{}/replay", first));
+
+    let replay = warp::test::request().path(&format!("/games/{}/replay", first)).reply(&routes).await;
+    let body: serde_json::Value = serde_json::from_slice(replay.body()).unwrap();
+    assert_eq!(body["rounds"].as_array().unwrap().len(), 2);
+
+    // A second game pushes the first out of the one-entry history
+    let second = play_game(manager.clone()).await;
+    assert_ne!(first, second);
+    assert!(manager.game_record(&second).await.is_some());
+    let evicted = warp::test::request().path(&format!("/games/{}", first)).reply(&routes).await;
+    assert_eq!(evicted.status(), 404);
+}
+
+#[tokio::test]
+async fn test_move_response_times_are_latency_compensated() {
+    use crate::domain::LatencyEstimate;
+
+    let estimate = LatencyEstimate::new();
+    assert!(estimate.rtt().is_none());
+    let payload = estimate.ping_sent();
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    estimate.pong_received(Some(&payload));
+    assert!(estimate.rtt().unwrap() >= std::time::Duration::from_millis(20));
+
+    // Max compensation defaults to 250ms, so the far player is only compensated that much
+    let mut room = GameRoom::new("room".to_string(), GameConfig::default());
+    let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    let near = Player::new("near".to_string(), tx1);
+    near.latency.observe(std::time::Duration::from_millis(10));
+    let far = Player::new("far".to_string(), tx2);
+    far.latency.observe(std::time::Duration::from_secs(2));
+    room.add_player(Arc::new(near)).unwrap();
+    room.add_player(Arc::new(far)).unwrap();
+
+    tokio::time::sleep(std::time::Duration::from_millis(400)).await;
+    room.submit_move("near", GameChoice::Rock).unwrap();
+    room.submit_move("far", GameChoice::Paper).unwrap();
+    room.process_round().await.unwrap();
+
+    let round = &room.record().rounds[0];
+    let (near, far) = (round.response_ms["near"], round.response_ms["far"]);
+    assert!(near >= 390, "{}", near);
+    assert!(far >= 150 && far < near && far + 240 >= near, "{} vs {}", far, near);
+
+    // Decision times are left as the server measured them
+    assert!(round.decision_ms["near"] >= 400 && round.decision_ms["far"] >= 400, "{:?}", round.decision_ms);
+    assert!(round.decision_ms["far"] >= far + 250, "{} vs {}", round.decision_ms["far"], far);
+}
+
+#[tokio::test]
+async fn test_round_timer_follows_an_injected_clock() {
+    use crate::application::{Clock, MockClock};
+    use crate::config::BlitzConfig;
+
+    let clock = Arc::new(MockClock::new());
+    let deadline = clock.now() + std::time::Duration::from_secs(1);
+    let sleeper = {
+        let clock = clock.clone();
+        tokio::spawn(async move { clock.sleep_until(deadline).await })
+    };
+    clock.advance(std::time::Duration::from_millis(999));
+    tokio::task::yield_now().await;
+    assert!(!sleeper.is_finished());
+    clock.advance(std::time::Duration::from_millis(1));
+    tokio::time::timeout(std::time::Duration::from_secs(1), sleeper).await.unwrap().unwrap();
+
+    // A 10-minute move window closes as soon as the clock says so, with nobody waiting on it
+    let manager = GameManager::new(GameConfig::default())
+        .with_blitz(BlitzConfig { rounds: 3, round_window_ms: 600_000 })
+        .with_clock(clock.clone());
+    let (tx_a, mut rx_a) = tokio::sync::mpsc::unbounded_channel();
+    let (tx_b, _rx_b) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match_in_mode(Arc::new(Player::new("a".to_string(), tx_a)), GameMode::Blitz).await.unwrap();
+    manager.find_match_in_mode(Arc::new(Player::new("b".to_string(), tx_b)), GameMode::Blitz).await.unwrap();
+    manager.submit_move("a", GameChoice::Rock).await.unwrap();
+    tokio::task::yield_now().await;
+    assert!(std::iter::from_fn(|| rx_a.try_recv().ok()).all(|message| !matches!(message, ServerMessage::BlitzRound { .. })));
+
+    clock.advance(std::time::Duration::from_secs(600));
+    let round = tokio::time::timeout(std::time::Duration::from_secs(1), async {
+        loop {
+            if let ServerMessage::BlitzRound { round, next_round, .. } = rx_a.recv().await.unwrap() {
+                return (round, next_round);
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(round, (1, Some(2)));
+}
+
+#[tokio::test]
+async fn test_blitz_rounds_close_on_the_server_timer() {
+    use crate::config::BlitzConfig;
+
+    let manager = GameManager::new(GameConfig::default()).with_blitz(BlitzConfig { rounds: 3, round_window_ms: 150 });
+    let (tx_a, mut rx_a) = tokio::sync::mpsc::unbounded_channel();
+    let (tx_b, _rx_b) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match_in_mode(Arc::new(Player::new("a".to_string(), tx_a)), GameMode::Blitz).await.unwrap();
+    let matched = manager.find_match_in_mode(Arc::new(Player::new("b".to_string(), tx_b)), GameMode::Blitz).await.unwrap();
+    assert!(matches!(matched, ServerMessage::Matchmaking { matched: true, .. }));
+
+    async fn next(rx: &mut tokio::sync::mpsc::UnboundedReceiver<ServerMessage>) -> ServerMessage {
+        tokio::time::timeout(std::time::Duration::from_secs(2), rx.recv()).await.unwrap().unwrap()
+    }
+    let seats = match next(&mut rx_a).await {
+        ServerMessage::GameStart { players, max_rounds: 3, round_window_ms: Some(150), .. } => players,
+        other => panic!("unexpected {:?}", other),
+    };
+    let seat = |id: &str| seats.iter().position(|p| p.id == id);
+
+    // Both move: resolved at once
+    manager.submit_move("a", GameChoice::Rock).await.unwrap();
+    manager.submit_move("b", GameChoice::Scissors).await.unwrap();
+    assert!(matches!(next(&mut rx_a).await, ServerMessage::OpponentMoved { player_id, .. } if player_id == "a"));
+    match next(&mut rx_a).await {
+        ServerMessage::BlitzRound { round: 1, winner, next_round: Some(2), .. } => assert_eq!(winner, seat("a")),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    // Nobody moves: the window closes on a draw
+    match next(&mut rx_a).await {
+        ServerMessage::BlitzRound { round: 2, winner: None, moves, next_round: Some(3), .. } => assert_eq!(moves, vec![None, None]),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    // Only b moves: a loses the round and the game ends level
+    manager.submit_move("b", GameChoice::Paper).await.unwrap();
+    assert!(matches!(next(&mut rx_a).await, ServerMessage::OpponentMoved { player_id, .. } if player_id == "b"));
+    match next(&mut rx_a).await {
+        ServerMessage::BlitzRound { round: 3, winner, scores, next_round: None, .. } => {
+            assert_eq!(winner, seat("b"));
+            assert_eq!(scores, vec![1, 1]);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match next(&mut rx_a).await {
+        ServerMessage::GameEnd { winner: None, rounds, .. } => assert_eq!(rounds.len(), 3),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_a_round_the_timer_resolved_first_is_not_resolved_again() {
+    use crate::application::MockClock;
+
+    let clock = Arc::new(MockClock::new());
+    let config = GameConfig { mode: GameMode::Blitz, round_window_ms: Some(1000), ..GameConfig::default() };
+    let mut room = GameRoom::new("blitz".to_string(), config).with_clock(clock.clone());
+    let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    room.add_player(Arc::new(Player::new("p1".to_string(), tx1))).unwrap();
+    room.add_player(Arc::new(Player::new("p2".to_string(), tx2))).unwrap();
+    room.submit_move("p1", GameChoice::Rock).unwrap();
+    assert!(room.submit_move("p2", GameChoice::Scissors).unwrap());
+
+    // The window closes and the round timer resolves the round before the last mover does
+    clock.advance(std::time::Duration::from_secs(1));
+    assert!(room.expire_round().await.unwrap());
+    room.process_round().await.unwrap();
+
+    assert_eq!(room.current_round, 2);
+    assert_eq!((room.scores["p1"], room.scores["p2"]), (1, 0));
+    let mut rounds = Vec::new();
+    while let Ok(Some(message)) = tokio::time::timeout(std::time::Duration::from_millis(100), rx1.recv()).await {
+        if let ServerMessage::BlitzRound { round, .. } = message {
+            rounds.push(round);
+        }
+    }
+    assert_eq!(rounds, vec![1]);
+}
+
+#[tokio::test]
+async fn test_series_carries_score_between_games() {
+    use crate::application::{SeriesManager, SeriesStatus};
+    use crate::config::SeriesConfig;
+
+    let config = GameConfig { max_rounds: 1, ..GameConfig::default() };
+    let manager = Arc::new(GameManager::new(config));
+    let mut events = manager.events().subscribe();
+    let series = Arc::new(SeriesManager::new(manager.clone(), SeriesConfig { next_game_delay_ms: 10, ..SeriesConfig::default() }));
+    series.clone().spawn();
+    assert!(series.create(vec!["p1".to_string(), "p2".to_string()], 4).await.is_err());
+    let created = series.create(vec!["p1".to_string(), "p2".to_string()], 3).await.unwrap();
+
+    let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.join_room(Arc::new(Player::new("p1".to_string(), tx1)), &created.first_match.join_code).await.unwrap();
+    manager.join_room(Arc::new(Player::new("p2".to_string(), tx2)), &created.first_match.room_id).await.unwrap();
+
+    async fn next(rx: &mut tokio::sync::mpsc::UnboundedReceiver<ServerMessage>) -> ServerMessage {
+        tokio::time::timeout(std::time::Duration::from_secs(2), rx.recv()).await.unwrap().unwrap()
+    }
+    async fn play_game(manager: &GameManager, rx: &mut tokio::sync::mpsc::UnboundedReceiver<ServerMessage>) {
+        while !matches!(next(rx).await, ServerMessage::GameStart { .. }) {}
+        manager.submit_move("p1", GameChoice::Rock).await.unwrap();
+        manager.submit_move("p2", GameChoice::Scissors).await.unwrap();
+    }
+
+    play_game(&manager, &mut rx1).await;
+    let update = loop {
+        if let ServerMessage::SeriesUpdate { next_game, scores, .. } = next(&mut rx1).await {
+            break (next_game, scores["p1"]);
+        }
+    };
+    assert_eq!(update, (2, 1));
+
+    // The second game starts by itself and decides the series
+    play_game(&manager, &mut rx1).await;
+    let (winner, scores, games) = loop {
+        if let ServerMessage::SeriesEnd { winner, scores, games, reason: GameEndReason::Completed, .. } = next(&mut rx1).await {
+            break (winner, scores, games);
+        }
+    };
+    assert_eq!(winner.as_deref(), Some("p1"));
+    assert_eq!((scores["p1"], scores["p2"]), (2, 0));
+    assert_eq!(games.len(), 2);
+
+    let finished = series.get(&created.id).unwrap();
+    assert_eq!(finished.status, SeriesStatus::Finished);
+    assert_eq!(finished.games, games);
+    let counters = manager.live_stats();
+    assert_eq!((counters.series_started, counters.series_finished), (1, 1));
+    loop {
+        if let GameEvent::SeriesEnded { series_id, .. } = events.recv().await.unwrap().event {
+            assert_eq!(series_id, created.id);
+            break;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_equipped_title_is_shown_in_game_start() {
+    use crate::domain::TitleSource;
+    use crate::infrastructure::{create_title_routes, TitleStore};
+
+    let path = std::env::temp_dir().join(format!("rps-titles-{}.jsonl", uuid::Uuid::new_v4()));
+    let titles = Arc::new(TitleStore::open(&path).unwrap());
+    let secrets = Arc::new(SecretStore::from_keys(vec![Secret::new("ops", "admin-key")], vec![]));
+    let routes = create_title_routes(titles.clone(), Arc::new(AuditLog::in_memory()), secrets);
+
+    let grant = warp::test::request()
+        .method("POST")
+        .path("/admin/players/p1/titles")
+        .header("x-api-key", "admin-key")
+        .json(&serde_json::json!({ "id": "spring-cup", "name": "Spring Champion", "source": "tournament" }))
+        .reply(&routes)
+        .await;
+    assert_eq!(grant.status(), 201);
+    assert_eq!(titles.grant("p1", "spring-cup", "Spring Champion", TitleSource::Tournament).unwrap(), None);
+
+    let equip = |title_id: &str, key: &str| {
+        warp::test::request()
+            .method("PUT")
+            .path("/players/p1/titles/equipped")
+            .header("x-api-key", key)
+            .json(&serde_json::json!({ "titleId": title_id }))
+    };
+    assert!(!equip("spring-cup", "wrong-key").reply(&routes).await.status().is_success());
+    assert_eq!(equip("unearned", "admin-key").reply(&routes).await.status(), 404);
+    assert_eq!(equip("spring-cup", "admin-key").reply(&routes).await.status(), 200);
+
+    let listed = warp::test::request().path("/players/p1/titles").reply(&routes).await;
+    let body: serde_json::Value = serde_json::from_slice(listed.body()).unwrap();
+    assert_eq!(body["equipped"], "spring-cup");
+    assert_eq!(body["titles"][0]["source"], "tournament");
+
+    // Survives a restart, and rooms pick it up
+    let reopened = Arc::new(TitleStore::open(&path).unwrap());
+    assert_eq!(reopened.titles("p1"), titles.titles("p1"));
+    let manager = GameManager::new(GameConfig::default()).with_titles(reopened);
+    let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+    manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+    let Some(ServerMessage::GameStart { players, .. }) = rx1.recv().await else {
+        panic!("expected GameStart");
+    };
+    let shown: Vec<_> = players.iter().map(|p| (p.id.as_str(), p.title.as_deref())).collect();
+    assert!(shown.contains(&("p1", Some("Spring Champion"))) && shown.contains(&("p2", None)));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_other_games_use_their_own_queue_and_rules() {
+    use crate::domain::GameType;
+
+    let manager = GameManager::new(GameConfig::default());
+    let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    let (tx3, _rx3) = tokio::sync::mpsc::unbounded_channel();
+    let player = |id: &str, tx| Arc::new(Player::new(id.to_string(), tx));
+
+    // A classic player doesn't pair with someone waiting for odds and evens
+    let waiting = manager.find_match_for(player("odd", tx1), GameMode::Solo, GameType::OddsAndEvens).await.unwrap();
+    assert!(matches!(waiting, ServerMessage::Matchmaking { matched: false, .. }));
+    let classic = manager.find_match(player("classic", tx3)).await.unwrap();
+    assert!(matches!(classic, ServerMessage::Matchmaking { matched: false, .. }));
+    let matched = manager.find_match_for(player("even", tx2), GameMode::Solo, GameType::OddsAndEvens).await.unwrap();
+    assert!(matches!(matched, ServerMessage::Matchmaking { matched: true, .. }));
+    assert!(std::iter::from_fn(|| rx1.try_recv().ok())
+        .any(|message| matches!(message, ServerMessage::GameStart { game: GameType::OddsAndEvens, .. })));
+
+    // Rock isn't a finger count; 2 + 3 is odd, so the first seat takes the round
+    let refused = manager.submit_move("odd", GameChoice::Rock).await.unwrap_err();
+    assert_eq!(refused.downcast_ref::<MoveRefused>(), Some(&MoveRefused::InvalidChoice));
+    manager.submit_move("even", GameChoice::from("3".to_string())).await.unwrap();
+    manager.submit_move("odd", GameChoice::from("2".to_string())).await.unwrap();
+    let round = std::iter::from_fn(|| rx1.try_recv().ok()).find_map(|message| match message {
+        ServerMessage::RoundResult { winner, .. } => Some(winner),
+        _ => None,
+    });
+    assert_eq!(round, Some(Some("odd".to_string())));
+
+    assert!(manager.find_match_for(player("team", tokio::sync::mpsc::unbounded_channel().0), GameMode::Teams, GameType::Morra).await.is_err());
+
+    // Morra: only a lone right guess of the total wins
+    let morra = GameType::Morra.build(&Default::default());
+    let choice = |text: &str| GameChoice::from(text.to_string());
+    assert!(!morra.validate_move(&choice("6:7")) && !morra.validate_move(&choice("3")));
+    assert_eq!(morra.result([&choice("2:5"), &choice("3:6")]), Some(0));
+    assert_eq!(morra.result([&choice("2:5"), &choice("3:5")]), None);
+    assert_eq!(morra.result([&choice("1:5"), &choice("3:6")]), None);
+}
+
+#[tokio::test]
+async fn test_room_templates_resolve_rounds_with_a_sandboxed_script() {
+    use crate::config::ScriptedRulesConfig;
+    use crate::infrastructure::{RoomTemplateStore, ScriptedRules};
+
+    // Higher card wins; "loop" spins until the sandbox stops it
+    let high_card = r#"
+        fn validate(choice) { choice == "loop" || (parse_int(choice) >= 1 && parse_int(choice) <= 13) }
+        fn resolve(first, second) {
+            if first == "loop" { loop {} }
+            let a = parse_int(first);
+            let b = parse_int(second);
+            if a > b { 0 } else if b > a { 1 } else { -1 }
+        }
+    "#;
+    let config = ScriptedRulesConfig::default();
+    assert!(ScriptedRules::from_sources(&config, [("broken".to_string(), "fn validate(c) { true }".to_string())]).is_err());
+    let scripts = ScriptedRules::from_sources(&config, [("highCard".to_string(), high_card.to_string())]).unwrap();
+    let templates = RoomTemplateStore::in_memory();
+    templates.save(serde_json::from_value(serde_json::json!({ "name": "cards", "script": "highCard" })).unwrap()).unwrap();
+    templates.save(serde_json::from_value(serde_json::json!({ "name": "typo", "script": "highCrad" })).unwrap()).unwrap();
+
+    let manager = GameManager::new(GameConfig::default())
+        .with_rule_scripts(Arc::new(scripts))
+        .with_room_templates(Arc::new(templates));
+    let players = vec!["p1".to_string(), "p2".to_string()];
+    assert!(manager.reserve_match_from(players.clone(), Some("missing")).await.is_err());
+    assert!(manager.reserve_match_from(players.clone(), Some("typo")).await.is_err());
+    let reserved = manager.reserve_match_from(players, Some("cards")).await.unwrap();
+
+    let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.join_room(Arc::new(Player::new("p1".to_string(), tx1)), &reserved.room_id).await.unwrap();
+    manager.join_room(Arc::new(Player::new("p2".to_string(), tx2)), &reserved.join_code).await.unwrap();
+    let mut rounds = Vec::new();
+    let mut next = || {
+        std::iter::from_fn(|| rx1.try_recv().ok()).find_map(|message| match message {
+            ServerMessage::GameStart { template, .. } => {
+                assert_eq!(template.as_deref(), Some("cards"));
+                None
+            }
+            ServerMessage::RoundResult { winner, .. } => Some(winner),
+            _ => None,
+        })
+    };
+
+    // "rock" is no card, so p1's 5 is their first valid move
+    let card = |text: &str| GameChoice::from(text.to_string());
+    let refused = manager.submit_move("p1", GameChoice::Rock).await.unwrap_err();
+    assert_eq!(refused.downcast_ref::<MoveRefused>(), Some(&MoveRefused::InvalidChoice));
+    manager.submit_move("p2", card("9")).await.unwrap();
+    manager.submit_move("p1", card("5")).await.unwrap();
+    rounds.push(next());
+
+    // A runaway script is cut off and the round drawn
+    manager.submit_move("p1", card("loop")).await.unwrap();
+    manager.submit_move("p2", card("2")).await.unwrap();
+    rounds.push(next());
+    assert_eq!(rounds, [Some(Some("p2".to_string())), Some(None)]);
+}
+
+#[tokio::test]
+async fn test_room_templates_are_edited_at_runtime_and_matched_on() {
+    use crate::infrastructure::{create_room_template_routes, RoomTemplateStore};
+
+    // Pairs `first` and `second` on the "quick" template; returns what p1's GameStart said
+    async fn play_quick(manager: &GameManager, first: &str, second: &str) -> Option<(u32, bool, Option<String>)> {
+        let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+        let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+        manager.find_match_on_template(Arc::new(Player::new(first.to_string(), tx1)), "quick").await.unwrap();
+        manager.find_match_on_template(Arc::new(Player::new(second.to_string(), tx2)), "quick").await.unwrap();
+        std::iter::from_fn(|| rx1.try_recv().ok()).find_map(|message| match message {
+            ServerMessage::GameStart { max_rounds, chat_enabled, template, .. } => Some((max_rounds, chat_enabled, template)),
+            _ => None,
+        })
+    }
+
+    let path = std::env::temp_dir().join(format!("rps-templates-{}.jsonl", uuid::Uuid::new_v4()));
+    let templates = Arc::new(RoomTemplateStore::open(&path).unwrap());
+    let secrets = Arc::new(SecretStore::from_keys(vec![Secret::new("ops", "admin-key")], vec![]));
+    let routes = create_room_template_routes(templates.clone(), Arc::new(AuditLog::in_memory()), secrets);
+    let put = |key: &str, body: serde_json::Value| {
+        warp::test::request().method("PUT").path("/admin/templates/quick").header("x-api-key", key).json(&body)
+    };
+
+    assert!(!put("wrong-key", serde_json::json!({})).reply(&routes).await.status().is_success());
+    assert_eq!(put("admin-key", serde_json::json!({ "maxRounds": 1, "chatEnabled": false })).reply(&routes).await.status(), 201);
+    assert_eq!(put("admin-key", serde_json::json!({ "mode": "teams", "game": "morra" })).reply(&routes).await.status(), 400);
+
+    // Only players asking for the template are paired on it
+    let manager = GameManager::new(GameConfig::default()).with_room_templates(templates.clone());
+    let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("plain".to_string(), tx))).await.unwrap();
+    assert_eq!(play_quick(&manager, "p1", "p2").await, Some((1, false, Some("quick".to_string()))));
+    assert_eq!(manager.player_phase("plain").await, PlayerPhase::Queued);
+
+    // An edit applies to the next room without a restart, and survives one
+    assert_eq!(put("admin-key", serde_json::json!({ "maxRounds": 5 })).reply(&routes).await.status(), 200);
+    assert_eq!(play_quick(&manager, "p3", "p4").await, Some((5, true, Some("quick".to_string()))));
+    assert_eq!(RoomTemplateStore::open(&path).unwrap().get("quick").unwrap().max_rounds, Some(5));
+
+    let listed = warp::test::request().path("/admin/templates").header("x-api-key", "admin-key").reply(&routes).await;
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(listed.body()).unwrap()[0]["name"], "quick");
+    let delete = warp::test::request().method("DELETE").path("/admin/templates/quick").header("x-api-key", "admin-key");
+    assert_eq!(delete.reply(&routes).await.status(), 200);
+    let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+    assert!(manager.find_match_on_template(Arc::new(Player::new("p5".to_string(), tx)), "quick").await.is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_crowded_room_broadcasts_through_its_fan_out_worker() {
+    let next_round = |round| ServerMessage::NextRound { seq: None, round };
+    let mut room = GameRoom::new("r1".to_string(), GameConfig::default());
+    let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+    room.add_player(Arc::new(Player::new("p1".to_string(), tx1))).unwrap();
+    let mut audience = Vec::new();
+    for i in 0..50 {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        room.spectators.push(Arc::new(Player::new(format!("s{}", i), tx)));
+        audience.push(rx);
+    }
+
+    // Handed to the worker: the room is free again before anyone has the message
+    room.broadcast_to_all(&next_round(1)).await.unwrap();
+    assert!(rx1.try_recv().is_err());
+    // Small enough to send inline, but it must not overtake round 1
+    room.spectators.clear();
+    room.broadcast_to_all(&next_round(2)).await.unwrap();
+    assert!(rx1.try_recv().is_err());
+
+    for expected in [1, 2] {
+        assert!(matches!(rx1.recv().await, Some(ServerMessage::NextRound { round, .. }) if round == expected));
+    }
+    for rx in &mut audience {
+        assert!(matches!(rx.recv().await, Some(ServerMessage::NextRound { round: 1, .. })));
+        assert!(rx.try_recv().is_err());
+    }
+
+    // With the worker drained, small rooms are served inline again
+    room.broadcast_to_all(&next_round(3)).await.unwrap();
+    assert!(matches!(rx1.try_recv(), Ok(ServerMessage::NextRound { round: 3, .. })));
+}
+
+#[tokio::test]
+async fn test_room_broadcasts_carry_a_sequence_number_that_survives_migration() {
+    let mut room = GameRoom::new("r1".to_string(), GameConfig::default());
+    let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+    let p1 = Arc::new(Player::new("p1".to_string(), tx1));
+    room.add_player(p1.clone()).unwrap();
+
+    for round in 1..=2 {
+        room.broadcast_to_all(&ServerMessage::NextRound { seq: None, round }).await.unwrap();
+    }
+    assert_eq!(rx1.recv().await.unwrap().seq(), Some(1));
+    assert_eq!(rx1.recv().await.unwrap().seq(), Some(2));
+    // Direct replies aren't part of the room's stream
+    room.send_to(&p1, ServerMessage::NextRound { seq: None, round: 3 });
+    assert_eq!(rx1.recv().await.unwrap().seq(), None);
+
+    let state = room.state();
+    assert_eq!(state.seq, 2);
+    let mut moved = GameRoom::new("r1".to_string(), GameConfig::default());
+    let connections = std::collections::HashMap::from([("p1".to_string(), p1.clone())]);
+    moved.restore(state, &connections).unwrap();
+    moved.broadcast_to_all(&ServerMessage::PlayerLeft { seq: None, player_id: "p2".to_string() }).await.unwrap();
+    assert_eq!(rx1.recv().await.unwrap().seq(), Some(3));
+}
+
+#[tokio::test]
+async fn test_invite_links_seat_the_guest_in_the_hosts_room() {
+    use crate::config::InvitesConfig;
+    use crate::infrastructure::{create_invite_routes, InviteRejected, Invites};
+
+    async fn messages(sessions: &LongPollSessions, session: &str) -> Vec<serde_json::Value> {
+        let response = sessions.recv(session, 0).await.unwrap();
+        response.messages.iter().map(|message| serde_json::to_value(message).unwrap()).collect()
+    }
+
+    let manager = Arc::new(GameManager::new(GameConfig::default()));
+    let config = InvitesConfig {
+        public_url: Some("https://rps.example.com/".to_string()),
+        websocket_url: Some("wss://rps.example.com/ws".to_string()),
+        ..InvitesConfig::default()
+    };
+    let invites = Arc::new(Invites::with_keys(config, vec![Secret::new("k1", "invite-secret")]));
+    let handler = WebSocketHandler::new(manager.clone(), ServerConfig::default().websocket).with_invites(invites.clone());
+    let poll_config = LongPollConfig {
+        poll_timeout_ms: 50,
+        session_idle_timeout_ms: 0,
+        ..LongPollConfig::default()
+    };
+    let sessions = LongPollSessions::new(handler, poll_config);
+    let routes = create_invite_routes(invites.clone(), manager.clone());
+
+    let host = sessions.open(None);
+    sessions.send(&host, r#"{"type":"connect","playerId":"host"}"#).await;
+    sessions.send(&host, r#"{"type":"createInvite"}"#).await;
+    let invite = messages(&sessions, &host).await.into_iter().find(|message| message["type"] == "invite").unwrap();
+    let token = invite["token"].as_str().unwrap().to_string();
+    assert_eq!(invite["url"], format!("https://rps.example.com/join/{}", token));
+    assert_eq!(manager.player_phase("host").await, PlayerPhase::Queued);
+
+    // The link answers with how to connect; a tampered one is unknown
+    let opened = warp::test::request().path(&format!("/join/{}", token)).reply(&routes).await;
+    assert_eq!(opened.status(), 200);
+    let instructions: serde_json::Value = serde_json::from_slice(opened.body()).unwrap();
+    assert_eq!(instructions["openSeats"], 1);
+    assert_eq!(instructions["websocketUrl"], "wss://rps.example.com/ws");
+    assert_eq!(instructions["connect"], serde_json::json!({ "type": "connect", "playerId": null, "locale": null, "clientVersion": null, "protocolVersion": null, "invite": token }));
+    let tampered = format!("{}0", token);
+    assert_eq!(warp::test::request().path(&format!("/join/{}", tampered)).reply(&routes).await.status(), 404);
+    let (payload, _) = token.rsplit_once('.').unwrap();
+    assert_eq!(invites.verify(&token, i64::MAX), Err(InviteRejected::Expired));
+    assert_eq!(invites.verify(&format!("{}.00", payload), 0), Err(InviteRejected::Invalid));
+
+    // Connecting with the token starts the game straight away
+    let guest = sessions.open(None);
+    sessions.send(&guest, &format!(r#"{{"type":"connect","playerId":"guest","invite":"{}"}}"#, token)).await;
+    let received = messages(&sessions, &guest).await;
+    assert_eq!(received[0]["type"], "connected");
+    assert!(received.iter().any(|message| message["type"] == "gameStart"), "{:?}", received);
+    assert_eq!(manager.player_phase("guest").await, PlayerPhase::InGame);
+
+    // Nobody else gets in on the same link
+    assert_eq!(warp::test::request().path(&format!("/join/{}", token)).reply(&routes).await.status(), 404);
+    let late = sessions.open(None);
+    sessions.send(&late, &format!(r#"{{"type":"connect","playerId":"late","invite":"{}"}}"#, token)).await;
+    let received = messages(&sessions, &late).await;
+    assert_eq!(received[1]["code"], "invite_invalid");
+    assert_eq!(manager.player_phase("late").await, PlayerPhase::Idle);
+}
+
+#[tokio::test]
+async fn test_invites_play_the_rules_they_were_created_with() {
+    use crate::config::InvitesConfig;
+    use crate::infrastructure::Invites;
+
+    async fn messages(sessions: &LongPollSessions, session: &str) -> Vec<serde_json::Value> {
+        let response = sessions.recv(session, 0).await.unwrap();
+        response.messages.iter().map(|message| serde_json::to_value(message).unwrap()).collect()
+    }
+
+    let manager = Arc::new(GameManager::new(GameConfig::default()));
+    let invites = Arc::new(Invites::with_keys(InvitesConfig::default(), vec![Secret::new("k1", "invite-secret")]));
+    let handler = WebSocketHandler::new(manager.clone(), ServerConfig::default().websocket).with_invites(invites);
+    let poll_config = LongPollConfig {
+        poll_timeout_ms: 50,
+        session_idle_timeout_ms: 0,
+        ..LongPollConfig::default()
+    };
+    let sessions = LongPollSessions::new(handler, poll_config);
+
+    // Rock-paper-scissors-Spock-lizard: i beats j when (i - j) mod 5 is odd
+    let matrix: Vec<Vec<i8>> = (0..5)
+        .map(|i| (0..5).map(|j| match (i + 5 - j) % 5 { 0 => 0, 1 | 3 => 1, _ => -1 }).collect())
+        .collect();
+    let rules = RuleSet {
+        choices: ["rock", "paper", "scissors", "spock", "lizard"].map(|name| GameChoice::from(name.to_string())).to_vec(),
+        matrix,
+    };
+    let mut broken = rules.clone();
+    broken.matrix[1][0] = i8::MIN;
+
+    let host = sessions.open(None);
+    sessions.send(&host, r#"{"type":"connect","playerId":"host"}"#).await;
+    let create = |rules: &RuleSet| serde_json::json!({ "type": "createInvite", "rules": rules }).to_string();
+    sessions.send(&host, &create(&broken)).await;
+    let refused = messages(&sessions, &host).await.into_iter().find(|message| message["type"] == "error").unwrap();
+    assert_eq!(refused["code"], "invalid_rules");
+    assert_eq!(manager.player_phase("host").await, PlayerPhase::Idle);
+
+    sessions.send(&host, &create(&rules)).await;
+    let invite = messages(&sessions, &host).await.into_iter().find(|message| message["type"] == "invite").unwrap();
+    let token = invite["token"].as_str().unwrap().to_string();
+    let guest = sessions.open(None);
+    sessions.send(&guest, &format!(r#"{{"type":"connect","playerId":"guest","invite":"{}"}}"#, token)).await;
+    assert_eq!(manager.player_phase("guest").await, PlayerPhase::InGame);
+
+    // Spock isn't a classic choice, but this room's rules have it beating rock
+    manager.submit_move("host", GameChoice::from("spock".to_string())).await.unwrap();
+    manager.submit_move("guest", GameChoice::Rock).await.unwrap();
+    let received = messages(&sessions, &guest).await;
+    let round = received.iter().find(|message| message["type"] == "roundResult").unwrap();
+    assert_eq!(round["winner"], "host");
+}
+
+#[tokio::test]
+async fn test_tournament_stream_follows_the_bracket_live() {
+    use crate::infrastructure::{create_tournament_routes, TournamentFeed};
+    use warp::hyper::body::HttpBody;
+    use warp::hyper::service::Service;
+
+    async fn next_event(body: &mut warp::hyper::Body) -> (String, serde_json::Value) {
+        let mut text = String::new();
+        while !text.ends_with("\n\n") {
+            let chunk = body.data().await.unwrap().unwrap();
+            text.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        let field = |name: &str| text.lines().find_map(|line| line.strip_prefix(name)).unwrap().to_string();
+        (field("event:"), serde_json::from_str(&field("data:")).unwrap())
+    }
+
+    let manager = Arc::new(GameManager::new(GameConfig::default()));
+    let feed = Arc::new(TournamentFeed::new());
+    feed.clone().spawn(manager.events());
+    let routes = create_tournament_routes(feed.clone());
+    assert_eq!(warp::test::request().path("/tournaments/spring-cup/stream").reply(&routes).await.status(), 404);
+
+    // Only matches reserved for the tournament make its bracket
+    manager.reserve_match(vec!["x".to_string(), "y".to_string()]).await.unwrap();
+    let reserved = manager.reserve_tournament_match("spring-cup", vec!["p1".to_string(), "p2".to_string()], None).await.unwrap();
+    assert_eq!(reserved.tournament.as_deref(), Some("spring-cup"));
+    while feed.bracket("spring-cup").is_none() {
+        tokio::task::yield_now().await;
+    }
+
+    let request = warp::hyper::Request::get("/tournaments/spring-cup/stream").body(warp::hyper::Body::empty()).unwrap();
+    let response = warp::service(routes).call(request).await.unwrap();
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut body = response.into_body();
+    let (event, bracket) = next_event(&mut body).await;
+    assert_eq!(event, "bracket");
+    assert_eq!(bracket["matches"].as_array().unwrap().len(), 1);
+    assert_eq!(bracket["matches"][0]["roomId"], reserved.room_id.as_str());
+    assert_eq!(bracket["matches"][0]["status"], "reserved");
+
+    // The game starting and ending reach the overlay without it asking
+    let mut receivers = Vec::new();
+    for id in ["p1", "p2"] {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        receivers.push(rx);
+        manager.join_room(Arc::new(Player::new(id.to_string(), tx)), &reserved.join_code).await.unwrap();
+    }
+    let (event, update) = next_event(&mut body).await;
+    assert_eq!(event, "matchUpdated");
+    assert_eq!(update["match"]["status"], "playing");
+    assert_eq!(update["match"]["round"], 1);
+
+    let finished = loop {
+        manager.submit_move("p1", GameChoice::Rock).await.unwrap();
+        manager.submit_move("p2", GameChoice::Scissors).await.unwrap();
+        let (_, update) = next_event(&mut body).await;
+        if update["match"]["status"] == "finished" {
+            break update;
+        }
+    };
+    assert_eq!(finished["match"]["winner"], "p1");
+    assert_eq!(finished["match"]["reason"], serde_json::to_value(GameEndReason::Completed).unwrap());
+    assert_eq!(finished["match"]["scores"]["p1"], 2);
+    assert!(finished["match"]["gameId"].is_string());
+    assert_eq!(feed.bracket("spring-cup").unwrap()[0].winner.as_deref(), Some("p1"));
+}
+
+#[tokio::test]
+async fn test_decision_times_reach_round_results_and_player_stats() {
+    use crate::application::{EloRatings, MockClock};
+    use crate::config::PlayerStatsConfig;
+    use crate::infrastructure::{create_player_stats_routes, PlayerStatsService};
+    use std::collections::HashMap;
+
+    let clock = Arc::new(MockClock::new());
+    let manager = GameManager::new(GameConfig::default()).with_clock(clock.clone());
+    let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+    manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+
+    // p1 wins both rounds; p2 is the slower of the two each time
+    for (p1_ms, p2_ms) in [(300, 700), (100, 200)] {
+        clock.advance(std::time::Duration::from_millis(p1_ms));
+        manager.submit_move("p1", GameChoice::Rock).await.unwrap();
+        clock.advance(std::time::Duration::from_millis(p2_ms - p1_ms));
+        manager.submit_move("p2", GameChoice::Scissors).await.unwrap();
+        let decision_ms = loop {
+            if let ServerMessage::RoundResult { decision_ms, .. } = rx1.recv().await.unwrap() {
+                break decision_ms;
+            }
+        };
+        assert_eq!(decision_ms, HashMap::from([("p1".to_string(), p1_ms), ("p2".to_string(), p2_ms)]));
+    }
+
+    let history = manager.game_history();
+    let results = history.results_of(&["p1".to_string(), "p2".to_string(), "p3".to_string()]);
+    assert_eq!((results[0].decisions, results[0].avg_decision_ms, results[0].fastest_decision_ms), (2, Some(200), Some(100)));
+    assert_eq!((results[1].decisions, results[1].avg_decision_ms, results[1].fastest_decision_ms), (2, Some(450), Some(200)));
+    assert_eq!((results[2].decisions, results[2].avg_decision_ms), (0, None));
+
+    let fastest: Vec<_> = history.fastest_players(10, 2).into_iter().map(|row| (row.player_id, row.avg_decision_ms)).collect();
+    assert_eq!(fastest, [("p1".to_string(), 200), ("p2".to_string(), 450)]);
+    assert_eq!(history.fastest_players(1, 2).len(), 1);
+    assert!(history.fastest_players(10, 3).is_empty());
+
+    // Two moves each is short of what the public board asks for
+    let stats = PlayerStatsService::new(Arc::new(EloRatings::new(32.0)), history.clone(), &PlayerStatsConfig::default());
+    let routes = create_player_stats_routes(Arc::new(stats));
+    let response = warp::test::request().path("/players/fastest?limit=5").reply(&routes).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["players"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn test_repeat_moves_follow_the_rooms_move_change_policy() {
+    use crate::application::MoveAlreadySubmitted;
+    use crate::domain::MoveChangePolicy;
+
+    async fn play(config: GameConfig) -> (Option<anyhow::Error>, Vec<ServerMessage>) {
+        let manager = GameManager::new(config);
+        let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+        let (tx2, mut rx2) = tokio::sync::mpsc::unbounded_channel();
+        manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+        manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+        manager.submit_move("p1", GameChoice::Rock).await.unwrap();
+        let repeat = manager.submit_move("p1", GameChoice::Paper).await.err();
+        manager.submit_move("p2", GameChoice::Rock).await.unwrap();
+        (repeat, std::iter::from_fn(|| rx2.try_recv().ok()).collect())
+    }
+    let moved = |messages: &[ServerMessage]| {
+        messages.iter().filter(|message| matches!(message, ServerMessage::OpponentMoved { player_id, .. } if player_id == "p1")).count()
+    };
+    let round_winner = |messages: &[ServerMessage]| {
+        messages.iter().find_map(|message| match message {
+            ServerMessage::RoundResult { winner, moves, .. } => Some((winner.clone(), moves["p1"].clone())),
+            _ => None,
+        })
+    };
+
+    // By default the first move stands and the repeat is refused
+    let (repeat, messages) = play(GameConfig::default()).await;
+    assert_eq!(repeat.unwrap().downcast_ref::<MoveAlreadySubmitted>(), Some(&MoveAlreadySubmitted { round: 1 }));
+    assert_eq!(moved(&messages), 1);
+    assert_eq!(round_winner(&messages), Some((None, GameChoice::Rock)));
+
+    // Or the player may change their mind until the round resolves, announced only once
+    let config = GameConfig {
+        move_changes: MoveChangePolicy::ChangeUntilRevealed,
+        ..GameConfig::default()
+    };
+    let (repeat, messages) = play(config).await;
+    assert!(repeat.is_none());
+    assert_eq!(moved(&messages), 1);
+    assert_eq!(round_winner(&messages), Some((Some("p1".to_string()), GameChoice::Paper)));
+}
@@ -1,18 +1,24 @@
 use anyhow::Result;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::timeout;
 use tracing::info;
 
 use crate::application::GameManager;
 use crate::config::ServerConfig;
-use crate::domain::{GameConfig, Player};
+use crate::domain::Player;
+use crate::test_support::spawn_test_server_with;
 use crate::tests::load_test::{test_concurrent_connections, test_connection_limits, LoadTestConfig, LoadTestRunner};
 
 pub struct IntegrationTestSuite {
     config: ServerConfig,
 }
 
+impl Default for IntegrationTestSuite {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl IntegrationTestSuite {
     pub fn new() -> Self {
         Self {
@@ -20,20 +26,23 @@ impl IntegrationTestSuite {
         }
     }
 
+    /// Runs everything against an in-process server built from the suite's config.
     pub async fn run_all_tests(&self) -> Result<()> {
         info!("🚀 Starting Integration Test Suite");
+        let server = spawn_test_server_with(|config| *config = self.config.clone()).await?;
+        let url = server.ws_url();
 
         // Test 1: Basic functionality
         self.test_basic_game_flow().await?;
 
         // Test 2: Concurrent connections
-        self.test_concurrent_connections().await?;
+        self.test_concurrent_connections(&url).await?;
 
         // Test 3: Connection limits
-        self.test_connection_limits().await?;
+        self.test_connection_limits(&url).await?;
 
         // Test 4: Memory usage under load
-        self.test_memory_usage().await?;
+        self.test_memory_usage(&url).await?;
 
         info!("✅ All integration tests completed successfully");
         Ok(())
@@ -52,7 +61,7 @@ impl IntegrationTestSuite {
         let player2 = Arc::new(Player::new("test_player_2".to_string(), tx2));
 
         // Test matchmaking
-        let match_result1 = game_manager.find_match(player1.clone()).await?;
+        game_manager.find_match(player1.clone()).await?;
         let match_result2 = game_manager.find_match(player2.clone()).await?;
 
         // Verify match was created
@@ -68,7 +77,7 @@ impl IntegrationTestSuite {
         Ok(())
     }
 
-    async fn test_concurrent_connections(&self) -> Result<()> {
+    async fn test_concurrent_connections(&self, url: &str) -> Result<()> {
         info!("🧪 Testing concurrent connections...");
 
         let test_cases = [10, 25, 50];
@@ -76,7 +85,7 @@ impl IntegrationTestSuite {
         for &num_connections in &test_cases {
             info!("Testing {} concurrent connections", num_connections);
             
-            let metrics = test_concurrent_connections(num_connections).await?;
+            let metrics = test_concurrent_connections(url, num_connections).await?;
             
             // Verify results
             let success_rate = metrics.successful_connections as f64 / num_connections as f64;
@@ -92,10 +101,10 @@ impl IntegrationTestSuite {
         Ok(())
     }
 
-    async fn test_connection_limits(&self) -> Result<()> {
+    async fn test_connection_limits(&self, url: &str) -> Result<()> {
         info!("🧪 Testing connection limits...");
 
-        let results = test_connection_limits().await?;
+        let results = test_connection_limits(url).await?;
         
         // Find the breaking point
         let mut max_successful = 0;
@@ -114,14 +123,14 @@ impl IntegrationTestSuite {
         Ok(())
     }
 
-    async fn test_memory_usage(&self) -> Result<()> {
+    async fn test_memory_usage(&self, url: &str) -> Result<()> {
         info!("🧪 Testing memory usage under load...");
 
         // Run a sustained load test
         let config = LoadTestConfig {
             concurrent_connections: 20,
             test_duration: Duration::from_secs(30),
-            ..Default::default()
+            ..LoadTestConfig::new(url)
         };
 
         let runner = LoadTestRunner::new(config);
@@ -139,6 +148,7 @@ impl IntegrationTestSuite {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::GameConfig;
 
     #[tokio::test]
     async fn test_game_manager_basic() {
@@ -1,16 +1,13 @@
 use anyhow::Result;
-use futures_util::{SinkExt, StreamExt};
-use serde_json;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::net::TcpStream;
 use tokio::sync::Barrier;
 use tokio::time::timeout;
-use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
 use tracing::{error, info, warn};
 
-use crate::domain::{ClientMessage, GameChoice, ServerMessage};
+use crate::client::{ClientOptions, GameClient};
+use crate::domain::{GameChoice, GameMode, ServerMessage};
 
 #[derive(Debug, Clone)]
 pub struct LoadTestConfig {
@@ -21,10 +18,11 @@ pub struct LoadTestConfig {
     pub message_timeout: Duration,
 }
 
-impl Default for LoadTestConfig {
-    fn default() -> Self {
+impl LoadTestConfig {
+    /// Default load against the server at `server_url`.
+    pub fn new(server_url: impl Into<String>) -> Self {
         Self {
-            server_url: "ws://127.0.0.1:8080".to_string(),
+            server_url: server_url.into(),
             concurrent_connections: 100,
             test_duration: Duration::from_secs(30),
             connection_timeout: Duration::from_secs(5),
@@ -66,7 +64,6 @@ impl Default for LoadTestMetrics {
 
 pub struct LoadTestRunner {
     config: LoadTestConfig,
-    metrics: Arc<LoadTestMetrics>,
     // Atomic counters for thread-safe metrics
     successful_connections: Arc<AtomicU32>,
     failed_connections: Arc<AtomicU32>,
@@ -81,7 +78,6 @@ impl LoadTestRunner {
     pub fn new(config: LoadTestConfig) -> Self {
         Self {
             config,
-            metrics: Arc::new(LoadTestMetrics::default()),
             successful_connections: Arc::new(AtomicU32::new(0)),
             failed_connections: Arc::new(AtomicU32::new(0)),
             successful_matches: Arc::new(AtomicU32::new(0)),
@@ -169,101 +165,90 @@ impl LoadTestRunner {
         Ok(final_metrics)
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn run_client_session(
         client_id: String,
         config: LoadTestConfig,
         successful_connections: Arc<AtomicU32>,
         failed_connections: Arc<AtomicU32>,
         successful_matches: Arc<AtomicU32>,
-        failed_matches: Arc<AtomicU32>,
+        _failed_matches: Arc<AtomicU32>,
         completed_games: Arc<AtomicU32>,
         messages_sent: Arc<AtomicU32>,
         messages_received: Arc<AtomicU32>,
     ) -> Result<()> {
         // Connect to server
-        let connection_start = Instant::now();
-        let ws_stream = match timeout(config.connection_timeout, connect_async(&config.server_url)).await {
-            Ok(Ok((ws_stream, _))) => {
+        let options = ClientOptions {
+            url: config.server_url.clone(),
+            player_id: Some(client_id.clone()),
+            client_version: Some(format!("load-test/{}", env!("CARGO_PKG_VERSION"))),
+            connect_timeout: config.connection_timeout,
+            response_timeout: config.message_timeout,
+            ..ClientOptions::default()
+        };
+        let mut client = match GameClient::connect(options).await {
+            Ok(client) => {
                 successful_connections.fetch_add(1, Ordering::Relaxed);
-                ws_stream
+                client
             }
-            Ok(Err(e)) => {
-                failed_connections.fetch_add(1, Ordering::Relaxed);
-                return Err(anyhow::anyhow!("Connection failed: {}", e));
-            }
-            Err(_) => {
+            Err(e) => {
                 failed_connections.fetch_add(1, Ordering::Relaxed);
-                return Err(anyhow::anyhow!("Connection timeout"));
+                return Err(e.context("Connection failed"));
             }
         };
+        // Connect and its reply
+        messages_sent.fetch_add(1, Ordering::Relaxed);
+        messages_received.fetch_add(1, Ordering::Relaxed);
 
-        let (mut ws_sender, mut ws_receiver) = ws_stream.split();
-        
-        // Send connect message
-        let connect_msg = ClientMessage::Connect {
-            player_id: Some(client_id.clone()),
-        };
-        
-        Self::send_message(&mut ws_sender, &connect_msg, &messages_sent).await?;
-        
-        // Wait for connected response
-        let _connected_msg = Self::receive_message(&mut ws_receiver, &messages_received, &config).await?;
-        
         // Send find match
-        let find_match_msg = ClientMessage::FindMatch;
-        Self::send_message(&mut ws_sender, &find_match_msg, &messages_sent).await?;
-        
+        messages_sent.fetch_add(1, Ordering::Relaxed);
+        let mut msg = client.find_match(GameMode::Solo).await?;
+
         // Wait for matchmaking response
-        let match_start = Instant::now();
         loop {
-            let msg = Self::receive_message(&mut ws_receiver, &messages_received, &config).await?;
-            
+            messages_received.fetch_add(1, Ordering::Relaxed);
+
             match msg {
                 ServerMessage::Matchmaking { matched: true, .. } => {
                     successful_matches.fetch_add(1, Ordering::Relaxed);
                     break;
                 }
-                ServerMessage::Matchmaking { matched: false, .. } => {
-                    // Still waiting for match
-                    continue;
-                }
                 ServerMessage::GameStart { .. } => {
                     // Game started
                     break;
                 }
-                _ => continue,
+                // Still waiting for match
+                _ => msg = client.next_event().await?,
             }
         }
-        
+
         // Play the game
-        Self::play_game(&mut ws_sender, &mut ws_receiver, &config, &messages_sent, &messages_received).await?;
-        
+        Self::play_game(&mut client, &messages_sent, &messages_received).await?;
+
         completed_games.fetch_add(1, Ordering::Relaxed);
-        
-        Ok(())
+
+        client.close().await
     }
 
     async fn play_game(
-        ws_sender: &mut futures_util::stream::SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
-        ws_receiver: &mut futures_util::stream::SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
-        config: &LoadTestConfig,
+        client: &mut GameClient,
         messages_sent: &Arc<AtomicU32>,
         messages_received: &Arc<AtomicU32>,
     ) -> Result<()> {
         let moves = [GameChoice::Rock, GameChoice::Paper, GameChoice::Scissors];
         let mut round = 0;
-        
+
         loop {
             // Make a random move
             let choice = moves[round % moves.len()].clone();
-            let move_msg = ClientMessage::PlayerMove { choice };
-            
-            Self::send_message(ws_sender, &move_msg, messages_sent).await?;
-            
+            client.play(choice).await?;
+            messages_sent.fetch_add(1, Ordering::Relaxed);
+
             // Wait for round result or game end
             loop {
-                let msg = Self::receive_message(ws_receiver, messages_received, config).await?;
-                
+                let msg = client.next_event().await?;
+                messages_received.fetch_add(1, Ordering::Relaxed);
+
                 match msg {
                     ServerMessage::RoundResult { .. } => {
                         // Round completed
@@ -281,72 +266,41 @@ impl LoadTestRunner {
                     _ => continue,
                 }
             }
-            
+
             if round >= 10 {
                 // Safety limit
                 break;
             }
         }
-        
-        Ok(())
-    }
 
-    async fn send_message(
-        ws_sender: &mut futures_util::stream::SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
-        message: &ClientMessage,
-        messages_sent: &Arc<AtomicU32>,
-    ) -> Result<()> {
-        let json = serde_json::to_string(message)?;
-        ws_sender.send(Message::Text(json)).await?;
-        messages_sent.fetch_add(1, Ordering::Relaxed);
         Ok(())
     }
-
-    async fn receive_message(
-        ws_receiver: &mut futures_util::stream::SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
-        messages_received: &Arc<AtomicU32>,
-        config: &LoadTestConfig,
-    ) -> Result<ServerMessage> {
-        let msg = timeout(config.message_timeout, ws_receiver.next()).await
-            .map_err(|_| anyhow::anyhow!("Message receive timeout"))?
-            .ok_or_else(|| anyhow::anyhow!("Connection closed"))?
-            .map_err(|e| anyhow::anyhow!("WebSocket error: {}", e))?;
-        
-        match msg {
-            Message::Text(text) => {
-                messages_received.fetch_add(1, Ordering::Relaxed);
-                let server_msg: ServerMessage = serde_json::from_str(&text)?;
-                Ok(server_msg)
-            }
-            _ => Err(anyhow::anyhow!("Unexpected message type")),
-        }
-    }
 }
 
 // Convenience functions for different test scenarios
-pub async fn test_concurrent_connections(num_connections: usize) -> Result<LoadTestMetrics> {
+pub async fn test_concurrent_connections(server_url: &str, num_connections: usize) -> Result<LoadTestMetrics> {
     let config = LoadTestConfig {
         concurrent_connections: num_connections,
         test_duration: Duration::from_secs(60),
-        ..Default::default()
+        ..LoadTestConfig::new(server_url)
     };
     
     let runner = LoadTestRunner::new(config);
     runner.run_load_test().await
 }
 
-pub async fn test_sustained_load(duration_secs: u64) -> Result<LoadTestMetrics> {
+pub async fn test_sustained_load(server_url: &str, duration_secs: u64) -> Result<LoadTestMetrics> {
     let config = LoadTestConfig {
         concurrent_connections: 50,
         test_duration: Duration::from_secs(duration_secs),
-        ..Default::default()
+        ..LoadTestConfig::new(server_url)
     };
     
     let runner = LoadTestRunner::new(config);
     runner.run_load_test().await
 }
 
-pub async fn test_connection_limits() -> Result<Vec<(usize, LoadTestMetrics)>> {
+pub async fn test_connection_limits(server_url: &str) -> Result<Vec<(usize, LoadTestMetrics)>> {
     let connection_counts = [10, 25, 50, 100, 200, 500, 1000];
     let mut results = Vec::new();
     
@@ -357,7 +311,7 @@ pub async fn test_connection_limits() -> Result<Vec<(usize, LoadTestMetrics)>> {
             concurrent_connections: count,
             test_duration: Duration::from_secs(30),
             connection_timeout: Duration::from_secs(10),
-            ..Default::default()
+            ..LoadTestConfig::new(server_url)
         };
         
         let runner = LoadTestRunner::new(config);
@@ -0,0 +1,427 @@
+use std::sync::Arc;
+
+use crate::application::{EloRatings, GameManager, MoveSample, PresenceRegistry};
+use crate::config::{AbandonedMatchPolicy, BotDetectionConfig, CapacityConfig, MatchmakingConfig, QueueOverflowPolicy};
+use crate::domain::{GameConfig, GameEvent, GameMode, GameStatus, GameType, Player, PlayerPhase, ServerMessage};
+
+#[tokio::test]
+async fn test_waiting_player_keeps_their_place_when_a_match_with_a_stale_player_fails() {
+    let manager = GameManager::new(GameConfig::default());
+    let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+    let waiting = manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+    assert!(matches!(waiting, ServerMessage::Matchmaking { matched: false, .. }));
+
+    // A request still in flight when its connection closed would be paired with p1
+    let (stale_tx, stale_rx) = tokio::sync::mpsc::unbounded_channel();
+    drop(stale_rx);
+    assert!(manager.find_match(Arc::new(Player::new("stale".to_string(), stale_tx))).await.is_err());
+    assert_eq!(manager.get_stats().await, (0, 0, 1));
+    assert_eq!(manager.player_phase("stale").await, PlayerPhase::Idle);
+
+    let (tx3, _rx3) = tokio::sync::mpsc::unbounded_channel();
+    let matched = manager.find_match(Arc::new(Player::new("p3".to_string(), tx3))).await.unwrap();
+    assert!(matches!(matched, ServerMessage::Matchmaking { matched: true, .. }));
+    assert!(matches!(rx1.recv().await, Some(ServerMessage::GameStart { .. })));
+}
+
+#[tokio::test]
+async fn test_repeated_find_match_queues_player_once() {
+    let manager = GameManager::new(GameConfig::default());
+    let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+    let p1 = Arc::new(Player::new("p1".to_string(), tx1));
+
+    for _ in 0..2 {
+        let response = manager.find_match(p1.clone()).await.unwrap();
+        assert!(matches!(response, ServerMessage::Matchmaking { matched: false, waiting: Some(true), .. }));
+    }
+    assert_eq!(manager.get_stats().await, (0, 0, 1));
+    assert_eq!(manager.player_phase("p1").await, PlayerPhase::Queued);
+
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    let matched = manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+    assert!(matches!(matched, ServerMessage::Matchmaking { matched: true, .. }));
+    assert_eq!(manager.get_stats().await, (1, 1, 0));
+    assert_eq!(manager.player_phase("p1").await, PlayerPhase::InGame);
+}
+
+#[tokio::test]
+async fn test_stats_come_from_counters_and_match_a_deep_scan() {
+    let manager = GameManager::new(GameConfig::default());
+    let mut receivers = Vec::new();
+    let mut player = |id: &str| {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        receivers.push(rx);
+        Arc::new(Player::new(id.to_string(), tx))
+    };
+
+    manager.find_match(player("p1")).await.unwrap();
+    manager.find_match(player("p2")).await.unwrap();
+    manager.find_match(player("p3")).await.unwrap();
+    manager.find_match_for(player("p4"), GameMode::Solo, GameType::Morra).await.unwrap();
+    assert_eq!(manager.get_stats().await, (1, 1, 2));
+
+    let deep = manager.deep_stats().await;
+    assert_eq!(deep.rooms, 1);
+    assert_eq!(deep.rooms_by_status.get(&GameStatus::Playing), Some(&1));
+    assert_eq!(deep.seated_players, 2);
+    assert_eq!(deep.queued["waiting"], 1);
+    assert_eq!(deep.queued["morra"], 1); // Game queues are reported too, by game
+
+    // A reserved room is open but not yet active
+    let room_id = manager.reserve_match(vec!["a".to_string(), "b".to_string()]).await.unwrap().room_id;
+    let snapshot = manager.room_snapshot(&room_id).await.unwrap();
+    assert_eq!(snapshot.status, GameStatus::Waiting);
+    assert_eq!(manager.get_stats().await, (2, 1, 2));
+
+    // A game stops counting as active when it pauses or its room goes away
+    assert!(manager.request_pause("p1").await.unwrap() || manager.request_pause("p2").await.unwrap());
+    manager.remove_player("p1").await.unwrap();
+    manager.close_room(&room_id, "test").await.unwrap();
+    assert_eq!(manager.get_stats().await, (0, 0, 2));
+    let counters = manager.live_stats();
+    assert_eq!((counters.rooms_created, counters.rooms_closed, counters.games_started), (2, 2, 1));
+    assert_eq!(counters.active_games, 0);
+}
+
+#[tokio::test]
+async fn test_matches_skip_stale_and_duplicate_queue_entries() {
+    let manager = GameManager::new(GameConfig::default());
+
+    // Queued, then the connection dropped without a remove_player
+    let (ghost_tx, ghost_rx) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("ghost".to_string(), ghost_tx))).await.unwrap();
+    drop(ghost_rx);
+
+    let (old_tx, _old_rx) = tokio::sync::mpsc::unbounded_channel();
+    let waiting = manager.find_match(Arc::new(Player::new("p1".to_string(), old_tx))).await.unwrap();
+    assert!(matches!(waiting, ServerMessage::Matchmaking { matched: false, .. }));
+    assert_eq!(manager.get_stats().await, (0, 0, 1));
+    assert_eq!(manager.player_phase("ghost").await, PlayerPhase::Idle);
+
+    // A second connection under the same id takes over the queue entry
+    let (new_tx, mut new_rx) = tokio::sync::mpsc::unbounded_channel();
+    let waiting = manager.find_match(Arc::new(Player::new("p1".to_string(), new_tx))).await.unwrap();
+    assert!(matches!(waiting, ServerMessage::Matchmaking { matched: false, .. }));
+    assert_eq!(manager.get_stats().await, (0, 0, 1));
+
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    let matched = manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+    assert!(matches!(matched, ServerMessage::Matchmaking { matched: true, .. }));
+    assert!(matches!(new_rx.recv().await, Some(ServerMessage::GameStart { .. })));
+
+    let (closed_tx, closed_rx) = tokio::sync::mpsc::unbounded_channel();
+    drop(closed_rx);
+    let offline = Arc::new(Player::new("p3".to_string(), closed_tx));
+    assert!(manager.find_match_in_mode(offline, GameMode::Bot).await.is_err());
+}
+
+#[tokio::test]
+async fn test_dead_queue_entries_are_evicted() {
+    let manager = GameManager::new(GameConfig::default());
+    let presence = PresenceRegistry::new();
+    let mut events = manager.events().subscribe();
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("crashed".to_string(), tx))).await.unwrap();
+    assert_eq!(manager.evict_stale_queue_entries().await, 0);
+
+    drop(rx);
+    assert_eq!(manager.evict_stale_queue_entries().await, 1);
+    assert_eq!(manager.get_stats().await, (0, 0, 0));
+    assert_eq!(manager.player_phase("crashed").await, PlayerPhase::Idle);
+
+    while let Ok(envelope) = events.try_recv() {
+        presence.apply(&envelope.event);
+    }
+    assert!(presence.get("crashed").is_none());
+}
+
+#[tokio::test]
+async fn test_abandoned_players_are_rematched_per_policy() {
+    let rematch = |policy: AbandonedMatchPolicy| async move {
+        let config = GameConfig { reconnect_grace_ms: 0, ..GameConfig::default() };
+        let manager = GameManager::new(config).with_matchmaking(MatchmakingConfig { abandoned_match: policy, ..MatchmakingConfig::default() });
+        let (tx1, mut rx1) = tokio::sync::mpsc::unbounded_channel();
+        let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+        manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+        manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+        manager.disconnect_player("p2").await.unwrap();
+
+        let mut after_left = Vec::new();
+        let mut left = false;
+        while let Ok(message) = rx1.try_recv() {
+            match message {
+                ServerMessage::PlayerLeft { .. } => left = true,
+                ServerMessage::Matchmaking { matched, .. } if left => after_left.push(matched),
+                _ => {}
+            }
+        }
+        (manager, after_left, rx1)
+    };
+
+    let (manager, messages, _rx1) = rematch(AbandonedMatchPolicy::Off).await;
+    assert!(messages.is_empty());
+    assert_eq!(manager.player_phase("p1").await, PlayerPhase::Idle);
+
+    let (manager, messages, _rx1) = rematch(AbandonedMatchPolicy::Bot).await;
+    assert_eq!(messages, [true]);
+    assert_eq!(manager.player_phase("p1").await, PlayerPhase::InGame);
+
+    let (manager, messages, _rx1) = rematch(AbandonedMatchPolicy::Requeue).await;
+    assert_eq!(messages, [false]);
+    let (tx3, _rx3) = tokio::sync::mpsc::unbounded_channel();
+    let matched = manager.find_match(Arc::new(Player::new("p3".to_string(), tx3))).await.unwrap();
+    assert!(matches!(matched, ServerMessage::Matchmaking { matched: true, .. }));
+    assert_eq!(manager.player_phase("p1").await, PlayerPhase::InGame);
+}
+
+#[tokio::test]
+async fn test_find_match_is_refused_with_server_busy_at_capacity() {
+    let capacity = CapacityConfig { max_rooms: 1, max_players: None, retry_after_ms: 2500 };
+    let manager = GameManager::new(GameConfig::default()).with_capacity(capacity.clone(), 1000);
+    let mut receivers = Vec::new();
+    let mut player = |id: &str| {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        receivers.push(rx);
+        Arc::new(Player::new(id.to_string(), tx))
+    };
+
+    manager.find_match(player("p1")).await.unwrap();
+    assert!(matches!(manager.find_match(player("p2")).await.unwrap(), ServerMessage::Matchmaking { matched: true, .. }));
+    let busy = manager.find_match(player("p3")).await.unwrap();
+    assert!(matches!(busy, ServerMessage::ServerBusy { retry_after_ms: 2500 }));
+    assert_eq!(serde_json::to_value(&busy).unwrap(), serde_json::json!({ "type": "serverBusy", "retryAfterMs": 2500 }));
+
+    // Without `max_players`, the connection limit caps engaged players
+    let manager = GameManager::new(GameConfig::default()).with_capacity(capacity, 1);
+    let p1 = player("p1");
+    manager.find_match(p1.clone()).await.unwrap();
+    assert!(matches!(manager.find_match(player("p2")).await.unwrap(), ServerMessage::ServerBusy { .. }));
+    // Already queued players are acknowledged, not refused
+    assert!(matches!(manager.find_match(p1).await.unwrap(), ServerMessage::Matchmaking { matched: false, .. }));
+}
+
+#[tokio::test]
+async fn test_sharded_manager_spreads_rooms_and_aggregates_stats() {
+    let manager = GameManager::new(GameConfig::default()).with_shards(4);
+    let mut receivers = Vec::new();
+    let mut player = |id: String| {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        receivers.push(rx);
+        Arc::new(Player::new(id, tx))
+    };
+
+    for i in 0..40 {
+        manager.find_match(player(format!("p{}", i))).await.unwrap();
+    }
+    manager.find_match(player("waiting".to_string())).await.unwrap();
+
+    let shards = manager.shard_stats().await;
+    assert_eq!(shards.len(), 4);
+    assert!(shards.iter().filter(|shard| shard.rooms > 0).count() > 1);
+    assert_eq!(shards.iter().map(|shard| shard.rooms).sum::<usize>(), 20);
+    assert_eq!(shards.iter().map(|shard| shard.seated_players).sum::<usize>(), 40);
+    assert_eq!(shards.iter().map(|shard| shard.queued_players).sum::<usize>(), 1);
+    assert_eq!(manager.get_stats().await, (20, 20, 1));
+
+    // Lookups go through whichever shards own the player and their room
+    assert_eq!(manager.player_phase("p7").await, PlayerPhase::InGame);
+    assert_eq!(manager.player_phase("waiting").await, PlayerPhase::Queued);
+    manager.remove_player("p7").await.unwrap();
+    assert_eq!(manager.player_phase("p7").await, PlayerPhase::Idle);
+    assert_eq!(manager.get_stats().await.0, 19);
+}
+
+#[tokio::test]
+async fn test_full_waiting_queue_applies_overflow_policy() {
+    let mut receivers = Vec::new();
+    let mut player = |id: &str| {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        receivers.push(rx);
+        Arc::new(Player::new(id.to_string(), tx))
+    };
+
+    // A team game needs four players, so a queue of two fills up before anyone pairs
+    let bounded = |policy| MatchmakingConfig { max_queue_len: 2, queue_overflow: policy, ..MatchmakingConfig::default() };
+    let manager = GameManager::new(GameConfig::default()).with_matchmaking(bounded(QueueOverflowPolicy::Reject));
+    manager.find_match_in_mode(player("a"), GameMode::Teams).await.unwrap();
+    manager.find_match_in_mode(player("b"), GameMode::Teams).await.unwrap();
+    let refused = manager.find_match_in_mode(player("c"), GameMode::Teams).await.unwrap();
+    assert!(matches!(refused, ServerMessage::ServerBusy { .. }));
+    assert_eq!(manager.player_phase("c").await, PlayerPhase::Idle);
+    assert_eq!(manager.get_stats().await, (0, 0, 2));
+    assert_eq!(manager.live_stats().queue_overflows, 1);
+
+    let manager = GameManager::new(GameConfig::default()).with_matchmaking(bounded(QueueOverflowPolicy::BotMatchOldest));
+    let (oldest_tx, mut oldest_rx) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match_in_mode(Arc::new(Player::new("a".to_string(), oldest_tx)), GameMode::Teams).await.unwrap();
+    manager.find_match_in_mode(player("b"), GameMode::Teams).await.unwrap();
+    let queued = manager.find_match_in_mode(player("c"), GameMode::Teams).await.unwrap();
+    assert!(matches!(queued, ServerMessage::Matchmaking { matched: false, .. }));
+    assert_eq!(manager.player_phase("a").await, PlayerPhase::InGame);
+    assert_eq!(manager.player_phase("c").await, PlayerPhase::Queued);
+    assert_eq!(manager.get_stats().await, (1, 1, 2));
+    let mut sent = Vec::new();
+    while let Ok(message) = oldest_rx.try_recv() {
+        sent.push(message);
+    }
+    assert!(sent.iter().any(|m| matches!(m, ServerMessage::Matchmaking { matched: true, .. })));
+}
+
+#[tokio::test]
+async fn test_reserved_room_backfills_lost_seat_with_consent() {
+    let manager = GameManager::new(GameConfig::default())
+        .with_matchmaking(MatchmakingConfig { backfill_reserved_rooms: true, ..MatchmakingConfig::default() });
+    let connect = |id: &str| {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        (Arc::new(Player::new(id.to_string(), tx)), rx)
+    };
+
+    // The reserved opponent goes away before joining; the seated player is asked first
+    let reserved = manager.reserve_match(vec!["a".to_string(), "b".to_string()]).await.unwrap();
+    let (a, mut rx_a) = connect("a");
+    manager.join_room(a, &reserved.join_code).await.unwrap();
+    let (c, mut rx_c) = connect("c");
+    manager.find_match(c).await.unwrap();
+    manager.remove_player("b").await.unwrap();
+    match rx_a.try_recv().unwrap() {
+        ServerMessage::BackfillOffer { room_id, player_id } => {
+            assert_eq!(room_id, reserved.room_id);
+            assert_eq!(player_id, "b");
+        }
+        other => panic!("expected a backfill offer, got {:?}", other),
+    }
+    assert_eq!(manager.player_phase("c").await, PlayerPhase::Queued);
+
+    // Consent hands the seat to whoever has waited longest
+    let answer = manager.answer_backfill("a", true).await.unwrap();
+    assert!(matches!(answer, Some(ServerMessage::Matchmaking { matched: true, .. })));
+    let received: Vec<_> = std::iter::from_fn(|| rx_c.try_recv().ok()).collect();
+    assert!(received.iter().any(|m| matches!(m, ServerMessage::GameStart { .. })));
+    assert!(received.iter().any(|m| matches!(m, ServerMessage::Matchmaking { matched: true, .. })));
+    assert!(manager.join_room(connect("b").0, &reserved.join_code).await.unwrap().is_none());
+
+    // With nobody queued, the next player to ask for a match takes the seat
+    let reserved = manager.reserve_match(vec!["d".to_string(), "e".to_string()]).await.unwrap();
+    manager.join_room(connect("d").0, &reserved.room_id).await.unwrap();
+    manager.remove_player("e").await.unwrap();
+    let answer = manager.answer_backfill("d", true).await.unwrap();
+    assert!(matches!(answer, Some(ServerMessage::Matchmaking { matched: false, .. })));
+    let (f, _rx_f) = connect("f");
+    let joined = manager.find_match(f).await.unwrap();
+    assert!(matches!(joined, ServerMessage::Matchmaking { matched: true, room_id: Some(ref id), .. } if *id == reserved.room_id));
+
+    // Players without an open seat have nothing to answer
+    assert!(manager.answer_backfill("f", true).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_frequent_quitters_cool_down_and_the_penalty_wears_off() {
+    use crate::application::{Clock, MockClock, QuitCooldown, QuitPenalties, QuitPenaltyStatus};
+    use crate::config::{PlayerStatsConfig, QuitPenaltyConfig};
+    use crate::infrastructure::PlayerStatsService;
+    use std::time::{Duration, Instant};
+
+    let penalty = QuitPenaltyConfig { enabled: true, window_ms: 3_600_000, min_games: 2, max_quit_rate: 0.5, cooldown_ms: 60_000 };
+    let config = GameConfig { reconnect_grace_ms: 10_000, ..GameConfig::default() };
+    let clock = Arc::new(MockClock::new());
+    let manager = GameManager::new(config).with_clock(clock.clone()).with_quit_penalty(penalty.clone());
+    let mut receivers = Vec::new();
+    let mut player = |id: &str| {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        receivers.push(rx);
+        Arc::new(Player::new(id.to_string(), tx))
+    };
+
+    // Leaving one game of one is too few games to judge
+    manager.find_match(player("quitter")).await.unwrap();
+    manager.find_match(player("o1")).await.unwrap();
+    manager.remove_player("quitter").await.unwrap();
+    manager.find_match(player("quitter")).await.unwrap();
+    manager.find_match(player("o2")).await.unwrap();
+    // Staying away past the reconnect grace counts as leaving too
+    manager.disconnect_player("quitter").await.unwrap();
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(manager.expire_reconnect_grace().await.unwrap(), 1);
+
+    let refused = manager.find_match(player("quitter")).await.unwrap_err();
+    assert_eq!(refused.downcast_ref::<QuitCooldown>(), Some(&QuitCooldown { remaining: Duration::from_secs(60) }));
+    let status = manager.quit_penalties().status("quitter", clock.now());
+    assert_eq!((status.recent_games, status.recent_quits, status.penalized), (2, 2, true));
+    assert_eq!(status.cooldown_remaining_ms, Some(60_000));
+    assert_eq!(manager.quit_penalties().status("o1", clock.now()).recent_quits, 0);
+
+    clock.advance(Duration::from_secs(60));
+    let waiting = manager.find_match(player("quitter")).await.unwrap();
+    assert!(matches!(waiting, ServerMessage::Matchmaking { matched: false, .. }));
+    manager.remove_player("quitter").await.unwrap(); // Leaving the queue isn't quitting a game
+    assert_eq!(manager.quit_penalties().status("quitter", clock.now()).recent_quits, 2);
+    clock.advance(Duration::from_secs(3600));
+    assert_eq!(manager.quit_penalties().status("quitter", clock.now()), QuitPenaltyStatus::default());
+    assert_eq!(manager.prune_quit_penalties(), 3);
+
+    // Shown alongside the player's other stats
+    let penalties = Arc::new(QuitPenalties::new(penalty));
+    penalties.game_started("p1", Instant::now());
+    penalties.player_quit("p1", Instant::now());
+    let stats = PlayerStatsService::new(Arc::new(EloRatings::new(32.0)), manager.game_history().clone(), &PlayerStatsConfig { max_batch: 4 })
+        .with_quit_penalties(penalties);
+    let entry = serde_json::to_value(&stats.lookup(vec!["p1".to_string()])[0]).unwrap();
+    assert_eq!(
+        entry["quitPenalty"],
+        serde_json::json!({ "recentGames": 1, "recentQuits": 1, "quitRate": 1.0, "penalized": false, "cooldownRemainingMs": null })
+    );
+}
+
+#[tokio::test]
+async fn test_starved_players_are_reported_and_let_out_of_the_suspect_pool() {
+    use crate::application::MockClock;
+    use crate::config::StarvationConfig;
+    use std::time::Duration;
+
+    let clock = Arc::new(MockClock::new());
+    let matchmaking = MatchmakingConfig { starvation: StarvationConfig { slo_ms: 30_000, relax_pairing: true }, ..MatchmakingConfig::default() };
+    let manager = GameManager::new(GameConfig::default())
+        .with_clock(clock.clone())
+        .with_bot_detection(BotDetectionConfig { separate_pool: true, ..BotDetectionConfig::default() })
+        .with_matchmaking(matchmaking);
+    for i in 0..20 {
+        manager.bot_detector().record(&MoveSample { player_id: "bot".to_string(), reaction_ms: 200 + i % 2, countered_previous: Some(true) });
+    }
+    let mut events = manager.events().subscribe();
+    let mut receivers = Vec::new();
+    let mut player = |id: &str| {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        receivers.push(rx);
+        Arc::new(Player::new(id.to_string(), tx))
+    };
+
+    // Kept in separate pools, the two never meet on their own
+    manager.find_match(player("bot")).await.unwrap();
+    manager.find_match(player("human")).await.unwrap();
+    clock.advance(Duration::from_secs(29));
+    assert_eq!(manager.check_starvation().await, 0);
+    let report = manager.starvation_report();
+    assert_eq!(report.queues.iter().map(|queue| (queue.queue.as_str(), queue.waiting, queue.starved)).collect::<Vec<_>>(), vec![("suspect", 1, 0), ("waiting", 1, 0)]);
+
+    // Past the SLO both are reported once, and the suspect is paired from the main queue
+    clock.advance(Duration::from_secs(2));
+    assert_eq!(manager.check_starvation().await, 2);
+    assert_eq!(manager.check_starvation().await, 0);
+    let mut starved = Vec::new();
+    while let Ok(envelope) = events.try_recv() {
+        if let GameEvent::QueueStarved { player_id, queue, waited_ms } = envelope.event {
+            starved.push((player_id, queue, waited_ms));
+        }
+    }
+    starved.sort();
+    assert_eq!(starved, vec![("bot".to_string(), "suspect".to_string(), 31_000), ("human".to_string(), "waiting".to_string(), 31_000)]);
+    assert_eq!(manager.player_phase("bot").await, PlayerPhase::InGame);
+    assert_eq!(manager.player_phase("human").await, PlayerPhase::InGame);
+
+    let report = manager.starvation_report();
+    assert_eq!((report.slo_ms, report.starved_total, report.relaxed_total), (30_000, 2, 1));
+    // Each wait counts towards the queue it was spent in
+    let waits: Vec<_> = report.queues.iter().map(|queue| (queue.queue.as_str(), queue.waiting, queue.p50_ms, queue.p99_ms)).collect();
+    assert_eq!(waits, vec![("suspect", 0, 31_000, 31_000), ("waiting", 0, 31_000, 31_000)]);
+}
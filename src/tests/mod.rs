@@ -1,5 +1,20 @@
 pub mod load_test;
 pub mod integration_test;
+pub mod conformance;
+
+#[cfg(test)]
+mod cluster;
+#[cfg(test)]
+mod games;
+#[cfg(test)]
+mod matchmaking;
+#[cfg(test)]
+mod players;
+#[cfg(test)]
+mod resume;
+#[cfg(test)]
+mod websocket;
 
 pub use load_test::*;
-pub use integration_test::*;
\ No newline at end of file
+pub use integration_test::*;
+pub use conformance::*;
@@ -0,0 +1,245 @@
+use std::sync::Arc;
+use warp::Filter;
+
+use crate::application::{EloRatings, GameManager, RatedGame};
+use crate::config::{Role, SeasonsConfig, Secret, SecretStore};
+use crate::domain::{GameChoice, GameConfig, GameEndReason, GameEvent, Player};
+use crate::infrastructure::{with_request_id, AdminAction, AuditLog, AuditQuery, NotificationInbox, Seasons};
+
+#[tokio::test]
+async fn test_stats_batch_answers_many_players_in_one_call() {
+    use crate::application::{GameHistory, GameRecord};
+    use crate::config::PlayerStatsConfig;
+    use crate::domain::{GameMode, GameStatus};
+    use crate::infrastructure::{create_player_stats_routes, PlayerStatsService};
+
+    let elo = Arc::new(EloRatings::new(32.0));
+    let history = Arc::new(GameHistory::new(10));
+    for (game_id, winner) in [("g1", Some("p1")), ("g2", None), ("g3", Some("p1"))] {
+        let winner = winner.map(str::to_string);
+        elo.apply(&RatedGame {
+            game_id: game_id.to_string(),
+            room_id: "r".to_string(),
+            players: vec!["p1".to_string(), "p2".to_string()],
+            teams: Default::default(),
+            winner: winner.clone(),
+            reason: GameEndReason::Completed,
+        });
+        history.record(GameRecord {
+            game_id: game_id.to_string(),
+            room_id: "r".to_string(),
+            mode: GameMode::Solo,
+            status: GameStatus::Finished,
+            players: vec!["p1".to_string(), "p2".to_string()],
+            teams: Default::default(),
+            winner,
+            scores: Default::default(),
+            reason: Some(GameEndReason::Completed),
+            rounds: Vec::new(),
+            created_at: chrono::Utc::now(),
+            ended_at: Some(chrono::Utc::now()),
+        });
+    }
+
+    let stats = Arc::new(PlayerStatsService::new(elo.clone(), history, &PlayerStatsConfig { max_batch: 4 }));
+    let routes = create_player_stats_routes(stats);
+    let batch = |ids: &[&str]| {
+        warp::test::request()
+            .method("POST")
+            .path("/players/stats:batch")
+            .json(&serde_json::json!({ "playerIds": ids }))
+    };
+
+    let response = batch(&["p2", "newcomer", "p1", "p2"]).reply(&routes).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    let players = body["players"].as_array().unwrap();
+    let ids: Vec<_> = players.iter().map(|row| row["playerId"].as_str().unwrap()).collect();
+    assert_eq!(ids, ["p2", "newcomer", "p1"]); // Asked order, repeats dropped
+    assert_eq!(players[0]["rank"], 2);
+    assert_eq!(players[0]["rating"], elo.rating("p2"));
+    assert_eq!(players[0]["recent"], serde_json::json!({ "games": 3, "wins": 0, "losses": 2, "draws": 1, "decisions": 0 }));
+    assert_eq!(players[1]["rank"], serde_json::Value::Null);
+    assert_eq!(players[1]["rating"], 1200.0);
+    assert_eq!(players[1]["recent"]["games"], 0);
+    assert_eq!(players[2]["rank"], 1);
+    assert_eq!(players[2]["ratedGames"], 3);
+    assert_eq!(players[2]["recent"]["wins"], 2);
+
+    assert_eq!(batch(&["a", "b", "c", "d", "e"]).reply(&routes).await.status(), 400);
+    assert_eq!(batch(&[]).reply(&routes).await.status(), 400);
+}
+
+#[tokio::test]
+async fn test_issued_api_keys_are_limited_to_their_role_and_survive_a_restart() {
+    use crate::infrastructure::{create_api_key_routes, create_room_template_routes, ApiKeyStore, RoomTemplateStore};
+
+    let path = std::env::temp_dir().join(format!("rps-api-keys-{}.jsonl", uuid::Uuid::new_v4()));
+    let secrets = Arc::new(SecretStore::from_keys(vec![Secret::new("ops", "admin-key")], vec![]));
+    let keys = Arc::new(ApiKeyStore::open(&path, secrets.clone()).unwrap());
+    let audit_log = Arc::new(AuditLog::in_memory());
+    let routes = with_request_id(
+        create_api_key_routes(keys, audit_log.clone(), secrets.clone())
+            .or(create_room_template_routes(Arc::new(RoomTemplateStore::in_memory()), audit_log.clone(), secrets.clone())),
+    );
+    let issue = |key: &str, body: serde_json::Value| warp::test::request().method("POST").path("/admin/keys").header("x-api-key", key).json(&body);
+    let templates = |key: &str, method: &str| {
+        warp::test::request().method(method).path("/admin/templates/quick").header("x-api-key", key).json(&serde_json::json!({}))
+    };
+
+    let response = issue("admin-key", serde_json::json!({ "id": "dashboard", "role": "viewer" })).reply(&routes).await;
+    assert_eq!(response.status(), 201);
+    let viewer = serde_json::from_slice::<serde_json::Value>(response.body()).unwrap()["key"].as_str().unwrap().to_string();
+    assert_eq!(issue("admin-key", serde_json::json!({ "id": "dashboard", "role": "admin" })).reply(&routes).await.status(), 409);
+
+    // Viewers read, but neither write nor manage keys
+    assert_eq!(templates(&viewer, "GET").reply(&routes).await.status(), 404);
+    assert_eq!(templates(&viewer, "PUT").reply(&routes).await.status(), 403);
+    assert_eq!(issue(&viewer, serde_json::json!({ "role": "admin" })).reply(&routes).await.status(), 403);
+    assert_eq!(templates("wrong-key", "GET").reply(&routes).await.status(), 401);
+
+    // Only the hash is stored, and the key is accepted again after a restart
+    assert!(!std::fs::read_to_string(&path).unwrap().contains(&viewer));
+    let restarted = Arc::new(SecretStore::from_keys(Vec::new(), vec![]));
+    let reopened = ApiKeyStore::open(&path, restarted.clone()).unwrap();
+    assert_eq!(restarted.verify_api_key(&viewer), Some(("dashboard".to_string(), Role::Viewer)));
+
+    assert!(reopened.revoke("dashboard").unwrap());
+    assert_eq!(restarted.verify_api_key(&viewer), None);
+    assert!(ApiKeyStore::open(&path, Arc::new(SecretStore::from_keys(Vec::new(), vec![]))).unwrap().list().is_empty());
+    let actions: Vec<_> = audit_log.query(&AuditQuery::default()).into_iter().map(|entry| entry.action).collect();
+    assert!(actions.contains(&AdminAction::IssueApiKey));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_activity_timeline_is_open_to_admins_and_the_player_only() {
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use hmac::{Hmac, Mac};
+    use crate::application::PlayerActivity;
+    use crate::config::ActivityConfig;
+    use crate::infrastructure::create_player_activity_routes;
+
+    let manager = GameManager::new(GameConfig::default());
+    let activity = Arc::new(PlayerActivity::new(manager.game_history().clone(), &ActivityConfig::default()));
+    let mut events = manager.events().subscribe();
+    manager.events().publish(GameEvent::PlayerConnected { player_id: "p1".to_string() });
+    let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+    manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+    for _ in 0..2 {
+        manager.submit_move("p1", GameChoice::Rock).await.unwrap();
+        manager.submit_move("p2", GameChoice::Scissors).await.unwrap();
+    }
+    while let Ok(envelope) = events.try_recv() {
+        activity.apply(&envelope);
+    }
+
+    let secrets = Arc::new(SecretStore::from_keys(vec![Secret::new("ops", "admin-key")], vec![Secret::new("k1", "jwt-secret")]));
+    let routes = create_player_activity_routes(activity, secrets);
+    let token = |sub: &str, exp: i64| {
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","kid":"k1"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(serde_json::json!({ "sub": sub, "exp": exp }).to_string());
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"jwt-secret").unwrap();
+        mac.update(format!("{}.{}", header, claims).as_bytes());
+        format!("Bearer {}.{}.{}", header, claims, URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()))
+    };
+    let later = chrono::Utc::now().timestamp() + 60;
+    let get = |authorization: String| warp::test::request().path("/players/p1/activity").header("authorization", authorization);
+
+    let own = get(token("p1", later)).reply(&routes).await;
+    assert_eq!(own.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(own.body()).unwrap();
+    let types: Vec<&str> = body["entries"].as_array().unwrap().iter().map(|entry| entry["type"].as_str().unwrap()).collect();
+    assert_eq!(types, ["game", "matched", "queued", "connected"]);
+    assert_eq!(body["entries"][0]["outcome"], "won");
+    assert_eq!(body["entries"][1]["opponents"], serde_json::json!(["p2"]));
+
+    assert_eq!(get("Bearer admin-key".to_string()).reply(&routes).await.status(), 200);
+    assert_eq!(get(token("p2", later)).reply(&routes).await.status(), 403);
+    assert!(!get(token("p1", later - 120)).reply(&routes).await.status().is_success());
+    assert!(!get(token("p1", later).replace("Bearer ey", "Bearer ex")).reply(&routes).await.status().is_success());
+}
+
+#[tokio::test]
+async fn test_player_data_is_exported_and_erased_on_request() {
+    use crate::application::PlayerActivity;
+    use crate::config::ActivityConfig;
+    use crate::domain::TitleSource;
+    use crate::infrastructure::{create_player_data_routes, PlayerDataService, TitleStore};
+
+    let manager = GameManager::new(GameConfig::default());
+    let (tx1, _rx1) = tokio::sync::mpsc::unbounded_channel();
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("p1".to_string(), tx1))).await.unwrap();
+    manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+    for _ in 0..2 {
+        manager.submit_move("p1", GameChoice::Rock).await.unwrap();
+        manager.submit_move("p2", GameChoice::Scissors).await.unwrap();
+    }
+
+    let elo = Arc::new(EloRatings::new(32.0));
+    let rate = || {
+        elo.apply(&RatedGame {
+            game_id: uuid::Uuid::new_v4().to_string(),
+            room_id: "r".to_string(),
+            players: vec!["p1".to_string(), "p2".to_string()],
+            teams: Default::default(),
+            winner: Some("p1".to_string()),
+            reason: GameEndReason::Completed,
+        })
+    };
+    rate();
+    let notifications = Arc::new(NotificationInbox::in_memory(10));
+    let seasons = Arc::new(Seasons::in_memory(elo.clone(), &SeasonsConfig::default()));
+    seasons.roll_over(&notifications).unwrap();
+    rate(); // The rollover also left p1 a seasonEnded notification
+    let titles_path = std::env::temp_dir().join(format!("rps-titles-{}.jsonl", uuid::Uuid::new_v4()));
+    let titles = Arc::new(TitleStore::open(&titles_path).unwrap());
+    titles.grant("p1", "spring-cup", "Spring Champion", TitleSource::Tournament).unwrap();
+    titles.grant("p2", "summer-cup", "Summer Champion", TitleSource::Tournament).unwrap();
+    let activity = Arc::new(PlayerActivity::new(manager.game_history().clone(), &ActivityConfig::default()));
+
+    let service = PlayerDataService::new(elo.clone(), manager.game_history().clone(), titles.clone(), notifications.clone(), seasons.clone(), activity);
+    let audit_log = Arc::new(AuditLog::in_memory());
+    let secrets = Arc::new(SecretStore::from_keys(vec![Secret::new("ops", "admin-key")], Vec::new()));
+    let routes = create_player_data_routes(Arc::new(service), audit_log.clone(), secrets);
+    let request = |method: &str, player: &str| {
+        warp::test::request().method(method).path(&format!("/players/{}/data", player)).header("x-api-key", "admin-key")
+    };
+
+    let export = request("GET", "p1").reply(&routes).await;
+    assert_eq!(export.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(export.body()).unwrap();
+    assert_eq!(body["rating"]["playerId"], "p1");
+    assert_eq!(body["seasons"][0]["season"], 1);
+    assert_eq!(body["games"].as_array().unwrap().len(), 1);
+    assert_eq!(body["titles"]["titles"][0]["id"], "spring-cup");
+    assert_eq!(body["notifications"][0]["kind"], "seasonEnded");
+
+    let deleted = request("DELETE", "p1").reply(&routes).await;
+    assert_eq!(deleted.status(), 200);
+    let report: serde_json::Value = serde_json::from_slice(deleted.body()).unwrap();
+    let alias = report["alias"].as_str().unwrap().to_string();
+    assert_eq!(report["gamesAnonymized"], 1);
+    assert_eq!(report["seasonStandingsAnonymized"], 1);
+    assert_eq!(report["notificationsRemoved"], 1);
+
+    // p2 keeps the game and their standing, played against the alias
+    assert!(elo.leaderboard(10).iter().all(|standing| standing.player_id == "p2"));
+    let game = &manager.game_history().games_of("p2", 10)[0];
+    assert_eq!(game.players, vec![alias.clone(), "p2".to_string()]);
+    assert_eq!(game.winner.as_deref(), Some(alias.as_str()));
+    assert!(manager.game_history().games_of("p1", 10).is_empty());
+    assert_eq!(seasons.archived(1).unwrap().standings[0].player_id, alias);
+    let reopened = TitleStore::open(&titles_path).unwrap();
+    assert!(reopened.titles("p1").titles.is_empty());
+    assert_eq!(reopened.titles("p2").titles.len(), 1);
+    assert_eq!(audit_log.query(&AuditQuery::default())[0].target, alias);
+
+    let export: serde_json::Value = serde_json::from_slice(request("GET", "p1").reply(&routes).await.body()).unwrap();
+    assert!(export["rating"].is_null() && export["games"].as_array().unwrap().is_empty());
+    let _ = std::fs::remove_file(&titles_path);
+}
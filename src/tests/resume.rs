@@ -0,0 +1,77 @@
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::application::GameManager;
+use crate::config::{LongPollConfig, Secret, ServerConfig};
+use crate::domain::{GameConfig, NotificationKind, Player, PlayerPhase, ServerMessage};
+use crate::infrastructure::{LongPollSessions, NotificationInbox, ResumeTokens, WebSocketHandler};
+
+async fn messages(sessions: &LongPollSessions, session: &str) -> Vec<Value> {
+    let response = sessions.recv(session, 0).await.unwrap();
+    response.messages.iter().map(|message| serde_json::to_value(message).unwrap()).collect()
+}
+
+#[tokio::test]
+async fn test_paused_seats_and_inboxes_are_only_resumed_with_the_resume_token() {
+    let manager = Arc::new(GameManager::new(GameConfig {
+        reconnect_grace_ms: 60_000,
+        ..GameConfig::default()
+    }));
+    let inbox = Arc::new(NotificationInbox::in_memory(10));
+    let tokens = Arc::new(ResumeTokens::with_keys(vec![Secret::new("k1", "resume-secret")]));
+    let handler = WebSocketHandler::new(manager.clone(), ServerConfig::default().websocket)
+        .with_notifications(inbox.clone())
+        .with_resume_tokens(tokens.clone());
+    let config = LongPollConfig {
+        poll_timeout_ms: 50,
+        session_idle_timeout_ms: 0,
+        ..LongPollConfig::default()
+    };
+    let sessions = LongPollSessions::new(handler, config);
+
+    let victim = sessions.open(None);
+    sessions.send(&victim, r#"{"type":"connect","playerId":"victim"}"#).await;
+    let connected = messages(&sessions, &victim).await.remove(0);
+    assert_eq!(connected["playerId"], "victim");
+    let token = connected["resumeToken"].as_str().unwrap().to_string();
+    assert!(tokens.verify("victim", &token));
+    assert!(!tokens.verify("someone-else", &token));
+
+    sessions.send(&victim, r#"{"type":"findMatch"}"#).await;
+    let (tx, mut opponent) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("opponent".to_string(), tx))).await.unwrap();
+    manager.disconnect_player("victim").await.unwrap();
+    inbox.push("victim", NotificationKind::ChallengeReceived, json!({ "from": "opponent" })).unwrap();
+    let resumed = |opponent: &mut tokio::sync::mpsc::UnboundedReceiver<ServerMessage>| {
+        std::iter::from_fn(|| opponent.try_recv().ok()).any(|message| matches!(message, ServerMessage::GameResumed { .. }))
+    };
+
+    // Knowing the id, or a token for another id, gets a fresh player instead of the seat and inbox
+    for connect in [
+        r#"{"type":"connect","playerId":"victim"}"#.to_string(),
+        format!(r#"{{"type":"connect","playerId":"victim","reconnectToken":"{}"}}"#, tokens.issue("someone-else")),
+    ] {
+        let intruder = sessions.open(None);
+        sessions.send(&intruder, &connect).await;
+        let received = messages(&sessions, &intruder).await;
+        assert_eq!(received[0]["type"], "connected");
+        assert_ne!(received[0]["playerId"], "victim");
+        assert!(received.iter().all(|message| message["type"] != "notifications"), "{:?}", received);
+        assert!(!resumed(&mut opponent));
+    }
+    assert_eq!(inbox.pending("victim").len(), 1);
+
+    // The token from `Connected` takes both back
+    let back = sessions.open(None);
+    sessions.send(&back, &format!(r#"{{"type":"connect","playerId":"victim","reconnectToken":"{}"}}"#, token)).await;
+    let received = messages(&sessions, &back).await;
+    assert_eq!(received[0]["playerId"], "victim");
+    assert!(received.iter().any(|message| message["type"] == "notifications"), "{:?}", received);
+    assert!(resumed(&mut opponent));
+    assert_eq!(manager.player_phase("victim").await, PlayerPhase::InGame);
+
+    // Ids nobody is using are still the client's to pick
+    let newcomer = sessions.open(None);
+    sessions.send(&newcomer, r#"{"type":"connect","playerId":"newcomer"}"#).await;
+    assert_eq!(messages(&sessions, &newcomer).await[0]["playerId"], "newcomer");
+}
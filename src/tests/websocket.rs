@@ -0,0 +1,662 @@
+use std::sync::Arc;
+
+use crate::application::{GameManager, SpamAction};
+use crate::config::{LongPollConfig, Secret, SecretStore, ServerConfig, SpamGuardConfig};
+use crate::domain::{ClientMessage, GameChoice, GameConfig, GameMode, Player, PlayerPhase, ServerMessage};
+use crate::infrastructure::{AuditLog, Catalog, ConnectionState, LongPollSessions, MessageKey, WebSocketHandler, BATCHED_FRAMES, STALLED_HANDSHAKES, UNEXPECTED_FRAMES};
+use crate::test_support::{spawn_ws_server, TestListener};
+
+#[tokio::test]
+async fn test_stalled_handshakes_are_closed() {
+    use futures_util::StreamExt;
+    use std::sync::atomic::Ordering;
+
+    let mut config = ServerConfig::default().websocket;
+    config.connection_timeout_ms = 50;
+    let handler = WebSocketHandler::new(Arc::new(GameManager::new(GameConfig::default())), config);
+    let listener = TestListener::bind().await.unwrap();
+    let addr = listener.addr();
+    let before = STALLED_HANDSHAKES.load(Ordering::Relaxed);
+
+    // Plain TCP that never upgrades
+    let _idle = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let stream = listener.accept().await.unwrap();
+    handler.handle_connection(stream).await.unwrap();
+
+    // Upgrades but never sends Connect
+    let client = tokio::spawn(async move {
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+        let mut texts = Vec::new();
+        while let Some(Ok(message)) = ws.next().await {
+            if message.is_text() {
+                texts.push(message.into_text().unwrap());
+            }
+        }
+        texts
+    });
+    let stream = listener.accept().await.unwrap();
+    handler.handle_connection(stream).await.unwrap();
+
+    let texts = tokio::time::timeout(std::time::Duration::from_secs(2), client).await.unwrap().unwrap();
+    assert!(texts[0].contains("Connect timeout"));
+    assert!(STALLED_HANDSHAKES.load(Ordering::Relaxed) >= before + 2);
+}
+
+#[tokio::test]
+async fn test_oversized_messages_are_rejected() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let mut config = ServerConfig::default().websocket;
+    config.max_message_size = 1024;
+    config.max_frame_size = 1024;
+    let handler = WebSocketHandler::new(Arc::new(GameManager::new(GameConfig::default())), config);
+    let listener = TestListener::bind().await.unwrap();
+    let addr = listener.addr();
+
+    let client = tokio::spawn(async move {
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+        ws.send(Message::Text(r#"{"type":"connect"}"#.to_string())).await.unwrap();
+        ws.send(Message::Text("x".repeat(2000))).await.unwrap();
+        let mut texts = Vec::new();
+        let mut close_code = None;
+        while let Some(Ok(message)) = ws.next().await {
+            match message {
+                Message::Text(text) => texts.push(text),
+                Message::Close(frame) => close_code = frame.map(|f| f.code),
+                _ => {}
+            }
+        }
+        (texts, close_code)
+    });
+    let stream = listener.accept().await.unwrap();
+    handler.handle_connection(stream).await.unwrap();
+
+    let (texts, close_code) = tokio::time::timeout(std::time::Duration::from_secs(2), client).await.unwrap().unwrap();
+    assert!(texts.iter().any(|text| text.contains("Message too large")));
+    assert_eq!(close_code, Some(CloseCode::Size));
+}
+
+#[tokio::test]
+async fn test_socket_io_clients_are_translated() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let mut config = ServerConfig::default().websocket;
+    config.socket_io_compat = true;
+    let handler = WebSocketHandler::new(Arc::new(GameManager::new(GameConfig::default())), config);
+    let listener = TestListener::bind().await.unwrap();
+    let addr = listener.addr();
+
+    let client = tokio::spawn(async move {
+        let url = format!("ws://{}/socket.io/?EIO=4&transport=websocket", addr);
+        let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let mut frames = Vec::new();
+        while let Some(Ok(message)) = ws.next().await {
+            let Message::Text(text) = message else { continue };
+            frames.push(text);
+            match frames.len() {
+                1 => ws.send(Message::Text(r#"40{"playerId":"sio"}"#.to_string())).await.unwrap(),
+                3 => ws.send(Message::Text(r#"42["findMatch",{}]"#.to_string())).await.unwrap(),
+                4 => break,
+                _ => {}
+            }
+        }
+        ws.send(Message::Text("41".to_string())).await.unwrap();
+        frames
+    });
+    let stream = listener.accept().await.unwrap();
+    handler.handle_connection(stream).await.unwrap();
+
+    let frames = tokio::time::timeout(std::time::Duration::from_secs(2), client).await.unwrap().unwrap();
+    assert!(frames[0].starts_with("0{") && frames[0].contains("pingInterval"));
+    assert_eq!(frames[1], r#"40{"sid":"sio"}"#);
+    assert!(frames[2].starts_with(r#"42["connected",{"#) && frames[2].contains(r#""playerId":"sio""#));
+    assert!(frames[3].starts_with(r#"42["matchmaking","#));
+}
+
+#[tokio::test]
+async fn test_binary_frames_are_rejected_with_typed_error() {
+    use futures_util::{SinkExt, StreamExt};
+    use std::sync::atomic::Ordering;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let handler = WebSocketHandler::new(
+        Arc::new(GameManager::new(GameConfig::default())),
+        ServerConfig::default().websocket,
+    );
+    let listener = TestListener::bind().await.unwrap();
+    let addr = listener.addr();
+    let before = UNEXPECTED_FRAMES.load(Ordering::Relaxed);
+
+    let client = tokio::spawn(async move {
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+        ws.send(Message::Text(r#"{"type":"connect"}"#.to_string())).await.unwrap();
+        ws.send(Message::Binary(vec![0x82, 0xa1])).await.unwrap();
+        let mut texts = Vec::new();
+        while let Some(Ok(message)) = ws.next().await {
+            // Keepalive pings arrive alongside the replies
+            let Message::Text(text) = message else {
+                continue;
+            };
+            let done = text.contains("unsupported_frame");
+            texts.push(text);
+            if done {
+                break;
+            }
+        }
+        texts
+    });
+    let stream = listener.accept().await.unwrap();
+    tokio::spawn(async move { handler.handle_connection(stream).await });
+
+    let texts = tokio::time::timeout(std::time::Duration::from_secs(2), client).await.unwrap().unwrap();
+    let error: serde_json::Value = serde_json::from_str(texts.last().unwrap()).unwrap();
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["code"], "unsupported_frame");
+    assert!(UNEXPECTED_FRAMES.load(Ordering::Relaxed) > before);
+}
+
+#[tokio::test]
+async fn test_out_of_order_messages_get_typed_errors() {
+    assert_eq!(
+        ConnectionState::Connecting.check(&ClientMessage::PauseRequest),
+        Err(MessageKey::NotConnected)
+    );
+    assert_eq!(
+        ConnectionState::InGame.check(&ClientMessage::JoinRoom { room: "r".to_string() }),
+        Err(MessageKey::AlreadyInGame)
+    );
+    assert_eq!(ConnectionState::PostGame.check(&ClientMessage::FindMatch { mode: GameMode::default(), game: Default::default(), template: None }), Ok(()));
+    assert_eq!(ConnectionState::from(PlayerPhase::Idle), ConnectionState::Connected);
+
+    let handler = WebSocketHandler::new(
+        Arc::new(GameManager::new(GameConfig::default())),
+        ServerConfig::default().websocket,
+    );
+    let sessions = LongPollSessions::new(handler, LongPollConfig::default());
+    let session = sessions.open(None);
+    for text in [
+        r#"{"type":"playerMove","choice":"rock"}"#,
+        r#"{"type":"connect","playerId":"strict"}"#,
+        r#"{"type":"connect","playerId":"strict"}"#,
+        r#"{"type":"playerMove","choice":"rock"}"#,
+        r#"{"type":"findMatch"}"#,
+        r#"{"type":"findMatch"}"#,
+    ] {
+        assert!(sessions.send(&session, text).await);
+    }
+
+    let response = sessions.recv(&session, 0).await.unwrap();
+    let messages: Vec<_> = response.messages.iter().map(|m| serde_json::to_value(m).unwrap()).collect();
+    let codes: Vec<_> = messages.iter().map(|m| m["code"].as_str().unwrap_or_else(|| m["type"].as_str().unwrap())).collect();
+    assert_eq!(
+        codes,
+        ["not_connected", "connected", "already_connected", "not_in_game", "matchmaking", "already_queued"]
+    );
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn test_typed_client_plays_a_match_and_reconnects() {
+    use crate::client::{ClientOptions, GameClient, ServerError};
+
+    let handler = WebSocketHandler::new(
+        Arc::new(GameManager::new(GameConfig::default())),
+        ServerConfig::default().websocket,
+    );
+    let server = spawn_ws_server(handler).await.unwrap();
+    let url = server.ws_url();
+
+    let options = |id: &str| ClientOptions {
+        url: url.clone(),
+        player_id: Some(id.to_string()),
+        response_timeout: std::time::Duration::from_secs(2),
+        ..ClientOptions::default()
+    };
+    let mut alice = GameClient::connect(options("alice")).await.unwrap();
+    let mut bob = GameClient::connect(options("bob")).await.unwrap();
+    assert_eq!(alice.player_id(), "alice");
+
+    let waiting = alice.find_match(GameMode::Solo).await.unwrap();
+    assert!(matches!(waiting, ServerMessage::Matchmaking { matched: false, .. }));
+    let error = alice.find_match(GameMode::Solo).await.unwrap_err();
+    assert_eq!(error.downcast_ref::<ServerError>().unwrap().code.as_deref(), Some("already_queued"));
+    let matched = bob.find_match(GameMode::Solo).await.unwrap();
+    assert!(matches!(matched, ServerMessage::Matchmaking { matched: true, .. }));
+    assert!(matches!(alice.next_event().await.unwrap(), ServerMessage::GameStart { .. }));
+
+    // Dropping the socket pauses the game; reconnecting under the same id resumes it
+    alice.reconnect().await.unwrap();
+    loop {
+        if let ServerMessage::GameResumed { .. } = alice.next_event().await.unwrap() {
+            break;
+        }
+    }
+
+    alice.play(GameChoice::Rock).await.unwrap();
+    bob.play(GameChoice::Scissors).await.unwrap();
+    let winner = loop {
+        if let ServerMessage::RoundResult { winner, .. } = alice.next_event().await.unwrap() {
+            break winner;
+        }
+    };
+    assert_eq!(winner.as_deref(), Some("alice"));
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn test_moves_the_room_refuses_come_back_as_errors() {
+    use crate::client::{ClientOptions, GameClient};
+
+    let handler = WebSocketHandler::new(
+        Arc::new(GameManager::new(GameConfig::default())),
+        ServerConfig::default().websocket,
+    );
+    let server = spawn_ws_server(handler).await.unwrap();
+    let url = server.ws_url();
+
+    let options = |id: &str| ClientOptions {
+        url: url.clone(),
+        player_id: Some(id.to_string()),
+        response_timeout: std::time::Duration::from_secs(2),
+        ..ClientOptions::default()
+    };
+    let mut alice = GameClient::connect(options("alice")).await.unwrap();
+    let mut bob = GameClient::connect(options("bob")).await.unwrap();
+    alice.find_match(GameMode::Solo).await.unwrap();
+    bob.find_match(GameMode::Solo).await.unwrap();
+
+    async fn next_error(client: &mut GameClient) -> Option<String> {
+        loop {
+            if let ServerMessage::Error { code, .. } = client.next_event().await.unwrap() {
+                return code;
+            }
+        }
+    }
+
+    alice.play(GameChoice::from("dynamite".to_string())).await.unwrap();
+    assert_eq!(next_error(&mut alice).await.as_deref(), Some("invalid_move"));
+
+    alice.request_pause().await.unwrap();
+    bob.request_pause().await.unwrap();
+    loop {
+        if let ServerMessage::GamePaused { .. } = alice.next_event().await.unwrap() {
+            break;
+        }
+    }
+    alice.play(GameChoice::Rock).await.unwrap();
+    assert_eq!(next_error(&mut alice).await.as_deref(), Some("game_paused"));
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn test_support_server_runs_on_ephemeral_ports_until_shut_down() {
+    use crate::client::{ClientOptions, GameClient};
+    use crate::test_support::spawn_test_server_with;
+
+    let server = spawn_test_server_with(|config| {
+        config.game.max_rounds = 1;
+        config.websocket.port = 8080; // Ignored: the harness always picks free ports
+    })
+    .await
+    .unwrap();
+    let ws_url = server.ws_url();
+    assert!(!ws_url.ends_with(":8080"));
+
+    let options = |id: &str| ClientOptions {
+        url: ws_url.clone(),
+        player_id: Some(id.to_string()),
+        response_timeout: std::time::Duration::from_secs(2),
+        ..ClientOptions::default()
+    };
+    let mut alice = GameClient::connect(options("alice")).await.unwrap();
+    let mut bob = GameClient::connect(options("bob")).await.unwrap();
+    alice.find_match(GameMode::Solo).await.unwrap();
+    bob.find_match(GameMode::Solo).await.unwrap();
+    alice.play(GameChoice::Paper).await.unwrap();
+    bob.play(GameChoice::Rock).await.unwrap();
+    let winner = loop {
+        if let ServerMessage::GameEnd { winner, .. } = alice.next_event().await.unwrap() {
+            break winner;
+        }
+    };
+    assert_eq!(winner.as_deref(), Some("alice"));
+
+    let stats: hyper::Uri = format!("{}/stats", server.rest_url()).parse().unwrap();
+    let response = hyper::Client::new().get(stats).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(server.game_manager().get_stats().await.2, 0);
+
+    server.shutdown().await;
+    assert!(GameClient::connect(options("carol")).await.is_err());
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn test_protocol_conformance_suite() {
+    let report = crate::tests::run_conformance_suite().await;
+    assert!(report.failed.is_empty(), "{:#?}", report.failed);
+    assert_eq!(report.passed.len(), crate::tests::scenarios().len());
+}
+
+#[tokio::test]
+async fn test_spam_mutes_escalate_and_are_shared_across_request_kinds() {
+    let manager = GameManager::new(GameConfig::default()).with_spam_guard(SpamGuardConfig {
+        max_per_window: 2,
+        window_ms: 60000,
+        base_mute_ms: 50,
+        ..SpamGuardConfig::default()
+    });
+    let guard = manager.spam_guard();
+
+    assert!(guard.check("p1", SpamAction::Rematch).is_ok());
+    assert!(guard.check("p1", SpamAction::Rematch).is_ok());
+    let first = guard.check("p1", SpamAction::Rematch).unwrap_err();
+    assert_eq!(first.strikes, 1);
+    assert_eq!(first.remaining, std::time::Duration::from_millis(50));
+
+    // One mute covers every kind of request, and other players are unaffected
+    assert!(guard.check("p1", SpamAction::Chat).is_err());
+    assert!(guard.check("p1", SpamAction::Challenge).is_err());
+    assert!(guard.check("p2", SpamAction::Challenge).is_ok());
+
+    tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+    assert!(guard.muted("p1").is_none());
+    assert!(guard.check("p1", SpamAction::Challenge).is_ok());
+    assert!(guard.check("p1", SpamAction::Challenge).is_ok());
+    let second = guard.check("p1", SpamAction::Challenge).unwrap_err();
+    assert_eq!(second.strikes, 2);
+    assert_eq!(second.remaining, std::time::Duration::from_millis(100));
+
+    let args = [("seconds", second.seconds().to_string())];
+    assert_eq!(
+        Catalog::builtin().render("en", MessageKey::Muted, &args),
+        "Too many requests; you can send challenges, rematches, and chat again in 1s"
+    );
+    assert!(guard.unmute("p1"));
+    assert!(guard.check("p1", SpamAction::Chat).is_ok());
+}
+
+#[tokio::test]
+async fn test_version_two_clients_get_backed_up_messages_in_one_frame() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let manager = Arc::new(GameManager::new(GameConfig::default()));
+    let handler = WebSocketHandler::new(manager.clone(), ServerConfig::default().websocket);
+    let server = spawn_ws_server(handler).await.unwrap();
+    let addr = server.addr();
+
+    async fn next_text<S: futures_util::Stream<Item = tokio_tungstenite::tungstenite::Result<Message>> + Unpin>(ws: &mut S) -> serde_json::Value {
+        // Keepalive pings start right after the upgrade and can land anywhere
+        loop {
+            match tokio::time::timeout(std::time::Duration::from_secs(2), ws.next()).await.unwrap() {
+                Some(Ok(Message::Text(text))) => return serde_json::from_str(&text).unwrap(),
+                Some(Ok(Message::Ping(_))) => continue,
+                other => panic!("expected a text frame, got {:?}", other),
+            }
+        }
+    }
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+    ws.send(Message::Text(r#"{"type":"connect","playerId":"p1","protocolVersion":7}"#.to_string())).await.unwrap();
+    let connected = next_text(&mut ws).await;
+    assert_eq!(connected["protocolVersion"], 2);
+    ws.send(Message::Text(r#"{"type":"findMatch"}"#.to_string())).await.unwrap();
+    assert_eq!(next_text(&mut ws).await["waiting"], true);
+
+    // Without yielding, so everything queues up behind the sender
+    let (tx2, _rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+    manager.remove_player("p2").await.unwrap();
+
+    let batch = next_text(&mut ws).await;
+    let types: Vec<_> = batch.as_array().expect("a batched frame").iter().map(|m| m["type"].clone()).collect();
+    assert_eq!(types.first().unwrap(), "gameStart");
+    assert!(types.len() > 1);
+    assert!(BATCHED_FRAMES.load(std::sync::atomic::Ordering::Relaxed) >= 1);
+
+    // Clients that don't declare version 2 keep one message per frame
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+    ws.send(Message::Text(r#"{"type":"connect","playerId":"p3"}"#.to_string())).await.unwrap();
+    let connected = next_text(&mut ws).await;
+    assert_eq!(connected["type"], "connected");
+    assert!(connected.get("protocolVersion").is_none());
+    ws.send(Message::Text(r#"{"type":"findMatch"}"#.to_string())).await.unwrap();
+    assert_eq!(next_text(&mut ws).await["type"], "matchmaking");
+    let (tx4, _rx4) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("p4".to_string(), tx4))).await.unwrap();
+    manager.remove_player("p4").await.unwrap();
+    assert_eq!(next_text(&mut ws).await["type"], "gameStart");
+}
+
+#[tokio::test]
+async fn test_a_panicking_handler_closes_only_its_connection_and_cleans_up() {
+    use futures_util::{SinkExt, StreamExt};
+    use crate::application::RuleScripts;
+    use crate::domain::Game;
+    use crate::infrastructure::{RoomTemplateStore, CONNECTION_PANICS};
+    use tokio_tungstenite::tungstenite::Message;
+
+    // Accepts any move, then panics resolving the round
+    struct Buggy;
+    impl Game for Buggy {
+        fn validate_move(&self, _: &GameChoice) -> bool {
+            true
+        }
+        fn result(&self, _: [&GameChoice; 2]) -> Option<usize> {
+            panic!("rules bug")
+        }
+    }
+    struct BuggyScripts;
+    impl RuleScripts for BuggyScripts {
+        fn game(&self, _: &str) -> Option<Box<dyn Game>> {
+            Some(Box::new(Buggy))
+        }
+    }
+    let templates = RoomTemplateStore::in_memory();
+    templates.save(serde_json::from_value(serde_json::json!({ "name": "buggy", "script": "buggy" })).unwrap()).unwrap();
+    let manager = Arc::new(
+        GameManager::new(GameConfig::default())
+            .with_rule_scripts(Arc::new(BuggyScripts))
+            .with_room_templates(Arc::new(templates)),
+    );
+    let reserved = manager.reserve_match_from(vec!["p1".to_string(), "p2".to_string()], Some("buggy")).await.unwrap();
+
+    let handler = WebSocketHandler::new(manager.clone(), ServerConfig::default().websocket);
+    let server = spawn_ws_server(handler).await.unwrap();
+    let addr = server.addr();
+
+    let (tx2, mut rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.join_room(Arc::new(Player::new("p2".to_string(), tx2)), &reserved.room_id).await.unwrap();
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+    ws.send(Message::Text(r#"{"type":"connect","playerId":"p1"}"#.to_string())).await.unwrap();
+    ws.send(Message::Text(format!(r#"{{"type":"joinRoom","room":"{}"}}"#, reserved.room_id))).await.unwrap();
+    while let Some(Ok(message)) = ws.next().await {
+        if matches!(message, Message::Text(ref text) if text.contains(r#""type":"matchmaking""#)) {
+            break;
+        }
+    }
+    manager.submit_move("p2", GameChoice::Rock).await.unwrap();
+    let before = CONNECTION_PANICS.load(std::sync::atomic::Ordering::Relaxed);
+    ws.send(Message::Text(r#"{"type":"playerMove","choice":"paper"}"#.to_string())).await.unwrap();
+
+    // p1 is told, then the socket closes
+    let mut errors = Vec::new();
+    while let Some(Ok(message)) = tokio::time::timeout(std::time::Duration::from_secs(2), ws.next()).await.unwrap() {
+        match message {
+            Message::Text(text) => {
+                let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+                if value["type"] == "error" {
+                    errors.push(value);
+                }
+            }
+            Message::Close(frame) => {
+                assert_eq!(frame.unwrap().code, tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode::Error);
+                break;
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(errors.len(), 1);
+    assert!(CONNECTION_PANICS.load(std::sync::atomic::Ordering::Relaxed) > before);
+
+    // p1 left as on any disconnect, so p2's room waits for them to come back
+    let paused = tokio::time::timeout(std::time::Duration::from_secs(2), async {
+        loop {
+            if let ServerMessage::GamePaused { player_id, .. } = rx2.recv().await.unwrap() {
+                return player_id;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(paused.as_deref(), Some("p1"));
+}
+
+#[tokio::test]
+async fn test_connect_metadata_rides_along_on_the_player_into_matchmaking() {
+    use futures_util::{SinkExt, StreamExt};
+    use crate::domain::{ClientVersion, ExperimentFlags, Extensions, Region};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let mut extensions = Extensions::new();
+    assert!(extensions.is_empty());
+    extensions.insert(Region("us-east".to_string()));
+    extensions.insert(Region("eu-west".to_string()));
+    assert_eq!(extensions.len(), 1);
+    assert_eq!(extensions.get::<Region>(), Some(&Region("eu-west".to_string())));
+    assert!(extensions.get::<ClientVersion>().is_none());
+    assert!(extensions.remove::<Region>());
+    assert!(!extensions.contains::<Region>());
+
+    let manager = Arc::new(GameManager::new(GameConfig::default()));
+    let handler = WebSocketHandler::new(manager.clone(), ServerConfig::default().websocket);
+    let server = spawn_ws_server(handler).await.unwrap();
+    let addr = server.addr();
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+    let connect = r#"{"type":"connect","playerId":"p1","clientVersion":"web/3.1","region":" eu-west ","experiments":["fastRounds"]}"#;
+    ws.send(Message::Text(connect.to_string())).await.unwrap();
+    ws.send(Message::Text(r#"{"type":"findMatch"}"#.to_string())).await.unwrap();
+    while let Some(Ok(message)) = ws.next().await {
+        if matches!(message, Message::Text(ref text) if text.contains(r#""waiting":true"#)) {
+            break;
+        }
+    }
+
+    let (tx2, mut rx2) = tokio::sync::mpsc::unbounded_channel();
+    manager.find_match(Arc::new(Player::new("p2".to_string(), tx2))).await.unwrap();
+    let room_id = loop {
+        if let ServerMessage::GameStart { room_id, .. } = rx2.recv().await.unwrap() {
+            break room_id;
+        }
+    };
+    let (_, players) = manager.take_room(&room_id).await.unwrap();
+    let p1 = players.iter().find(|player| player.id == "p1").unwrap();
+    assert_eq!(p1.extensions.get::<Region>(), Some(&Region("eu-west".to_string())));
+    assert!(p1.extensions.get::<ExperimentFlags>().unwrap().is_enabled("fastRounds"));
+    assert!(p1.extensions.get::<ClientVersion>().is_some());
+    let p2 = players.iter().find(|player| player.id == "p2").unwrap();
+    assert!(p2.extensions.is_empty());
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn test_profanity_filter_screens_names_and_chat() {
+    use crate::application::ProfanityFilter;
+    use crate::client::{ClientOptions, GameClient, ServerError};
+    use crate::config::ProfanityConfig;
+    use crate::infrastructure::create_profanity_routes;
+
+    let filter = Arc::new(ProfanityFilter::new(&ProfanityConfig { words: vec!["Darn".to_string()], ..ProfanityConfig::default() }).unwrap());
+    let handler = WebSocketHandler::new(
+        Arc::new(GameManager::new(GameConfig::default()).with_profanity_filter(filter.clone())),
+        ServerConfig::default().websocket,
+    );
+    let server = spawn_ws_server(handler).await.unwrap();
+    let url = server.ws_url();
+    let options = |id: &str, name: &str| ClientOptions {
+        url: url.clone(),
+        player_id: Some(id.to_string()),
+        display_name: Some(name.to_string()),
+        response_timeout: std::time::Duration::from_secs(2),
+        ..ClientOptions::default()
+    };
+
+    // Masked by default; substitutions are undone but longer words are left alone
+    let mut alice = GameClient::connect(options("alice", "D4RN Alice")).await.unwrap();
+    let mut bob = GameClient::connect(options("bob", "Bob")).await.unwrap();
+    alice.find_match(GameMode::Solo).await.unwrap();
+    bob.find_match(GameMode::Solo).await.unwrap();
+    let ServerMessage::GameStart { players, .. } = bob.next_event().await.unwrap() else { panic!("expected gameStart") };
+    assert_eq!(players[0].name.as_deref(), Some("**** Alice"));
+    assert_eq!(players[1].name.as_deref(), Some("Bob"));
+    alice.chat("well darn, darned good move").await.unwrap();
+    let chat = loop {
+        if let ServerMessage::Chat { player_id, text, .. } = bob.next_event().await.unwrap() {
+            break (player_id, text);
+        }
+    };
+    assert_eq!(chat, ("alice".to_string(), "well ****, darned good move".to_string()));
+
+    let secrets = Arc::new(SecretStore::from_keys(vec![Secret::new("ops", "admin-key")], Vec::new()));
+    let routes = create_profanity_routes(filter.clone(), Arc::new(AuditLog::in_memory()), secrets);
+    let update = |body: serde_json::Value| {
+        warp::test::request().method("PUT").path("/admin/profanity").header("x-api-key", "admin-key").json(&body)
+    };
+    let updated = update(serde_json::json!({ "words": ["darn", "heck"], "action": "reject" })).reply(&routes).await;
+    assert_eq!(updated.status(), 200);
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(updated.body()).unwrap()["words"], serde_json::json!(["darn", "heck"]));
+
+    alice.chat("oh heck").await.unwrap();
+    let error = loop {
+        if let ServerMessage::Error { code, .. } = alice.next_event().await.unwrap() {
+            break code;
+        }
+    };
+    assert_eq!(error.as_deref(), Some("inappropriate_language"));
+    let refused = GameClient::connect(options("carol", "heck")).await.err().unwrap();
+    assert_eq!(refused.downcast_ref::<ServerError>().unwrap().code.as_deref(), Some("inappropriate_language"));
+
+    // Shadow-muted lines come back to the sender and nobody else
+    update(serde_json::json!({ "words": ["heck"], "action": "shadowMute" })).reply(&routes).await;
+    alice.chat("heck").await.unwrap();
+    assert!(matches!(alice.next_event().await.unwrap(), ServerMessage::Chat { .. }));
+    while let Ok(message) = bob.next_event_within(std::time::Duration::from_millis(200)).await {
+        assert!(!matches!(message, ServerMessage::Chat { ref text, .. } if text == "heck"));
+    }
+}
+
+#[tokio::test]
+async fn test_connected_reports_the_build_recorded_at_compile_time() {
+    use futures_util::{SinkExt, StreamExt};
+    use crate::domain::BuildInfo;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let build = BuildInfo::current();
+    assert_eq!(build.version, env!("CARGO_PKG_VERSION"));
+    assert!(!build.git_hash.is_empty());
+    assert!(build.rustc.starts_with("rustc "));
+    assert!(build.built_at.is_some());
+    assert_eq!(build.features.contains(&"client".to_string()), cfg!(feature = "client"));
+    assert!(!build.features.contains(&"default".to_string()));
+
+    let manager = Arc::new(GameManager::new(GameConfig::default()));
+    let handler = WebSocketHandler::new(manager, ServerConfig::default().websocket);
+    let server = spawn_ws_server(handler).await.unwrap();
+    let addr = server.addr();
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr)).await.unwrap();
+    ws.send(Message::Text(r#"{"type":"connect","playerId":"p1"}"#.to_string())).await.unwrap();
+    let Some(Ok(Message::Text(text))) = ws.next().await else {
+        panic!("expected Connected");
+    };
+    let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(json["build"]["gitHash"], build.git_hash.as_str());
+    match serde_json::from_str(&text).unwrap() {
+        ServerMessage::Connected { build: Some(reported), .. } => assert_eq!(&reported, build),
+        other => panic!("expected Connected with a build, got {:?}", other),
+    }
+}